@@ -30,12 +30,14 @@ fn create_test_sound(num_samples: usize) -> Sound {
 fn create_manager_with_instances(
 	num_instances: usize,
 ) -> (AudioManager, Backend, Vec<InstanceHandle>) {
-	let (mut audio_manager, mut backend) =
-		AudioManager::new_without_audio_thread(AudioManagerSettings {
+	let (mut audio_manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
 			num_instances: num_instances,
 			num_commands: num_instances,
 			..Default::default()
-		});
+		},
+		48000,
+	);
 	// add a test sound
 	let mut sound_handle = audio_manager.add_sound(create_test_sound(48000)).unwrap();
 	backend.process();
@@ -0,0 +1,106 @@
+/*
+ * C API for the Kira audio library.
+ *
+ * Every handle returned by a kira_* function is owned by the caller
+ * and must be freed with the matching kira_*_free function. Functions
+ * that create something return NULL on failure, and functions that
+ * send commands to the audio thread return false on failure. Either
+ * way, kira_last_error() describes what went wrong.
+ */
+
+#ifndef KIRA_H
+#define KIRA_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct KiraAudioManager KiraAudioManager;
+typedef struct KiraSound KiraSound;
+typedef struct KiraInstance KiraInstance;
+typedef struct KiraParameter KiraParameter;
+
+typedef enum KiraInstanceState {
+	KIRA_INSTANCE_STATE_SCHEDULED,
+	KIRA_INSTANCE_STATE_PLAYING,
+	KIRA_INSTANCE_STATE_PAUSED,
+	KIRA_INSTANCE_STATE_STOPPED,
+	KIRA_INSTANCE_STATE_PAUSING,
+	KIRA_INSTANCE_STATE_STOPPING,
+} KiraInstanceState;
+
+typedef struct KiraInstanceSettings {
+	/* The volume of the instance. */
+	double volume;
+	/* The playback rate, as a factor of the original playback rate. */
+	double playback_rate;
+	/* The panning of the instance (0 = hard left, 1 = hard right). */
+	double panning;
+	/* The position to start playing at (in seconds). */
+	double start_position;
+	/* Whether to play the instance in reverse. */
+	bool reverse;
+	/* Whether to loop back to loop_start. If false, the sound's
+	   default loop start point is used. */
+	bool looping;
+	/* The position to loop back to (in seconds). */
+	double loop_start;
+	/* How long to fade in (in seconds), or 0 for the sound's default. */
+	double fade_in_duration;
+} KiraInstanceSettings;
+
+/* Errors */
+
+/* Returns the last error on this thread, or NULL if there hasn't been one.
+   The string stays valid until the next error on the same thread. */
+const char *kira_last_error(void);
+void kira_clear_last_error(void);
+
+/* Audio manager */
+
+KiraAudioManager *kira_manager_new(void);
+void kira_manager_free(KiraAudioManager *manager);
+KiraSound *kira_manager_load_sound(KiraAudioManager *manager, const char *path);
+/* samples holds num_frames interleaved stereo frames. */
+KiraSound *kira_manager_add_sound_from_samples(KiraAudioManager *manager,
+	uint32_t sample_rate, const float *samples, size_t num_frames);
+KiraParameter *kira_manager_add_parameter(KiraAudioManager *manager, double value);
+void kira_manager_free_unused_resources(KiraAudioManager *manager);
+
+/* Sounds */
+
+KiraInstanceSettings kira_instance_settings_default(void);
+void kira_sound_free(KiraSound *sound);
+double kira_sound_duration(const KiraSound *sound);
+/* settings may be NULL to use the default settings. */
+KiraInstance *kira_sound_play(KiraSound *sound, const KiraInstanceSettings *settings);
+
+/* Instances */
+
+void kira_instance_free(KiraInstance *instance);
+KiraInstanceState kira_instance_state(const KiraInstance *instance);
+double kira_instance_position(const KiraInstance *instance);
+bool kira_instance_set_volume(KiraInstance *instance, double volume);
+bool kira_instance_link_volume(KiraInstance *instance, const KiraParameter *parameter);
+bool kira_instance_set_playback_rate(KiraInstance *instance, double playback_rate);
+bool kira_instance_link_playback_rate(KiraInstance *instance, const KiraParameter *parameter);
+bool kira_instance_set_panning(KiraInstance *instance, double panning);
+bool kira_instance_seek_to(KiraInstance *instance, double position);
+bool kira_instance_pause(KiraInstance *instance, double fade_duration);
+bool kira_instance_resume(KiraInstance *instance, double fade_duration);
+bool kira_instance_stop(KiraInstance *instance, double fade_duration);
+
+/* Parameters */
+
+void kira_parameter_free(KiraParameter *parameter);
+bool kira_parameter_set(KiraParameter *parameter, double value, double tween_duration);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
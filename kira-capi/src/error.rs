@@ -0,0 +1,49 @@
+use std::{cell::RefCell, ffi::CString, os::raw::c_char, ptr::null};
+
+thread_local! {
+	static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records an error so it can be retrieved with [`kira_last_error`].
+pub(crate) fn set_last_error(error: impl ToString) {
+	// interior nul bytes can't be represented in a C string
+	let message = error.to_string().replace('\0', "");
+	LAST_ERROR.with(|last_error| {
+		*last_error.borrow_mut() = CString::new(message).ok();
+	});
+}
+
+/// Records the error (if any) and returns the value (if any).
+pub(crate) fn check<T, E: ToString>(result: Result<T, E>) -> Option<T> {
+	match result {
+		Ok(value) => Some(value),
+		Err(error) => {
+			set_last_error(error);
+			None
+		}
+	}
+}
+
+/// Returns the message of the last error that happened on this
+/// thread, or null if there hasn't been one.
+///
+/// The string is owned by Kira and stays valid until the next
+/// error happens on the same thread or [`kira_clear_last_error`]
+/// is called.
+#[no_mangle]
+pub extern "C" fn kira_last_error() -> *const c_char {
+	LAST_ERROR.with(|last_error| {
+		last_error
+			.borrow()
+			.as_ref()
+			.map_or(null(), |message| message.as_ptr())
+	})
+}
+
+/// Forgets the last error that happened on this thread.
+#[no_mangle]
+pub extern "C" fn kira_clear_last_error() {
+	LAST_ERROR.with(|last_error| {
+		*last_error.borrow_mut() = None;
+	});
+}
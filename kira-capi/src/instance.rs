@@ -0,0 +1,169 @@
+use kira::instance::{
+	handle::InstanceHandle, InstanceState, PauseInstanceSettings, ResumeInstanceSettings,
+	StopInstanceSettings,
+};
+
+use crate::{error::check, linear_tween, KiraParameter};
+
+/// A playing sound.
+pub struct KiraInstance(pub(crate) InstanceHandle);
+
+/// The playback state of an instance.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KiraInstanceState {
+	/// The instance is waiting for its start time.
+	Scheduled,
+	/// The instance is playing.
+	Playing,
+	/// The instance is paused.
+	Paused,
+	/// The instance is stopped and cannot be resumed.
+	Stopped,
+	/// The instance is fading out and will be paused when
+	/// the fade-out is finished.
+	Pausing,
+	/// The instance is fading out and will be stopped when
+	/// the fade-out is finished.
+	Stopping,
+}
+
+impl From<InstanceState> for KiraInstanceState {
+	fn from(state: InstanceState) -> Self {
+		match state {
+			InstanceState::Scheduled => Self::Scheduled,
+			InstanceState::Playing => Self::Playing,
+			InstanceState::Paused(_) => Self::Paused,
+			InstanceState::Stopped => Self::Stopped,
+			InstanceState::Pausing(_) => Self::Pausing,
+			InstanceState::Stopping => Self::Stopping,
+		}
+	}
+}
+
+/// Frees an instance handle.
+///
+/// This doesn't stop the instance.
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_free(instance: *mut KiraInstance) {
+	free_handle!(instance);
+}
+
+/// Returns the playback state of the instance.
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_state(instance: *const KiraInstance) -> KiraInstanceState {
+	let instance = handle_ref!(instance, KiraInstanceState::Stopped);
+	instance.state().into()
+}
+
+/// Returns the playback position of the instance (in seconds).
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_position(instance: *const KiraInstance) -> f64 {
+	let instance = handle_ref!(instance, 0.0);
+	instance.position()
+}
+
+/// Sets the volume of the instance.
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_set_volume(
+	instance: *mut KiraInstance,
+	volume: f64,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	check(instance.set_volume(volume)).is_some()
+}
+
+/// Links the volume of the instance to a parameter.
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_link_volume(
+	instance: *mut KiraInstance,
+	parameter: *const KiraParameter,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	let parameter = handle_ref!(parameter, false);
+	check(instance.set_volume(parameter)).is_some()
+}
+
+/// Sets the playback rate of the instance.
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_set_playback_rate(
+	instance: *mut KiraInstance,
+	playback_rate: f64,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	check(instance.set_playback_rate(playback_rate)).is_some()
+}
+
+/// Links the playback rate of the instance to a parameter.
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_link_playback_rate(
+	instance: *mut KiraInstance,
+	parameter: *const KiraParameter,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	let parameter = handle_ref!(parameter, false);
+	check(instance.set_playback_rate(parameter)).is_some()
+}
+
+/// Sets the panning of the instance (0 = hard left, 1 = hard right).
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_set_panning(
+	instance: *mut KiraInstance,
+	panning: f64,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	check(instance.set_panning(panning)).is_some()
+}
+
+/// Moves the playback position of the instance to the
+/// given time (in seconds).
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_seek_to(instance: *mut KiraInstance, position: f64) -> bool {
+	let instance = handle_mut!(instance, false);
+	check(instance.seek_to(position)).is_some()
+}
+
+/// Pauses the instance, fading it out over the given number
+/// of seconds (or a very short fade if it's `0`).
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_pause(
+	instance: *mut KiraInstance,
+	fade_duration: f64,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	let mut settings = PauseInstanceSettings::new();
+	if let Some(tween) = linear_tween(fade_duration) {
+		settings = settings.fade_tween(tween);
+	}
+	check(instance.pause(settings)).is_some()
+}
+
+/// Resumes the instance, fading it in over the given number
+/// of seconds (or a very short fade if it's `0`).
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_resume(
+	instance: *mut KiraInstance,
+	fade_duration: f64,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	let mut settings = ResumeInstanceSettings::new();
+	if let Some(tween) = linear_tween(fade_duration) {
+		settings = settings.fade_tween(tween);
+	}
+	check(instance.resume(settings)).is_some()
+}
+
+/// Stops the instance, fading it out over the given number
+/// of seconds (or the sound's default fade-out if it's `0`).
+#[no_mangle]
+pub unsafe extern "C" fn kira_instance_stop(
+	instance: *mut KiraInstance,
+	fade_duration: f64,
+) -> bool {
+	let instance = handle_mut!(instance, false);
+	let mut settings = StopInstanceSettings::new();
+	if let Some(tween) = linear_tween(fade_duration) {
+		settings = settings.fade_tween(tween);
+	}
+	check(instance.stop(settings)).is_some()
+}
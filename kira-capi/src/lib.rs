@@ -0,0 +1,121 @@
+//! A C API for [Kira](https://crates.io/crates/kira).
+//!
+//! This crate builds Kira as a shared or static library that
+//! engines written in C, C++, or any language with a C foreign
+//! function interface can link to. The functions are declared
+//! in `include/kira.h`.
+//!
+//! ## Handles
+//!
+//! The audio manager, sounds, instances, and parameters are
+//! represented by opaque pointers. Every pointer returned by a
+//! `kira_*` function is owned by the caller and must be freed
+//! with the matching `kira_*_free` function. Sound, instance, and
+//! parameter handles stay valid after the audio manager is freed,
+//! but they won't do anything anymore.
+//!
+//! ## Errors
+//!
+//! Functions that create something return a null pointer if
+//! something went wrong, and functions that send a command to
+//! the audio thread return `false`. In both cases, a description
+//! of the error can be retrieved with [`kira_last_error`].
+//! Passing a null pointer as a handle is treated as an error
+//! rather than crashing.
+//!
+//! ## Safety
+//!
+//! Functions that take pointers are `unsafe` to call from Rust.
+//! Every non-null handle passed to them must have been returned
+//! by this library and not freed yet, and handles must not be
+//! used from more than one thread at a time. Strings must be
+//! nul-terminated, and sample buffers must be as long as the
+//! number of frames passed along with them.
+
+#![allow(clippy::missing_safety_doc)]
+
+/// Gets a mutable reference to the value behind a handle,
+/// recording an error and returning `$error_value` (or nothing)
+/// if the handle is null.
+macro_rules! handle_mut {
+	($handle:expr) => {
+		match unsafe { $handle.as_mut() } {
+			Some(handle) => &mut handle.0,
+			None => {
+				$crate::error::set_last_error(concat!(
+					"The ",
+					stringify!($handle),
+					" handle is null"
+				));
+				return;
+			}
+		}
+	};
+	($handle:expr, $error_value:expr) => {
+		match unsafe { $handle.as_mut() } {
+			Some(handle) => &mut handle.0,
+			None => {
+				$crate::error::set_last_error(concat!(
+					"The ",
+					stringify!($handle),
+					" handle is null"
+				));
+				return $error_value;
+			}
+		}
+	};
+}
+
+/// Gets a reference to the value behind a handle,
+/// recording an error and returning `$error_value` if the
+/// handle is null.
+macro_rules! handle_ref {
+	($handle:expr, $error_value:expr) => {
+		match unsafe { $handle.as_ref() } {
+			Some(handle) => &handle.0,
+			None => {
+				$crate::error::set_last_error(concat!(
+					"The ",
+					stringify!($handle),
+					" handle is null"
+				));
+				return $error_value;
+			}
+		}
+	};
+}
+
+/// Frees the value behind a handle created with `Box::into_raw`.
+/// Null handles are ignored.
+macro_rules! free_handle {
+	($handle:expr) => {
+		if !$handle.is_null() {
+			drop(unsafe { Box::from_raw($handle) });
+		}
+	};
+}
+
+mod error;
+mod instance;
+mod manager;
+mod parameter;
+mod sound;
+
+#[cfg(test)]
+mod tests;
+
+pub use error::*;
+pub use instance::*;
+pub use manager::*;
+pub use parameter::*;
+pub use sound::*;
+
+/// Returns a tween that lasts the given number of seconds,
+/// or `None` if the duration isn't positive.
+pub(crate) fn linear_tween(duration: f64) -> Option<kira::parameter::tween::Tween> {
+	if duration > 0.0 {
+		Some(kira::parameter::tween::Tween::linear(duration))
+	} else {
+		None
+	}
+}
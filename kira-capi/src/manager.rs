@@ -0,0 +1,126 @@
+use std::{
+	ffi::CStr,
+	mem::size_of,
+	os::raw::{c_char, c_float},
+	ptr::null_mut,
+	slice,
+};
+
+use kira::{
+	manager::{AudioManager, AudioManagerSettings},
+	parameter::ParameterSettings,
+	sound::{Sound, SoundSettings},
+	Frame, Sample,
+};
+
+use crate::{error::check, KiraParameter, KiraSound};
+
+/// Plays audio and sends commands to the audio thread.
+pub struct KiraAudioManager(pub(crate) AudioManager);
+
+/// Starts an audio manager with the default settings.
+///
+/// Returns null if the audio device couldn't be opened.
+#[no_mangle]
+pub extern "C" fn kira_manager_new() -> *mut KiraAudioManager {
+	match check(AudioManager::new(AudioManagerSettings::default())) {
+		Some(manager) => Box::into_raw(Box::new(KiraAudioManager(manager))),
+		None => null_mut(),
+	}
+}
+
+/// Stops the audio thread and frees the audio manager.
+#[no_mangle]
+pub unsafe extern "C" fn kira_manager_free(manager: *mut KiraAudioManager) {
+	free_handle!(manager);
+}
+
+/// Loads a sound from a file and sends it to the audio thread.
+///
+/// `path` must be a nul-terminated UTF-8 string. Returns null
+/// if the sound couldn't be loaded.
+#[no_mangle]
+pub unsafe extern "C" fn kira_manager_load_sound(
+	manager: *mut KiraAudioManager,
+	path: *const c_char,
+) -> *mut KiraSound {
+	let manager = handle_mut!(manager, null_mut());
+	if path.is_null() {
+		crate::error::set_last_error("The path is null");
+		return null_mut();
+	}
+	let path = match check(unsafe { CStr::from_ptr(path) }.to_str()) {
+		Some(path) => path,
+		None => return null_mut(),
+	};
+	match check(manager.load_sound(path, SoundSettings::default())) {
+		Some(handle) => Box::into_raw(Box::new(KiraSound(handle))),
+		None => null_mut(),
+	}
+}
+
+/// Creates a sound from interleaved stereo samples and sends it
+/// to the audio thread.
+///
+/// `samples` must point to `num_frames * 2` floats. The samples
+/// are copied, so they can be freed after this function returns.
+/// This is useful for engines that decode audio files themselves.
+///
+/// Returns null if `num_frames` is too large to address that many
+/// samples.
+#[no_mangle]
+pub unsafe extern "C" fn kira_manager_add_sound_from_samples(
+	manager: *mut KiraAudioManager,
+	sample_rate: u32,
+	samples: *const c_float,
+	num_frames: usize,
+) -> *mut KiraSound {
+	let manager = handle_mut!(manager, null_mut());
+	if samples.is_null() {
+		crate::error::set_last_error("The samples pointer is null");
+		return null_mut();
+	}
+	// a slice can't hold more than isize::MAX bytes
+	let num_samples = match num_frames.checked_mul(2) {
+		Some(num_samples) if num_samples <= isize::MAX as usize / size_of::<c_float>() => {
+			num_samples
+		}
+		_ => {
+			crate::error::set_last_error("The number of frames is too large");
+			return null_mut();
+		}
+	};
+	let samples = unsafe { slice::from_raw_parts(samples, num_samples) };
+	let frames = samples
+		.chunks_exact(2)
+		.map(|frame| Frame::new(frame[0] as Sample, frame[1] as Sample))
+		.collect::<Vec<_>>();
+	let sound = Sound::from_frames(sample_rate, frames, SoundSettings::default());
+	match check(manager.add_sound(sound)) {
+		Some(handle) => Box::into_raw(Box::new(KiraSound(handle))),
+		None => null_mut(),
+	}
+}
+
+/// Creates a parameter with the given starting value.
+#[no_mangle]
+pub unsafe extern "C" fn kira_manager_add_parameter(
+	manager: *mut KiraAudioManager,
+	value: f64,
+) -> *mut KiraParameter {
+	let manager = handle_mut!(manager, null_mut());
+	match check(manager.add_parameter(ParameterSettings::new().value(value))) {
+		Some(handle) => Box::into_raw(Box::new(KiraParameter(handle))),
+		None => null_mut(),
+	}
+}
+
+/// Frees resources that are no longer being used, like removed
+/// sounds.
+///
+/// This should be called regularly, for example once per frame.
+#[no_mangle]
+pub unsafe extern "C" fn kira_manager_free_unused_resources(manager: *mut KiraAudioManager) {
+	let manager = handle_mut!(manager);
+	manager.free_unused_resources();
+}
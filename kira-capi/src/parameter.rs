@@ -0,0 +1,26 @@
+use kira::parameter::handle::ParameterHandle;
+
+use crate::{error::check, linear_tween};
+
+/// A value that instances can be linked to.
+pub struct KiraParameter(pub(crate) ParameterHandle);
+
+/// Frees a parameter handle.
+///
+/// This doesn't remove the parameter from the audio thread.
+#[no_mangle]
+pub unsafe extern "C" fn kira_parameter_free(parameter: *mut KiraParameter) {
+	free_handle!(parameter);
+}
+
+/// Sets the value of the parameter, tweening it over the given
+/// number of seconds (or immediately if it's `0`).
+#[no_mangle]
+pub unsafe extern "C" fn kira_parameter_set(
+	parameter: *mut KiraParameter,
+	value: f64,
+	tween_duration: f64,
+) -> bool {
+	let parameter = handle_mut!(parameter, false);
+	check(parameter.set(value, linear_tween(tween_duration))).is_some()
+}
@@ -0,0 +1,112 @@
+use std::ptr::null_mut;
+
+use kira::{
+	instance::{InstanceLoopStart, InstanceSettings},
+	sound::handle::SoundHandle,
+};
+
+use crate::{error::check, linear_tween, KiraInstance};
+
+/// A sound that has been sent to the audio thread.
+pub struct KiraSound(pub(crate) SoundHandle);
+
+/// Settings for playing a sound.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct KiraInstanceSettings {
+	/// The volume of the instance.
+	pub volume: f64,
+	/// The playback rate of the instance, as a factor of the
+	/// original playback rate.
+	pub playback_rate: f64,
+	/// The panning of the instance (0 = hard left, 1 = hard right).
+	pub panning: f64,
+	/// The position to start playing the instance at (in seconds).
+	pub start_position: f64,
+	/// Whether to play the instance in reverse.
+	pub reverse: bool,
+	/// Whether the instance should loop back to `loop_start`
+	/// when it reaches the end. If this is `false`, the sound's
+	/// default loop start point is used.
+	pub looping: bool,
+	/// The position the instance loops back to (in seconds).
+	pub loop_start: f64,
+	/// How long the instance takes to fade in from silence
+	/// (in seconds), or `0` to use the sound's default fade-in.
+	pub fade_in_duration: f64,
+}
+
+impl Default for KiraInstanceSettings {
+	fn default() -> Self {
+		Self {
+			volume: 1.0,
+			playback_rate: 1.0,
+			panning: 0.5,
+			start_position: 0.0,
+			reverse: false,
+			looping: false,
+			loop_start: 0.0,
+			fade_in_duration: 0.0,
+		}
+	}
+}
+
+impl From<KiraInstanceSettings> for InstanceSettings {
+	fn from(settings: KiraInstanceSettings) -> Self {
+		let mut instance_settings = InstanceSettings::new()
+			.volume(settings.volume)
+			.playback_rate(settings.playback_rate)
+			.panning(settings.panning)
+			.start_position(settings.start_position);
+		if settings.reverse {
+			instance_settings = instance_settings.reverse();
+		}
+		if settings.looping {
+			instance_settings =
+				instance_settings.loop_start(InstanceLoopStart::Custom(settings.loop_start));
+		}
+		if let Some(tween) = linear_tween(settings.fade_in_duration) {
+			instance_settings = instance_settings.fade_in_tween(tween);
+		}
+		instance_settings
+	}
+}
+
+/// Returns the default settings for playing a sound.
+#[no_mangle]
+pub extern "C" fn kira_instance_settings_default() -> KiraInstanceSettings {
+	KiraInstanceSettings::default()
+}
+
+/// Frees a sound handle.
+///
+/// This doesn't remove the sound from the audio thread.
+#[no_mangle]
+pub unsafe extern "C" fn kira_sound_free(sound: *mut KiraSound) {
+	free_handle!(sound);
+}
+
+/// Returns the duration of the sound (in seconds).
+#[no_mangle]
+pub unsafe extern "C" fn kira_sound_duration(sound: *const KiraSound) -> f64 {
+	let sound = handle_ref!(sound, 0.0);
+	sound.duration()
+}
+
+/// Plays the sound.
+///
+/// If `settings` is null, the default settings are used.
+/// Returns null if the command couldn't be sent to the
+/// audio thread.
+#[no_mangle]
+pub unsafe extern "C" fn kira_sound_play(
+	sound: *mut KiraSound,
+	settings: *const KiraInstanceSettings,
+) -> *mut KiraInstance {
+	let sound = handle_mut!(sound, null_mut());
+	let settings = unsafe { settings.as_ref() }.copied().unwrap_or_default();
+	match check(sound.play(settings.into())) {
+		Some(handle) => Box::into_raw(Box::new(KiraInstance(handle))),
+		None => null_mut(),
+	}
+}
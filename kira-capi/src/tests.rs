@@ -0,0 +1,86 @@
+use std::{ffi::CStr, ptr::null_mut};
+
+use kira::{
+	instance::InstanceSettings,
+	manager::{AudioManager, AudioManagerSettings},
+};
+
+use crate::{
+	kira_clear_last_error, kira_instance_set_volume, kira_instance_settings_default,
+	kira_instance_state, kira_last_error, kira_manager_add_sound_from_samples,
+	kira_manager_load_sound, kira_sound_free, kira_sound_play, KiraAudioManager, KiraInstanceState,
+};
+
+fn last_error() -> Option<String> {
+	let message = kira_last_error();
+	if message.is_null() {
+		None
+	} else {
+		Some(
+			unsafe { CStr::from_ptr(message) }
+				.to_string_lossy()
+				.into_owned(),
+		)
+	}
+}
+
+#[test]
+fn reports_null_handles_as_errors() {
+	kira_clear_last_error();
+	assert_eq!(last_error(), None);
+	assert!(unsafe { kira_sound_play(null_mut(), std::ptr::null()) }.is_null());
+	assert_eq!(last_error().as_deref(), Some("The sound handle is null"));
+	assert!(!unsafe { kira_instance_set_volume(null_mut(), 0.5) });
+	assert_eq!(last_error().as_deref(), Some("The instance handle is null"));
+	assert!(unsafe { kira_manager_load_sound(null_mut(), std::ptr::null()) }.is_null());
+	assert_eq!(last_error().as_deref(), Some("The manager handle is null"));
+	assert_eq!(
+		unsafe { kira_instance_state(std::ptr::null()) },
+		KiraInstanceState::Stopped
+	);
+	kira_clear_last_error();
+	assert_eq!(last_error(), None);
+}
+
+#[test]
+fn rejects_sample_counts_that_overflow() {
+	let (manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+	let mut manager = KiraAudioManager(manager);
+	let samples = [0.0f32; 2];
+	kira_clear_last_error();
+	for num_frames in [usize::MAX, usize::MAX / 2 + 1, isize::MAX as usize / 4] {
+		assert!(unsafe {
+			kira_manager_add_sound_from_samples(&mut manager, 48000, samples.as_ptr(), num_frames)
+		}
+		.is_null());
+		assert_eq!(
+			last_error().as_deref(),
+			Some("The number of frames is too large")
+		);
+	}
+	let sound =
+		unsafe { kira_manager_add_sound_from_samples(&mut manager, 48000, samples.as_ptr(), 1) };
+	assert!(!sound.is_null());
+	unsafe { kira_sound_free(sound) };
+}
+
+#[test]
+fn ignores_null_handles_when_freeing() {
+	unsafe { kira_sound_free(null_mut()) };
+}
+
+#[test]
+fn converts_default_instance_settings() {
+	let settings: InstanceSettings = kira_instance_settings_default().into();
+	let default_settings = InstanceSettings::new();
+	assert_eq!(settings.start_position, default_settings.start_position);
+	assert_eq!(settings.reverse, default_settings.reverse);
+	assert!(matches!(
+		settings.loop_start,
+		kira::instance::InstanceLoopStart::Default
+	));
+	assert!(matches!(
+		settings.fade_in_tween,
+		kira::instance::InstanceFadeTween::Default
+	));
+}
@@ -0,0 +1,19 @@
+//! Auditions the sounds in a preview project.
+//!
+//! Usage: `cargo run --example preview --features preview -- project.ron`
+
+use kira::{manager::AudioManagerSettings, preview};
+
+fn main() {
+	let path = match std::env::args().nth(1) {
+		Some(path) => path,
+		None => {
+			eprintln!("Usage: preview <project file>");
+			std::process::exit(1);
+		}
+	};
+	if let Err(error) = preview::run(path, AudioManagerSettings::default()) {
+		eprintln!("{}", error);
+		std::process::exit(1);
+	}
+}
@@ -1,11 +1,11 @@
 use basedrop::Owned;
 
 use crate::{
+	dsp::inverse_lerp,
+	dsp::lerp,
 	sound::{handle::SoundHandle, Sound, SoundId},
 	static_container::index_map::StaticIndexMap,
-	util::inverse_lerp,
-	util::lerp,
-	Frame,
+	Frame, Sample,
 };
 
 /// A segment of a sound in an arrangement.
@@ -24,6 +24,39 @@ pub struct SoundClip {
 	/// This range of the sound is stretched over
 	/// the range of the clip.
 	pub sound_time_range: (f64, f64),
+	/// The volume of the clip.
+	#[cfg_attr(feature = "serde_support", serde(default = "default_volume"))]
+	pub volume: f64,
+	/// The panning of the clip, where 0 is hard left
+	/// and 1 is hard right.
+	#[cfg_attr(feature = "serde_support", serde(default = "default_panning"))]
+	pub panning: f64,
+	/// How long it takes the clip to fade in from silence
+	/// (in seconds).
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub fade_in: f64,
+	/// How long it takes the clip to fade out to silence
+	/// (in seconds).
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub fade_out: f64,
+	/// The layer of the arrangement this clip belongs to, if any.
+	///
+	/// The clip's volume will be multiplied by the volume
+	/// of the layer.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub layer: Option<usize>,
+}
+
+// clips serialized before these fields existed play
+// at full volume in the center with no fades
+#[cfg(feature = "serde_support")]
+fn default_volume() -> f64 {
+	1.0
+}
+
+#[cfg(feature = "serde_support")]
+fn default_panning() -> f64 {
+	0.5
 }
 
 impl SoundClip {
@@ -35,6 +68,11 @@ impl SoundClip {
 			sound_id: sound_handle.id(),
 			clip_time_range: (clip_start_time, clip_start_time + sound_handle.duration()),
 			sound_time_range: (0.0, sound_handle.duration()),
+			volume: 1.0,
+			panning: 0.5,
+			fade_in: 0.0,
+			fade_out: 0.0,
+			layer: None,
 		}
 	}
 
@@ -65,6 +103,56 @@ impl SoundClip {
 		self
 	}
 
+	/// Sets the volume of the clip.
+	pub fn volume(self, volume: f64) -> Self {
+		Self { volume, ..self }
+	}
+
+	/// Sets the panning of the clip, where 0 is hard left
+	/// and 1 is hard right.
+	pub fn panning(self, panning: f64) -> Self {
+		Self { panning, ..self }
+	}
+
+	/// Sets how long it takes the clip to fade in from silence
+	/// (in seconds).
+	///
+	/// Overlapping a clip that fades out with a clip that
+	/// fades in creates a crossfade between the two.
+	pub fn fade_in(self, fade_in: f64) -> Self {
+		Self { fade_in, ..self }
+	}
+
+	/// Sets how long it takes the clip to fade out to silence
+	/// (in seconds).
+	///
+	/// Overlapping a clip that fades out with a clip that
+	/// fades in creates a crossfade between the two.
+	pub fn fade_out(self, fade_out: f64) -> Self {
+		Self { fade_out, ..self }
+	}
+
+	/// Sets the layer of the arrangement this clip belongs to.
+	pub fn layer(self, layer: usize) -> Self {
+		Self {
+			layer: Some(layer),
+			..self
+		}
+	}
+
+	/// Gets the volume of the clip at a given time,
+	/// taking fades into account.
+	fn volume_at_position(&self, position: f64) -> f64 {
+		let mut volume = self.volume;
+		if self.fade_in > 0.0 {
+			volume *= ((position - self.clip_time_range.0) / self.fade_in).min(1.0);
+		}
+		if self.fade_out > 0.0 {
+			volume *= ((self.clip_time_range.1 - position) / self.fade_out).min(1.0);
+		}
+		volume
+	}
+
 	/// Gets the frame that this clip will output at a given time.
 	///
 	/// If the time is outside of the clip's time range, no sound
@@ -80,11 +168,19 @@ impl SoundClip {
 			if relative_time < 0.0 || relative_time > 1.0 {
 				Frame::from_mono(0.0)
 			} else {
-				sound.get_frame_at_position(lerp(
+				let frame = sound.get_frame_at_position(lerp(
 					self.sound_time_range.0,
 					self.sound_time_range.1,
 					relative_time,
-				))
+				));
+				let volume = self.volume_at_position(position) as Sample;
+				// use a balance control rather than the instance panning
+				// law so that centered clips keep their original volume
+				let panning = self.panning as Sample;
+				Frame::new(
+					frame.left * volume * (2.0 * (1.0 - panning)).min(1.0),
+					frame.right * volume * (2.0 * panning).min(1.0),
+				)
 			}
 		} else {
 			Frame::from_mono(0.0)
@@ -0,0 +1,185 @@
+//! Describes arrangements as data.
+//!
+//! An [`ArrangementDefinition`] is like an [`Arrangement`], but it
+//! refers to sounds, groups, and mixer tracks by the names given
+//! to them with [`AudioManager::set_name`](crate::manager::AudioManager::set_name).
+//! With the `serde_support` feature, definitions can be loaded
+//! from data files, and [`AudioManager::arrangement_from_definition`](crate::manager::AudioManager::arrangement_from_definition)
+//! turns them into arrangements:
+//!
+//! ```json
+//! {
+//! 	"default_track": "music",
+//! 	"default_loop_start": 4.0,
+//! 	"clips": [
+//! 		{ "sound": "intro" },
+//! 		{ "sound": "loop", "start_time": 4.0 },
+//! 		{ "sound": "loop", "start_time": 12.0, "duration": 8.0 }
+//! 	]
+//! }
+//! ```
+
+use crate::{
+	manager::{error::ResolveNameError, names::Names},
+	mixer::TrackIndex,
+	parameter::tween::Tween,
+	Tempo, Value,
+};
+
+use super::{Arrangement, ArrangementSettings, SoundClip};
+
+/// A clip in an [`ArrangementDefinition`].
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct SoundClipDefinition {
+	/// The name of the sound.
+	pub sound: String,
+	/// When the clip starts in the arrangement (in seconds).
+	pub start_time: f64,
+	/// The start and end point of the sound.
+	///
+	/// If this is `None`, the whole sound will be used.
+	pub sound_time_range: Option<(f64, f64)>,
+	/// How long the clip is (in seconds).
+	///
+	/// The sound will be sped up or slowed down to fit.
+	/// If this is `None`, the sound will play at its
+	/// normal speed.
+	pub duration: Option<f64>,
+	/// The volume of the clip.
+	pub volume: f64,
+	/// The panning of the clip, where 0 is hard left
+	/// and 1 is hard right.
+	pub panning: f64,
+	/// How long it takes the clip to fade in from silence
+	/// (in seconds).
+	pub fade_in: f64,
+	/// How long it takes the clip to fade out to silence
+	/// (in seconds).
+	pub fade_out: f64,
+	/// The layer of the arrangement this clip belongs to, if any.
+	pub layer: Option<usize>,
+}
+
+impl SoundClipDefinition {
+	/// Creates a new clip definition that plays the whole sound
+	/// with the given name, starting at the specified time.
+	pub fn new(sound: impl Into<String>, start_time: f64) -> Self {
+		Self {
+			sound: sound.into(),
+			start_time,
+			..Default::default()
+		}
+	}
+}
+
+impl Default for SoundClipDefinition {
+	fn default() -> Self {
+		Self {
+			sound: String::new(),
+			start_time: 0.0,
+			sound_time_range: None,
+			duration: None,
+			volume: 1.0,
+			panning: 0.5,
+			fade_in: 0.0,
+			fade_out: 0.0,
+			layer: None,
+		}
+	}
+}
+
+/// An arrangement that refers to resources by name.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct ArrangementDefinition {
+	/// The name of the track instances of this arrangement
+	/// will play on by default.
+	///
+	/// If this is `None`, the main track will be used.
+	pub default_track: Option<String>,
+	/// Whether the arrangement should have a "cool off" period
+	/// after playing before it can be played again, and if so,
+	/// the duration of that cool off period.
+	pub cooldown: Option<f64>,
+	/// How long the arrangement is musically.
+	pub semantic_duration: Option<f64>,
+	/// The tempo the arrangement was recorded at.
+	pub native_tempo: Option<Tempo>,
+	/// Whether the arrangement should be looped by default, and if so,
+	/// the point an instance should jump back to when it reaches
+	/// the end.
+	pub default_loop_start: Option<f64>,
+	/// The tween instances of the arrangement will fade in with
+	/// by default.
+	pub default_fade_in_tween: Option<Tween>,
+	/// The tween instances of the arrangement will fade out with
+	/// by default when they're stopped.
+	pub default_fade_out_tween: Option<Tween>,
+	/// The names of the groups the arrangement belongs to.
+	pub groups: Vec<String>,
+	/// The starting volumes of the arrangement's layers.
+	pub layer_volumes: Vec<Value<f64>>,
+	/// The clips in the arrangement.
+	pub clips: Vec<SoundClipDefinition>,
+	/// Whether to keep track of how often the arrangement
+	/// is played and how long it's heard for.
+	pub collect_statistics: bool,
+}
+
+impl ArrangementDefinition {
+	/// Creates a new, empty arrangement definition.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates an arrangement from the definition, looking up
+	/// resources using the given names.
+	pub(crate) fn resolve(&self, names: &Names) -> Result<Arrangement, ResolveNameError> {
+		let default_track = match &self.default_track {
+			Some(name) => names.resolve_track(name)?,
+			None => TrackIndex::Main,
+		};
+		let mut arrangement = Arrangement::new(ArrangementSettings {
+			id: None,
+			default_track,
+			cooldown: self.cooldown,
+			semantic_duration: self.semantic_duration,
+			native_tempo: self.native_tempo,
+			default_loop_start: self.default_loop_start,
+			default_fade_in_tween: self.default_fade_in_tween,
+			default_fade_out_tween: self.default_fade_out_tween,
+			groups: names.resolve_groups(&self.groups)?,
+			collect_statistics: self.collect_statistics,
+		});
+		for volume in &self.layer_volumes {
+			arrangement.add_layer(*volume);
+		}
+		for clip in &self.clips {
+			let sound = names.resolve_sound(&clip.sound)?;
+			let sound_time_range = clip.sound_time_range.unwrap_or((0.0, sound.duration()));
+			let duration = clip
+				.duration
+				.unwrap_or(sound_time_range.1 - sound_time_range.0);
+			arrangement.add_clip(SoundClip {
+				sound_id: sound.id(),
+				clip_time_range: (clip.start_time, clip.start_time + duration),
+				sound_time_range,
+				volume: clip.volume,
+				panning: clip.panning,
+				fade_in: clip.fade_in,
+				fade_out: clip.fade_out,
+				layer: clip.layer,
+			});
+		}
+		Ok(arrangement)
+	}
+}
@@ -1,15 +1,25 @@
 //! An interface for controlling arrangements.
 
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc,
+};
+
 use crate::{
 	command::{
 		producer::{CommandError, CommandProducer},
-		InstanceCommand,
+		InstanceCommand, ResourceCommand,
 	},
 	instance::{
-		handle::InstanceHandle, Instance, InstanceId, InstanceSettings, PauseInstanceSettings,
-		ResumeInstanceSettings, StopInstanceSettings,
+		handle::{InstanceEffectContext, InstanceHandle},
+		Instance, InstanceId, InstanceSettings, PauseInstanceSettings, ResumeInstanceSettings,
+		StopInstanceSettings,
 	},
 	mixer::TrackIndex,
+	parameter::tween::Tween,
+	playable::{PlaybackCounters, PlaybackStatistics},
+	util::SharedRng,
+	Value,
 };
 
 use super::{Arrangement, ArrangementId};
@@ -22,26 +32,75 @@ pub struct ArrangementHandle {
 	default_track: TrackIndex,
 	semantic_duration: Option<f64>,
 	default_loop_start: Option<f64>,
+	default_fade_in_tween: Option<Tween>,
+	default_fade_out_tween: Option<Tween>,
+	statistics: Option<Arc<PlaybackCounters>>,
 	command_producer: CommandProducer,
+	rng: SharedRng,
+	effect_context: InstanceEffectContext,
+	loaded: Arc<AtomicBool>,
+	// keeps the arrangement loaded while the handle exists when
+	// the audio manager unloads unused resources automatically
+	_reference: Option<Arc<()>>,
 }
 
 impl ArrangementHandle {
-	pub(crate) fn new(arrangement: &Arrangement, command_producer: CommandProducer) -> Self {
+	pub(crate) fn new(
+		arrangement: &Arrangement,
+		command_producer: CommandProducer,
+		rng: SharedRng,
+		effect_context: InstanceEffectContext,
+	) -> Self {
 		Self {
 			id: arrangement.id(),
 			duration: arrangement.duration(),
 			default_track: arrangement.default_track(),
 			semantic_duration: arrangement.semantic_duration(),
 			default_loop_start: arrangement.default_loop_start(),
+			default_fade_in_tween: arrangement.default_fade_in_tween(),
+			default_fade_out_tween: arrangement.default_fade_out_tween(),
+			statistics: arrangement.statistics().cloned(),
 			command_producer,
+			rng,
+			effect_context,
+			loaded: Arc::new(AtomicBool::new(true)),
+			_reference: None,
 		}
 	}
 
+	pub(crate) fn with_reference(self, reference: Option<Arc<()>>) -> Self {
+		Self {
+			_reference: reference,
+			..self
+		}
+	}
+
+	/// Tells every handle to the arrangement that it has been
+	/// removed from the audio thread.
+	pub(crate) fn mark_unloaded(&self) {
+		self.loaded.store(false, Ordering::Relaxed);
+	}
+
+	/// Returns the audio manager's random number generator.
+	pub(crate) fn rng(&self) -> &SharedRng {
+		&self.rng
+	}
+
 	/// Returns the ID of the arrangement.
 	pub fn id(&self) -> ArrangementId {
 		self.id
 	}
 
+	/// Returns `true` if the arrangement hasn't been removed
+	/// from the audio manager.
+	///
+	/// Playing a arrangement that has been removed does nothing,
+	/// and the audio thread reports it as an
+	/// [error](crate::manager::AudioManager::pop_audio_thread_error).
+	pub fn is_loaded(&self) -> bool {
+		self.loaded.load(Ordering::Relaxed)
+	}
+
 	/// Returns the duration of the arrangement (in seconds).
 	pub fn duration(&self) -> f64 {
 		self.duration
@@ -66,19 +125,40 @@ impl ArrangementHandle {
 		self.default_loop_start
 	}
 
+	/// Returns how often the arrangement has been played and
+	/// how long it was heard for, or `None` if the arrangement
+	/// doesn't [collect statistics](super::ArrangementSettings::collect_statistics).
+	pub fn statistics(&self) -> Option<PlaybackStatistics> {
+		self.statistics
+			.as_ref()
+			.map(|statistics| statistics.statistics())
+	}
+
 	/// Plays the arrangement.
 	pub fn play(&mut self, settings: InstanceSettings) -> Result<InstanceHandle, CommandError> {
 		let id = settings.id.unwrap_or(InstanceId::new());
+		let settings = InstanceSettings {
+			random_seed: Some(settings.random_seed.unwrap_or_else(|| self.rng.next_seed())),
+			..settings
+		};
 		let instance = Instance::new(
 			self.id.into(),
 			self.duration,
 			None,
-			settings.into_internal(self.duration, self.default_loop_start, self.default_track),
+			settings.into_internal(
+				self.duration,
+				self.default_loop_start,
+				self.default_track,
+				self.default_fade_in_tween,
+				self.default_fade_out_tween,
+			),
 		);
 		let handle = InstanceHandle::new(
 			id,
 			instance.public_state(),
 			instance.public_position(),
+			settings.num_effects,
+			self.effect_context.clone(),
 			self.command_producer.clone(),
 		);
 		self.command_producer
@@ -103,4 +183,16 @@ impl ArrangementHandle {
 		self.command_producer
 			.push(InstanceCommand::StopInstancesOf(self.id.into(), settings).into())
 	}
+
+	/// Sets the volume of one of the arrangement's layers.
+	///
+	/// This affects all instances of the arrangement.
+	pub fn set_layer_volume(
+		&mut self,
+		layer: usize,
+		volume: impl Into<Value<f64>>,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(ResourceCommand::SetArrangementLayerVolume(self.id, layer, volume.into()).into())
+	}
 }
@@ -1,9 +1,9 @@
 //! Combines individual sounds into larger pieces.
 //!
 //! `Arrangement`s are containers of `SoundClip`s, which are portions of
-//! a sound that can be positioned in time, stretched, trimmed, and
-//! reversed. You can play instances of an arrangement just like you would
-//! play instances of a sound.
+//! a sound that can be positioned in time, stretched, trimmed,
+//! reversed, and faded in and out. You can play instances of an
+//! arrangement just like you would play instances of a sound.
 //!
 //! `Arrangement`s are a lot like arrangement views in DAWs, like the
 //! playlist view in FL Studio. In fact, the playlist view in FL Studio
@@ -74,7 +74,7 @@
 //! # 	sound::{Sound, SoundSettings}, Tempo,
 //! # };
 //! #
-//! # let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file(
 //! # 	std::env::current_dir()?.join("assets/loop.wav"),
 //! # 	SoundSettings::default(),
@@ -102,7 +102,7 @@
 //! # 	sound::{Sound, SoundSettings}, Tempo,
 //! # };
 //! #
-//! # let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
 //! let tempo = Tempo(140.0);
 //! let sound_handle = audio_manager.add_sound(Sound::from_file(
 //! 	std::env::current_dir()?.join("assets/loop.wav"),
@@ -123,11 +123,107 @@
 //!
 //! For brevity, we'll just say you can use [`Arrangement::new_loop_with_intro`]
 //! to create these.
+//!
+//! ## Slicing sounds
+//!
+//! An arrangement can also play just a region of a sound using
+//! [`Arrangement::new_slice`]. The slice refers to the sound's
+//! audio data instead of copying it, so you can cut audio sprites,
+//! stingers, or dialogue lines out of one long file and play, group,
+//! and loop each of them independently:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	arrangement::{Arrangement, ArrangementSettings},
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sound::{Sound, SoundSettings},
+//! # };
+//! #
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let sound_handle = audio_manager.load_sound("sprites.ogg", SoundSettings::default())?;
+//! let mut jump_sound_handle = audio_manager.add_arrangement(Arrangement::new_slice(
+//! 	&sound_handle,
+//! 	1.5,
+//! 	2.25,
+//! 	ArrangementSettings::default(),
+//! ))?;
+//! jump_sound_handle.play(InstanceSettings::default())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Layered music
+//!
+//! Adaptive music is often split into stems (for example, drums,
+//! bass, and lead) that fade in and out depending on what's
+//! happening in the game. [`Arrangement::new_layered`] loops
+//! several sounds in sync, each on its own layer, and each
+//! layer's volume can be controlled separately. Linking layer
+//! volumes to a [parameter](crate::parameter) lets you fade
+//! all of the layers with a single "intensity" value:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	arrangement::{Arrangement, LoopArrangementSettings},
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	parameter::{Mapping, ParameterSettings},
+//! # 	sound::SoundSettings,
+//! # 	Value,
+//! # };
+//! #
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let drums = audio_manager.load_sound("drums.ogg", SoundSettings::default())?;
+//! let bass = audio_manager.load_sound("bass.ogg", SoundSettings::default())?;
+//! let lead = audio_manager.load_sound("lead.ogg", SoundSettings::default())?;
+//! let mut intensity = audio_manager.add_parameter(ParameterSettings::new().value(0.0))?;
+//! let mut arrangement =
+//! 	Arrangement::new_layered(&[&drums, &bass, &lead], LoopArrangementSettings::default());
+//! // the bass fades in as the intensity goes from 0 to 0.5,
+//! // and the lead fades in as the intensity goes from 0.5 to 1
+//! arrangement
+//! 	.set_layer_volume(
+//! 		1,
+//! 		Value::Parameter(
+//! 			intensity.id(),
+//! 			Mapping {
+//! 				input_range: (0.0, 0.5),
+//! 				output_range: (0.0, 1.0),
+//! 				clamp_bottom: true,
+//! 				clamp_top: true,
+//! 				..Default::default()
+//! 			},
+//! 		),
+//! 	)
+//! 	.set_layer_volume(
+//! 		2,
+//! 		Value::Parameter(
+//! 			intensity.id(),
+//! 			Mapping {
+//! 				input_range: (0.5, 1.0),
+//! 				output_range: (0.0, 1.0),
+//! 				clamp_bottom: true,
+//! 				clamp_top: true,
+//! 				..Default::default()
+//! 			},
+//! 		),
+//! 	);
+//! let mut arrangement_handle = audio_manager.add_arrangement(arrangement)?;
+//! arrangement_handle.play(InstanceSettings::default())?;
+//! // later, when things heat up
+//! intensity.set(1.0, Some(4.0.into()))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
 mod clip;
+pub mod definition;
 pub mod handle;
 mod id;
 mod settings;
+#[cfg(test)]
+mod tests;
+
+use std::sync::Arc;
 
 use basedrop::Owned;
 pub use clip::SoundClip;
@@ -138,9 +234,11 @@ pub use settings::{ArrangementSettings, LoopArrangementSettings};
 use crate::{
 	group::{groups::Groups, GroupId, GroupSet},
 	mixer::TrackIndex,
+	parameter::{tween::Tween, Parameters},
+	playable::PlaybackCounters,
 	sound::{handle::SoundHandle, Sound, SoundId},
 	static_container::index_map::StaticIndexMap,
-	Frame,
+	CachedValue, Frame, Sample, Tempo, Value,
 };
 
 /// An arrangement of sound clips to play at specific times.
@@ -156,8 +254,15 @@ pub struct Arrangement {
 	default_track: TrackIndex,
 	cooldown: Option<f64>,
 	semantic_duration: Option<f64>,
+	native_tempo: Option<Tempo>,
 	default_loop_start: Option<f64>,
+	default_fade_in_tween: Option<Tween>,
+	default_fade_out_tween: Option<Tween>,
 	groups: GroupSet,
+	layer_volumes: Vec<CachedValue<f64>>,
+	collect_statistics: bool,
+	#[cfg_attr(feature = "serde_support", serde(skip))]
+	statistics: Option<Arc<PlaybackCounters>>,
 	cooldown_timer: f64,
 }
 
@@ -171,8 +276,14 @@ impl Arrangement {
 			default_track: settings.default_track,
 			cooldown: settings.cooldown,
 			semantic_duration: settings.semantic_duration,
+			native_tempo: settings.native_tempo,
 			default_loop_start: settings.default_loop_start,
+			default_fade_in_tween: settings.default_fade_in_tween,
+			default_fade_out_tween: settings.default_fade_out_tween,
 			groups: settings.groups,
+			layer_volumes: vec![],
+			collect_statistics: settings.collect_statistics,
+			statistics: None,
 			cooldown_timer: 0.0,
 		}
 	}
@@ -192,8 +303,12 @@ impl Arrangement {
 			default_track: settings.default_track,
 			cooldown: settings.cooldown,
 			semantic_duration: settings.semantic_duration,
+			native_tempo: settings.native_tempo,
 			default_loop_start: Some(duration),
+			default_fade_in_tween: settings.default_fade_in_tween,
+			default_fade_out_tween: settings.default_fade_out_tween,
 			groups: settings.groups,
+			collect_statistics: settings.collect_statistics,
 		});
 		arrangement
 			.add_clip(SoundClip::new(sound_handle, 0.0))
@@ -225,8 +340,12 @@ impl Arrangement {
 			default_track: settings.default_track,
 			cooldown: settings.cooldown,
 			semantic_duration: settings.semantic_duration,
+			native_tempo: settings.native_tempo,
 			default_loop_start: Some(intro_duration + loop_duration),
+			default_fade_in_tween: settings.default_fade_in_tween,
+			default_fade_out_tween: settings.default_fade_out_tween,
 			groups: settings.groups,
+			collect_statistics: settings.collect_statistics,
 		});
 		arrangement
 			.add_clip(SoundClip::new(intro_sound_handle, 0.0))
@@ -238,6 +357,75 @@ impl Arrangement {
 		arrangement
 	}
 
+	/// Creates a new arrangement that plays a region of a sound.
+	///
+	/// `start` and `end` are the start and end points of the
+	/// region (in seconds), and they will be clamped to the
+	/// duration of the sound. The sound's audio data is not copied.
+	pub fn new_slice(
+		sound_handle: &SoundHandle,
+		start: f64,
+		end: f64,
+		settings: ArrangementSettings,
+	) -> Self {
+		let start = start.max(0.0).min(sound_handle.duration());
+		let end = end.max(start).min(sound_handle.duration());
+		let mut arrangement = Self::new(settings);
+		arrangement.add_clip(SoundClip {
+			sound_id: sound_handle.id(),
+			clip_time_range: (0.0, end - start),
+			sound_time_range: (start, end),
+			..SoundClip::new(sound_handle, 0.0)
+		});
+		arrangement
+	}
+
+	/// Creates a new arrangement that seamlessly loops multiple
+	/// sounds at the same time, each on its own layer.
+	///
+	/// Each sound is placed on the layer with the same index,
+	/// so the volume of each sound can be controlled separately
+	/// using [`set_layer_volume`](Self::set_layer_volume) or
+	/// [`ArrangementHandle::set_layer_volume`]. All layers start
+	/// at full volume.
+	///
+	/// The arrangement loops at the end of the longest sound.
+	/// As with [`new_loop`](Self::new_loop), semantic durations
+	/// are used to set the loop point if the sounds have them.
+	pub fn new_layered(sound_handles: &[&SoundHandle], settings: LoopArrangementSettings) -> Self {
+		let duration = sound_handles
+			.iter()
+			.map(|sound_handle| {
+				sound_handle
+					.semantic_duration()
+					.unwrap_or(sound_handle.duration())
+			})
+			.fold(0.0, f64::max);
+		let mut arrangement = Self::new(ArrangementSettings {
+			id: settings.id,
+			default_track: settings.default_track,
+			cooldown: settings.cooldown,
+			semantic_duration: settings.semantic_duration,
+			native_tempo: settings.native_tempo,
+			default_loop_start: Some(duration),
+			default_fade_in_tween: settings.default_fade_in_tween,
+			default_fade_out_tween: settings.default_fade_out_tween,
+			groups: settings.groups,
+			collect_statistics: settings.collect_statistics,
+		});
+		for sound_handle in sound_handles {
+			let layer = arrangement.add_layer(1.0);
+			arrangement
+				.add_clip(SoundClip::new(sound_handle, 0.0).layer(layer))
+				.add_clip(
+					SoundClip::new(sound_handle, duration)
+						.trim(duration)
+						.layer(layer),
+				);
+		}
+		arrangement
+	}
+
 	/// Adds a sound clip to the arrangement.
 	pub fn add_clip(&mut self, clip: SoundClip) -> &mut Self {
 		self.duration = self.duration.max(clip.clip_time_range.1);
@@ -245,6 +433,29 @@ impl Arrangement {
 		self
 	}
 
+	/// Adds a layer to the arrangement with the given volume
+	/// and returns the index of the new layer.
+	///
+	/// Clips can be assigned to the layer using [`SoundClip::layer`].
+	pub fn add_layer(&mut self, volume: impl Into<Value<f64>>) -> usize {
+		self.layer_volumes
+			.push(CachedValue::new(volume.into(), 1.0).with_min(0.0));
+		self.layer_volumes.len() - 1
+	}
+
+	/// Sets the volume of a layer.
+	pub fn set_layer_volume(&mut self, layer: usize, volume: impl Into<Value<f64>>) -> &mut Self {
+		if let Some(layer_volume) = self.layer_volumes.get_mut(layer) {
+			layer_volume.set(volume.into());
+		}
+		self
+	}
+
+	/// Gets the number of layers in the arrangement.
+	pub fn num_layers(&self) -> usize {
+		self.layer_volumes.len()
+	}
+
 	/// Gets the unique identifier for this arrangement.
 	pub fn id(&self) -> ArrangementId {
 		self.id
@@ -272,6 +483,12 @@ impl Arrangement {
 		self.semantic_duration
 	}
 
+	/// Gets the tempo the arrangement was recorded at (if
+	/// there is one).
+	pub fn native_tempo(&self) -> Option<Tempo> {
+		self.native_tempo
+	}
+
 	/// Returns the default time (in seconds) instances
 	/// of this arrangement will loop back to when they reach
 	/// the end.
@@ -279,6 +496,18 @@ impl Arrangement {
 		self.default_loop_start
 	}
 
+	/// Returns the tween instances of this arrangement
+	/// fade in with by default.
+	pub fn default_fade_in_tween(&self) -> Option<Tween> {
+		self.default_fade_in_tween
+	}
+
+	/// Returns the tween instances of this arrangement
+	/// fade out with by default when they're stopped.
+	pub fn default_fade_out_tween(&self) -> Option<Tween> {
+		self.default_fade_out_tween
+	}
+
 	/// Gets the frame at the given position of the arrangement.
 	pub(crate) fn get_frame_at_position(
 		&self,
@@ -287,12 +516,51 @@ impl Arrangement {
 	) -> Frame {
 		let mut frame = Frame::from_mono(0.0);
 		for clip in &self.clips {
-			frame += clip.get_frame_at_position(position, sounds);
+			let layer_volume = clip
+				.layer
+				.and_then(|layer| self.layer_volumes.get(layer))
+				.map_or(1.0, |volume| volume.value());
+			frame += clip.get_frame_at_position(position, sounds) * layer_volume as Sample;
 		}
 		frame
 	}
 
+	/// Returns `true` if any of the arrangement's clips
+	/// play the given sound.
+	pub(crate) fn uses_sound(&self, id: SoundId) -> bool {
+		self.clips.iter().any(|clip| clip.sound_id == id)
+	}
+
+	/// Returns the IDs of the sounds the arrangement's clips play.
+	pub(crate) fn sound_ids(&self) -> impl Iterator<Item = SoundId> + '_ {
+		self.clips.iter().map(|clip| clip.sound_id)
+	}
+
+	/// Updates the volumes of the arrangement's layers.
+	pub(crate) fn update_layer_volumes(&mut self, parameters: &Parameters) {
+		for volume in &mut self.layer_volumes {
+			volume.update(parameters);
+		}
+	}
+
 	/// Starts the cooldown timer for the arrangement.
+	/// Creates the playback counters for the arrangement if it
+	/// should collect statistics.
+	///
+	/// This is done when the arrangement is added to an audio
+	/// manager, so each added arrangement has its own statistics.
+	pub(crate) fn init_statistics(&mut self) {
+		if self.collect_statistics {
+			self.statistics = Some(Arc::new(PlaybackCounters::default()));
+		}
+	}
+
+	/// Returns the playback counters of the arrangement, if
+	/// it collects statistics.
+	pub(crate) fn statistics(&self) -> Option<&Arc<PlaybackCounters>> {
+		self.statistics.as_ref()
+	}
+
 	pub(crate) fn start_cooldown(&mut self) {
 		if let Some(cooldown) = self.cooldown {
 			self.cooldown_timer = cooldown;
@@ -1,4 +1,4 @@
-use crate::{group::GroupSet, mixer::TrackIndex};
+use crate::{group::GroupSet, mixer::TrackIndex, parameter::tween::Tween, Tempo};
 
 use super::ArrangementId;
 
@@ -33,12 +33,31 @@ pub struct ArrangementSettings {
 	/// If set, the semantic duration of the arrangement will be
 	/// used as the default end point when looping the arrangement.
 	pub semantic_duration: Option<f64>,
+	/// The tempo the arrangement was recorded at (if it's music).
+	///
+	/// Instances of the arrangement that are
+	/// [synced to a metronome's tempo](crate::instance::InstanceSettings::tempo_sync)
+	/// play faster or slower so they stay in time with the metronome.
+	pub native_tempo: Option<Tempo>,
 	/// Whether the arrangement should be looped by default, and if so,
 	/// the point an instance should jump back to when it reaches
 	/// the end.
 	pub default_loop_start: Option<f64>,
+	/// The tween instances of this arrangement will fade in with,
+	/// unless a different fade-in tween is set when playing it.
+	pub default_fade_in_tween: Option<Tween>,
+	/// The tween instances of this arrangement will fade out with when
+	/// they're stopped, unless a different fade-out tween is set
+	/// when stopping them.
+	pub default_fade_out_tween: Option<Tween>,
 	/// The groups this arrangement belongs to.
 	pub groups: GroupSet,
+	/// Whether to keep track of how often the arrangement is played
+	/// and how long it's heard for.
+	///
+	/// The statistics can be retrieved with
+	/// [`ArrangementHandle::statistics`](crate::arrangement::handle::ArrangementHandle::statistics).
+	pub collect_statistics: bool,
 }
 
 impl ArrangementSettings {
@@ -79,6 +98,14 @@ impl ArrangementSettings {
 		}
 	}
 
+	/// Sets the tempo the arrangement was recorded at.
+	pub fn native_tempo(self, native_tempo: impl Into<Tempo>) -> Self {
+		Self {
+			native_tempo: Some(native_tempo.into()),
+			..self
+		}
+	}
+
 	/// Sets the default loop start point of the arrangement.
 	pub fn default_loop_start(self, default_loop_start: f64) -> Self {
 		Self {
@@ -87,6 +114,23 @@ impl ArrangementSettings {
 		}
 	}
 
+	/// Sets the tween instances of this arrangement will fade in with.
+	pub fn default_fade_in_tween(self, tween: impl Into<Tween>) -> Self {
+		Self {
+			default_fade_in_tween: Some(tween.into()),
+			..self
+		}
+	}
+
+	/// Sets the tween instances of this arrangement will fade out with
+	/// when they're stopped.
+	pub fn default_fade_out_tween(self, tween: impl Into<Tween>) -> Self {
+		Self {
+			default_fade_out_tween: Some(tween.into()),
+			..self
+		}
+	}
+
 	/// Sets the group this arrangement belongs to.
 	pub fn groups(self, groups: impl Into<GroupSet>) -> Self {
 		Self {
@@ -94,6 +138,15 @@ impl ArrangementSettings {
 			..self
 		}
 	}
+
+	/// Makes the arrangement keep track of how often it's played
+	/// and how long it's heard for.
+	pub fn collect_statistics(self) -> Self {
+		Self {
+			collect_statistics: true,
+			..self
+		}
+	}
 }
 
 impl Default for ArrangementSettings {
@@ -103,8 +156,12 @@ impl Default for ArrangementSettings {
 			default_track: TrackIndex::Main,
 			cooldown: Some(0.0001),
 			semantic_duration: None,
+			native_tempo: None,
 			default_loop_start: None,
+			default_fade_in_tween: None,
+			default_fade_out_tween: None,
 			groups: GroupSet::new(),
+			collect_statistics: false,
 		}
 	}
 }
@@ -140,8 +197,27 @@ pub struct LoopArrangementSettings {
 	/// If set, the semantic duration of the arrangement will be
 	/// used as the default end point when looping the arrangement.
 	pub semantic_duration: Option<f64>,
+	/// The tempo the arrangement was recorded at (if it's music).
+	///
+	/// Instances of the arrangement that are
+	/// [synced to a metronome's tempo](crate::instance::InstanceSettings::tempo_sync)
+	/// play faster or slower so they stay in time with the metronome.
+	pub native_tempo: Option<Tempo>,
+	/// The tween instances of this arrangement will fade in with,
+	/// unless a different fade-in tween is set when playing it.
+	pub default_fade_in_tween: Option<Tween>,
+	/// The tween instances of this arrangement will fade out with when
+	/// they're stopped, unless a different fade-out tween is set
+	/// when stopping them.
+	pub default_fade_out_tween: Option<Tween>,
 	/// The groups this arrangement belongs to.
 	pub groups: GroupSet,
+	/// Whether to keep track of how often the arrangement is played
+	/// and how long it's heard for.
+	///
+	/// The statistics can be retrieved with
+	/// [`ArrangementHandle::statistics`](crate::arrangement::handle::ArrangementHandle::statistics).
+	pub collect_statistics: bool,
 }
 
 impl LoopArrangementSettings {
@@ -182,6 +258,31 @@ impl LoopArrangementSettings {
 		}
 	}
 
+	/// Sets the tempo the arrangement was recorded at.
+	pub fn native_tempo(self, native_tempo: impl Into<Tempo>) -> Self {
+		Self {
+			native_tempo: Some(native_tempo.into()),
+			..self
+		}
+	}
+
+	/// Sets the tween instances of this arrangement will fade in with.
+	pub fn default_fade_in_tween(self, tween: impl Into<Tween>) -> Self {
+		Self {
+			default_fade_in_tween: Some(tween.into()),
+			..self
+		}
+	}
+
+	/// Sets the tween instances of this arrangement will fade out with
+	/// when they're stopped.
+	pub fn default_fade_out_tween(self, tween: impl Into<Tween>) -> Self {
+		Self {
+			default_fade_out_tween: Some(tween.into()),
+			..self
+		}
+	}
+
 	/// Sets the group this arrangement belongs to.
 	pub fn groups(self, groups: impl Into<GroupSet>) -> Self {
 		Self {
@@ -189,6 +290,15 @@ impl LoopArrangementSettings {
 			..self
 		}
 	}
+
+	/// Makes the arrangement keep track of how often it's played
+	/// and how long it's heard for.
+	pub fn collect_statistics(self) -> Self {
+		Self {
+			collect_statistics: true,
+			..self
+		}
+	}
 }
 
 impl Default for LoopArrangementSettings {
@@ -198,7 +308,11 @@ impl Default for LoopArrangementSettings {
 			default_track: TrackIndex::Main,
 			cooldown: Some(0.0001),
 			semantic_duration: None,
+			native_tempo: None,
+			default_fade_in_tween: None,
+			default_fade_out_tween: None,
 			groups: GroupSet::new(),
+			collect_statistics: false,
 		}
 	}
 }
@@ -0,0 +1,178 @@
+use crate::{
+	instance::{InstanceSettings, InstanceState},
+	manager::Backend,
+	sound::SoundSettings,
+	testing::{constant_sound, create_manager, ramp_sound},
+	Frame, Sample,
+};
+
+use super::{Arrangement, SoundClip};
+
+/// Processes the given number of frames and returns the last one.
+fn last_frame(backend: &mut Backend, num_frames: usize) -> Frame {
+	let mut frame = Frame::from_mono(0.0);
+	for _ in 0..num_frames {
+		frame = backend.process();
+	}
+	frame
+}
+
+fn assert_frames_close(a: Frame, b: Frame) {
+	assert!(
+		(a.left - b.left).abs() < 0.001 && (a.right - b.right).abs() < 0.001,
+		"{:?} is not close to {:?}",
+		a,
+		b
+	);
+}
+
+#[test]
+fn slices_only_play_their_region_of_the_sound() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(ramp_sound(SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let start = 0.25;
+	let end = 0.25 + 100.0 / 48000.0;
+	let mut arrangement_handle = manager
+		.add_arrangement(Arrangement::new_slice(
+			&sound_handle,
+			start,
+			end,
+			Default::default(),
+		))
+		.unwrap();
+	assert!((arrangement_handle.duration() - 100.0 / 48000.0).abs() < 1.0e-9);
+	let instance_handle = arrangement_handle
+		.play(InstanceSettings::new().fade_in_tween(None).panning(0.0))
+		.unwrap();
+	backend.process();
+	let first_frame = backend.process().left;
+	assert!((first_frame - start as Sample).abs() < 0.001);
+	for _ in 0..90 {
+		let frame = backend.process().left;
+		assert!(frame >= first_frame && frame < end as Sample);
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Playing);
+	for _ in 0..20 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+}
+
+#[test]
+fn clamps_slices_to_the_duration_of_the_sound() {
+	let (mut manager, _) = create_manager();
+	let sound_handle = manager.add_sound(ramp_sound(Default::default())).unwrap();
+	let whole_sound = Arrangement::new_slice(&sound_handle, -1.0, 2.0, Default::default());
+	assert_eq!(whole_sound.duration(), 1.0);
+	let backwards = Arrangement::new_slice(&sound_handle, 0.75, 0.5, Default::default());
+	assert_eq!(backwards.duration(), 0.0);
+}
+
+#[test]
+fn applies_clip_volume_and_panning() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut arrangement = Arrangement::new(Default::default());
+	arrangement.add_clip(SoundClip::new(&sound_handle, 0.0).volume(0.5).panning(0.25));
+	let mut arrangement_handle = manager.add_arrangement(arrangement).unwrap();
+	arrangement_handle
+		.play(InstanceSettings::new().fade_in_tween(None))
+		.unwrap();
+	// clips are panned with a balance control, so the side
+	// the clip is panned towards stays at full volume
+	assert_frames_close(
+		last_frame(&mut backend, 2),
+		Frame::new(0.5, 0.25).panned(0.5),
+	);
+}
+
+#[test]
+fn fades_clips_in_and_out() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut arrangement = Arrangement::new(Default::default());
+	arrangement
+		.add_clip(
+			SoundClip::new(&sound_handle, 0.0)
+				.fade_in(0.25)
+				.fade_out(0.25),
+		)
+		.add_clip(
+			SoundClip::new(&sound_handle, 0.75)
+				.volume(0.5)
+				.fade_in(0.25),
+		);
+	let mut arrangement_handle = manager.add_arrangement(arrangement).unwrap();
+	arrangement_handle
+		.play(InstanceSettings::new().fade_in_tween(None).panning(0.0))
+		.unwrap();
+	// halfway through the fade-in
+	assert!((last_frame(&mut backend, 6000).left - 0.5).abs() < 0.001);
+	// after the fade-in
+	assert!((last_frame(&mut backend, 18000).left - 1.0).abs() < 0.001);
+	// halfway through the first clip's fade-out, which
+	// overlaps the second clip's fade-in
+	assert!((last_frame(&mut backend, 18000).left - 0.75).abs() < 0.001);
+	// once the first clip has faded out, only the second
+	// clip is left
+	assert!((last_frame(&mut backend, 6000).left - 0.5).abs() < 0.001);
+}
+
+#[test]
+fn layer_volumes_only_affect_their_own_layer() {
+	let (mut manager, mut backend) = create_manager();
+	let loud_sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let quiet_sound_handle = manager
+		.add_sound(constant_sound(0.25, Default::default()))
+		.unwrap();
+	let mut arrangement = Arrangement::new_layered(
+		&[&loud_sound_handle, &quiet_sound_handle],
+		Default::default(),
+	);
+	assert_eq!(arrangement.num_layers(), 2);
+	arrangement.set_layer_volume(1, 2.0);
+	let mut arrangement_handle = manager.add_arrangement(arrangement).unwrap();
+	arrangement_handle
+		.play(InstanceSettings::new().fade_in_tween(None).panning(0.0))
+		.unwrap();
+	// 1 + 0.25 * 2
+	assert!((last_frame(&mut backend, 2).left - 1.5).abs() < 0.001);
+	// 1 * 0.5 + 0.25 * 2
+	arrangement_handle.set_layer_volume(0, 0.5).unwrap();
+	assert!((last_frame(&mut backend, 1).left - 1.0).abs() < 0.001);
+	// 1 * 0.5 + 0.25 * 0
+	arrangement_handle.set_layer_volume(1, 0.0).unwrap();
+	assert!((last_frame(&mut backend, 1).left - 0.5).abs() < 0.001);
+	// layers that don't exist are ignored
+	arrangement_handle.set_layer_volume(2, 0.0).unwrap();
+	assert!((last_frame(&mut backend, 1).left - 0.5).abs() < 0.001);
+	// the layer volumes apply to the looped part of the arrangement too
+	assert!((last_frame(&mut backend, 48000).left - 0.5).abs() < 0.001);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn reads_clips_saved_without_volume_panning_fades_or_layers() {
+	let clip: SoundClip = serde_json::from_str(
+		r#"{
+			"sound_id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+			"clip_time_range": [1.0, 2.0],
+			"sound_time_range": [0.0, 1.0]
+		}"#,
+	)
+	.unwrap();
+	assert_eq!(clip.clip_time_range, (1.0, 2.0));
+	assert_eq!(clip.volume, 1.0);
+	assert_eq!(clip.panning, 0.5);
+	assert_eq!(clip.fade_in, 0.0);
+	assert_eq!(clip.fade_out, 0.0);
+	assert_eq!(clip.layer, None);
+}
@@ -0,0 +1,294 @@
+//! An audio stream that plays overlapping grains of a sound.
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{parameter::Parameters, sound::Sound, CachedValue, Frame, Sample, Semitones, Value};
+
+use super::AudioStream;
+
+/// The most grains that can start each second.
+const MAX_DENSITY: f64 = 10_000.0;
+
+/// Settings for a [`GranularStream`].
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct GranularSettings {
+	/// How long each grain lasts (in seconds).
+	pub grain_size: Value<f64>,
+	/// How many grains start each second.
+	///
+	/// This is limited to 10,000 grains per second.
+	pub density: Value<f64>,
+	/// Where in the sound grains are taken from (in seconds).
+	pub position: Value<f64>,
+	/// How far (in seconds) before or after the position
+	/// each grain can start.
+	pub position_spread: Value<f64>,
+	/// The playback rate of the grains, as a factor of the
+	/// original playback rate.
+	pub playback_rate: Value<f64>,
+	/// How far (in semitones) the pitch of each grain can
+	/// be raised or lowered from the playback rate.
+	pub pitch_spread: Value<f64>,
+	/// The maximum number of grains that can play at once.
+	///
+	/// Grains that would start while this many grains are
+	/// playing are skipped.
+	pub max_grains: usize,
+	/// The seed to use when picking the positions and
+	/// pitches of grains.
+	///
+	/// If this is `None`, a random seed is used.
+	pub random_seed: Option<u64>,
+}
+
+impl GranularSettings {
+	/// Creates a new `GranularSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets how long each grain lasts (in seconds).
+	pub fn grain_size(self, grain_size: impl Into<Value<f64>>) -> Self {
+		Self {
+			grain_size: grain_size.into(),
+			..self
+		}
+	}
+
+	/// Sets how many grains start each second.
+	pub fn density(self, density: impl Into<Value<f64>>) -> Self {
+		Self {
+			density: density.into(),
+			..self
+		}
+	}
+
+	/// Sets where in the sound grains are taken from (in seconds).
+	pub fn position(self, position: impl Into<Value<f64>>) -> Self {
+		Self {
+			position: position.into(),
+			..self
+		}
+	}
+
+	/// Sets how far (in seconds) before or after the position
+	/// each grain can start.
+	pub fn position_spread(self, position_spread: impl Into<Value<f64>>) -> Self {
+		Self {
+			position_spread: position_spread.into(),
+			..self
+		}
+	}
+
+	/// Sets the playback rate of the grains.
+	pub fn playback_rate(self, playback_rate: impl Into<Value<f64>>) -> Self {
+		Self {
+			playback_rate: playback_rate.into(),
+			..self
+		}
+	}
+
+	/// Sets how far (in semitones) the pitch of each grain can
+	/// be raised or lowered.
+	pub fn pitch_spread(self, pitch_spread: impl Into<Value<f64>>) -> Self {
+		Self {
+			pitch_spread: pitch_spread.into(),
+			..self
+		}
+	}
+
+	/// Sets the maximum number of grains that can play at once.
+	pub fn max_grains(self, max_grains: usize) -> Self {
+		Self { max_grains, ..self }
+	}
+
+	/// Sets the seed to use when picking the positions and
+	/// pitches of grains.
+	pub fn random_seed(self, random_seed: u64) -> Self {
+		Self {
+			random_seed: Some(random_seed),
+			..self
+		}
+	}
+}
+
+impl Default for GranularSettings {
+	fn default() -> Self {
+		Self {
+			grain_size: Value::Fixed(0.05),
+			density: Value::Fixed(40.0),
+			position: Value::Fixed(0.0),
+			position_spread: Value::Fixed(0.0),
+			playback_rate: Value::Fixed(1.0),
+			pitch_spread: Value::Fixed(0.0),
+			max_grains: 32,
+			random_seed: None,
+		}
+	}
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Grain {
+	position: f64,
+	playback_rate: f64,
+	duration: f64,
+	time: f64,
+}
+
+impl Grain {
+	/// Returns the volume of the grain's envelope, which
+	/// fades in and out smoothly to avoid clicks.
+	fn envelope(&self) -> f64 {
+		let phase = self.time / self.duration;
+		0.5 - 0.5 * (phase * std::f64::consts::TAU).cos()
+	}
+}
+
+/// Plays many short, overlapping pieces ("grains") of a sound.
+///
+/// Granular synthesis can turn a short recording into an
+/// endless texture, like wind or rain, or scrub through a
+/// sound at any speed by moving the position the grains are
+/// taken from. Any of the settings can be linked to a
+/// parameter:
+///
+/// ```no_run
+/// # use kira::{
+/// # 	audio_stream::granular::{GranularSettings, GranularStream},
+/// # 	manager::AudioManager,
+/// # 	mixer::TrackIndex,
+/// # 	parameter::{Mapping, ParameterSettings},
+/// # 	sound::{Sound, SoundSettings},
+/// # 	Value,
+/// # };
+/// # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+/// let sound = Sound::from_file("wind.ogg", SoundSettings::default())?;
+/// let scrub = audio_manager.add_parameter(ParameterSettings::new())?;
+/// let stream = GranularStream::new(
+/// 	sound.clone(),
+/// 	GranularSettings::new()
+/// 		.position(Value::Parameter(
+/// 			scrub.id(),
+/// 			Mapping {
+/// 				output_range: (0.0, sound.duration()),
+/// 				..Default::default()
+/// 			},
+/// 		))
+/// 		.position_spread(0.1)
+/// 		.pitch_spread(2.0),
+/// );
+/// audio_manager.add_stream(stream, TrackIndex::Main)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct GranularStream {
+	sound: Sound,
+	grain_size: CachedValue<f64>,
+	density: CachedValue<f64>,
+	position: CachedValue<f64>,
+	position_spread: CachedValue<f64>,
+	playback_rate: CachedValue<f64>,
+	pitch_spread: CachedValue<f64>,
+	grains: Vec<Grain>,
+	max_grains: usize,
+	spawn_timer: f64,
+	rng: StdRng,
+}
+
+impl GranularStream {
+	/// Creates a new granular stream that takes grains
+	/// from the given sound.
+	pub fn new(sound: Sound, settings: GranularSettings) -> Self {
+		Self {
+			sound,
+			grain_size: CachedValue::new(settings.grain_size, 0.05).with_min(0.0),
+			density: CachedValue::new(settings.density, 40.0).with_valid_range(0.0..MAX_DENSITY),
+			position: CachedValue::new(settings.position, 0.0),
+			position_spread: CachedValue::new(settings.position_spread, 0.0).with_min(0.0),
+			playback_rate: CachedValue::new(settings.playback_rate, 1.0),
+			pitch_spread: CachedValue::new(settings.pitch_spread, 0.0).with_min(0.0),
+			grains: Vec::with_capacity(settings.max_grains),
+			max_grains: settings.max_grains,
+			spawn_timer: 0.0,
+			rng: match settings.random_seed {
+				Some(seed) => StdRng::seed_from_u64(seed),
+				None => StdRng::from_entropy(),
+			},
+		}
+	}
+
+	/// Returns the number of grains that are playing.
+	pub fn num_grains(&self) -> usize {
+		self.grains.len()
+	}
+
+	fn spawn_grain(&mut self) {
+		let duration = self.grain_size.value();
+		if self.grains.len() >= self.max_grains || duration <= 0.0 {
+			return;
+		}
+		let position_spread = self.position_spread.value();
+		let pitch_spread = self.pitch_spread.value();
+		let offset = if position_spread > 0.0 {
+			self.rng.gen_range(-position_spread..position_spread)
+		} else {
+			0.0
+		};
+		let semitones = if pitch_spread > 0.0 {
+			self.rng.gen_range(-pitch_spread..pitch_spread)
+		} else {
+			0.0
+		};
+		let position = (self.position.value() + offset)
+			.max(0.0)
+			.min(self.sound.duration());
+		self.grains.push(Grain {
+			position,
+			playback_rate: self.playback_rate.value() * Semitones(semitones).playback_rate(),
+			duration,
+			time: 0.0,
+		});
+	}
+}
+
+impl AudioStream for GranularStream {
+	fn update(&mut self, parameters: &Parameters) {
+		self.grain_size.update(parameters);
+		self.density.update(parameters);
+		self.position.update(parameters);
+		self.position_spread.update(parameters);
+		self.playback_rate.update(parameters);
+		self.pitch_spread.update(parameters);
+	}
+
+	fn next(&mut self, dt: f64) -> Frame {
+		let density = self.density.value();
+		if density > 0.0 {
+			self.spawn_timer -= dt;
+			while self.spawn_timer <= 0.0 {
+				// no more grains can start this frame, so wait
+				// for the next one to be due instead of skipping
+				// every grain that's overdue
+				if self.grains.len() >= self.max_grains {
+					self.spawn_timer = 1.0 / density;
+					break;
+				}
+				self.spawn_grain();
+				self.spawn_timer += 1.0 / density;
+			}
+		}
+		let mut out = Frame::from_mono(0.0);
+		for grain in &mut self.grains {
+			out += self.sound.get_frame_at_position(grain.position) * grain.envelope() as Sample;
+			grain.position += grain.playback_rate * dt;
+			grain.time += dt;
+		}
+		self.grains.retain(|grain| grain.time < grain.duration);
+		out
+	}
+}
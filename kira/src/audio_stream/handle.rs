@@ -0,0 +1,92 @@
+//! An interface for controlling audio streams.
+
+use crate::{
+	command::{
+		producer::{CommandError, CommandProducer},
+		StreamCommand,
+	},
+	mixer::TrackIndex,
+	parameter::tween::Tween,
+	Value,
+};
+
+use super::AudioStreamId;
+
+/// Allows you to control an audio stream.
+#[derive(Debug, Clone)]
+pub struct AudioStreamHandle {
+	id: AudioStreamId,
+	command_producer: CommandProducer,
+}
+
+impl AudioStreamHandle {
+	pub(crate) fn new(id: AudioStreamId, command_producer: CommandProducer) -> Self {
+		Self {
+			id,
+			command_producer,
+		}
+	}
+
+	/// Returns the ID of the audio stream.
+	pub fn id(&self) -> AudioStreamId {
+		self.id
+	}
+
+	/// Sets the volume of the audio stream.
+	pub fn set_volume(&mut self, volume: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(StreamCommand::SetStreamVolume(self.id, volume.into()).into())
+	}
+
+	/// Sets the panning of the audio stream, where 0 is hard left
+	/// and 1 is hard right.
+	///
+	/// Unlike instances, audio streams are not attenuated when
+	/// centered, so a panning of 0.5 leaves the audio unchanged.
+	pub fn set_panning(&mut self, panning: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(StreamCommand::SetStreamPanning(self.id, panning.into()).into())
+	}
+
+	/// Pauses the audio stream, optionally fading out first.
+	///
+	/// While paused, the stream will not be asked for
+	/// any new audio.
+	pub fn pause(&mut self, fade_tween: impl Into<Option<Tween>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(StreamCommand::PauseStream(self.id, fade_tween.into()).into())
+	}
+
+	/// Stops the audio stream, optionally fading out first.
+	///
+	/// Unlike paused streams, stopped streams cannot be resumed.
+	/// The stream still counts towards the
+	/// [`num_streams`](crate::manager::AudioManagerSettings::num_streams)
+	/// limit until it's removed with
+	/// [`AudioManager::remove_stream`](crate::manager::AudioManager::remove_stream).
+	pub fn stop(&mut self, fade_tween: impl Into<Option<Tween>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(StreamCommand::StopStream(self.id, fade_tween.into()).into())
+	}
+
+	/// Resumes the audio stream, optionally fading in.
+	pub fn resume(&mut self, fade_tween: impl Into<Option<Tween>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(StreamCommand::ResumeStream(self.id, fade_tween.into()).into())
+	}
+
+	/// Routes the audio stream to a different mixer track.
+	///
+	/// If the track doesn't exist, the stream's audio will
+	/// not be heard.
+	pub fn set_track(&mut self, track: impl Into<TrackIndex>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(StreamCommand::SetStreamTrack(self.id, track.into()).into())
+	}
+}
+
+impl From<&AudioStreamHandle> for AudioStreamId {
+	fn from(handle: &AudioStreamHandle) -> Self {
+		handle.id()
+	}
+}
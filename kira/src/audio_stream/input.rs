@@ -0,0 +1,216 @@
+//! Plays audio from a microphone or other input device.
+//!
+//! Input streams capture audio from the default input device
+//! and feed it into a mixer track, so you can use kira's effects
+//! on a player's voice or record it using a custom effect.
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	audio_stream::input::InputStreamSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::TrackIndex,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let mut microphone =
+//! 	audio_manager.add_input_stream(TrackIndex::Main, InputStreamSettings::default())?;
+//! microphone.stream().set_volume(0.5)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt::{Debug, Formatter};
+
+use cpal::{
+	traits::{DeviceTrait, HostTrait, StreamTrait},
+	Sample as CpalSample, SampleFormat, Stream, StreamConfig,
+};
+use ringbuf::{Producer, RingBuffer};
+
+use crate::{
+	audio_stream::{handle::AudioStreamHandle, AudioStreamId},
+	manager::error::AddInputStreamError,
+	Frame, Sample,
+};
+
+use super::ring_buffer::{ring_buffer_stream, RingBufferProducer, RingBufferStream};
+
+const WRAPPER_THREAD_SLEEP_DURATION: f64 = 1.0 / 60.0;
+
+/// Settings for an input stream.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct InputStreamSettings {
+	/// The maximum amount of captured audio (in seconds) that
+	/// can be waiting to be played.
+	///
+	/// If the input device delivers audio faster than it's
+	/// played, captured audio will be dropped once this
+	/// much audio is waiting.
+	pub buffer_duration: f64,
+}
+
+impl InputStreamSettings {
+	/// Creates a new `InputStreamSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the maximum amount of captured audio (in seconds) that
+	/// can be waiting to be played.
+	pub fn buffer_duration(self, buffer_duration: f64) -> Self {
+		Self { buffer_duration }
+	}
+}
+
+impl Default for InputStreamSettings {
+	fn default() -> Self {
+		Self {
+			buffer_duration: 0.1,
+		}
+	}
+}
+
+/// Allows you to control an audio stream that plays audio
+/// from an input device.
+///
+/// When the handle is dropped, the input device is closed.
+pub struct InputStreamHandle {
+	stream: AudioStreamHandle,
+	quit_signal_producer: Producer<bool>,
+}
+
+impl InputStreamHandle {
+	pub(crate) fn new(stream: AudioStreamHandle, quit_signal_producer: Producer<bool>) -> Self {
+		Self {
+			stream,
+			quit_signal_producer,
+		}
+	}
+
+	/// Returns the ID of the audio stream playing the input.
+	pub fn id(&self) -> AudioStreamId {
+		self.stream.id()
+	}
+
+	/// Returns a handle for controlling the volume, panning,
+	/// and playback of the audio stream.
+	pub fn stream(&mut self) -> &mut AudioStreamHandle {
+		&mut self.stream
+	}
+}
+
+impl Debug for InputStreamHandle {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("InputStreamHandle")
+			.field("stream", &self.stream)
+			.finish()
+	}
+}
+
+impl Drop for InputStreamHandle {
+	fn drop(&mut self) {
+		self.quit_signal_producer.push(true).ok();
+	}
+}
+
+impl From<&InputStreamHandle> for AudioStreamId {
+	fn from(handle: &InputStreamHandle) -> Self {
+		handle.id()
+	}
+}
+
+/// Opens the default input device and returns an audio stream
+/// that plays the captured audio, along with a producer that
+/// closes the device when it's sent a signal.
+pub(crate) fn start_input_stream(
+	settings: InputStreamSettings,
+) -> Result<(RingBufferStream, Producer<bool>), AddInputStreamError> {
+	let (quit_signal_producer, mut quit_signal_consumer) = RingBuffer::new(1).split();
+	let (mut setup_result_producer, mut setup_result_consumer) = RingBuffer::new(1).split();
+	// cpal streams can't be sent between threads on every platform,
+	// so the stream is created and kept alive on its own thread,
+	// just like the output stream
+	std::thread::spawn(move || match setup_input_stream(settings) {
+		Ok((_stream, audio_stream)) => {
+			if setup_result_producer.push(Ok(audio_stream)).is_err() {
+				panic!("Setup result producer is full");
+			}
+			while quit_signal_consumer.pop().is_none() {
+				std::thread::sleep(std::time::Duration::from_secs_f64(
+					WRAPPER_THREAD_SLEEP_DURATION,
+				));
+			}
+		}
+		Err(error) => {
+			if setup_result_producer.push(Err(error)).is_err() {
+				panic!("Setup result producer is full");
+			}
+		}
+	});
+	loop {
+		if let Some(result) = setup_result_consumer.pop() {
+			return result.map(|audio_stream| (audio_stream, quit_signal_producer));
+		}
+	}
+}
+
+fn setup_input_stream(
+	settings: InputStreamSettings,
+) -> Result<(Stream, RingBufferStream), AddInputStreamError> {
+	let host = cpal::default_host();
+	let device = host
+		.default_input_device()
+		.ok_or(AddInputStreamError::NoDefaultInputDevice)?;
+	let supported_config = device.default_input_config()?;
+	let sample_format = supported_config.sample_format();
+	let config = supported_config.config();
+	let capacity = ((settings.buffer_duration * config.sample_rate.0 as f64) as usize).max(1);
+	let (producer, audio_stream) = ring_buffer_stream(capacity, config.sample_rate.0);
+	let stream = match sample_format {
+		SampleFormat::F32 => build_input_stream::<f32>(&device, &config, producer)?,
+		SampleFormat::I16 => build_input_stream::<i16>(&device, &config, producer)?,
+		SampleFormat::U16 => build_input_stream::<u16>(&device, &config, producer)?,
+	};
+	stream.play()?;
+	Ok((stream, audio_stream))
+}
+
+fn build_input_stream<T: CpalSample>(
+	device: &cpal::Device,
+	config: &StreamConfig,
+	mut producer: RingBufferProducer,
+) -> Result<Stream, AddInputStreamError> {
+	let channels = config.channels as usize;
+	Ok(device.build_input_stream(
+		config,
+		move |data: &[T], _: &cpal::InputCallbackInfo| {
+			push_captured_samples(data, channels, &mut producer);
+		},
+		move |_| {},
+	)?)
+}
+
+/// Turns interleaved samples captured from an input device
+/// into frames and pushes them into the stream's buffer.
+///
+/// Mono input is played on both channels, and any channels
+/// after the first two are ignored.
+pub(super) fn push_captured_samples<T: CpalSample>(
+	data: &[T],
+	channels: usize,
+	producer: &mut RingBufferProducer,
+) {
+	for frame in data.chunks_exact(channels) {
+		let frame = if channels == 1 {
+			Frame::from_mono(frame[0].to_f32() as Sample)
+		} else {
+			Frame::new(frame[0].to_f32() as Sample, frame[1].to_f32() as Sample)
+		};
+		// if the buffer is full, the captured audio is dropped
+		// rather than blocking the input device
+		producer.push(frame).ok();
+	}
+}
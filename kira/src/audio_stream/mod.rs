@@ -0,0 +1,98 @@
+//! A stream of arbitrary audio.
+//!
+//! Audio streams are useful if you need to dynamically generate
+//! audio. For instance, you can use an audio stream to synthesize
+//! sound effects in real time or feed audio from a voice chat
+//! into the mixer.
+//!
+//! If you just need to play an audio file, you should probably use
+//! [instances](crate::instance).
+//!
+//! If the audio is produced on another thread, like a voice chat
+//! client or a video decoder, you can use a
+//! [`RingBufferStream`](ring_buffer::RingBufferStream) to push
+//! frames into the mixer without writing any real-time code
+//! yourself:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	audio_stream::ring_buffer::ring_buffer_stream,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::TrackIndex,
+//! # 	Frame,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let (mut producer, stream) = ring_buffer_stream(48000, 48000);
+//! audio_manager.add_stream(stream, TrackIndex::Main)?;
+//! std::thread::spawn(move || loop {
+//! 	// push decoded audio as it arrives
+//! 	producer.push(Frame::from_mono(0.0)).ok();
+//! });
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod granular;
+pub mod handle;
+#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+pub mod input;
+pub mod ring_buffer;
+#[cfg(test)]
+mod tests;
+
+use std::fmt::Debug;
+
+use uuid::Uuid;
+
+use crate::{parameter::Parameters, Frame};
+
+/// Produces a constant flow of audio data in real time.
+pub trait AudioStream: Debug + Send + 'static {
+	/// Updates any values the stream reads its settings from.
+	///
+	/// This is called before each call to [`next`](AudioStream::next),
+	/// so [`CachedValue`](crate::CachedValue)s linked to parameters
+	/// can respond to parameter changes.
+	#[allow(unused_variables)]
+	fn update(&mut self, parameters: &Parameters) {}
+
+	/// Produces the next sample.
+	///
+	/// The audio thread has to wait for this function to finish,
+	/// so it should process quickly and in a consistent amount
+	/// of time to avoid audio glitches, such as stuttering.
+	///
+	/// `dt` represents how many seconds have elapsed since the last request.
+	fn next(&mut self, dt: f64) -> Frame;
+
+	/// Returns a description of a problem the stream ran into,
+	/// like audio data that couldn't be decoded.
+	///
+	/// This is called after each call to [`next`](AudioStream::next),
+	/// and the error is sent to the main thread, where it can be
+	/// received with
+	/// [`AudioManager::pop_audio_thread_error`](crate::manager::AudioManager::pop_audio_thread_error).
+	/// Each error should only be returned once. Since this runs on the
+	/// audio thread, the error message shouldn't be allocated here.
+	fn pop_error(&mut self) -> Option<String> {
+		None
+	}
+}
+
+/// A unique identifier for an [`AudioStream`](crate::audio_stream::AudioStream).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(transparent)
+)]
+pub struct AudioStreamId {
+	uuid: Uuid,
+}
+
+impl AudioStreamId {
+	pub(crate) fn new() -> Self {
+		Self {
+			uuid: Uuid::new_v4(),
+		}
+	}
+}
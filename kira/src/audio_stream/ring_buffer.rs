@@ -0,0 +1,107 @@
+//! An audio stream that plays audio pushed from another thread.
+
+use std::fmt::{Debug, Formatter};
+
+use ringbuf::{Consumer, Producer, RingBuffer};
+
+use crate::{dsp::Resampler, Frame};
+
+use super::AudioStream;
+
+/// Creates a [`RingBufferStream`] and a [`RingBufferProducer`]
+/// that can push audio into it.
+///
+/// `capacity` is the maximum number of frames that can be
+/// waiting to be played at once, and `sample_rate` is the
+/// sample rate of the audio that will be pushed.
+pub fn ring_buffer_stream(
+	capacity: usize,
+	sample_rate: u32,
+) -> (RingBufferProducer, RingBufferStream) {
+	let (producer, consumer) = RingBuffer::new(capacity).split();
+	(
+		RingBufferProducer { producer },
+		RingBufferStream {
+			consumer,
+			resampler: Resampler::new(sample_rate),
+		},
+	)
+}
+
+/// Pushes audio into a [`RingBufferStream`].
+///
+/// The producer can be moved to any thread, so a voice chat
+/// client or video decoder can send audio to the stream
+/// as soon as it's available.
+pub struct RingBufferProducer {
+	producer: Producer<Frame>,
+}
+
+impl RingBufferProducer {
+	/// Pushes a frame of audio into the stream.
+	///
+	/// Returns the frame back if the stream's buffer is full.
+	pub fn push(&mut self, frame: Frame) -> Result<(), Frame> {
+		self.producer.push(frame)
+	}
+
+	/// Pushes as many frames from a slice as will fit in the
+	/// stream's buffer and returns how many frames were pushed.
+	pub fn push_slice(&mut self, frames: &[Frame]) -> usize {
+		self.producer.push_slice(frames)
+	}
+
+	/// Returns the number of frames that can be pushed
+	/// before the stream's buffer is full.
+	pub fn remaining_capacity(&self) -> usize {
+		self.producer.remaining()
+	}
+
+	/// Returns the number of frames waiting to be played.
+	pub fn len(&self) -> usize {
+		self.producer.len()
+	}
+
+	/// Returns `true` if there are no frames waiting to be played.
+	pub fn is_empty(&self) -> bool {
+		self.producer.is_empty()
+	}
+}
+
+impl Debug for RingBufferProducer {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("RingBufferProducer")
+			.field("len", &self.producer.len())
+			.field("remaining_capacity", &self.producer.remaining())
+			.finish()
+	}
+}
+
+/// An audio stream that plays frames pushed by a
+/// [`RingBufferProducer`].
+///
+/// If the audio is pushed at a different sample rate than the
+/// audio output uses, the stream will resample it. If the
+/// producer can't keep up, the stream will output silence
+/// until more audio is pushed.
+pub struct RingBufferStream {
+	consumer: Consumer<Frame>,
+	resampler: Resampler,
+}
+
+impl Debug for RingBufferStream {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("RingBufferStream")
+			.field("len", &self.consumer.len())
+			.field("sample_rate", &self.resampler.sample_rate())
+			.finish()
+	}
+}
+
+impl AudioStream for RingBufferStream {
+	fn next(&mut self, dt: f64) -> Frame {
+		let consumer = &mut self.consumer;
+		self.resampler
+			.next(dt, || consumer.pop().unwrap_or(Frame::from_mono(0.0)))
+	}
+}
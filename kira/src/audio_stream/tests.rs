@@ -0,0 +1,154 @@
+use crate::{
+	mixer::TrackIndex,
+	parameter::ParameterSettings,
+	sound::SoundSettings,
+	testing::{constant_sound, create_manager, ramp_sound, ConstantStream},
+	Frame,
+};
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+use super::input::push_captured_samples;
+use super::{
+	granular::{GranularSettings, GranularStream},
+	ring_buffer::ring_buffer_stream,
+	AudioStream,
+};
+
+#[test]
+fn controls_audio_streams() {
+	let (mut manager, mut backend) = create_manager();
+	let mut stream_handle = manager
+		.add_stream(ConstantStream, TrackIndex::Main)
+		.unwrap();
+	backend.process();
+	stream_handle.set_volume(0.5).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(0.5));
+	stream_handle.set_panning(1.0).unwrap();
+	assert_eq!(backend.process(), Frame::new(0.0, 0.5));
+	stream_handle.pause(None).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+	stream_handle.resume(None).unwrap();
+	assert_eq!(backend.process(), Frame::new(0.0, 0.5));
+}
+
+#[test]
+fn plays_audio_pushed_into_ring_buffer_streams() {
+	let (mut manager, mut backend) = create_manager();
+	let (mut producer, stream) = ring_buffer_stream(10, 48000);
+	manager.add_stream(stream, TrackIndex::Main).unwrap();
+	assert_eq!(producer.push_slice(&[Frame::from_mono(0.5); 3]), 3);
+	backend.process();
+	for _ in 0..3 {
+		assert_eq!(backend.process(), Frame::from_mono(0.5));
+	}
+	assert!(producer.is_empty());
+	assert_eq!(
+		backend.process(),
+		Frame::from_mono(0.0),
+		"Ring buffer streams should output silence when they run out of audio"
+	);
+}
+
+#[test]
+fn plays_grains_of_sounds_from_granular_streams() {
+	let (mut manager, mut backend) = create_manager();
+	// each frame of the sound is its position in seconds
+	let sound = ramp_sound(SoundSettings::default());
+	let mut position = manager
+		.add_parameter(ParameterSettings::new().value(0.75))
+		.unwrap();
+	let stream = GranularStream::new(
+		sound,
+		// overlapping grains by half their length keeps the
+		// volume of the sound constant
+		GranularSettings::new()
+			.grain_size(0.01)
+			.density(200.0)
+			.position(&position)
+			.random_seed(0),
+	);
+	manager.add_stream(stream, TrackIndex::Main).unwrap();
+	backend.process();
+	for _ in 0..1000 {
+		backend.process();
+	}
+	assert!((backend.process().left - 0.755).abs() < 0.01);
+
+	// moving the position parameter scrubs through the sound
+	position.set(0.25, None).unwrap();
+	backend.process();
+	for _ in 0..1000 {
+		backend.process();
+	}
+	assert!((backend.process().left - 0.255).abs() < 0.01);
+}
+
+#[test]
+fn limits_the_grains_started_by_granular_streams() {
+	const DT: f64 = 1.0 / 48000.0;
+	let mut stream = GranularStream::new(
+		constant_sound(1.0, SoundSettings::default()),
+		GranularSettings::new()
+			.grain_size(1.0)
+			.density(f64::INFINITY)
+			.max_grains(4)
+			.random_seed(0),
+	);
+	// an infinite density is limited to 10,000 grains per second
+	stream.next(DT);
+	assert_eq!(stream.num_grains(), 1);
+	for _ in 0..100 {
+		stream.next(DT);
+	}
+	assert_eq!(stream.num_grains(), 4);
+	// long frames stop starting grains once the limit is reached
+	stream.next(0.5);
+	assert_eq!(stream.num_grains(), 4);
+	// a density that isn't a number starts no grains
+	let mut stream = GranularStream::new(
+		constant_sound(1.0, SoundSettings::default()),
+		GranularSettings::new().density(f64::NAN).random_seed(0),
+	);
+	stream.next(DT);
+	assert_eq!(stream.num_grains(), 0);
+}
+
+#[test]
+#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+fn converts_captured_input_to_frames() {
+	const DT: f64 = 1.0 / 48000.0;
+	let (mut producer, mut stream) = ring_buffer_stream(10, 48000);
+	// mono input is played on both channels
+	push_captured_samples(&[0.5f32, -0.25], 1, &mut producer);
+	// extra channels are ignored
+	push_captured_samples(&[0.25f32, 0.75, 1.0, -0.5, 0.125, 1.0], 3, &mut producer);
+	// integer samples are converted to the -1 to 1 range
+	push_captured_samples(&[i16::MIN, 0], 2, &mut producer);
+	// the stream plays the pushed audio one frame late
+	assert_eq!(stream.next(DT), Frame::from_mono(0.0));
+	assert_eq!(stream.next(DT), Frame::from_mono(0.5));
+	assert_eq!(stream.next(DT), Frame::from_mono(-0.25));
+	assert_eq!(stream.next(DT), Frame::new(0.25, 0.75));
+	assert_eq!(stream.next(DT), Frame::new(-0.5, 0.125));
+	assert_eq!(stream.next(DT), Frame::new(-1.0, 0.0));
+}
+
+#[test]
+#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+fn drops_input_when_full_and_plays_silence_when_empty() {
+	const DT: f64 = 1.0 / 48000.0;
+	let (mut producer, mut stream) = ring_buffer_stream(2, 48000);
+	// audio captured while the buffer is full is dropped
+	push_captured_samples(&[0.25f32, 0.5, 0.75], 1, &mut producer);
+	assert_eq!(producer.len(), 2);
+	stream.next(DT);
+	assert_eq!(stream.next(DT), Frame::from_mono(0.25));
+	assert_eq!(stream.next(DT), Frame::from_mono(0.5));
+	// when the input device falls behind, the stream
+	// plays silence until more audio is captured
+	assert_eq!(stream.next(DT), Frame::from_mono(0.0));
+	assert_eq!(stream.next(DT), Frame::from_mono(0.0));
+	push_captured_samples(&[1.0f32], 1, &mut producer);
+	stream.next(DT);
+	assert_eq!(stream.next(DT), Frame::from_mono(1.0));
+}
@@ -0,0 +1,182 @@
+//! Integrates Kira with the [Bevy](https://bevyengine.org) game engine.
+//!
+//! With the `bevy` feature enabled, adding the [`KiraPlugin`] to an
+//! app creates an [`AudioManager`], stores it in the
+//! [`KiraAudioManager`] resource, and lets the asset server load
+//! audio files as [`KiraSound`] assets:
+//!
+//! ```no_run
+//! # use bevy_app::{App, Startup, Update};
+//! # use bevy_asset::{AssetPlugin, AssetServer, Assets, Handle};
+//! # use bevy_ecs::prelude::*;
+//! # use kira::{
+//! # 	bevy::{KiraAudioManager, KiraPlugin, KiraSound},
+//! # 	instance::InstanceSettings,
+//! # 	sound::handle::SoundHandle,
+//! # };
+//! #[derive(Resource)]
+//! struct Music {
+//! 	asset: Handle<KiraSound>,
+//! 	sound: Option<SoundHandle>,
+//! }
+//!
+//! fn load_music(mut commands: Commands, asset_server: Res<AssetServer>) {
+//! 	commands.insert_resource(Music {
+//! 		asset: asset_server.load("music.ogg"),
+//! 		sound: None,
+//! 	});
+//! }
+//!
+//! fn play_music(
+//! 	mut music: ResMut<Music>,
+//! 	sounds: Res<Assets<KiraSound>>,
+//! 	mut audio: ResMut<KiraAudioManager>,
+//! ) {
+//! 	if music.sound.is_some() {
+//! 		return;
+//! 	}
+//! 	if let Some(sound) = sounds.get(&music.asset) {
+//! 		let mut sound_handle = audio.manager().add_sound(sound.sound().clone()).unwrap();
+//! 		sound_handle.play(InstanceSettings::new().loop_start(0.0)).unwrap();
+//! 		music.sound = Some(sound_handle);
+//! 	}
+//! }
+//!
+//! # let asset_plugin = AssetPlugin::default();
+//! App::new()
+//! 	// ...along with Bevy's other plugins
+//! 	.add_plugins((asset_plugin, KiraPlugin::default()))
+//! 	.add_systems(Startup, load_music)
+//! 	.add_systems(Update, play_music)
+//! 	.run();
+//! ```
+//!
+//! Each [`KiraSound`] holds a decoded [`Sound`], which is added to the
+//! audio manager like any other sound. The sound keeps its ID when it's
+//! cloned, so each asset can only be added to the audio manager once.
+//! The [`SoundSettings`] used to decode a sound can be set with
+//! [`AssetServer::load_with_settings`](bevy_asset::AssetServer::load_with_settings).
+
+#[cfg(all(test, feature = "wav"))]
+mod tests;
+
+use std::{ffi::OsStr, io::Cursor, sync::Mutex};
+
+use bevy_app::{App, Plugin};
+use bevy_asset::{io::Reader, Asset, AssetApp, AssetLoader, LoadContext};
+use bevy_ecs::resource::Resource;
+use bevy_reflect::TypePath;
+
+use crate::{
+	manager::{AudioManager, AudioManagerSettings},
+	sound::{error::SoundFromFileError, Sound, SoundSettings, SUPPORTED_EXTENSIONS},
+};
+
+/// Adds an [`AudioManager`] and support for loading
+/// [`KiraSound`]s to a Bevy app.
+///
+/// Bevy's `AssetPlugin` has to be added before this plugin.
+///
+/// If the audio manager can't be started, a warning is logged
+/// and the manager is left [deferred](AudioManager::new_deferred),
+/// so it can be started later using [`KiraAudioManager::manager`].
+#[derive(Debug, Default)]
+pub struct KiraPlugin {
+	/// The settings to create the audio manager with.
+	pub settings: AudioManagerSettings,
+}
+
+impl Plugin for KiraPlugin {
+	fn build(&self, app: &mut App) {
+		#[allow(unused_mut)]
+		let mut audio_manager = AudioManager::new_deferred(self.settings.clone());
+		#[cfg(feature = "cpal")]
+		if let Err(error) = audio_manager.start() {
+			tracing::warn!("Could not start the Kira audio manager: {}", error);
+		}
+		app.insert_resource(KiraAudioManager::new(audio_manager))
+			.init_asset::<KiraSound>()
+			.register_asset_loader(KiraSoundLoader);
+	}
+}
+
+/// A resource that holds the app's [`AudioManager`].
+#[derive(Resource)]
+pub struct KiraAudioManager {
+	// the audio manager can be sent between threads, but not
+	// shared between them, which resources have to allow.
+	// since it's only handed out mutably, the mutex is never
+	// actually locked.
+	audio_manager: Mutex<AudioManager>,
+}
+
+impl KiraAudioManager {
+	/// Creates a new resource that holds an audio manager.
+	pub fn new(audio_manager: AudioManager) -> Self {
+		Self {
+			audio_manager: Mutex::new(audio_manager),
+		}
+	}
+
+	/// Returns the audio manager.
+	pub fn manager(&mut self) -> &mut AudioManager {
+		self.audio_manager
+			.get_mut()
+			.unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+impl std::fmt::Debug for KiraAudioManager {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("KiraAudioManager").finish()
+	}
+}
+
+/// A sound loaded by Bevy's asset server.
+#[derive(Debug, Clone, Asset, TypePath)]
+pub struct KiraSound {
+	sound: Sound,
+}
+
+impl KiraSound {
+	/// Returns the decoded sound.
+	pub fn sound(&self) -> &Sound {
+		&self.sound
+	}
+}
+
+/// Decodes audio files into [`KiraSound`]s.
+///
+/// The files' extensions are used to tell which audio format
+/// they're in.
+#[derive(Debug, Default, TypePath)]
+pub struct KiraSoundLoader;
+
+impl AssetLoader for KiraSoundLoader {
+	type Asset = KiraSound;
+	type Settings = SoundSettings;
+	type Error = SoundFromFileError;
+
+	async fn load(
+		&self,
+		reader: &mut dyn Reader,
+		settings: &SoundSettings,
+		load_context: &mut LoadContext<'_>,
+	) -> Result<KiraSound, SoundFromFileError> {
+		let extension = load_context
+			.path()
+			.path()
+			.extension()
+			.and_then(OsStr::to_str)
+			.ok_or(SoundFromFileError::UnsupportedAudioFileFormat)?
+			.to_string();
+		let mut bytes = vec![];
+		reader.read_to_end(&mut bytes).await?;
+		let sound = Sound::from_reader(Cursor::new(bytes), &extension, settings.clone())?;
+		Ok(KiraSound { sound })
+	}
+
+	fn extensions(&self) -> &[&str] {
+		SUPPORTED_EXTENSIONS
+	}
+}
@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use bevy_app::{App, TaskPoolPlugin};
+use bevy_asset::{AssetApp, AssetPlugin, AssetServer, Assets, Handle, LoadState};
+
+use crate::{instance::InstanceSettings, sound::SoundSettings, testing::create_manager, Frame};
+
+use super::{KiraAudioManager, KiraSound, KiraSoundLoader};
+
+/// Creates a directory with a short mono wav file in it
+/// for the asset server to load.
+fn create_asset_directory(name: &str) -> PathBuf {
+	let directory = std::env::temp_dir().join(format!("kira-bevy-{}-{}", name, std::process::id()));
+	std::fs::create_dir_all(&directory).unwrap();
+	let mut writer = hound::WavWriter::create(
+		directory.join("beep.wav"),
+		hound::WavSpec {
+			channels: 1,
+			sample_rate: 48000,
+			bits_per_sample: 32,
+			sample_format: hound::SampleFormat::Float,
+		},
+	)
+	.unwrap();
+	for _ in 0..4800 {
+		writer.write_sample(0.5f32).unwrap();
+	}
+	writer.finalize().unwrap();
+	directory
+}
+
+fn create_app(directory: &Path) -> App {
+	let mut app = App::new();
+	app.add_plugins((
+		TaskPoolPlugin::default(),
+		AssetPlugin {
+			file_path: directory.to_str().unwrap().to_string(),
+			..Default::default()
+		},
+	))
+	.init_asset::<KiraSound>()
+	.register_asset_loader(KiraSoundLoader);
+	app
+}
+
+/// Updates the app until the asset has finished loading.
+fn wait_for_asset(app: &mut App, handle: &Handle<KiraSound>) {
+	for _ in 0..1000 {
+		app.update();
+		match app.world().resource::<AssetServer>().load_state(handle) {
+			LoadState::Loaded => return,
+			LoadState::Failed(error) => panic!("{}", error),
+			_ => std::thread::sleep(std::time::Duration::from_millis(1)),
+		}
+	}
+	panic!("the asset took too long to load");
+}
+
+#[test]
+fn loads_sounds_as_assets() {
+	let directory = create_asset_directory("load");
+	let mut app = create_app(&directory);
+	let (audio_manager, mut backend) = create_manager();
+	app.insert_resource(KiraAudioManager::new(audio_manager));
+	let handle: Handle<KiraSound> = app.world().resource::<AssetServer>().load_with_settings(
+		"beep.wav",
+		|settings: &mut SoundSettings| {
+			settings.semantic_duration = Some(0.05);
+		},
+	);
+	wait_for_asset(&mut app, &handle);
+	let sound = app
+		.world()
+		.resource::<Assets<KiraSound>>()
+		.get(&handle)
+		.unwrap()
+		.sound()
+		.clone();
+	assert_eq!(sound.duration(), 0.1);
+	assert_eq!(sound.semantic_duration(), Some(0.05));
+	// the sound can be played using the audio manager resource
+	let mut audio_manager = app.world_mut().resource_mut::<KiraAudioManager>();
+	let mut sound_handle = audio_manager.manager().add_sound(sound).unwrap();
+	sound_handle
+		.play(InstanceSettings::new().fade_in_tween(None).panning(0.0))
+		.unwrap();
+	backend.process();
+	assert_eq!(backend.process(), Frame::new(0.5, 0.0));
+	std::fs::remove_dir_all(directory).ok();
+}
@@ -1,5 +1,7 @@
 pub mod producer;
 
+use std::any::Any;
+
 use basedrop::Owned;
 
 use crate::{
@@ -8,36 +10,47 @@ use crate::{
 	group::{Group, GroupId},
 	instance::{
 		Instance, InstanceId, PauseInstanceSettings, ResumeInstanceSettings, StopInstanceSettings,
+		SwapPlayableSettings,
 	},
+	manager::snapshot::SnapshotRequest,
 	metronome::{Metronome, MetronomeId},
 	mixer::{
 		effect::{Effect, EffectId, EffectSettings},
 		SendTrackId, SubTrackId, Track, TrackIndex,
 	},
-	parameter::{tween::Tween, ParameterId},
+	parameter::{
+		tween::Tween, AutomationPlayer, DerivedParameterSettings, Modulation, ModulationId,
+		ParameterId,
+	},
 	playable::PlayableId,
-	sequence::{SequenceInstance, SequenceInstanceId},
+	sequence::{SequenceInstance, SequenceInstanceId, SequencePosition},
 	sound::{Sound, SoundId},
 	tempo::Tempo,
 	value::Value,
+	voice::VoiceId,
+	StartTime,
 };
 
 #[derive(Clone)]
 pub(crate) enum ResourceCommand {
 	AddSound(Owned<Sound>),
 	RemoveSound(SoundId),
+	ReplaceSound(Owned<Sound>),
 	AddArrangement(Owned<Arrangement>),
 	RemoveArrangement(ArrangementId),
+	RemoveWhenUnused(PlayableId),
+	SetArrangementLayerVolume(ArrangementId, usize, Value<f64>),
 }
 
-#[derive(Debug, Clone)]
 pub(crate) enum InstanceCommand {
 	Play(InstanceId, Instance),
 	SetInstanceVolume(InstanceId, Value<f64>),
 	SetInstancePlaybackRate(InstanceId, Value<f64>),
 	SetInstancePanning(InstanceId, Value<f64>),
+	SetInstanceWidth(InstanceId, Value<f64>),
 	SeekInstance(InstanceId, f64),
 	SeekInstanceTo(InstanceId, f64),
+	SwapInstancePlayable(InstanceId, PlayableId, SwapPlayableSettings),
 	PauseInstance(InstanceId, PauseInstanceSettings),
 	ResumeInstance(InstanceId, ResumeInstanceSettings),
 	StopInstance(InstanceId, StopInstanceSettings),
@@ -50,15 +63,72 @@ pub(crate) enum InstanceCommand {
 	PauseGroup(GroupId, PauseInstanceSettings),
 	ResumeGroup(GroupId, ResumeInstanceSettings),
 	StopGroup(GroupId, StopInstanceSettings),
+	AddEffect(InstanceId, EffectId, Owned<Box<dyn Effect>>, EffectSettings),
+	SetEffectEnabled(InstanceId, EffectId, bool),
+	SetEffectMix(InstanceId, EffectId, Value<f64>),
+	SetEffectValue(InstanceId, EffectId, usize, Value<f64>),
+	SendEffectMessage(InstanceId, EffectId, Owned<Box<dyn Any + Send>>),
+	RemoveEffect(InstanceId, EffectId),
+}
+
+impl InstanceCommand {
+	/// Returns the ID of the existing instance the command
+	/// acts on, if it acts on a single instance.
+	pub fn target_instance(&self) -> Option<InstanceId> {
+		match self {
+			InstanceCommand::SetInstanceVolume(id, ..)
+			| InstanceCommand::SetInstancePlaybackRate(id, ..)
+			| InstanceCommand::SetInstancePanning(id, ..)
+			| InstanceCommand::SetInstanceWidth(id, ..)
+			| InstanceCommand::SeekInstance(id, ..)
+			| InstanceCommand::SeekInstanceTo(id, ..)
+			| InstanceCommand::SwapInstancePlayable(id, ..)
+			| InstanceCommand::PauseInstance(id, ..)
+			| InstanceCommand::ResumeInstance(id, ..)
+			| InstanceCommand::StopInstance(id, ..)
+			| InstanceCommand::AddEffect(id, ..)
+			| InstanceCommand::SetEffectEnabled(id, ..)
+			| InstanceCommand::SetEffectMix(id, ..)
+			| InstanceCommand::SetEffectValue(id, ..)
+			| InstanceCommand::SendEffectMessage(id, ..)
+			| InstanceCommand::RemoveEffect(id, ..) => Some(*id),
+			_ => None,
+		}
+	}
+
+	/// Returns the ID of the sound or arrangement the
+	/// command needs, if any.
+	pub fn target_playable(&self) -> Option<PlayableId> {
+		match self {
+			InstanceCommand::Play(_, instance) => Some(instance.playable_id()),
+			InstanceCommand::SwapInstancePlayable(_, id, ..)
+			| InstanceCommand::PauseInstancesOf(id, ..)
+			| InstanceCommand::ResumeInstancesOf(id, ..)
+			| InstanceCommand::StopInstancesOf(id, ..) => Some(*id),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum VoiceCommand {
+	PauseVoice(VoiceId, PauseInstanceSettings),
+	ResumeVoice(VoiceId, ResumeInstanceSettings),
+	StopVoice(VoiceId, StopInstanceSettings),
+	PauseVoicesOnTrack(TrackIndex, PauseInstanceSettings),
+	ResumeVoicesOnTrack(TrackIndex, ResumeInstanceSettings),
+	StopVoicesOnTrack(TrackIndex, StopInstanceSettings),
 }
 
 pub(crate) enum MetronomeCommand {
 	AddMetronome(MetronomeId, Owned<Metronome>),
 	RemoveMetronome(MetronomeId),
 	SetMetronomeTempo(MetronomeId, Value<Tempo>),
+	SetMetronomeTime(MetronomeId, f64),
 	StartMetronome(MetronomeId),
 	PauseMetronome(MetronomeId),
 	StopMetronome(MetronomeId),
+	SetMetronomeSyncInstance(MetronomeId, Option<InstanceId>),
 }
 
 pub(crate) enum SequenceCommand {
@@ -68,6 +138,9 @@ pub(crate) enum SequenceCommand {
 	PauseSequenceInstance(SequenceInstanceId),
 	ResumeSequenceInstance(SequenceInstanceId),
 	StopSequenceInstance(SequenceInstanceId),
+	SignalSequenceInstance(SequenceInstanceId, usize),
+	SetSequenceInstanceSpeed(SequenceInstanceId, Value<f64>),
+	SetSequenceInstancePosition(SequenceInstanceId, SequencePosition, usize),
 	PauseGroup(GroupId),
 	ResumeGroup(GroupId),
 	StopGroup(GroupId),
@@ -76,30 +149,50 @@ pub(crate) enum SequenceCommand {
 pub(crate) enum MixerCommand {
 	AddTrack(Owned<Track>),
 	SetTrackVolume(TrackIndex, Value<f64>),
+	SetTrackWidth(TrackIndex, Value<f64>),
+	SetTrackMuted(TrackIndex, bool),
+	SetTrackSoloed(TrackIndex, bool),
 	RemoveSubTrack(SubTrackId),
 	RemoveSendTrack(SendTrackId),
 	AddEffect(TrackIndex, EffectId, Owned<Box<dyn Effect>>, EffectSettings),
 	SetEffectEnabled(TrackIndex, EffectId, bool),
 	SetEffectMix(TrackIndex, EffectId, Value<f64>),
+	SetEffectValue(TrackIndex, EffectId, usize, Value<f64>),
+	SendEffectMessage(TrackIndex, EffectId, Owned<Box<dyn Any + Send>>),
+	MoveEffect(TrackIndex, EffectId, usize),
 	RemoveEffect(TrackIndex, EffectId),
+	SetLevelAnalysisEnabled(bool),
 }
 
-#[derive(Debug, Copy, Clone)]
 pub(crate) enum ParameterCommand {
 	AddParameter(ParameterId, f64),
 	RemoveParameter(ParameterId),
-	SetParameter(ParameterId, f64, Option<Tween>),
+	SetParameter(ParameterId, f64, Option<Tween>, StartTime),
+	AddModulation(ModulationId, Modulation),
+	RemoveModulation(ModulationId),
+	SetModulationDepth(ModulationId, f64, Option<Tween>),
+	StartAutomation(ParameterId, Owned<AutomationPlayer>),
+	StopAutomation(ParameterId),
+	AddDerivedParameter(ParameterId, Owned<DerivedParameterSettings>),
 }
 
 #[derive(Clone)]
 pub(crate) enum GroupCommand {
 	AddGroup(GroupId, Owned<Group>),
 	RemoveGroup(GroupId),
+	SetGroupVolume(GroupId, Value<f64>),
+	SetGroupMuffleAmount(GroupId, Value<f64>),
 }
 
 pub(crate) enum StreamCommand {
 	AddStream(AudioStreamId, TrackIndex, Owned<Box<dyn AudioStream>>),
 	RemoveStream(AudioStreamId),
+	SetStreamVolume(AudioStreamId, Value<f64>),
+	SetStreamPanning(AudioStreamId, Value<f64>),
+	PauseStream(AudioStreamId, Option<Tween>),
+	ResumeStream(AudioStreamId, Option<Tween>),
+	StopStream(AudioStreamId, Option<Tween>),
+	SetStreamTrack(AudioStreamId, TrackIndex),
 }
 
 pub(crate) enum Command {
@@ -111,6 +204,202 @@ pub(crate) enum Command {
 	Parameter(ParameterCommand),
 	Group(GroupCommand),
 	Stream(StreamCommand),
+	Voice(VoiceCommand),
+	CaptureSnapshot(Owned<SnapshotRequest>),
+}
+
+impl Command {
+	/// Returns when the command should be run.
+	pub fn start_time(&self) -> StartTime {
+		match self {
+			Command::Instance(command) => match command {
+				InstanceCommand::PauseInstance(_, settings)
+				| InstanceCommand::PauseInstancesOf(_, settings)
+				| InstanceCommand::PauseInstancesOfSequence(_, settings)
+				| InstanceCommand::PauseGroup(_, settings) => settings.start_time,
+				InstanceCommand::ResumeInstance(_, settings)
+				| InstanceCommand::ResumeInstancesOf(_, settings)
+				| InstanceCommand::ResumeInstancesOfSequence(_, settings)
+				| InstanceCommand::ResumeGroup(_, settings) => settings.start_time,
+				InstanceCommand::StopInstance(_, settings)
+				| InstanceCommand::StopInstancesOf(_, settings)
+				| InstanceCommand::StopInstancesOfSequence(_, settings)
+				| InstanceCommand::StopGroup(_, settings) => settings.start_time,
+				_ => StartTime::Immediate,
+			},
+			Command::Voice(command) => match command {
+				VoiceCommand::PauseVoice(_, settings)
+				| VoiceCommand::PauseVoicesOnTrack(_, settings) => settings.start_time,
+				VoiceCommand::ResumeVoice(_, settings)
+				| VoiceCommand::ResumeVoicesOnTrack(_, settings) => settings.start_time,
+				VoiceCommand::StopVoice(_, settings)
+				| VoiceCommand::StopVoicesOnTrack(_, settings) => settings.start_time,
+			},
+			Command::Parameter(ParameterCommand::SetParameter(_, _, _, start_time)) => *start_time,
+			_ => StartTime::Immediate,
+		}
+	}
+
+	/// Returns the name of the command, for tracing.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Command::Resource(command) => match command {
+				ResourceCommand::AddSound(..) => "Resource::AddSound",
+				ResourceCommand::RemoveSound(..) => "Resource::RemoveSound",
+				ResourceCommand::ReplaceSound(..) => "Resource::ReplaceSound",
+				ResourceCommand::AddArrangement(..) => "Resource::AddArrangement",
+				ResourceCommand::RemoveArrangement(..) => "Resource::RemoveArrangement",
+				ResourceCommand::RemoveWhenUnused(..) => "Resource::RemoveWhenUnused",
+				ResourceCommand::SetArrangementLayerVolume(..) => {
+					"Resource::SetArrangementLayerVolume"
+				}
+			},
+			Command::Instance(command) => match command {
+				InstanceCommand::Play(..) => "Instance::Play",
+				InstanceCommand::SetInstanceVolume(..) => "Instance::SetInstanceVolume",
+				InstanceCommand::SetInstancePlaybackRate(..) => "Instance::SetInstancePlaybackRate",
+				InstanceCommand::SetInstancePanning(..) => "Instance::SetInstancePanning",
+				InstanceCommand::SetInstanceWidth(..) => "Instance::SetInstanceWidth",
+				InstanceCommand::SeekInstance(..) => "Instance::SeekInstance",
+				InstanceCommand::SeekInstanceTo(..) => "Instance::SeekInstanceTo",
+				InstanceCommand::SwapInstancePlayable(..) => "Instance::SwapInstancePlayable",
+				InstanceCommand::PauseInstance(..) => "Instance::PauseInstance",
+				InstanceCommand::ResumeInstance(..) => "Instance::ResumeInstance",
+				InstanceCommand::StopInstance(..) => "Instance::StopInstance",
+				InstanceCommand::PauseInstancesOf(..) => "Instance::PauseInstancesOf",
+				InstanceCommand::ResumeInstancesOf(..) => "Instance::ResumeInstancesOf",
+				InstanceCommand::StopInstancesOf(..) => "Instance::StopInstancesOf",
+				InstanceCommand::PauseInstancesOfSequence(..) => {
+					"Instance::PauseInstancesOfSequence"
+				}
+				InstanceCommand::ResumeInstancesOfSequence(..) => {
+					"Instance::ResumeInstancesOfSequence"
+				}
+				InstanceCommand::StopInstancesOfSequence(..) => "Instance::StopInstancesOfSequence",
+				InstanceCommand::PauseGroup(..) => "Instance::PauseGroup",
+				InstanceCommand::ResumeGroup(..) => "Instance::ResumeGroup",
+				InstanceCommand::StopGroup(..) => "Instance::StopGroup",
+				InstanceCommand::AddEffect(..) => "Instance::AddEffect",
+				InstanceCommand::SetEffectEnabled(..) => "Instance::SetEffectEnabled",
+				InstanceCommand::SetEffectMix(..) => "Instance::SetEffectMix",
+				InstanceCommand::SetEffectValue(..) => "Instance::SetEffectValue",
+				InstanceCommand::SendEffectMessage(..) => "Instance::SendEffectMessage",
+				InstanceCommand::RemoveEffect(..) => "Instance::RemoveEffect",
+			},
+			Command::Metronome(command) => match command {
+				MetronomeCommand::AddMetronome(..) => "Metronome::AddMetronome",
+				MetronomeCommand::RemoveMetronome(..) => "Metronome::RemoveMetronome",
+				MetronomeCommand::SetMetronomeTempo(..) => "Metronome::SetMetronomeTempo",
+				MetronomeCommand::SetMetronomeTime(..) => "Metronome::SetMetronomeTime",
+				MetronomeCommand::StartMetronome(..) => "Metronome::StartMetronome",
+				MetronomeCommand::PauseMetronome(..) => "Metronome::PauseMetronome",
+				MetronomeCommand::StopMetronome(..) => "Metronome::StopMetronome",
+				MetronomeCommand::SetMetronomeSyncInstance(..) => {
+					"Metronome::SetMetronomeSyncInstance"
+				}
+			},
+			Command::Sequence(command) => match command {
+				SequenceCommand::StartSequenceInstance(..) => "Sequence::StartSequenceInstance",
+				SequenceCommand::MuteSequenceInstance(..) => "Sequence::MuteSequenceInstance",
+				SequenceCommand::UnmuteSequenceInstance(..) => "Sequence::UnmuteSequenceInstance",
+				SequenceCommand::PauseSequenceInstance(..) => "Sequence::PauseSequenceInstance",
+				SequenceCommand::ResumeSequenceInstance(..) => "Sequence::ResumeSequenceInstance",
+				SequenceCommand::StopSequenceInstance(..) => "Sequence::StopSequenceInstance",
+				SequenceCommand::SignalSequenceInstance(..) => "Sequence::SignalSequenceInstance",
+				SequenceCommand::SetSequenceInstanceSpeed(..) => {
+					"Sequence::SetSequenceInstanceSpeed"
+				}
+				SequenceCommand::SetSequenceInstancePosition(..) => {
+					"Sequence::SetSequenceInstancePosition"
+				}
+				SequenceCommand::PauseGroup(..) => "Sequence::PauseGroup",
+				SequenceCommand::ResumeGroup(..) => "Sequence::ResumeGroup",
+				SequenceCommand::StopGroup(..) => "Sequence::StopGroup",
+			},
+			Command::Mixer(command) => match command {
+				MixerCommand::AddTrack(..) => "Mixer::AddTrack",
+				MixerCommand::SetTrackVolume(..) => "Mixer::SetTrackVolume",
+				MixerCommand::SetTrackWidth(..) => "Mixer::SetTrackWidth",
+				MixerCommand::SetTrackMuted(..) => "Mixer::SetTrackMuted",
+				MixerCommand::SetTrackSoloed(..) => "Mixer::SetTrackSoloed",
+				MixerCommand::RemoveSubTrack(..) => "Mixer::RemoveSubTrack",
+				MixerCommand::RemoveSendTrack(..) => "Mixer::RemoveSendTrack",
+				MixerCommand::AddEffect(..) => "Mixer::AddEffect",
+				MixerCommand::SetEffectEnabled(..) => "Mixer::SetEffectEnabled",
+				MixerCommand::SetEffectMix(..) => "Mixer::SetEffectMix",
+				MixerCommand::SetEffectValue(..) => "Mixer::SetEffectValue",
+				MixerCommand::SendEffectMessage(..) => "Mixer::SendEffectMessage",
+				MixerCommand::MoveEffect(..) => "Mixer::MoveEffect",
+				MixerCommand::RemoveEffect(..) => "Mixer::RemoveEffect",
+				MixerCommand::SetLevelAnalysisEnabled(..) => "Mixer::SetLevelAnalysisEnabled",
+			},
+			Command::Parameter(command) => match command {
+				ParameterCommand::AddParameter(..) => "Parameter::AddParameter",
+				ParameterCommand::RemoveParameter(..) => "Parameter::RemoveParameter",
+				ParameterCommand::SetParameter(..) => "Parameter::SetParameter",
+				ParameterCommand::AddModulation(..) => "Parameter::AddModulation",
+				ParameterCommand::RemoveModulation(..) => "Parameter::RemoveModulation",
+				ParameterCommand::SetModulationDepth(..) => "Parameter::SetModulationDepth",
+				ParameterCommand::StartAutomation(..) => "Parameter::StartAutomation",
+				ParameterCommand::StopAutomation(..) => "Parameter::StopAutomation",
+				ParameterCommand::AddDerivedParameter(..) => "Parameter::AddDerivedParameter",
+			},
+			Command::Group(command) => match command {
+				GroupCommand::AddGroup(..) => "Group::AddGroup",
+				GroupCommand::RemoveGroup(..) => "Group::RemoveGroup",
+				GroupCommand::SetGroupVolume(..) => "Group::SetGroupVolume",
+				GroupCommand::SetGroupMuffleAmount(..) => "Group::SetGroupMuffleAmount",
+			},
+			Command::Stream(command) => match command {
+				StreamCommand::AddStream(..) => "Stream::AddStream",
+				StreamCommand::RemoveStream(..) => "Stream::RemoveStream",
+				StreamCommand::SetStreamVolume(..) => "Stream::SetStreamVolume",
+				StreamCommand::SetStreamPanning(..) => "Stream::SetStreamPanning",
+				StreamCommand::PauseStream(..) => "Stream::PauseStream",
+				StreamCommand::ResumeStream(..) => "Stream::ResumeStream",
+				StreamCommand::StopStream(..) => "Stream::StopStream",
+				StreamCommand::SetStreamTrack(..) => "Stream::SetStreamTrack",
+			},
+			Command::Voice(command) => match command {
+				VoiceCommand::PauseVoice(..) => "Voice::PauseVoice",
+				VoiceCommand::ResumeVoice(..) => "Voice::ResumeVoice",
+				VoiceCommand::StopVoice(..) => "Voice::StopVoice",
+				VoiceCommand::PauseVoicesOnTrack(..) => "Voice::PauseVoicesOnTrack",
+				VoiceCommand::ResumeVoicesOnTrack(..) => "Voice::ResumeVoicesOnTrack",
+				VoiceCommand::StopVoicesOnTrack(..) => "Voice::StopVoicesOnTrack",
+			},
+			Command::CaptureSnapshot(..) => "CaptureSnapshot",
+		}
+	}
+
+	/// Returns `false` if the command only changes a value that
+	/// a later command of the same kind would overwrite anyway,
+	/// so it can be dropped when the command queue overflows.
+	pub fn is_critical(&self) -> bool {
+		!matches!(
+			self,
+			Command::Resource(ResourceCommand::SetArrangementLayerVolume(..))
+				| Command::Instance(
+					InstanceCommand::SetInstanceVolume(..)
+						| InstanceCommand::SetInstancePlaybackRate(..)
+						| InstanceCommand::SetInstancePanning(..)
+						| InstanceCommand::SetInstanceWidth(..)
+						| InstanceCommand::SetEffectMix(..)
+				) | Command::Metronome(MetronomeCommand::SetMetronomeTempo(..))
+				| Command::Sequence(SequenceCommand::SetSequenceInstanceSpeed(..))
+				| Command::Mixer(
+					MixerCommand::SetTrackVolume(..)
+						| MixerCommand::SetTrackWidth(..)
+						| MixerCommand::SetEffectMix(..)
+				) | Command::Parameter(
+				ParameterCommand::SetParameter(..) | ParameterCommand::SetModulationDepth(..)
+			) | Command::Group(
+				GroupCommand::SetGroupVolume(..) | GroupCommand::SetGroupMuffleAmount(..)
+			) | Command::Stream(
+				StreamCommand::SetStreamVolume(..) | StreamCommand::SetStreamPanning(..)
+			)
+		)
+	}
 }
 
 impl From<ResourceCommand> for Command {
@@ -160,3 +449,9 @@ impl From<StreamCommand> for Command {
 		Self::Stream(command)
 	}
 }
+
+impl From<VoiceCommand> for Command {
+	fn from(command: VoiceCommand) -> Self {
+		Self::Voice(command)
+	}
+}
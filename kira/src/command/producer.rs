@@ -1,5 +1,10 @@
-use std::sync::{Arc, Mutex};
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex, MutexGuard},
+	time::Duration,
+};
 
+use instant::Instant;
 use ringbuf::Producer;
 use thiserror::Error;
 
@@ -17,24 +22,163 @@ pub enum CommandError {
 	MutexPoisoned,
 }
 
+/// What to do when a command is sent while the command
+/// queue is full.
+///
+/// Commands that can't be sent right away with the
+/// [`Grow`](CommandOverflowStrategy::Grow) and
+/// [`DropNonCritical`](CommandOverflowStrategy::DropNonCritical)
+/// strategies are held in a backlog on the sending thread. The
+/// backlog is sent, in order, before any new command, and it can
+/// be sent on its own using
+/// [`AudioManager::flush_commands`](crate::manager::AudioManager::flush_commands).
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum CommandOverflowStrategy {
+	/// Return a [`CommandError::CommandQueueFull`] error.
+	ReturnError,
+	/// Wait for the audio thread to make room in the queue
+	/// for up to the given number of seconds, then return
+	/// a [`CommandError::CommandQueueFull`] error.
+	///
+	/// On wasm, the audio is processed on the main thread,
+	/// so waiting never frees up room in the queue.
+	Block(f64),
+	/// Hold the command in a backlog that grows as needed.
+	///
+	/// This never loses commands, but it allocates memory
+	/// when the backlog grows.
+	Grow,
+	/// Hold the command in a backlog that can hold up to
+	/// the given number of commands. When the backlog is full,
+	/// the oldest command in it that only changes a value
+	/// (like a volume or parameter change) is dropped. If there
+	/// is no such command, a [`CommandError::CommandQueueFull`]
+	/// error is returned.
+	DropNonCritical(usize),
+}
+
+struct CommandQueue {
+	producer: Producer<Command>,
+	backlog: VecDeque<Command>,
+	overflow_strategy: CommandOverflowStrategy,
+}
+
+impl CommandQueue {
+	/// Sends as many commands from the backlog as there
+	/// is room for.
+	fn flush(&mut self) {
+		while let Some(command) = self.backlog.pop_front() {
+			if let Err(command) = self.producer.push(command) {
+				self.backlog.push_front(command);
+				break;
+			}
+		}
+	}
+
+	fn push_to_backlog(
+		&mut self,
+		command: Command,
+		capacity: Option<usize>,
+	) -> Result<(), CommandError> {
+		if let Some(capacity) = capacity {
+			if self.backlog.len() >= capacity {
+				let index = self
+					.backlog
+					.iter()
+					.position(|command| !command.is_critical())
+					.ok_or(CommandError::CommandQueueFull)?;
+				self.backlog.remove(index);
+			}
+		}
+		self.backlog.push_back(command);
+		Ok(())
+	}
+}
+
 #[derive(Clone)]
 pub(crate) struct CommandProducer {
-	producer: Arc<Mutex<Producer<Command>>>,
+	queue: Arc<Mutex<CommandQueue>>,
 }
 
 impl CommandProducer {
-	pub fn new(producer: Producer<Command>) -> Self {
+	pub fn new(producer: Producer<Command>, overflow_strategy: CommandOverflowStrategy) -> Self {
 		Self {
-			producer: Arc::new(Mutex::new(producer)),
+			queue: Arc::new(Mutex::new(CommandQueue {
+				producer,
+				backlog: VecDeque::new(),
+				overflow_strategy,
+			})),
 		}
 	}
 
+	fn lock(&self) -> Result<MutexGuard<'_, CommandQueue>, CommandError> {
+		self.queue.lock().map_err(|_| CommandError::MutexPoisoned)
+	}
+
 	pub fn push(&mut self, command: Command) -> Result<(), CommandError> {
-		self.producer
-			.lock()
-			.map_err(|_| CommandError::MutexPoisoned)?
-			.push(command)
-			.map_err(|_| CommandError::CommandQueueFull)
+		let mut queue = self.lock()?;
+		queue.flush();
+		// commands can't skip ahead of the backlog, or they
+		// would be run out of order
+		let command = if queue.backlog.is_empty() {
+			match queue.producer.push(command) {
+				Ok(()) => return Ok(()),
+				Err(command) => command,
+			}
+		} else {
+			command
+		};
+		match queue.overflow_strategy {
+			CommandOverflowStrategy::ReturnError => Err(CommandError::CommandQueueFull),
+			CommandOverflowStrategy::Block(timeout) => {
+				drop(queue);
+				self.push_blocking(command, timeout)
+			}
+			CommandOverflowStrategy::Grow => queue.push_to_backlog(command, None),
+			CommandOverflowStrategy::DropNonCritical(capacity) => {
+				queue.push_to_backlog(command, Some(capacity))
+			}
+		}
+	}
+
+	fn push_blocking(&mut self, mut command: Command, timeout: f64) -> Result<(), CommandError> {
+		let start_time = Instant::now();
+		loop {
+			command = match self.lock()?.producer.push(command) {
+				Ok(()) => return Ok(()),
+				Err(command) => command,
+			};
+			if Instant::now() - start_time >= Duration::from_secs_f64(timeout) {
+				return Err(CommandError::CommandQueueFull);
+			}
+			std::thread::yield_now();
+		}
+	}
+
+	/// Sends as many commands from the backlog as there is
+	/// room for and returns the number of commands still
+	/// in the backlog.
+	pub fn flush(&mut self) -> Result<usize, CommandError> {
+		let mut queue = self.lock()?;
+		queue.flush();
+		Ok(queue.backlog.len())
+	}
+
+	/// Returns the number of commands waiting to be run
+	/// by the audio thread, including commands in the backlog.
+	pub fn len(&self) -> Result<usize, CommandError> {
+		let queue = self.lock()?;
+		Ok(queue.producer.len() + queue.backlog.len())
+	}
+
+	/// Returns the number of commands that fit in the
+	/// command queue, not including the backlog.
+	pub fn capacity(&self) -> Result<usize, CommandError> {
+		Ok(self.lock()?.producer.capacity())
 	}
 }
 
@@ -0,0 +1,127 @@
+use std::ops::{Add, Neg, Range, Sub};
+
+use crate::{
+	dsp::{amplitude_to_decibels, decibels_to_amplitude},
+	parameter::{Mapping, MappingCurve, ParameterId},
+	Value,
+};
+
+/// A volume (in decibels).
+///
+/// Decibels can be used anywhere a volume is expected, so an
+/// instance can be played 6 dB quieter than normal with:
+///
+/// ```no_run
+/// # use kira::{
+/// # 	instance::InstanceSettings, manager::{AudioManager, AudioManagerSettings},
+/// # 	sound::SoundSettings, Decibels,
+/// # };
+/// # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+/// # let mut sound_handle = audio_manager.load_sound("loop.ogg", SoundSettings::default())?;
+/// let mut instance_handle = sound_handle.play(InstanceSettings::new().volume(Decibels(-6.0)))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// 0 dB leaves the volume unchanged, and every 6 dB roughly
+/// doubles or halves the amplitude:
+///
+/// ```
+/// # use kira::Decibels;
+/// assert_eq!(Decibels::IDENTITY.amplitude(), 1.0);
+/// assert!((Decibels(-6.0).amplitude() - 0.501).abs() < 0.001);
+/// assert_eq!(Decibels::SILENCE.amplitude(), 0.0);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Decibels(pub f64);
+
+impl Decibels {
+	/// The volume that leaves audio unchanged.
+	pub const IDENTITY: Self = Self(0.0);
+
+	/// The volume that silences audio.
+	pub const SILENCE: Self = Self(f64::NEG_INFINITY);
+
+	/// Gets the volume in decibels of an amplitude.
+	pub fn from_amplitude(amplitude: f64) -> Self {
+		Self(amplitude_to_decibels(amplitude))
+	}
+
+	/// Returns the amplitude that changes the volume of
+	/// audio by this many decibels.
+	pub fn amplitude(&self) -> f64 {
+		decibels_to_amplitude(self.0)
+	}
+
+	/// Returns a volume that follows a parameter, changing
+	/// evenly in decibels from `start` to `end` as the
+	/// parameter goes from 0 to 1.
+	///
+	/// Tweening the parameter fades the volume in a way
+	/// that sounds even, unlike tweening the amplitude
+	/// directly, which sounds like it changes quickly at
+	/// first and slowly at the end. Both volumes should be a
+	/// finite number of decibels, like `-60.0` for the quiet
+	/// end of the fade.
+	pub fn fade(parameter: impl Into<ParameterId>, start: Self, end: Self) -> Value<f64> {
+		Value::Parameter(
+			parameter.into(),
+			Mapping {
+				output_range: (start.0, end.0),
+				curve: MappingCurve::Decibels,
+				..Default::default()
+			},
+		)
+	}
+}
+
+impl Add for Decibels {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		Self(self.0 + rhs.0)
+	}
+}
+
+impl Sub for Decibels {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self::Output {
+		Self(self.0 - rhs.0)
+	}
+}
+
+impl Neg for Decibels {
+	type Output = Self;
+
+	fn neg(self) -> Self::Output {
+		Self(-self.0)
+	}
+}
+
+impl From<f64> for Decibels {
+	fn from(decibels: f64) -> Self {
+		Self(decibels)
+	}
+}
+
+impl From<Decibels> for f64 {
+	fn from(decibels: Decibels) -> Self {
+		decibels.0
+	}
+}
+
+impl From<Decibels> for Value<f64> {
+	fn from(decibels: Decibels) -> Self {
+		Self::Fixed(decibels.amplitude())
+	}
+}
+
+impl From<Range<Decibels>> for Value<f64> {
+	fn from(range: Range<Decibels>) -> Self {
+		Self::Random(range.start.amplitude(), range.end.amplitude())
+	}
+}
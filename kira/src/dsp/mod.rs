@@ -0,0 +1,140 @@
+//! Building blocks for processing audio.
+//!
+//! These are the same helpers Kira's built-in effects and audio
+//! streams use, so custom [`Effect`](crate::mixer::effect::Effect)s
+//! and [`AudioStream`](crate::audio_stream::AudioStream)s can
+//! interpolate, convert volumes, and resample audio the same
+//! way the rest of the library does.
+//!
+//! [`Frame`]s can be added, subtracted, and scaled like numbers,
+//! and they can be panned with [`Frame::panned_with`]:
+//!
+//! ```
+//! # use kira::{dsp::{decibels_to_amplitude, lerp_frame}, Frame, Sample};
+//! let left = Frame::new(1.0, 0.0);
+//! let right = Frame::new(0.0, 1.0);
+//! assert_eq!(lerp_frame(left, right, 0.5), Frame::from_mono(0.5));
+//! assert_eq!(left * decibels_to_amplitude(0.0) as Sample + right, Frame::from_mono(1.0));
+//! ```
+
+use crate::{Frame, Sample};
+
+/// Blends between `a` and `b`, where an `amount` of `0.0`
+/// returns `a` and an `amount` of `1.0` returns `b`.
+pub fn lerp(a: f64, b: f64, amount: f64) -> f64 {
+	a + (b - a) * amount
+}
+
+/// Returns how far `point` is from `start` to `end`, where
+/// `start` is `0.0` and `end` is `1.0`.
+///
+/// This is the opposite of [`lerp`].
+pub fn inverse_lerp(start: f64, end: f64, point: f64) -> f64 {
+	(point - start) / (end - start)
+}
+
+/// Blends between two frames, where an `amount` of `0.0`
+/// returns `a` and an `amount` of `1.0` returns `b`.
+pub fn lerp_frame(a: Frame, b: Frame, amount: Sample) -> Frame {
+	a + (b - a) * amount
+}
+
+/// Converts a volume in decibels to an amplitude.
+pub fn decibels_to_amplitude(decibels: f64) -> f64 {
+	10.0f64.powf(decibels / 20.0)
+}
+
+/// Converts an amplitude to a volume in decibels.
+///
+/// An amplitude of `0.0` is negative infinity decibels.
+pub fn amplitude_to_decibels(amplitude: f64) -> f64 {
+	20.0 * amplitude.log10()
+}
+
+/// Given a previous frame, a current frame, the two next frames,
+/// and a position `x` from 0.0 to 1.0 between the current frame
+/// and next frame, get an approximated frame.
+///
+/// This is the 4-point, 3rd-order Hermite interpolation x-form
+/// algorithm from "Polynomial Interpolators for High-Quality
+/// Resampling of Oversampled Audio" by Olli Niemitalo, p. 43:
+/// http://yehar.com/blog/wp-content/uploads/2009/08/deip.pdf
+pub fn interpolate_frame(
+	previous: Frame,
+	current: Frame,
+	next_1: Frame,
+	next_2: Frame,
+	fraction: Sample,
+) -> Frame {
+	let c0 = current;
+	let c1 = (next_1 - previous) * 0.5;
+	let c2 = previous - current * 2.5 + next_1 * 2.0 - next_2 * 0.5;
+	let c3 = (next_2 - previous) * 0.5 + (current - next_1) * 1.5;
+	((c3 * fraction + c2) * fraction + c1) * fraction + c0
+}
+
+/// Plays audio recorded at one sample rate at the sample
+/// rate of the audio thread.
+///
+/// The resampler reads input frames as it needs them and blends
+/// between the two most recent frames, which delays the audio
+/// by one input frame.
+///
+/// ```
+/// # use kira::{dsp::Resampler, Frame};
+/// // play audio recorded at 2 Hz on a 4 Hz audio thread
+/// let mut resampler = Resampler::new(2);
+/// let mut input = vec![Frame::from_mono(1.0), Frame::from_mono(0.0)].into_iter();
+/// let mut next_input = || input.next().unwrap_or(Frame::from_mono(0.0));
+/// assert_eq!(resampler.next(0.25, &mut next_input), Frame::from_mono(0.0));
+/// assert_eq!(resampler.next(0.25, &mut next_input), Frame::from_mono(0.0));
+/// assert_eq!(resampler.next(0.25, &mut next_input), Frame::from_mono(0.5));
+/// assert_eq!(resampler.next(0.25, &mut next_input), Frame::from_mono(1.0));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Resampler {
+	sample_rate: u32,
+	previous_frame: Frame,
+	next_frame: Frame,
+	fractional_position: f64,
+}
+
+impl Resampler {
+	/// Creates a new resampler for audio with the given
+	/// sample rate.
+	pub fn new(sample_rate: u32) -> Self {
+		Self {
+			sample_rate,
+			previous_frame: Frame::from_mono(0.0),
+			next_frame: Frame::from_mono(0.0),
+			fractional_position: 0.0,
+		}
+	}
+
+	/// Returns the sample rate of the input audio.
+	pub fn sample_rate(&self) -> u32 {
+		self.sample_rate
+	}
+
+	/// Produces the next output frame.
+	///
+	/// `dt` is the time (in seconds) between output frames, and
+	/// `next_input` is called once for each input frame the
+	/// resampler moves past.
+	pub fn next(&mut self, dt: f64, mut next_input: impl FnMut() -> Frame) -> Frame {
+		self.fractional_position += dt * self.sample_rate as f64;
+		while self.fractional_position >= 1.0 {
+			self.fractional_position -= 1.0;
+			self.previous_frame = self.next_frame;
+			self.next_frame = next_input();
+		}
+		lerp_frame(
+			self.previous_frame,
+			self.next_frame,
+			self.fractional_position as Sample,
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests;
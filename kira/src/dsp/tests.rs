@@ -0,0 +1,95 @@
+use super::{amplitude_to_decibels, decibels_to_amplitude, interpolate_frame, Resampler};
+use crate::{Frame, Sample};
+
+#[test]
+fn decibels_and_amplitudes_round_trip() {
+	assert_eq!(decibels_to_amplitude(0.0), 1.0);
+	assert_eq!(amplitude_to_decibels(1.0), 0.0);
+	assert!((decibels_to_amplitude(-20.0) - 0.1).abs() < 1.0e-9);
+	assert!((amplitude_to_decibels(0.5) - -6.0206).abs() < 1.0e-4);
+	assert_eq!(amplitude_to_decibels(0.0), f64::NEG_INFINITY);
+	for decibels in [-60.0, -12.5, -3.0, 0.0, 6.0, 24.0] {
+		let round_trip = amplitude_to_decibels(decibels_to_amplitude(decibels));
+		assert!((round_trip - decibels).abs() < 1.0e-9);
+	}
+}
+
+#[test]
+fn interpolate_frame_passes_through_the_current_and_next_frames() {
+	let previous = Frame::new(0.25, -1.0);
+	let current = Frame::new(1.0, 0.5);
+	let next_1 = Frame::new(-0.5, 0.75);
+	let next_2 = Frame::new(0.0, -0.25);
+	assert_eq!(
+		interpolate_frame(previous, current, next_1, next_2, 0.0),
+		current
+	);
+	let end = interpolate_frame(previous, current, next_1, next_2, 1.0);
+	assert!((end.left - next_1.left).abs() < 1.0e-6);
+	assert!((end.right - next_1.right).abs() < 1.0e-6);
+}
+
+#[test]
+fn interpolate_frame_follows_straight_lines() {
+	for (fraction, expected) in [(0.25, 1.25), (0.5, 1.5), (0.75, 1.75)] {
+		let frame = interpolate_frame(
+			Frame::from_mono(0.0),
+			Frame::from_mono(1.0),
+			Frame::from_mono(2.0),
+			Frame::from_mono(3.0),
+			fraction,
+		);
+		assert!((frame.left - expected).abs() < 1.0e-6);
+		assert!((frame.right - expected).abs() < 1.0e-6);
+	}
+}
+
+/// Resamples 100 output frames of a ramp that rises by 1.0
+/// every input frame, returning the left channel of the output
+/// and how many input frames were used.
+fn resample_ramp(input_sample_rate: u32, output_sample_rate: u32) -> (Vec<Sample>, usize) {
+	let mut resampler = Resampler::new(input_sample_rate);
+	let mut num_inputs = 0;
+	let output = (0..100)
+		.map(|_| {
+			resampler
+				.next(1.0 / output_sample_rate as f64, || {
+					num_inputs += 1;
+					Frame::from_mono(num_inputs as Sample)
+				})
+				.left
+		})
+		.collect();
+	(output, num_inputs)
+}
+
+#[test]
+fn resampler_upsamples() {
+	let (output, num_inputs) = resample_ramp(24000, 48000);
+	assert_eq!(num_inputs, 50);
+	// each input frame is followed by a frame halfway
+	// to the next input frame, one input frame late
+	for (i, sample) in output.iter().enumerate().skip(1) {
+		assert!((sample - (i as Sample / 2.0 - 0.5)).abs() < 1.0e-3);
+	}
+}
+
+#[test]
+fn resampler_downsamples() {
+	let (output, num_inputs) = resample_ramp(96000, 48000);
+	assert_eq!(num_inputs, 200);
+	// every other input frame is skipped
+	for (i, sample) in output.iter().enumerate() {
+		assert!((sample - (i * 2 + 1) as Sample).abs() < 1.0e-3);
+	}
+}
+
+#[test]
+fn resampler_passes_matching_sample_rates_through() {
+	let (output, num_inputs) = resample_ramp(48000, 48000);
+	assert_eq!(num_inputs, 100);
+	// the output is one input frame behind
+	for (i, sample) in output.iter().enumerate() {
+		assert!((sample - i as Sample).abs() < 1.0e-3);
+	}
+}
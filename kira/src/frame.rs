@@ -1,40 +1,78 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+/// The type used for audio samples.
+///
+/// Audio is processed with `f32` samples by default. Enable the
+/// `f64_processing` feature to process audio with `f64` samples,
+/// which keeps rounding errors from building up over long effect
+/// chains or offline renders at the cost of speed and memory.
+#[cfg(not(feature = "f64_processing"))]
+pub type Sample = f32;
+
+/// The type used for audio samples.
+///
+/// Audio is processed with `f32` samples by default. Enable the
+/// `f64_processing` feature to process audio with `f64` samples,
+/// which keeps rounding errors from building up over long effect
+/// chains or offline renders at the cost of speed and memory.
+#[cfg(feature = "f64_processing")]
+pub type Sample = f64;
+
 /// An audio sample with a left and right channel.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Frame {
 	/// The sample for the left channel.
-	pub left: f32,
+	pub left: Sample,
 	/// The sample for the right channel.
-	pub right: f32,
+	pub right: Sample,
 }
 
 impl Frame {
 	/// Creates a frame with the given left and right values.
-	pub fn new(left: f32, right: f32) -> Self {
+	pub fn new(left: Sample, right: Sample) -> Self {
 		Self { left, right }
 	}
 
 	/// Creates a frame with both the left and right channels set
 	/// to the same value.
-	pub fn from_mono(value: f32) -> Self {
+	pub fn from_mono(value: Sample) -> Self {
 		Self::new(value, value)
 	}
 
 	/// Creates a frame from `i32`s with the given bit depth.
 	pub fn from_i32(left: i32, right: i32, bit_depth: u32) -> Self {
 		let max_int = (1 << bit_depth) / 2;
-		let scale = 1.0 / max_int as f32;
-		Self::new(left as f32 * scale, right as f32 * scale)
+		let scale = 1.0 / max_int as Sample;
+		Self::new(left as Sample * scale, right as Sample * scale)
+	}
+
+	/// Returns the left and right samples as `f32`s, which is
+	/// what audio devices and plugins expect.
+	// the casts only do something when f64 processing is enabled
+	#[allow(clippy::unnecessary_cast)]
+	#[cfg_attr(not(feature = "cpal"), allow(dead_code))]
+	pub(crate) fn to_f32(self) -> (f32, f32) {
+		(self.left as f32, self.right as f32)
 	}
 
 	/// Pans a frame to the left or right.
 	///
 	/// An `x` of 0 represents a hard left panning, an `x` of 1
 	/// represents a hard right panning.
-	pub fn panned(self, x: f32) -> Self {
+	pub fn panned(self, x: Sample) -> Self {
 		Self::new(self.left * (1.0 - x).sqrt(), self.right * x.sqrt())
 	}
+
+	/// Changes the stereo width of a frame.
+	///
+	/// A `width` of 0 makes the frame mono, 1 leaves it unchanged,
+	/// and values above 1 widen it by turning up the differences
+	/// between the left and right channels.
+	pub fn with_width(self, width: Sample) -> Self {
+		let mid = (self.left + self.right) * 0.5;
+		let side = (self.left - self.right) * 0.5 * width;
+		Self::new(mid + side, mid - side)
+	}
 }
 
 impl Add for Frame {
@@ -67,31 +105,31 @@ impl SubAssign for Frame {
 	}
 }
 
-impl Mul<f32> for Frame {
+impl Mul<Sample> for Frame {
 	type Output = Self;
 
-	fn mul(self, rhs: f32) -> Self::Output {
+	fn mul(self, rhs: Sample) -> Self::Output {
 		Self::new(self.left * rhs, self.right * rhs)
 	}
 }
 
-impl MulAssign<f32> for Frame {
-	fn mul_assign(&mut self, rhs: f32) {
+impl MulAssign<Sample> for Frame {
+	fn mul_assign(&mut self, rhs: Sample) {
 		self.left *= rhs;
 		self.right *= rhs;
 	}
 }
 
-impl Div<f32> for Frame {
+impl Div<Sample> for Frame {
 	type Output = Self;
 
-	fn div(self, rhs: f32) -> Self::Output {
+	fn div(self, rhs: Sample) -> Self::Output {
 		Self::new(self.left / rhs, self.right / rhs)
 	}
 }
 
-impl DivAssign<f32> for Frame {
-	fn div_assign(&mut self, rhs: f32) {
+impl DivAssign<Sample> for Frame {
+	fn div_assign(&mut self, rhs: Sample) {
 		self.left /= rhs;
 		self.right /= rhs;
 	}
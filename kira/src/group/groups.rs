@@ -1,6 +1,11 @@
 use basedrop::Owned;
 
-use crate::{command::GroupCommand, static_container::index_map::StaticIndexMap};
+use crate::{
+	command::GroupCommand,
+	manager::resources::{RemovedResource, RemovedResourceProducer},
+	parameter::Parameters,
+	static_container::index_map::StaticIndexMap,
+};
 
 use super::{Group, GroupId};
 
@@ -19,13 +24,68 @@ impl Groups {
 		self.groups.get(&id)
 	}
 
-	pub fn run_command(&mut self, command: GroupCommand) {
+	pub fn iter(&self) -> impl Iterator<Item = (&GroupId, &Owned<Group>)> {
+		self.groups.iter()
+	}
+
+	/// Returns `true` if any of the groups something is in,
+	/// or any of their ancestors, is cooling down.
+	///
+	/// `is_in_group` should return whether the thing is in
+	/// the group with the given ID.
+	pub fn cooling_down(&self, is_in_group: impl Fn(GroupId, &Self) -> bool) -> bool {
+		self.groups
+			.iter()
+			.any(|(id, group)| group.cooling_down() && is_in_group(*id, self))
+	}
+
+	/// Starts the cooldown timers for the groups something is in
+	/// and all of their ancestors.
+	pub fn start_cooldowns(&mut self, is_in_group: impl Fn(GroupId, &Self) -> bool) {
+		for i in 0..self.groups.len() {
+			let id = *self.groups.get_index(i).unwrap().0;
+			if is_in_group(id, self) {
+				self.groups.get_mut(&id).unwrap().start_cooldown();
+			}
+		}
+	}
+
+	pub fn update(&mut self, dt: f64, parameters: &Parameters) {
+		for group in self.groups.values_mut() {
+			group.update_cooldown(dt);
+			group.update_values(parameters);
+		}
+	}
+
+	/// Returns `true` if any group changes the audio of
+	/// the instances in it.
+	pub fn have_effects(&self) -> bool {
+		self.groups.iter().any(|(_, group)| group.has_effects())
+	}
+
+	pub fn run_command(
+		&mut self,
+		command: GroupCommand,
+		removed_resources: &mut RemovedResourceProducer,
+	) {
 		match command {
 			GroupCommand::AddGroup(id, group) => {
 				self.groups.try_insert(id, group).ok();
 			}
 			GroupCommand::RemoveGroup(id) => {
-				self.groups.remove(&id);
+				if let Some(group) = self.groups.remove(&id) {
+					removed_resources.push(RemovedResource::Group { id, _group: group });
+				}
+			}
+			GroupCommand::SetGroupVolume(id, volume) => {
+				if let Some(group) = self.groups.get_mut(&id) {
+					group.set_volume(volume);
+				}
+			}
+			GroupCommand::SetGroupMuffleAmount(id, amount) => {
+				if let Some(group) = self.groups.get_mut(&id) {
+					group.set_muffle_amount(amount);
+				}
 			}
 		}
 	}
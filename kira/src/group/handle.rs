@@ -3,9 +3,10 @@
 use crate::{
 	command::{
 		producer::{CommandError, CommandProducer},
-		InstanceCommand, SequenceCommand,
+		GroupCommand, InstanceCommand, SequenceCommand,
 	},
 	instance::{PauseInstanceSettings, ResumeInstanceSettings, StopInstanceSettings},
+	Value,
 };
 
 use super::GroupId;
@@ -56,4 +57,22 @@ impl GroupHandle {
 			.push(SequenceCommand::StopGroup(self.id().into()).into())?;
 		Ok(())
 	}
+
+	/// Sets the volume of all instances in this group.
+	pub fn set_volume(&mut self, volume: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(GroupCommand::SetGroupVolume(self.id, volume.into()).into())
+	}
+
+	/// Sets how muffled all instances in this group are, where
+	/// 0 is unaffected and 1 is fully muffled.
+	///
+	/// If the group wasn't created with
+	/// [muffle settings](super::GroupSettings::muffle), the
+	/// default [`MuffleSettings`](crate::mixer::effect::muffle::MuffleSettings)
+	/// are used.
+	pub fn set_muffle_amount(&mut self, amount: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(GroupCommand::SetGroupMuffleAmount(self.id, amount.into()).into())
+	}
 }
@@ -0,0 +1,88 @@
+use std::iter::FromIterator;
+
+use super::{groups::Groups, GroupId};
+
+const MAX_INSTANCE_GROUPS: usize = 4;
+
+/// A set of groups that an instance can belong to in addition
+/// to the groups of its sound or arrangement.
+///
+/// Unlike a [`GroupSet`](super::GroupSet), an `InstanceGroups` is
+/// stored inline so instance settings can be copied to the audio
+/// thread without allocating memory. Because of this, it can only
+/// hold up to 4 groups. Any groups added past that are ignored.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct InstanceGroups([Option<GroupId>; MAX_INSTANCE_GROUPS]);
+
+impl InstanceGroups {
+	/// Creates a new, empty `InstanceGroups`.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a group to the set.
+	#[allow(clippy::should_implement_trait)]
+	pub fn add(mut self, id: impl Into<GroupId>) -> Self {
+		let id = id.into();
+		if self.contains(id) {
+			return self;
+		}
+		if let Some(slot) = self.0.iter_mut().find(|slot| slot.is_none()) {
+			*slot = Some(id);
+		}
+		self
+	}
+
+	/// Returns true if the set contains the specified group.
+	pub fn contains(&self, id: impl Into<GroupId>) -> bool {
+		let id = id.into();
+		self.iter().any(|group| group == id)
+	}
+
+	/// Returns the number of groups in the set.
+	pub fn len(&self) -> usize {
+		self.iter().count()
+	}
+
+	/// Returns true if the set has no groups.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns an iterator over the group IDs in this set.
+	pub fn iter(&self) -> impl Iterator<Item = GroupId> + '_ {
+		self.0.iter().filter_map(|id| *id)
+	}
+
+	/// Returns true if one of the groups in the set has a specified
+	/// group as an ancestor or is that group itself.
+	pub(crate) fn has_ancestor(&self, ancestor: GroupId, all_groups: &Groups) -> bool {
+		if all_groups.get(ancestor).is_none() {
+			return false;
+		}
+		self.iter().any(|id| {
+			id == ancestor
+				|| matches!(
+					all_groups.get(id),
+					Some(group) if group.groups().has_ancestor(ancestor, all_groups)
+				)
+		})
+	}
+}
+
+impl<T: Into<GroupId>> FromIterator<T> for InstanceGroups {
+	fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+		iter.into_iter()
+			.fold(Self::new(), |groups, id| groups.add(id))
+	}
+}
+
+impl<T: Into<GroupId>, const N: usize> From<[T; N]> for InstanceGroups {
+	fn from(ids: [T; N]) -> Self {
+		Self::from_iter(ids)
+	}
+}
@@ -5,19 +5,53 @@
 //! and [`Sequence`](crate::sequence::Sequence)s can be assigned
 //! to any number of groups when they're created.
 //! Groups themselves can also be assigned to groups.
+//! Individual instances can be added to more groups when
+//! they're played with [`InstanceSettings::groups`](crate::instance::InstanceSettings::groups),
+//! which is useful when the same sound is used for different
+//! things, like footsteps of the player and of enemies.
 //!
 //! The [`pause`](handle::GroupHandle::pause), [`resume`](handle::GroupHandle::resume), and
 //! [`stop`](handle::GroupHandle::stop) functions on [`GroupHandle`](handle::GroupHandle)s will
 //! affect all instances that have the specified group anywhere in their ancestry.
+//!
+//! ## Group volume and muffling
+//!
+//! A group can change the volume of every instance in it and
+//! muffle them, no matter which mixer tracks they play on. This
+//! is useful when the split between sounds that should be
+//! affected and sounds that shouldn't doesn't line up with the
+//! mixer's tracks:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	group::GroupSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::effect::muffle::MuffleSettings,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let mut world = audio_manager.add_group(
+//! 	GroupSettings::new().muffle(MuffleSettings::new().amount(0.0).cutoff(400.0)),
+//! )?;
+//! // when the player goes underwater, every sound in the
+//! // "world" group is muffled, but UI sounds aren't
+//! world.set_muffle_amount(1.0)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
 pub(crate) mod groups;
 pub mod handle;
+mod instance_groups;
 mod set;
+#[cfg(test)]
+mod tests;
 
 use handle::GroupHandle;
+pub use instance_groups::InstanceGroups;
 pub use set::GroupSet;
 use uuid::Uuid;
 
+use crate::{mixer::effect::muffle::MuffleSettings, parameter::Parameters, CachedValue, Value};
+
 /// A unique identifier for a group.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(
@@ -55,6 +89,31 @@ pub struct GroupSettings {
 	pub id: Option<GroupId>,
 	/// The groups this group belongs to.
 	pub groups: GroupSet,
+	/// Whether sounds and arrangements in this group should
+	/// have a "cool off" period after any of them is played,
+	/// and if so, the duration of that period.
+	///
+	/// While the group is cooling down, none of the sounds
+	/// and arrangements in it (or in its sub-groups) can be
+	/// played. This is useful for limiting how often sounds
+	/// from a whole category, like dialogue barks, can play.
+	pub cooldown: Option<f64>,
+	/// The maximum number of instances of sounds and
+	/// arrangements in this group (or in its sub-groups)
+	/// that can exist at a time, or `None` for no limit.
+	///
+	/// While the limit is reached, new instances in the
+	/// group aren't started. Instances that are fading
+	/// out to be stopped don't count towards the limit.
+	pub max_instances: Option<usize>,
+	/// The volume of the instances in this group (or in
+	/// its sub-groups).
+	///
+	/// This is applied on top of the volume of each instance.
+	pub volume: Value<f64>,
+	/// Whether the instances in this group (or in its sub-groups)
+	/// should be muffled, and if so, how.
+	pub muffle: Option<MuffleSettings>,
 }
 
 impl GroupSettings {
@@ -67,7 +126,7 @@ impl GroupSettings {
 	pub fn id(self, id: impl Into<GroupId>) -> Self {
 		Self {
 			id: Some(id.into()),
-			..Default::default()
+			..self
 		}
 	}
 
@@ -75,7 +134,41 @@ impl GroupSettings {
 	pub fn groups(self, groups: impl Into<GroupSet>) -> Self {
 		Self {
 			groups: groups.into(),
-			..Default::default()
+			..self
+		}
+	}
+
+	/// Sets the cooldown for the sounds and arrangements
+	/// in this group.
+	pub fn cooldown(self, cooldown: f64) -> Self {
+		Self {
+			cooldown: Some(cooldown),
+			..self
+		}
+	}
+
+	/// Sets the maximum number of instances of sounds and
+	/// arrangements in this group that can exist at a time.
+	pub fn max_instances(self, max_instances: usize) -> Self {
+		Self {
+			max_instances: Some(max_instances),
+			..self
+		}
+	}
+
+	/// Sets the volume of the instances in this group.
+	pub fn volume(self, volume: impl Into<Value<f64>>) -> Self {
+		Self {
+			volume: volume.into(),
+			..self
+		}
+	}
+
+	/// Sets how the instances in this group should be muffled.
+	pub fn muffle(self, muffle: MuffleSettings) -> Self {
+		Self {
+			muffle: Some(muffle),
+			..self
 		}
 	}
 }
@@ -85,23 +178,119 @@ impl Default for GroupSettings {
 		Self {
 			id: None,
 			groups: GroupSet::new(),
+			cooldown: None,
+			max_instances: None,
+			volume: Value::Fixed(1.0),
+			muffle: None,
 		}
 	}
 }
 
+/// How much a group muffles the instances in it.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct GroupMuffle {
+	pub amount: f64,
+	pub cutoff: f64,
+	pub volume: f64,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Group {
 	groups: GroupSet,
+	cooldown: Option<f64>,
+	max_instances: Option<usize>,
+	cooldown_timer: f64,
+	volume: CachedValue<f64>,
+	muffle: Option<(CachedValue<f64>, MuffleSettings)>,
 }
 
 impl Group {
 	pub fn new(settings: GroupSettings) -> Self {
 		Self {
 			groups: settings.groups,
+			cooldown: settings.cooldown,
+			max_instances: settings.max_instances,
+			cooldown_timer: 0.0,
+			volume: CachedValue::new(settings.volume, 1.0).with_min(0.0),
+			muffle: settings.muffle.map(|muffle| {
+				(
+					CachedValue::new(muffle.amount, 1.0).with_valid_range(0.0..1.0),
+					muffle,
+				)
+			}),
 		}
 	}
 
 	pub fn groups(&self) -> &GroupSet {
 		&self.groups
 	}
+
+	pub fn max_instances(&self) -> Option<usize> {
+		self.max_instances
+	}
+
+	/// Starts the cooldown timer for the group.
+	pub fn start_cooldown(&mut self) {
+		if let Some(cooldown) = self.cooldown {
+			self.cooldown_timer = cooldown;
+		}
+	}
+
+	/// Updates the cooldown timer for the group.
+	pub fn update_cooldown(&mut self, dt: f64) {
+		if self.cooldown_timer > 0.0 {
+			self.cooldown_timer -= dt;
+		}
+	}
+
+	/// Gets whether the group is currently "cooling down".
+	pub fn cooling_down(&self) -> bool {
+		self.cooldown_timer > 0.0
+	}
+
+	pub fn set_volume(&mut self, volume: Value<f64>) {
+		self.volume.set(volume);
+	}
+
+	/// Sets how muffled the instances in the group are,
+	/// muffling them with the default settings if the group
+	/// wasn't set up to muffle them.
+	pub fn set_muffle_amount(&mut self, amount: Value<f64>) {
+		match &mut self.muffle {
+			Some((current_amount, _)) => current_amount.set(amount),
+			None => {
+				self.muffle = Some((
+					CachedValue::new(amount, 1.0).with_valid_range(0.0..1.0),
+					MuffleSettings::default(),
+				))
+			}
+		}
+	}
+
+	/// Updates the volume and muffle amount of the group
+	/// if they're linked to parameters.
+	pub fn update_values(&mut self, parameters: &Parameters) {
+		self.volume.update(parameters);
+		if let Some((amount, _)) = &mut self.muffle {
+			amount.update(parameters);
+		}
+	}
+
+	/// Returns `true` if the group changes the audio of the
+	/// instances in it.
+	pub fn has_effects(&self) -> bool {
+		self.volume.value() != 1.0 || self.muffle.is_some()
+	}
+
+	pub fn volume(&self) -> f64 {
+		self.volume.value()
+	}
+
+	pub fn muffle(&self) -> Option<GroupMuffle> {
+		self.muffle.as_ref().map(|(amount, settings)| GroupMuffle {
+			amount: amount.value(),
+			cutoff: settings.cutoff,
+			volume: settings.volume,
+		})
+	}
 }
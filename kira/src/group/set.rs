@@ -1,8 +1,27 @@
+use std::iter::FromIterator;
+
 use indexmap::IndexSet;
 
 use super::{groups::Groups, GroupId};
 
 /// A set of groups that an item can belong to.
+///
+/// A `GroupSet` can be built by chaining calls to [`add`](GroupSet::add),
+/// or from an array or iterator of groups:
+///
+/// ```no_run
+/// # use kira::{
+/// # 	group::{GroupSet, GroupSettings},
+/// # 	manager::{AudioManager, AudioManagerSettings},
+/// # 	sound::SoundSettings,
+/// # };
+/// # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+/// let sfx = audio_manager.add_group(GroupSettings::new())?;
+/// let ui = audio_manager.add_group(GroupSettings::new())?;
+/// let settings = SoundSettings::new().groups([&sfx, &ui]);
+/// let groups: GroupSet = vec![&sfx, &ui].into_iter().collect();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
 #[derive(Debug, Clone)]
 #[cfg_attr(
 	feature = "serde_support",
@@ -23,6 +42,12 @@ impl GroupSet {
 		self
 	}
 
+	/// Adds multiple groups to the set.
+	pub fn add_iter<T: Into<GroupId>>(mut self, ids: impl IntoIterator<Item = T>) -> Self {
+		self.extend(ids);
+		self
+	}
+
 	/// Removes a group from the set.
 	pub fn remove(mut self, id: impl Into<GroupId>) -> Self {
 		self.0.remove(&id.into());
@@ -34,6 +59,16 @@ impl GroupSet {
 		self.0.contains(&id.into())
 	}
 
+	/// Returns the number of groups in the set.
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	/// Returns true if the set has no groups.
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
 	/// Returns an iterator over the group IDs in this set.
 	pub fn iter(&self) -> indexmap::set::Iter<GroupId> {
 		self.0.iter()
@@ -65,6 +100,30 @@ impl GroupSet {
 	}
 }
 
+impl Default for GroupSet {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Into<GroupId>> Extend<T> for GroupSet {
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		self.0.extend(iter.into_iter().map(Into::into));
+	}
+}
+
+impl<T: Into<GroupId>> FromIterator<T> for GroupSet {
+	fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+		Self::new().add_iter(iter)
+	}
+}
+
+impl<T: Into<GroupId>, const N: usize> From<[T; N]> for GroupSet {
+	fn from(ids: [T; N]) -> Self {
+		Self::from_iter(ids)
+	}
+}
+
 impl<'a> IntoIterator for &'a GroupSet {
 	type Item = &'a GroupId;
 
@@ -0,0 +1,217 @@
+use crate::{
+	instance::{InstanceSettings, InstanceState, PauseInstanceSettings, StopInstanceSettings},
+	manager::{resources::ResourceKind, AudioManager, AudioManagerSettings},
+	sound::{Sound, SoundSettings},
+	testing::{constant_sound, create_manager},
+	Frame,
+};
+
+use super::{GroupSet, GroupSettings};
+
+#[test]
+fn group_and_global_cooldowns_limit_how_often_sounds_play() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			global_cooldown: Some(0.25),
+			..Default::default()
+		},
+		48000,
+	);
+	let parent_group = manager
+		.add_group(GroupSettings::new().cooldown(0.5))
+		.unwrap();
+	let group = manager
+		.add_group(GroupSettings::new().groups(GroupSet::new().add(&parent_group)))
+		.unwrap();
+	let mut sounds = (0..2)
+		.map(|_| {
+			manager
+				.add_sound(Sound::from_frames(
+					48000,
+					vec![Frame::from_mono(1.0); 48000 * 5],
+					SoundSettings::new().groups(GroupSet::new().add(&group)),
+				))
+				.unwrap()
+		})
+		.collect::<Vec<_>>();
+	let mut other_sound = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			vec![Frame::from_mono(1.0); 48000 * 5],
+			Default::default(),
+		))
+		.unwrap();
+	backend.process();
+	sounds[0].play(InstanceSettings::default()).unwrap();
+	backend.process();
+	// the sub-group inherits the parent group's cooldown, and the
+	// global cooldown applies to sounds outside of the group
+	sounds[1].play(InstanceSettings::default()).unwrap();
+	other_sound.play(InstanceSettings::default()).unwrap();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 1);
+	// after the global cooldown, sounds in the group are
+	// still blocked by the group's cooldown
+	for _ in 0..18000 {
+		backend.process();
+	}
+	sounds[1].play(InstanceSettings::default()).unwrap();
+	other_sound.play(InstanceSettings::default()).unwrap();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 2);
+	for _ in 0..18000 {
+		backend.process();
+	}
+	sounds[1].play(InstanceSettings::default()).unwrap();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 3);
+}
+
+#[test]
+fn controls_instances_added_to_groups_when_played() {
+	let (mut manager, mut backend) = create_manager();
+	let mut characters = manager.add_group(GroupSettings::new()).unwrap();
+	let mut player = manager
+		.add_group(GroupSettings::new().groups([&characters]))
+		.unwrap();
+	let enemies = manager
+		.add_group(GroupSettings::new().max_instances(1))
+		.unwrap();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let player_instance = sound_handle
+		.play(InstanceSettings::new().groups([&player]))
+		.unwrap();
+	let enemy_instance = sound_handle
+		.play(InstanceSettings::new().groups([&enemies]))
+		.unwrap();
+	// the enemies group only allows one instance at a time
+	sound_handle
+		.play(InstanceSettings::new().groups([&enemies]))
+		.unwrap();
+	backend.process();
+	assert_eq!(player_instance.state(), InstanceState::Playing);
+	assert_eq!(enemy_instance.state(), InstanceState::Playing);
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 2);
+	player
+		.pause(PauseInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert!(matches!(player_instance.state(), InstanceState::Paused(_)));
+	assert_eq!(enemy_instance.state(), InstanceState::Playing);
+	// groups of groups apply to instances too
+	characters
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert_eq!(player_instance.state(), InstanceState::Stopped);
+	assert_eq!(enemy_instance.state(), InstanceState::Playing);
+}
+
+#[test]
+fn limits_the_number_of_instances_in_a_group() {
+	let (mut manager, mut backend) = create_manager();
+	let parent_group = manager
+		.add_group(GroupSettings::new().max_instances(2))
+		.unwrap();
+	let group = manager
+		.add_group(GroupSettings::new().groups([&parent_group]))
+		.unwrap();
+	let sound = constant_sound(
+		0.0,
+		SoundSettings {
+			cooldown: None,
+			..Default::default()
+		},
+	);
+	let mut grouped_sound_handle = manager
+		.add_sound(sound.with_settings(SoundSettings {
+			cooldown: None,
+			groups: GroupSet::new().add(&group),
+			..Default::default()
+		}))
+		.unwrap();
+	let mut sound_handle = manager.add_sound(sound).unwrap();
+	let first_instance = grouped_sound_handle
+		.play(InstanceSettings::default())
+		.unwrap();
+	grouped_sound_handle
+		.play(InstanceSettings::default())
+		.unwrap();
+	grouped_sound_handle
+		.play(InstanceSettings::default())
+		.unwrap();
+	// sounds outside of the group aren't limited
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 3);
+	// instances that are stopping make room for new ones
+	manager
+		.stop_voice(first_instance.id(), StopInstanceSettings::new())
+		.unwrap();
+	backend.process();
+	grouped_sound_handle
+		.play(InstanceSettings::default())
+		.unwrap();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 4);
+}
+
+#[test]
+fn builds_group_sets_from_arrays_and_iterators() {
+	let (mut manager, _) = create_manager();
+	let group_a = manager.add_group(GroupSettings::new()).unwrap();
+	let group_b = manager.add_group(GroupSettings::new()).unwrap();
+	let group_c = manager.add_group(GroupSettings::new()).unwrap();
+	let from_array = GroupSet::from([&group_a, &group_b, &group_a]);
+	assert_eq!(from_array.len(), 2);
+	assert!(from_array.contains(&group_a) && from_array.contains(&group_b));
+	assert!(!from_array.contains(&group_c));
+	let from_iter: GroupSet = vec![group_b.id(), group_c.id()].into_iter().collect();
+	assert_eq!(
+		from_iter.iter().copied().collect::<Vec<_>>(),
+		vec![group_b.id(), group_c.id()]
+	);
+	let combined = from_array.add_iter(from_iter.iter().copied());
+	assert_eq!(combined.len(), 3);
+	assert!(GroupSet::new().is_empty());
+}
+
+#[test]
+fn applies_group_volume_and_muffling_to_instances() {
+	let (mut manager, mut backend) = AudioManager::new_headless(Default::default(), 48000);
+	let mut world = manager.add_group(GroupSettings::new().volume(0.5)).unwrap();
+	let mut ui_sound = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut world_sound = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			(0..48000)
+				.map(|i| Frame::from_mono(if i % 2 == 0 { 1.0 } else { -1.0 }))
+				.collect::<Vec<_>>(),
+			SoundSettings::new().groups(GroupSet::new().add(&world)),
+		))
+		.unwrap();
+	ui_sound.play(InstanceSettings::default()).unwrap();
+	let ui_frame = backend.process();
+	world_sound.play(InstanceSettings::default()).unwrap();
+	// the world sound plays at half volume
+	let world_frame = backend.process() - ui_frame;
+	assert!((world_frame.left.abs() - ui_frame.left / 2.0).abs() < 0.000001);
+	// muffling filters out the world sound's high frequencies,
+	// but leaves sounds outside of the group alone
+	world.set_muffle_amount(1.0).unwrap();
+	for _ in 0..100 {
+		backend.process();
+	}
+	let world_frame = backend.process() - ui_frame;
+	assert!(world_frame.left.abs() < ui_frame.left / 10.0);
+}
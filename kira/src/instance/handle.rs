@@ -1,27 +1,86 @@
 //! An interface for controlling instances of sounds and arrangements.
 
-use std::sync::Arc;
+use std::{
+	any::Any,
+	sync::{atomic::AtomicU32, Arc, Mutex},
+};
 
 use atomic::{Atomic, Ordering};
+use basedrop::Owned;
+use instant::Instant;
+use thiserror::Error;
 
 use crate::{
 	command::{
 		producer::{CommandError, CommandProducer},
 		InstanceCommand,
 	},
+	mixer::effect::{Effect, EffectId, EffectSettings},
+	playable::PlayableId,
 	Value,
 };
 
 use super::{
-	InstanceId, InstanceState, PauseInstanceSettings, ResumeInstanceSettings, StopInstanceSettings,
+	InstanceId, InstanceState, PauseInstanceSettings, PositionReport, PublicPosition,
+	ResumeInstanceSettings, StopInstanceSettings, SwapPlayableSettings,
 };
 
+/// Something that can go wrong when using an [`InstanceHandle`]
+/// to add an effect to an instance.
+#[derive(Debug, Error)]
+pub enum AddInstanceEffectError {
+	/// The maximum effect limit for this instance has been reached.
+	#[error(
+		"Cannot add an effect because the max number of effects for this instance has been reached"
+	)]
+	EffectLimitReached,
+	/// The audio manager has not been started yet, so the
+	/// effect cannot be initialized with a sample rate.
+	#[error("Cannot add an effect before the audio manager has been started")]
+	AudioManagerNotStarted,
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// Something that can go wrong when using an [`InstanceHandle`]
+/// to remove an effect from an instance.
+#[derive(Debug, Error)]
+pub enum RemoveInstanceEffectError {
+	/// No effect with the specified ID exists on this instance.
+	#[error("No effect with the specified ID exists on this instance")]
+	NoEffectWithId(EffectId),
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// The sample rate and resource collector an instance
+/// handle needs to add effects to the instance.
+#[derive(Clone)]
+pub(crate) struct InstanceEffectContext {
+	pub sample_rate: Arc<AtomicU32>,
+	pub resource_collector_handle: basedrop::Handle,
+}
+
+impl std::fmt::Debug for InstanceEffectContext {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("InstanceEffectContext")
+			.field("sample_rate", &self.sample_rate)
+			.finish()
+	}
+}
+
 #[derive(Debug, Clone)]
 /// Allows you to control an instance of a sound or arrangement.
 pub struct InstanceHandle {
 	id: InstanceId,
 	state: Arc<Atomic<InstanceState>>,
-	position: Arc<Atomic<f64>>,
+	position: Arc<PublicPosition>,
+	last_position_report: Option<(PositionReport, Instant)>,
+	num_effects: usize,
+	active_effects: Arc<Mutex<Vec<EffectId>>>,
+	effect_context: InstanceEffectContext,
 	command_producer: CommandProducer,
 }
 
@@ -29,13 +88,19 @@ impl InstanceHandle {
 	pub(crate) fn new(
 		id: InstanceId,
 		state: Arc<Atomic<InstanceState>>,
-		position: Arc<Atomic<f64>>,
+		position: Arc<PublicPosition>,
+		num_effects: usize,
+		effect_context: InstanceEffectContext,
 		command_producer: CommandProducer,
 	) -> Self {
 		Self {
 			id,
 			state,
 			position,
+			last_position_report: None,
+			num_effects,
+			active_effects: Arc::new(Mutex::new(Vec::with_capacity(num_effects))),
+			effect_context,
 			command_producer,
 		}
 	}
@@ -50,9 +115,55 @@ impl InstanceHandle {
 		self.state.load(Ordering::Relaxed)
 	}
 
+	/// Returns `true` if the instance is still on the
+	/// audio thread.
+	///
+	/// Instances stop existing when they're stopped, when they
+	/// finish playing, or when they're removed to make room for
+	/// new instances. An instance that couldn't start (for
+	/// example, because its sound was cooling down) also stops
+	/// existing once the audio thread receives it. Commands sent
+	/// to an instance that doesn't exist are reported as
+	/// [errors](crate::manager::AudioManager::pop_audio_thread_error).
+	pub fn exists(&self) -> bool {
+		self.state() != InstanceState::Stopped
+	}
+
 	/// Returns the playback position of the instance.
+	///
+	/// This is the position as of the last time the audio
+	/// thread reported it, which depends on the instance's
+	/// `position_report_interval` setting.
 	pub fn position(&self) -> f64 {
-		self.position.load(Ordering::Relaxed)
+		self.position.position()
+	}
+
+	/// Returns the last playback position and playback rate
+	/// reported by the audio thread.
+	pub fn position_report(&self) -> PositionReport {
+		self.position.load()
+	}
+
+	/// Returns an estimate of the current playback position
+	/// of the instance.
+	///
+	/// The position is extrapolated from the last reported
+	/// position using the reported playback rate and the time
+	/// that has passed since the report was first seen by this
+	/// handle. This is useful for smoothly animating things in
+	/// time with an instance, even if the position is reported
+	/// infrequently.
+	pub fn interpolated_position(&mut self) -> f64 {
+		let report = self.position_report();
+		let now = Instant::now();
+		let report_time = match self.last_position_report {
+			Some((last_report, time)) if last_report == report => time,
+			_ => {
+				self.last_position_report = Some((report, now));
+				now
+			}
+		};
+		report.position + report.playback_rate * (now - report_time).as_secs_f64()
 	}
 
 	/// Sets the volume of the instance.
@@ -76,18 +187,73 @@ impl InstanceHandle {
 			.push(InstanceCommand::SetInstancePanning(self.id, panning.into()).into())
 	}
 
+	/// Sets the stereo width of the instance.
+	///
+	/// 0 makes the instance mono, 1 leaves it unchanged, and
+	/// values above 1 widen it.
+	pub fn set_width(&mut self, width: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(InstanceCommand::SetInstanceWidth(self.id, width.into()).into())
+	}
+
 	/// Offsets the playback position of the instance by the specified amount (in seconds).
+	///
+	/// If the new position is out of range, it wraps around the
+	/// instance's loop region, or if the instance doesn't loop,
+	/// the instance's [`SeekBehavior`](super::SeekBehavior) is used.
 	pub fn seek(&mut self, offset: f64) -> Result<(), CommandError> {
 		self.command_producer
 			.push(InstanceCommand::SeekInstance(self.id, offset).into())
 	}
 
 	/// Sets the playback position of the instance to the specified time (in seconds).
+	///
+	/// Out of range positions are handled the same way as
+	/// they are by [`seek`](Self::seek).
 	pub fn seek_to(&mut self, position: f64) -> Result<(), CommandError> {
 		self.command_producer
 			.push(InstanceCommand::SeekInstanceTo(self.id, position).into())
 	}
 
+	/// Switches the instance to a different sound or arrangement
+	/// without stopping it.
+	///
+	/// The instance keeps its state, volume, playback rate,
+	/// panning, and track. If the instance is looping, it will
+	/// loop using the new sound or arrangement's loop point.
+	/// This is useful for switching between different mixes of
+	/// the same piece of music without a gap:
+	///
+	/// ```no_run
+	/// # use kira::{
+	/// # 	instance::{InstanceSettings, SwapPlayableSettings, SyncMode},
+	/// # 	manager::AudioManager,
+	/// # 	parameter::tween::Tween,
+	/// # 	sound::Sound,
+	/// # };
+	/// # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+	/// # let mut calm = audio_manager.add_sound(Sound::from_file("calm.ogg", Default::default())?)?;
+	/// # let intense = audio_manager.add_sound(Sound::from_file("intense.ogg", Default::default())?)?;
+	/// let mut instance = calm.play(InstanceSettings::new().loop_start(0.0))?;
+	/// // later...
+	/// instance.swap_playable(
+	/// 	&intense,
+	/// 	SwapPlayableSettings::new()
+	/// 		.sync_mode(SyncMode::Phase)
+	/// 		.fade_tween(Tween::linear(2.0)),
+	/// )?;
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	pub fn swap_playable(
+		&mut self,
+		playable: impl Into<PlayableId>,
+		settings: impl Into<SwapPlayableSettings>,
+	) -> Result<(), CommandError> {
+		self.command_producer.push(
+			InstanceCommand::SwapInstancePlayable(self.id, playable.into(), settings.into()).into(),
+		)
+	}
+
 	/// Pauses the instance.
 	pub fn pause(&mut self, settings: PauseInstanceSettings) -> Result<(), CommandError> {
 		self.command_producer
@@ -105,4 +271,169 @@ impl InstanceHandle {
 		self.command_producer
 			.push(InstanceCommand::StopInstance(self.id, settings).into())
 	}
+
+	/// Adds an effect to the instance.
+	///
+	/// The instance's audio is run through its effects before
+	/// it's sent to its track, so an effect can be applied to a
+	/// single instance without setting up a sub-track for it.
+	/// The instance can hold up to
+	/// [`num_effects`](super::InstanceSettings::num_effects)
+	/// effects. Any tail an effect produces (like the echoes
+	/// of a delay) is cut off when the instance stops.
+	///
+	/// ```no_run
+	/// # use kira::{
+	/// # 	instance::InstanceSettings,
+	/// # 	manager::AudioManager,
+	/// # 	mixer::effect::{distortion::{Distortion, DistortionSettings}, EffectSettings},
+	/// # 	sound::Sound,
+	/// # };
+	/// # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+	/// # let mut sound = audio_manager.add_sound(Sound::from_file("radio.ogg", Default::default())?)?;
+	/// let mut instance = sound.play(InstanceSettings::new().num_effects(1))?;
+	/// instance.add_effect(
+	/// 	Distortion::new(DistortionSettings::new().drive(4.0)),
+	/// 	EffectSettings::new(),
+	/// )?;
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	pub fn add_effect(
+		&mut self,
+		mut effect: impl Effect + 'static,
+		settings: EffectSettings,
+	) -> Result<InstanceEffectHandle, AddInstanceEffectError> {
+		let mut active_effects = self
+			.active_effects
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner());
+		if active_effects.len() >= self.num_effects {
+			return Err(AddInstanceEffectError::EffectLimitReached);
+		}
+		let sample_rate = self.effect_context.sample_rate.load(Ordering::Relaxed);
+		if sample_rate == 0 {
+			return Err(AddInstanceEffectError::AudioManagerNotStarted);
+		}
+		let effect_id = settings.id.unwrap_or(EffectId::new());
+		effect.init(sample_rate);
+		self.command_producer.push(
+			InstanceCommand::AddEffect(
+				self.id,
+				effect_id,
+				Owned::new(
+					&self.effect_context.resource_collector_handle,
+					Box::new(effect),
+				),
+				settings,
+			)
+			.into(),
+		)?;
+		active_effects.push(effect_id);
+		Ok(InstanceEffectHandle {
+			id: effect_id,
+			instance_id: self.id,
+			resource_collector_handle: self.effect_context.resource_collector_handle.clone(),
+			command_producer: self.command_producer.clone(),
+		})
+	}
+
+	/// Removes an effect from the instance.
+	pub fn remove_effect(
+		&mut self,
+		id: impl Into<EffectId>,
+	) -> Result<(), RemoveInstanceEffectError> {
+		let id = id.into();
+		let mut active_effects = self
+			.active_effects
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner());
+		let index = active_effects
+			.iter()
+			.position(|effect_id| *effect_id == id)
+			.ok_or(RemoveInstanceEffectError::NoEffectWithId(id))?;
+		self.command_producer
+			.push(InstanceCommand::RemoveEffect(self.id, id).into())?;
+		active_effects.remove(index);
+		Ok(())
+	}
+}
+
+#[derive(Clone)]
+/// Allows you to control an effect that was added to an instance.
+pub struct InstanceEffectHandle {
+	id: EffectId,
+	instance_id: InstanceId,
+	resource_collector_handle: basedrop::Handle,
+	command_producer: CommandProducer,
+}
+
+impl InstanceEffectHandle {
+	/// Returns the ID of the effect.
+	pub fn id(&self) -> EffectId {
+		self.id
+	}
+
+	/// Returns the ID of the instance that contains this effect.
+	pub fn instance_id(&self) -> InstanceId {
+		self.instance_id
+	}
+
+	/// Sets whether the effect is currently enabled.
+	pub fn set_enabled(&mut self, enabled: bool) -> Result<(), CommandError> {
+		self.command_producer
+			.push(InstanceCommand::SetEffectEnabled(self.instance_id, self.id, enabled).into())
+	}
+
+	/// Sets the balance between dry (unaffected) signal and wet
+	/// (affected) signal to output. 0.0 is fully dry, 1.0 is fully wet.
+	pub fn set_mix(&mut self, mix: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(InstanceCommand::SetEffectMix(self.instance_id, self.id, mix.into()).into())
+	}
+
+	/// Sets one of the effect's [`EffectValues`](crate::mixer::effect::values::EffectValues).
+	///
+	/// This does nothing if the effect doesn't have a set of values
+	/// or if there's no value at the given index.
+	pub fn set_value(
+		&mut self,
+		index: usize,
+		value: impl Into<Value<f64>>,
+	) -> Result<(), CommandError> {
+		self.command_producer.push(
+			InstanceCommand::SetEffectValue(self.instance_id, self.id, index, value.into()).into(),
+		)
+	}
+
+	/// Sends a message to the effect.
+	///
+	/// The effect receives the message in its
+	/// [`on_message`](crate::mixer::effect::Effect::on_message) implementation.
+	pub fn send_message(&mut self, message: impl Any + Send) -> Result<(), CommandError> {
+		let message: Box<dyn Any + Send> = Box::new(message);
+		self.command_producer.push(
+			InstanceCommand::SendEffectMessage(
+				self.instance_id,
+				self.id,
+				Owned::new(&self.resource_collector_handle, message),
+			)
+			.into(),
+		)
+	}
+}
+
+impl std::fmt::Debug for InstanceEffectHandle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("InstanceEffectHandle")
+			.field("id", &self.id)
+			.field("instance_id", &self.instance_id)
+			.field("command_producer", &self.command_producer)
+			.finish()
+	}
+}
+
+impl From<&InstanceEffectHandle> for EffectId {
+	fn from(handle: &InstanceEffectHandle) -> Self {
+		handle.id()
+	}
 }
@@ -13,7 +13,7 @@
 //! #
 //! # use kira::{manager::AudioManager, instance::InstanceSettings, sound::Sound};
 //! #
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let mut sound = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! let instance_handle = sound.play(InstanceSettings::new().playback_rate(0.5))?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
@@ -26,7 +26,7 @@
 //! #
 //! # use kira::{manager::AudioManager, sound::Sound, parameter::tween::Tween, instance::StopInstanceSettings};
 //! #
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let mut sound = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! # let mut instance_handle = sound.play(Default::default())?;
 //! instance_handle.stop(StopInstanceSettings::new().fade_tween(Some(2.0.into())))?;
@@ -60,23 +60,43 @@
 
 pub mod handle;
 mod settings;
+#[cfg(test)]
+mod tests;
 
 use atomic::Atomic;
 use handle::InstanceHandle;
 pub use settings::*;
 
+use basedrop::Owned;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use uuid::Uuid;
 
 use crate::{
-	frame::Frame,
-	mixer::TrackIndex,
-	parameter::{Parameter, Parameters},
+	frame::{Frame, Sample},
+	group::{groups::Groups, GroupId, GroupMuffle, InstanceGroups},
+	manager::snapshot::InstanceSnapshot,
+	metronome::{MetronomeId, Metronomes},
+	mixer::{
+		effect::{
+			filter::{Filter, FilterSettings},
+			muffle::muffle_frame,
+			Effect, EffectId, EffectSettings,
+		},
+		effect_slot::EffectSlot,
+		TrackIndex,
+	},
+	parameter::{tween::Tween, Parameter, Parameters},
 	playable::{PlayableId, Playables},
 	sequence::SequenceInstanceId,
+	static_container::index_map::StaticIndexMap,
 	value::CachedValue,
 	value::Value,
+	PanningLaw, StartTime,
+};
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Arc,
 };
-use std::sync::{atomic::Ordering, Arc};
 
 /// A unique identifier for an instance.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -106,6 +126,9 @@ impl From<&InstanceHandle> for InstanceId {
 /// The playback state of the instance.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum InstanceState {
+	/// The instance is waiting for its
+	/// [start time](StartTime) to be reached.
+	Scheduled,
 	/// The instance is playing.
 	Playing,
 	/// The instance is paused.
@@ -126,22 +149,108 @@ pub enum InstanceState {
 	Stopping,
 }
 
+/// The playback position of an instance as last reported
+/// by the audio thread.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PositionReport {
+	/// The playback position of the instance (in seconds).
+	pub position: f64,
+	/// How fast the playback position was moving
+	/// (in seconds per second) when it was reported.
+	///
+	/// This is negative if the instance is playing backwards
+	/// and zero if the instance is paused or stopped.
+	pub playback_rate: f64,
+}
+
+/// The last [`PositionReport`] of an instance, shared between
+/// the audio thread and instance handles.
+///
+/// A `PositionReport` is too large to be stored in a single
+/// lock-free atomic, so the position and playback rate are
+/// stored separately as the bits of each `f64`. A handle may
+/// read the position from one report and the playback rate
+/// from the next, which is harmless since reports are only
+/// ever a snapshot.
+#[derive(Debug)]
+pub(crate) struct PublicPosition {
+	position: AtomicU64,
+	playback_rate: AtomicU64,
+}
+
+impl PublicPosition {
+	fn new(report: PositionReport) -> Self {
+		Self {
+			position: AtomicU64::new(report.position.to_bits()),
+			playback_rate: AtomicU64::new(report.playback_rate.to_bits()),
+		}
+	}
+
+	fn store(&self, report: PositionReport) {
+		self.position
+			.store(report.position.to_bits(), Ordering::Relaxed);
+		self.playback_rate
+			.store(report.playback_rate.to_bits(), Ordering::Relaxed);
+	}
+
+	pub fn position(&self) -> f64 {
+		f64::from_bits(self.position.load(Ordering::Relaxed))
+	}
+
+	pub fn load(&self) -> PositionReport {
+		PositionReport {
+			position: self.position(),
+			playback_rate: f64::from_bits(self.playback_rate.load(Ordering::Relaxed)),
+		}
+	}
+}
+
+/// The sound or arrangement an instance is crossfading
+/// away from after switching to a different one.
 #[derive(Debug, Clone)]
+struct SwappedPlayable {
+	playable_id: PlayableId,
+	region_start: f64,
+	duration: f64,
+	loop_start: Option<f64>,
+	position: f64,
+}
+
 pub(crate) struct Instance {
 	playable_id: PlayableId,
+	region_start: f64,
 	duration: f64,
 	sequence_id: Option<SequenceInstanceId>,
 	track_index: TrackIndex,
+	groups: InstanceGroups,
 	volume: CachedValue<f64>,
 	playback_rate: CachedValue<f64>,
 	panning: CachedValue<f64>,
+	panning_law: PanningLaw,
+	width: CachedValue<f64>,
+	tempo_sync: Option<MetronomeId>,
+	tempo_ratio: f64,
 	reverse: bool,
 	loop_start: Option<f64>,
+	seek_behavior: SeekBehavior,
 	state: InstanceState,
 	public_state: Arc<Atomic<InstanceState>>,
 	position: f64,
-	public_position: Arc<Atomic<f64>>,
+	public_position: Arc<PublicPosition>,
+	position_report_interval: f64,
+	position_report_timer: f64,
 	fade_volume: Parameter,
+	fade_out_tween: Option<Tween>,
+	start_time: StartTime,
+	swapped_from: Option<SwappedPlayable>,
+	swap_fade: Parameter,
+	// the fade used for stops, pauses, resumes, and seeks
+	// that don't have a fade of their own
+	declick_tween: Option<Tween>,
+	// filters the instance's audio when one of its
+	// groups muffles it
+	group_filter: Filter,
+	effect_slots: StaticIndexMap<EffectId, EffectSlot>,
 }
 
 impl Instance {
@@ -158,24 +267,202 @@ impl Instance {
 		} else {
 			fade_volume = Parameter::new(1.0);
 		}
+		let (volume, playback_rate, panning, width) = match settings.random_seed {
+			Some(seed) => Self::resolve_values(&settings, &mut StdRng::seed_from_u64(seed)),
+			None => Self::resolve_values(&settings, &mut thread_rng()),
+		};
+		let state = match settings.start_time {
+			StartTime::Immediate => InstanceState::Playing,
+			_ => InstanceState::Scheduled,
+		};
+		let (region_start, duration) = match settings.region {
+			Some(region) => (region.start, region.duration()),
+			None => (0.0, duration),
+		};
 		Self {
 			playable_id: playable,
+			region_start,
 			duration,
 			sequence_id,
 			track_index: settings.track,
-			volume: CachedValue::new(settings.volume, 1.0),
-			playback_rate: CachedValue::new(settings.playback_rate, 1.0),
-			panning: CachedValue::new(settings.panning, 0.5).with_valid_range(0.0..1.0),
+			groups: settings.groups,
+			volume,
+			playback_rate,
+			panning,
+			panning_law: settings.panning_law,
+			width,
+			tempo_sync: settings.tempo_sync,
+			tempo_ratio: 1.0,
 			reverse: settings.reverse,
 			loop_start: settings.loop_start,
-			state: InstanceState::Playing,
-			public_state: Arc::new(Atomic::new(InstanceState::Playing)),
+			seek_behavior: settings.seek_behavior,
+			state,
+			public_state: Arc::new(Atomic::new(state)),
 			position: settings.start_position,
-			public_position: Arc::new(Atomic::new(settings.start_position)),
+			public_position: Arc::new(PublicPosition::new(PositionReport {
+				position: settings.start_position,
+				playback_rate: 0.0,
+			})),
+			position_report_interval: settings.position_report_interval,
+			position_report_timer: 0.0,
 			fade_volume,
+			fade_out_tween: settings.fade_out_tween,
+			start_time: settings.start_time,
+			swapped_from: None,
+			swap_fade: Parameter::new(1.0),
+			declick_tween: None,
+			group_filter: Filter::new(FilterSettings::new().cutoff(20000.0)),
+			effect_slots: StaticIndexMap::new(settings.num_effects),
 		}
 	}
 
+	pub fn add_effect(
+		&mut self,
+		id: EffectId,
+		effect: Owned<Box<dyn Effect>>,
+		settings: EffectSettings,
+	) {
+		let effect_slot = EffectSlot::new(effect, settings);
+		self.effect_slots.try_insert(id, effect_slot).ok();
+	}
+
+	pub fn effect_mut(&mut self, id: EffectId) -> Option<&mut EffectSlot> {
+		self.effect_slots.get_mut(&id)
+	}
+
+	pub fn remove_effect(&mut self, id: EffectId) -> Option<EffectSlot> {
+		self.effect_slots.shift_remove(&id)
+	}
+
+	/// Runs a frame of the instance's audio through
+	/// its effects.
+	pub fn process_effects(&mut self, dt: f64, mut input: Frame, parameters: &Parameters) -> Frame {
+		for (_, effect_slot) in &mut self.effect_slots {
+			input = effect_slot.process(dt, input, parameters);
+		}
+		input
+	}
+
+	/// Applies the volume and muffling of the instance's
+	/// groups to a frame of its audio.
+	pub fn apply_group_effects(
+		&mut self,
+		dt: f64,
+		input: Frame,
+		volume: f64,
+		muffle: Option<GroupMuffle>,
+		parameters: &Parameters,
+	) -> Frame {
+		let output = match muffle {
+			Some(muffle) => muffle_frame(
+				&mut self.group_filter,
+				dt,
+				input,
+				muffle.amount,
+				muffle.cutoff,
+				muffle.volume,
+				parameters,
+			),
+			None => input,
+		};
+		output * volume as Sample
+	}
+
+	/// Creates the cached volume, playback rate, panning, and
+	/// width values, picking any random values with the given
+	/// random number generator.
+	fn resolve_values(
+		settings: &InternalInstanceSettings,
+		rng: &mut impl Rng,
+	) -> (
+		CachedValue<f64>,
+		CachedValue<f64>,
+		CachedValue<f64>,
+		CachedValue<f64>,
+	) {
+		(
+			CachedValue::new_with_rng(settings.volume, 1.0, rng),
+			CachedValue::new_with_rng(settings.playback_rate, 1.0, rng),
+			CachedValue::new_with_rng(settings.panning, 0.5, rng).with_valid_range(0.0..1.0),
+			CachedValue::new_with_rng(settings.width, 1.0, rng).with_min(0.0),
+		)
+	}
+
+	/// Creates an instance that picks up where an instance
+	/// from a snapshot left off.
+	pub fn from_snapshot(snapshot: &InstanceSnapshot) -> Self {
+		let mut instance = Self::new(
+			snapshot.playable,
+			snapshot.duration,
+			snapshot.sequence,
+			InternalInstanceSettings {
+				volume: snapshot.volume,
+				playback_rate: snapshot.playback_rate,
+				panning: snapshot.panning,
+				panning_law: snapshot.panning_law,
+				width: snapshot.width,
+				tempo_sync: snapshot.tempo_sync,
+				start_position: snapshot.position,
+				region: Some(PlaybackRegion::new(
+					snapshot.region_start,
+					snapshot.region_start + snapshot.duration,
+				)),
+				reverse: snapshot.reverse,
+				fade_in_tween: None,
+				fade_out_tween: snapshot.fade_out_tween,
+				loop_start: snapshot.loop_start,
+				seek_behavior: snapshot.seek_behavior,
+				track: snapshot.track,
+				groups: snapshot.groups,
+				position_report_interval: 0.0,
+				random_seed: None,
+				start_time: StartTime::Immediate,
+				num_effects: 0,
+			},
+		);
+		if snapshot.paused {
+			instance.set_state(InstanceState::Paused(snapshot.position));
+		}
+		instance
+	}
+
+	/// Records the state of the instance, or returns `None`
+	/// if the instance isn't playing or paused.
+	pub fn snapshot(&self, id: InstanceId) -> Option<InstanceSnapshot> {
+		let paused = match self.state {
+			InstanceState::Playing => false,
+			InstanceState::Paused(_) | InstanceState::Pausing(_) => true,
+			_ => return None,
+		};
+		// random values keep the value that was picked, so the
+		// instance sounds the same when it's restored
+		let setting = |value: &CachedValue<f64>| match value.setting() {
+			Value::Random(..) => Value::Fixed(value.value()),
+			setting => setting,
+		};
+		Some(InstanceSnapshot {
+			id,
+			playable: self.playable_id,
+			sequence: self.sequence_id,
+			region_start: self.region_start,
+			duration: self.duration,
+			track: self.track_index,
+			groups: self.groups,
+			volume: setting(&self.volume),
+			playback_rate: setting(&self.playback_rate),
+			panning: setting(&self.panning),
+			panning_law: self.panning_law,
+			width: setting(&self.width),
+			tempo_sync: self.tempo_sync,
+			reverse: self.reverse,
+			loop_start: self.loop_start,
+			seek_behavior: self.seek_behavior,
+			fade_out_tween: self.fade_out_tween,
+			position: self.position,
+			paused,
+		})
+	}
+
 	pub fn playable_id(&self) -> PlayableId {
 		self.playable_id
 	}
@@ -184,6 +471,19 @@ impl Instance {
 		self.track_index
 	}
 
+	pub fn groups(&self) -> InstanceGroups {
+		self.groups
+	}
+
+	/// Returns `true` if the instance was added to the group
+	/// (or one of its descendants) when it was played.
+	///
+	/// This doesn't include the groups of the instance's sound
+	/// or arrangement.
+	pub fn is_in_group(&self, id: GroupId, all_groups: &Groups) -> bool {
+		self.groups.has_ancestor(id, all_groups)
+	}
+
 	pub fn sequence_id(&self) -> Option<SequenceInstanceId> {
 		self.sequence_id
 	}
@@ -192,16 +492,25 @@ impl Instance {
 		self.volume.value() * self.fade_volume.value()
 	}
 
+	pub fn position(&self) -> f64 {
+		self.position
+	}
+
+	pub fn state(&self) -> InstanceState {
+		self.state
+	}
+
 	pub fn public_state(&self) -> Arc<Atomic<InstanceState>> {
 		self.public_state.clone()
 	}
 
-	pub fn public_position(&self) -> Arc<Atomic<f64>> {
+	pub fn public_position(&self) -> Arc<PublicPosition> {
 		self.public_position.clone()
 	}
 
 	pub fn playing(&self) -> bool {
 		match self.state {
+			InstanceState::Scheduled => false,
 			InstanceState::Playing => true,
 			InstanceState::Paused(_) => false,
 			InstanceState::Stopped => false,
@@ -226,26 +535,180 @@ impl Instance {
 		self.panning.set(panning);
 	}
 
+	pub fn set_width(&mut self, width: Value<f64>) {
+		self.width.set(width);
+	}
+
+	/// Sets the fade used for stops, pauses, resumes, and
+	/// seeks that don't have a fade of their own.
+	pub fn set_declick_tween(&mut self, declick_tween: Option<Tween>) {
+		self.declick_tween = declick_tween;
+	}
+
 	pub fn seek(&mut self, offset: f64) {
-		self.position += offset;
+		self.seek_to(self.position + offset);
 	}
 
+	/// Jumps to a new playback position, crossfading from the
+	/// old position so the jump doesn't click.
 	pub fn seek_to(&mut self, position: f64) {
+		let position = match self.seek_target(position) {
+			Some(position) => position,
+			None => {
+				self.stop(StopInstanceSettings::new());
+				return;
+			}
+		};
+		// a crossfade to a different sound or arrangement
+		// is left alone and the seek happens immediately
+		if let (Some(tween), None, true) = (self.declick_tween, &self.swapped_from, self.playing())
+		{
+			self.swapped_from = Some(SwappedPlayable {
+				playable_id: self.playable_id,
+				region_start: self.region_start,
+				duration: self.duration,
+				loop_start: self.loop_start,
+				position: self.position,
+			});
+			self.swap_fade = Parameter::new(0.0);
+			self.swap_fade.set(1.0, Some(tween));
+		}
+		self.set_position(position);
+	}
+
+	/// Returns the position a seek to the given position should
+	/// actually jump to, or `None` if the instance should stop.
+	fn seek_target(&self, position: f64) -> Option<f64> {
+		if (0.0..=self.duration).contains(&position) {
+			return Some(position);
+		}
+		if let Some(loop_start) = self.loop_start {
+			let loop_length = self.duration - loop_start;
+			if loop_length > 0.0 {
+				return Some(loop_start + (position - loop_start).rem_euclid(loop_length));
+			}
+		}
+		match self.seek_behavior {
+			SeekBehavior::Snap => Some(position.clamp(0.0, self.duration)),
+			SeekBehavior::Stop => None,
+		}
+	}
+
+	fn set_position(&mut self, position: f64) {
 		self.position = position;
+		self.position_report_timer = 0.0;
+	}
+
+	/// Switches the instance to a different sound or arrangement,
+	/// keeping its playback state and settings.
+	pub fn swap_playable(
+		&mut self,
+		playable_id: PlayableId,
+		duration: f64,
+		default_loop_start: Option<f64>,
+		settings: SwapPlayableSettings,
+	) {
+		// a looping instance keeps looping, preferably from the
+		// new playable's own loop point
+		let loop_start = self
+			.loop_start
+			.map(|loop_start| default_loop_start.unwrap_or(loop_start))
+			.map(|loop_start| {
+				if loop_start < duration {
+					loop_start
+				} else {
+					0.0
+				}
+			});
+		let mut position = match settings.sync_mode {
+			SyncMode::Position => self.position,
+			SyncMode::Phase => match (self.loop_start, loop_start) {
+				(Some(old_loop_start), Some(new_loop_start))
+					if self.position >= old_loop_start && self.duration > old_loop_start =>
+				{
+					let phase = (self.position - old_loop_start) / (self.duration - old_loop_start);
+					new_loop_start + phase * (duration - new_loop_start)
+				}
+				_ if self.duration > 0.0 => self.position / self.duration * duration,
+				_ => 0.0,
+			},
+		};
+		if let Some(loop_start) = loop_start {
+			wrap_position(
+				&mut position,
+				duration,
+				loop_start,
+				self.effective_playback_rate(),
+			);
+		}
+		if let Some(tween) = settings.fade_tween {
+			self.swapped_from = Some(SwappedPlayable {
+				playable_id: self.playable_id,
+				region_start: self.region_start,
+				duration: self.duration,
+				loop_start: self.loop_start,
+				position: self.position,
+			});
+			self.swap_fade = Parameter::new(0.0);
+			self.swap_fade.set(1.0, Some(tween));
+		} else {
+			self.swapped_from = None;
+			self.swap_fade = Parameter::new(1.0);
+		}
+		self.playable_id = playable_id;
+		self.region_start = 0.0;
+		self.duration = duration;
+		self.loop_start = loop_start;
+		self.set_position(position);
+	}
+
+	/// Marks an instance that's being dropped without
+	/// finishing as stopped, so its handles know it's gone.
+	pub(crate) fn discard(&mut self) {
+		self.set_state(InstanceState::Stopped);
 	}
 
 	fn set_state(&mut self, state: InstanceState) {
 		self.state = state;
 		self.public_state.store(state, Ordering::Relaxed);
+		// report the position right away so the main thread
+		// doesn't keep extrapolating the old playback rate
+		self.position_report_timer = 0.0;
+	}
+
+	fn effective_playback_rate(&self) -> f64 {
+		if !self.playing() {
+			return 0.0;
+		}
+		let mut playback_rate = self.playback_rate.value() * self.tempo_ratio;
+		if self.reverse {
+			playback_rate *= -1.0;
+		}
+		playback_rate
+	}
+
+	fn report_position(&mut self, dt: f64) {
+		self.position_report_timer -= dt;
+		if self.position_report_timer <= 0.0 {
+			self.position_report_timer += self.position_report_interval;
+			if self.position_report_timer < 0.0 {
+				self.position_report_timer = 0.0;
+			}
+			self.public_position.store(PositionReport {
+				position: self.position,
+				playback_rate: self.effective_playback_rate(),
+			});
+		}
 	}
 
 	pub fn pause(&mut self, settings: PauseInstanceSettings) {
-		self.set_state(if settings.fade_tween.is_some() {
+		let fade_tween = settings.fade_tween.or(self.declick_tween);
+		self.set_state(if fade_tween.is_some() {
 			InstanceState::Pausing(self.position)
 		} else {
 			InstanceState::Paused(self.position)
 		});
-		self.fade_volume.set(0.0, settings.fade_tween);
+		self.fade_volume.set(0.0, fade_tween);
 	}
 
 	pub fn resume(&mut self, settings: ResumeInstanceSettings) {
@@ -253,48 +716,87 @@ impl Instance {
 			InstanceState::Paused(position) | InstanceState::Pausing(position) => {
 				self.set_state(InstanceState::Playing);
 				if settings.rewind_to_pause_position {
-					self.seek_to(position);
+					self.set_position(position);
 				}
-				self.fade_volume.set(1.0, settings.fade_tween);
+				self.fade_volume
+					.set(1.0, settings.fade_tween.or(self.declick_tween));
 			}
 			_ => {}
 		}
 	}
 
 	pub fn stop(&mut self, settings: StopInstanceSettings) {
-		self.set_state(if settings.fade_tween.is_some() {
+		let fade_tween = settings
+			.fade_tween
+			.or(self.fade_out_tween.or(self.declick_tween));
+		self.set_state(if fade_tween.is_some() {
 			InstanceState::Stopping
 		} else {
 			InstanceState::Stopped
 		});
-		self.fade_volume.set(0.0, settings.fade_tween);
+		self.fade_volume.set(0.0, fade_tween);
+	}
+
+	/// Updates how much faster or slower than normal the
+	/// instance plays to keep up with the tempo of the
+	/// metronome it's synced to.
+	pub fn update_tempo_ratio(&mut self, playables: &Playables, metronomes: &Metronomes) {
+		let metronome_tempo = match self.tempo_sync.and_then(|id| metronomes.get(id)) {
+			Some(metronome) => metronome.tempo(),
+			None => return,
+		};
+		let native_tempo = playables
+			.playable(self.playable_id)
+			.and_then(|playable| playable.native_tempo());
+		self.tempo_ratio = match native_tempo {
+			Some(native_tempo) if native_tempo.0 > 0.0 => metronome_tempo.0 / native_tempo.0,
+			_ => 1.0,
+		};
+	}
+
+	/// Starts playing the instance if it's scheduled and its
+	/// start time has been reached.
+	pub fn check_start_time(&mut self, clock_time: f64, metronomes: &Metronomes) {
+		if self.state != InstanceState::Scheduled {
+			return;
+		}
+		if self.start_time.has_passed(clock_time, metronomes) {
+			self.set_state(InstanceState::Playing);
+		}
 	}
 
 	pub fn update(&mut self, dt: f64, parameters: &Parameters) {
+		// scheduled instances shouldn't start fading in
+		// before they start playing
+		if self.state == InstanceState::Scheduled {
+			return;
+		}
 		if self.playing() {
 			self.volume.update(parameters);
 			self.playback_rate.update(parameters);
 			self.panning.update(parameters);
-			let mut playback_rate = self.playback_rate.value();
-			if self.reverse {
-				playback_rate *= -1.0;
-			}
+			self.width.update(parameters);
+			let playback_rate = self.effective_playback_rate();
 			self.position += playback_rate * dt;
-			if playback_rate < 0.0 {
-				if let Some(loop_start) = self.loop_start {
-					while self.position < loop_start {
-						self.position += self.duration - loop_start;
-					}
-				} else if self.position < 0.0 {
-					self.set_state(InstanceState::Stopped);
+			if let Some(loop_start) = self.loop_start {
+				wrap_position(&mut self.position, self.duration, loop_start, playback_rate);
+			} else if (playback_rate < 0.0 && self.position < 0.0)
+				|| (playback_rate >= 0.0 && self.position > self.duration)
+			{
+				self.set_state(InstanceState::Stopped);
+			}
+			if let Some(swapped_from) = &mut self.swapped_from {
+				swapped_from.position += playback_rate * dt;
+				if let Some(loop_start) = swapped_from.loop_start {
+					wrap_position(
+						&mut swapped_from.position,
+						swapped_from.duration,
+						loop_start,
+						playback_rate,
+					);
 				}
-			} else {
-				if let Some(loop_start) = self.loop_start {
-					while self.position > self.duration {
-						self.position -= self.duration - loop_start;
-					}
-				} else if self.position > self.duration {
-					self.set_state(InstanceState::Stopped);
+				if self.swap_fade.update(dt) {
+					self.swapped_from = None;
 				}
 			}
 		}
@@ -310,14 +812,44 @@ impl Instance {
 				_ => {}
 			}
 		}
-		self.public_position.store(self.position, Ordering::Relaxed);
+		self.report_position(dt);
 	}
 
 	pub fn get_sample(&self, playables: &Playables) -> Frame {
 		let mut out = playables
-			.frame_at_position(self.playable_id, self.position)
+			.frame_at_position(self.playable_id, self.region_start + self.position)
 			.unwrap_or(Frame::from_mono(0.0));
-		out = out.panned(self.panning.value() as f32);
-		out * (self.effective_volume() as f32)
+		if let Some(swapped_from) = &self.swapped_from {
+			let swap_fade = self.swap_fade.value() as Sample;
+			let old = playables
+				.frame_at_position(
+					swapped_from.playable_id,
+					swapped_from.region_start + swapped_from.position,
+				)
+				.unwrap_or(Frame::from_mono(0.0));
+			out = out * swap_fade + old * (1.0 - swap_fade);
+		}
+		out = out.with_width(self.width.value() as Sample);
+		out = out.panned_with(self.panning.value() as Sample, self.panning_law);
+		out * (self.effective_volume() as Sample)
+	}
+}
+
+/// Wraps a playback position that has moved past the end
+/// of a loop region (or past the start, if it's moving
+/// backwards) back into the loop region.
+fn wrap_position(position: &mut f64, duration: f64, loop_start: f64, playback_rate: f64) {
+	let loop_length = duration - loop_start;
+	if loop_length <= 0.0 {
+		return;
+	}
+	if playback_rate < 0.0 {
+		while *position < loop_start {
+			*position += loop_length;
+		}
+	} else {
+		while *position > duration {
+			*position -= loop_length;
+		}
 	}
 }
@@ -1,11 +1,15 @@
 use crate::{
-	mixer::{SubTrackId, TrackIndex},
+	group::InstanceGroups,
+	metronome::MetronomeId,
+	mixer::{PauseTailBehavior, SubTrackId, TrackIndex},
 	parameter::tween::{EaseDirection, Easing, Tween},
-	Value,
+	PanningLaw, StartTime, Value,
 };
 
 use super::InstanceId;
 
+use std::ops::Range;
+
 /// A track index for an instance to play on.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[cfg_attr(
@@ -73,6 +77,134 @@ impl From<Option<f64>> for InstanceLoopStart {
 	}
 }
 
+/// What happens when an instance that doesn't loop is seeked
+/// to a position before the start or past the end of its sound
+/// or arrangement.
+///
+/// Instances that loop always wrap the position back into their
+/// loop region instead.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SeekBehavior {
+	/// The position is moved to the nearest end of the sound
+	/// or arrangement.
+	#[default]
+	Snap,
+	/// The instance is stopped.
+	Stop,
+}
+
+/// A portion of a sound or arrangement for an instance to play.
+///
+/// Both positions are in seconds from the start of the
+/// sound or arrangement.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct PlaybackRegion {
+	/// The start of the region.
+	pub start: f64,
+	/// The end of the region.
+	pub end: f64,
+}
+
+impl PlaybackRegion {
+	/// Creates a new `PlaybackRegion`.
+	pub fn new(start: f64, end: f64) -> Self {
+		Self { start, end }
+	}
+
+	/// Returns the region limited to a sound or arrangement
+	/// with the given duration.
+	pub(crate) fn within(self, duration: f64) -> Self {
+		let start = self.start.clamp(0.0, duration);
+		Self {
+			start,
+			end: self.end.clamp(start, duration),
+		}
+	}
+
+	/// Returns the length of the region (in seconds).
+	pub fn duration(&self) -> f64 {
+		self.end - self.start
+	}
+}
+
+impl From<(f64, f64)> for PlaybackRegion {
+	fn from((start, end): (f64, f64)) -> Self {
+		Self::new(start, end)
+	}
+}
+
+impl From<Range<f64>> for PlaybackRegion {
+	fn from(range: Range<f64>) -> Self {
+		Self::new(range.start, range.end)
+	}
+}
+
+/// A fade tween for an instance.
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum InstanceFadeTween {
+	/// The default fade tween for the sound or arrangement.
+	#[default]
+	Default,
+	/// No fade - the volume changes immediately.
+	None,
+	/// A custom fade tween.
+	Custom(Tween),
+}
+
+impl InstanceFadeTween {
+	/// Returns the tween to use, falling back to the given
+	/// default tween.
+	pub(crate) fn or(self, default: Option<Tween>) -> Option<Tween> {
+		match self {
+			Self::Default => default,
+			Self::None => None,
+			Self::Custom(tween) => Some(tween),
+		}
+	}
+}
+
+impl From<Tween> for InstanceFadeTween {
+	fn from(tween: Tween) -> Self {
+		Self::Custom(tween)
+	}
+}
+
+impl From<Option<Tween>> for InstanceFadeTween {
+	fn from(option: Option<Tween>) -> Self {
+		match option {
+			Some(tween) => Self::Custom(tween),
+			None => Self::None,
+		}
+	}
+}
+
+/// Returns the fade used when an instance is stopped, paused,
+/// resumed, or seeked without a fade tween, which keeps the
+/// instance from clicking, or `None` if the duration is zero.
+pub(crate) fn declick_tween(duration: f64) -> Option<Tween> {
+	if duration <= 0.0 {
+		return None;
+	}
+	Some(Tween {
+		duration,
+		easing: Easing::Linear,
+		ease_direction: EaseDirection::In,
+		delay: 0.0,
+	})
+}
+
 /// Settings for an instance.
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(
@@ -90,18 +222,81 @@ pub struct InstanceSettings {
 	pub playback_rate: Value<f64>,
 	/// The panning of the instance (0 = hard left, 1 = hard right).
 	pub panning: Value<f64>,
+	/// How the instance's audio is moved to the left or right
+	/// when it's panned.
+	pub panning_law: PanningLaw,
+	/// The stereo width of the instance.
+	///
+	/// 0 makes the instance mono, 1 leaves it unchanged, and
+	/// values above 1 widen it. Narrowing sounds that are far
+	/// away from the listener makes them sound more distant.
+	pub width: Value<f64>,
+	/// A metronome whose tempo the instance should keep up with,
+	/// if any.
+	///
+	/// If the sound or arrangement has a
+	/// [native tempo](crate::sound::SoundSettings::native_tempo),
+	/// the instance's playback rate is multiplied by the ratio of
+	/// the metronome's tempo to the native tempo, so changing the
+	/// tempo of the metronome keeps every synced layer of music
+	/// in time. Sounds and arrangements without a native tempo
+	/// aren't affected.
+	pub tempo_sync: Option<MetronomeId>,
 	/// The position to start playing the instance at (in seconds).
 	pub start_position: f64,
+	/// The portion of the sound or arrangement to play, or
+	/// `None` to play the whole thing.
+	///
+	/// An instance with a region acts as if the region were
+	/// the whole sound: its start position, loop start point,
+	/// and playback position are relative to the start of the
+	/// region, and it stops when it reaches the end of the
+	/// region. The sound or arrangement's default loop start
+	/// point isn't used, so the instance will only loop if
+	/// it has a custom loop start point.
+	pub region: Option<PlaybackRegion>,
 	/// Whether to play the instance in reverse.
 	pub reverse: bool,
 	/// Whether to fade in the instance from silence, and if so,
 	/// the tween to use.
-	pub fade_in_tween: Option<Tween>,
+	///
+	/// By default, the sound or arrangement's
+	/// [default fade-in tween](crate::sound::SoundSettings::default_fade_in_tween)
+	/// is used.
+	pub fade_in_tween: InstanceFadeTween,
 	/// Whether the instance should loop, and if so, the position
 	/// it should jump back to when it reaches the end.
 	pub loop_start: InstanceLoopStart,
+	/// What happens when the instance is seeked out of range
+	/// and it doesn't loop.
+	pub seek_behavior: SeekBehavior,
 	/// Which track to play the instance on.
 	pub track: InstanceTrackIndex,
+	/// The groups the instance belongs to, in addition to
+	/// the groups of its sound or arrangement.
+	pub groups: InstanceGroups,
+	/// How often (in seconds) the audio thread should report
+	/// the playback position of the instance to the main thread.
+	///
+	/// If this is `0.0`, the position will be reported every frame.
+	pub position_report_interval: f64,
+	/// The seed to use when picking values for settings
+	/// set to [`Value::Random`].
+	///
+	/// Instances played with the same seed and settings
+	/// will always pick the same random volume, playback rate,
+	/// and panning. If this is `None`, the values will be
+	/// picked using the thread's random number generator.
+	pub random_seed: Option<u64>,
+	/// When the instance should start playing.
+	///
+	/// Scheduled instances start on the exact frame their start
+	/// time is reached, even if that's in the middle of an
+	/// audio buffer.
+	pub start_time: StartTime,
+	/// The maximum number of effects that can be added to the
+	/// instance using [`InstanceHandle::add_effect`](super::handle::InstanceHandle::add_effect).
+	pub num_effects: usize,
 }
 
 impl InstanceSettings {
@@ -142,6 +337,15 @@ impl InstanceSettings {
 		}
 	}
 
+	/// Makes the playback rate of the instance follow the
+	/// tempo of a metronome.
+	pub fn tempo_sync(self, metronome: impl Into<MetronomeId>) -> Self {
+		Self {
+			tempo_sync: Some(metronome.into()),
+			..self
+		}
+	}
+
 	/// Sets where in the sound playback will start (in seconds).
 	pub fn start_position(self, start_position: f64) -> Self {
 		Self {
@@ -150,6 +354,14 @@ impl InstanceSettings {
 		}
 	}
 
+	/// Sets the portion of the sound or arrangement to play.
+	pub fn region(self, region: impl Into<PlaybackRegion>) -> Self {
+		Self {
+			region: Some(region.into()),
+			..self
+		}
+	}
+
 	/// Play the instance in reverse.
 	pub fn reverse(self) -> Self {
 		Self {
@@ -159,9 +371,9 @@ impl InstanceSettings {
 	}
 
 	/// Sets the tween the instance will use to fade in from silence.
-	pub fn fade_in_tween(self, fade_in_tween: Tween) -> Self {
+	pub fn fade_in_tween<T: Into<InstanceFadeTween>>(self, fade_in_tween: T) -> Self {
 		Self {
-			fade_in_tween: Some(fade_in_tween),
+			fade_in_tween: fade_in_tween.into(),
 			..self
 		}
 	}
@@ -174,6 +386,15 @@ impl InstanceSettings {
 		}
 	}
 
+	/// Sets what happens when the instance is seeked out of range
+	/// and it doesn't loop.
+	pub fn seek_behavior(self, seek_behavior: SeekBehavior) -> Self {
+		Self {
+			seek_behavior,
+			..self
+		}
+	}
+
 	/// Sets the track the instance will play on.
 	pub fn track<T: Into<InstanceTrackIndex>>(self, track: T) -> Self {
 		Self {
@@ -182,32 +403,116 @@ impl InstanceSettings {
 		}
 	}
 
+	/// Sets the groups the instance belongs to, in addition to
+	/// the groups of its sound or arrangement.
+	pub fn groups(self, groups: impl Into<InstanceGroups>) -> Self {
+		Self {
+			groups: groups.into(),
+			..self
+		}
+	}
+
+	/// Sets how often (in seconds) the audio thread should report
+	/// the playback position of the instance to the main thread.
+	pub fn position_report_interval(self, position_report_interval: f64) -> Self {
+		Self {
+			position_report_interval,
+			..self
+		}
+	}
+
+	/// Sets the seed to use when picking values for settings
+	/// set to [`Value::Random`].
+	pub fn random_seed(self, random_seed: u64) -> Self {
+		Self {
+			random_seed: Some(random_seed),
+			..self
+		}
+	}
+
+	/// Sets when the instance should start playing.
+	pub fn start_time(self, start_time: StartTime) -> Self {
+		Self { start_time, ..self }
+	}
+
+	/// Sets the maximum number of effects that can be added
+	/// to the instance.
+	pub fn num_effects(self, num_effects: usize) -> Self {
+		Self {
+			num_effects,
+			..self
+		}
+	}
+
+	/// Sets the stereo width of the instance.
+	pub fn width(self, width: impl Into<Value<f64>>) -> Self {
+		Self {
+			width: width.into(),
+			..self
+		}
+	}
+
+	/// Sets how the instance's audio is moved to the left or
+	/// right when it's panned.
+	pub fn panning_law(self, panning_law: PanningLaw) -> Self {
+		Self {
+			panning_law,
+			..self
+		}
+	}
+
+	/// Makes the instance start on the next interval of a metronome.
+	///
+	/// This is a shorthand for setting the start time to
+	/// [`StartTime::NextInterval`].
+	pub fn quantize(self, metronome: impl Into<MetronomeId>, interval: f64) -> Self {
+		self.start_time(StartTime::NextInterval(metronome.into(), interval))
+	}
+
 	pub(crate) fn into_internal(
 		self,
 		duration: f64,
 		default_loop_start: Option<f64>,
 		default_track: TrackIndex,
+		default_fade_in_tween: Option<Tween>,
+		default_fade_out_tween: Option<Tween>,
 	) -> InternalInstanceSettings {
+		let region = self.region.map(|region| region.within(duration));
+		let (duration, default_loop_start) = match region {
+			Some(region) => (region.duration(), None),
+			None => (duration, default_loop_start),
+		};
 		InternalInstanceSettings {
 			volume: self.volume,
 			playback_rate: self.playback_rate,
 			panning: self.panning,
+			panning_law: self.panning_law,
+			width: self.width,
+			tempo_sync: self.tempo_sync,
 			start_position: if self.reverse {
 				duration - self.start_position
 			} else {
 				self.start_position
 			},
+			region,
 			reverse: self.reverse,
-			fade_in_tween: self.fade_in_tween,
+			fade_in_tween: self.fade_in_tween.or(default_fade_in_tween),
+			fade_out_tween: default_fade_out_tween,
 			loop_start: match self.loop_start {
 				InstanceLoopStart::Default => default_loop_start,
 				InstanceLoopStart::None => None,
 				InstanceLoopStart::Custom(position) => Some(position),
 			},
+			seek_behavior: self.seek_behavior,
 			track: match self.track {
 				InstanceTrackIndex::DefaultForSound => default_track,
 				InstanceTrackIndex::Custom(track) => track,
 			},
+			groups: self.groups,
+			position_report_interval: self.position_report_interval,
+			random_seed: self.random_seed,
+			start_time: self.start_time,
+			num_effects: self.num_effects,
 		}
 	}
 }
@@ -219,11 +524,21 @@ impl Default for InstanceSettings {
 			volume: Value::Fixed(1.0),
 			playback_rate: Value::Fixed(1.0),
 			panning: Value::Fixed(0.5),
+			panning_law: PanningLaw::default(),
+			width: Value::Fixed(1.0),
+			tempo_sync: None,
 			start_position: 0.0,
+			region: None,
 			reverse: false,
-			fade_in_tween: None,
+			fade_in_tween: InstanceFadeTween::Default,
 			loop_start: InstanceLoopStart::default(),
+			seek_behavior: SeekBehavior::default(),
 			track: InstanceTrackIndex::default(),
+			groups: InstanceGroups::new(),
+			position_report_interval: 0.0,
+			random_seed: None,
+			start_time: StartTime::Immediate,
+			num_effects: 0,
 		}
 	}
 }
@@ -232,11 +547,22 @@ pub(crate) struct InternalInstanceSettings {
 	pub volume: Value<f64>,
 	pub playback_rate: Value<f64>,
 	pub panning: Value<f64>,
+	pub panning_law: PanningLaw,
+	pub width: Value<f64>,
+	pub tempo_sync: Option<MetronomeId>,
 	pub start_position: f64,
+	pub region: Option<PlaybackRegion>,
 	pub reverse: bool,
 	pub fade_in_tween: Option<Tween>,
+	pub fade_out_tween: Option<Tween>,
 	pub loop_start: Option<f64>,
+	pub seek_behavior: SeekBehavior,
 	pub track: TrackIndex,
+	pub groups: InstanceGroups,
+	pub position_report_interval: f64,
+	pub random_seed: Option<u64>,
+	pub start_time: StartTime,
+	pub num_effects: usize,
 }
 
 /// Settings for pausing an instance.
@@ -249,7 +575,16 @@ pub(crate) struct InternalInstanceSettings {
 pub struct PauseInstanceSettings {
 	/// Whether to fade the instance to silence, and if so,
 	/// the tween to use.
-	pub fade_tween: Option<Tween>,
+	///
+	/// By default, a very short fade is used to keep the
+	/// instance from clicking.
+	pub fade_tween: InstanceFadeTween,
+	/// When the instance should be paused.
+	pub start_time: StartTime,
+	/// When pausing every voice on a mixer track, what happens
+	/// to the track's effects, or `None` to use the track's
+	/// own [`PauseTailBehavior`].
+	pub pause_tail: Option<PauseTailBehavior>,
 }
 
 impl PauseInstanceSettings {
@@ -259,22 +594,34 @@ impl PauseInstanceSettings {
 	}
 
 	/// Sets the fade-out tween to use.
-	pub fn fade_tween<T: Into<Option<Tween>>>(self, tween: T) -> Self {
+	pub fn fade_tween<T: Into<InstanceFadeTween>>(self, tween: T) -> Self {
 		Self {
 			fade_tween: tween.into(),
 			..self
 		}
 	}
+
+	/// Sets when the instance should be paused.
+	pub fn start_time(self, start_time: StartTime) -> Self {
+		Self { start_time, ..self }
+	}
+
+	/// When pausing every voice on a mixer track, sets what
+	/// happens to the track's effects.
+	pub fn pause_tail(self, pause_tail: PauseTailBehavior) -> Self {
+		Self {
+			pause_tail: Some(pause_tail),
+			..self
+		}
+	}
 }
 
 impl Default for PauseInstanceSettings {
 	fn default() -> Self {
 		Self {
-			fade_tween: Some(Tween {
-				duration: 0.001,
-				easing: Easing::Linear,
-				ease_direction: EaseDirection::In,
-			}),
+			fade_tween: InstanceFadeTween::Default,
+			start_time: StartTime::Immediate,
+			pause_tail: None,
 		}
 	}
 }
@@ -289,10 +636,15 @@ impl Default for PauseInstanceSettings {
 pub struct ResumeInstanceSettings {
 	/// Whether to fade in the instance from silence, and if so,
 	/// the tween to use.
-	pub fade_tween: Option<Tween>,
+	///
+	/// By default, a very short fade is used to keep the
+	/// instance from clicking.
+	pub fade_tween: InstanceFadeTween,
 	/// Whether to seek the instance backwards to the playback
 	/// position it was at when it was paused.
 	pub rewind_to_pause_position: bool,
+	/// When the instance should be resumed.
+	pub start_time: StartTime,
 }
 
 impl ResumeInstanceSettings {
@@ -302,7 +654,7 @@ impl ResumeInstanceSettings {
 	}
 
 	/// Sets the fade-in tween to use.
-	pub fn fade_tween<T: Into<Option<Tween>>>(self, tween: T) -> Self {
+	pub fn fade_tween<T: Into<InstanceFadeTween>>(self, tween: T) -> Self {
 		Self {
 			fade_tween: tween.into(),
 			..self
@@ -317,17 +669,19 @@ impl ResumeInstanceSettings {
 			..self
 		}
 	}
+
+	/// Sets when the instance should be resumed.
+	pub fn start_time(self, start_time: StartTime) -> Self {
+		Self { start_time, ..self }
+	}
 }
 
 impl Default for ResumeInstanceSettings {
 	fn default() -> Self {
 		Self {
-			fade_tween: Some(Tween {
-				duration: 0.001,
-				easing: Easing::Linear,
-				ease_direction: EaseDirection::In,
-			}),
+			fade_tween: InstanceFadeTween::Default,
 			rewind_to_pause_position: false,
+			start_time: StartTime::Immediate,
 		}
 	}
 }
@@ -342,7 +696,13 @@ impl Default for ResumeInstanceSettings {
 pub struct StopInstanceSettings {
 	/// Whether to fade the instance to silence, and if so,
 	/// the tween to use.
-	pub fade_tween: Option<Tween>,
+	///
+	/// By default, the sound or arrangement's
+	/// [default fade-out tween](crate::sound::SoundSettings::default_fade_out_tween)
+	/// is used, or a very short fade if it doesn't have one.
+	pub fade_tween: InstanceFadeTween,
+	/// When the instance should be stopped.
+	pub start_time: StartTime,
 }
 
 impl StopInstanceSettings {
@@ -352,22 +712,156 @@ impl StopInstanceSettings {
 	}
 
 	/// Sets the fade-out tween to use.
-	pub fn fade_tween<T: Into<Option<Tween>>>(self, tween: T) -> Self {
+	pub fn fade_tween<T: Into<InstanceFadeTween>>(self, tween: T) -> Self {
 		Self {
 			fade_tween: tween.into(),
 			..self
 		}
 	}
+
+	/// Sets when the instance should be stopped.
+	pub fn start_time(self, start_time: StartTime) -> Self {
+		Self { start_time, ..self }
+	}
 }
 
 impl Default for StopInstanceSettings {
 	fn default() -> Self {
 		Self {
-			fade_tween: Some(Tween {
-				duration: 0.001,
-				easing: Easing::Linear,
-				ease_direction: EaseDirection::In,
-			}),
+			fade_tween: InstanceFadeTween::Default,
+			start_time: StartTime::Immediate,
+		}
+	}
+}
+
+/// Settings for crossfading from one instance to a new
+/// instance of a sound or arrangement.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct CrossfadeSettings {
+	/// The tween used to fade the old instance out
+	/// and the new instance in.
+	pub fade_tween: Tween,
+	/// Whether the crossfade should wait for a metronome
+	/// interval before starting, and if so, the metronome to
+	/// use and the interval to wait for (in beats).
+	pub quantize: Option<(MetronomeId, f64)>,
+	/// The settings for the new instance.
+	///
+	/// The fade-in tween of these settings will be
+	/// overridden by `fade_tween`.
+	pub instance_settings: InstanceSettings,
+}
+
+impl CrossfadeSettings {
+	/// Creates a new `CrossfadeSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the tween used to fade the old instance out
+	/// and the new instance in.
+	pub fn fade_tween(self, fade_tween: impl Into<Tween>) -> Self {
+		Self {
+			fade_tween: fade_tween.into(),
+			..self
+		}
+	}
+
+	/// Makes the crossfade wait until the metronome reaches
+	/// the next multiple of the given interval (in beats)
+	/// before starting.
+	pub fn quantize(self, metronome: impl Into<MetronomeId>, interval: f64) -> Self {
+		Self {
+			quantize: Some((metronome.into(), interval)),
+			..self
+		}
+	}
+
+	/// Sets the settings for the new instance.
+	pub fn instance_settings(self, instance_settings: InstanceSettings) -> Self {
+		Self {
+			instance_settings,
+			..self
+		}
+	}
+}
+
+impl Default for CrossfadeSettings {
+	fn default() -> Self {
+		Self {
+			fade_tween: Tween::linear(1.0),
+			quantize: None,
+			instance_settings: InstanceSettings::default(),
+		}
+	}
+}
+
+/// How an instance's playback position carries over when it
+/// switches to a different sound or arrangement.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SyncMode {
+	/// Keep the same playback position (in seconds).
+	#[default]
+	Position,
+	/// Keep the same position relative to the loop region
+	/// (or the whole duration if the instance isn't in its
+	/// loop region yet), so loops of different lengths
+	/// stay in phase.
+	Phase,
+}
+
+/// Settings for switching an instance to a different
+/// sound or arrangement.
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct SwapPlayableSettings {
+	/// How the playback position carries over to the
+	/// new sound or arrangement.
+	pub sync_mode: SyncMode,
+	/// The tween used to crossfade from the old sound or
+	/// arrangement to the new one.
+	///
+	/// If this is `None`, the instance will switch immediately.
+	pub fade_tween: Option<Tween>,
+}
+
+impl SwapPlayableSettings {
+	/// Creates a new `SwapPlayableSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets how the playback position carries over to the
+	/// new sound or arrangement.
+	pub fn sync_mode(self, sync_mode: SyncMode) -> Self {
+		Self { sync_mode, ..self }
+	}
+
+	/// Sets the tween used to crossfade from the old sound or
+	/// arrangement to the new one.
+	pub fn fade_tween<T: Into<Option<Tween>>>(self, tween: T) -> Self {
+		Self {
+			fade_tween: tween.into(),
+			..self
 		}
 	}
 }
+
+impl From<SyncMode> for SwapPlayableSettings {
+	fn from(sync_mode: SyncMode) -> Self {
+		Self::new().sync_mode(sync_mode)
+	}
+}
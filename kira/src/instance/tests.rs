@@ -0,0 +1,580 @@
+use std::time::Duration;
+
+use instant::Instant;
+
+use crate::{
+	manager::{AudioManager, AudioManagerSettings},
+	metronome::MetronomeSettings,
+	mixer::effect::EffectSettings,
+	parameter::tween::Tween,
+	sound::{Sound, SoundSettings},
+	testing::{constant_sound, create_manager, ramp_sound, ScaleEffect},
+	Frame, PanningLaw, Semitones, StartTime, Tempo, Value,
+};
+
+use super::{
+	handle::AddInstanceEffectError, CrossfadeSettings, InstanceSettings, InstanceState,
+	PauseInstanceSettings, ResumeInstanceSettings, SeekBehavior, StopInstanceSettings,
+	SwapPlayableSettings, SyncMode,
+};
+
+#[test]
+fn crossfade_stops_the_old_instance() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.5, Default::default()))
+		.unwrap();
+	let old_instance_handle = sound_handle.play(InstanceSettings::default()).unwrap();
+	backend.process();
+	manager
+		.crossfade(
+			&old_instance_handle,
+			&sound_handle,
+			CrossfadeSettings::new().fade_tween(0.01),
+		)
+		.unwrap();
+	for _ in 0..1000 {
+		backend.process();
+	}
+	assert_eq!(
+		old_instance_handle.state(),
+		InstanceState::Stopped,
+		"Crossfading should stop the old instance"
+	);
+}
+
+#[test]
+fn random_seed_picks_the_same_random_values() {
+	let mut outputs = vec![];
+	for _ in 0..2 {
+		let (mut manager, mut backend) = create_manager();
+		let mut sound_handle = manager
+			.add_sound(constant_sound(0.5, Default::default()))
+			.unwrap();
+		sound_handle
+			.play(
+				InstanceSettings::new()
+					.volume(Value::Random(0.0, 1.0))
+					.panning(Value::Random(0.0, 1.0))
+					.random_seed(42),
+			)
+			.unwrap();
+		backend.process();
+		outputs.push(backend.process());
+	}
+	assert_eq!(
+		outputs[0], outputs[1],
+		"Instances with the same random seed should pick the same random values"
+	);
+}
+
+#[test]
+fn starts_scheduled_instances_on_the_exact_frame() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let instance_handle = sound_handle
+		.play(InstanceSettings::new().start_time(StartTime::ClockTime(100.5 / 48000.0)))
+		.unwrap();
+	assert_eq!(instance_handle.state(), InstanceState::Scheduled);
+	for _ in 0..101 {
+		assert_eq!(backend.process(), Frame::from_mono(0.0));
+	}
+	assert_ne!(backend.process(), Frame::from_mono(0.0));
+	assert_eq!(instance_handle.state(), InstanceState::Playing);
+}
+
+#[test]
+fn plays_regions_of_sounds() {
+	let (mut manager, mut backend) = create_manager();
+	// each frame of the sound is its position in seconds
+	let mut sound_handle = manager
+		.add_sound(ramp_sound(SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let instance_handle = sound_handle
+		.play(
+			InstanceSettings::new()
+				.region((0.5, 0.5 + 100.0 / 48000.0))
+				.fade_in_tween(None)
+				.panning(0.0),
+		)
+		.unwrap();
+	backend.process();
+	let first_frame = backend.process().left;
+	assert!((first_frame - 0.5).abs() < 0.001);
+	for _ in 0..90 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Playing);
+	assert!(instance_handle.position() < 100.0 / 48000.0);
+	for _ in 0..20 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+
+	// positions are relative to the start of the region
+	let instance_handle = sound_handle
+		.play(
+			InstanceSettings::new()
+				.region((0.5, 0.75))
+				.start_position(0.125)
+				.fade_in_tween(None)
+				.panning(0.0),
+		)
+		.unwrap();
+	backend.process();
+	let first_frame = backend.process().left;
+	assert!((first_frame - 0.625).abs() < 0.001);
+	assert!(instance_handle.position() < 0.126);
+}
+
+#[test]
+fn uses_the_default_fade_tweens_of_sounds() {
+	let (mut manager, mut backend) = create_manager();
+	// the sound fades in and out over 100 frames
+	let mut sound_handle = manager
+		.add_sound(constant_sound(
+			1.0,
+			SoundSettings::new()
+				.cooldown(0.0)
+				.default_fade_in_tween(Tween::linear(100.0 / 48000.0))
+				.default_fade_out_tween(Tween::linear(100.0 / 48000.0)),
+		))
+		.unwrap();
+	let mut instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	let start_volume = backend.process().left;
+	for _ in 0..100 {
+		backend.process();
+	}
+	let full_volume = backend.process().left;
+	assert!(start_volume < full_volume * 0.1);
+	instance_handle.stop(StopInstanceSettings::new()).unwrap();
+	for _ in 0..50 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Stopping);
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+
+	// the defaults can be overridden for each instance
+	let mut instance_handle = sound_handle
+		.play(InstanceSettings::new().fade_in_tween(None))
+		.unwrap();
+	backend.process();
+	assert_eq!(backend.process().left, full_volume);
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+}
+
+#[test]
+fn starts_quantized_instances_on_the_next_interval() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	// one beat every 100 frames
+	let mut metronome = manager
+		.add_metronome(MetronomeSettings::new().tempo(Tempo(28800.0)))
+		.unwrap();
+	metronome.start().unwrap();
+	for _ in 0..150 {
+		backend.process();
+	}
+	// the metronome is halfway through the second beat,
+	// so the instance should start on the third beat
+	let instance_handle = sound_handle
+		.play(InstanceSettings::new().quantize(&metronome, 1.0))
+		.unwrap();
+	assert_eq!(instance_handle.state(), InstanceState::Scheduled);
+	for _ in 0..45 {
+		assert_eq!(backend.process(), Frame::from_mono(0.0));
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Scheduled);
+	for _ in 0..10 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Playing);
+}
+
+#[test]
+fn swaps_the_playable_of_a_looping_instance() {
+	let (mut manager, mut backend) = create_manager();
+	let mut short_sound = manager
+		.add_sound(constant_sound(0.25, Default::default()))
+		.unwrap();
+	let long_sound = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			vec![Frame::from_mono(0.5); 96000],
+			Default::default(),
+		))
+		.unwrap();
+	let mut instance = short_sound
+		.play(
+			InstanceSettings::new()
+				.loop_start(0.0)
+				.position_report_interval(0.0),
+		)
+		.unwrap();
+	for _ in 0..72000 {
+		backend.process();
+	}
+	let old_output = backend.process();
+	// halfway through the second loop of the short sound is
+	// halfway through the long sound
+	instance
+		.swap_playable(&long_sound, SyncMode::Phase)
+		.unwrap();
+	assert_eq!(backend.process(), old_output * 2.0);
+	assert!((instance.position() - 1.0).abs() < 0.001);
+	// crossfading back keeps some of the long sound for a while
+	instance
+		.swap_playable(
+			&short_sound,
+			SwapPlayableSettings::new().fade_tween(Tween::linear(1.0)),
+		)
+		.unwrap();
+	for _ in 0..24000 {
+		backend.process();
+	}
+	let crossfade_output = backend.process();
+	assert!(crossfade_output.left > old_output.left);
+	assert!(crossfade_output.left < old_output.left * 2.0);
+	for _ in 0..48000 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), old_output);
+	assert_eq!(instance.state(), InstanceState::Playing);
+}
+
+#[test]
+fn transposes_instances_by_semitones() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.5, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let mut instance_handle = sound_handle
+		.play(InstanceSettings::new().playback_rate(Semitones(12.0)))
+		.unwrap();
+	backend.process();
+	let position = instance_handle.position();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert!((instance_handle.position() - position - 200.0 / 48000.0).abs() < 1.0e-9);
+	instance_handle.set_playback_rate(Semitones(-12.0)).unwrap();
+	backend.process();
+	let position = instance_handle.position();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert!((instance_handle.position() - position - 50.0 / 48000.0).abs() < 1.0e-9);
+	assert!((Semitones::from_playback_rate(1.5).playback_rate() - 1.5).abs() < 1.0e-9);
+	assert_eq!(Semitones(1.5).cents(), 150.0);
+}
+
+#[test]
+fn syncs_playback_rates_to_metronome_tempos() {
+	let (mut manager, mut backend) = create_manager();
+	let mut metronome = manager
+		.add_metronome(MetronomeSettings::new().tempo(Tempo(120.0)))
+		.unwrap();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(
+			0.5,
+			SoundSettings::new()
+				.native_tempo(Tempo(120.0))
+				.cooldown(0.0),
+		))
+		.unwrap();
+	let synced = sound_handle
+		.play(InstanceSettings::new().tempo_sync(&metronome))
+		.unwrap();
+	backend.process();
+	let unsynced = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	let synced_position = synced.position();
+	let unsynced_position = unsynced.position();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert!((synced.position() - synced_position - 100.0 / 48000.0).abs() < 1.0e-9);
+	assert!((unsynced.position() - unsynced_position - 100.0 / 48000.0).abs() < 1.0e-9);
+
+	// the synced instance speeds up with the metronome
+	metronome.set_tempo(Tempo(180.0)).unwrap();
+	backend.process();
+	let synced_position = synced.position();
+	let unsynced_position = unsynced.position();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert!((synced.position() - synced_position - 150.0 / 48000.0).abs() < 1.0e-9);
+	assert!((unsynced.position() - unsynced_position - 100.0 / 48000.0).abs() < 1.0e-9);
+}
+
+#[test]
+fn applies_effects_to_single_instances() {
+	let (mut manager, mut backend) = AudioManager::new_headless(Default::default(), 48000);
+	let mut sound = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let mut instance = sound.play(InstanceSettings::new().num_effects(1)).unwrap();
+	let unaffected = backend.process();
+	let effect = instance
+		.add_effect(ScaleEffect(0.5), EffectSettings::new())
+		.unwrap();
+	assert_eq!(backend.process(), unaffected * 0.5);
+	// the instance can only hold one effect
+	assert!(matches!(
+		instance.add_effect(ScaleEffect(0.5), EffectSettings::new()),
+		Err(AddInstanceEffectError::EffectLimitReached)
+	));
+	// other instances of the sound aren't affected
+	sound.play(InstanceSettings::new()).unwrap();
+	assert_eq!(backend.process(), unaffected * 1.5);
+	instance.remove_effect(&effect).unwrap();
+	assert_eq!(backend.process(), unaffected * 2.0);
+}
+
+#[test]
+fn pans_instances_with_panning_laws() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			vec![Frame::new(1.0, 0.5); 48000],
+			SoundSettings::new().cooldown(0.0),
+		))
+		.unwrap();
+	let mut instance_handle = sound_handle
+		.play(InstanceSettings::new().panning_law(PanningLaw::Balance))
+		.unwrap();
+	// balanced and truly panned instances keep their
+	// original volume when they're centered
+	assert_eq!(backend.process(), Frame::new(1.0, 0.5));
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	sound_handle
+		.play(
+			InstanceSettings::new()
+				.panning(0.0)
+				.panning_law(PanningLaw::TruePan),
+		)
+		.unwrap();
+	assert_eq!(backend.process(), Frame::new(1.5, 0.0));
+}
+
+#[test]
+fn fades_out_hard_stops_pauses_and_seeks() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			declick_duration: 100.0 / 48000.0,
+			..Default::default()
+		},
+		48000,
+	);
+	// the first half of the sound is loud and the second
+	// half is silent
+	let mut frames = vec![Frame::from_mono(1.0); 24000];
+	frames.extend(vec![Frame::from_mono(0.0); 24000]);
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			frames,
+			SoundSettings::new().cooldown(0.0),
+		))
+		.unwrap();
+
+	// seeking crossfades to the new position
+	let mut instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	let full_volume = backend.process().left;
+	instance_handle.seek_to(0.75).unwrap();
+	backend.process();
+	for _ in 0..50 {
+		backend.process();
+	}
+	let volume = backend.process().left;
+	assert!(volume > 0.0 && volume < full_volume);
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(backend.process().left, 0.0);
+
+	// pausing and stopping without a fade tween still
+	// fade out over a few frames
+	instance_handle.seek_to(0.0).unwrap();
+	instance_handle.pause(PauseInstanceSettings::new()).unwrap();
+	for _ in 0..50 {
+		backend.process();
+	}
+	assert!(matches!(instance_handle.state(), InstanceState::Pausing(_)));
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert!(matches!(instance_handle.state(), InstanceState::Paused(_)));
+	instance_handle
+		.resume(ResumeInstanceSettings::new())
+		.unwrap();
+	instance_handle.stop(StopInstanceSettings::new()).unwrap();
+	for _ in 0..50 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Stopping);
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+
+	// the fades can be turned off
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			declick_duration: 0.0,
+			..Default::default()
+		},
+		48000,
+	);
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	instance_handle.stop(StopInstanceSettings::new()).unwrap();
+	backend.process();
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+}
+
+#[test]
+fn wraps_and_clamps_out_of_range_seeks() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+
+	// seeks past the end of a looping instance wrap around
+	// the loop region
+	let mut instance_handle = sound_handle
+		.play(InstanceSettings::new().loop_start(0.5))
+		.unwrap();
+	backend.process();
+	instance_handle.seek_to(1.25).unwrap();
+	backend.process();
+	assert!((instance_handle.position() - 0.75).abs() < 0.001);
+	instance_handle.seek(-1.0).unwrap();
+	backend.process();
+	assert!((instance_handle.position() - 0.75).abs() < 0.001);
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+
+	// seeks out of range snap to the nearest end by default
+	let mut instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	instance_handle.seek_to(-1.0).unwrap();
+	backend.process();
+	assert!(instance_handle.position() < 0.001);
+	assert_eq!(instance_handle.state(), InstanceState::Playing);
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+
+	// or stop the instance
+	let mut instance_handle = sound_handle
+		.play(InstanceSettings::new().seek_behavior(SeekBehavior::Stop))
+		.unwrap();
+	backend.process();
+	instance_handle.seek_to(5.0).unwrap();
+	backend.process();
+	assert_eq!(instance_handle.state(), InstanceState::Stopping);
+}
+
+#[test]
+fn reports_positions_at_the_position_report_interval() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			vec![Frame::from_mono(0.5); 96000],
+			Default::default(),
+		))
+		.unwrap();
+	let instance_handle = sound_handle
+		.play(
+			InstanceSettings::new()
+				.playback_rate(2.0)
+				.position_report_interval(0.1),
+		)
+		.unwrap();
+	let mut reports = vec![];
+	for _ in 0..24000 {
+		backend.process();
+		let report = instance_handle.position_report();
+		if reports.last() != Some(&report) {
+			reports.push(report);
+		}
+	}
+	// a report every 0.1 seconds, during which the
+	// instance moves 0.2 seconds through the sound
+	assert_eq!(reports.len(), 5);
+	for (i, report) in reports.iter().enumerate() {
+		assert!((report.position - 0.2 * i as f64).abs() < 0.001);
+		assert_eq!(report.playback_rate, 2.0);
+	}
+	assert_eq!(instance_handle.position(), reports[4].position);
+}
+
+#[test]
+fn extrapolates_interpolated_positions_from_the_playback_rate() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.5, Default::default()))
+		.unwrap();
+	// the instance only reports its position when its state
+	// changes or it's seeked
+	let mut instance_handle = sound_handle
+		.play(
+			InstanceSettings::new()
+				.playback_rate(2.0)
+				.position_report_interval(100.0),
+		)
+		.unwrap();
+	for _ in 0..4800 {
+		backend.process();
+	}
+	let report = instance_handle.position_report();
+	assert_eq!(report.playback_rate, 2.0);
+	let start = Instant::now();
+	let first_position = instance_handle.interpolated_position();
+	std::thread::sleep(Duration::from_millis(50));
+	let second_position = instance_handle.interpolated_position();
+	let elapsed = start.elapsed().as_secs_f64();
+	assert!(first_position >= report.position);
+	assert!(second_position - first_position >= 0.1 - 1e-9);
+	assert!(second_position <= report.position + 2.0 * elapsed);
+
+	// a new report restarts the extrapolation from its position
+	instance_handle.seek_to(0.5).unwrap();
+	backend.process();
+	assert!((instance_handle.interpolated_position() - 0.5).abs() < 0.01);
+
+	// paused instances report a playback rate of zero,
+	// so their position doesn't move
+	instance_handle
+		.pause(PauseInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert_eq!(instance_handle.position_report().playback_rate, 0.0);
+	let paused_position = instance_handle.interpolated_position();
+	std::thread::sleep(Duration::from_millis(10));
+	assert_eq!(instance_handle.interpolated_position(), paused_position);
+}
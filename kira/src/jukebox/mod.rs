@@ -0,0 +1,372 @@
+//! Plays a playlist of music tracks.
+//!
+//! A [`Jukebox`] plays sounds and arrangements one after another,
+//! either in order or shuffled, optionally crossfading between
+//! them. It keeps a history of played tracks so you can go back
+//! to previous tracks, and so shuffled playlists don't repeat
+//! the same tracks too often.
+//!
+//! Unlike most of kira, the jukebox runs on the main thread, so
+//! you need to call [`Jukebox::update`] regularly (for example,
+//! once per frame) to let it move on to the next track.
+//!
+//! Each track can have some metadata attached to it, like the
+//! title and artist of the song. When the jukebox moves to a
+//! new track, it emits a [`JukeboxEvent`] containing that metadata,
+//! which you can use to show a "now playing" popup:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	jukebox::{Jukebox, JukeboxEvent, JukeboxSettings},
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sound::SoundSettings,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let mut jukebox = Jukebox::new(
+//! 	JukeboxSettings::new()
+//! 		.shuffle(true)
+//! 		.avoid_repeats(2)
+//! 		.crossfade_duration(3.0),
+//! );
+//! jukebox
+//! 	.add_track(
+//! 		&audio_manager.load_sound("music/morning.ogg", SoundSettings::default())?,
+//! 		"Morning Shift",
+//! 	)
+//! 	.add_track(
+//! 		&audio_manager.load_sound("music/rush-hour.ogg", SoundSettings::default())?,
+//! 		"Rush Hour",
+//! 	)
+//! 	.add_track(
+//! 		&audio_manager.load_sound("music/closing-time.ogg", SoundSettings::default())?,
+//! 		"Closing Time",
+//! 	);
+//! jukebox.play()?;
+//! // in the game loop
+//! jukebox.update()?;
+//! while let Some(event) = jukebox.pop_event() {
+//! 	if let JukeboxEvent::TrackChanged { metadata, .. } = event {
+//! 		println!("Now playing: {}", metadata);
+//! 	}
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod settings;
+
+#[cfg(test)]
+mod tests;
+
+pub use settings::JukeboxSettings;
+
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::{
+	arrangement::handle::ArrangementHandle,
+	instance::{
+		handle::InstanceHandle, InstanceLoopStart, InstanceSettings, InstanceState,
+		StopInstanceSettings,
+	},
+	parameter::tween::Tween,
+	sound::handle::SoundHandle,
+	util::SharedRng,
+	CommandError,
+};
+
+/// Something a jukebox can play.
+#[derive(Debug, Clone)]
+pub enum JukeboxSource {
+	/// A sound.
+	Sound(SoundHandle),
+	/// An arrangement.
+	Arrangement(ArrangementHandle),
+}
+
+impl JukeboxSource {
+	fn duration(&self) -> f64 {
+		match self {
+			JukeboxSource::Sound(handle) => handle.duration(),
+			JukeboxSource::Arrangement(handle) => handle.duration(),
+		}
+	}
+
+	fn rng(&self) -> &SharedRng {
+		match self {
+			JukeboxSource::Sound(handle) => handle.rng(),
+			JukeboxSource::Arrangement(handle) => handle.rng(),
+		}
+	}
+
+	fn play(&mut self, settings: InstanceSettings) -> Result<InstanceHandle, CommandError> {
+		match self {
+			JukeboxSource::Sound(handle) => handle.play(settings),
+			JukeboxSource::Arrangement(handle) => handle.play(settings),
+		}
+	}
+}
+
+impl From<SoundHandle> for JukeboxSource {
+	fn from(handle: SoundHandle) -> Self {
+		Self::Sound(handle)
+	}
+}
+
+impl From<&SoundHandle> for JukeboxSource {
+	fn from(handle: &SoundHandle) -> Self {
+		Self::Sound(handle.clone())
+	}
+}
+
+impl From<ArrangementHandle> for JukeboxSource {
+	fn from(handle: ArrangementHandle) -> Self {
+		Self::Arrangement(handle)
+	}
+}
+
+impl From<&ArrangementHandle> for JukeboxSource {
+	fn from(handle: &ArrangementHandle) -> Self {
+		Self::Arrangement(handle.clone())
+	}
+}
+
+/// Something that happened in a [`Jukebox`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum JukeboxEvent<M> {
+	/// The jukebox started playing a track.
+	TrackChanged {
+		/// The index of the track.
+		index: usize,
+		/// The metadata of the track.
+		metadata: M,
+	},
+	/// The jukebox reached the end of the playlist
+	/// and stopped.
+	Finished,
+}
+
+#[derive(Debug, Clone)]
+struct JukeboxTrack<M> {
+	source: JukeboxSource,
+	metadata: M,
+}
+
+#[derive(Debug)]
+struct CurrentTrack {
+	index: usize,
+	instance: InstanceHandle,
+}
+
+/// Plays a playlist of sounds and arrangements.
+#[derive(Debug)]
+pub struct Jukebox<M = ()> {
+	settings: JukeboxSettings,
+	tracks: Vec<JukeboxTrack<M>>,
+	current: Option<CurrentTrack>,
+	history: Vec<usize>,
+	events: VecDeque<JukeboxEvent<M>>,
+}
+
+impl<M: Clone> Jukebox<M> {
+	/// Creates a new, empty jukebox.
+	pub fn new(settings: JukeboxSettings) -> Self {
+		Self {
+			settings,
+			tracks: vec![],
+			current: None,
+			history: vec![],
+			events: VecDeque::new(),
+		}
+	}
+
+	/// Adds a track to the end of the playlist.
+	pub fn add_track(&mut self, source: impl Into<JukeboxSource>, metadata: M) -> &mut Self {
+		self.tracks.push(JukeboxTrack {
+			source: source.into(),
+			metadata,
+		});
+		self
+	}
+
+	/// Returns the number of tracks in the playlist.
+	pub fn num_tracks(&self) -> usize {
+		self.tracks.len()
+	}
+
+	/// Returns the index of the track that's currently playing.
+	pub fn current_track(&self) -> Option<usize> {
+		self.current.as_ref().map(|current| current.index)
+	}
+
+	/// Returns the metadata of the track that's currently playing.
+	pub fn current_metadata(&self) -> Option<&M> {
+		self.current
+			.as_ref()
+			.map(|current| &self.tracks[current.index].metadata)
+	}
+
+	/// Returns `true` if the jukebox is playing a track.
+	pub fn is_playing(&self) -> bool {
+		self.current.is_some()
+	}
+
+	/// Starts playing the playlist if it isn't already playing.
+	pub fn play(&mut self) -> Result<(), CommandError> {
+		if self.current.is_some() {
+			return Ok(());
+		}
+		match self.pick_next_track() {
+			Some(index) => self.switch_to(index, true),
+			None => Ok(()),
+		}
+	}
+
+	/// Plays the track with the given index.
+	pub fn play_track(&mut self, index: usize) -> Result<(), CommandError> {
+		if index >= self.tracks.len() {
+			return Ok(());
+		}
+		self.switch_to(index, true)
+	}
+
+	/// Moves on to the next track.
+	pub fn skip(&mut self) -> Result<(), CommandError> {
+		match self.pick_next_track() {
+			Some(index) => self.switch_to(index, true),
+			None => {
+				self.stop()?;
+				self.events.push_back(JukeboxEvent::Finished);
+				Ok(())
+			}
+		}
+	}
+
+	/// Goes back to the previously played track.
+	///
+	/// If there's no previous track, the current track will
+	/// be restarted.
+	pub fn previous(&mut self) -> Result<(), CommandError> {
+		match self.history.pop() {
+			Some(index) => self.switch_to(index, false),
+			None => match &mut self.current {
+				Some(current) => current.instance.seek_to(0.0),
+				None => Ok(()),
+			},
+		}
+	}
+
+	/// Stops the current track.
+	pub fn stop(&mut self) -> Result<(), CommandError> {
+		if let Some(mut current) = self.current.take() {
+			current.instance.stop(self.fade_out_settings())?;
+			self.history.push(current.index);
+		}
+		Ok(())
+	}
+
+	/// Moves on to the next track if the current one is finished
+	/// (or about to finish, if crossfading is enabled).
+	///
+	/// This should be called regularly.
+	pub fn update(&mut self) -> Result<(), CommandError> {
+		let should_advance = match &self.current {
+			Some(current) => {
+				let duration = self.tracks[current.index].source.duration();
+				current.instance.state() == InstanceState::Stopped
+					|| current.instance.position()
+						>= (duration - self.settings.crossfade_duration).max(0.0)
+			}
+			None => false,
+		};
+		if should_advance {
+			self.skip()?;
+		}
+		Ok(())
+	}
+
+	/// Gets the first event that was emitted by the jukebox
+	/// since the last call to `pop_event`.
+	pub fn pop_event(&mut self) -> Option<JukeboxEvent<M>> {
+		self.events.pop_front()
+	}
+
+	fn fade_out_settings(&self) -> StopInstanceSettings {
+		if self.settings.crossfade_duration > 0.0 {
+			StopInstanceSettings::new().fade_tween(Tween::linear(self.settings.crossfade_duration))
+		} else {
+			StopInstanceSettings::new()
+		}
+	}
+
+	fn switch_to(&mut self, index: usize, record_history: bool) -> Result<(), CommandError> {
+		let mut instance_settings = InstanceSettings::new()
+			.loop_start(InstanceLoopStart::None)
+			.track(self.settings.track);
+		if let Some(mut current) = self.current.take() {
+			current.instance.stop(self.fade_out_settings())?;
+			if record_history {
+				self.history.push(current.index);
+			}
+			if self.settings.crossfade_duration > 0.0 {
+				instance_settings = instance_settings
+					.fade_in_tween(Tween::linear(self.settings.crossfade_duration));
+			}
+		}
+		let track = &mut self.tracks[index];
+		let instance = track.source.play(instance_settings)?;
+		self.current = Some(CurrentTrack { index, instance });
+		self.events.push_back(JukeboxEvent::TrackChanged {
+			index,
+			metadata: track.metadata.clone(),
+		});
+		Ok(())
+	}
+
+	/// Returns `true` if the track was played recently enough
+	/// that it shouldn't be picked in shuffle mode.
+	fn is_recent(&self, index: usize) -> bool {
+		let num_recent = self
+			.settings
+			.avoid_repeats
+			.min(self.tracks.len().saturating_sub(1));
+		if num_recent == 0 {
+			return false;
+		}
+		self.current
+			.as_ref()
+			.map(|current| current.index)
+			.into_iter()
+			.chain(self.history.iter().rev().copied())
+			.take(num_recent)
+			.any(|recent_index| recent_index == index)
+	}
+
+	fn pick_next_track(&self) -> Option<usize> {
+		if self.tracks.is_empty() {
+			return None;
+		}
+		if self.settings.shuffle {
+			let num_candidates = (0..self.tracks.len())
+				.filter(|index| !self.is_recent(*index))
+				.count();
+			// shuffle with the audio manager's generator so the
+			// order follows its random seed
+			let choice = self.tracks[0]
+				.source
+				.rng()
+				.with(|rng| rng.gen_range(0..num_candidates));
+			return (0..self.tracks.len())
+				.filter(|index| !self.is_recent(*index))
+				.nth(choice);
+		}
+		let next_index = self.current.as_ref().map_or(0, |current| current.index + 1);
+		if next_index < self.tracks.len() {
+			Some(next_index)
+		} else if self.settings.repeat {
+			Some(0)
+		} else {
+			None
+		}
+	}
+}
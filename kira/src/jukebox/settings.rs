@@ -0,0 +1,85 @@
+use crate::instance::InstanceTrackIndex;
+
+/// Settings for a [`Jukebox`](super::Jukebox).
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct JukeboxSettings {
+	/// Whether tracks should be picked randomly instead of
+	/// played in order.
+	///
+	/// In shuffle mode, the jukebox keeps picking new tracks
+	/// until it's stopped.
+	pub shuffle: bool,
+	/// How many of the most recently played tracks should not be
+	/// picked again in shuffle mode.
+	pub avoid_repeats: usize,
+	/// Whether the jukebox should start from the first track
+	/// again after the last track finishes (when not in shuffle mode).
+	pub repeat: bool,
+	/// How long it takes to crossfade from one track to the
+	/// next (in seconds).
+	pub crossfade_duration: f64,
+	/// The mixer track the music should be played on.
+	pub track: InstanceTrackIndex,
+}
+
+impl JukeboxSettings {
+	/// Creates a new `JukeboxSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets whether tracks should be picked randomly instead of
+	/// played in order.
+	pub fn shuffle(self, shuffle: bool) -> Self {
+		Self { shuffle, ..self }
+	}
+
+	/// Sets how many of the most recently played tracks should not be
+	/// picked again in shuffle mode.
+	pub fn avoid_repeats(self, avoid_repeats: usize) -> Self {
+		Self {
+			avoid_repeats,
+			..self
+		}
+	}
+
+	/// Sets whether the jukebox should start from the first track
+	/// again after the last track finishes.
+	pub fn repeat(self, repeat: bool) -> Self {
+		Self { repeat, ..self }
+	}
+
+	/// Sets how long it takes to crossfade from one track to the
+	/// next (in seconds).
+	pub fn crossfade_duration(self, crossfade_duration: f64) -> Self {
+		Self {
+			crossfade_duration,
+			..self
+		}
+	}
+
+	/// Sets the mixer track the music should be played on.
+	pub fn track<T: Into<InstanceTrackIndex>>(self, track: T) -> Self {
+		Self {
+			track: track.into(),
+			..self
+		}
+	}
+}
+
+impl Default for JukeboxSettings {
+	fn default() -> Self {
+		Self {
+			shuffle: false,
+			avoid_repeats: 1,
+			repeat: true,
+			crossfade_duration: 0.0,
+			track: InstanceTrackIndex::default(),
+		}
+	}
+}
@@ -0,0 +1,115 @@
+use crate::{
+	manager::{AudioManager, AudioManagerSettings, Backend},
+	testing::{constant_sound, create_manager},
+};
+
+use super::{Jukebox, JukeboxEvent, JukeboxSettings};
+
+fn create_jukebox(
+	settings: JukeboxSettings,
+	num_tracks: usize,
+) -> (AudioManager, Backend, Jukebox<usize>) {
+	let (mut manager, backend) = create_manager();
+	let mut jukebox = Jukebox::new(settings);
+	for i in 0..num_tracks {
+		let sound_handle = manager
+			.add_sound(constant_sound(0.0, Default::default()))
+			.unwrap();
+		jukebox.add_track(&sound_handle, i);
+	}
+	(manager, backend, jukebox)
+}
+
+fn next_track_change(jukebox: &mut Jukebox<usize>) -> Option<usize> {
+	match jukebox.pop_event() {
+		Some(JukeboxEvent::TrackChanged { index, metadata }) => {
+			assert_eq!(index, metadata);
+			Some(index)
+		}
+		_ => None,
+	}
+}
+
+#[test]
+fn plays_tracks_in_order() {
+	let (_manager, _backend, mut jukebox) = create_jukebox(JukeboxSettings::default(), 3);
+	jukebox.play().unwrap();
+	assert_eq!(next_track_change(&mut jukebox), Some(0));
+	for expected_index in [1, 2, 0, 1].iter() {
+		jukebox.skip().unwrap();
+		assert_eq!(next_track_change(&mut jukebox), Some(*expected_index));
+		assert_eq!(jukebox.current_metadata(), Some(expected_index));
+	}
+}
+
+#[test]
+fn finishes_after_last_track_without_repeat() {
+	let (_manager, _backend, mut jukebox) = create_jukebox(JukeboxSettings::new().repeat(false), 2);
+	jukebox.play().unwrap();
+	jukebox.skip().unwrap();
+	jukebox.skip().unwrap();
+	assert_eq!(next_track_change(&mut jukebox), Some(0));
+	assert_eq!(next_track_change(&mut jukebox), Some(1));
+	assert_eq!(jukebox.pop_event(), Some(JukeboxEvent::Finished));
+	assert!(!jukebox.is_playing());
+}
+
+#[test]
+fn goes_back_through_history() {
+	let (_manager, _backend, mut jukebox) = create_jukebox(JukeboxSettings::default(), 3);
+	jukebox.play().unwrap();
+	jukebox.play_track(2).unwrap();
+	jukebox.skip().unwrap();
+	jukebox.previous().unwrap();
+	assert_eq!(jukebox.current_track(), Some(2));
+	jukebox.previous().unwrap();
+	assert_eq!(jukebox.current_track(), Some(0));
+}
+
+#[test]
+fn shuffle_avoids_recently_played_tracks() {
+	let (_manager, mut backend, mut jukebox) =
+		create_jukebox(JukeboxSettings::new().shuffle(true).avoid_repeats(3), 4);
+	jukebox.play().unwrap();
+	let mut played = vec![next_track_change(&mut jukebox).unwrap()];
+	for _ in 0..50 {
+		jukebox.skip().unwrap();
+		backend.process();
+		let index = next_track_change(&mut jukebox).unwrap();
+		assert!(
+			!played.iter().rev().take(3).any(|recent| *recent == index),
+			"Shuffled jukeboxes should not pick recently played tracks"
+		);
+		played.push(index);
+	}
+}
+
+#[test]
+fn shuffles_with_the_manager_seed() {
+	fn shuffle(seed: u64) -> Vec<usize> {
+		let (mut manager, _backend) = AudioManager::new_headless(
+			AudioManagerSettings {
+				random_seed: Some(seed),
+				..Default::default()
+			},
+			48000,
+		);
+		let mut jukebox = Jukebox::new(JukeboxSettings::new().shuffle(true));
+		for i in 0..8 {
+			let sound_handle = manager
+				.add_sound(constant_sound(0.0, Default::default()))
+				.unwrap();
+			jukebox.add_track(&sound_handle, i);
+		}
+		jukebox.play().unwrap();
+		let mut played = vec![next_track_change(&mut jukebox).unwrap()];
+		for _ in 0..20 {
+			jukebox.skip().unwrap();
+			played.push(next_track_change(&mut jukebox).unwrap());
+		}
+		played
+	}
+
+	assert_eq!(shuffle(1), shuffle(1));
+	assert_ne!(shuffle(1), shuffle(2));
+}
@@ -14,6 +14,7 @@
 //! #
 //! # use kira::manager::{AudioManager, AudioManagerSettings};
 //! #
+//! # #[cfg(feature = "cpal")]
 //! let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
@@ -31,7 +32,7 @@
 //! # 	sound::SoundSettings,
 //! # };
 //! #
-//! # let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
 //! let mut sound_handle = audio_manager.load_sound("sound.ogg", SoundSettings::default())?;
 //! sound_handle.play(InstanceSettings::default())?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
@@ -50,7 +51,7 @@
 //! # 	Tempo,
 //! # };
 //! #
-//! # let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
 //! let sound_handle = audio_manager.load_sound(
 //! 	"loop.ogg",
 //! 	SoundSettings::new().semantic_duration(Tempo(128.0).beats_to_seconds(8.0)),
@@ -85,7 +86,7 @@
 //! 	Kick,
 //! }
 //!
-//! # let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
 //! let kick_sound_handle = audio_manager.load_sound("kick.wav", SoundSettings::default())?;
 //! let mut metronome_handle =
 //! 	audio_manager.add_metronome(MetronomeSettings::new().tempo(Tempo(150.0)))?;
@@ -108,26 +109,57 @@
 
 pub mod arrangement;
 pub mod audio_stream;
+#[cfg(all(
+	feature = "bevy",
+	any(feature = "mp3", feature = "ogg", feature = "flac", feature = "wav")
+))]
+pub mod bevy;
 mod command;
+mod decibels;
+pub mod dsp;
 mod duration;
 mod frame;
 pub mod group;
 pub mod instance;
+pub mod jukebox;
+#[cfg(feature = "lua")]
+pub mod lua;
 pub mod manager;
 pub mod metronome;
+#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+pub mod midi;
 pub mod mixer;
+pub mod multi_sound;
+mod panning;
 pub mod parameter;
 mod playable;
+#[cfg(all(
+	feature = "preview",
+	feature = "cpal",
+	not(target_arch = "wasm32"),
+	any(feature = "mp3", feature = "ogg", feature = "flac", feature = "wav")
+))]
+pub mod preview;
+mod semitones;
 pub mod sequence;
 pub mod sound;
+pub mod sound_bank;
+mod start_time;
 mod static_container;
 mod tempo;
+#[cfg(test)]
+mod testing;
 mod util;
 mod value;
+pub mod voice;
 
-pub use command::producer::CommandError;
+pub use command::producer::{CommandError, CommandOverflowStrategy};
+pub use decibels::Decibels;
 pub use duration::Duration;
-pub use frame::Frame;
-pub use playable::PlayableId;
+pub use frame::{Frame, Sample};
+pub use panning::PanningLaw;
+pub use playable::{PlayableId, PlaybackStatistics};
+pub use semitones::Semitones;
+pub use start_time::StartTime;
 pub use tempo::Tempo;
 pub use value::{CachedValue, Value};
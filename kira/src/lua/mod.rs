@@ -0,0 +1,396 @@
+//! Lets Lua scripts build and control sequences.
+//!
+//! With the `lua` feature enabled, [`create_module`] creates a
+//! Lua table of functions for building sequences and starting
+//! them on an audio manager. This lets audio designers script
+//! interactive music without recompiling the game.
+//!
+//! The `lua` feature builds and links a vendored Lua 5.4, so
+//! scripts run the same on every platform without a system Lua.
+//!
+//! ```no_run
+//! # use std::sync::{Arc, Mutex};
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sound::{Sound, SoundSettings},
+//! # };
+//! # use mlua::Lua;
+//! # #[cfg(feature = "cpal")]
+//! # {
+//! let mut audio_manager = AudioManager::new(AudioManagerSettings::default())?;
+//! let drums = audio_manager.add_sound(Sound::from_file("drums.ogg", SoundSettings::default())?)?;
+//! audio_manager.set_name("drums", &drums)?;
+//! let audio_manager = Arc::new(Mutex::new(audio_manager));
+//! let lua = Lua::new();
+//! lua.globals()
+//! 	.set("kira", kira::lua::create_module(&lua, audio_manager.clone())?)?;
+//! lua.load(
+//! 	r#"
+//! 		local sequence = kira.sequence()
+//! 		sequence:start_loop()
+//! 		sequence:play("drums", { volume = 0.5 })
+//! 		sequence:emit("bar")
+//! 		sequence:wait(2.0)
+//! 		music = kira.start_sequence(sequence)
+//! 	"#,
+//! )
+//! .exec()?;
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Sequences built in Lua work like [`SequenceDefinition`]s:
+//! they refer to sounds, arrangements, and parameters by the
+//! names given to them with
+//! [`AudioManager::set_name`](crate::manager::AudioManager::set_name),
+//! and to the instances they play by names of their own.
+//! Sequence events and signals are strings.
+//!
+//! The table has the following functions:
+//!
+//! - `sequence()` creates an empty sequence.
+//! - `start_sequence(sequence, settings)` starts a sequence and
+//!   returns a handle to the sequence instance. `settings` is an
+//!   optional table that can set the `speed` of the sequence.
+//!
+//! Sequences have the following methods, each of which adds
+//! a step to the sequence:
+//!
+//! - `wait(seconds)`
+//! - `wait_beats(beats)`
+//! - `wait_for_interval(beats)`
+//! - `start_loop()`
+//! - `play(sound_name, settings)` and
+//!   `play_arrangement(arrangement_name, settings)`, where
+//!   `settings` is an optional table with the `instance` name to
+//!   give the instance and its `volume`, `playback_rate`,
+//!   `panning`, `start_position`, and `loop_start`.
+//! - `chance(probability)`
+//! - `set_instance_volume(instance, volume)`,
+//!   `set_instance_playback_rate(instance, playback_rate)`, and
+//!   `set_instance_panning(instance, panning)`
+//! - `pause_instance(instance, fade_duration)`,
+//!   `resume_instance(instance, fade_duration)`, and
+//!   `stop_instance(instance, fade_duration)`, where
+//!   `fade_duration` is optional and the instance's default
+//!   fade is used if it's left out
+//! - `set_parameter(parameter_name, target, tween_duration)`,
+//!   where `tween_duration` is optional
+//! - `emit(event)`
+//! - `wait_for_signal(event)`
+//!
+//! Sequence instances have the methods `pause()`, `resume()`,
+//! `stop()`, `mute()`, `unmute()`, `set_speed(speed)`,
+//! `signal(event)`, `pop_event()`, which returns the next event
+//! or `nil`, `state()`, which returns `"playing"`, `"paused"`,
+//! or `"finished"`, and `loop_count()`.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use mlua::{Lua, Table, UserData, UserDataMethods, UserDataRef};
+
+use crate::{
+	instance::{
+		InstanceFadeTween, InstanceSettings, PauseInstanceSettings, ResumeInstanceSettings,
+		StopInstanceSettings,
+	},
+	manager::{names::PlayableName, AudioManager},
+	parameter::tween::Tween,
+	sequence::{
+		definition::{SequenceDefinition, SequenceStepDefinition},
+		handle::SequenceInstanceHandle,
+		SequenceInstanceSettings, SequenceInstanceState,
+	},
+	Duration,
+};
+
+fn lock(audio_manager: &Mutex<AudioManager>) -> mlua::Result<MutexGuard<'_, AudioManager>> {
+	audio_manager
+		.lock()
+		.map_err(|_| mlua::Error::runtime("The audio manager's mutex was poisoned"))
+}
+
+/// Reads the instance name and [`InstanceSettings`] from
+/// the optional settings table passed to `play`.
+fn instance_settings(settings: Option<Table>) -> mlua::Result<(Option<String>, InstanceSettings)> {
+	let settings = match settings {
+		Some(settings) => settings,
+		None => return Ok((None, InstanceSettings::new())),
+	};
+	let mut instance_settings = InstanceSettings::new();
+	if let Some(volume) = settings.get::<Option<f64>>("volume")? {
+		instance_settings = instance_settings.volume(volume);
+	}
+	if let Some(playback_rate) = settings.get::<Option<f64>>("playback_rate")? {
+		instance_settings = instance_settings.playback_rate(playback_rate);
+	}
+	if let Some(panning) = settings.get::<Option<f64>>("panning")? {
+		instance_settings = instance_settings.panning(panning);
+	}
+	if let Some(start_position) = settings.get::<Option<f64>>("start_position")? {
+		instance_settings = instance_settings.start_position(start_position);
+	}
+	if let Some(loop_start) = settings.get::<Option<f64>>("loop_start")? {
+		instance_settings = instance_settings.loop_start(loop_start);
+	}
+	Ok((settings.get("instance")?, instance_settings))
+}
+
+/// Turns the optional fade duration passed to `pause_instance`,
+/// `resume_instance`, and `stop_instance` into a fade tween.
+fn fade_tween(fade_duration: Option<f64>) -> InstanceFadeTween {
+	match fade_duration {
+		Some(duration) => Tween::linear(duration).into(),
+		None => InstanceFadeTween::Default,
+	}
+}
+
+/// A sequence built by a Lua script.
+///
+/// Steps are added using the Lua methods listed in the
+/// [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct LuaSequence {
+	definition: SequenceDefinition<String>,
+}
+
+impl LuaSequence {
+	/// Creates a new, empty sequence.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the definition of the sequence.
+	pub fn definition(&self) -> &SequenceDefinition<String> {
+		&self.definition
+	}
+
+	fn add_step(&mut self, step: SequenceStepDefinition<String>) {
+		self.definition.steps.push(step);
+	}
+}
+
+impl From<SequenceDefinition<String>> for LuaSequence {
+	fn from(definition: SequenceDefinition<String>) -> Self {
+		Self { definition }
+	}
+}
+
+impl UserData for LuaSequence {
+	fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+		methods.add_method_mut("wait", |_, this, seconds: f64| {
+			this.add_step(SequenceStepDefinition::Wait(Duration::Seconds(seconds)));
+			Ok(())
+		});
+		methods.add_method_mut("wait_beats", |_, this, beats: f64| {
+			this.add_step(SequenceStepDefinition::Wait(Duration::Beats(beats)));
+			Ok(())
+		});
+		methods.add_method_mut("wait_for_interval", |_, this, interval: f64| {
+			this.add_step(SequenceStepDefinition::WaitForInterval(interval));
+			Ok(())
+		});
+		methods.add_method_mut("start_loop", |_, this, ()| {
+			this.add_step(SequenceStepDefinition::StartLoop);
+			Ok(())
+		});
+		methods.add_method_mut(
+			"play",
+			|_, this, (name, settings): (String, Option<Table>)| {
+				let (instance, settings) = instance_settings(settings)?;
+				this.add_step(SequenceStepDefinition::Play {
+					playable: PlayableName::Sound(name),
+					instance,
+					settings,
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"play_arrangement",
+			|_, this, (name, settings): (String, Option<Table>)| {
+				let (instance, settings) = instance_settings(settings)?;
+				this.add_step(SequenceStepDefinition::Play {
+					playable: PlayableName::Arrangement(name),
+					instance,
+					settings,
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut("chance", |_, this, probability: f64| {
+			this.add_step(SequenceStepDefinition::Chance(probability));
+			Ok(())
+		});
+		methods.add_method_mut(
+			"set_instance_volume",
+			|_, this, (instance, volume): (String, f64)| {
+				this.add_step(SequenceStepDefinition::SetInstanceVolume {
+					instance,
+					volume: volume.into(),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"set_instance_playback_rate",
+			|_, this, (instance, playback_rate): (String, f64)| {
+				this.add_step(SequenceStepDefinition::SetInstancePlaybackRate {
+					instance,
+					playback_rate: playback_rate.into(),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"set_instance_panning",
+			|_, this, (instance, panning): (String, f64)| {
+				this.add_step(SequenceStepDefinition::SetInstancePanning {
+					instance,
+					panning: panning.into(),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"pause_instance",
+			|_, this, (instance, fade_duration): (String, Option<f64>)| {
+				this.add_step(SequenceStepDefinition::PauseInstance {
+					instance,
+					settings: PauseInstanceSettings::new().fade_tween(fade_tween(fade_duration)),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"resume_instance",
+			|_, this, (instance, fade_duration): (String, Option<f64>)| {
+				this.add_step(SequenceStepDefinition::ResumeInstance {
+					instance,
+					settings: ResumeInstanceSettings::new().fade_tween(fade_tween(fade_duration)),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"stop_instance",
+			|_, this, (instance, fade_duration): (String, Option<f64>)| {
+				this.add_step(SequenceStepDefinition::StopInstance {
+					instance,
+					settings: StopInstanceSettings::new().fade_tween(fade_tween(fade_duration)),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut(
+			"set_parameter",
+			|_, this, (parameter, target, tween_duration): (String, f64, Option<f64>)| {
+				this.add_step(SequenceStepDefinition::SetParameter {
+					parameter,
+					target,
+					tween: tween_duration.map(Tween::linear),
+				});
+				Ok(())
+			},
+		);
+		methods.add_method_mut("emit", |_, this, event: String| {
+			this.add_step(SequenceStepDefinition::Emit(event));
+			Ok(())
+		});
+		methods.add_method_mut("wait_for_signal", |_, this, event: String| {
+			this.add_step(SequenceStepDefinition::WaitForSignal(event));
+			Ok(())
+		});
+	}
+}
+
+/// A handle to a sequence instance started by a Lua script.
+#[derive(Debug, Clone)]
+pub struct LuaSequenceInstance {
+	handle: SequenceInstanceHandle<String>,
+}
+
+impl LuaSequenceInstance {
+	/// Returns the handle to the sequence instance.
+	pub fn handle(&mut self) -> &mut SequenceInstanceHandle<String> {
+		&mut self.handle
+	}
+}
+
+impl UserData for LuaSequenceInstance {
+	fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+		methods.add_method_mut("pause", |_, this, ()| {
+			this.handle.pause().map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("resume", |_, this, ()| {
+			this.handle.resume().map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("stop", |_, this, ()| {
+			this.handle.stop().map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("mute", |_, this, ()| {
+			this.handle.mute().map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("unmute", |_, this, ()| {
+			this.handle.unmute().map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("set_speed", |_, this, speed: f64| {
+			this.handle.set_speed(speed).map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("signal", |_, this, event: String| {
+			this.handle.signal(&event).map_err(mlua::Error::external)
+		});
+		methods.add_method_mut("pop_event", |_, this, ()| {
+			Ok(this
+				.handle
+				.pop_event()
+				.map_err(mlua::Error::external)?
+				.cloned())
+		});
+		methods.add_method("state", |_, this, ()| {
+			Ok(match this.handle.state() {
+				SequenceInstanceState::Playing => "playing",
+				SequenceInstanceState::Paused => "paused",
+				SequenceInstanceState::Finished => "finished",
+			})
+		});
+		methods.add_method("loop_count", |_, this, ()| Ok(this.handle.loop_count()));
+	}
+}
+
+/// Creates a table of functions that let Lua scripts build
+/// sequences and start them on the given audio manager.
+pub fn create_module(lua: &Lua, audio_manager: Arc<Mutex<AudioManager>>) -> mlua::Result<Table> {
+	let module = lua.create_table()?;
+	module.set(
+		"sequence",
+		lua.create_function(|_, ()| Ok(LuaSequence::new()))?,
+	)?;
+	module.set(
+		"start_sequence",
+		lua.create_function(
+			move |_, (sequence, settings): (UserDataRef<LuaSequence>, Option<Table>)| {
+				let mut instance_settings = SequenceInstanceSettings::new();
+				if let Some(speed) = settings
+					.map(|settings| settings.get::<Option<f64>>("speed"))
+					.transpose()?
+					.flatten()
+				{
+					instance_settings = instance_settings.speed(speed);
+				}
+				let mut audio_manager = lock(&audio_manager)?;
+				let sequence = audio_manager
+					.sequence_from_definition(sequence.definition())
+					.map_err(mlua::Error::external)?;
+				let handle = audio_manager
+					.start_sequence(sequence, instance_settings)
+					.map_err(mlua::Error::external)?;
+				Ok(LuaSequenceInstance { handle })
+			},
+		)?,
+	)?;
+	Ok(module)
+}
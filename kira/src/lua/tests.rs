@@ -0,0 +1,173 @@
+use std::sync::{Arc, Mutex};
+
+use mlua::Lua;
+
+use crate::{
+	manager::{AudioManager, AudioManagerSettings},
+	testing::{constant_sound, create_manager},
+	Frame,
+};
+
+use super::create_module;
+
+/// Creates a Lua state with the module stored in the
+/// `kira` global.
+fn create_lua(audio_manager: AudioManager) -> Lua {
+	let lua = Lua::new();
+	let module = create_module(&lua, Arc::new(Mutex::new(audio_manager))).unwrap();
+	lua.globals().set("kira", module).unwrap();
+	lua
+}
+
+#[test]
+fn plays_sounds_from_lua_sequences() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	manager.set_name("drums", &sound_handle).unwrap();
+	let lua = create_lua(manager);
+	lua.load(
+		r#"
+			local sequence = kira.sequence()
+			sequence:play("drums", { instance = "drums", volume = 0.5, panning = 0.0 })
+			sequence:emit("played")
+			sequence:wait(0.01)
+			sequence:stop_instance("drums")
+			sequence:emit("stopped")
+			music = kira.start_sequence(sequence)
+		"#,
+	)
+	.exec()
+	.unwrap();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::new(0.5, 0.0));
+	let next_event = || {
+		lua.load("return music:pop_event()")
+			.eval::<Option<String>>()
+	};
+	assert_eq!(next_event().unwrap().as_deref(), Some("played"));
+	assert_eq!(next_event().unwrap(), None);
+	for _ in 0..1000 {
+		backend.process();
+	}
+	assert_eq!(next_event().unwrap().as_deref(), Some("stopped"));
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+	assert_eq!(
+		lua.load("return music:state()").eval::<String>().unwrap(),
+		"finished"
+	);
+}
+
+#[test]
+fn controls_sequence_instances_from_lua() {
+	let (manager, mut backend) = create_manager();
+	let lua = create_lua(manager);
+	lua.load(
+		r#"
+			local sequence = kira.sequence()
+			sequence:start_loop()
+			sequence:emit("beat")
+			sequence:wait_for_signal("next")
+			music = kira.start_sequence(sequence, { speed = 2.0 })
+		"#,
+	)
+	.exec()
+	.unwrap();
+	backend.process();
+	assert_eq!(
+		lua.load("return music:pop_event()")
+			.eval::<Option<String>>()
+			.unwrap()
+			.as_deref(),
+		Some("beat")
+	);
+	lua.load("music:signal('next')").exec().unwrap();
+	backend.process();
+	assert_eq!(
+		lua.load("return music:loop_count()")
+			.eval::<usize>()
+			.unwrap(),
+		1
+	);
+	lua.load("music:pause()").exec().unwrap();
+	backend.process();
+	assert_eq!(
+		lua.load("return music:state()").eval::<String>().unwrap(),
+		"paused"
+	);
+	// signals for events the sequence never waits for are errors
+	assert!(lua.load("music:signal('previous')").exec().is_err());
+}
+
+#[test]
+fn reports_missing_names_as_lua_errors() {
+	let (manager, _) = create_manager();
+	let lua = create_lua(manager);
+	let error = lua
+		.load(
+			r#"
+				local sequence = kira.sequence()
+				sequence:play("footstep")
+				kira.start_sequence(sequence)
+			"#,
+		)
+		.exec()
+		.unwrap_err();
+	assert!(error.to_string().contains("No sound is named \"footstep\""));
+}
+
+#[test]
+fn fades_out_scripted_stops_without_a_fade_duration() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			declick_duration: 100.0 / 48000.0,
+			..Default::default()
+		},
+		48000,
+	);
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	manager.set_name("drums", &sound_handle).unwrap();
+	let lua = create_lua(manager);
+	lua.load(
+		r#"
+			local sequence = kira.sequence()
+			sequence:play("drums", { instance = "drums", panning = 0.0 })
+			sequence:wait(0.01)
+			sequence:stop_instance("drums")
+			sequence:emit("stopped")
+			music = kira.start_sequence(sequence)
+		"#,
+	)
+	.exec()
+	.unwrap();
+	let mut stopped = false;
+	for _ in 0..1000 {
+		backend.process();
+		if lua
+			.load("return music:pop_event()")
+			.eval::<Option<String>>()
+			.unwrap()
+			.is_some()
+		{
+			stopped = true;
+			break;
+		}
+	}
+	assert!(stopped);
+	// the instance fades out over the declick duration
+	// instead of cutting off
+	for _ in 0..50 {
+		backend.process();
+	}
+	let volume = backend.process().left;
+	assert!(volume > 0.0 && volume < 1.0);
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+}
@@ -4,31 +4,49 @@ use crate::{
 	group::GroupId,
 	metronome::MetronomeId,
 	mixer::{SendTrackId, SubTrackId},
-	parameter::ParameterId,
+	parameter::{ModulationId, ParameterId},
 	sound::SoundId,
 };
 
+use std::sync::atomic::Ordering;
+
 use indexmap::IndexSet;
 
 use super::{
+	backend::SharedBackendState,
 	error::{
-		AddArrangementError, AddGroupError, AddMetronomeError, AddParameterError,
-		AddSendTrackError, AddSoundError, AddStreamError, AddSubTrackError, RemoveArrangementError,
-		RemoveGroupError, RemoveMetronomeError, RemoveParameterError, RemoveSendTrackError,
-		RemoveSoundError, RemoveStreamError, RemoveSubTrackError,
+		AddArrangementError, AddGroupError, AddMetronomeError, AddModulationError,
+		AddParameterError, AddSendTrackError, AddSoundError, AddStreamError, AddSubTrackError,
+		RemoveArrangementError, RemoveGroupError, RemoveMetronomeError, RemoveModulationError,
+		RemoveParameterError, RemoveSendTrackError, RemoveSoundError, RemoveStreamError,
+		RemoveSubTrackError,
 	},
+	resources::{ResourceKind, ResourceUsage},
 	AudioManagerSettings,
 };
 
+fn resource_usage<T>(kind: ResourceKind, ids: &IndexSet<T>) -> ResourceUsage {
+	ResourceUsage {
+		kind,
+		used: ids.len(),
+		capacity: ids.capacity(),
+	}
+}
+
 pub struct ActiveIds {
 	pub active_sound_ids: IndexSet<SoundId>,
 	pub active_arrangement_ids: IndexSet<ArrangementId>,
 	pub active_parameter_ids: IndexSet<ParameterId>,
+	pub active_modulation_ids: IndexSet<ModulationId>,
 	pub active_sub_track_ids: IndexSet<SubTrackId>,
 	pub active_send_track_ids: IndexSet<SendTrackId>,
 	pub active_group_ids: IndexSet<GroupId>,
 	pub active_metronome_ids: IndexSet<MetronomeId>,
 	pub active_stream_ids: IndexSet<AudioStreamId>,
+	// instances and sequences are only tracked on the audio thread,
+	// so only their capacity is kept here
+	pub instance_capacity: usize,
+	pub sequence_instance_capacity: usize,
 }
 
 impl ActiveIds {
@@ -37,17 +55,46 @@ impl ActiveIds {
 			active_sound_ids: IndexSet::with_capacity(settings.num_sounds),
 			active_arrangement_ids: IndexSet::with_capacity(settings.num_arrangements),
 			active_parameter_ids: IndexSet::with_capacity(settings.num_parameters),
+			active_modulation_ids: IndexSet::with_capacity(settings.num_modulations),
 			active_sub_track_ids: IndexSet::with_capacity(settings.num_sub_tracks),
 			active_send_track_ids: IndexSet::with_capacity(settings.num_send_tracks),
 			active_group_ids: IndexSet::with_capacity(settings.num_groups),
 			active_metronome_ids: IndexSet::with_capacity(settings.num_metronomes),
 			active_stream_ids: IndexSet::with_capacity(settings.num_streams),
+			instance_capacity: settings.num_instances,
+			sequence_instance_capacity: settings.num_sequences,
+		}
+	}
+
+	/// Returns how many resources of a kind are in use.
+	pub fn usage(&self, kind: ResourceKind, shared_state: &SharedBackendState) -> ResourceUsage {
+		match kind {
+			ResourceKind::Sound => resource_usage(kind, &self.active_sound_ids),
+			ResourceKind::Arrangement => resource_usage(kind, &self.active_arrangement_ids),
+			ResourceKind::Parameter => resource_usage(kind, &self.active_parameter_ids),
+			ResourceKind::Modulation => resource_usage(kind, &self.active_modulation_ids),
+			ResourceKind::SubTrack => resource_usage(kind, &self.active_sub_track_ids),
+			ResourceKind::SendTrack => resource_usage(kind, &self.active_send_track_ids),
+			ResourceKind::Group => resource_usage(kind, &self.active_group_ids),
+			ResourceKind::Metronome => resource_usage(kind, &self.active_metronome_ids),
+			ResourceKind::Stream => resource_usage(kind, &self.active_stream_ids),
+			ResourceKind::Instance => ResourceUsage {
+				kind,
+				used: shared_state.num_instances.load(Ordering::Relaxed),
+				capacity: self.instance_capacity,
+			},
+			ResourceKind::SequenceInstance => ResourceUsage {
+				kind,
+				used: shared_state.num_sequence_instances.load(Ordering::Relaxed),
+				capacity: self.sequence_instance_capacity,
+			},
 		}
 	}
 
 	pub fn add_sound_id(&mut self, id: SoundId) -> Result<(), AddSoundError> {
-		if self.active_sound_ids.len() >= self.active_sound_ids.capacity() {
-			return Err(AddSoundError::SoundLimitReached);
+		let usage = resource_usage(ResourceKind::Sound, &self.active_sound_ids);
+		if usage.is_full() {
+			return Err(AddSoundError::SoundLimitReached(usage));
 		}
 		self.active_sound_ids.insert(id);
 		Ok(())
@@ -61,8 +108,9 @@ impl ActiveIds {
 	}
 
 	pub fn add_arrangement_id(&mut self, id: ArrangementId) -> Result<(), AddArrangementError> {
-		if self.active_arrangement_ids.len() >= self.active_arrangement_ids.capacity() {
-			return Err(AddArrangementError::ArrangementLimitReached);
+		let usage = resource_usage(ResourceKind::Arrangement, &self.active_arrangement_ids);
+		if usage.is_full() {
+			return Err(AddArrangementError::ArrangementLimitReached(usage));
 		}
 		self.active_arrangement_ids.insert(id);
 		Ok(())
@@ -79,8 +127,9 @@ impl ActiveIds {
 	}
 
 	pub fn add_parameter_id(&mut self, id: ParameterId) -> Result<(), AddParameterError> {
-		if self.active_parameter_ids.len() >= self.active_parameter_ids.capacity() {
-			return Err(AddParameterError::ParameterLimitReached);
+		let usage = resource_usage(ResourceKind::Parameter, &self.active_parameter_ids);
+		if usage.is_full() {
+			return Err(AddParameterError::ParameterLimitReached(usage));
 		}
 		self.active_parameter_ids.insert(id);
 		Ok(())
@@ -93,9 +142,26 @@ impl ActiveIds {
 		Ok(())
 	}
 
+	pub fn add_modulation_id(&mut self, id: ModulationId) -> Result<(), AddModulationError> {
+		let usage = resource_usage(ResourceKind::Modulation, &self.active_modulation_ids);
+		if usage.is_full() {
+			return Err(AddModulationError::ModulationLimitReached(usage));
+		}
+		self.active_modulation_ids.insert(id);
+		Ok(())
+	}
+
+	pub fn remove_modulation_id(&mut self, id: ModulationId) -> Result<(), RemoveModulationError> {
+		if !self.active_modulation_ids.remove(&id) {
+			return Err(RemoveModulationError::NoModulationWithId(id));
+		}
+		Ok(())
+	}
+
 	pub fn add_sub_track_id(&mut self, id: SubTrackId) -> Result<(), AddSubTrackError> {
-		if self.active_sub_track_ids.len() >= self.active_sub_track_ids.capacity() {
-			return Err(AddSubTrackError::TrackLimitReached);
+		let usage = resource_usage(ResourceKind::SubTrack, &self.active_sub_track_ids);
+		if usage.is_full() {
+			return Err(AddSubTrackError::TrackLimitReached(usage));
 		}
 		self.active_sub_track_ids.insert(id);
 		Ok(())
@@ -109,8 +175,9 @@ impl ActiveIds {
 	}
 
 	pub fn add_send_track_id(&mut self, id: SendTrackId) -> Result<(), AddSendTrackError> {
-		if self.active_send_track_ids.len() >= self.active_send_track_ids.capacity() {
-			return Err(AddSendTrackError::TrackLimitReached);
+		let usage = resource_usage(ResourceKind::SendTrack, &self.active_send_track_ids);
+		if usage.is_full() {
+			return Err(AddSendTrackError::TrackLimitReached(usage));
 		}
 		self.active_send_track_ids.insert(id);
 		Ok(())
@@ -124,8 +191,9 @@ impl ActiveIds {
 	}
 
 	pub fn add_group_id(&mut self, id: GroupId) -> Result<(), AddGroupError> {
-		if self.active_group_ids.len() >= self.active_group_ids.capacity() {
-			return Err(AddGroupError::GroupLimitReached);
+		let usage = resource_usage(ResourceKind::Group, &self.active_group_ids);
+		if usage.is_full() {
+			return Err(AddGroupError::GroupLimitReached(usage));
 		}
 		self.active_group_ids.insert(id);
 		Ok(())
@@ -139,8 +207,9 @@ impl ActiveIds {
 	}
 
 	pub fn add_metronome_id(&mut self, id: MetronomeId) -> Result<(), AddMetronomeError> {
-		if self.active_metronome_ids.len() >= self.active_metronome_ids.capacity() {
-			return Err(AddMetronomeError::MetronomeLimitReached);
+		let usage = resource_usage(ResourceKind::Metronome, &self.active_metronome_ids);
+		if usage.is_full() {
+			return Err(AddMetronomeError::MetronomeLimitReached(usage));
 		}
 		self.active_metronome_ids.insert(id);
 		Ok(())
@@ -154,8 +223,9 @@ impl ActiveIds {
 	}
 
 	pub fn add_stream_id(&mut self, id: AudioStreamId) -> Result<(), AddStreamError> {
-		if self.active_stream_ids.len() >= self.active_stream_ids.capacity() {
-			return Err(AddStreamError::StreamLimitReached);
+		let usage = resource_usage(ResourceKind::Stream, &self.active_stream_ids);
+		if usage.is_full() {
+			return Err(AddStreamError::StreamLimitReached(usage));
 		}
 		self.active_stream_ids.insert(id);
 		Ok(())
@@ -1,24 +1,112 @@
 use crate::{
 	command::InstanceCommand,
-	group::groups::Groups,
-	instance::{Instance, InstanceId, StopInstanceSettings},
-	parameter::Parameters,
+	frame::Frame,
+	group::{groups::Groups, GroupId, GroupMuffle},
+	instance::{
+		Instance, InstanceId, InstanceState, PauseInstanceSettings, ResumeInstanceSettings,
+		StopInstanceSettings,
+	},
+	manager::{
+		error::CommandFailure,
+		resources::{ResourceKind, ResourceUsage},
+		snapshot::SnapshotRequest,
+		trace::{TraceEventKind, Tracer},
+		InstanceLimitBehavior,
+	},
+	metronome::Metronomes,
+	mixer::{effect::EffectId, effect_slot::EffectSlot, TrackIndex},
+	parameter::{tween::Tween, Parameters},
 	playable::{PlayableId, Playables},
 	static_container::{index_map::StaticIndexMap, vec::StaticVec},
 };
 
-use super::mixer::Mixer;
+use super::{mixer::Mixer, AudioClock};
 
 pub(crate) struct Instances {
 	instances: StaticIndexMap<InstanceId, Instance>,
 	instances_to_remove: StaticVec<InstanceId>,
+	global_cooldown: Option<f64>,
+	global_cooldown_timer: f64,
+	declick_tween: Option<Tween>,
+	limit_behavior: InstanceLimitBehavior,
 }
 
 impl Instances {
-	pub fn new(capacity: usize) -> Self {
+	pub fn new(
+		capacity: usize,
+		global_cooldown: Option<f64>,
+		declick_tween: Option<Tween>,
+		limit_behavior: InstanceLimitBehavior,
+	) -> Self {
 		Self {
 			instances: StaticIndexMap::new(capacity),
 			instances_to_remove: StaticVec::new(capacity),
+			global_cooldown,
+			global_cooldown_timer: 0.0,
+			declick_tween,
+			limit_behavior,
+		}
+	}
+
+	/// Updates the global cooldown timer.
+	pub fn update_cooldown(&mut self, dt: f64) {
+		if self.global_cooldown_timer > 0.0 {
+			self.global_cooldown_timer -= dt;
+		}
+	}
+
+	pub fn get(&self, id: InstanceId) -> Option<&Instance> {
+		self.instances.get(&id)
+	}
+
+	pub fn num_instances(&self) -> usize {
+		self.instances.len()
+	}
+
+	pub fn snapshot(&self, request: &mut SnapshotRequest) {
+		for (id, instance) in &self.instances {
+			if let Some(snapshot) = instance.snapshot(*id) {
+				request.add_instance(snapshot);
+			}
+		}
+	}
+
+	/// Returns `true` if any instance is playing the given
+	/// sound or arrangement.
+	pub fn uses_playable(&self, playable: PlayableId) -> bool {
+		self.instances
+			.iter()
+			.any(|(_, instance)| instance.playable_id() == playable)
+	}
+
+	pub fn num_playing(&self) -> usize {
+		self.instances
+			.iter()
+			.filter(|(_, instance)| instance.playing())
+			.count()
+	}
+
+	pub fn pause_on_track(&mut self, track: TrackIndex, settings: PauseInstanceSettings) {
+		for (_, instance) in &mut self.instances {
+			if instance.track_index() == track {
+				instance.pause(settings);
+			}
+		}
+	}
+
+	pub fn resume_on_track(&mut self, track: TrackIndex, settings: ResumeInstanceSettings) {
+		for (_, instance) in &mut self.instances {
+			if instance.track_index() == track {
+				instance.resume(settings);
+			}
+		}
+	}
+
+	pub fn stop_on_track(&mut self, track: TrackIndex, settings: StopInstanceSettings) {
+		for (_, instance) in &mut self.instances {
+			if instance.track_index() == track {
+				instance.stop(settings);
+			}
 		}
 	}
 
@@ -30,26 +118,170 @@ impl Instances {
 		}
 	}
 
+	/// Returns the first group the instance belongs to that
+	/// already has as many instances as it allows.
+	fn full_group(
+		&self,
+		instance: &Instance,
+		playables: &Playables,
+		all_groups: &Groups,
+	) -> Option<GroupId> {
+		all_groups.iter().find_map(|(group_id, group)| {
+			let max_instances = group.max_instances()?;
+			if !is_in_group(instance, *group_id, playables, all_groups) {
+				return None;
+			}
+			let num_instances = self
+				.instances
+				.iter()
+				.filter(|(_, instance)| {
+					!matches!(
+						instance.state(),
+						InstanceState::Stopping | InstanceState::Stopped
+					) && is_in_group(instance, *group_id, playables, all_groups)
+				})
+				.count();
+			if num_instances >= max_instances {
+				Some(*group_id)
+			} else {
+				None
+			}
+		})
+	}
+
+	fn effect_slot_mut(
+		&mut self,
+		instance_id: InstanceId,
+		effect_id: EffectId,
+	) -> Option<&mut EffectSlot> {
+		self.instances
+			.get_mut(&instance_id)
+			.and_then(|instance| instance.effect_mut(effect_id))
+	}
+
 	pub fn run_command(
 		&mut self,
 		command: InstanceCommand,
 		playables: &mut Playables,
-		all_groups: &Groups,
-	) {
+		all_groups: &mut Groups,
+		tracer: &mut Tracer,
+	) -> Result<Option<ResourceUsage>, CommandFailure> {
+		if let Some(id) = command.target_instance() {
+			if self.instances.get(&id).is_none() {
+				return Err(CommandFailure::NoInstanceWithId(id));
+			}
+		}
+		if let Some(id) = command.target_playable() {
+			if playables.playable(id).is_none() {
+				if let InstanceCommand::Play(_, mut instance) = command {
+					instance.discard();
+				}
+				return Err(CommandFailure::NoPlayableWithId(id));
+			}
+		}
+		let mut limit_reached = None;
 		match command {
-			InstanceCommand::Play(instance_id, instance) => {
-				if let Some(mut playable) = playables.playable_mut(instance.playable_id()) {
-					if !playable.cooling_down() {
-						// if we're at the instance limit, remove the instance that was
-						// started the longest time ago.
-						if self.instances.len() >= self.instances.capacity() {
-							self.instances.shift_remove_index(0);
+			InstanceCommand::Play(instance_id, mut instance) => {
+				if let Some(group) = self.full_group(&instance, playables, all_groups) {
+					tracer.record(TraceEventKind::InstanceGroupFull {
+						instance: instance_id,
+						playable: instance.playable_id(),
+						group,
+					});
+					instance.discard();
+				} else if let Some(mut playable) = playables.playable_mut(instance.playable_id()) {
+					let instance_groups = instance.groups();
+					if !playable.cooling_down()
+						&& !all_groups.cooling_down(|id, all_groups| {
+							playable.groups().has_ancestor(id, all_groups)
+								|| instance_groups.has_ancestor(id, all_groups)
+						}) && self.global_cooldown_timer <= 0.0
+					{
+						// if we're at the instance limit, either remove the instance that
+						// was started the longest time ago or reject the new one
+						let usage = ResourceUsage {
+							kind: ResourceKind::Instance,
+							used: self.instances.len(),
+							capacity: self.instances.capacity(),
+						};
+						if usage.is_full() {
+							if self.limit_behavior == InstanceLimitBehavior::RejectNew {
+								tracer.record(TraceEventKind::InstanceRejected {
+									instance: instance_id,
+									playable: instance.playable_id(),
+								});
+								instance.discard();
+								return Ok(Some(usage));
+							}
+							if let Some((stolen_id, mut stolen)) =
+								self.instances.shift_remove_index(0)
+							{
+								stolen.discard();
+								tracer.record(TraceEventKind::InstanceStolen {
+									instance: stolen_id,
+									by: instance_id,
+								});
+							}
+							limit_reached = Some(usage);
 						}
-						self.instances.try_insert(instance_id, instance).ok();
+						tracer.record(TraceEventKind::InstanceStarted {
+							instance: instance_id,
+							playable: instance.playable_id(),
+						});
+						instance.set_declick_tween(self.declick_tween);
+						if let Err((_, mut instance)) =
+							self.instances.try_insert(instance_id, instance)
+						{
+							instance.discard();
+						}
+						playable.record_play();
 						playable.start_cooldown();
+						all_groups.start_cooldowns(|id, all_groups| {
+							playable.groups().has_ancestor(id, all_groups)
+								|| instance_groups.has_ancestor(id, all_groups)
+						});
+						if let Some(cooldown) = self.global_cooldown {
+							self.global_cooldown_timer = cooldown;
+						}
+					} else {
+						tracer.record(TraceEventKind::InstanceCoolingDown {
+							instance: instance_id,
+							playable: instance.playable_id(),
+						});
+						instance.discard();
 					}
 				}
 			}
+			InstanceCommand::AddEffect(id, effect_id, effect, settings) => {
+				if let Some(instance) = self.instances.get_mut(&id) {
+					instance.add_effect(effect_id, effect, settings);
+				}
+			}
+			InstanceCommand::SetEffectEnabled(id, effect_id, enabled) => {
+				if let Some(effect_slot) = self.effect_slot_mut(id, effect_id) {
+					effect_slot.enabled = enabled;
+				}
+			}
+			InstanceCommand::SetEffectMix(id, effect_id, mix) => {
+				if let Some(effect_slot) = self.effect_slot_mut(id, effect_id) {
+					effect_slot.mix.set(mix);
+				}
+			}
+			InstanceCommand::SetEffectValue(id, effect_id, index, value) => {
+				if let Some(effect_slot) = self.effect_slot_mut(id, effect_id) {
+					effect_slot.set_value(index, value);
+				}
+			}
+			InstanceCommand::SendEffectMessage(id, effect_id, mut message) => {
+				if let Some(effect_slot) = self.effect_slot_mut(id, effect_id) {
+					effect_slot.receive_message(message.as_mut());
+				}
+			}
+			InstanceCommand::RemoveEffect(id, effect_id) => {
+				if let Some(instance) = self.instances.get_mut(&id) {
+					instance.remove_effect(effect_id);
+				}
+			}
 			InstanceCommand::SetInstanceVolume(id, value) => {
 				if let Some(instance) = self.instances.get_mut(&id) {
 					instance.set_volume(value);
@@ -65,6 +297,11 @@ impl Instances {
 					instance.set_panning(value);
 				}
 			}
+			InstanceCommand::SetInstanceWidth(id, value) => {
+				if let Some(instance) = self.instances.get_mut(&id) {
+					instance.set_width(value);
+				}
+			}
 			InstanceCommand::SeekInstance(id, offset) => {
 				if let Some(instance) = self.instances.get_mut(&id) {
 					instance.seek(offset);
@@ -75,6 +312,18 @@ impl Instances {
 					instance.seek_to(position);
 				}
 			}
+			InstanceCommand::SwapInstancePlayable(id, playable_id, settings) => {
+				if let Some(instance) = self.instances.get_mut(&id) {
+					if let Some(playable) = playables.playable(playable_id) {
+						instance.swap_playable(
+							playable_id,
+							playable.duration(),
+							playable.default_loop_start(),
+							settings,
+						);
+					}
+				}
+			}
 			InstanceCommand::PauseInstance(id, settings) => {
 				if let Some(instance) = self.instances.get_mut(&id) {
 					instance.pause(settings);
@@ -109,28 +358,22 @@ impl Instances {
 			}
 			InstanceCommand::PauseGroup(id, settings) => {
 				for (_, instance) in &mut self.instances {
-					if let Some(playable) = playables.playable(instance.playable_id()) {
-						if playable.is_in_group(id, all_groups) {
-							instance.pause(settings);
-						}
+					if is_in_group(instance, id, playables, all_groups) {
+						instance.pause(settings);
 					}
 				}
 			}
 			InstanceCommand::ResumeGroup(id, settings) => {
 				for (_, instance) in &mut self.instances {
-					if let Some(playable) = playables.playable(instance.playable_id()) {
-						if playable.is_in_group(id, all_groups) {
-							instance.resume(settings);
-						}
+					if is_in_group(instance, id, playables, all_groups) {
+						instance.resume(settings);
 					}
 				}
 			}
 			InstanceCommand::StopGroup(id, settings) => {
 				for (_, instance) in &mut self.instances {
-					if let Some(playable) = playables.playable(instance.playable_id()) {
-						if playable.is_in_group(id, all_groups) {
-							instance.stop(settings);
-						}
+					if is_in_group(instance, id, playables, all_groups) {
+						instance.stop(settings);
 					}
 				}
 			}
@@ -156,28 +399,101 @@ impl Instances {
 				}
 			}
 		}
+		Ok(limit_reached)
 	}
 
+	#[allow(clippy::too_many_arguments)]
 	pub fn process(
 		&mut self,
 		dt: f64,
 		playables: &Playables,
 		mixer: &mut Mixer,
 		parameters: &Parameters,
+		metronomes: &Metronomes,
+		groups: &Groups,
+		clock: AudioClock,
+		tracer: &mut Tracer,
 	) {
+		let groups_have_effects = groups.have_effects();
 		// TODO: simplify this code (preferably by removing self.instances_to_remove)
 		// while making sure every sample of the sound gets played before the instance is removed
 		for (instance_id, instance) in &mut self.instances {
+			let previous_state = instance.state();
+			instance.check_start_time(clock.time, metronomes);
+			instance.update_tempo_ratio(playables, metronomes);
 			if instance.playing() {
-				mixer.add_input(instance.track_index(), instance.get_sample(playables));
+				let mut output = instance.get_sample(playables);
+				output = instance.process_effects(dt, output, parameters);
+				if groups_have_effects {
+					output =
+						apply_group_effects(instance, dt, output, playables, groups, parameters);
+				}
+				if output != Frame::from_mono(0.0) {
+					if let Some(statistics) = playables
+						.playable(instance.playable_id())
+						.and_then(|playable| playable.statistics())
+					{
+						statistics.record_audible_time(dt);
+					}
+				}
+				mixer.add_input(instance.track_index(), output);
 			}
 			if instance.finished() {
 				self.instances_to_remove.try_push(*instance_id).ok();
 			}
 			instance.update(dt, parameters);
+			if instance.state() != previous_state {
+				tracer.record(TraceEventKind::InstanceStateChanged {
+					instance: *instance_id,
+					state: instance.state(),
+				});
+			}
 		}
 		for instance_id in self.instances_to_remove.drain(..) {
 			self.instances.shift_remove(&instance_id);
 		}
 	}
 }
+
+/// Applies the volume and muffling of the groups the instance
+/// is in. If the instance is in multiple groups that muffle it,
+/// the most muffled group is used.
+fn apply_group_effects(
+	instance: &mut Instance,
+	dt: f64,
+	input: Frame,
+	playables: &Playables,
+	groups: &Groups,
+	parameters: &Parameters,
+) -> Frame {
+	let mut volume = 1.0;
+	let mut muffle: Option<GroupMuffle> = None;
+	for (id, group) in groups.iter() {
+		if !group.has_effects() || !is_in_group(instance, *id, playables, groups) {
+			continue;
+		}
+		volume *= group.volume();
+		if let Some(group_muffle) = group.muffle() {
+			match muffle {
+				Some(current) if current.amount >= group_muffle.amount => {}
+				_ => muffle = Some(group_muffle),
+			}
+		}
+	}
+	instance.apply_group_effects(dt, input, volume, muffle, parameters)
+}
+
+/// Returns `true` if the instance or its sound or arrangement
+/// belongs to the group (or one of its descendants).
+fn is_in_group(
+	instance: &Instance,
+	id: GroupId,
+	playables: &Playables,
+	all_groups: &Groups,
+) -> bool {
+	instance.is_in_group(id, all_groups)
+		|| matches!(
+			playables.playable(instance.playable_id()),
+			Some(playable) if playable.is_in_group(id, all_groups)
+		)
+}
@@ -1,14 +1,19 @@
 #[cfg(test)]
 mod tests;
 
+use std::sync::Arc;
+
 use basedrop::Owned;
 
 use crate::{
 	command::MixerCommand,
-	frame::Frame,
+	frame::{Frame, Sample},
+	manager::resources::{RemovedResource, RemovedResourceProducer},
 	mixer::{
 		effect::{Effect, EffectId},
-		SendTrackId, SubTrackId, Track, TrackIndex, TrackKind,
+		effect_slot::EffectSlot,
+		levels::TrackLevels,
+		PauseTailBehavior, SendTrackId, SubTrackId, Track, TrackIndex, TrackKind, MAX_OUTPUTS,
 	},
 	parameter::Parameters,
 	static_container::index_map::StaticIndexMap,
@@ -19,31 +24,63 @@ pub(crate) struct Mixer {
 	main_track: Track,
 	sub_tracks: StaticIndexMap<SubTrackId, Owned<Track>>,
 	send_tracks: StaticIndexMap<SendTrackId, Owned<Track>>,
+	outputs: [Frame; MAX_OUTPUTS],
+	analyzing_levels: bool,
 }
 
 impl Mixer {
-	pub fn new(sub_track_capacity: usize, send_track_capacity: usize) -> Self {
+	pub fn new(
+		sub_track_capacity: usize,
+		send_track_capacity: usize,
+		main_track_levels: Arc<TrackLevels>,
+		main_track_pause_tail: PauseTailBehavior,
+	) -> Self {
 		Self {
-			main_track: Track::new_main_track(),
+			main_track: Track::new_main_track(main_track_levels, main_track_pause_tail),
 			sub_tracks: StaticIndexMap::new(sub_track_capacity),
 			send_tracks: StaticIndexMap::new(send_track_capacity),
+			outputs: [Frame::from_mono(0.0); MAX_OUTPUTS],
+			analyzing_levels: false,
 		}
 	}
 
-	pub fn run_command(&mut self, command: MixerCommand) {
+	pub fn run_command(
+		&mut self,
+		command: MixerCommand,
+		removed_resources: &mut RemovedResourceProducer,
+	) {
 		match command {
-			MixerCommand::AddTrack(track) => self.add_track(track),
+			MixerCommand::AddTrack(track) => {
+				self.add_track(track);
+				self.update_solo();
+			}
 			MixerCommand::SetTrackVolume(index, volume) => {
 				self.set_track_volume(index, volume);
 			}
+			MixerCommand::SetTrackWidth(index, width) => {
+				self.set_track_width(index, width);
+			}
+			MixerCommand::SetTrackMuted(index, muted) => {
+				self.set_track_muted(index, muted);
+			}
+			MixerCommand::SetTrackSoloed(index, soloed) => {
+				self.set_track_soloed(index, soloed);
+				self.update_solo();
+			}
 			MixerCommand::AddEffect(index, id, effect, settings) => {
 				self.add_effect(index, id, effect, settings);
 			}
 			MixerCommand::RemoveSubTrack(id) => {
-				self.remove_sub_track(id);
+				if let Some(track) = self.remove_sub_track(id) {
+					removed_resources.push(RemovedResource::SubTrack { id, _track: track });
+				}
+				self.update_solo();
 			}
 			MixerCommand::RemoveSendTrack(id) => {
-				self.remove_send_track(id);
+				if let Some(track) = self.remove_send_track(id) {
+					removed_resources.push(RemovedResource::SendTrack { id, _track: track });
+				}
+				self.update_solo();
 			}
 			MixerCommand::SetEffectEnabled(track_index, effect_id, enabled) => {
 				self.set_effect_enabled(track_index, effect_id, enabled);
@@ -51,12 +88,46 @@ impl Mixer {
 			MixerCommand::SetEffectMix(track_index, effect_id, mix) => {
 				self.set_effect_mix(track_index, effect_id, mix);
 			}
+			MixerCommand::SetEffectValue(track_index, effect_id, index, value) => {
+				if let Some(effect_slot) = self.effect_slot_mut(track_index, effect_id) {
+					effect_slot.set_value(index, value);
+				}
+			}
+			MixerCommand::SendEffectMessage(track_index, effect_id, mut message) => {
+				if let Some(effect_slot) = self.effect_slot_mut(track_index, effect_id) {
+					effect_slot.receive_message(message.as_mut());
+				}
+			}
+			MixerCommand::MoveEffect(track_index, effect_id, index) => {
+				self.move_effect(track_index, effect_id, index);
+			}
 			MixerCommand::RemoveEffect(track_index, effect_id) => {
-				self.remove_effect(track_index, effect_id);
+				if let Some(effect_slot) = self.remove_effect(track_index, effect_id) {
+					removed_resources.push(RemovedResource::Effect {
+						id: effect_id,
+						_effect_slot: effect_slot,
+					});
+				}
+			}
+			MixerCommand::SetLevelAnalysisEnabled(enabled) => {
+				self.set_level_analysis_enabled(enabled);
 			}
 		}
 	}
 
+	pub fn set_level_analysis_enabled(&mut self, enabled: bool) {
+		if enabled && !self.analyzing_levels {
+			self.main_track.reset_levels();
+			for (_, track) in &mut self.sub_tracks {
+				track.reset_levels();
+			}
+			for (_, track) in &mut self.send_tracks {
+				track.reset_levels();
+			}
+		}
+		self.analyzing_levels = enabled;
+	}
+
 	pub fn add_track(&mut self, track: Owned<Track>) {
 		match track.kind() {
 			TrackKind::Main => {
@@ -89,6 +160,149 @@ impl Mixer {
 		}
 	}
 
+	pub fn set_track_width(&mut self, index: TrackIndex, width: Value<f64>) {
+		match index {
+			TrackIndex::Main => {
+				self.main_track.set_width(width);
+			}
+			TrackIndex::Sub(id) => {
+				if let Some(track) = self.sub_tracks.get_mut(&id) {
+					track.set_width(width);
+				}
+			}
+			TrackIndex::Send(id) => {
+				if let Some(track) = self.send_tracks.get_mut(&id) {
+					track.set_width(width);
+				}
+			}
+		}
+	}
+
+	pub fn set_track_muted(&mut self, index: TrackIndex, muted: bool) {
+		match index {
+			TrackIndex::Main => {
+				self.main_track.set_muted(muted);
+			}
+			TrackIndex::Sub(id) => {
+				if let Some(track) = self.sub_tracks.get_mut(&id) {
+					track.set_muted(muted);
+				}
+			}
+			TrackIndex::Send(id) => {
+				if let Some(track) = self.send_tracks.get_mut(&id) {
+					track.set_muted(muted);
+				}
+			}
+		}
+	}
+
+	pub fn set_track_soloed(&mut self, index: TrackIndex, soloed: bool) {
+		match index {
+			// soloing the main track wouldn't silence anything
+			TrackIndex::Main => {}
+			TrackIndex::Sub(id) => {
+				if let Some(track) = self.sub_tracks.get_mut(&id) {
+					track.set_soloed(soloed);
+				}
+			}
+			TrackIndex::Send(id) => {
+				if let Some(track) = self.send_tracks.get_mut(&id) {
+					track.set_soloed(soloed);
+				}
+			}
+		}
+	}
+
+	/// Silences every track that isn't in the signal
+	/// path of a soloed track.
+	fn update_solo(&mut self) {
+		let any_sub_track_soloed = self.sub_tracks.iter().any(|(_, track)| track.soloed());
+		let any_send_track_soloed = self.send_tracks.iter().any(|(_, track)| track.soloed());
+		for i in 0..self.sub_tracks.len() {
+			let id = *self.sub_tracks.get_index(i).unwrap().0;
+			let silenced = any_sub_track_soloed && !self.is_in_solo_path(id);
+			self.sub_tracks
+				.get_mut(&id)
+				.unwrap()
+				.set_silenced_by_solo(silenced);
+		}
+		for (_, track) in &mut self.send_tracks {
+			let silenced = any_send_track_soloed && !track.soloed();
+			track.set_silenced_by_solo(silenced);
+		}
+	}
+
+	/// Returns whether a sub-track, one of its ancestors,
+	/// or one of its descendants is soloed.
+	fn is_in_solo_path(&self, id: SubTrackId) -> bool {
+		self.sub_tracks.iter().any(|(other_id, other_track)| {
+			other_track.soloed()
+				&& (*other_id == id
+					|| self.is_ancestor(*other_id, id)
+					|| self.is_ancestor(id, *other_id))
+		})
+	}
+
+	/// Returns whether a sub-track is routed to another
+	/// sub-track, directly or through other sub-tracks.
+	fn is_ancestor(&self, ancestor: SubTrackId, id: SubTrackId) -> bool {
+		let mut parent = self
+			.sub_tracks
+			.get(&id)
+			.and_then(|track| track.parent_track());
+		while let Some(TrackIndex::Sub(parent_id)) = parent {
+			if parent_id == ancestor {
+				return true;
+			}
+			parent = self
+				.sub_tracks
+				.get(&parent_id)
+				.and_then(|track| track.parent_track());
+		}
+		false
+	}
+
+	pub fn pause_track(
+		&mut self,
+		index: TrackIndex,
+		tail: Option<PauseTailBehavior>,
+		fade_duration: f64,
+	) {
+		match index {
+			TrackIndex::Main => {
+				self.main_track.pause(tail, fade_duration);
+			}
+			TrackIndex::Sub(id) => {
+				if let Some(track) = self.sub_tracks.get_mut(&id) {
+					track.pause(tail, fade_duration);
+				}
+			}
+			TrackIndex::Send(id) => {
+				if let Some(track) = self.send_tracks.get_mut(&id) {
+					track.pause(tail, fade_duration);
+				}
+			}
+		}
+	}
+
+	pub fn resume_track(&mut self, index: TrackIndex) {
+		match index {
+			TrackIndex::Main => {
+				self.main_track.resume();
+			}
+			TrackIndex::Sub(id) => {
+				if let Some(track) = self.sub_tracks.get_mut(&id) {
+					track.resume();
+				}
+			}
+			TrackIndex::Send(id) => {
+				if let Some(track) = self.send_tracks.get_mut(&id) {
+					track.resume();
+				}
+			}
+		}
+	}
+
 	pub fn add_effect(
 		&mut self,
 		index: TrackIndex,
@@ -113,12 +327,12 @@ impl Mixer {
 		};
 	}
 
-	pub fn remove_sub_track(&mut self, id: SubTrackId) {
-		self.sub_tracks.remove(&id);
+	pub fn remove_sub_track(&mut self, id: SubTrackId) -> Option<Owned<Track>> {
+		self.sub_tracks.remove(&id)
 	}
 
-	pub fn remove_send_track(&mut self, id: SendTrackId) {
-		self.send_tracks.remove(&id);
+	pub fn remove_send_track(&mut self, id: SendTrackId) -> Option<Owned<Track>> {
+		self.send_tracks.remove(&id)
 	}
 
 	pub fn set_effect_enabled(
@@ -179,26 +393,61 @@ impl Mixer {
 		};
 	}
 
-	pub fn remove_effect(
+	fn effect_slot_mut(
+		&mut self,
+		track_index: TrackIndex,
+		effect_id: crate::mixer::effect::EffectId,
+	) -> Option<&mut EffectSlot> {
+		match track_index {
+			TrackIndex::Main => self.main_track.effect_mut(effect_id),
+			TrackIndex::Sub(id) => self
+				.sub_tracks
+				.get_mut(&id)
+				.and_then(|track| track.effect_mut(effect_id)),
+			TrackIndex::Send(id) => self
+				.send_tracks
+				.get_mut(&id)
+				.and_then(|track| track.effect_mut(effect_id)),
+		}
+	}
+
+	pub fn move_effect(
 		&mut self,
 		track_index: TrackIndex,
 		effect_id: crate::mixer::effect::EffectId,
+		index: usize,
 	) {
 		match track_index {
-			TrackIndex::Main => {
-				self.main_track.remove_effect(effect_id);
-			}
+			TrackIndex::Main => self.main_track.move_effect(effect_id, index),
 			TrackIndex::Sub(id) => {
 				if let Some(track) = self.sub_tracks.get_mut(&id) {
-					track.remove_effect(effect_id);
+					track.move_effect(effect_id, index);
 				}
 			}
 			TrackIndex::Send(id) => {
 				if let Some(track) = self.send_tracks.get_mut(&id) {
-					track.remove_effect(effect_id);
+					track.move_effect(effect_id, index);
 				}
 			}
-		};
+		}
+	}
+
+	pub fn remove_effect(
+		&mut self,
+		track_index: TrackIndex,
+		effect_id: crate::mixer::effect::EffectId,
+	) -> Option<EffectSlot> {
+		match track_index {
+			TrackIndex::Main => self.main_track.remove_effect(effect_id),
+			TrackIndex::Sub(id) => self
+				.sub_tracks
+				.get_mut(&id)
+				.and_then(|track| track.remove_effect(effect_id)),
+			TrackIndex::Send(id) => self
+				.send_tracks
+				.get_mut(&id)
+				.and_then(|track| track.remove_effect(effect_id)),
+		}
 	}
 
 	pub fn add_input(&mut self, index: TrackIndex, input: Frame) {
@@ -232,15 +481,19 @@ impl Mixer {
 				}
 			}
 		}
+		let analyzing_levels = self.analyzing_levels;
 		if let Some(sub_track) = self.sub_tracks.get_mut(&id) {
 			// process this track
 			sub_track.add_input(children_input);
 			let output = sub_track.process(dt, parameters);
+			if analyzing_levels {
+				sub_track.measure_levels(output, dt);
+			}
 			// route this track's output to send tracks
 			if let TrackKind::Sub { sends, .. } = &sub_track.kind() {
 				for (send_track_id, send_volume) in sends.iter() {
 					if let Some(send_track) = self.send_tracks.get_mut(send_track_id) {
-						send_track.add_input(output * send_volume.value() as f32);
+						send_track.add_input(output * send_volume.value() as Sample);
 					}
 				}
 				return output;
@@ -256,8 +509,12 @@ impl Mixer {
 			let (id, track) = self.sub_tracks.get_index(i).unwrap();
 			let id = *id;
 			if let Some(TrackIndex::Main) = track.parent_track() {
+				let track_output = track.output();
 				let output = self.process_sub_track(id, dt, parameters);
-				self.main_track.add_input(output);
+				match track_output {
+					Some(index) => self.outputs[index] += output,
+					None => self.main_track.add_input(output),
+				}
 			}
 		}
 	}
@@ -265,14 +522,31 @@ impl Mixer {
 	/// Processes all send tracks and sends their output to the main track.
 	fn process_send_tracks(&mut self, dt: f64, parameters: &Parameters) {
 		for (_, track) in &mut self.send_tracks {
-			self.main_track.add_input(track.process(dt, parameters));
+			let output = track.process(dt, parameters);
+			if self.analyzing_levels {
+				track.measure_levels(output, dt);
+			}
+			self.main_track.add_input(output);
 		}
 	}
 
-	/// Processes all tracks.
+	/// Processes all tracks and returns the audio for the
+	/// first pair of output channels.
 	pub fn process(&mut self, dt: f64, parameters: &Parameters) -> Frame {
+		self.outputs = [Frame::from_mono(0.0); MAX_OUTPUTS];
 		self.process_sub_tracks(dt, parameters);
 		self.process_send_tracks(dt, parameters);
-		self.main_track.process(dt, parameters)
+		let output = self.main_track.process(dt, parameters);
+		if self.analyzing_levels {
+			self.main_track.measure_levels(output, dt);
+		}
+		self.outputs[0] += output;
+		self.outputs[0]
+	}
+
+	/// Returns the audio for a pair of output channels
+	/// from the last call to [`Mixer::process`].
+	pub fn output(&self, index: usize) -> Frame {
+		self.outputs[index]
 	}
 }
@@ -1,7 +1,9 @@
 use basedrop::{Collector, Owned};
 
 use crate::{
-	mixer::{SendTrackId, SendTrackSettings, SubTrackId, SubTrackSettings, Track, TrackSends},
+	mixer::{
+		SendTrackId, SendTrackSettings, SubTrackId, SubTrackSettings, Track, TrackIndex, TrackSends,
+	},
 	parameter::Parameters,
 	Frame,
 };
@@ -11,8 +13,8 @@ use super::Mixer;
 #[test]
 fn routes_audio_to_parent_tracks() {
 	let collector = Collector::new();
-	let parameters = Parameters::new(100);
-	let mut mixer = Mixer::new(100, 100);
+	let parameters = Parameters::new(100, 100);
+	let mut mixer = Mixer::new(100, 100, Default::default(), Default::default());
 	// parent track has a volume of 50%
 	let parent_track_id = {
 		let settings = SubTrackSettings::new().volume(0.5);
@@ -65,8 +67,8 @@ fn routes_audio_to_parent_tracks() {
 #[test]
 fn routes_audio_to_send_tracks() {
 	let collector = Collector::new();
-	let parameters = Parameters::new(100);
-	let mut mixer = Mixer::new(100, 100);
+	let parameters = Parameters::new(100, 100);
+	let mut mixer = Mixer::new(100, 100, Default::default(), Default::default());
 	let send_track_1_id = {
 		let settings = SendTrackSettings::new();
 		let id = settings.id.unwrap_or(SendTrackId::new());
@@ -102,3 +104,71 @@ fn routes_audio_to_send_tracks() {
 	let out = mixer.process(1.0, &parameters);
 	assert_eq!(out, Frame::from_mono(111.0));
 }
+
+#[test]
+fn routes_audio_to_extra_outputs() {
+	let collector = Collector::new();
+	let parameters = Parameters::new(100, 100);
+	let mut mixer = Mixer::new(100, 100, Default::default(), Default::default());
+	let mut add_sub_track = |settings: SubTrackSettings| {
+		let id = settings.id.unwrap_or(SubTrackId::new());
+		mixer.add_track(Owned::new(
+			&collector.handle(),
+			Track::new_sub_track(id, settings),
+		));
+		id
+	};
+	let main_track_id = add_sub_track(SubTrackSettings::new());
+	// output 0 bypasses the main track, but shares its channels
+	let output_0_track_id = add_sub_track(SubTrackSettings::new().output(0));
+	let output_1_track_id = add_sub_track(SubTrackSettings::new().output(1));
+	// child tracks go through their parent track's output
+	let child_track_id = add_sub_track(SubTrackSettings::new().parent_track(output_1_track_id));
+	mixer.set_track_volume(TrackIndex::Main, 0.5.into());
+	mixer.add_input(main_track_id.into(), Frame::from_mono(2.0));
+	mixer.add_input(output_0_track_id.into(), Frame::from_mono(10.0));
+	mixer.add_input(output_1_track_id.into(), Frame::from_mono(100.0));
+	mixer.add_input(child_track_id.into(), Frame::from_mono(1000.0));
+	assert_eq!(mixer.process(1.0, &parameters), Frame::from_mono(11.0));
+	assert_eq!(mixer.output(0), Frame::from_mono(11.0));
+	assert_eq!(mixer.output(1), Frame::from_mono(1100.0));
+	assert_eq!(mixer.output(2), Frame::from_mono(0.0));
+}
+
+#[test]
+fn silences_tracks_outside_of_the_solo_path() {
+	let collector = Collector::new();
+	let parameters = Parameters::new(100, 100);
+	let mut mixer = Mixer::new(100, 100, Default::default(), Default::default());
+	let mut add_sub_track = |settings: SubTrackSettings| {
+		let id = settings.id.unwrap_or(SubTrackId::new());
+		mixer.add_track(Owned::new(
+			&collector.handle(),
+			Track::new_sub_track(id, settings),
+		));
+		id
+	};
+	// the parent track has a child track, and the other
+	// track is routed straight to the main track
+	let parent_track_id = add_sub_track(SubTrackSettings::new());
+	let child_track_id = add_sub_track(SubTrackSettings::new().parent_track(parent_track_id));
+	let other_track_id = add_sub_track(SubTrackSettings::new());
+	let process = |mixer: &mut Mixer| {
+		mixer.add_input(parent_track_id.into(), Frame::from_mono(1.0));
+		mixer.add_input(child_track_id.into(), Frame::from_mono(10.0));
+		mixer.add_input(other_track_id.into(), Frame::from_mono(100.0));
+		mixer.process(1.0, &parameters)
+	};
+	assert_eq!(process(&mut mixer), Frame::from_mono(111.0));
+	// soloing the child track keeps its parent track audible
+	mixer.set_track_soloed(child_track_id.into(), true);
+	mixer.update_solo();
+	assert_eq!(process(&mut mixer), Frame::from_mono(11.0));
+	// muting the parent track silences its child track
+	mixer.set_track_muted(parent_track_id.into(), true);
+	assert_eq!(process(&mut mixer), Frame::from_mono(0.0));
+	mixer.set_track_muted(parent_track_id.into(), false);
+	mixer.set_track_soloed(child_track_id.into(), false);
+	mixer.update_solo();
+	assert_eq!(process(&mut mixer), Frame::from_mono(111.0));
+}
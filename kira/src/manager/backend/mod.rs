@@ -5,110 +5,564 @@ mod streams;
 
 use self::mixer::Mixer;
 
-use super::AudioManagerSettings;
+use super::{
+	error::{AudioThreadError, CommandFailure, FailedCommand},
+	performance::PerformanceMonitor,
+	resources::{RemovedResourceProducer, ResourceUsage},
+	trace::{TraceEventKind, Tracer},
+	AudioManagerSettings,
+};
 use crate::{
-	command::Command, frame::Frame, group::groups::Groups, metronome::Metronomes,
-	parameter::Parameters, playable::Playables, static_container::vec::StaticVec,
+	command::{Command, InstanceCommand, ParameterCommand, StreamCommand, VoiceCommand},
+	frame::Frame,
+	group::groups::Groups,
+	instance::declick_tween,
+	metronome::Metronomes,
+	mixer::levels::TrackLevels,
+	parameter::{tween::Tween, Automations, DerivedParameters, Parameters},
+	playable::Playables,
+	static_container::vec::StaticVec,
+	voice::VoiceId,
 };
-use instances::Instances;
-use ringbuf::Consumer;
+pub(crate) use instances::Instances;
+use instant::Instant;
+use ringbuf::{Consumer, Producer};
 use sequences::Sequences;
+use std::{
+	sync::{
+		atomic::{fence, AtomicU64, AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 use streams::Streams;
 
+/// The current position of the audio thread in time.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct AudioClock {
+	/// The time since the audio thread started (in seconds).
+	pub time: f64,
+	/// The index of the current frame within the audio buffer.
+	pub buffer_offset: usize,
+}
+
+/// When the current audio buffer started and when
+/// it will be heard.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct OutputTiming {
+	/// The moment the audio thread started producing the buffer.
+	pub buffer_start: Instant,
+	/// The time on the audio clock at the start of the buffer.
+	pub buffer_start_time: f64,
+	/// How long it takes for the first frame of the buffer
+	/// to be played by the audio device (in seconds).
+	pub device_latency: f64,
+	/// The duration of the buffer (in seconds).
+	pub buffer_duration: f64,
+}
+
+/// The [`OutputTiming`] of the current audio buffer, shared
+/// between the audio thread and the main thread.
+///
+/// The timing is too large for a single lock-free atomic, so
+/// each field is stored in its own atomic, and a sequence
+/// number that's odd while the audio thread is writing tells
+/// the main thread when it has to read the fields again. The
+/// audio thread never waits for the main thread.
+#[derive(Debug)]
+pub(crate) struct SharedOutputTiming {
+	/// The moment buffer start times are measured from.
+	epoch: Instant,
+	/// The number of writes that have started and finished.
+	/// `0` means no timing has been recorded yet.
+	sequence: AtomicU64,
+	/// The nanoseconds between the epoch and the start
+	/// of the buffer.
+	buffer_start: AtomicU64,
+	buffer_start_time: AtomicU64,
+	device_latency: AtomicU64,
+	buffer_duration: AtomicU64,
+}
+
+impl SharedOutputTiming {
+	pub fn store(&self, timing: OutputTiming) {
+		let sequence = self.sequence.load(Ordering::Relaxed);
+		self.sequence.store(sequence + 1, Ordering::Relaxed);
+		fence(Ordering::Release);
+		self.buffer_start.store(
+			timing.buffer_start.duration_since(self.epoch).as_nanos() as u64,
+			Ordering::Relaxed,
+		);
+		self.buffer_start_time
+			.store(timing.buffer_start_time.to_bits(), Ordering::Relaxed);
+		self.device_latency
+			.store(timing.device_latency.to_bits(), Ordering::Relaxed);
+		self.buffer_duration
+			.store(timing.buffer_duration.to_bits(), Ordering::Relaxed);
+		self.sequence.store(sequence + 2, Ordering::Release);
+	}
+
+	pub fn load(&self) -> Option<OutputTiming> {
+		loop {
+			let sequence = self.sequence.load(Ordering::Acquire);
+			if sequence == 0 {
+				return None;
+			}
+			if sequence % 2 == 1 {
+				std::hint::spin_loop();
+				continue;
+			}
+			let timing = OutputTiming {
+				buffer_start: self.epoch
+					+ Duration::from_nanos(self.buffer_start.load(Ordering::Relaxed)),
+				buffer_start_time: f64::from_bits(self.buffer_start_time.load(Ordering::Relaxed)),
+				device_latency: f64::from_bits(self.device_latency.load(Ordering::Relaxed)),
+				buffer_duration: f64::from_bits(self.buffer_duration.load(Ordering::Relaxed)),
+			};
+			fence(Ordering::Acquire);
+			if self.sequence.load(Ordering::Relaxed) == sequence {
+				return Some(timing);
+			}
+		}
+	}
+}
+
+impl Default for SharedOutputTiming {
+	fn default() -> Self {
+		Self {
+			epoch: Instant::now(),
+			sequence: AtomicU64::new(0),
+			buffer_start: AtomicU64::new(0),
+			buffer_start_time: AtomicU64::new(0),
+			device_latency: AtomicU64::new(0),
+			buffer_duration: AtomicU64::new(0),
+		}
+	}
+}
+
+/// State of the audio thread that the main thread can read.
+#[derive(Debug, Default)]
+pub(crate) struct SharedBackendState {
+	/// The number of frames that had been processed at the
+	/// start of the current audio buffer.
+	pub frame_count: AtomicU64,
+	/// The number of voices that were playing at the start
+	/// of the current audio buffer.
+	pub num_voices: AtomicUsize,
+	/// The number of instances that existed at the start
+	/// of the current audio buffer.
+	pub num_instances: AtomicUsize,
+	/// The number of sequence instances that were running at
+	/// the start of the current audio buffer.
+	pub num_sequence_instances: AtomicUsize,
+	/// The timing of the current audio buffer, if it's known.
+	pub output_timing: SharedOutputTiming,
+	/// The levels measured at the output of the main track.
+	pub main_track_levels: Arc<TrackLevels>,
+}
+
 /// Processes audio on the audio thread.
+///
+/// Normally the audio manager creates the backend and runs it in
+/// the audio device's callback, but engines that own the audio
+/// callback can get the backend from
+/// [`AudioManager::start_embedded`](crate::manager::AudioManager::start_embedded)
+/// and drive it themselves.
 pub struct Backend {
 	dt: f64,
 	playables: Playables,
-	command_queue: StaticVec<Command>,
+	sequence_command_queue: StaticVec<Command>,
+	scheduled_commands: StaticVec<Command>,
 	command_consumer: Consumer<Command>,
 	metronomes: Metronomes,
 	parameters: Parameters,
+	automations: Automations,
+	derived_parameters: DerivedParameters,
 	instances: Instances,
 	sequences: Sequences,
 	mixer: Mixer,
 	groups: Groups,
 	streams: Streams,
+	declick_tween: Option<Tween>,
+	frame_count: u64,
+	buffer_offset: usize,
+	shared_state: Arc<SharedBackendState>,
+	performance_monitor: PerformanceMonitor,
+	removed_resources: RemovedResourceProducer,
+	error_producer: Producer<AudioThreadError>,
+	tracer: Tracer,
 }
 
 impl Backend {
+	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn new(
 		sample_rate: u32,
 		settings: AudioManagerSettings,
 		command_consumer: Consumer<Command>,
+		shared_state: Arc<SharedBackendState>,
+		performance_monitor: PerformanceMonitor,
+		removed_resources: RemovedResourceProducer,
+		error_producer: Producer<AudioThreadError>,
+		tracer: Tracer,
 	) -> Self {
 		Self {
 			dt: 1.0 / sample_rate as f64,
 			playables: Playables::new(settings.num_sounds, settings.num_arrangements),
-			command_queue: StaticVec::new(settings.num_commands),
+			sequence_command_queue: StaticVec::new(settings.num_commands),
+			scheduled_commands: StaticVec::new(settings.num_commands),
 			command_consumer,
-			parameters: Parameters::new(settings.num_parameters),
+			parameters: Parameters::new(settings.num_parameters, settings.num_modulations),
+			automations: Automations::new(settings.num_parameters),
+			derived_parameters: DerivedParameters::new(settings.num_parameters),
 			metronomes: Metronomes::new(settings.num_metronomes),
-			instances: Instances::new(settings.num_instances),
+			instances: Instances::new(
+				settings.num_instances,
+				settings.global_cooldown,
+				declick_tween(settings.declick_duration),
+				settings.instance_limit_behavior,
+			),
 			sequences: Sequences::new(settings.num_sequences, settings.num_commands),
-			mixer: Mixer::new(settings.num_sub_tracks, settings.num_send_tracks),
+			mixer: Mixer::new(
+				settings.num_sub_tracks,
+				settings.num_send_tracks,
+				shared_state.main_track_levels.clone(),
+				settings.main_track_pause_tail,
+			),
 			groups: Groups::new(settings.num_groups),
 			streams: Streams::new(settings.num_streams),
+			declick_tween: declick_tween(settings.declick_duration),
+			frame_count: 0,
+			buffer_offset: 0,
+			shared_state,
+			performance_monitor,
+			removed_resources,
+			error_producer,
+			tracer,
 		}
 	}
 
-	fn process_commands(&mut self) {
-		while let Some(command) = self.command_consumer.pop() {
-			// TODO: find a way to avoid sharing the command queue
-			// between user-called functions and sequence-produced
-			// commands. I don't want sequence commands cutting
-			// into the capacity of the command queue
-			self.command_queue.try_push(command).ok();
+	fn clock_time(&self) -> f64 {
+		self.frame_count as f64 * self.dt
+	}
+
+	fn run_command(&mut self, command: Command) {
+		// commands that should run later are held onto until
+		// their start time is reached
+		if !command
+			.start_time()
+			.has_passed(self.clock_time(), &self.metronomes)
+		{
+			let name = command.name();
+			self.tracer.record(TraceEventKind::CommandScheduled(name));
+			if self.scheduled_commands.try_push(command).is_err() {
+				self.report_error(AudioThreadError::CommandFailed(FailedCommand {
+					command: name,
+					failure: CommandFailure::TooManyScheduledCommands,
+				}));
+			}
+			return;
 		}
-		for command in self.command_queue.drain(..) {
-			match command {
-				Command::Resource(command) => {
-					self.playables.run_command(command);
+		self.execute_command(command);
+	}
+
+	fn execute_command(&mut self, command: Command) {
+		let name = command.name();
+		self.tracer.record(TraceEventKind::CommandApplied(name));
+		match command {
+			Command::Resource(command) => {
+				self.playables
+					.run_command(command, &mut self.removed_resources);
+			}
+			Command::Metronome(command) => {
+				self.metronomes
+					.run_command(command, &mut self.removed_resources);
+			}
+			Command::Instance(command) => match self.instances.run_command(
+				command,
+				&mut self.playables,
+				&mut self.groups,
+				&mut self.tracer,
+			) {
+				Ok(Some(usage)) => self.report_resource_limit(usage),
+				Ok(None) => {}
+				Err(failure) => {
+					self.report_error(AudioThreadError::CommandFailed(FailedCommand {
+						command: name,
+						failure,
+					}));
 				}
-				Command::Metronome(command) => {
-					self.metronomes.run_command(command);
+			},
+			Command::Sequence(command) => {
+				if let Some(usage) = self.sequences.run_command(command, &self.groups) {
+					self.report_resource_limit(usage);
 				}
-				Command::Instance(command) => {
-					self.instances
-						.run_command(command, &mut self.playables, &self.groups);
+			}
+			Command::Mixer(command) => {
+				self.mixer.run_command(command, &mut self.removed_resources);
+			}
+			Command::Parameter(command) => match command {
+				ParameterCommand::StartAutomation(id, automation) => {
+					self.automations.start(id, automation);
 				}
-				Command::Sequence(command) => {
-					self.sequences.run_command(command, &self.groups);
+				ParameterCommand::StopAutomation(id) => {
+					self.automations.stop(id);
 				}
-				Command::Mixer(command) => {
-					self.mixer.run_command(command);
+				ParameterCommand::AddDerivedParameter(id, settings) => {
+					self.parameters
+						.run_command(ParameterCommand::AddParameter(id, 0.0));
+					self.derived_parameters.add(id, settings);
 				}
-				Command::Parameter(command) => {
+				command => {
+					// setting or removing a parameter stops its automation
+					match command {
+						ParameterCommand::SetParameter(id, ..) => self.automations.stop(id),
+						ParameterCommand::RemoveParameter(id) => {
+							self.automations.stop(id);
+							self.derived_parameters.remove(id);
+						}
+						_ => {}
+					}
 					self.parameters.run_command(command);
 				}
-				Command::Group(command) => {
-					self.groups.run_command(command);
-				}
-				Command::Stream(command) => {
-					self.streams.run_command(command);
-				}
+			},
+			Command::Group(command) => {
+				self.groups
+					.run_command(command, &mut self.removed_resources);
+			}
+			Command::Stream(command) => {
+				self.streams
+					.run_command(command, &mut self.removed_resources);
+			}
+			Command::Voice(command) => {
+				self.run_voice_command(command);
+			}
+			Command::CaptureSnapshot(mut request) => {
+				self.instances.snapshot(&mut request);
+				self.parameters.snapshot(&mut request);
+				self.metronomes.snapshot(&mut request);
+				self.sequences.snapshot(&mut request);
+				request.finish();
+			}
+		}
+	}
+
+	fn report_error(&mut self, error: AudioThreadError) {
+		self.error_producer.push(error).ok();
+	}
+
+	fn report_resource_limit(&mut self, usage: ResourceUsage) {
+		self.performance_monitor
+			.record_resource_limit(self.clock_time(), usage);
+		self.report_error(AudioThreadError::ResourceLimitReached(usage));
+	}
+
+	fn run_voice_command(&mut self, command: VoiceCommand) {
+		match command {
+			VoiceCommand::PauseVoice(VoiceId::Instance(id), settings) => {
+				self.execute_command(InstanceCommand::PauseInstance(id, settings).into());
+			}
+			VoiceCommand::PauseVoice(VoiceId::Stream(id), settings) => {
+				self.streams.run_command(
+					StreamCommand::PauseStream(id, settings.fade_tween.or(self.declick_tween)),
+					&mut self.removed_resources,
+				);
+			}
+			VoiceCommand::ResumeVoice(VoiceId::Instance(id), settings) => {
+				self.execute_command(InstanceCommand::ResumeInstance(id, settings).into());
+			}
+			VoiceCommand::ResumeVoice(VoiceId::Stream(id), settings) => {
+				self.streams.run_command(
+					StreamCommand::ResumeStream(id, settings.fade_tween.or(self.declick_tween)),
+					&mut self.removed_resources,
+				);
+			}
+			VoiceCommand::StopVoice(VoiceId::Instance(id), settings) => {
+				self.execute_command(InstanceCommand::StopInstance(id, settings).into());
+			}
+			VoiceCommand::StopVoice(VoiceId::Stream(id), settings) => {
+				self.streams.run_command(
+					StreamCommand::StopStream(id, settings.fade_tween.or(self.declick_tween)),
+					&mut self.removed_resources,
+				);
+			}
+			VoiceCommand::PauseVoicesOnTrack(track, settings) => {
+				let fade_tween = settings.fade_tween.or(self.declick_tween);
+				self.instances.pause_on_track(track, settings);
+				self.streams.pause_on_track(track, fade_tween);
+				let fade_duration = match fade_tween {
+					Some(fade_tween) => fade_tween.total_duration(),
+					None => 0.0,
+				};
+				self.mixer
+					.pause_track(track, settings.pause_tail, fade_duration);
+			}
+			VoiceCommand::ResumeVoicesOnTrack(track, settings) => {
+				self.instances.resume_on_track(track, settings);
+				self.streams
+					.resume_on_track(track, settings.fade_tween.or(self.declick_tween));
+				self.mixer.resume_track(track);
+			}
+			VoiceCommand::StopVoicesOnTrack(track, settings) => {
+				self.instances.stop_on_track(track, settings);
+				self.streams
+					.stop_on_track(track, settings.fade_tween.or(self.declick_tween));
+			}
+		}
+	}
+
+	fn process_commands(&mut self) {
+		// run commands produced by sequences on the previous frame.
+		// the queue is temporarily swapped out for an empty one (which
+		// doesn't allocate) so we can run commands while draining it
+		let mut sequence_command_queue =
+			std::mem::replace(&mut self.sequence_command_queue, StaticVec::new(0));
+		for command in sequence_command_queue.drain(..) {
+			self.run_command(command);
+		}
+		self.sequence_command_queue = sequence_command_queue;
+		// run commands sent from the main thread
+		while let Some(command) = self.command_consumer.pop() {
+			self.run_command(command);
+		}
+	}
+
+	fn run_scheduled_commands(&mut self, clock_time: f64) {
+		let mut i = 0;
+		while let Some(command) = self.scheduled_commands.get(i) {
+			if command
+				.start_time()
+				.has_passed(clock_time, &self.metronomes)
+			{
+				let command = self.scheduled_commands.remove(i);
+				self.execute_command(command);
+			} else {
+				i += 1;
 			}
 		}
 	}
 
 	fn update_sequences(&mut self) {
-		for command in self
-			.sequences
-			.update(self.dt, &self.playables, &self.metronomes)
-		{
-			self.command_queue.try_push(command.into()).ok();
+		for command in self.sequences.update(
+			self.dt,
+			&self.playables,
+			&self.metronomes,
+			&self.parameters,
+			&mut self.removed_resources,
+			&mut self.tracer,
+		) {
+			self.sequence_command_queue.try_push(command).ok();
 		}
 	}
 
+	/// Marks the start of a new audio buffer.
+	///
+	/// This should be called before producing the first
+	/// frame of each buffer.
+	pub fn start_buffer(&mut self) {
+		self.buffer_offset = 0;
+		self.shared_state
+			.frame_count
+			.store(self.frame_count, Ordering::Relaxed);
+		self.shared_state.num_voices.store(
+			self.instances.num_playing() + self.streams.num_playing(),
+			Ordering::Relaxed,
+		);
+		self.shared_state
+			.num_instances
+			.store(self.instances.num_instances(), Ordering::Relaxed);
+		self.shared_state
+			.num_sequence_instances
+			.store(self.sequences.num_sequence_instances(), Ordering::Relaxed);
+	}
+
+	/// Records when the current audio buffer will be heard.
+	///
+	/// This should be called after [`start_buffer`](Backend::start_buffer).
+	/// `device_latency` is the time between the start of the buffer
+	/// and when its first frame will be played by the audio device.
+	pub fn set_output_timing(&mut self, num_frames: usize, device_latency: Duration) {
+		self.shared_state.output_timing.store(OutputTiming {
+			buffer_start: Instant::now(),
+			buffer_start_time: self.clock_time(),
+			device_latency: device_latency.as_secs_f64(),
+			buffer_duration: num_frames as f64 * self.dt,
+		});
+	}
+
+	/// Records how long the audio buffer that was just produced
+	/// took to process.
+	///
+	/// This should be called after producing the last frame of
+	/// each buffer. `callback_interval` is the time since the audio
+	/// device asked for the previous buffer, if it's known.
+	pub fn finish_buffer(
+		&mut self,
+		num_frames: usize,
+		callback_interval: Option<Duration>,
+		processing_time: Duration,
+	) {
+		let start_time = self.frame_count.saturating_sub(num_frames as u64) as f64 * self.dt;
+		self.performance_monitor.record_buffer(
+			start_time,
+			num_frames as f64 * self.dt,
+			callback_interval,
+			processing_time,
+		);
+	}
+
 	/// Produces a frame of audio.
 	pub fn process(&mut self) -> Frame {
+		self.tracer.set_time(self.clock_time());
 		self.process_commands();
+		self.run_scheduled_commands(self.clock_time());
+		self.automations
+			.update(self.dt, &mut self.parameters, &self.metronomes);
 		self.parameters.update(self.dt);
-		self.playables.update(self.dt);
-		self.metronomes.update(self.dt, &self.parameters);
+		self.derived_parameters.update(&mut self.parameters);
+		self.playables.update(self.dt, &self.parameters);
+		self.groups.update(self.dt, &self.parameters);
+		self.instances.update_cooldown(self.dt);
+		let clock = AudioClock {
+			time: self.clock_time(),
+			buffer_offset: self.buffer_offset,
+		};
+		self.metronomes
+			.update(self.dt, &self.parameters, &self.instances, clock);
 		self.update_sequences();
-		self.streams.process(self.dt, &mut self.mixer);
-		self.instances
-			.process(self.dt, &self.playables, &mut self.mixer, &self.parameters);
+		let error_producer = &mut self.error_producer;
+		self.streams.process(
+			self.dt,
+			&mut self.mixer,
+			&self.parameters,
+			|stream, error| {
+				error_producer
+					.push(AudioThreadError::StreamError { stream, error })
+					.ok();
+			},
+		);
+		self.instances.process(
+			self.dt,
+			&self.playables,
+			&mut self.mixer,
+			&self.parameters,
+			&self.metronomes,
+			&self.groups,
+			clock,
+			&mut self.tracer,
+		);
+		let instances = &self.instances;
+		self.playables.remove_unused(
+			|playable| instances.uses_playable(playable),
+			&mut self.removed_resources,
+		);
+		self.frame_count += 1;
+		self.buffer_offset += 1;
 		self.mixer.process(self.dt, &self.parameters)
 	}
+
+	/// Returns the audio for a pair of output channels
+	/// from the last call to [`Backend::process`].
+	pub fn output(&self, index: usize) -> Frame {
+		self.mixer.output(index)
+	}
 }
@@ -1,11 +1,20 @@
 use crate::{
-	command::{Command, InstanceCommand, MetronomeCommand, ParameterCommand, SequenceCommand},
+	command::{
+		Command, InstanceCommand, MetronomeCommand, MixerCommand, ParameterCommand, SequenceCommand,
+	},
 	group::groups::Groups,
 	instance::Instance,
+	manager::{
+		resources::{RemovedResource, RemovedResourceProducer, ResourceKind, ResourceUsage},
+		snapshot::SnapshotRequest,
+		trace::{TraceEventKind, Tracer},
+	},
 	metronome::Metronomes,
+	parameter::Parameters,
 	playable::Playables,
 	sequence::{SequenceInstance, SequenceInstanceId, SequenceOutputCommand},
 	static_container::{index_map::StaticIndexMap, vec::StaticVec},
+	StartTime,
 };
 use basedrop::Owned;
 use std::vec::Drain;
@@ -27,19 +36,47 @@ impl Sequences {
 		}
 	}
 
+	pub fn num_sequence_instances(&self) -> usize {
+		self.sequence_instances.len()
+	}
+
+	pub fn snapshot(&self, request: &mut SnapshotRequest) {
+		for (_, instance) in &self.sequence_instances {
+			request.add_sequence(instance.snapshot());
+		}
+	}
+
+	/// Starts a sequence instance, or returns the usage of the
+	/// sequence instance pool if there's no room for it.
 	fn start_sequence_instance(
 		&mut self,
 		id: SequenceInstanceId,
 		mut instance: Owned<SequenceInstance>,
-	) {
+	) -> Option<ResourceUsage> {
+		let usage = ResourceUsage {
+			kind: ResourceKind::SequenceInstance,
+			used: self.sequence_instances.len(),
+			capacity: self.sequence_instances.capacity(),
+		};
+		if usage.is_full() {
+			return Some(usage);
+		}
 		instance.start();
 		self.sequence_instances.try_insert(id, instance).ok();
+		None
 	}
 
-	pub fn run_command(&mut self, command: SequenceCommand, groups: &Groups) {
+	/// Runs a sequence command. If a sequence instance can't be
+	/// started because the limit has been reached, returns
+	/// the usage of the sequence instance pool.
+	pub fn run_command(
+		&mut self,
+		command: SequenceCommand,
+		groups: &Groups,
+	) -> Option<ResourceUsage> {
 		match command {
 			SequenceCommand::StartSequenceInstance(id, instance) => {
-				self.start_sequence_instance(id, instance);
+				return self.start_sequence_instance(id, instance);
 			}
 			SequenceCommand::MuteSequenceInstance(id) => {
 				if let Some(instance) = self.sequence_instances.get_mut(&id) {
@@ -66,6 +103,21 @@ impl Sequences {
 					instance.stop();
 				}
 			}
+			SequenceCommand::SignalSequenceInstance(id, event) => {
+				if let Some(instance) = self.sequence_instances.get_mut(&id) {
+					instance.signal(event);
+				}
+			}
+			SequenceCommand::SetSequenceInstanceSpeed(id, speed) => {
+				if let Some(instance) = self.sequence_instances.get_mut(&id) {
+					instance.set_speed(speed);
+				}
+			}
+			SequenceCommand::SetSequenceInstancePosition(id, position, loop_count) => {
+				if let Some(instance) = self.sequence_instances.get_mut(&id) {
+					instance.set_position(position, loop_count);
+				}
+			}
 			SequenceCommand::PauseGroup(id) => {
 				for (_, instance) in &mut self.sequence_instances {
 					if instance.is_in_group(id, groups) {
@@ -88,6 +140,7 @@ impl Sequences {
 				}
 			}
 		}
+		None
 	}
 
 	pub fn update(
@@ -95,10 +148,22 @@ impl Sequences {
 		dt: f64,
 		playables: &Playables,
 		metronomes: &Metronomes,
+		parameters: &Parameters,
+		removed_resources: &mut RemovedResourceProducer,
+		tracer: &mut Tracer,
 	) -> Drain<Command> {
 		// update sequences and process their commands
 		for (id, sequence_instance) in &mut self.sequence_instances {
-			sequence_instance.update(dt, metronomes, &mut self.sequence_output_command_queue);
+			let remaining_capacity =
+				self.output_command_queue.capacity() - self.output_command_queue.len();
+			sequence_instance.update(
+				dt,
+				metronomes,
+				parameters,
+				&mut self.sequence_output_command_queue,
+				remaining_capacity,
+				tracer,
+			);
 			// convert sequence commands to commands that can be consumed
 			// by the backend
 			for command in self.sequence_output_command_queue.drain(..) {
@@ -116,6 +181,8 @@ impl Sequences {
 											playable.duration(),
 											playable.default_loop_start(),
 											playable.default_track(),
+											playable.default_fade_in_tween(),
+											playable.default_fade_out_tween(),
 										),
 									),
 								)))
@@ -251,7 +318,37 @@ impl Sequences {
 					SequenceOutputCommand::SetParameter(id, target, tween) => {
 						self.output_command_queue
 							.try_push(Command::Parameter(ParameterCommand::SetParameter(
-								id, target, tween,
+								id,
+								target,
+								tween,
+								StartTime::Immediate,
+							)))
+							.ok();
+					}
+					SequenceOutputCommand::SetTrackVolume(index, volume) => {
+						self.output_command_queue
+							.try_push(Command::Mixer(MixerCommand::SetTrackVolume(index, volume)))
+							.ok();
+					}
+					SequenceOutputCommand::SetEffectEnabled(index, id, enabled) => {
+						self.output_command_queue
+							.try_push(Command::Mixer(MixerCommand::SetEffectEnabled(
+								index, id, enabled,
+							)))
+							.ok();
+					}
+					SequenceOutputCommand::SetEffectMix(index, id, mix) => {
+						self.output_command_queue
+							.try_push(Command::Mixer(MixerCommand::SetEffectMix(index, id, mix)))
+							.ok();
+					}
+					SequenceOutputCommand::SetEffectValue(index, id, value_index, value) => {
+						self.output_command_queue
+							.try_push(Command::Mixer(MixerCommand::SetEffectValue(
+								index,
+								id,
+								value_index,
+								value,
 							)))
 							.ok();
 					}
@@ -263,7 +360,12 @@ impl Sequences {
 		}
 		// remove finished sequences
 		for id in self.sequence_instances_to_remove.drain(..) {
-			self.sequence_instances.remove(&id).unwrap();
+			let instance = self.sequence_instances.remove(&id).unwrap();
+			tracer.record(TraceEventKind::SequenceFinished { sequence: id });
+			removed_resources.push(RemovedResource::SequenceInstance {
+				id,
+				_instance: instance,
+			});
 		}
 		self.output_command_queue.drain(..)
 	}
@@ -1,16 +1,116 @@
 use crate::{
 	audio_stream::{AudioStream, AudioStreamId},
 	command::StreamCommand,
-	manager::TrackIndex,
+	manager::{
+		resources::{RemovedResource, RemovedResourceProducer},
+		TrackIndex,
+	},
+	parameter::{tween::Tween, Parameter, Parameters},
 	static_container::index_map::StaticIndexMap,
+	CachedValue, Frame, Sample, Value,
 };
 
 use super::mixer::Mixer;
 
 use basedrop::Owned;
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum StreamState {
+	Playing,
+	Pausing,
+	Paused,
+	Stopping,
+	Stopped,
+}
+
+struct Stream {
+	track: TrackIndex,
+	stream: Owned<Box<dyn AudioStream>>,
+	volume: CachedValue<f64>,
+	panning: CachedValue<f64>,
+	state: StreamState,
+	fade_volume: Parameter,
+}
+
+impl Stream {
+	fn new(track: TrackIndex, stream: Owned<Box<dyn AudioStream>>) -> Self {
+		Self {
+			track,
+			stream,
+			volume: CachedValue::new(Value::Fixed(1.0), 1.0),
+			panning: CachedValue::new(Value::Fixed(0.5), 0.5).with_valid_range(0.0..1.0),
+			state: StreamState::Playing,
+			fade_volume: Parameter::new(1.0),
+		}
+	}
+
+	fn playing(&self) -> bool {
+		matches!(
+			self.state,
+			StreamState::Playing | StreamState::Pausing | StreamState::Stopping
+		)
+	}
+
+	fn pause(&mut self, fade_tween: Option<Tween>) {
+		if !matches!(self.state, StreamState::Playing | StreamState::Pausing) {
+			return;
+		}
+		self.state = if fade_tween.is_some() {
+			StreamState::Pausing
+		} else {
+			StreamState::Paused
+		};
+		self.fade_volume.set(0.0, fade_tween);
+	}
+
+	fn resume(&mut self, fade_tween: Option<Tween>) {
+		if matches!(self.state, StreamState::Stopping | StreamState::Stopped) {
+			return;
+		}
+		self.state = StreamState::Playing;
+		self.fade_volume.set(1.0, fade_tween);
+	}
+
+	fn stop(&mut self, fade_tween: Option<Tween>) {
+		if self.state == StreamState::Stopped {
+			return;
+		}
+		self.state = if fade_tween.is_some() {
+			StreamState::Stopping
+		} else {
+			StreamState::Stopped
+		};
+		self.fade_volume.set(0.0, fade_tween);
+	}
+
+	fn process(&mut self, dt: f64, parameters: &Parameters) -> Option<Frame> {
+		if self.fade_volume.update(dt) {
+			match self.state {
+				StreamState::Pausing => self.state = StreamState::Paused,
+				StreamState::Stopping => self.state = StreamState::Stopped,
+				_ => {}
+			}
+		}
+		if !self.playing() {
+			return None;
+		}
+		self.volume.update(parameters);
+		self.panning.update(parameters);
+		self.stream.update(parameters);
+		let frame = self.stream.next(dt);
+		let volume = (self.volume.value() * self.fade_volume.value()) as Sample;
+		// use a balance control so centered streams
+		// keep their original volume
+		let panning = self.panning.value() as Sample;
+		Some(Frame::new(
+			frame.left * volume * (2.0 * (1.0 - panning)).min(1.0),
+			frame.right * volume * (2.0 * panning).min(1.0),
+		))
+	}
+}
+
 pub(crate) struct Streams {
-	streams: StaticIndexMap<AudioStreamId, (TrackIndex, Owned<Box<dyn AudioStream>>)>,
+	streams: StaticIndexMap<AudioStreamId, Stream>,
 }
 
 impl Streams {
@@ -20,20 +120,103 @@ impl Streams {
 		}
 	}
 
-	pub fn run_command(&mut self, command: StreamCommand) {
+	pub fn run_command(
+		&mut self,
+		command: StreamCommand,
+		removed_resources: &mut RemovedResourceProducer,
+	) {
 		match command {
 			StreamCommand::AddStream(stream_id, track_id, stream) => {
-				self.streams.try_insert(stream_id, (track_id, stream)).ok();
+				self.streams
+					.try_insert(stream_id, Stream::new(track_id, stream))
+					.ok();
 			}
 			StreamCommand::RemoveStream(stream_id) => {
-				self.streams.remove(&stream_id);
+				if let Some(stream) = self.streams.remove(&stream_id) {
+					removed_resources.push(RemovedResource::Stream {
+						id: stream_id,
+						_stream: stream.stream,
+					});
+				}
+			}
+			StreamCommand::SetStreamVolume(stream_id, volume) => {
+				if let Some(stream) = self.streams.get_mut(&stream_id) {
+					stream.volume.set(volume);
+				}
+			}
+			StreamCommand::SetStreamPanning(stream_id, panning) => {
+				if let Some(stream) = self.streams.get_mut(&stream_id) {
+					stream.panning.set(panning);
+				}
+			}
+			StreamCommand::PauseStream(stream_id, fade_tween) => {
+				if let Some(stream) = self.streams.get_mut(&stream_id) {
+					stream.pause(fade_tween);
+				}
+			}
+			StreamCommand::ResumeStream(stream_id, fade_tween) => {
+				if let Some(stream) = self.streams.get_mut(&stream_id) {
+					stream.resume(fade_tween);
+				}
+			}
+			StreamCommand::StopStream(stream_id, fade_tween) => {
+				if let Some(stream) = self.streams.get_mut(&stream_id) {
+					stream.stop(fade_tween);
+				}
+			}
+			StreamCommand::SetStreamTrack(stream_id, track) => {
+				if let Some(stream) = self.streams.get_mut(&stream_id) {
+					stream.track = track;
+				}
 			}
 		}
 	}
 
-	pub fn process(&mut self, dt: f64, mixer: &mut Mixer) {
-		for (track, stream) in self.streams.values_mut() {
-			mixer.add_input(*track, stream.next(dt));
+	pub fn num_playing(&self) -> usize {
+		self.streams
+			.iter()
+			.filter(|(_, stream)| stream.playing())
+			.count()
+	}
+
+	pub fn pause_on_track(&mut self, track: TrackIndex, fade_tween: Option<Tween>) {
+		for stream in self.streams.values_mut() {
+			if stream.track == track {
+				stream.pause(fade_tween);
+			}
+		}
+	}
+
+	pub fn resume_on_track(&mut self, track: TrackIndex, fade_tween: Option<Tween>) {
+		for stream in self.streams.values_mut() {
+			if stream.track == track {
+				stream.resume(fade_tween);
+			}
+		}
+	}
+
+	pub fn stop_on_track(&mut self, track: TrackIndex, fade_tween: Option<Tween>) {
+		for stream in self.streams.values_mut() {
+			if stream.track == track {
+				stream.stop(fade_tween);
+			}
+		}
+	}
+
+	pub fn process(
+		&mut self,
+		dt: f64,
+		mixer: &mut Mixer,
+		parameters: &Parameters,
+		mut report_error: impl FnMut(AudioStreamId, String),
+	) {
+		for (id, stream) in self.streams.iter_mut() {
+			if let Some(frame) = stream.process(dt, parameters) {
+				mixer.add_input(stream.track, frame);
+			}
+			if let Some(error) = stream.stream.pop_error() {
+				report_error(*id, error);
+			}
 		}
 	}
 }
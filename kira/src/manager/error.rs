@@ -1,6 +1,10 @@
 //! Things that can go wrong when using an [`AudioManager`](super::AudioManager).
 
-use cpal::{BuildStreamError, DefaultStreamConfigError, PlayStreamError};
+#[cfg(feature = "cpal")]
+use cpal::{
+	BuildStreamError, DefaultStreamConfigError, DevicesError, PlayStreamError,
+	SupportedStreamConfigsError,
+};
 use thiserror::Error;
 
 use crate::{
@@ -8,14 +12,19 @@ use crate::{
 	audio_stream::AudioStreamId,
 	command::producer::CommandError,
 	group::GroupId,
+	instance::InstanceId,
 	metronome::MetronomeId,
-	mixer::{SendTrackId, SubTrackId, TrackIndex},
-	parameter::ParameterId,
+	mixer::{AddEffectError, SendTrackId, SubTrackId, TrackIndex},
+	parameter::{ModulationId, ParameterId},
 	sequence::error::SequenceError,
 	sound::{error::SoundFromFileError, SoundId},
+	PlayableId,
 };
 
+use super::resources::ResourceUsage;
+
 /// Things that can go wrong when creating an `AudioManager`.
+#[cfg(feature = "cpal")]
 #[derive(Debug, Error)]
 pub enum SetupError {
 	/// A default audio output device could not be determined.
@@ -33,14 +42,50 @@ pub enum SetupError {
 	/// An error occured when starting the audio stream.
 	#[error("{0}")]
 	PlayStreamError(#[from] PlayStreamError),
+
+	/// An error occurred when listing the available audio devices.
+	#[error("{0}")]
+	DevicesError(#[from] DevicesError),
+
+	/// An error occurred when getting the output configurations
+	/// an audio device supports.
+	#[error("{0}")]
+	SupportedStreamConfigsError(#[from] SupportedStreamConfigsError),
+
+	/// No audio output device has the given name.
+	#[error("Cannot find an audio output device named {0}")]
+	NoOutputDeviceWithName(String),
+
+	/// An output device was given an output index that is not
+	/// less than [`MAX_OUTPUTS`](crate::mixer::MAX_OUTPUTS).
+	#[error("The mixer does not have an output with index {0}")]
+	NoOutputWithIndex(usize),
+
+	/// The audio stream could not be set up because something
+	/// went wrong inside Kira or the audio driver, such as the
+	/// thread that sets up the stream stopping unexpectedly.
+	///
+	/// After this error, the audio manager can't be started.
+	#[error("Setting up the audio stream failed unexpectedly")]
+	StreamSetupFailed,
+
+	/// An output device cannot play audio at the default
+	/// device's sample rate.
+	#[error("The audio device {device_name} does not support a sample rate of {sample_rate} Hz")]
+	UnsupportedSampleRate {
+		/// The name of the audio device.
+		device_name: String,
+		/// The sample rate of the default audio device.
+		sample_rate: u32,
+	},
 }
 
 /// Things that can go wrong when adding a sound to the audio thread.
 #[derive(Debug, Error)]
 pub enum AddSoundError {
 	/// The maximum sound limit has been reached.
-	#[error("Cannot add a sound because the max number of sounds has been reached")]
-	SoundLimitReached,
+	#[error("Cannot add a sound because the max number of sounds ({}) has been reached", .0.capacity)]
+	SoundLimitReached(ResourceUsage),
 
 	/// The default track for the sound does not exist.
 	#[error("The default track for the sound does not exist")]
@@ -81,12 +126,97 @@ pub enum RemoveSoundError {
 	CommandProducerError(#[from] CommandError),
 }
 
+/// Things that can go wrong when replacing a sound's audio data.
+#[derive(Debug, Error)]
+pub enum ReplaceSoundError {
+	/// No sound with the specified ID exists.
+	#[error("The sound with the specified ID does not exist")]
+	NoSoundWithId(SoundId),
+
+	/// The default track for the new sound does not exist.
+	#[error("The default track for the sound does not exist")]
+	NoTrackWithIndex(TrackIndex),
+
+	/// The new sound belongs to a group that does not exist.
+	#[error("The sound belongs to a group that does not exist")]
+	NoGroupWithId(GroupId),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// Things that can go wrong when playing a sound or arrangement
+/// using [`AudioManager::play`](super::AudioManager::play).
+#[derive(Debug, Error)]
+pub enum PlayError {
+	/// No sound with the specified ID exists.
+	#[error("The sound with the specified ID does not exist")]
+	NoSoundWithId(SoundId),
+
+	/// No arrangement with the specified ID exists.
+	#[error("The arrangement with the specified ID does not exist")]
+	NoArrangementWithId(ArrangementId),
+
+	/// No sound or arrangement has the specified name.
+	#[error("{0}")]
+	ResolveNameError(#[from] ResolveNameError),
+
+	/// The maximum number of instances was already playing,
+	/// and new instances are
+	/// [rejected](super::InstanceLimitBehavior::RejectNew).
+	#[error("Cannot play an instance because the max number of instances ({}) has been reached", .0.capacity)]
+	InstanceLimitReached(ResourceUsage),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// Things that can go wrong when giving a resource a name.
+#[derive(Debug, Error)]
+pub enum SetNameError {
+	/// The resource does not exist.
+	#[error("Cannot name a resource that does not exist")]
+	NonexistentResource,
+}
+
+/// Things that can go wrong when turning a sequence or
+/// arrangement definition into a sequence or arrangement.
+#[derive(Debug, Error)]
+pub enum ResolveNameError {
+	/// No sound has the given name.
+	#[error("No sound is named \"{0}\"")]
+	NoSoundWithName(String),
+
+	/// No arrangement has the given name.
+	#[error("No arrangement is named \"{0}\"")]
+	NoArrangementWithName(String),
+
+	/// No parameter has the given name.
+	#[error("No parameter is named \"{0}\"")]
+	NoParameterWithName(String),
+
+	/// No group has the given name.
+	#[error("No group is named \"{0}\"")]
+	NoGroupWithName(String),
+
+	/// No mixer track has the given name.
+	#[error("No mixer track is named \"{0}\"")]
+	NoTrackWithName(String),
+
+	/// No instance with the given name was played
+	/// earlier in the sequence.
+	#[error("No instance named \"{0}\" is played earlier in the sequence")]
+	NoInstanceWithName(String),
+}
+
 /// Things that can go wrong when adding an arrangement to the audio thread.
 #[derive(Debug, Error)]
 pub enum AddArrangementError {
 	/// The maximum arrangement limit has been reached.
-	#[error("Cannot add an arrangement because the max number of arrangements has been reached")]
-	ArrangementLimitReached,
+	#[error("Cannot add an arrangement because the max number of arrangements ({}) has been reached", .0.capacity)]
+	ArrangementLimitReached(ResourceUsage),
 
 	/// The default track for the arrangement does not exist.
 	#[error("The default track for the arrangement does not exist")]
@@ -118,8 +248,8 @@ pub enum RemoveArrangementError {
 #[derive(Debug, Error)]
 pub enum AddMetronomeError {
 	/// The maximum metronome limit has been reached.
-	#[error("Cannot add a metronome because the max number of metronomes has been reached")]
-	MetronomeLimitReached,
+	#[error("Cannot add a metronome because the max number of metronomes ({}) has been reached", .0.capacity)]
+	MetronomeLimitReached(ResourceUsage),
 
 	/// A command could not be sent to the audio thread.
 	#[error("Could not send the command to the audio thread.")]
@@ -143,8 +273,8 @@ pub enum RemoveMetronomeError {
 #[derive(Debug, Error)]
 pub enum AddGroupError {
 	/// The maximum group limit has been reached.
-	#[error("Cannot add an group because the max number of groups has been reached")]
-	GroupLimitReached,
+	#[error("Cannot add an group because the max number of groups ({}) has been reached", .0.capacity)]
+	GroupLimitReached(ResourceUsage),
 
 	/// The group belongs to a parent group that does not exist.
 	#[error("The group belongs to a parent group that does not exist")]
@@ -172,14 +302,75 @@ pub enum RemoveGroupError {
 #[derive(Debug, Error)]
 pub enum AddParameterError {
 	/// The maximum parameter limit has been reached.
-	#[error("Cannot add an parameter because the max number of parameters has been reached")]
-	ParameterLimitReached,
+	#[error("Cannot add an parameter because the max number of parameters ({}) has been reached", .0.capacity)]
+	ParameterLimitReached(ResourceUsage),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// Things that can go wrong when adding a derived parameter
+/// to the audio thread.
+#[derive(Debug, Error)]
+pub enum AddDerivedParameterError {
+	/// The maximum parameter limit has been reached.
+	#[error("Cannot add an parameter because the max number of parameters ({}) has been reached", .0.capacity)]
+	ParameterLimitReached(ResourceUsage),
+
+	/// No parameter with the specified ID exists.
+	#[error("The parameter with the specified ID does not exist")]
+	NoParameterWithId(ParameterId),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+impl From<AddParameterError> for AddDerivedParameterError {
+	fn from(error: AddParameterError) -> Self {
+		match error {
+			AddParameterError::ParameterLimitReached(usage) => Self::ParameterLimitReached(usage),
+			AddParameterError::CommandProducerError(error) => Self::CommandProducerError(error),
+		}
+	}
+}
+
+/// Things that can go wrong when adding muffle effects to
+/// mixer tracks.
+#[derive(Debug, Error)]
+pub enum AddMuffleError {
+	/// The audio manager has not been started yet, so the
+	/// effects cannot be initialized with a sample rate.
+	#[error("Cannot add effects before the audio manager has been started")]
+	AudioManagerNotStarted,
+
+	/// The maximum parameter limit has been reached.
+	#[error("Cannot add an parameter because the max number of parameters ({}) has been reached", .0.capacity)]
+	ParameterLimitReached(ResourceUsage),
+
+	/// No mixer sub-track with the specified ID exists.
+	#[error("The sub-track with the specified ID does not exist")]
+	NoSubTrackWithId(SubTrackId),
+
+	/// No mixer send track with the specified ID exists.
+	#[error("The send track with the specified ID does not exist")]
+	NoSendTrackWithId(SendTrackId),
 
 	/// A command could not be sent to the audio thread.
 	#[error("Could not send the command to the audio thread.")]
 	CommandProducerError(#[from] CommandError),
 }
 
+impl From<AddParameterError> for AddMuffleError {
+	fn from(error: AddParameterError) -> Self {
+		match error {
+			AddParameterError::ParameterLimitReached(usage) => Self::ParameterLimitReached(usage),
+			AddParameterError::CommandProducerError(error) => Self::CommandProducerError(error),
+		}
+	}
+}
+
 /// Things that can go wrong when removing a parameter from the
 /// audio thread.
 #[derive(Debug, Error)]
@@ -193,12 +384,41 @@ pub enum RemoveParameterError {
 	CommandProducerError(#[from] CommandError),
 }
 
+/// Things that can go wrong when adding a modulation to the audio thread.
+#[derive(Debug, Error)]
+pub enum AddModulationError {
+	/// The maximum modulation limit has been reached.
+	#[error("Cannot add a modulation because the max number of modulations ({}) has been reached", .0.capacity)]
+	ModulationLimitReached(ResourceUsage),
+
+	/// No parameter with the specified ID exists.
+	#[error("The parameter with the specified ID does not exist")]
+	NoParameterWithId(ParameterId),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// Things that can go wrong when removing a modulation from the
+/// audio thread.
+#[derive(Debug, Error)]
+pub enum RemoveModulationError {
+	/// No modulation with the specified ID exists.
+	#[error("The modulation with the specified ID does not exist")]
+	NoModulationWithId(ModulationId),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
 /// Things that can go wrong when adding a mixer sub-track to the audio thread.
 #[derive(Debug, Error)]
 pub enum AddSubTrackError {
 	/// The maximum track limit has been reached.
-	#[error("Cannot add an track because the max number of tracks has been reached")]
-	TrackLimitReached,
+	#[error("Cannot add an track because the max number of tracks ({}) has been reached", .0.capacity)]
+	TrackLimitReached(ResourceUsage),
 
 	/// The track's parent track does not exist.
 	#[error("The track's parent track does not exist")]
@@ -208,6 +428,10 @@ pub enum AddSubTrackError {
 	#[error("One of the track's send tracks does not exist")]
 	NonexistentSendTrack(SendTrackId),
 
+	/// The track's output is not less than [`MAX_OUTPUTS`](crate::mixer::MAX_OUTPUTS).
+	#[error("The track's output ({0}) does not exist")]
+	NonexistentOutput(usize),
+
 	/// A command could not be sent to the audio thread.
 	#[error("Could not send the command to the audio thread.")]
 	CommandProducerError(#[from] CommandError),
@@ -217,14 +441,31 @@ pub enum AddSubTrackError {
 #[derive(Debug, Error)]
 pub enum AddSendTrackError {
 	/// The maximum track limit has been reached.
-	#[error("Cannot add an track because the max number of tracks has been reached")]
-	TrackLimitReached,
+	#[error("Cannot add an track because the max number of tracks ({}) has been reached", .0.capacity)]
+	TrackLimitReached(ResourceUsage),
 
 	/// A command could not be sent to the audio thread.
 	#[error("Could not send the command to the audio thread.")]
 	CommandProducerError(#[from] CommandError),
 }
 
+/// Things that can go wrong when applying a
+/// [`MixerConfig`](crate::mixer::config::MixerConfig).
+#[derive(Debug, Error)]
+pub enum AddMixerConfigError {
+	/// A send track could not be added.
+	#[error("{0}")]
+	AddSendTrackError(#[from] AddSendTrackError),
+
+	/// A sub-track could not be added.
+	#[error("{0}")]
+	AddSubTrackError(#[from] AddSubTrackError),
+
+	/// An effect could not be added to a track.
+	#[error("{0}")]
+	AddEffectError(#[from] AddEffectError),
+}
+
 /// Things that can go wrong when removing a mixer sub-track from the
 /// audio thread.
 #[derive(Debug, Error)]
@@ -255,8 +496,41 @@ pub enum RemoveSendTrackError {
 #[derive(Debug, Error)]
 pub enum AddStreamError {
 	/// The maximum audio stream limit has been reached.
-	#[error("Cannot add a stream because the max number of streams has been reached")]
-	StreamLimitReached,
+	#[error("Cannot add a stream because the max number of streams ({}) has been reached", .0.capacity)]
+	StreamLimitReached(ResourceUsage),
+
+	/// The specified track for the stream does not exist.
+	#[error("The specified track for the stream does not exist")]
+	NoTrackWithIndex(TrackIndex),
+
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
+/// Things that can go wrong when adding an input stream.
+#[cfg(feature = "cpal")]
+#[derive(Debug, Error)]
+pub enum AddInputStreamError {
+	/// A default audio input device could not be determined.
+	#[error("Cannot find the default audio input device")]
+	NoDefaultInputDevice,
+
+	/// An error occurred when getting the default input configuration.
+	#[error("{0}")]
+	DefaultStreamConfigError(#[from] DefaultStreamConfigError),
+
+	/// An error occured when building the input stream.
+	#[error("{0}")]
+	BuildStreamError(#[from] BuildStreamError),
+
+	/// An error occured when starting the input stream.
+	#[error("{0}")]
+	PlayStreamError(#[from] PlayStreamError),
+
+	/// The maximum audio stream limit has been reached.
+	#[error("Cannot add a stream because the max number of streams ({}) has been reached", .0.capacity)]
+	StreamLimitReached(ResourceUsage),
 
 	/// The specified track for the stream does not exist.
 	#[error("The specified track for the stream does not exist")]
@@ -267,6 +541,17 @@ pub enum AddStreamError {
 	CommandProducerError(#[from] CommandError),
 }
 
+#[cfg(feature = "cpal")]
+impl From<AddStreamError> for AddInputStreamError {
+	fn from(error: AddStreamError) -> Self {
+		match error {
+			AddStreamError::StreamLimitReached(usage) => Self::StreamLimitReached(usage),
+			AddStreamError::NoTrackWithIndex(track) => Self::NoTrackWithIndex(track),
+			AddStreamError::CommandProducerError(error) => Self::CommandProducerError(error),
+		}
+	}
+}
+
 /// Things that can go wrong when removing an audio stream from the
 /// audio thread.
 #[derive(Debug, Error)]
@@ -291,7 +576,72 @@ pub enum StartSequenceError {
 	#[error("The sequence belongs to a group that does not exist")]
 	NoGroupWithId(GroupId),
 
+	/// The maximum number of sequences was already running
+	/// at the start of the current audio buffer.
+	#[error("Cannot start a sequence because the max number of sequences ({}) has been reached", .0.capacity)]
+	SequenceLimitReached(ResourceUsage),
+
 	/// A command could not be sent to the audio thread.
 	#[error("Could not send the command to the audio thread.")]
 	CommandProducerError(#[from] CommandError),
 }
+
+/// Reasons the audio thread couldn't carry out a command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+pub enum CommandFailure {
+	/// The instance has finished playing, was stopped, or
+	/// was never started.
+	#[error("The instance with the specified ID does not exist")]
+	NoInstanceWithId(InstanceId),
+
+	/// The sound or arrangement has been removed.
+	#[error("The sound or arrangement with the specified ID does not exist")]
+	NoPlayableWithId(PlayableId),
+
+	/// The command has a start time, but too many commands
+	/// were already waiting for their start times.
+	#[error("Too many commands were already waiting for their start times")]
+	TooManyScheduledCommands,
+}
+
+/// A command the audio thread received but couldn't carry out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Error)]
+#[error("The command {command} failed: {failure}")]
+pub struct FailedCommand {
+	/// The name of the command.
+	pub command: &'static str,
+	/// Why the command failed.
+	pub failure: CommandFailure,
+}
+
+/// Something that went wrong on the audio thread.
+///
+/// The audio thread can't return errors from the commands it
+/// receives, so it sends them back to the main thread instead,
+/// where they can be received with
+/// [`AudioManager::pop_audio_thread_error`](super::AudioManager::pop_audio_thread_error).
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AudioThreadError {
+	/// A command couldn't be carried out.
+	#[error("{0}")]
+	CommandFailed(FailedCommand),
+
+	/// An instance or sequence couldn't be started without going
+	/// over the limit set in the
+	/// [`AudioManagerSettings`](super::AudioManagerSettings).
+	///
+	/// This is also reported as a
+	/// [performance event](super::performance::PerformanceEvent::ResourceLimitReached).
+	#[error("The limit of {} resources of kind {:?} was reached", .0.capacity, .0.kind)]
+	ResourceLimitReached(ResourceUsage),
+
+	/// An audio stream ran into a problem, like audio data
+	/// that couldn't be decoded.
+	#[error("The audio stream ran into an error: {error}")]
+	StreamError {
+		/// The ID of the audio stream.
+		stream: AudioStreamId,
+		/// A description of the problem.
+		error: String,
+	},
+}
@@ -1,58 +1,209 @@
 //! Bridges the main thread and the audio thread.
 
 mod active_ids;
-mod backend;
+pub(crate) mod backend;
 pub mod error;
+pub mod names;
+pub mod output_device;
+pub mod performance;
+pub mod resources;
+pub mod snapshot;
 #[cfg(test)]
 mod tests;
+pub mod trace;
 
+#[cfg(not(test))]
+use std::io::{stderr, Write};
 use std::{
 	hash::Hash,
-	io::{stderr, Write},
+	sync::{
+		atomic::{AtomicU32, Ordering},
+		Arc, Weak,
+	},
+	time::Duration,
 };
 
+#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+use crate::audio_stream::input::{start_input_stream, InputStreamHandle, InputStreamSettings};
 use active_ids::ActiveIds;
-#[cfg(not(feature = "benchmarking"))]
-use backend::Backend;
-#[cfg(feature = "benchmarking")]
 pub use backend::Backend;
+use backend::SharedBackendState;
 use basedrop::{Collector, Owned};
+#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+use error::AddInputStreamError;
+#[cfg(feature = "cpal")]
+use error::SetupError;
 use error::{
-	AddArrangementError, AddGroupError, AddMetronomeError, AddParameterError, AddSendTrackError,
-	AddSoundError, AddStreamError, AddSubTrackError, RemoveArrangementError, RemoveGroupError,
-	RemoveMetronomeError, RemoveParameterError, RemoveSendTrackError, RemoveSoundError,
-	RemoveStreamError, RemoveSubTrackError, SetupError, StartSequenceError,
+	AddArrangementError, AddDerivedParameterError, AddGroupError, AddMetronomeError,
+	AddMixerConfigError, AddModulationError, AddMuffleError, AddParameterError, AddSendTrackError,
+	AddSoundError, AddStreamError, AddSubTrackError, AudioThreadError, PlayError,
+	RemoveArrangementError, RemoveGroupError, RemoveMetronomeError, RemoveModulationError,
+	RemoveParameterError, RemoveSendTrackError, RemoveSoundError, RemoveStreamError,
+	RemoveSubTrackError, ReplaceSoundError, ResolveNameError, SetNameError, StartSequenceError,
+};
+use indexmap::{IndexMap, IndexSet};
+use names::{NamedResource, Names, PlayableRef};
+#[cfg(feature = "cpal")]
+use output_device::{open_output_devices, process_frame};
+use output_device::OutputDeviceSettings;
+use performance::{
+	PerformanceCounters, PerformanceEvent, PerformanceMonitor, PerformanceReport,
+	NUM_PERFORMANCE_EVENTS,
+};
+use resources::{
+	FreedResource, FreedResources, RemovedResource, RemovedResourceProducer, ResourceKind,
+	ResourceUsage,
 };
 use ringbuf::{Consumer, Producer, RingBuffer};
+use snapshot::{AudioSnapshot, SharedSnapshot, SnapshotRequest};
+use trace::{SharedTrace, TraceEvent, Tracer};
 
+#[cfg(all(target_arch = "wasm32", feature = "ogg"))]
+use crate::sound::streaming::{
+	start_streaming_sound, StreamingSoundHandle, StreamingSoundSettings,
+};
 use crate::{
-	arrangement::{handle::ArrangementHandle, Arrangement, ArrangementId},
-	audio_stream::{AudioStream, AudioStreamId},
+	arrangement::{
+		definition::ArrangementDefinition, handle::ArrangementHandle, Arrangement, ArrangementId,
+	},
+	audio_stream::{handle::AudioStreamHandle, AudioStream, AudioStreamId},
 	command::{
-		producer::CommandProducer, Command, GroupCommand, MetronomeCommand, MixerCommand,
-		ParameterCommand, ResourceCommand, SequenceCommand, StreamCommand,
+		producer::{CommandError, CommandOverflowStrategy, CommandProducer},
+		Command, GroupCommand, InstanceCommand, MetronomeCommand, MixerCommand, ParameterCommand,
+		ResourceCommand, SequenceCommand, StreamCommand, VoiceCommand,
 	},
 	group::{handle::GroupHandle, Group, GroupId, GroupSet, GroupSettings},
+	instance::{
+		handle::{InstanceEffectContext, InstanceHandle},
+		CrossfadeSettings, Instance, InstanceId, InstanceSettings, PauseInstanceSettings,
+		ResumeInstanceSettings, StopInstanceSettings,
+	},
 	metronome::{handle::MetronomeHandle, Metronome, MetronomeId, MetronomeSettings},
 	mixer::{
-		MainTrackHandle, SendTrackHandle, SendTrackId, SendTrackSettings, SubTrackHandle,
-		SubTrackId, SubTrackSettings, Track, TrackIndex,
+		config::{MixerConfig, MixerConfigHandles},
+		effect::{
+			handle::EffectHandle,
+			muffle::{Muffle, MuffleHandle, MuffleSettings},
+			Effect, EffectId, EffectSettings,
+		},
+		levels::{HeadroomTarget, TrackLevelReport, TrackLevels},
+		MainTrackHandle, PauseTailBehavior, SendTrackHandle, SendTrackId, SendTrackSettings,
+		SubTrackHandle, SubTrackId, SubTrackSettings, Track, TrackIndex, MAX_OUTPUTS,
+	},
+	parameter::{
+		handle::{ModulationHandle, ParameterHandle},
+		DerivedParameterSettings, Modulation, ModulationId, ModulationSettings, ParameterId,
+		ParameterSettings,
 	},
-	parameter::{handle::ParameterHandle, ParameterId, ParameterSettings},
 	sequence::{
-		handle::SequenceInstanceHandle, Sequence, SequenceInstanceId, SequenceInstanceSettings,
+		definition::SequenceDefinition, handle::SequenceInstanceHandle, Sequence,
+		SequenceInstanceId, SequenceInstanceSettings,
 	},
 	sound::{handle::SoundHandle, Sound, SoundId},
+	util::SharedRng,
+	voice::VoiceId,
+	PlayableId, StartTime,
 };
+#[cfg(feature = "cpal")]
 use cpal::{
 	traits::{DeviceTrait, HostTrait, StreamTrait},
-	Stream,
+	BufferSize, Stream, SupportedBufferSize,
 };
 use instant::Instant;
 
+#[cfg(not(test))]
 const DROP_CLEANUP_TIMEOUT_MILLIS: u64 = 1000;
 
-/// Settings for an [`AudioManager`](crate::manager::AudioManager).
+/// A set of default [`AudioManagerSettings`] tuned for
+/// a kind of platform.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SettingsProfile {
+	/// General-purpose settings that aren't tuned for
+	/// any particular platform.
+	#[default]
+	Default,
+	/// Settings for desktop computers and consoles, which
+	/// have plenty of memory and processing power to spare.
+	Desktop,
+	/// Settings for phones and tablets, which have less memory
+	/// and processing power and need to save battery.
+	Mobile,
+	/// Settings for browsers, where audio is processed on
+	/// the main thread and commands can pile up while the
+	/// page is busy.
+	Web,
+}
+
+/// What happens when an instance is played while the
+/// [instance limit](AudioManagerSettings::num_instances)
+/// has been reached.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum InstanceLimitBehavior {
+	/// The instance that was started the longest time ago
+	/// is stopped to make room for the new one.
+	#[default]
+	StopOldest,
+	/// The new instance isn't played.
+	///
+	/// [`AudioManager::play`] returns an error if the limit had
+	/// already been reached at the start of the current audio
+	/// buffer. Instances that the audio thread rejects are
+	/// reported as [errors](AudioManager::pop_audio_thread_error).
+	RejectNew,
+}
+
+/// Whether the browser is letting an [`AudioManager`] play audio.
+///
+/// Browsers don't let pages play audio until the user has
+/// interacted with them, so audio started too early is silent.
+/// See [`AudioManager::resume_context`] for more info.
+#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AudioContextState {
+	/// The audio manager hasn't been started yet.
+	NotStarted,
+	/// The audio manager has been started, but the browser
+	/// isn't producing any audio yet.
+	Suspended,
+	/// The browser is producing audio.
+	Running,
+}
+
+/**
+Settings for an [`AudioManager`](crate::manager::AudioManager).
+
+Instead of starting from [`AudioManagerSettings::default`],
+you can start from settings tuned for the platform the game
+is running on, and adjust them from there:
+
+```no_run
+# use kira::manager::{AudioManager, AudioManagerSettings};
+let settings = if cfg!(target_arch = "wasm32") {
+	AudioManagerSettings::web()
+} else if cfg!(any(target_os = "android", target_os = "ios")) {
+	AudioManagerSettings::mobile()
+} else {
+	AudioManagerSettings::desktop()
+};
+# #[cfg(feature = "cpal")]
+# {
+let audio_manager = AudioManager::new(AudioManagerSettings {
+	num_sounds: 1000,
+	..settings
+})?;
+println!("using the {:?} settings", audio_manager.settings_profile());
+# }
+# Ok::<(), Box<dyn std::error::Error>>(())
+```
+*/
 #[derive(Debug, Clone)]
 #[cfg_attr(
 	feature = "serde_support",
@@ -65,14 +216,23 @@ pub struct AudioManagerSettings {
 	/// Each action you take, like starting an instance or pausing a sequence,
 	/// queues up one command.
 	pub num_commands: usize,
+	/// What to do when a command is sent while the command queue is full.
+	pub command_overflow_strategy: CommandOverflowStrategy,
 	/// The maximum number of sounds that can be loaded at a time.
 	pub num_sounds: usize,
 	/// The maximum number of arrangements that can be loaded at a time.
 	pub num_arrangements: usize,
 	/// The maximum number of parameters that can exist at a time.
 	pub num_parameters: usize,
+	/// The maximum number of modulations between parameters
+	/// that can exist at a time.
+	pub num_modulations: usize,
 	/// The maximum number of instances of sounds that can be playing at a time.
 	pub num_instances: usize,
+	/// What happens when an instance is played while
+	/// [`num_instances`](Self::num_instances) instances are
+	/// already playing.
+	pub instance_limit_behavior: InstanceLimitBehavior,
 	/// The maximum number of sequences that can be running at a time.
 	pub num_sequences: usize,
 	/// The maximum number of mixer sub-tracks that can be used at a time.
@@ -85,26 +245,211 @@ pub struct AudioManagerSettings {
 	pub num_streams: usize,
 	/// The maximum number of metronomes that can be used at a time.
 	pub num_metronomes: usize,
+	/// Whether there should be a "cool off" period after any
+	/// sound or arrangement is played, and if so, the duration
+	/// of that period.
+	///
+	/// This applies on top of the cooldowns of individual sounds,
+	/// arrangements, and groups.
+	pub global_cooldown: Option<f64>,
+	/// The duration (in seconds) of the short fade used when
+	/// an instance is stopped, paused, resumed, or seeked
+	/// without a fade tween.
+	///
+	/// Cutting off audio in the middle of a waveform causes
+	/// an audible click, so even "immediate" changes fade in
+	/// or out over a few milliseconds. Set this to `0.0` to
+	/// make them truly immediate.
+	pub declick_duration: f64,
+	/// Whether to unload sounds and arrangements automatically
+	/// once they're no longer used.
+	///
+	/// When this is enabled, a sound or arrangement is removed
+	/// from the audio thread when every handle to it has been
+	/// dropped and no instances of it are playing. Arrangements
+	/// keep the sounds they play loaded. Unused resources are
+	/// found when [`AudioManager::free_unused_resources`] is called,
+	/// and they count towards the sound and arrangement limits
+	/// until the audio thread has removed them.
+	///
+	/// Names don't keep resources loaded, and sequences don't
+	/// keep loaded the sounds and arrangements they play.
+	pub auto_unload: bool,
+	/// How long producing a buffer of audio can take (as a fraction
+	/// of the buffer's duration) before it's reported as a
+	/// [slow buffer](performance::PerformanceEvent::SlowBuffer).
+	pub slow_buffer_threshold: f64,
+	/// Whether the audio thread should keep a [trace](trace) of
+	/// its most recent decisions, and if so, the maximum number
+	/// of events to keep.
+	pub trace_capacity: Option<usize>,
+	/// The levels the main track should stay under, used
+	/// when suggesting volume changes after a
+	/// [level analysis](crate::mixer::levels).
+	pub main_track_headroom: HeadroomTarget,
+	/// What happens to the main track's effects while the
+	/// voices playing on it are paused.
+	pub main_track_pause_tail: PauseTailBehavior,
+	/// The number of frames the audio device should process
+	/// at a time, or `None` to let the device decide.
+	///
+	/// Smaller buffers reduce latency, and larger buffers use
+	/// less processing power. The size is clamped to the range
+	/// the device supports, and it's ignored if the device
+	/// doesn't report what sizes it supports.
+	pub buffer_size: Option<u32>,
+	/// Which of the mixer's outputs should be played on audio
+	/// devices other than the default one.
+	///
+	/// See the [`output_device`] module for more info.
+	pub output_devices: Vec<OutputDeviceSettings>,
+	/// Whether the audio context should stay suspended when the
+	/// audio manager is started, until
+	/// [`AudioManager::resume_context`] is called.
+	///
+	/// This is only used on the web.
+	pub start_suspended: bool,
+	/// The seed for the random choices made by the audio manager,
+	/// like the random values of instances played from handles
+	/// and the seeds of sequence instances.
+	///
+	/// If this is `None`, a random seed is used. Setting a seed
+	/// makes randomized audio reproducible, which is useful for
+	/// replays and lockstep multiplayer, as long as sounds are
+	/// played in the same order.
+	pub random_seed: Option<u64>,
+	/// The profile these settings are based on.
+	pub profile: SettingsProfile,
+}
+
+impl AudioManagerSettings {
+	/// Creates settings tuned for desktop computers and consoles.
+	///
+	/// These allow for more sounds, instances, and audio streams
+	/// than the default settings.
+	pub fn desktop() -> Self {
+		Self {
+			num_commands: 256,
+			num_sounds: 500,
+			num_arrangements: 200,
+			num_parameters: 200,
+			num_modulations: 200,
+			num_instances: 256,
+			num_sequences: 50,
+			num_send_tracks: 16,
+			num_groups: 200,
+			num_streams: 16,
+			num_metronomes: 10,
+			profile: SettingsProfile::Desktop,
+			..Default::default()
+		}
+	}
+
+	/// Creates settings tuned for phones and tablets.
+	///
+	/// These use less memory and allow fewer simultaneous
+	/// instances than the default settings, and they ask for
+	/// a larger audio buffer to save processing power.
+	pub fn mobile() -> Self {
+		Self {
+			num_sounds: 200,
+			num_arrangements: 50,
+			num_parameters: 50,
+			num_modulations: 50,
+			num_instances: 48,
+			num_sequences: 10,
+			num_sub_tracks: 32,
+			num_send_tracks: 4,
+			num_groups: 50,
+			num_streams: 4,
+			num_metronomes: 2,
+			buffer_size: Some(1024),
+			profile: SettingsProfile::Mobile,
+			..Default::default()
+		}
+	}
+
+	/// Creates settings tuned for browsers.
+	///
+	/// These use a larger command queue, since commands can
+	/// pile up while the page is busy, and allow fewer
+	/// simultaneous instances than the default settings.
+	pub fn web() -> Self {
+		Self {
+			num_commands: 256,
+			num_sounds: 200,
+			num_instances: 64,
+			num_sequences: 10,
+			num_sub_tracks: 32,
+			num_send_tracks: 4,
+			num_streams: 4,
+			num_metronomes: 2,
+			profile: SettingsProfile::Web,
+			..Default::default()
+		}
+	}
 }
 
 impl Default for AudioManagerSettings {
 	fn default() -> Self {
 		Self {
 			num_commands: 100,
+			command_overflow_strategy: CommandOverflowStrategy::ReturnError,
 			num_sounds: 100,
 			num_arrangements: 100,
 			num_parameters: 100,
+			num_modulations: 100,
 			num_instances: 100,
+			instance_limit_behavior: InstanceLimitBehavior::default(),
 			num_sequences: 25,
 			num_sub_tracks: 100,
 			num_send_tracks: 10,
 			num_groups: 100,
 			num_streams: 10,
 			num_metronomes: 5,
+			global_cooldown: None,
+			declick_duration: 0.001,
+			auto_unload: false,
+			slow_buffer_threshold: 1.0,
+			trace_capacity: None,
+			main_track_headroom: HeadroomTarget::default(),
+			main_track_pause_tail: PauseTailBehavior::default(),
+			buffer_size: None,
+			output_devices: vec![],
+			start_suspended: false,
+			random_seed: None,
+			profile: SettingsProfile::Default,
 		}
 	}
 }
 
+/// The pieces needed to create a [`Backend`] once the
+/// audio manager is started.
+struct BackendSetup {
+	settings: AudioManagerSettings,
+	command_consumer: Consumer<Command>,
+	shared_state: Arc<SharedBackendState>,
+	performance_monitor: PerformanceMonitor,
+	removed_resources: RemovedResourceProducer,
+	error_producer: Producer<AudioThreadError>,
+	tracer: Tracer,
+}
+
+impl BackendSetup {
+	fn into_backend(self, sample_rate: u32) -> Backend {
+		Backend::new(
+			sample_rate,
+			self.settings,
+			self.command_consumer,
+			self.shared_state,
+			self.performance_monitor,
+			self.removed_resources,
+			self.error_producer,
+			self.tracer,
+		)
+	}
+}
+
 /**
 Plays and manages audio.
 
@@ -115,142 +460,854 @@ pub struct AudioManager {
 	command_producer: CommandProducer,
 	resource_collector: Option<Collector>,
 	active_ids: ActiveIds,
-	sample_rate: u32,
+	// the sample rate of the audio stream, or 0 if the
+	// audio manager hasn't been started yet. this is shared
+	// with the mixer track handles so they can initialize
+	// effects with the correct sample rate
+	sample_rate: Arc<AtomicU32>,
+	// the state of the audio thread as of the start
+	// of the current audio buffer
+	shared_state: Arc<SharedBackendState>,
+	performance_counters: Arc<PerformanceCounters>,
+	performance_event_consumer: Consumer<PerformanceEvent>,
+	// resources the audio thread is done with, waiting
+	// to be freed on the main thread
+	removed_resource_consumer: Consumer<RemovedResource>,
+	// problems the audio thread ran into
+	error_consumer: Consumer<AudioThreadError>,
+	trace: Option<SharedTrace>,
+	// the latest snapshot captured by the audio thread
+	snapshot: SharedSnapshot,
+	names: Names,
+	// handles to every sound and arrangement, so they can
+	// be played without keeping their handles around
+	sound_handles: IndexMap<SoundId, SoundHandle>,
+	arrangement_handles: IndexMap<ArrangementId, ArrangementHandle>,
+	auto_unload: bool,
+	instance_limit_behavior: InstanceLimitBehavior,
+	// tracks whether any handles to each sound and arrangement
+	// still exist when resources are unloaded automatically
+	references: IndexMap<PlayableId, Weak<()>>,
+	// sounds and arrangements that are waiting for the audio
+	// thread to unload them. their IDs stay in use until the
+	// audio thread hands them back
+	unloading_ids: IndexSet<PlayableId>,
+	// keeps the sounds each arrangement plays loaded
+	arrangement_sound_references: IndexMap<ArrangementId, Vec<Arc<()>>>,
+	track_levels: Vec<(TrackIndex, HeadroomTarget, Arc<TrackLevels>)>,
+	settings_profile: SettingsProfile,
+	// picks the random seeds of instances and sequences
+	// started from the main thread
+	rng: SharedRng,
+	// holds everything needed to create the backend until
+	// the audio manager is started
+	backend_setup: Option<Box<BackendSetup>>,
 
-	#[cfg(not(target_arch = "wasm32"))]
-	quit_signal_producer: Producer<bool>,
+	#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+	quit_signal_producer: Option<Producer<bool>>,
 	// on wasm, holds the stream (as it has been created on the main thread)
 	// so it can live for as long as the audio manager
-	// in all cases, in benchmarking mode, we do not want an
-	// audio stream anyway so we leave it out
-	#[cfg(all(target_arch = "wasm32", not(feature = "benchmarking")))]
-	_stream: Stream,
+	#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+	_streams: Vec<Stream>,
+	// on wasm, the audio clock's frame count when the audio
+	// context was last resumed, or `None` if it's suspended
+	#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+	context_resumed_at: Option<u64>,
 }
 
 impl AudioManager {
 	/// Creates a new audio manager and starts an audio thread.
-	#[cfg(not(target_arch = "wasm32"))]
+	///
+	/// This is equivalent to calling [`AudioManager::new_deferred`]
+	/// followed by [`AudioManager::start`].
+	#[cfg(feature = "cpal")]
 	pub fn new(settings: AudioManagerSettings) -> Result<Self, SetupError> {
+		let mut audio_manager = Self::new_deferred(settings);
+		audio_manager.start()?;
+		Ok(audio_manager)
+	}
+
+	/// Creates a new audio manager without opening an audio
+	/// device or starting an audio thread.
+	///
+	/// Resources can be added to a deferred audio manager, and
+	/// commands can be sent to it, but they won't take effect until
+	/// [`start`](AudioManager::start) is called. Commands sent in the
+	/// meantime still count towards the
+	/// [`num_commands`](AudioManagerSettings::num_commands) limit.
+	///
+	/// Effects cannot be added to mixer tracks until the audio
+	/// manager is started, since the sample rate of the audio
+	/// device isn't known yet.
+	///
+	/// ```no_run
+	/// # use kira::{manager::{AudioManager, AudioManagerSettings}, sound::{Sound, SoundSettings}};
+	/// #
+	/// let mut audio_manager = AudioManager::new_deferred(AudioManagerSettings::default());
+	/// // load sounds while the audio device is still untouched
+	/// let sound_handle = audio_manager.load_sound("loop.ogg", SoundSettings::default())?;
+	/// // later, when the platform is ready to play audio
+	/// # #[cfg(feature = "cpal")]
+	/// audio_manager.start()?;
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	pub fn new_deferred(settings: AudioManagerSettings) -> Self {
 		let active_ids = ActiveIds::new(&settings);
-		let (quit_signal_producer, mut quit_signal_consumer) = RingBuffer::new(1).split();
 		let (command_producer, command_consumer) = RingBuffer::new(settings.num_commands).split();
-		let resource_collector = Collector::new();
+		let shared_state = Arc::new(SharedBackendState::default());
+		let (performance_monitor, performance_counters, performance_event_consumer) =
+			Self::create_performance_monitor(&settings);
+		let (removed_resource_producer, removed_resource_consumer) =
+			RingBuffer::new(settings.num_commands + settings.num_sequences).split();
+		let (error_producer, error_consumer) = RingBuffer::new(settings.num_commands).split();
+		let (tracer, trace) = Tracer::new(settings.trace_capacity);
+		Self {
+			command_producer: CommandProducer::new(
+				command_producer,
+				settings.command_overflow_strategy,
+			),
+			resource_collector: Some(Collector::new()),
+			active_ids,
+			sample_rate: Arc::new(AtomicU32::new(0)),
+			shared_state: shared_state.clone(),
+			performance_counters,
+			performance_event_consumer,
+			removed_resource_consumer,
+			error_consumer,
+			trace,
+			snapshot: Default::default(),
+			names: Names::default(),
+			sound_handles: IndexMap::new(),
+			arrangement_handles: IndexMap::new(),
+			auto_unload: settings.auto_unload,
+			instance_limit_behavior: settings.instance_limit_behavior,
+			references: IndexMap::new(),
+			unloading_ids: IndexSet::new(),
+			arrangement_sound_references: IndexMap::new(),
+			track_levels: vec![(
+				TrackIndex::Main,
+				settings.main_track_headroom,
+				shared_state.main_track_levels.clone(),
+			)],
+			settings_profile: settings.profile,
+			rng: SharedRng::new(settings.random_seed),
+			backend_setup: Some(Box::new(BackendSetup {
+				settings,
+				command_consumer,
+				shared_state,
+				performance_monitor,
+				removed_resources: RemovedResourceProducer::new(removed_resource_producer),
+				error_producer,
+				tracer,
+			})),
+			#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+			quit_signal_producer: None,
+			#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+			_streams: vec![],
+			#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+			context_resumed_at: None,
+		}
+	}
+
+	fn create_performance_monitor(
+		settings: &AudioManagerSettings,
+	) -> (
+		PerformanceMonitor,
+		Arc<PerformanceCounters>,
+		Consumer<PerformanceEvent>,
+	) {
+		let performance_counters = Arc::new(PerformanceCounters::default());
+		let (performance_event_producer, performance_event_consumer) =
+			RingBuffer::new(NUM_PERFORMANCE_EVENTS).split();
+		(
+			PerformanceMonitor::new(
+				performance_counters.clone(),
+				performance_event_producer,
+				settings.slow_buffer_threshold,
+			),
+			performance_counters,
+			performance_event_consumer,
+		)
+	}
+
+	/// Pauses a voice.
+	pub fn pause_voice(
+		&mut self,
+		id: impl Into<VoiceId>,
+		settings: PauseInstanceSettings,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::PauseVoice(id.into(), settings).into())
+	}
+
+	/// Resumes a voice.
+	pub fn resume_voice(
+		&mut self,
+		id: impl Into<VoiceId>,
+		settings: ResumeInstanceSettings,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::ResumeVoice(id.into(), settings).into())
+	}
+
+	/// Stops a voice.
+	pub fn stop_voice(
+		&mut self,
+		id: impl Into<VoiceId>,
+		settings: StopInstanceSettings,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::StopVoice(id.into(), settings).into())
+	}
+
+	/// Pauses every instance and audio stream playing on a mixer track.
+	pub fn pause_voices_on_track(
+		&mut self,
+		track: TrackIndex,
+		settings: PauseInstanceSettings,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::PauseVoicesOnTrack(track, settings).into())
+	}
+
+	/// Resumes every instance and audio stream playing on a mixer track.
+	pub fn resume_voices_on_track(
+		&mut self,
+		track: TrackIndex,
+		settings: ResumeInstanceSettings,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::ResumeVoicesOnTrack(track, settings).into())
+	}
+
+	/// Stops every instance and audio stream playing on a mixer track.
+	pub fn stop_voices_on_track(
+		&mut self,
+		track: TrackIndex,
+		settings: StopInstanceSettings,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::StopVoicesOnTrack(track, settings).into())
+	}
+
+	/// Returns the profile the audio manager's settings are based on.
+	pub fn settings_profile(&self) -> SettingsProfile {
+		self.settings_profile
+	}
+
+	/// Returns the number of instances and audio streams that
+	/// were playing at the start of the current audio buffer.
+	pub fn num_voices(&self) -> usize {
+		self.shared_state.num_voices.load(Ordering::Relaxed)
+	}
+
+	/// Returns how many resources of a kind are in use and how
+	/// many can be in use at a time.
+	///
+	/// The numbers of instances and sequence instances are
+	/// as of the start of the current audio buffer.
+	pub fn resource_usage(&self, kind: ResourceKind) -> ResourceUsage {
+		self.active_ids.usage(kind, &self.shared_state)
+	}
+
+	/// Returns counts of the problems the audio thread has run
+	/// into since the audio manager was started.
+	pub fn performance_report(&self) -> PerformanceReport {
+		self.performance_counters.report()
+	}
+
+	/// Gets the first problem the audio thread ran into since the
+	/// last call to `pop_performance_event`.
+	pub fn pop_performance_event(&mut self) -> Option<PerformanceEvent> {
+		self.performance_event_consumer.pop()
+	}
+
+	/// Gets the first error the audio thread ran into since
+	/// the last call to `pop_audio_thread_error`.
+	///
+	/// Errors include commands that refer to instances, sounds,
+	/// or arrangements that no longer exist (for example, pausing
+	/// an instance that has already finished playing), resource
+	/// limits being reached, and audio streams that fail.
+	///
+	/// ```no_run
+	/// # use kira::manager::{AudioManager, AudioManagerSettings};
+	/// # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+	/// // in the game loop
+	/// while let Some(error) = audio_manager.pop_audio_thread_error() {
+	/// 	eprintln!("audio error: {}", error);
+	/// }
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	pub fn pop_audio_thread_error(&mut self) -> Option<AudioThreadError> {
+		self.error_consumer.pop()
+	}
+
+	/// Returns a copy of the audio thread's [trace](trace),
+	/// from the oldest event to the newest.
+	///
+	/// If [`trace_capacity`](AudioManagerSettings::trace_capacity)
+	/// isn't set, the trace is always empty.
+	pub fn dump_trace(&self) -> Vec<TraceEvent> {
+		match &self.trace {
+			Some(trace) => trace
+				.lock()
+				.map(|events| events.iter().copied().collect())
+				.unwrap_or_default(),
+			None => vec![],
+		}
+	}
+
+	/// Asks the audio thread to capture a [snapshot](snapshot)
+	/// of its state.
+	///
+	/// The snapshot can be retrieved with
+	/// [`pop_snapshot`](AudioManager::pop_snapshot) after the
+	/// audio thread has processed the request.
+	pub fn capture_snapshot(&mut self) -> Result<(), CommandError> {
+		let request = SnapshotRequest::new(
+			self.snapshot.clone(),
+			self.active_ids.instance_capacity,
+			self.active_ids.active_parameter_ids.len(),
+			self.active_ids.active_metronome_ids.len(),
+			self.active_ids.sequence_instance_capacity,
+		);
+		self.command_producer
+			.push(Command::CaptureSnapshot(Owned::new(
+				&self.resource_collector().handle(),
+				request,
+			)))
+	}
+
+	/// Returns the latest snapshot captured by the audio thread,
+	/// if there's one that hasn't been retrieved yet.
+	pub fn pop_snapshot(&mut self) -> Option<AudioSnapshot> {
+		self.snapshot
+			.lock()
+			.map(|mut snapshot| snapshot.take())
+			.unwrap_or_default()
+	}
+
+	/// Restores the state from a [snapshot](snapshot).
+	///
+	/// Instances from the snapshot are played again with new IDs,
+	/// so they never replace instances that are already playing,
+	/// and handles to them are returned. If the
+	/// [instance limit](AudioManagerSettings::instance_limit_behavior)
+	/// rejects new instances, only the instances that fit under
+	/// the limit are played. Parameters, metronomes, and sequence
+	/// instances are moved back to their saved state if they exist.
+	pub fn restore_snapshot(
+		&mut self,
+		snapshot: &AudioSnapshot,
+	) -> Result<Vec<InstanceHandle>, CommandError> {
+		for parameter in &snapshot.parameters {
+			if self.active_ids.active_parameter_ids.contains(&parameter.id) {
+				self.command_producer.push(
+					ParameterCommand::SetParameter(
+						parameter.id,
+						parameter.value,
+						None,
+						StartTime::Immediate,
+					)
+					.into(),
+				)?;
+			}
+		}
+		for metronome in &snapshot.metronomes {
+			if !self.active_ids.active_metronome_ids.contains(&metronome.id) {
+				continue;
+			}
+			self.command_producer
+				.push(MetronomeCommand::SetMetronomeTime(metronome.id, metronome.time).into())?;
+			self.command_producer.push(
+				if metronome.ticking {
+					MetronomeCommand::StartMetronome(metronome.id)
+				} else {
+					MetronomeCommand::PauseMetronome(metronome.id)
+				}
+				.into(),
+			)?;
+		}
+		for sequence in &snapshot.sequences {
+			self.command_producer.push(
+				SequenceCommand::SetSequenceInstancePosition(
+					sequence.id,
+					sequence.position,
+					sequence.loop_count,
+				)
+				.into(),
+			)?;
+			if sequence.paused {
+				self.command_producer
+					.push(SequenceCommand::PauseSequenceInstance(sequence.id).into())?;
+			}
+		}
+		let mut handles = vec![];
+		let usage = self.resource_usage(ResourceKind::Instance);
+		for instance_snapshot in &snapshot.instances {
+			if self.instance_limit_behavior == InstanceLimitBehavior::RejectNew
+				&& usage.used + handles.len() >= usage.capacity
+			{
+				break;
+			}
+			let playable_exists = match instance_snapshot.playable {
+				PlayableId::Sound(id) => self.active_ids.active_sound_ids.contains(&id),
+				PlayableId::Arrangement(id) => self.active_ids.active_arrangement_ids.contains(&id),
+			};
+			if !playable_exists || !self.does_track_exist(instance_snapshot.track) {
+				continue;
+			}
+			let id = InstanceId::new();
+			let instance = Instance::from_snapshot(instance_snapshot);
+			handles.push(InstanceHandle::new(
+				id,
+				instance.public_state(),
+				instance.public_position(),
+				0,
+				self.instance_effect_context(),
+				self.command_producer.clone(),
+			));
+			self.command_producer
+				.push(InstanceCommand::Play(id, instance).into())?;
+		}
+		Ok(handles)
+	}
+
+	/// Starts measuring the levels of every mixer track.
+	///
+	/// Any levels measured by a previous analysis are discarded.
+	/// See the [`levels`](crate::mixer::levels) module for more info.
+	pub fn start_level_analysis(&mut self) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetLevelAnalysisEnabled(true).into())
+	}
+
+	/// Stops measuring the levels of mixer tracks.
+	///
+	/// The levels measured so far are kept until the next
+	/// analysis is started.
+	pub fn stop_level_analysis(&mut self) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetLevelAnalysisEnabled(false).into())
+	}
+
+	/// Returns the levels measured for each mixer track and
+	/// how much each track's volume should be changed to
+	/// reach its headroom target.
+	pub fn level_report(&self) -> Vec<TrackLevelReport> {
+		self.track_levels
+			.iter()
+			.map(|(track, target, levels)| TrackLevelReport::new(*track, *target, levels))
+			.collect()
+	}
+
+	/// Returns the time on the audio clock (in seconds).
+	///
+	/// The audio clock starts at 0 when the audio manager is
+	/// started and is updated at the start of each audio buffer,
+	/// so it advances in steps of one buffer. Together with
+	/// [`MetronomeEvent::time`](crate::metronome::MetronomeEvent::time),
+	/// it can be used to schedule visuals in sync with the audio.
+	pub fn audio_time(&self) -> f64 {
+		let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+		if sample_rate == 0 {
+			return 0.0;
+		}
+		self.shared_state.frame_count.load(Ordering::Relaxed) as f64 / sample_rate as f64
+	}
+
+	/// Returns how long it takes (in seconds) for a command sent
+	/// from the main thread to be heard in the worst case.
+	///
+	/// This is the duration of one audio buffer (since commands
+	/// are picked up at the start of the next buffer) plus the
+	/// latency of the audio device. Returns `0.0` if the audio
+	/// manager hasn't produced any audio yet.
+	pub fn output_latency(&self) -> f64 {
+		match self.shared_state.output_timing.load() {
+			Some(timing) => timing.buffer_duration + timing.device_latency,
+			None => 0.0,
+		}
+	}
+
+	/// Converts a moment in game time to a time on the
+	/// audio clock (in seconds).
+	///
+	/// This can be used to find out which time on the audio clock
+	/// corresponds to a player's input, for example to judge
+	/// the timing of a button press in a rhythm game.
+	pub fn audio_time_at(&self, instant: Instant) -> f64 {
+		match self.shared_state.output_timing.load() {
+			Some(timing) => {
+				timing.buffer_start_time + signed_duration_secs(timing.buffer_start, instant)
+			}
+			None => self.audio_time(),
+		}
+	}
+
+	/// Returns the moment in game time when audio produced at a
+	/// time on the audio clock (in seconds) will be heard, including
+	/// the latency of the audio device.
+	///
+	/// This can be used to line up visuals, like lip movements,
+	/// with the audio the player actually hears.
+	pub fn heard_at(&self, audio_time: f64) -> Instant {
+		match self.shared_state.output_timing.load() {
+			Some(timing) => offset_instant(
+				timing.buffer_start,
+				audio_time - timing.buffer_start_time + timing.device_latency,
+			),
+			None => offset_instant(Instant::now(), audio_time - self.audio_time()),
+		}
+	}
+
+	/// Returns `true` if the audio manager has been started.
+	pub fn is_started(&self) -> bool {
+		self.sample_rate.load(Ordering::Relaxed) != 0
+	}
+
+	/// Takes the state needed to start the audio manager, or
+	/// returns `Ok(None)` if it's already been started.
+	#[cfg(feature = "cpal")]
+	fn take_backend_setup(&mut self) -> Result<Option<Box<BackendSetup>>, SetupError> {
+		match self.backend_setup.take() {
+			Some(backend_setup) => Ok(Some(backend_setup)),
+			None if self.is_started() => Ok(None),
+			// the setup was lost when setting up the stream failed
+			None => Err(SetupError::StreamSetupFailed),
+		}
+	}
+
+	/// Opens the default audio device and starts processing audio.
+	///
+	/// Any commands sent before the audio manager was started will
+	/// be processed right away. If the audio manager has already been
+	/// started, this does nothing.
+	///
+	/// If setting up the audio stream fails, the audio manager stays
+	/// in its deferred state, so `start` can be called again later.
+	#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+	pub fn start(&mut self) -> Result<(), SetupError> {
+		let backend_setup = match self.take_backend_setup()? {
+			Some(backend_setup) => backend_setup,
+			None => return Ok(()),
+		};
+		let (quit_signal_producer, mut quit_signal_consumer) = RingBuffer::new(1).split();
 
 		const WRAPPER_THREAD_SLEEP_DURATION: f64 = 1.0 / 60.0;
 
-		let (mut setup_result_producer, mut setup_result_consumer) = RingBuffer::new(1).split();
+		let (setup_result_sender, setup_result_receiver) = std::sync::mpsc::channel();
 		// set up a cpal stream on a new thread. we could do this on the main thread,
 		// but that causes issues with LÖVE.
-		std::thread::spawn(move || {
-			match Self::setup_stream(settings, command_consumer) {
-				Ok((_stream, sample_rate)) => {
-					setup_result_producer.push(Ok(sample_rate)).unwrap();
-					// wait for a quit message before ending the thread and dropping
-					// the stream
-					while quit_signal_consumer.pop().is_none() {
-						std::thread::sleep(std::time::Duration::from_secs_f64(
-							WRAPPER_THREAD_SLEEP_DURATION,
-						));
-					}
+		std::thread::spawn(move || match Self::setup_stream(backend_setup) {
+			Ok((_streams, sample_rate)) => {
+				// the audio manager waits for the result, so
+				// this only fails if the audio manager is gone
+				if setup_result_sender.send(Ok(sample_rate)).is_err() {
+					return;
 				}
-				Err(error) => {
-					setup_result_producer.push(Err(error)).unwrap();
+				// wait for a quit message before ending the thread and dropping
+				// the streams
+				while quit_signal_consumer.pop().is_none() {
+					std::thread::sleep(std::time::Duration::from_secs_f64(
+						WRAPPER_THREAD_SLEEP_DURATION,
+					));
 				}
 			}
+			Err(error) => {
+				setup_result_sender.send(Err(error)).ok();
+			}
 		});
-		// wait for the audio thread to report back a result
-		let sample_rate = loop {
-			if let Some(result) = setup_result_consumer.pop() {
-				match result {
-					Ok(sample_rate) => break sample_rate,
-					Err(error) => return Err(error),
-				}
+		// wait for the thread to report back a result. if it stops
+		// without sending one, the backend setup is gone with it
+		let sample_rate = match setup_result_receiver.recv() {
+			Ok(Ok(sample_rate)) => sample_rate,
+			Ok(Err((error, backend_setup))) => {
+				self.backend_setup = Some(backend_setup);
+				return Err(error);
 			}
+			Err(_) => return Err(SetupError::StreamSetupFailed),
 		};
 
-		Ok(Self {
-			quit_signal_producer,
-			command_producer: CommandProducer::new(command_producer),
-			active_ids,
-			sample_rate,
-			resource_collector: Some(resource_collector),
-		})
+		self.quit_signal_producer = Some(quit_signal_producer);
+		self.sample_rate.store(sample_rate, Ordering::Relaxed);
+		Ok(())
 	}
 
-	/// Creates a new audio manager and starts an audio thread.
-	#[cfg(target_arch = "wasm32")]
-	pub fn new(settings: AudioManagerSettings) -> Result<Self, SetupError> {
-		let active_ids = ActiveIds::new(&settings);
-		let (command_producer, command_consumer) = RingBuffer::new(settings.num_commands).split();
-		let resource_collector = Collector::new();
-		let (_stream, sample_rate) = Self::setup_stream(settings, command_consumer)?;
-		Ok(Self {
-			command_producer: CommandProducer::new(command_producer),
-			active_ids,
-			resource_collector: Some(resource_collector),
-			sample_rate,
-			_stream,
-		})
+	/// Opens the default audio device and starts processing audio.
+	///
+	/// Any commands sent before the audio manager was started will
+	/// be processed right away. If the audio manager has already been
+	/// started, this does nothing.
+	///
+	/// If setting up the audio stream fails, the audio manager stays
+	/// in its deferred state, so `start` can be called again later.
+	#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+	pub fn start(&mut self) -> Result<(), SetupError> {
+		let backend_setup = match self.take_backend_setup()? {
+			Some(backend_setup) => backend_setup,
+			None => return Ok(()),
+		};
+		let start_suspended = backend_setup.settings.start_suspended;
+		match Self::setup_stream(backend_setup) {
+			Ok((streams, sample_rate)) => {
+				self._streams = streams;
+				self.sample_rate.store(sample_rate, Ordering::Relaxed);
+				if !start_suspended {
+					self.context_resumed_at = Some(0);
+				}
+				Ok(())
+			}
+			Err((error, backend_setup)) => {
+				self.backend_setup = Some(backend_setup);
+				Err(error)
+			}
+		}
+	}
+
+	/// Starts the audio manager without opening an audio device,
+	/// returning the [`Backend`] that produces the audio.
+	///
+	/// This is for engines and middleware that own the audio
+	/// callback themselves. The backend should be moved to the
+	/// engine's audio thread and asked for a frame of audio at a
+	/// time at the given sample rate. If the audio manager has
+	/// already been started, this returns `None`.
+	///
+	/// This is available even when the `cpal` feature is disabled,
+	/// so kira can be compiled without its own audio device and
+	/// threading support:
+	///
+	/// ```no_run
+	/// # use kira::manager::{AudioManager, AudioManagerSettings, Backend};
+	/// let mut audio_manager = AudioManager::new_deferred(AudioManagerSettings::default());
+	/// let mut backend = audio_manager.start_embedded(48000).unwrap();
+	/// // in the engine's audio callback
+	/// fn fill_buffer(backend: &mut Backend, buffer: &mut [f32]) {
+	/// 	backend.start_buffer();
+	/// 	for frame in buffer.chunks_exact_mut(2) {
+	/// 		let out = backend.process();
+	/// 		frame[0] = out.left as f32;
+	/// 		frame[1] = out.right as f32;
+	/// 	}
+	/// }
+	/// ```
+	pub fn start_embedded(&mut self, sample_rate: u32) -> Option<Backend> {
+		let backend_setup = self.backend_setup.take()?;
+		self.sample_rate.store(sample_rate, Ordering::Relaxed);
+		#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+		{
+			self.context_resumed_at = Some(0);
+		}
+		Some(backend_setup.into_backend(sample_rate))
+	}
+
+	/// Creates an audio manager that doesn't use an audio device
+	/// or start an audio thread, and returns it along with the
+	/// [`Backend`] that produces its audio.
+	///
+	/// Nothing is heard until the backend is asked for audio, so
+	/// this is useful for tests, rendering audio to a file faster
+	/// than real time, and engines that step the audio themselves:
+	///
+	/// ```
+	/// # use kira::{
+	/// # 	instance::InstanceSettings,
+	/// # 	manager::{AudioManager, AudioManagerSettings},
+	/// # 	sound::{Sound, SoundSettings},
+	/// # 	Frame,
+	/// # };
+	/// let (mut audio_manager, mut backend) =
+	/// 	AudioManager::new_headless(AudioManagerSettings::default(), 44100);
+	/// let mut sound_handle = audio_manager.add_sound(Sound::from_frames(
+	/// 	44100,
+	/// 	vec![Frame::from_mono(0.5); 44100],
+	/// 	SoundSettings::default(),
+	/// ))?;
+	/// sound_handle.play(InstanceSettings::default())?;
+	/// // render one second of audio
+	/// let rendered: Vec<Frame> = (0..44100).map(|_| backend.process()).collect();
+	/// # drop(backend);
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	///
+	/// This is equivalent to calling [`AudioManager::new_deferred`]
+	/// followed by [`AudioManager::start_embedded`]. The backend
+	/// should be dropped before the audio manager so the audio
+	/// manager can free the resources the backend was using.
+	pub fn new_headless(settings: AudioManagerSettings, sample_rate: u32) -> (Self, Backend) {
+		let mut audio_manager = Self::new_deferred(settings);
+		// a new audio manager always has a backend to start
+		let backend = audio_manager.start_embedded(sample_rate).unwrap();
+		(audio_manager, backend)
+	}
+
+	/// Resumes the browser's audio context, starting the audio
+	/// manager first if it hasn't been started yet.
+	///
+	/// Browsers keep audio contexts suspended until the user has
+	/// interacted with the page, so this should be called from an
+	/// event handler for a click, tap, or key press. Combined with
+	/// [`AudioManagerSettings::start_suspended`], this lets a game
+	/// set up its audio right away and start hearing it once the
+	/// player has interacted with the page:
+	///
+	/// ```no_run
+	/// # use kira::manager::{AudioContextState, AudioManager, AudioManagerSettings};
+	/// let mut audio_manager = AudioManager::new(AudioManagerSettings {
+	/// 	start_suspended: true,
+	/// 	..AudioManagerSettings::web()
+	/// })?;
+	/// // in the click handler
+	/// if audio_manager.context_state() != AudioContextState::Running {
+	/// 	audio_manager.resume_context()?;
+	/// }
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+	pub fn resume_context(&mut self) -> Result<(), SetupError> {
+		self.start()?;
+		for stream in &self._streams {
+			stream.play()?;
+		}
+		self.context_resumed_at = Some(self.shared_state.frame_count.load(Ordering::Relaxed));
+		Ok(())
+	}
+
+	/// Returns whether the browser is letting the audio
+	/// manager play audio.
+	///
+	/// The audio context only counts as running once audio
+	/// has been produced since it was resumed, since browsers
+	/// ignore requests to resume that don't come from a user
+	/// interaction.
+	#[cfg(all(target_arch = "wasm32", feature = "cpal"))]
+	pub fn context_state(&self) -> AudioContextState {
+		if !self.is_started() {
+			return AudioContextState::NotStarted;
+		}
+		match self.context_resumed_at {
+			Some(frame_count)
+				if self.shared_state.frame_count.load(Ordering::Relaxed) > frame_count =>
+			{
+				AudioContextState::Running
+			}
+			_ => AudioContextState::Suspended,
+		}
 	}
 
+	/// Sets up a cpal stream for the default device and any extra
+	/// output devices. If anything goes wrong, the backend setup
+	/// is handed back so the audio manager can try again later.
+	#[cfg(feature = "cpal")]
 	fn setup_stream(
-		settings: AudioManagerSettings,
-		command_consumer: Consumer<Command>,
-	) -> Result<(Stream, u32), SetupError> {
+		backend_setup: Box<BackendSetup>,
+	) -> Result<(Vec<Stream>, u32), (SetupError, Box<BackendSetup>)> {
 		let host = cpal::default_host();
-		let device = host
-			.default_output_device()
-			.ok_or(SetupError::NoDefaultOutputDevice)?;
-		let config = device.default_output_config()?.config();
+		let device = match host.default_output_device() {
+			Some(device) => device,
+			None => return Err((SetupError::NoDefaultOutputDevice, backend_setup)),
+		};
+		let supported_config = match device.default_output_config() {
+			Ok(config) => config,
+			Err(error) => return Err((error.into(), backend_setup)),
+		};
+		let mut config = supported_config.config();
+		if let (Some(buffer_size), SupportedBufferSize::Range { min, max }) = (
+			backend_setup.settings.buffer_size,
+			supported_config.buffer_size(),
+		) {
+			config.buffer_size = BufferSize::Fixed(buffer_size.clamp(*min, *max));
+		}
 		let sample_rate = config.sample_rate.0;
 		let channels = config.channels;
-		let mut backend = Backend::new(sample_rate, settings, command_consumer);
-		let stream = device.build_output_stream(
+		let (mut streams, mut device_outputs) =
+			match open_output_devices(&backend_setup.settings.output_devices, sample_rate) {
+				Ok(output_devices) => output_devices,
+				Err(error) => return Err((error, backend_setup)),
+			};
+		// the backend is only created and sent to the audio thread once
+		// the stream is playing, so that if setup fails, the command
+		// consumer is still available for another attempt
+		let (mut backend_producer, mut backend_consumer) = RingBuffer::<Backend>::new(1).split();
+		let mut backend = None;
+		let mut previous_callback: Option<cpal::StreamInstant> = None;
+		let stream = match device.build_output_stream(
 			&config,
-			move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+			move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+				let start = Instant::now();
+				let timestamp = info.timestamp();
+				let callback = timestamp.callback;
+				let callback_interval =
+					previous_callback.and_then(|previous| callback.duration_since(&previous));
+				previous_callback = Some(callback);
+				if backend.is_none() {
+					backend = backend_consumer.pop();
+				}
+				if let Some(backend) = &mut backend {
+					backend.start_buffer();
+					backend.set_output_timing(
+						data.len() / channels as usize,
+						timestamp
+							.playback
+							.duration_since(&callback)
+							.unwrap_or_default(),
+					);
+				}
 				for frame in data.chunks_exact_mut(channels as usize) {
-					let out = backend.process();
-					if channels == 1 {
-						frame[0] = (out.left + out.right) / 2.0;
-					} else {
-						frame[0] = out.left;
-						frame[1] = out.right;
+					match &mut backend {
+						Some(backend) => process_frame(backend, &mut device_outputs, frame),
+						None => frame.iter_mut().for_each(|sample| *sample = 0.0),
 					}
 				}
+				if let Some(backend) = &mut backend {
+					backend.finish_buffer(
+						data.len() / channels as usize,
+						callback_interval,
+						start.elapsed(),
+					);
+				}
 			},
 			move |_| {},
-		)?;
-		stream.play()?;
-		Ok((stream, sample_rate))
+		) {
+			Ok(stream) => stream,
+			Err(error) => return Err((error.into(), backend_setup)),
+		};
+		// on the web, the stream can stay suspended until
+		// the user has interacted with the page
+		if !(cfg!(target_arch = "wasm32") && backend_setup.settings.start_suspended) {
+			if let Err(error) = stream.play() {
+				return Err((error.into(), backend_setup));
+			}
+		}
+		streams.insert(0, stream);
+		// check for room before the backend setup is used up,
+		// so it can be handed back if the backend can't be sent
+		if backend_producer.is_full() {
+			return Err((SetupError::StreamSetupFailed, backend_setup));
+		}
+		backend_producer
+			.push(backend_setup.into_backend(sample_rate))
+			.ok();
+		Ok((streams, sample_rate))
 	}
 
-	#[cfg(any(feature = "benchmarking", test))]
 	/// Creates an [`AudioManager`] and [`Backend`] without sending
 	/// the backend to another thread.
 	///
-	/// This is useful for updating the backend manually for
-	/// benchmarking.
+	/// This is a shortcut for [`AudioManager::new_headless`] with
+	/// a sample rate of 48,000 Hz.
+	#[deprecated = "use `AudioManager::new_headless` instead"]
 	pub fn new_without_audio_thread(settings: AudioManagerSettings) -> (Self, Backend) {
-		const SAMPLE_RATE: u32 = 48000;
-		let (quit_signal_producer, _) = RingBuffer::new(1).split();
-		let (command_producer, command_consumer) = RingBuffer::new(settings.num_commands).split();
-		let resource_collector = Collector::new();
-		let audio_manager = Self {
-			quit_signal_producer,
-			command_producer: CommandProducer::new(command_producer),
-			active_ids: ActiveIds::new(&settings),
-			sample_rate: SAMPLE_RATE,
-			resource_collector: Some(resource_collector),
-		};
-		let backend = Backend::new(SAMPLE_RATE, settings, command_consumer);
-		(audio_manager, backend)
+		Self::new_headless(settings, 48000)
 	}
 
 	fn does_track_exist(&self, track: TrackIndex) -> bool {
 		match track {
 			TrackIndex::Main => true,
 			TrackIndex::Sub(id) => self.active_ids.active_sub_track_ids.contains(&id),
-			_ => todo!(),
+			TrackIndex::Send(id) => self.active_ids.active_send_track_ids.contains(&id),
 		}
 	}
 
@@ -265,12 +1322,19 @@ impl AudioManager {
 		self.resource_collector.as_ref().unwrap()
 	}
 
+	fn instance_effect_context(&self) -> InstanceEffectContext {
+		InstanceEffectContext {
+			sample_rate: self.sample_rate.clone(),
+			resource_collector_handle: self.resource_collector().handle(),
+		}
+	}
+
 	fn resource_collector_mut(&mut self) -> &mut Collector {
 		self.resource_collector.as_mut().unwrap()
 	}
 
 	/// Sends a sound to the audio thread and returns a handle to the sound.
-	pub fn add_sound(&mut self, sound: Sound) -> Result<SoundHandle, AddSoundError> {
+	pub fn add_sound(&mut self, mut sound: Sound) -> Result<SoundHandle, AddSoundError> {
 		if !self.does_track_exist(sound.default_track()) {
 			return Err(AddSoundError::NoTrackWithIndex(sound.default_track()));
 		}
@@ -278,7 +1342,16 @@ impl AudioManager {
 			return Err(AddSoundError::NoGroupWithId(group));
 		}
 		self.active_ids.add_sound_id(sound.id())?;
-		let handle = SoundHandle::new(&sound, self.command_producer.clone());
+		sound.init_statistics();
+		let handle = SoundHandle::new(
+			&sound,
+			self.command_producer.clone(),
+			self.rng.clone(),
+			self.instance_effect_context(),
+		)
+		.with_reference(self.reference(sound.id().into()));
+		self.sound_handles
+			.insert(handle.id(), handle.clone().with_reference(None));
 		let sound = Owned::new(&self.resource_collector().handle(), sound);
 		self.command_producer
 			.push(ResourceCommand::AddSound(sound).into())?;
@@ -303,15 +1376,70 @@ impl AudioManager {
 	pub fn remove_sound(&mut self, id: impl Into<SoundId>) -> Result<(), RemoveSoundError> {
 		let id = id.into();
 		self.active_ids.remove_sound_id(id)?;
+		self.names.sounds.retain(|_, handle| handle.id() != id);
+		if let Some(handle) = self.sound_handles.remove(&id) {
+			handle.mark_unloaded();
+		}
+		self.references.remove(&PlayableId::from(id));
 		self.command_producer
 			.push(ResourceCommand::RemoveSound(id).into())?;
 		Ok(())
 	}
 
+	/// Replaces the audio data and settings of a sound, keeping
+	/// its ID, and returns a new handle to the sound.
+	///
+	/// The ID in the new sound's settings is ignored. Arrangements,
+	/// sequences, and handles that refer to the sound will use
+	/// the new sound, and instances of the sound that are already
+	/// playing will continue from the same position with the new
+	/// audio data. This is useful for hearing changes to audio
+	/// files without restarting your game.
+	pub fn replace_sound(
+		&mut self,
+		id: impl Into<SoundId>,
+		sound: Sound,
+	) -> Result<SoundHandle, ReplaceSoundError> {
+		let id = id.into();
+		if !self.active_ids.active_sound_ids.contains(&id)
+			|| self.unloading_ids.contains(&PlayableId::from(id))
+		{
+			return Err(ReplaceSoundError::NoSoundWithId(id));
+		}
+		if !self.does_track_exist(sound.default_track()) {
+			return Err(ReplaceSoundError::NoTrackWithIndex(sound.default_track()));
+		}
+		if let Some(group) = self.first_missing_group_in_set(sound.groups()) {
+			return Err(ReplaceSoundError::NoGroupWithId(group));
+		}
+		let mut sound = sound.with_id(id);
+		sound.init_statistics();
+		let mut handle = SoundHandle::new(
+			&sound,
+			self.command_producer.clone(),
+			self.rng.clone(),
+			self.instance_effect_context(),
+		);
+		if let Some(previous) = self.sound_handles.get(&id) {
+			handle = handle.replacing(previous);
+		}
+		for named_handle in self.names.sounds.values_mut() {
+			if named_handle.id() == id {
+				*named_handle = handle.clone();
+			}
+		}
+		self.sound_handles.insert(id, handle.clone());
+		let handle = handle.with_reference(self.reference(id.into()));
+		let sound = Owned::new(&self.resource_collector().handle(), sound);
+		self.command_producer
+			.push(ResourceCommand::ReplaceSound(sound).into())?;
+		Ok(handle)
+	}
+
 	/// Sends a arrangement to the audio thread and returns a handle to the arrangement.
 	pub fn add_arrangement(
 		&mut self,
-		arrangement: Arrangement,
+		mut arrangement: Arrangement,
 	) -> Result<ArrangementHandle, AddArrangementError> {
 		if !self.does_track_exist(arrangement.default_track()) {
 			return Err(AddArrangementError::NoTrackWithIndex(
@@ -322,7 +1450,28 @@ impl AudioManager {
 			return Err(AddArrangementError::NoGroupWithId(group));
 		}
 		self.active_ids.add_arrangement_id(arrangement.id())?;
-		let handle = ArrangementHandle::new(&arrangement, self.command_producer.clone());
+		arrangement.init_statistics();
+		let handle = ArrangementHandle::new(
+			&arrangement,
+			self.command_producer.clone(),
+			self.rng.clone(),
+			self.instance_effect_context(),
+		)
+		.with_reference(self.reference(arrangement.id().into()));
+		self.arrangement_handles
+			.insert(handle.id(), handle.clone().with_reference(None));
+		if self.auto_unload {
+			let sound_references = arrangement
+				.sound_ids()
+				.filter_map(|id| {
+					self.references
+						.get(&PlayableId::from(id))
+						.and_then(Weak::upgrade)
+				})
+				.collect();
+			self.arrangement_sound_references
+				.insert(arrangement.id(), sound_references);
+		}
 		let arrangement = Owned::new(&self.resource_collector().handle(), arrangement);
 		self.command_producer
 			.push(ResourceCommand::AddArrangement(arrangement).into())?;
@@ -336,6 +1485,14 @@ impl AudioManager {
 	) -> Result<(), RemoveArrangementError> {
 		let id = id.into();
 		self.active_ids.remove_arrangement_id(id)?;
+		self.names
+			.arrangements
+			.retain(|_, handle| handle.id() != id);
+		if let Some(handle) = self.arrangement_handles.remove(&id) {
+			handle.mark_unloaded();
+		}
+		self.references.remove(&PlayableId::from(id));
+		self.arrangement_sound_references.remove(&id);
 		self.command_producer
 			.push(ResourceCommand::RemoveArrangement(id.into()).into())?;
 		Ok(())
@@ -350,9 +1507,11 @@ impl AudioManager {
 		self.active_ids.add_metronome_id(id)?;
 		let (event_producer, event_consumer) =
 			RingBuffer::new(settings.event_queue_capacity).split();
+		let (position_report_producer, position_report_consumer) =
+			RingBuffer::new(settings.event_queue_capacity).split();
 		let metronome = Owned::new(
 			&self.resource_collector().handle(),
-			Metronome::new(settings, event_producer),
+			Metronome::new(settings, event_producer, position_report_producer),
 		);
 		self.command_producer
 			.push(MetronomeCommand::AddMetronome(id, metronome).into())?;
@@ -360,6 +1519,7 @@ impl AudioManager {
 			id,
 			self.command_producer.clone(),
 			event_consumer,
+			position_report_consumer,
 		))
 	}
 
@@ -386,7 +1546,15 @@ impl AudioManager {
 			return Err(StartSequenceError::NoGroupWithId(group));
 		}
 		sequence.validate()?;
+		let usage = self.resource_usage(ResourceKind::SequenceInstance);
+		if usage.is_full() {
+			return Err(StartSequenceError::SequenceLimitReached(usage));
+		}
 		let id = settings.id.unwrap_or(SequenceInstanceId::new());
+		let settings = SequenceInstanceSettings {
+			random_seed: Some(settings.random_seed.unwrap_or_else(|| self.rng.next_seed())),
+			..settings
+		};
 		let (instance, handle) =
 			sequence.create_instance(id, settings, self.command_producer.clone());
 		let instance = Owned::new(&self.resource_collector().handle(), instance);
@@ -395,6 +1563,90 @@ impl AudioManager {
 		Ok(handle)
 	}
 
+	/// Plays a sound or arrangement without needing a handle to it.
+	///
+	/// The sound or arrangement can be referred to by its ID or by
+	/// a [name](Self::set_name):
+	///
+	/// ```no_run
+	/// # use kira::{
+	/// # 	instance::InstanceSettings,
+	/// # 	manager::{AudioManager, AudioManagerSettings},
+	/// # 	sound::SoundSettings,
+	/// # };
+	/// # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+	/// let sound_handle = audio_manager.load_sound("footstep.ogg", SoundSettings::default())?;
+	/// audio_manager.set_name("footstep", &sound_handle)?;
+	/// // later...
+	/// audio_manager.play("footstep", InstanceSettings::default())?;
+	/// # Ok::<(), Box<dyn std::error::Error>>(())
+	/// ```
+	pub fn play(
+		&mut self,
+		playable: impl Into<PlayableRef>,
+		settings: InstanceSettings,
+	) -> Result<InstanceHandle, PlayError> {
+		let id = match playable.into() {
+			PlayableRef::Id(id) => id,
+			PlayableRef::Name(name) => self.names.resolve_playable(&name)?,
+		};
+		if self.instance_limit_behavior == InstanceLimitBehavior::RejectNew {
+			let usage = self.resource_usage(ResourceKind::Instance);
+			if usage.is_full() {
+				return Err(PlayError::InstanceLimitReached(usage));
+			}
+		}
+		match id {
+			PlayableId::Sound(id) => Ok(self
+				.sound_handles
+				.get_mut(&id)
+				.ok_or(PlayError::NoSoundWithId(id))?
+				.play(settings)?),
+			PlayableId::Arrangement(id) => Ok(self
+				.arrangement_handles
+				.get_mut(&id)
+				.ok_or(PlayError::NoArrangementWithId(id))?
+				.play(settings)?),
+		}
+	}
+
+	/// Fades out an instance while fading in a new instance
+	/// of a sound or arrangement.
+	///
+	/// If the settings specify a metronome to quantize to,
+	/// the crossfade will start when the metronome reaches the
+	/// next multiple of the given interval, so music can
+	/// transition on the beat. If the metronome isn't running,
+	/// the crossfade won't start until it is.
+	///
+	/// Returns the ID of the new instance, which can be used
+	/// as the starting point of another crossfade.
+	pub fn crossfade(
+		&mut self,
+		from: impl Into<InstanceId>,
+		to: impl Into<PlayableId>,
+		settings: CrossfadeSettings,
+	) -> Result<InstanceId, StartSequenceError> {
+		let mut sequence = Sequence::<()>::new(Default::default());
+		let mut sequence_instance_settings = SequenceInstanceSettings::new();
+		if let Some((metronome, interval)) = settings.quantize {
+			sequence.wait_for_interval(interval);
+			sequence_instance_settings = sequence_instance_settings.metronome(metronome);
+		}
+		let id = sequence.play(
+			to,
+			settings
+				.instance_settings
+				.fade_in_tween(settings.fade_tween),
+		);
+		sequence.stop_instance(
+			from,
+			StopInstanceSettings::new().fade_tween(settings.fade_tween),
+		);
+		self.start_sequence(sequence, sequence_instance_settings)?;
+		Ok(id)
+	}
+
 	/// Creates a parameter with the specified starting value.
 	pub fn add_parameter(
 		&mut self,
@@ -404,7 +1656,39 @@ impl AudioManager {
 		self.active_ids.add_parameter_id(id)?;
 		self.command_producer
 			.push(ParameterCommand::AddParameter(id, settings.value).into())?;
-		Ok(ParameterHandle::new(id, self.command_producer.clone()))
+		Ok(ParameterHandle::new(
+			id,
+			self.command_producer.clone(),
+			self.resource_collector().handle(),
+		))
+	}
+
+	/// Creates a parameter whose value is computed from
+	/// other parameters.
+	///
+	/// The value is updated on the audio thread every frame,
+	/// so setting the parameter with the returned handle
+	/// has no lasting effect. See [`DerivedParameterSettings`]
+	/// for more info.
+	pub fn add_derived_parameter(
+		&mut self,
+		settings: DerivedParameterSettings,
+	) -> Result<ParameterHandle, AddDerivedParameterError> {
+		for (input, _) in &settings.inputs {
+			if !self.active_ids.active_parameter_ids.contains(input) {
+				return Err(AddDerivedParameterError::NoParameterWithId(*input));
+			}
+		}
+		let id = settings.id.unwrap_or(ParameterId::new());
+		self.active_ids.add_parameter_id(id)?;
+		let settings = Owned::new(&self.resource_collector().handle(), settings);
+		self.command_producer
+			.push(ParameterCommand::AddDerivedParameter(id, settings).into())?;
+		Ok(ParameterHandle::new(
+			id,
+			self.command_producer.clone(),
+			self.resource_collector().handle(),
+		))
 	}
 
 	/// Removes a parameter from the audio thread.
@@ -414,20 +1698,110 @@ impl AudioManager {
 	) -> Result<(), RemoveParameterError> {
 		let id = id.into();
 		self.active_ids.remove_parameter_id(id)?;
+		self.names.parameters.retain(|_, handle| handle.id() != id);
 		self.command_producer
 			.push(ParameterCommand::RemoveParameter(id).into())?;
 		Ok(())
 	}
 
+	/// Routes one parameter to another, so that the source
+	/// parameter's value (multiplied by the depth) is added to
+	/// the target parameter's value.
+	///
+	/// Since effects and instances can be controlled by
+	/// parameters, modulations can be used to drive multiple
+	/// parameters from a single source. For example, a
+	/// "tension" parameter could be routed to both the cutoff
+	/// parameter of a filter and the volume parameter of
+	/// a drum loop, and a parameter being tweened back and
+	/// forth can act like an LFO.
+	///
+	/// Modulations are based on the unmodulated value of the
+	/// source parameter, so they can't be chained.
+	pub fn add_modulation(
+		&mut self,
+		settings: ModulationSettings,
+	) -> Result<ModulationHandle, AddModulationError> {
+		for parameter_id in [settings.source, settings.target].iter() {
+			if !self.active_ids.active_parameter_ids.contains(parameter_id) {
+				return Err(AddModulationError::NoParameterWithId(*parameter_id));
+			}
+		}
+		let id = settings.id.unwrap_or(ModulationId::new());
+		self.active_ids.add_modulation_id(id)?;
+		self.command_producer
+			.push(ParameterCommand::AddModulation(id, Modulation::new(settings)).into())?;
+		Ok(ModulationHandle::new(id, self.command_producer.clone()))
+	}
+
+	/// Removes a modulation from the audio thread.
+	pub fn remove_modulation(
+		&mut self,
+		id: impl Into<ModulationId>,
+	) -> Result<(), RemoveModulationError> {
+		let id = id.into();
+		self.active_ids.remove_modulation_id(id)?;
+		self.command_producer
+			.push(ParameterCommand::RemoveModulation(id).into())?;
+		Ok(())
+	}
+
 	/// Returns a handle to the main mixer track.
 	pub fn main_track(&mut self) -> MainTrackHandle {
 		MainTrackHandle::new(
 			self.command_producer.clone(),
-			self.sample_rate,
+			self.sample_rate.clone(),
 			self.resource_collector().handle(),
 		)
 	}
 
+	/// Adds a [`Muffle`] effect to each of the given tracks.
+	///
+	/// The effects start out unmuffled and can be controlled
+	/// all at once using the returned [`MuffleHandle`]. The amount
+	/// in the settings is ignored, since the handle controls
+	/// the amount.
+	pub fn add_muffle(
+		&mut self,
+		tracks: &[TrackIndex],
+		settings: MuffleSettings,
+	) -> Result<MuffleHandle, AddMuffleError> {
+		let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+		if sample_rate == 0 {
+			return Err(AddMuffleError::AudioManagerNotStarted);
+		}
+		for track in tracks {
+			match *track {
+				TrackIndex::Sub(id) if !self.active_ids.active_sub_track_ids.contains(&id) => {
+					return Err(AddMuffleError::NoSubTrackWithId(id));
+				}
+				TrackIndex::Send(id) if !self.active_ids.active_send_track_ids.contains(&id) => {
+					return Err(AddMuffleError::NoSendTrackWithId(id));
+				}
+				_ => {}
+			}
+		}
+		let amount = self.add_parameter(ParameterSettings::new().value(0.0))?;
+		for track in tracks {
+			let mut effect = Muffle::new(MuffleSettings {
+				amount: amount.id().into(),
+				..settings
+			});
+			effect.init(sample_rate);
+			let effect: Box<dyn Effect> = Box::new(effect);
+			self.command_producer.push(
+				MixerCommand::AddEffect(
+					*track,
+					EffectId::new(),
+					Owned::new(&self.resource_collector().handle(), effect),
+					EffectSettings::default(),
+				)
+				.into(),
+			)?;
+		}
+		Ok(MuffleHandle::new(amount))
+	}
+
 	/// Creates a mixer sub-track.
 	pub fn add_sub_track(
 		&mut self,
@@ -447,21 +1821,30 @@ impl AudioManager {
 				return Err(AddSubTrackError::NonexistentSendTrack(*send_track_id));
 			}
 		}
+		if let Some(output) = settings.output {
+			if output >= MAX_OUTPUTS {
+				return Err(AddSubTrackError::NonexistentOutput(output));
+			}
+		}
 		let id = settings.id.unwrap_or(SubTrackId::new());
 		self.active_ids.add_sub_track_id(id)?;
 		let handle = SubTrackHandle::new(
 			id,
 			&settings,
 			self.command_producer.clone(),
-			self.sample_rate,
+			self.sample_rate.clone(),
 			self.resource_collector().handle(),
 		);
+		let headroom = settings.headroom;
 		let track = Owned::new(
 			&self.resource_collector().handle(),
 			Track::new_sub_track(id, settings),
 		);
+		let levels = track.levels().clone();
 		self.command_producer
 			.push(MixerCommand::AddTrack(track).into())?;
+		self.track_levels
+			.push((TrackIndex::Sub(id), headroom, levels));
 		Ok(handle)
 	}
 
@@ -472,6 +1855,14 @@ impl AudioManager {
 	) -> Result<(), RemoveSubTrackError> {
 		let id = id.into();
 		self.active_ids.remove_sub_track_id(id)?;
+		self.names
+			.tracks
+			.retain(|_, index| *index != TrackIndex::Sub(id));
+		self.names
+			.effects
+			.retain(|_, handle| handle.track_index() != TrackIndex::Sub(id));
+		self.track_levels
+			.retain(|(track, _, _)| *track != TrackIndex::Sub(id));
 		self.command_producer
 			.push(MixerCommand::RemoveSubTrack(id).into())?;
 		Ok(())
@@ -488,15 +1879,19 @@ impl AudioManager {
 			id,
 			&settings,
 			self.command_producer.clone(),
-			self.sample_rate,
+			self.sample_rate.clone(),
 			self.resource_collector().handle(),
 		);
+		let headroom = settings.headroom;
 		let track = Owned::new(
 			&self.resource_collector().handle(),
 			Track::new_send_track(id, settings),
 		);
+		let levels = track.levels().clone();
 		self.command_producer
 			.push(MixerCommand::AddTrack(track).into())?;
+		self.track_levels
+			.push((TrackIndex::Send(id), headroom, levels));
 		Ok(handle)
 	}
 
@@ -507,11 +1902,57 @@ impl AudioManager {
 	) -> Result<(), RemoveSendTrackError> {
 		let id = id.into();
 		self.active_ids.remove_send_track_id(id)?;
+		self.names
+			.tracks
+			.retain(|_, index| *index != TrackIndex::Send(id));
+		self.names
+			.effects
+			.retain(|_, handle| handle.track_index() != TrackIndex::Send(id));
+		self.track_levels
+			.retain(|(track, _, _)| *track != TrackIndex::Send(id));
 		self.command_producer
 			.push(MixerCommand::RemoveSendTrack(id).into())?;
 		Ok(())
 	}
 
+	/// Adds the tracks and effects described by a [`MixerConfig`].
+	///
+	/// Effects are added to the main track first, then the send
+	/// tracks are added, then the sub-tracks. If something goes
+	/// wrong partway through, the tracks and effects that were
+	/// already added are kept.
+	pub fn add_mixer_config(
+		&mut self,
+		config: &MixerConfig,
+	) -> Result<MixerConfigHandles, AddMixerConfigError> {
+		let mut handles = MixerConfigHandles {
+			main_track_effects: vec![],
+			send_tracks: vec![],
+			sub_tracks: vec![],
+		};
+		let mut main_track = self.main_track();
+		for effect in &config.main_track_effects {
+			handles
+				.main_track_effects
+				.push(main_track.add_effect(effect.effect.create(), effect.settings.clone())?);
+		}
+		for track in &config.send_tracks {
+			let mut handle = self.add_send_track(track.settings.clone())?;
+			for effect in &track.effects {
+				handle.add_effect(effect.effect.create(), effect.settings.clone())?;
+			}
+			handles.send_tracks.push(handle);
+		}
+		for track in &config.sub_tracks {
+			let mut handle = self.add_sub_track(track.settings.clone())?;
+			for effect in &track.effects {
+				handle.add_effect(effect.effect.create(), effect.settings.clone())?;
+			}
+			handles.sub_tracks.push(handle);
+		}
+		Ok(handles)
+	}
+
 	/// Adds a group.
 	pub fn add_group(&mut self, settings: GroupSettings) -> Result<GroupHandle, AddGroupError> {
 		if let Some(group) = self.first_missing_group_in_set(&settings.groups) {
@@ -529,17 +1970,132 @@ impl AudioManager {
 	pub fn remove_group(&mut self, id: impl Into<GroupId>) -> Result<(), RemoveGroupError> {
 		let id = id.into();
 		self.active_ids.remove_group_id(id)?;
+		self.names.groups.retain(|_, handle| handle.id() != id);
 		self.command_producer
 			.push(GroupCommand::RemoveGroup(id).into())?;
 		Ok(())
 	}
 
+	/// Gives a resource a name that it can be looked up by.
+	///
+	/// If the resource already had a name, the old name is
+	/// removed. If another resource of the same kind had
+	/// this name, it loses the name.
+	pub fn set_name(
+		&mut self,
+		name: impl Into<String>,
+		resource: impl Into<NamedResource>,
+	) -> Result<(), SetNameError> {
+		let resource = resource.into();
+		let exists = match &resource {
+			NamedResource::Sound(handle) => self.active_ids.active_sound_ids.contains(&handle.id()),
+			NamedResource::Arrangement(handle) => self
+				.active_ids
+				.active_arrangement_ids
+				.contains(&handle.id()),
+			NamedResource::Parameter(handle) => {
+				self.active_ids.active_parameter_ids.contains(&handle.id())
+			}
+			NamedResource::Group(handle) => self.active_ids.active_group_ids.contains(&handle.id()),
+			NamedResource::Track(index) => self.does_track_exist(*index),
+			// effects are tracked by their track's handle, so
+			// only the track can be checked here
+			NamedResource::Effect(handle) => self.does_track_exist(handle.track_index()),
+		};
+		if !exists {
+			return Err(SetNameError::NonexistentResource);
+		}
+		self.names.set(name.into(), resource);
+		Ok(())
+	}
+
+	/// Removes the name of a resource.
+	pub fn remove_name(&mut self, resource: impl Into<NamedResource>) {
+		self.names.remove(&resource.into());
+	}
+
+	/// Returns the name of a resource, if it has one.
+	pub fn name_of(&self, resource: impl Into<NamedResource>) -> Option<&str> {
+		self.names.name_of(&resource.into())
+	}
+
+	/// Returns a handle to the sound with the given name.
+	///
+	/// If [`auto_unload`](AudioManagerSettings::auto_unload) is
+	/// enabled, sounds whose handles have all been dropped
+	/// can't be looked up anymore.
+	pub fn sound(&self, name: &str) -> Option<SoundHandle> {
+		let handle = self.names.sounds.get(name)?;
+		let reference = self.existing_reference(handle.id().into())?;
+		Some(handle.clone().with_reference(reference))
+	}
+
+	/// Returns a handle to the arrangement with the given name.
+	///
+	/// If [`auto_unload`](AudioManagerSettings::auto_unload) is
+	/// enabled, arrangements whose handles have all been dropped
+	/// can't be looked up anymore.
+	pub fn arrangement(&self, name: &str) -> Option<ArrangementHandle> {
+		let handle = self.names.arrangements.get(name)?;
+		let reference = self.existing_reference(handle.id().into())?;
+		Some(handle.clone().with_reference(reference))
+	}
+
+	/// Returns a handle to the parameter with the given name.
+	pub fn parameter(&self, name: &str) -> Option<ParameterHandle> {
+		self.names.parameters.get(name).cloned()
+	}
+
+	/// Returns a handle to the group with the given name.
+	pub fn group(&self, name: &str) -> Option<GroupHandle> {
+		self.names.groups.get(name).cloned()
+	}
+
+	/// Returns the index of the mixer track with the given name.
+	pub fn track(&self, name: &str) -> Option<TrackIndex> {
+		self.names.tracks.get(name).copied()
+	}
+
+	/// Returns a handle to the effect with the given name.
+	///
+	/// Removing an effect from its track doesn't remove its name,
+	/// so this can return a handle to an effect that no longer
+	/// exists. Commands sent to it are reported as
+	/// [errors](AudioManager::pop_audio_thread_error).
+	pub fn effect(&self, name: &str) -> Option<EffectHandle> {
+		self.names.effects.get(name).cloned()
+	}
+
+	/// Creates a sequence from a [`SequenceDefinition`], looking up
+	/// the resources it refers to by name.
+	pub fn sequence_from_definition<CustomEvent: Clone + Eq + Hash>(
+		&self,
+		definition: &SequenceDefinition<CustomEvent>,
+	) -> Result<Sequence<CustomEvent>, ResolveNameError> {
+		definition.resolve(&self.names)
+	}
+
+	/// Creates an arrangement from an [`ArrangementDefinition`], looking up
+	/// the resources it refers to by name.
+	///
+	/// The arrangement still has to be added to the audio thread
+	/// using [`add_arrangement`](Self::add_arrangement).
+	pub fn arrangement_from_definition(
+		&self,
+		definition: &ArrangementDefinition,
+	) -> Result<Arrangement, ResolveNameError> {
+		definition.resolve(&self.names)
+	}
+
 	/// Adds an audio stream.
+	///
+	/// Returns a handle that can be used to control the
+	/// volume, panning, playback, and routing of the stream.
 	pub fn add_stream(
 		&mut self,
 		stream: impl AudioStream,
 		track: TrackIndex,
-	) -> Result<AudioStreamId, AddStreamError> {
+	) -> Result<AudioStreamHandle, AddStreamError> {
 		if !self.does_track_exist(track) {
 			return Err(AddStreamError::NoTrackWithIndex(track));
 		}
@@ -553,29 +2109,230 @@ impl AudioManager {
 			)
 			.into(),
 		)?;
-		Ok(id)
+		Ok(AudioStreamHandle::new(id, self.command_producer.clone()))
+	}
+
+	/// Opens a MIDI input device so it can control parameters.
+	///
+	/// See the [`midi`](crate::midi) module for more info.
+	#[cfg(all(feature = "midi", not(target_arch = "wasm32")))]
+	pub fn open_midi_input(
+		&mut self,
+		settings: crate::midi::MidiInputSettings,
+	) -> Result<crate::midi::MidiInputHandle, crate::midi::OpenMidiInputError> {
+		crate::midi::open_midi_input(settings, self.command_producer.clone())
+	}
+
+	/// Opens the default input device (like a microphone) and
+	/// plays the captured audio on an audio stream.
+	///
+	/// The input device stays open until the returned handle
+	/// is dropped.
+	#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+	pub fn add_input_stream(
+		&mut self,
+		track: TrackIndex,
+		settings: InputStreamSettings,
+	) -> Result<InputStreamHandle, AddInputStreamError> {
+		if !self.does_track_exist(track) {
+			return Err(AddInputStreamError::NoTrackWithIndex(track));
+		}
+		let usage = self.resource_usage(ResourceKind::Stream);
+		if usage.is_full() {
+			return Err(AddInputStreamError::StreamLimitReached(usage));
+		}
+		let (audio_stream, quit_signal_producer) = start_input_stream(settings)?;
+		let stream = self.add_stream(audio_stream, track)?;
+		Ok(InputStreamHandle::new(stream, quit_signal_producer))
+	}
+
+	/// Starts downloading an ogg file from a URL and plays it
+	/// on an audio stream as soon as enough of it has been
+	/// [preloaded](StreamingSoundSettings::preload).
+	///
+	/// See the [`streaming`](crate::sound::streaming) module
+	/// for more details.
+	#[cfg(all(target_arch = "wasm32", feature = "ogg"))]
+	pub fn add_streaming_sound(
+		&mut self,
+		url: &str,
+		settings: StreamingSoundSettings,
+	) -> Result<StreamingSoundHandle, AddStreamError> {
+		let track = settings.track;
+		if !self.does_track_exist(track) {
+			return Err(AddStreamError::NoTrackWithIndex(track));
+		}
+		let id = AudioStreamId::new();
+		self.active_ids.add_stream_id(id)?;
+		let (stream, handle) = start_streaming_sound(
+			url,
+			settings,
+			AudioStreamHandle::new(id, self.command_producer.clone()),
+		);
+		self.command_producer.push(
+			StreamCommand::AddStream(
+				id,
+				track,
+				Owned::new(&self.resource_collector().handle(), Box::new(stream)),
+			)
+			.into(),
+		)?;
+		Ok(handle)
 	}
 
 	/// Removes an audio stream.
-	pub fn remove_stream(&mut self, id: AudioStreamId) -> Result<(), RemoveStreamError> {
+	pub fn remove_stream(&mut self, id: impl Into<AudioStreamId>) -> Result<(), RemoveStreamError> {
+		let id = id.into();
 		self.active_ids.remove_stream_id(id)?;
 		self.command_producer
 			.push(StreamCommand::RemoveStream(id).into())?;
 		Ok(())
 	}
 
+	/// Returns the number of commands waiting to be run by the
+	/// audio thread, including commands held back by the
+	/// [`command_overflow_strategy`](AudioManagerSettings::command_overflow_strategy).
+	pub fn command_queue_len(&self) -> Result<usize, CommandError> {
+		self.command_producer.len()
+	}
+
+	/// Returns the number of commands that can be waiting
+	/// in the command queue at a time.
+	pub fn command_queue_capacity(&self) -> Result<usize, CommandError> {
+		self.command_producer.capacity()
+	}
+
+	/// Sends as many commands held back by the
+	/// [`command_overflow_strategy`](AudioManagerSettings::command_overflow_strategy)
+	/// to the audio thread as there is room for, and returns
+	/// the number of commands that are still held back.
+	///
+	/// Held back commands are also sent whenever a new command
+	/// is sent, but if you stop sending commands, you should
+	/// call this regularly (for example, once per frame) so
+	/// they don't wait forever.
+	pub fn flush_commands(&mut self) -> Result<usize, CommandError> {
+		self.command_producer.flush()
+	}
+
 	/// Frees resources that are no longer in use, such as unloaded sounds
-	/// or finished sequences.
-	pub fn free_unused_resources(&mut self) {
+	/// or finished sequences, and returns how many resources of each
+	/// type were freed.
+	pub fn free_unused_resources(&mut self) -> FreedResources {
+		self.free_unused_resources_with(|_| {})
+	}
+
+	/// Frees resources that are no longer in use, calling `callback`
+	/// with each resource right before it's freed, and returns how
+	/// many resources of each type were freed.
+	pub fn free_unused_resources_with(
+		&mut self,
+		mut callback: impl FnMut(FreedResource),
+	) -> FreedResources {
+		self.unload_unreferenced_resources();
+		let mut freed = FreedResources::default();
+		while let Some(resource) = self.removed_resource_consumer.pop() {
+			if let Some(id) = resource.playable_id() {
+				self.release_unloaded_id(id);
+			}
+			resource.free(&mut freed, &mut callback);
+		}
 		self.resource_collector_mut().collect();
+		freed
+	}
+}
+
+impl AudioManager {
+	/// Returns a reference that keeps a sound or arrangement
+	/// loaded, or `None` if resources aren't unloaded
+	/// automatically.
+	fn reference(&mut self, id: PlayableId) -> Option<Arc<()>> {
+		if !self.auto_unload {
+			return None;
+		}
+		if let Some(reference) = self.references.get(&id).and_then(Weak::upgrade) {
+			return Some(reference);
+		}
+		let reference = Arc::new(());
+		self.references.insert(id, Arc::downgrade(&reference));
+		Some(reference)
+	}
+
+	/// Returns a new reference to a sound or arrangement that
+	/// handles still exist for, or `None` if every handle to it
+	/// has been dropped and it's waiting to be unloaded.
+	fn existing_reference(&self, id: PlayableId) -> Option<Option<Arc<()>>> {
+		match self.references.get(&id) {
+			Some(reference) => reference.upgrade().map(Some),
+			None => Some(None),
+		}
+	}
+
+	/// Frees up the ID of a sound or arrangement that was
+	/// unloaded automatically once the audio thread has
+	/// removed it.
+	fn release_unloaded_id(&mut self, id: PlayableId) {
+		if !self.unloading_ids.remove(&id) {
+			return;
+		}
+		match id {
+			PlayableId::Sound(id) => {
+				self.active_ids.remove_sound_id(id).ok();
+			}
+			PlayableId::Arrangement(id) => {
+				self.active_ids.remove_arrangement_id(id).ok();
+			}
+		}
+	}
+
+	/// Queues the removal of sounds and arrangements whose
+	/// handles have all been dropped. The audio thread removes
+	/// them once no instances are playing them.
+	fn unload_unreferenced_resources(&mut self) {
+		let unreferenced = self
+			.references
+			.iter()
+			.filter(|(_, reference)| reference.strong_count() == 0)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		for id in unreferenced {
+			if self
+				.command_producer
+				.push(ResourceCommand::RemoveWhenUnused(id).into())
+				.is_err()
+			{
+				continue;
+			}
+			self.references.remove(&id);
+			self.unloading_ids.insert(id);
+			match id {
+				PlayableId::Sound(id) => {
+					self.names.sounds.retain(|_, handle| handle.id() != id);
+					if let Some(handle) = self.sound_handles.remove(&id) {
+						handle.mark_unloaded();
+					}
+				}
+				PlayableId::Arrangement(id) => {
+					self.names
+						.arrangements
+						.retain(|_, handle| handle.id() != id);
+					if let Some(handle) = self.arrangement_handles.remove(&id) {
+						handle.mark_unloaded();
+					}
+					self.arrangement_sound_references.remove(&id);
+				}
+			}
+		}
 	}
 }
 
 #[cfg(not(test))]
 impl Drop for AudioManager {
 	fn drop(&mut self) {
-		#[cfg(not(target_arch = "wasm32"))]
-		self.quit_signal_producer.push(true).ok();
+		#[cfg(all(not(target_arch = "wasm32"), feature = "cpal"))]
+		if let Some(quit_signal_producer) = &mut self.quit_signal_producer {
+			quit_signal_producer.push(true).ok();
+		}
 
 		// cleanup all unused resources. if we can't get everything to successfully
 		// drop within a reasonable amount of time, just give up
@@ -599,3 +2356,22 @@ impl Drop for AudioManager {
 		}
 	}
 }
+
+/// Returns the number of seconds from `from` to `to`, which
+/// is negative if `to` is earlier than `from`.
+fn signed_duration_secs(from: Instant, to: Instant) -> f64 {
+	if to >= from {
+		(to - from).as_secs_f64()
+	} else {
+		-(from - to).as_secs_f64()
+	}
+}
+
+/// Offsets an instant by a (possibly negative) number of seconds.
+fn offset_instant(instant: Instant, seconds: f64) -> Instant {
+	if seconds >= 0.0 {
+		instant + Duration::from_secs_f64(seconds)
+	} else {
+		instant - Duration::from_secs_f64(-seconds)
+	}
+}
@@ -0,0 +1,328 @@
+//! Lets you refer to resources by name.
+//!
+//! Sounds, arrangements, parameters, groups, mixer tracks, and
+//! effects can be given names using [`AudioManager::set_name`](super::AudioManager::set_name)
+//! and looked up later, which is useful for games that describe
+//! their audio in data files:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sound::SoundSettings,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let sound_handle = audio_manager.load_sound("footstep.ogg", SoundSettings::default())?;
+//! audio_manager.set_name("footstep", &sound_handle)?;
+//! // later...
+//! if let Some(mut footstep) = audio_manager.sound("footstep") {
+//! 	footstep.play(InstanceSettings::default())?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Each kind of resource has its own set of names, so a sound and
+//! a group can have the same name. Sub-tracks and send tracks
+//! share a set of names. When a resource is removed, its name
+//! is removed as well. The exception is effects, which are
+//! removed using their track's handle, so their names have to
+//! be removed with [`AudioManager::remove_name`](super::AudioManager::remove_name).
+//!
+//! Names are also used by [`SequenceDefinition`](crate::sequence::definition::SequenceDefinition)s
+//! and [`ArrangementDefinition`](crate::arrangement::definition::ArrangementDefinition)s,
+//! which describe sequences and arrangements as data.
+
+use indexmap::IndexMap;
+
+use crate::{
+	arrangement::{handle::ArrangementHandle, ArrangementId},
+	group::{handle::GroupHandle, GroupSet},
+	mixer::{effect::handle::EffectHandle, SendTrackHandle, SubTrackHandle, TrackIndex},
+	parameter::handle::ParameterHandle,
+	playable::PlayableId,
+	sound::{handle::SoundHandle, SoundId},
+};
+
+use super::error::ResolveNameError;
+
+/// The name of a sound or arrangement.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum PlayableName {
+	/// The name of a sound.
+	Sound(String),
+	/// The name of an arrangement.
+	Arrangement(String),
+}
+
+/// A sound or arrangement to play with
+/// [`AudioManager::play`](super::AudioManager::play), referred
+/// to by its ID or its name.
+///
+/// A string is treated as the name of a sound. Use
+/// [`PlayableName::Arrangement`] to play an arrangement by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayableRef {
+	/// The ID of a sound or arrangement.
+	Id(PlayableId),
+	/// The name of a sound or arrangement.
+	Name(PlayableName),
+}
+
+impl From<PlayableId> for PlayableRef {
+	fn from(id: PlayableId) -> Self {
+		Self::Id(id)
+	}
+}
+
+impl From<SoundId> for PlayableRef {
+	fn from(id: SoundId) -> Self {
+		Self::Id(id.into())
+	}
+}
+
+impl From<ArrangementId> for PlayableRef {
+	fn from(id: ArrangementId) -> Self {
+		Self::Id(id.into())
+	}
+}
+
+impl From<&SoundHandle> for PlayableRef {
+	fn from(handle: &SoundHandle) -> Self {
+		Self::Id(handle.into())
+	}
+}
+
+impl From<&ArrangementHandle> for PlayableRef {
+	fn from(handle: &ArrangementHandle) -> Self {
+		Self::Id(handle.into())
+	}
+}
+
+impl From<PlayableName> for PlayableRef {
+	fn from(name: PlayableName) -> Self {
+		Self::Name(name)
+	}
+}
+
+impl From<&str> for PlayableRef {
+	fn from(name: &str) -> Self {
+		Self::Name(PlayableName::Sound(name.to_string()))
+	}
+}
+
+impl From<String> for PlayableRef {
+	fn from(name: String) -> Self {
+		Self::Name(PlayableName::Sound(name))
+	}
+}
+
+/// A resource that can be given a name.
+#[derive(Debug, Clone)]
+pub enum NamedResource {
+	/// A sound.
+	Sound(SoundHandle),
+	/// An arrangement.
+	Arrangement(ArrangementHandle),
+	/// A parameter.
+	Parameter(ParameterHandle),
+	/// A group.
+	Group(GroupHandle),
+	/// A mixer sub-track or send track.
+	Track(TrackIndex),
+	/// An effect on a mixer track, such as an
+	/// [effect rack](crate::mixer::effect::rack).
+	Effect(EffectHandle),
+}
+
+impl From<&SoundHandle> for NamedResource {
+	fn from(handle: &SoundHandle) -> Self {
+		Self::Sound(handle.clone())
+	}
+}
+
+impl From<&ArrangementHandle> for NamedResource {
+	fn from(handle: &ArrangementHandle) -> Self {
+		Self::Arrangement(handle.clone())
+	}
+}
+
+impl From<&ParameterHandle> for NamedResource {
+	fn from(handle: &ParameterHandle) -> Self {
+		Self::Parameter(handle.clone())
+	}
+}
+
+impl From<&GroupHandle> for NamedResource {
+	fn from(handle: &GroupHandle) -> Self {
+		Self::Group(handle.clone())
+	}
+}
+
+impl From<&SubTrackHandle> for NamedResource {
+	fn from(handle: &SubTrackHandle) -> Self {
+		Self::Track(TrackIndex::Sub(handle.id()))
+	}
+}
+
+impl From<&SendTrackHandle> for NamedResource {
+	fn from(handle: &SendTrackHandle) -> Self {
+		Self::Track(TrackIndex::Send(handle.id()))
+	}
+}
+
+impl From<TrackIndex> for NamedResource {
+	fn from(index: TrackIndex) -> Self {
+		Self::Track(index)
+	}
+}
+
+impl From<&EffectHandle> for NamedResource {
+	fn from(handle: &EffectHandle) -> Self {
+		Self::Effect(handle.clone())
+	}
+}
+
+/// The names given to resources.
+#[derive(Debug, Default)]
+pub(crate) struct Names {
+	pub sounds: IndexMap<String, SoundHandle>,
+	pub arrangements: IndexMap<String, ArrangementHandle>,
+	pub parameters: IndexMap<String, ParameterHandle>,
+	pub groups: IndexMap<String, GroupHandle>,
+	pub tracks: IndexMap<String, TrackIndex>,
+	pub effects: IndexMap<String, EffectHandle>,
+}
+
+impl Names {
+	/// Gives a resource a name, removing any name
+	/// it previously had.
+	///
+	/// Naming a sound or arrangement doesn't keep it loaded.
+	pub fn set(&mut self, name: String, resource: NamedResource) {
+		self.remove(&resource);
+		match resource {
+			NamedResource::Sound(handle) => {
+				self.sounds.insert(name, handle.with_reference(None));
+			}
+			NamedResource::Arrangement(handle) => {
+				self.arrangements.insert(name, handle.with_reference(None));
+			}
+			NamedResource::Parameter(handle) => {
+				self.parameters.insert(name, handle);
+			}
+			NamedResource::Group(handle) => {
+				self.groups.insert(name, handle);
+			}
+			NamedResource::Track(index) => {
+				self.tracks.insert(name, index);
+			}
+			NamedResource::Effect(handle) => {
+				self.effects.insert(name, handle);
+			}
+		}
+	}
+
+	/// Removes the name of a resource.
+	pub fn remove(&mut self, resource: &NamedResource) {
+		match resource {
+			NamedResource::Sound(handle) => {
+				self.sounds.retain(|_, other| other.id() != handle.id());
+			}
+			NamedResource::Arrangement(handle) => {
+				self.arrangements
+					.retain(|_, other| other.id() != handle.id());
+			}
+			NamedResource::Parameter(handle) => {
+				self.parameters.retain(|_, other| other.id() != handle.id());
+			}
+			NamedResource::Group(handle) => {
+				self.groups.retain(|_, other| other.id() != handle.id());
+			}
+			NamedResource::Track(index) => {
+				self.tracks.retain(|_, other| other != index);
+			}
+			NamedResource::Effect(handle) => {
+				self.effects.retain(|_, other| other.id() != handle.id());
+			}
+		}
+	}
+
+	/// Returns the name of a resource, if it has one.
+	pub fn name_of(&self, resource: &NamedResource) -> Option<&str> {
+		let name = match resource {
+			NamedResource::Sound(handle) => {
+				find_name(&self.sounds, |other| other.id() == handle.id())
+			}
+			NamedResource::Arrangement(handle) => {
+				find_name(&self.arrangements, |other| other.id() == handle.id())
+			}
+			NamedResource::Parameter(handle) => {
+				find_name(&self.parameters, |other| other.id() == handle.id())
+			}
+			NamedResource::Group(handle) => {
+				find_name(&self.groups, |other| other.id() == handle.id())
+			}
+			NamedResource::Track(index) => find_name(&self.tracks, |other| other == index),
+			NamedResource::Effect(handle) => {
+				find_name(&self.effects, |other| other.id() == handle.id())
+			}
+		};
+		name.map(|name| name.as_str())
+	}
+
+	pub fn resolve_sound(&self, name: &str) -> Result<&SoundHandle, ResolveNameError> {
+		self.sounds
+			.get(name)
+			.ok_or_else(|| ResolveNameError::NoSoundWithName(name.to_string()))
+	}
+
+	pub fn resolve_playable(&self, name: &PlayableName) -> Result<PlayableId, ResolveNameError> {
+		match name {
+			PlayableName::Sound(name) => Ok(self.resolve_sound(name)?.into()),
+			PlayableName::Arrangement(name) => self
+				.arrangements
+				.get(name)
+				.map(PlayableId::from)
+				.ok_or_else(|| ResolveNameError::NoArrangementWithName(name.clone())),
+		}
+	}
+
+	pub fn resolve_parameter(&self, name: &str) -> Result<&ParameterHandle, ResolveNameError> {
+		self.parameters
+			.get(name)
+			.ok_or_else(|| ResolveNameError::NoParameterWithName(name.to_string()))
+	}
+
+	pub fn resolve_groups(&self, names: &[String]) -> Result<GroupSet, ResolveNameError> {
+		let mut groups = GroupSet::new();
+		for name in names {
+			groups = groups.add(
+				self.groups
+					.get(name)
+					.ok_or_else(|| ResolveNameError::NoGroupWithName(name.clone()))?,
+			);
+		}
+		Ok(groups)
+	}
+
+	pub fn resolve_track(&self, name: &str) -> Result<TrackIndex, ResolveNameError> {
+		self.tracks
+			.get(name)
+			.copied()
+			.ok_or_else(|| ResolveNameError::NoTrackWithName(name.to_string()))
+	}
+}
+
+fn find_name<T>(
+	names: &IndexMap<String, T>,
+	mut predicate: impl FnMut(&T) -> bool,
+) -> Option<&String> {
+	names
+		.iter()
+		.find(|(_, value)| predicate(value))
+		.map(|(name, _)| name)
+}
@@ -0,0 +1,219 @@
+//! Plays some of the mixer's outputs on other audio devices.
+//!
+//! Sub-tracks can be sent to any of the mixer's
+//! [outputs](crate::mixer::SubTrackSettings::output). By default,
+//! every output is played on the default audio device, but each
+//! output can be moved to a different device instead. This
+//! makes it possible to play the game's audio on the speakers
+//! while a voice chat or a monitor mix goes to a headset:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{
+//! # 		output_device::OutputDeviceSettings,
+//! # 		AudioManager, AudioManagerSettings,
+//! # 	},
+//! # 	mixer::SubTrackSettings,
+//! # };
+//! # #[cfg(feature = "cpal")]
+//! # {
+//! # use kira::manager::output_device::output_device_names;
+//! println!("available devices: {:?}", output_device_names()?);
+//! let mut audio_manager = AudioManager::new(AudioManagerSettings {
+//! 	output_devices: vec![OutputDeviceSettings::new(1, "USB Headset")],
+//! 	..Default::default()
+//! })?;
+//! let monitor_track = audio_manager.add_sub_track(SubTrackSettings::new().output(1))?;
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The mixer runs on the default audio device's clock, and
+//! audio for the other devices is buffered until they ask for
+//! it. If a device's clock runs slightly slower or faster than
+//! the default device's, it will occasionally drop a frame or
+//! play a frame of silence.
+
+#[cfg(feature = "cpal")]
+use cpal::{
+	traits::{DeviceTrait, HostTrait, StreamTrait},
+	Device, SampleRate, Stream,
+};
+#[cfg(any(feature = "cpal", test))]
+use ringbuf::Producer;
+#[cfg(feature = "cpal")]
+use ringbuf::RingBuffer;
+
+#[cfg(any(feature = "cpal", test))]
+use crate::{mixer::MAX_OUTPUTS, Frame};
+
+#[cfg(feature = "cpal")]
+use super::error::SetupError;
+#[cfg(any(feature = "cpal", test))]
+use super::Backend;
+
+/// The number of frames that can be waiting to be played
+/// on an extra output device.
+#[cfg(feature = "cpal")]
+const OUTPUT_DEVICE_BUFFER_FRAMES: usize = 4096;
+
+/// The streams for the extra output devices, and for each
+/// mixer output, a producer for sending audio to its device.
+#[cfg(feature = "cpal")]
+type OutputDevices = (Vec<Stream>, Vec<Option<Producer<Frame>>>);
+
+/// Settings for playing one of the mixer's outputs on an
+/// audio device other than the default one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct OutputDeviceSettings {
+	/// The index of the mixer output to play on the device.
+	pub output: usize,
+	/// The name of the audio device.
+	pub device_name: String,
+}
+
+impl OutputDeviceSettings {
+	/// Creates a new `OutputDeviceSettings` that plays the given
+	/// mixer output on the device with the given name.
+	pub fn new(output: usize, device_name: impl Into<String>) -> Self {
+		Self {
+			output,
+			device_name: device_name.into(),
+		}
+	}
+}
+
+/// Processes a frame of audio and writes it to the
+/// interleaved channels of the default device.
+///
+/// Mixer outputs with a producer in `device_outputs` are sent to
+/// their own device. Other extra outputs are written to their own
+/// pair of channels, or mixed into the first pair if the default
+/// device doesn't have them.
+#[cfg(any(feature = "cpal", test))]
+pub(crate) fn process_frame(
+	backend: &mut Backend,
+	device_outputs: &mut [Option<Producer<Frame>>],
+	frame: &mut [f32],
+) {
+	let channels = frame.len();
+	let num_outputs = (channels / 2).clamp(1, MAX_OUTPUTS);
+	let main_output = backend.process();
+	let mut out = Frame::from_mono(0.0);
+	for (index, device_output) in device_outputs.iter_mut().enumerate() {
+		let output = if index == 0 {
+			main_output
+		} else {
+			backend.output(index)
+		};
+		if let Some(producer) = device_output {
+			// if the device has fallen behind, the
+			// frame is dropped
+			producer.push(output).ok();
+		} else if index > 0 && index < num_outputs {
+			let (left, right) = output.to_f32();
+			frame[index * 2] = left;
+			frame[index * 2 + 1] = right;
+		} else {
+			out += output;
+		}
+	}
+	let (left, right) = out.to_f32();
+	if channels == 1 {
+		frame[0] = (left + right) / 2.0;
+	} else {
+		frame[0] = left;
+		frame[1] = right;
+	}
+}
+
+/// Returns the names of the audio output devices that
+/// are available.
+#[cfg(feature = "cpal")]
+pub fn output_device_names() -> Result<Vec<String>, SetupError> {
+	Ok(cpal::default_host()
+		.output_devices()?
+		.filter_map(|device| device.name().ok())
+		.collect())
+}
+
+#[cfg(feature = "cpal")]
+fn find_device(name: &str) -> Result<Device, SetupError> {
+	cpal::default_host()
+		.output_devices()?
+		.find(|device| device.name().ok().as_deref() == Some(name))
+		.ok_or_else(|| SetupError::NoOutputDeviceWithName(name.to_string()))
+}
+
+/// Opens the audio devices for the mixer outputs that
+/// should be played on them.
+///
+/// The streams should be kept alive for as long as the
+/// audio manager.
+#[cfg(feature = "cpal")]
+pub(crate) fn open_output_devices(
+	settings: &[OutputDeviceSettings],
+	sample_rate: u32,
+) -> Result<OutputDevices, SetupError> {
+	let mut streams = vec![];
+	let mut producers = (0..MAX_OUTPUTS).map(|_| None).collect::<Vec<_>>();
+	for device_settings in settings {
+		if device_settings.output >= MAX_OUTPUTS {
+			return Err(SetupError::NoOutputWithIndex(device_settings.output));
+		}
+		let (stream, producer) = open_output_device(&device_settings.device_name, sample_rate)?;
+		streams.push(stream);
+		producers[device_settings.output] = Some(producer);
+	}
+	Ok((streams, producers))
+}
+
+#[cfg(feature = "cpal")]
+fn open_output_device(
+	name: &str,
+	sample_rate: u32,
+) -> Result<(Stream, Producer<Frame>), SetupError> {
+	let device = find_device(name)?;
+	// the audio is produced at the default device's sample rate,
+	// so the other devices have to be able to play at that rate
+	let config = device
+		.supported_output_configs()?
+		.find(|config| {
+			config.min_sample_rate().0 <= sample_rate && config.max_sample_rate().0 >= sample_rate
+		})
+		.ok_or_else(|| SetupError::UnsupportedSampleRate {
+			device_name: name.to_string(),
+			sample_rate,
+		})?
+		.with_sample_rate(SampleRate(sample_rate))
+		.config();
+	let channels = config.channels as usize;
+	let (producer, mut consumer) = RingBuffer::new(OUTPUT_DEVICE_BUFFER_FRAMES).split();
+	let stream = device.build_output_stream(
+		&config,
+		move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+			for frame in data.chunks_exact_mut(channels) {
+				// if the default device hasn't produced enough audio
+				// yet, play silence until it catches up
+				let (left, right) = consumer
+					.pop()
+					.unwrap_or_else(|| Frame::from_mono(0.0))
+					.to_f32();
+				frame.iter_mut().for_each(|sample| *sample = 0.0);
+				if channels == 1 {
+					frame[0] = (left + right) / 2.0;
+				} else {
+					frame[0] = left;
+					frame[1] = right;
+				}
+			}
+		},
+		move |_| {},
+	)?;
+	stream.play()?;
+	Ok((stream, producer))
+}
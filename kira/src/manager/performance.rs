@@ -0,0 +1,185 @@
+//! Reports on how well the audio thread is keeping up.
+//!
+//! If the audio thread takes too long to produce a buffer of
+//! audio, or the audio device asks for audio too late, the
+//! listener will hear crackling or gaps in the audio. The
+//! audio manager keeps count of these problems so you can
+//! check on them with
+//! [`AudioManager::performance_report`](super::AudioManager::performance_report),
+//! and it emits a [`PerformanceEvent`] each time one happens,
+//! which you can receive with
+//! [`AudioManager::pop_performance_event`](super::AudioManager::pop_performance_event):
+//!
+//! ```no_run
+//! # use kira::manager::{AudioManager, AudioManagerSettings};
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! // in the game loop
+//! while let Some(event) = audio_manager.pop_performance_event() {
+//! 	println!("audio glitch in the current scene: {:?}", event);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use ringbuf::Producer;
+
+use super::resources::ResourceUsage;
+
+pub(crate) const NUM_PERFORMANCE_EVENTS: usize = 100;
+
+/// Counts of the problems the audio thread has run into
+/// since the audio manager was started.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PerformanceReport {
+	/// The number of audio buffers that have been produced.
+	pub buffers: u64,
+	/// The number of times the audio device asked for a buffer
+	/// so late that the previous buffer likely ran out,
+	/// causing a gap in the audio.
+	pub underruns: u64,
+	/// The number of buffers that took longer to produce than
+	/// the [`slow_buffer_threshold`](super::AudioManagerSettings::slow_buffer_threshold)
+	/// allows.
+	pub slow_buffers: u64,
+}
+
+/// A problem the audio thread ran into.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PerformanceEvent {
+	/// The audio device asked for a buffer so late that the
+	/// previous buffer likely ran out.
+	Underrun {
+		/// The time on the audio clock when the buffer started
+		/// (in seconds).
+		time: f64,
+		/// How long it had been since the previous buffer
+		/// was requested (in seconds).
+		callback_interval: f64,
+	},
+	/// A buffer took longer to produce than the
+	/// [`slow_buffer_threshold`](super::AudioManagerSettings::slow_buffer_threshold)
+	/// allows.
+	SlowBuffer {
+		/// The time on the audio clock when the buffer started
+		/// (in seconds).
+		time: f64,
+		/// The time it took to produce the buffer, as a fraction
+		/// of the buffer's duration.
+		load: f64,
+	},
+	/// An instance or sequence couldn't be started without going
+	/// over the limit set in the
+	/// [`AudioManagerSettings`](super::AudioManagerSettings).
+	///
+	/// When the instance limit is reached, the oldest instance is
+	/// stopped to make room for the new one, unless the
+	/// [`instance_limit_behavior`](super::AudioManagerSettings::instance_limit_behavior)
+	/// says otherwise. When the sequence limit is reached, the new
+	/// sequence isn't started.
+	ResourceLimitReached {
+		/// The time on the audio clock when the limit was reached
+		/// (in seconds).
+		time: f64,
+		/// How many resources were in use when the limit
+		/// was reached.
+		usage: ResourceUsage,
+	},
+}
+
+/// Counters shared between the audio thread and the main thread.
+#[derive(Debug, Default)]
+pub(crate) struct PerformanceCounters {
+	buffers: AtomicU64,
+	underruns: AtomicU64,
+	slow_buffers: AtomicU64,
+}
+
+impl PerformanceCounters {
+	pub fn report(&self) -> PerformanceReport {
+		PerformanceReport {
+			buffers: self.buffers.load(Ordering::Relaxed),
+			underruns: self.underruns.load(Ordering::Relaxed),
+			slow_buffers: self.slow_buffers.load(Ordering::Relaxed),
+		}
+	}
+}
+
+/// Keeps track of buffer timings on the audio thread.
+pub(crate) struct PerformanceMonitor {
+	counters: Arc<PerformanceCounters>,
+	event_producer: Producer<PerformanceEvent>,
+	slow_buffer_threshold: f64,
+	previous_buffer_duration: Option<f64>,
+}
+
+impl PerformanceMonitor {
+	pub fn new(
+		counters: Arc<PerformanceCounters>,
+		event_producer: Producer<PerformanceEvent>,
+		slow_buffer_threshold: f64,
+	) -> Self {
+		Self {
+			counters,
+			event_producer,
+			slow_buffer_threshold,
+			previous_buffer_duration: None,
+		}
+	}
+
+	/// Records that the audio thread couldn't start an instance
+	/// or sequence without going over a limit.
+	pub fn record_resource_limit(&mut self, time: f64, usage: ResourceUsage) {
+		self.event_producer
+			.push(PerformanceEvent::ResourceLimitReached { time, usage })
+			.ok();
+	}
+
+	/// Records the timing of a finished buffer.
+	///
+	/// - `time` is the time on the audio clock when the buffer started
+	/// - `buffer_duration` is the duration of the audio in the buffer
+	/// - `callback_interval` is the time since the previous buffer was requested
+	/// - `processing_time` is how long it took to produce the buffer
+	pub fn record_buffer(
+		&mut self,
+		time: f64,
+		buffer_duration: f64,
+		callback_interval: Option<Duration>,
+		processing_time: Duration,
+	) {
+		self.counters.buffers.fetch_add(1, Ordering::Relaxed);
+		// if the device asks for audio more than one buffer later
+		// than expected, the previous buffer has already run out
+		if let (Some(callback_interval), Some(previous_buffer_duration)) =
+			(callback_interval, self.previous_buffer_duration)
+		{
+			let callback_interval = callback_interval.as_secs_f64();
+			if callback_interval > previous_buffer_duration * 2.0 {
+				self.counters.underruns.fetch_add(1, Ordering::Relaxed);
+				self.event_producer
+					.push(PerformanceEvent::Underrun {
+						time,
+						callback_interval,
+					})
+					.ok();
+			}
+		}
+		self.previous_buffer_duration = Some(buffer_duration);
+		if buffer_duration > 0.0 {
+			let load = processing_time.as_secs_f64() / buffer_duration;
+			if load > self.slow_buffer_threshold {
+				self.counters.slow_buffers.fetch_add(1, Ordering::Relaxed);
+				self.event_producer
+					.push(PerformanceEvent::SlowBuffer { time, load })
+					.ok();
+			}
+		}
+	}
+}
@@ -0,0 +1,324 @@
+//! Reports on the resources used and freed by the audio manager.
+//!
+//! The audio manager can only hold a limited number of each kind
+//! of resource, which you can configure in the
+//! [`AudioManagerSettings`](super::AudioManagerSettings).
+//! [`AudioManager::resource_usage`](super::AudioManager::resource_usage)
+//! tells you how close you are to each limit, which can help you
+//! pick settings that fit your game:
+//!
+//! ```no_run
+//! # use kira::manager::{AudioManager, AudioManagerSettings, resources::ResourceKind};
+//! # let (audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! for kind in ResourceKind::ALL {
+//! 	let usage = audio_manager.resource_usage(kind);
+//! 	println!("{:?}: {}/{}", usage.kind, usage.used, usage.capacity);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! When a resource, like a sound or a mixer track, is removed, the
+//! audio thread hands it back to the main thread, where it's freed
+//! the next time you call
+//! [`AudioManager::free_unused_resources`](super::AudioManager::free_unused_resources).
+//! That function returns how many resources of each type were freed,
+//! and [`AudioManager::free_unused_resources_with`](super::AudioManager::free_unused_resources_with)
+//! also lets you look at each resource before it's freed:
+//!
+//! ```no_run
+//! # use kira::manager::{AudioManager, AudioManagerSettings, resources::FreedResource};
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! // in the game loop
+//! let freed = audio_manager.free_unused_resources_with(|resource| {
+//! 	if let FreedResource::Sound(sound) = resource {
+//! 		println!("freed a sound that was {} seconds long", sound.duration());
+//! 	}
+//! });
+//! println!("freed {} resources", freed.total());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt::{Debug, Formatter};
+
+use basedrop::Owned;
+use ringbuf::Producer;
+
+use crate::{
+	arrangement::Arrangement,
+	audio_stream::{AudioStream, AudioStreamId},
+	group::{Group, GroupId},
+	metronome::{Metronome, MetronomeId},
+	mixer::{effect::EffectId, effect_slot::EffectSlot, SendTrackId, SubTrackId, Track},
+	playable::PlayableId,
+	sequence::{SequenceInstance, SequenceInstanceId},
+	sound::Sound,
+};
+
+/// A kind of resource the audio manager can only hold
+/// a limited number of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ResourceKind {
+	/// Sounds.
+	Sound,
+	/// Arrangements.
+	Arrangement,
+	/// Parameters.
+	Parameter,
+	/// Modulations between parameters.
+	Modulation,
+	/// Mixer sub-tracks.
+	SubTrack,
+	/// Mixer send tracks.
+	SendTrack,
+	/// Groups.
+	Group,
+	/// Metronomes.
+	Metronome,
+	/// Audio streams.
+	Stream,
+	/// Instances of sounds and arrangements.
+	Instance,
+	/// Running sequences.
+	SequenceInstance,
+}
+
+impl ResourceKind {
+	/// Every kind of resource.
+	pub const ALL: [Self; 11] = [
+		Self::Sound,
+		Self::Arrangement,
+		Self::Parameter,
+		Self::Modulation,
+		Self::SubTrack,
+		Self::SendTrack,
+		Self::Group,
+		Self::Metronome,
+		Self::Stream,
+		Self::Instance,
+		Self::SequenceInstance,
+	];
+}
+
+/// How many resources of a kind are in use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ResourceUsage {
+	/// The kind of resource.
+	pub kind: ResourceKind,
+	/// The number of resources of this kind in use.
+	pub used: usize,
+	/// The maximum number of resources of this kind
+	/// that can be in use at a time.
+	pub capacity: usize,
+}
+
+impl ResourceUsage {
+	/// Returns the number of resources of this kind that
+	/// can be added before the limit is reached.
+	pub fn remaining(&self) -> usize {
+		self.capacity.saturating_sub(self.used)
+	}
+
+	/// Returns `true` if no more resources of this kind
+	/// can be added.
+	pub fn is_full(&self) -> bool {
+		self.used >= self.capacity
+	}
+}
+
+/// The number of resources of each type that were freed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FreedResources {
+	/// The number of sounds that were freed.
+	pub sounds: usize,
+	/// The number of arrangements that were freed.
+	pub arrangements: usize,
+	/// The number of sequence instances that were freed.
+	pub sequence_instances: usize,
+	/// The number of metronomes that were freed.
+	pub metronomes: usize,
+	/// The number of mixer sub-tracks and send tracks that were freed.
+	pub tracks: usize,
+	/// The number of effects that were freed.
+	pub effects: usize,
+	/// The number of groups that were freed.
+	pub groups: usize,
+	/// The number of audio streams that were freed.
+	pub streams: usize,
+}
+
+impl FreedResources {
+	/// Returns the total number of resources that were freed.
+	pub fn total(&self) -> usize {
+		self.sounds
+			+ self.arrangements
+			+ self.sequence_instances
+			+ self.metronomes
+			+ self.tracks
+			+ self.effects
+			+ self.groups
+			+ self.streams
+	}
+
+	fn count(&mut self, resource: &FreedResource) {
+		match resource {
+			FreedResource::Sound(_) => self.sounds += 1,
+			FreedResource::Arrangement(_) => self.arrangements += 1,
+			FreedResource::SequenceInstance(_) => self.sequence_instances += 1,
+			FreedResource::Metronome(_) => self.metronomes += 1,
+			FreedResource::SubTrack(_) | FreedResource::SendTrack(_) => self.tracks += 1,
+			FreedResource::Effect(_) => self.effects += 1,
+			FreedResource::Group(_) => self.groups += 1,
+			FreedResource::Stream(_) => self.streams += 1,
+		}
+	}
+}
+
+/// A resource that is about to be freed.
+///
+/// Sounds and arrangements can be cloned from here if
+/// you want to reuse them later.
+#[derive(Copy, Clone)]
+pub enum FreedResource<'a> {
+	/// A sound.
+	Sound(&'a Sound),
+	/// An arrangement.
+	Arrangement(&'a Arrangement),
+	/// A sequence instance.
+	SequenceInstance(SequenceInstanceId),
+	/// A metronome.
+	Metronome(MetronomeId),
+	/// A mixer sub-track.
+	SubTrack(SubTrackId),
+	/// A mixer send track.
+	SendTrack(SendTrackId),
+	/// An effect.
+	Effect(EffectId),
+	/// A group.
+	Group(GroupId),
+	/// An audio stream.
+	Stream(AudioStreamId),
+}
+
+impl<'a> Debug for FreedResource<'a> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			FreedResource::Sound(sound) => f.debug_tuple("Sound").field(&sound.id()).finish(),
+			FreedResource::Arrangement(arrangement) => f
+				.debug_tuple("Arrangement")
+				.field(&arrangement.id())
+				.finish(),
+			FreedResource::SequenceInstance(id) => {
+				f.debug_tuple("SequenceInstance").field(id).finish()
+			}
+			FreedResource::Metronome(id) => f.debug_tuple("Metronome").field(id).finish(),
+			FreedResource::SubTrack(id) => f.debug_tuple("SubTrack").field(id).finish(),
+			FreedResource::SendTrack(id) => f.debug_tuple("SendTrack").field(id).finish(),
+			FreedResource::Effect(id) => f.debug_tuple("Effect").field(id).finish(),
+			FreedResource::Group(id) => f.debug_tuple("Group").field(id).finish(),
+			FreedResource::Stream(id) => f.debug_tuple("Stream").field(id).finish(),
+		}
+	}
+}
+
+/// A resource the audio thread is done with.
+///
+/// Resources that can't be reused are only held
+/// so they can be dropped on the main thread.
+pub(crate) enum RemovedResource {
+	Sound(Owned<Sound>),
+	/// The old version of a sound that was replaced. The sound
+	/// itself is still loaded.
+	ReplacedSound(Owned<Sound>),
+	Arrangement(Owned<Arrangement>),
+	SequenceInstance {
+		id: SequenceInstanceId,
+		_instance: Owned<SequenceInstance>,
+	},
+	Metronome {
+		id: MetronomeId,
+		_metronome: Owned<Metronome>,
+	},
+	SubTrack {
+		id: SubTrackId,
+		_track: Owned<Track>,
+	},
+	SendTrack {
+		id: SendTrackId,
+		_track: Owned<Track>,
+	},
+	Effect {
+		id: EffectId,
+		_effect_slot: EffectSlot,
+	},
+	Group {
+		id: GroupId,
+		_group: Owned<Group>,
+	},
+	Stream {
+		id: AudioStreamId,
+		_stream: Owned<Box<dyn AudioStream>>,
+	},
+}
+
+impl RemovedResource {
+	fn as_freed_resource(&self) -> FreedResource<'_> {
+		match self {
+			RemovedResource::Sound(sound) | RemovedResource::ReplacedSound(sound) => {
+				FreedResource::Sound(sound)
+			}
+			RemovedResource::Arrangement(arrangement) => FreedResource::Arrangement(arrangement),
+			RemovedResource::SequenceInstance { id, .. } => FreedResource::SequenceInstance(*id),
+			RemovedResource::Metronome { id, .. } => FreedResource::Metronome(*id),
+			RemovedResource::SubTrack { id, .. } => FreedResource::SubTrack(*id),
+			RemovedResource::SendTrack { id, .. } => FreedResource::SendTrack(*id),
+			RemovedResource::Effect { id, .. } => FreedResource::Effect(*id),
+			RemovedResource::Group { id, .. } => FreedResource::Group(*id),
+			RemovedResource::Stream { id, .. } => FreedResource::Stream(*id),
+		}
+	}
+
+	/// Returns the ID of the sound or arrangement the audio
+	/// thread removed, if this is one.
+	pub fn playable_id(&self) -> Option<PlayableId> {
+		match self {
+			RemovedResource::Sound(sound) => Some(sound.id().into()),
+			RemovedResource::Arrangement(arrangement) => Some(arrangement.id().into()),
+			_ => None,
+		}
+	}
+
+	/// Reports the resource to the callback and counts it,
+	/// then drops it.
+	pub fn free(self, freed: &mut FreedResources, callback: &mut impl FnMut(FreedResource)) {
+		let resource = self.as_freed_resource();
+		freed.count(&resource);
+		callback(resource);
+	}
+}
+
+/// Sends resources the audio thread is done with
+/// back to the main thread.
+pub(crate) struct RemovedResourceProducer {
+	producer: Producer<RemovedResource>,
+}
+
+impl RemovedResourceProducer {
+	pub fn new(producer: Producer<RemovedResource>) -> Self {
+		Self { producer }
+	}
+
+	pub fn push(&mut self, resource: RemovedResource) {
+		// if the queue is full, the resource is dropped here.
+		// it'll still be freed by the collector, it just won't
+		// show up in the report
+		self.producer.push(resource).ok();
+	}
+}
@@ -0,0 +1,246 @@
+//! Saves and restores the state of the audio thread.
+//!
+//! A snapshot records everything that's changed on the audio
+//! thread since things were created: which instances are playing
+//! and where they are, the values of parameters, how far
+//! metronomes have ticked, and which step each sequence
+//! instance is on. This lets games with save-anywhere systems
+//! resume music and ambience exactly where they left off.
+//!
+//! Capturing a snapshot is asynchronous, since the audio thread
+//! has to do the work. Request one with
+//! [`AudioManager::capture_snapshot`](super::AudioManager::capture_snapshot)
+//! and pick it up with
+//! [`AudioManager::pop_snapshot`](super::AudioManager::pop_snapshot)
+//! once the audio thread has processed the request:
+//!
+//! ```no_run
+//! # use kira::manager::{AudioManager, AudioManagerSettings};
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! audio_manager.capture_snapshot()?;
+//! // later, e.g. on the next frame of the game
+//! if let Some(snapshot) = audio_manager.pop_snapshot() {
+//! 	// save the snapshot along with the rest of the game state
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`AudioManager::restore_snapshot`](super::AudioManager::restore_snapshot)
+//! plays the instances from a snapshot again with new IDs and
+//! sends the rest of the saved state to the audio thread. Sounds, arrangements,
+//! parameters, metronomes, and sequence instances aren't created
+//! by restoring a snapshot - they should be set up with the same
+//! IDs as when the snapshot was captured before restoring it.
+//! Anything in the snapshot that doesn't exist is skipped.
+//!
+//! With the `serde_support` feature enabled, snapshots can be
+//! serialized and deserialized.
+
+use std::sync::{Arc, Mutex};
+
+use crate::{
+	group::InstanceGroups,
+	instance::{InstanceId, SeekBehavior},
+	metronome::MetronomeId,
+	mixer::TrackIndex,
+	parameter::{tween::Tween, ParameterId},
+	playable::PlayableId,
+	sequence::{SequenceInstanceId, SequencePosition},
+	PanningLaw, Value,
+};
+
+/// The state of an instance when a snapshot was captured.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct InstanceSnapshot {
+	/// The ID of the instance.
+	pub id: InstanceId,
+	/// The sound or arrangement the instance was playing.
+	pub playable: PlayableId,
+	/// The sequence instance that started the instance, if any.
+	pub sequence: Option<SequenceInstanceId>,
+	/// Where the region the instance was playing starts in
+	/// the sound or arrangement (in seconds).
+	pub region_start: f64,
+	/// The duration of the region the instance was playing
+	/// (in seconds).
+	pub duration: f64,
+	/// The track the instance was playing on.
+	pub track: TrackIndex,
+	/// The groups the instance was added to when it was played.
+	pub groups: InstanceGroups,
+	/// The volume setting of the instance.
+	pub volume: Value<f64>,
+	/// The playback rate setting of the instance.
+	pub playback_rate: Value<f64>,
+	/// The panning setting of the instance.
+	pub panning: Value<f64>,
+	/// How the instance's audio was panned.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub panning_law: PanningLaw,
+	/// The stereo width setting of the instance.
+	#[cfg_attr(feature = "serde_support", serde(default = "default_width"))]
+	pub width: Value<f64>,
+	/// The metronome the instance's playback rate follows
+	/// the tempo of, if any.
+	pub tempo_sync: Option<MetronomeId>,
+	/// Whether the instance was playing backwards.
+	pub reverse: bool,
+	/// The position the instance loops back to (in seconds),
+	/// if it loops.
+	pub loop_start: Option<f64>,
+	/// What happens when the instance is seeked out of range.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub seek_behavior: SeekBehavior,
+	/// The default tween used to fade out the instance
+	/// when it's stopped.
+	pub fade_out_tween: Option<Tween>,
+	/// The playback position of the instance (in seconds).
+	pub position: f64,
+	/// Whether the instance was paused.
+	pub paused: bool,
+}
+
+#[cfg(feature = "serde_support")]
+fn default_width() -> Value<f64> {
+	Value::Fixed(1.0)
+}
+
+/// The value of a parameter when a snapshot was captured.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ParameterSnapshot {
+	/// The ID of the parameter.
+	pub id: ParameterId,
+	/// The value of the parameter, not including modulations.
+	pub value: f64,
+}
+
+/// The state of a metronome when a snapshot was captured.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct MetronomeSnapshot {
+	/// The ID of the metronome.
+	pub id: MetronomeId,
+	/// How many beats the metronome had ticked.
+	pub time: f64,
+	/// Whether the metronome was ticking.
+	pub ticking: bool,
+}
+
+/// The state of a sequence instance when a snapshot was captured.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct SequenceSnapshot {
+	/// The ID of the sequence instance.
+	pub id: SequenceInstanceId,
+	/// The position of the sequence instance.
+	pub position: SequencePosition,
+	/// The number of times the sequence instance had looped.
+	pub loop_count: usize,
+	/// Whether the sequence instance was paused.
+	pub paused: bool,
+}
+
+/// The state of the audio thread at a point in time.
+///
+/// See the [module-level documentation](self) for more info.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct AudioSnapshot {
+	/// The instances that were playing or paused.
+	pub instances: Vec<InstanceSnapshot>,
+	/// The values of the parameters.
+	pub parameters: Vec<ParameterSnapshot>,
+	/// The state of the metronomes.
+	pub metronomes: Vec<MetronomeSnapshot>,
+	/// The state of the sequence instances.
+	pub sequences: Vec<SequenceSnapshot>,
+}
+
+pub(crate) type SharedSnapshot = Arc<Mutex<Option<AudioSnapshot>>>;
+
+/// A request for the audio thread to capture a snapshot.
+///
+/// The snapshot's lists are allocated on the main thread
+/// with enough room for every instance, parameter, metronome,
+/// and sequence instance, so filling them in never allocates
+/// memory on the audio thread.
+pub(crate) struct SnapshotRequest {
+	snapshot: AudioSnapshot,
+	destination: SharedSnapshot,
+}
+
+impl SnapshotRequest {
+	pub fn new(
+		destination: SharedSnapshot,
+		num_instances: usize,
+		num_parameters: usize,
+		num_metronomes: usize,
+		num_sequences: usize,
+	) -> Self {
+		Self {
+			snapshot: AudioSnapshot {
+				instances: Vec::with_capacity(num_instances),
+				parameters: Vec::with_capacity(num_parameters),
+				metronomes: Vec::with_capacity(num_metronomes),
+				sequences: Vec::with_capacity(num_sequences),
+			},
+			destination,
+		}
+	}
+
+	pub fn add_instance(&mut self, instance: InstanceSnapshot) {
+		push_within_capacity(&mut self.snapshot.instances, instance);
+	}
+
+	pub fn add_parameter(&mut self, parameter: ParameterSnapshot) {
+		push_within_capacity(&mut self.snapshot.parameters, parameter);
+	}
+
+	pub fn add_metronome(&mut self, metronome: MetronomeSnapshot) {
+		push_within_capacity(&mut self.snapshot.metronomes, metronome);
+	}
+
+	pub fn add_sequence(&mut self, sequence: SequenceSnapshot) {
+		push_within_capacity(&mut self.snapshot.sequences, sequence);
+	}
+
+	/// Hands the captured snapshot over to the main thread.
+	///
+	/// If the main thread is currently holding the lock,
+	/// the snapshot is discarded rather than blocking the
+	/// audio thread.
+	pub fn finish(&mut self) {
+		if let Ok(mut destination) = self.destination.try_lock() {
+			// the previous snapshot (if any) is moved into the request,
+			// which will be dropped on the main thread
+			std::mem::swap(
+				&mut self.snapshot,
+				destination.get_or_insert_with(Default::default),
+			);
+		}
+	}
+}
+
+fn push_within_capacity<T>(items: &mut Vec<T>, item: T) {
+	if items.len() < items.capacity() {
+		items.push(item);
+	}
+}
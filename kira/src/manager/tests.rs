@@ -1,27 +1,65 @@
-use crate::{arrangement::Arrangement, sound::Sound};
+use std::{sync::Arc, time::Duration};
+
+use instant::Instant;
+
+use ringbuf::RingBuffer;
+
+use crate::{
+	arrangement::{
+		definition::{ArrangementDefinition, SoundClipDefinition},
+		Arrangement,
+	},
+	audio_stream::AudioStream,
+	group::GroupSettings,
+	instance::{InstanceSettings, InstanceState, StopInstanceSettings},
+	metronome::{MetronomeId, MetronomeSettings},
+	mixer::{AddEffectError, SendTrackSettings, SubTrackId, SubTrackSettings, TrackIndex},
+	multi_sound::{MultiSound, MultiSoundSelection, MultiSoundVariation},
+	parameter::{ModulationSettings, ParameterId, ParameterSettings},
+	sequence::{
+		definition::{SequenceDefinition, SequenceStepDefinition},
+		Sequence, SequenceInstanceId, SequenceInstanceSettings, SequenceInstanceState,
+	},
+	sound::{Sound, SoundId, SoundSettings},
+	CommandError, CommandOverflowStrategy, Decibels, Frame, PlaybackStatistics, Sample, StartTime,
+	Value,
+};
+
+use crate::testing::{constant_sound, create_manager, ConstantStream, ScaleEffect};
 
 use super::{
+	backend::{Backend, OutputTiming, SharedOutputTiming},
 	error::{
-		AddArrangementError, AddGroupError, AddMetronomeError, AddParameterError,
-		AddSendTrackError, AddSoundError, AddSubTrackError,
+		AddArrangementError, AddGroupError, AddMetronomeError, AddModulationError, AddMuffleError,
+		AddParameterError, AddSendTrackError, AddSoundError, AddSubTrackError, AudioThreadError,
+		CommandFailure, FailedCommand, PlayError, ResolveNameError, SetNameError,
 	},
-	AudioManager, AudioManagerSettings,
+	names::PlayableName,
+	output_device::process_frame,
+	performance::{PerformanceEvent, PerformanceReport},
+	resources::{FreedResource, FreedResources, ResourceKind, ResourceUsage},
+	trace::TraceEventKind,
+	AudioManager, AudioManagerSettings, InstanceLimitBehavior, SettingsProfile,
 };
 
 fn create_manager_with_limited_capacity() -> AudioManager {
-	let (manager, _) = AudioManager::new_without_audio_thread(AudioManagerSettings {
-		num_sounds: 1,
-		num_arrangements: 1,
-		num_parameters: 1,
-		num_instances: 1,
-		num_sequences: 1,
-		num_sub_tracks: 1,
-		num_send_tracks: 1,
-		num_groups: 1,
-		num_streams: 1,
-		num_metronomes: 1,
-		..Default::default()
-	});
+	let (manager, _) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_sounds: 1,
+			num_arrangements: 1,
+			num_parameters: 1,
+			num_modulations: 1,
+			num_instances: 1,
+			num_sequences: 1,
+			num_sub_tracks: 1,
+			num_send_tracks: 1,
+			num_groups: 1,
+			num_streams: 1,
+			num_metronomes: 1,
+			..Default::default()
+		},
+		48000,
+	);
 	manager
 }
 
@@ -30,9 +68,17 @@ fn returns_error_on_exceeded_sound_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	let sound = Sound::from_frames(48000, vec![], Default::default());
 	assert!(manager.add_sound(sound.clone()).is_ok());
-	if let Err(AddSoundError::SoundLimitReached) = manager.add_sound(sound.clone()) {
+	if let Err(AddSoundError::SoundLimitReached(usage)) = manager.add_sound(sound.clone()) {
+		assert_eq!(
+			usage,
+			ResourceUsage {
+				kind: ResourceKind::Sound,
+				used: 1,
+				capacity: 1,
+			}
+		);
 	} else {
-		panic!("AudioManager::add_sound should return Err(AddSoundError::SoundLimitReached) when the maximum number of sounds is exceeded");
+		panic!("AudioManager::add_sound should return Err(AddSoundError::SoundLimitReached(_)) when the maximum number of sounds is exceeded");
 	}
 }
 
@@ -41,11 +87,11 @@ fn returns_error_on_exceeded_arrangement_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	let arrangement = Arrangement::new(Default::default());
 	assert!(manager.add_arrangement(arrangement.clone()).is_ok());
-	if let Err(AddArrangementError::ArrangementLimitReached) =
+	if let Err(AddArrangementError::ArrangementLimitReached(_)) =
 		manager.add_arrangement(arrangement.clone())
 	{
 	} else {
-		panic!("AudioManager::add_arrangement should return Err(AddArrangementError::ArrangementLimitReached) when the maximum number of arrangements is exceeded");
+		panic!("AudioManager::add_arrangement should return Err(AddArrangementError::ArrangementLimitReached(_)) when the maximum number of arrangements is exceeded");
 	}
 }
 
@@ -53,10 +99,11 @@ fn returns_error_on_exceeded_arrangement_capacity() {
 fn returns_error_on_exceeded_parameter_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	assert!(manager.add_parameter(Default::default()).is_ok());
-	if let Err(AddParameterError::ParameterLimitReached) = manager.add_parameter(Default::default())
+	if let Err(AddParameterError::ParameterLimitReached(_)) =
+		manager.add_parameter(Default::default())
 	{
 	} else {
-		panic!("AudioManager::add_parameter should return Err(AddParameterError::ParameterLimitReached) when the maximum number of arrangements is exceeded");
+		panic!("AudioManager::add_parameter should return Err(AddParameterError::ParameterLimitReached(_)) when the maximum number of arrangements is exceeded");
 	}
 }
 
@@ -64,9 +111,9 @@ fn returns_error_on_exceeded_parameter_capacity() {
 fn returns_error_on_exceeded_sub_track_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	assert!(manager.add_sub_track(Default::default()).is_ok());
-	if let Err(AddSubTrackError::TrackLimitReached) = manager.add_sub_track(Default::default()) {
+	if let Err(AddSubTrackError::TrackLimitReached(_)) = manager.add_sub_track(Default::default()) {
 	} else {
-		panic!("AudioManager::add_sub_track should return Err(AddSubTrackError::TrackLimitReached) when the maximum number of arrangements is exceeded");
+		panic!("AudioManager::add_sub_track should return Err(AddSubTrackError::TrackLimitReached(_)) when the maximum number of arrangements is exceeded");
 	}
 }
 
@@ -74,9 +121,10 @@ fn returns_error_on_exceeded_sub_track_capacity() {
 fn returns_error_on_exceeded_send_track_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	assert!(manager.add_send_track(Default::default()).is_ok());
-	if let Err(AddSendTrackError::TrackLimitReached) = manager.add_send_track(Default::default()) {
+	if let Err(AddSendTrackError::TrackLimitReached(_)) = manager.add_send_track(Default::default())
+	{
 	} else {
-		panic!("AudioManager::add_send_track should return Err(AddSendTrackError::TrackLimitReached) when the maximum number of arrangements is exceeded");
+		panic!("AudioManager::add_send_track should return Err(AddSendTrackError::TrackLimitReached(_)) when the maximum number of arrangements is exceeded");
 	}
 }
 
@@ -84,9 +132,9 @@ fn returns_error_on_exceeded_send_track_capacity() {
 fn returns_error_on_exceeded_group_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	assert!(manager.add_group(Default::default()).is_ok());
-	if let Err(AddGroupError::GroupLimitReached) = manager.add_group(Default::default()) {
+	if let Err(AddGroupError::GroupLimitReached(_)) = manager.add_group(Default::default()) {
 	} else {
-		panic!("AudioManager::add_group should return Err(AddGroupError::GroupLimitReached) when the maximum number of arrangements is exceeded");
+		panic!("AudioManager::add_group should return Err(AddGroupError::GroupLimitReached(_)) when the maximum number of arrangements is exceeded");
 	}
 }
 
@@ -94,11 +142,1244 @@ fn returns_error_on_exceeded_group_capacity() {
 fn returns_error_on_exceeded_metronome_capacity() {
 	let mut manager = create_manager_with_limited_capacity();
 	assert!(manager.add_metronome(Default::default()).is_ok());
-	if let Err(AddMetronomeError::MetronomeLimitReached) = manager.add_metronome(Default::default())
+	if let Err(AddMetronomeError::MetronomeLimitReached(_)) =
+		manager.add_metronome(Default::default())
 	{
 	} else {
-		panic!("AudioManager::add_metronome should return Err(AddMetronomeError::MetronomeLimitReached) when the maximum number of arrangements is exceeded");
+		panic!("AudioManager::add_metronome should return Err(AddMetronomeError::MetronomeLimitReached(_)) when the maximum number of arrangements is exceeded");
 	}
 }
 
 // TODO: write a test for exceeded stream capacity
+
+#[test]
+fn postpones_sequence_steps_when_command_queue_is_full() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_commands: 2,
+			..Default::default()
+		},
+		48000,
+	);
+	let mut sequence = Sequence::<()>::new(Default::default());
+	for i in 0..5 {
+		sequence.set_parameter(ParameterId::new(), i as f64, None);
+	}
+	let handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	for _ in 0..4 {
+		backend.process();
+	}
+	// the steps are postponed over two frames, but that's
+	// one overflow
+	assert_eq!(
+		handle.overflow_count(),
+		1,
+		"Sequences should report when they postpone steps"
+	);
+	assert_eq!(
+		handle.state(),
+		SequenceInstanceState::Finished,
+		"Postponed sequence steps should run on later frames"
+	);
+}
+
+#[test]
+fn returns_error_when_muffling_nonexistent_track() {
+	let mut manager = create_manager_with_limited_capacity();
+	let id = SubTrackId::new();
+	if let Err(AddMuffleError::NoSubTrackWithId(error_id)) =
+		manager.add_muffle(&[TrackIndex::Main, id.into()], Default::default())
+	{
+		assert_eq!(error_id, id);
+	} else {
+		panic!("AudioManager::add_muffle should return Err(AddMuffleError::NoSubTrackWithId) when a track doesn't exist");
+	}
+}
+
+#[test]
+fn returns_error_when_modulating_nonexistent_parameter() {
+	let mut manager = create_manager_with_limited_capacity();
+	let parameter_handle = manager.add_parameter(ParameterSettings::default()).unwrap();
+	let id = ParameterId::new();
+	if let Err(AddModulationError::NoParameterWithId(error_id)) =
+		manager.add_modulation(ModulationSettings::new(&parameter_handle, id))
+	{
+		assert_eq!(error_id, id);
+	} else {
+		panic!("AudioManager::add_modulation should return Err(AddModulationError::NoParameterWithId) when the target parameter does not exist");
+	}
+}
+
+#[test]
+fn picks_variations_of_multi_sounds() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handles = (0..3)
+		.map(|_| {
+			manager
+				.add_sound(constant_sound(1.0, Default::default()))
+				.unwrap()
+		})
+		.collect::<Vec<_>>();
+	let mut round_robin = MultiSound::new(MultiSoundSelection::RoundRobin);
+	let mut no_repeat = MultiSound::new(MultiSoundSelection::RandomNoRepeat);
+	assert!(round_robin.play(InstanceSettings::new()).unwrap().is_none());
+	for sound_handle in &sound_handles {
+		round_robin.add_variation(sound_handle);
+		no_repeat.add_variation(sound_handle);
+	}
+	let mut round_robin_order = vec![];
+	for _ in 0..6 {
+		round_robin.play(InstanceSettings::new()).unwrap();
+		backend.process();
+		round_robin_order.push(round_robin.last_variation().unwrap());
+	}
+	assert_eq!(round_robin_order, vec![0, 1, 2, 0, 1, 2]);
+	let mut last_variation = None;
+	for _ in 0..100 {
+		no_repeat.play(InstanceSettings::new()).unwrap();
+		backend.process();
+		assert_ne!(no_repeat.last_variation(), last_variation);
+		last_variation = no_repeat.last_variation();
+	}
+}
+
+#[test]
+fn applies_the_volume_ranges_of_multi_sound_variations() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut multi_sound = MultiSound::new(MultiSoundSelection::Random);
+	multi_sound.add_variation(MultiSoundVariation::new(&sound_handle).volume(0.5, 0.5));
+	multi_sound
+		.play(
+			InstanceSettings::new()
+				.volume(0.5)
+				.fade_in_tween(None)
+				.panning(0.0),
+		)
+		.unwrap();
+	backend.process();
+	assert!((backend.process().left - 0.25).abs() < 0.001);
+}
+
+#[test]
+fn picks_multi_sound_variations_with_the_manager_seed() {
+	fn pick(seed: u64) -> Vec<(usize, Frame)> {
+		let (mut manager, mut backend) = AudioManager::new_headless(
+			AudioManagerSettings {
+				random_seed: Some(seed),
+				..Default::default()
+			},
+			48000,
+		);
+		let mut multi_sound = MultiSound::new(MultiSoundSelection::Random);
+		for _ in 0..4 {
+			let sound_handle = manager
+				.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+				.unwrap();
+			multi_sound.add_variation(MultiSoundVariation::new(&sound_handle).volume(0.0, 1.0));
+		}
+		(0..20)
+			.map(|_| {
+				multi_sound.play(InstanceSettings::new()).unwrap();
+				(multi_sound.last_variation().unwrap(), backend.process())
+			})
+			.collect()
+	}
+
+	assert_eq!(pick(1), pick(1));
+	assert_ne!(pick(1), pick(2));
+}
+
+#[test]
+fn reports_underruns_and_slow_buffers() {
+	let (mut manager, mut backend) = create_manager();
+	// 480 frames = 10ms at 48kHz
+	for _ in 0..480 {
+		backend.process();
+	}
+	backend.finish_buffer(480, None, Duration::from_millis(1));
+	for _ in 0..480 {
+		backend.process();
+	}
+	backend.finish_buffer(
+		480,
+		Some(Duration::from_millis(30)),
+		Duration::from_millis(20),
+	);
+	assert_eq!(
+		manager.performance_report(),
+		PerformanceReport {
+			buffers: 2,
+			underruns: 1,
+			slow_buffers: 1,
+		}
+	);
+	assert!(matches!(
+		manager.pop_performance_event(),
+		Some(PerformanceEvent::Underrun { .. })
+	));
+	match manager.pop_performance_event() {
+		Some(PerformanceEvent::SlowBuffer { time, load }) => {
+			assert!((time - 0.01).abs() < 1e-9);
+			assert!((load - 2.0).abs() < 1e-9);
+		}
+		event => panic!("Expected a slow buffer event, got {:?}", event),
+	}
+	assert_eq!(manager.pop_performance_event(), None);
+}
+
+#[test]
+fn runs_scheduled_commands_at_their_start_time() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut parameter_handle = manager
+		.add_parameter(ParameterSettings::new().value(1.0))
+		.unwrap();
+	let mut instance_handle = sound_handle
+		.play(InstanceSettings::new().volume(&parameter_handle))
+		.unwrap();
+	parameter_handle
+		.set_at(0.5, None, StartTime::ClockTime(10.5 / 48000.0))
+		.unwrap();
+	instance_handle
+		.stop(
+			StopInstanceSettings::new()
+				.fade_tween(None)
+				.start_time(StartTime::ClockTime(20.5 / 48000.0)),
+		)
+		.unwrap();
+	let full_volume = backend.process();
+	// instances pick up parameter changes on the next frame
+	for _ in 1..12 {
+		assert_eq!(backend.process(), full_volume);
+	}
+	for _ in 12..21 {
+		assert_eq!(backend.process(), full_volume * 0.5);
+	}
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+}
+
+#[test]
+fn converts_between_game_time_and_audio_time() {
+	let (manager, mut backend) = create_manager();
+	assert_eq!(manager.output_latency(), 0.0);
+	// 480 frames = 10ms at 48kHz
+	for _ in 0..480 {
+		backend.process();
+	}
+	backend.set_output_timing(480, Duration::from_millis(20));
+	assert!((manager.output_latency() - 0.03).abs() < 1e-9);
+	// audio produced at a certain time is heard once the device
+	// latency has passed
+	let heard_at = manager.heard_at(0.015);
+	assert!((manager.audio_time_at(heard_at) - 0.035).abs() < 1e-6);
+	// game times before the buffer started map to earlier audio times
+	let heard_at = manager.heard_at(0.0);
+	assert!((manager.audio_time_at(heard_at) - 0.02).abs() < 1e-6);
+}
+
+#[test]
+fn never_reads_output_timings_that_are_being_written() {
+	let timing = Arc::new(SharedOutputTiming::default());
+	assert!(timing.load().is_none());
+	let writer = std::thread::spawn({
+		let timing = timing.clone();
+		move || {
+			for i in 1..=100_000 {
+				timing.store(OutputTiming {
+					buffer_start: Instant::now(),
+					buffer_start_time: i as f64,
+					device_latency: i as f64,
+					buffer_duration: i as f64,
+				});
+			}
+		}
+	});
+	while !writer.is_finished() {
+		if let Some(timing) = timing.load() {
+			assert_eq!(timing.device_latency, timing.buffer_start_time);
+			assert_eq!(timing.buffer_duration, timing.buffer_start_time);
+		}
+	}
+	writer.join().unwrap();
+	assert_eq!(timing.load().unwrap().buffer_start_time, 100_000.0);
+}
+
+#[test]
+fn holds_back_commands_when_the_command_queue_overflows() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_commands: 2,
+			command_overflow_strategy: CommandOverflowStrategy::Grow,
+			..Default::default()
+		},
+		48000,
+	);
+	for _ in 0..3 {
+		manager.add_parameter(ParameterSettings::new()).unwrap();
+	}
+	assert_eq!(manager.command_queue_capacity().unwrap(), 2);
+	assert_eq!(manager.command_queue_len().unwrap(), 3);
+	backend.process();
+	assert_eq!(manager.command_queue_len().unwrap(), 1);
+	assert_eq!(manager.flush_commands().unwrap(), 0);
+	backend.process();
+	assert_eq!(manager.command_queue_len().unwrap(), 0);
+}
+
+#[test]
+fn drops_non_critical_commands_when_the_command_queue_overflows() {
+	let (mut manager, _) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_commands: 1,
+			command_overflow_strategy: CommandOverflowStrategy::DropNonCritical(1),
+			..Default::default()
+		},
+		48000,
+	);
+	let mut parameter = manager.add_parameter(ParameterSettings::new()).unwrap();
+	parameter.set(1.0, None).unwrap();
+	assert_eq!(manager.command_queue_len().unwrap(), 2);
+	// the backlog is full, so the parameter change makes room
+	manager.add_parameter(ParameterSettings::new()).unwrap();
+	assert_eq!(manager.command_queue_len().unwrap(), 2);
+	// the backlog only holds critical commands now
+	assert!(matches!(
+		manager.add_parameter(ParameterSettings::new()),
+		Err(AddParameterError::CommandProducerError(
+			CommandError::CommandQueueFull
+		))
+	));
+}
+
+#[test]
+fn reports_freed_resources() {
+	let (mut manager, mut backend) = create_manager();
+	let sound = constant_sound(0.0, Default::default());
+	let sound_handle = manager.add_sound(sound).unwrap();
+	let group_handle = manager.add_group(Default::default()).unwrap();
+	backend.process();
+	assert_eq!(manager.free_unused_resources(), FreedResources::default());
+	manager.remove_sound(&sound_handle).unwrap();
+	manager.remove_group(&group_handle).unwrap();
+	backend.process();
+	let mut freed_sound_id = None;
+	let freed = manager.free_unused_resources_with(|resource| {
+		if let FreedResource::Sound(sound) = resource {
+			freed_sound_id = Some(sound.id());
+		}
+	});
+	assert_eq!(
+		freed,
+		FreedResources {
+			sounds: 1,
+			groups: 1,
+			..Default::default()
+		}
+	);
+	assert_eq!(freed.total(), 2);
+	assert_eq!(freed_sound_id, Some(sound_handle.id()));
+}
+
+#[test]
+fn uses_settings_profiles() {
+	let (manager, _) = create_manager();
+	assert_eq!(manager.settings_profile(), SettingsProfile::Default);
+	let (manager, _) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_instances: 10,
+			..AudioManagerSettings::mobile()
+		},
+		48000,
+	);
+	assert_eq!(manager.settings_profile(), SettingsProfile::Mobile);
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).capacity, 10);
+	assert_eq!(
+		manager.resource_usage(ResourceKind::Sound).capacity,
+		AudioManagerSettings::mobile().num_sounds
+	);
+}
+
+#[test]
+fn reports_when_the_instance_limit_is_reached() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_instances: 1,
+			..Default::default()
+		},
+		48000,
+	);
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	for _ in 0..10 {
+		backend.process();
+	}
+	backend.start_buffer();
+	let usage = ResourceUsage {
+		kind: ResourceKind::Instance,
+		used: 1,
+		capacity: 1,
+	};
+	assert_eq!(manager.resource_usage(ResourceKind::Instance), usage);
+	assert_eq!(manager.pop_performance_event(), None);
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	backend.process();
+	match manager.pop_performance_event() {
+		Some(PerformanceEvent::ResourceLimitReached {
+			usage: reported, ..
+		}) => {
+			assert_eq!(reported, usage);
+		}
+		event => panic!("Expected a resource limit event, got {:?}", event),
+	}
+	assert_eq!(
+		manager.pop_audio_thread_error(),
+		Some(AudioThreadError::ResourceLimitReached(usage))
+	);
+}
+
+#[test]
+fn collects_playback_statistics() {
+	let (mut manager, mut backend) = create_manager();
+	// a quarter second of sound followed by a quarter second of silence
+	let mut frames = vec![Frame::from_mono(1.0); 12000];
+	frames.extend(vec![Frame::from_mono(0.0); 12000]);
+	let sound = Sound::from_frames(48000, frames, SoundSettings::new().collect_statistics());
+	let untracked_sound_handle = manager
+		.add_sound(sound.with_settings(SoundSettings::new()))
+		.unwrap();
+	let mut sound_handle = manager.add_sound(sound).unwrap();
+	assert_eq!(untracked_sound_handle.statistics(), None);
+	assert_eq!(
+		sound_handle.statistics(),
+		Some(PlaybackStatistics {
+			times_played: 0,
+			audible_duration: 0.0,
+		})
+	);
+	for _ in 0..2 {
+		sound_handle.play(InstanceSettings::default()).unwrap();
+		for _ in 0..24000 {
+			backend.process();
+		}
+	}
+	// instances prevented from playing by a cooldown aren't counted
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	backend.process();
+	let statistics = sound_handle.statistics().unwrap();
+	assert_eq!(statistics.times_played, 3);
+	assert!((statistics.audible_duration - 0.5).abs() < 0.001);
+}
+
+#[test]
+fn looks_up_resources_by_name() {
+	let (mut manager, _) = create_manager();
+	let sound = constant_sound(0.0, Default::default());
+	let sound_handle = manager.add_sound(sound).unwrap();
+	let group_handle = manager.add_group(GroupSettings::new()).unwrap();
+	let mut send_track_handle = manager.add_send_track(SendTrackSettings::new()).unwrap();
+	manager.set_name("footstep", &sound_handle).unwrap();
+	manager.set_name("footstep", &group_handle).unwrap();
+	manager.set_name("reverb", &send_track_handle).unwrap();
+	assert_eq!(
+		manager.sound("footstep").map(|handle| handle.id()),
+		Some(sound_handle.id())
+	);
+	assert_eq!(
+		manager.group("footstep").map(|handle| handle.id()),
+		Some(group_handle.id())
+	);
+	assert_eq!(
+		manager.track("reverb"),
+		Some(TrackIndex::Send(send_track_handle.id()))
+	);
+	assert_eq!(manager.name_of(&sound_handle), Some("footstep"));
+	// effects are forgotten along with their track
+	let effect_handle = send_track_handle
+		.add_effect(ScaleEffect(0.5), Default::default())
+		.unwrap();
+	manager.set_name("tail", &effect_handle).unwrap();
+	assert_eq!(
+		manager.effect("tail").map(|handle| handle.id()),
+		Some(effect_handle.id())
+	);
+	manager.remove_send_track(&send_track_handle).unwrap();
+	assert!(manager.effect("tail").is_none());
+	assert!(manager.track("reverb").is_none());
+	// renaming a resource removes its old name
+	manager.set_name("step", &sound_handle).unwrap();
+	assert!(manager.sound("footstep").is_none());
+	// removing a resource removes its name
+	manager.remove_sound(&sound_handle).unwrap();
+	assert!(manager.sound("step").is_none());
+	assert!(matches!(
+		manager.set_name("step", &sound_handle),
+		Err(SetNameError::NonexistentResource)
+	));
+}
+
+#[test]
+fn creates_sequences_and_arrangements_from_definitions() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(constant_sound(0.5, Default::default()))
+		.unwrap();
+	manager.set_name("beep", &sound_handle).unwrap();
+	let track = manager.add_sub_track(SubTrackSettings::default()).unwrap();
+	manager.set_name("music", &track).unwrap();
+	let arrangement = manager
+		.arrangement_from_definition(&ArrangementDefinition {
+			default_track: Some("music".into()),
+			clips: vec![
+				SoundClipDefinition::new("beep", 0.0),
+				SoundClipDefinition {
+					duration: Some(2.0),
+					..SoundClipDefinition::new("beep", 1.0)
+				},
+			],
+			..Default::default()
+		})
+		.unwrap();
+	assert_eq!(arrangement.duration(), 3.0);
+	assert_eq!(arrangement.default_track(), TrackIndex::Sub(track.id()));
+	let arrangement_handle = manager.add_arrangement(arrangement).unwrap();
+	manager.set_name("song", &arrangement_handle).unwrap();
+	let mut definition = SequenceDefinition::<()>::new();
+	definition.steps = vec![
+		SequenceStepDefinition::Play {
+			playable: PlayableName::Sound("beep".into()),
+			instance: Some("first".into()),
+			settings: InstanceSettings::default(),
+		},
+		SequenceStepDefinition::Play {
+			playable: PlayableName::Arrangement("song".into()),
+			instance: None,
+			settings: InstanceSettings::default(),
+		},
+		SequenceStepDefinition::StopInstance {
+			instance: "first".into(),
+			settings: StopInstanceSettings::new().fade_tween(None),
+		},
+	];
+	let sequence = manager.sequence_from_definition(&definition).unwrap();
+	manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	backend.process();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 1);
+	definition
+		.steps
+		.push(SequenceStepDefinition::SetInstanceVolume {
+			instance: "second".into(),
+			volume: Value::Fixed(0.5),
+		});
+	assert!(matches!(
+		manager.sequence_from_definition(&definition),
+		Err(ResolveNameError::NoInstanceWithName(name)) if name == "second"
+	));
+	assert!(matches!(
+		manager.arrangement_from_definition(&ArrangementDefinition {
+			clips: vec![SoundClipDefinition::new("boop", 0.0)],
+			..Default::default()
+		}),
+		Err(ResolveNameError::NoSoundWithName(name)) if name == "boop"
+	));
+}
+
+#[test]
+fn restores_snapshots_of_the_audio_state() {
+	let sound_id = SoundId::new();
+	let parameter_id = ParameterId::new();
+	let metronome_id = MetronomeId::new();
+	let sequence_id = SequenceInstanceId::new();
+	// sets up an audio manager with the same resources each time,
+	// like a game would after loading a save file
+	let set_up = || {
+		let (mut manager, backend) = create_manager();
+		let sound_handle = manager
+			.add_sound(constant_sound(1.0, SoundSettings::new().id(sound_id)))
+			.unwrap();
+		let parameter_handle = manager
+			.add_parameter(ParameterSettings::new().id(parameter_id))
+			.unwrap();
+		let metronome_handle = manager
+			.add_metronome(MetronomeSettings::new().id(metronome_id))
+			.unwrap();
+		let mut sequence = Sequence::<()>::new(Default::default());
+		sequence.wait(crate::Duration::Seconds(0.01));
+		sequence.wait(crate::Duration::Seconds(0.01));
+		let sequence_handle = manager
+			.start_sequence(sequence, SequenceInstanceSettings::new().id(sequence_id))
+			.unwrap();
+		(
+			manager,
+			backend,
+			sound_handle,
+			parameter_handle,
+			metronome_handle,
+			sequence_handle,
+		)
+	};
+	let capture = |manager: &mut AudioManager, backend: &mut Backend| {
+		manager.capture_snapshot().unwrap();
+		backend.process();
+		manager.pop_snapshot().unwrap()
+	};
+
+	let (mut manager, mut backend, mut sound_handle, mut parameter_handle, mut metronome_handle, _) =
+		set_up();
+	sound_handle
+		.play(InstanceSettings::new().volume(0.5))
+		.unwrap();
+	parameter_handle.set(0.25, None).unwrap();
+	metronome_handle.start().unwrap();
+	for _ in 0..720 {
+		backend.process();
+	}
+	let snapshot = capture(&mut manager, &mut backend);
+	assert!(manager.pop_snapshot().is_none());
+	assert_eq!(snapshot.instances.len(), 1);
+	let instance_snapshot = snapshot.instances[0];
+	assert!(!instance_snapshot.paused);
+	assert_eq!(snapshot.parameters[0].value, 0.25);
+	let metronome_snapshot = snapshot.metronomes[0];
+	assert!(metronome_snapshot.ticking);
+	assert!(metronome_snapshot.time > 0.0);
+	assert_eq!(snapshot.sequences[0].position.step, 1);
+
+	let (mut manager, mut backend, _, _, _, sequence_handle) = set_up();
+	let instance_handles = manager.restore_snapshot(&snapshot).unwrap();
+	assert_eq!(instance_handles.len(), 1);
+	assert_ne!(instance_handles[0].id(), instance_snapshot.id);
+	let restored_snapshot = capture(&mut manager, &mut backend);
+	let restored_instance = restored_snapshot.instances[0];
+	assert_eq!(restored_instance.id, instance_handles[0].id());
+	assert!((restored_instance.position - instance_snapshot.position).abs() < 0.001);
+	assert_eq!(restored_snapshot.parameters[0].value, 0.25);
+	assert!(restored_snapshot.metronomes[0].ticking);
+	assert!((restored_snapshot.metronomes[0].time - metronome_snapshot.time).abs() < 0.01);
+	assert_eq!(sequence_handle.position().step, 1);
+	assert!(
+		(sequence_handle.position().wait_progress - snapshot.sequences[0].position.wait_progress)
+			.abs() < 0.01
+	);
+}
+
+#[test]
+fn restores_snapshots_alongside_playing_instances() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_instances: 3,
+			instance_limit_behavior: InstanceLimitBehavior::RejectNew,
+			..Default::default()
+		},
+		48000,
+	);
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	sound_handle.play(InstanceSettings::new()).unwrap();
+	sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	manager.capture_snapshot().unwrap();
+	backend.process();
+	let snapshot = manager.pop_snapshot().unwrap();
+	assert_eq!(snapshot.instances.len(), 2);
+	backend.start_buffer();
+	// the restored instances don't replace the ones that are
+	// playing, and only one of them fits under the limit
+	let instance_handles = manager.restore_snapshot(&snapshot).unwrap();
+	assert_eq!(instance_handles.len(), 1);
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.resource_usage(ResourceKind::Instance).used, 3);
+	assert_eq!(instance_handles[0].state(), InstanceState::Playing);
+}
+
+#[test]
+fn records_a_trace_of_audio_thread_decisions() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_instances: 1,
+			trace_capacity: Some(100),
+			..Default::default()
+		},
+		48000,
+	);
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let first = sound_handle.play(InstanceSettings::default()).unwrap();
+	// wait for the sound's cooldown to pass
+	for _ in 0..10 {
+		backend.process();
+	}
+	let second = sound_handle.play(InstanceSettings::default()).unwrap();
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.wait(crate::Duration::Seconds(1.0));
+	manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	backend.process();
+	let events = manager
+		.dump_trace()
+		.into_iter()
+		.map(|event| event.kind)
+		.collect::<Vec<_>>();
+	assert_eq!(
+		&events[..4],
+		&[
+			TraceEventKind::CommandApplied("Resource::AddSound"),
+			TraceEventKind::CommandApplied("Instance::Play"),
+			TraceEventKind::InstanceStarted {
+				instance: first.id(),
+				playable: sound_handle.id().into(),
+			},
+			TraceEventKind::CommandApplied("Instance::Play"),
+		]
+	);
+	assert!(events.contains(&TraceEventKind::InstanceStolen {
+		instance: first.id(),
+		by: second.id(),
+	}));
+	assert!(events
+		.iter()
+		.any(|event| matches!(event, TraceEventKind::SequenceStep { step: 0, .. })));
+
+	// the oldest events are discarded when the trace is full
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			trace_capacity: Some(2),
+			..Default::default()
+		},
+		48000,
+	);
+	for _ in 0..3 {
+		manager.add_parameter(ParameterSettings::default()).unwrap();
+	}
+	backend.process();
+	assert_eq!(manager.dump_trace().len(), 2);
+
+	// tracing is disabled by default
+	let (mut manager, mut backend) = create_manager();
+	manager.add_parameter(ParameterSettings::default()).unwrap();
+	backend.process();
+	assert!(manager.dump_trace().is_empty());
+}
+
+#[test]
+fn deferred_managers_wait_to_be_started() {
+	let mut manager = AudioManager::new_deferred(AudioManagerSettings::default());
+	assert!(!manager.is_started());
+	// effects need a sample rate, so they can't be added yet
+	assert!(matches!(
+		manager
+			.main_track()
+			.add_effect(ScaleEffect(2.0), Default::default()),
+		Err(AddEffectError::AudioManagerNotStarted)
+	));
+	assert!(matches!(
+		manager.add_muffle(&[TrackIndex::Main], Default::default()),
+		Err(AddMuffleError::AudioManagerNotStarted)
+	));
+	// other resources can be added before starting
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.5, Default::default()))
+		.unwrap();
+	sound_handle
+		.play(InstanceSettings::new().panning(0.0))
+		.unwrap();
+	let mut backend = manager.start_embedded(48000).unwrap();
+	assert!(manager.is_started());
+	manager
+		.main_track()
+		.add_effect(ScaleEffect(2.0), Default::default())
+		.unwrap();
+	assert_eq!(backend.process(), Frame::new(1.0, 0.0));
+}
+
+#[test]
+fn hands_out_the_backend_when_started_embedded() {
+	let mut manager = AudioManager::new_deferred(AudioManagerSettings::default());
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.5, Default::default()))
+		.unwrap();
+	// commands sent before starting are processed by the backend
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	let mut backend = manager.start_embedded(48000).unwrap();
+	assert!(manager.is_started());
+	assert_ne!(backend.process(), Frame::from_mono(0.0));
+	// the backend can only be handed out once
+	assert!(manager.start_embedded(48000).is_none());
+}
+
+#[test]
+fn seeded_managers_make_the_same_random_choices() {
+	fn render(seed: u64) -> Vec<Frame> {
+		let (mut manager, mut backend) = AudioManager::new_headless(
+			AudioManagerSettings {
+				random_seed: Some(seed),
+				..Default::default()
+			},
+			48000,
+		);
+		let mut quiet_sound = manager
+			.add_sound(constant_sound(0.5, SoundSettings::new().cooldown(0.0)))
+			.unwrap();
+		let loud_sound = manager
+			.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+			.unwrap();
+		quiet_sound
+			.play(InstanceSettings::new().volume(Value::Random(0.0, 1.0)))
+			.unwrap();
+		let mut sequence = Sequence::<()>::new(Default::default());
+		for _ in 0..4 {
+			sequence.play_random(
+				vec![quiet_sound.id().into(), loud_sound.id().into()],
+				InstanceSettings::new().volume(Value::Random(0.0, 1.0)),
+			);
+			sequence.wait(crate::Duration::Seconds(0.001));
+		}
+		manager
+			.start_sequence(sequence, SequenceInstanceSettings::new())
+			.unwrap();
+		(0..480).map(|_| backend.process()).collect()
+	}
+
+	assert_eq!(render(1), render(1));
+	assert_ne!(render(1), render(2));
+}
+
+#[test]
+fn sets_volumes_in_decibels() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let mut instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	let unaffected = backend.process();
+	instance_handle.set_volume(Decibels(-6.0)).unwrap();
+	backend.process();
+	let quieter = backend.process();
+	assert!((quieter.left / unaffected.left - Decibels(-6.0).amplitude() as Sample).abs() < 1.0e-6);
+	// a parameter halfway through a fade from -60 dB
+	// to 0 dB sets the volume to -30 dB
+	let parameter = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	instance_handle
+		.set_volume(Decibels::fade(
+			&parameter,
+			Decibels(-60.0),
+			Decibels::IDENTITY,
+		))
+		.unwrap();
+	backend.process();
+	let faded = backend.process();
+	assert!((faded.left / unaffected.left - Decibels(-30.0).amplitude() as Sample).abs() < 1.0e-6);
+	assert!((Decibels::from_amplitude(0.5).amplitude() - 0.5).abs() < 1.0e-9);
+}
+
+#[test]
+fn plays_sounds_and_arrangements_by_id_or_name() {
+	let (mut manager, mut backend) = create_manager();
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let arrangement_handle = manager
+		.add_arrangement(Arrangement::new_loop(&sound_handle, Default::default()))
+		.unwrap();
+	manager.set_name("hum", &sound_handle).unwrap();
+	manager.set_name("hum", &arrangement_handle).unwrap();
+	let instance_handles = vec![
+		manager
+			.play(sound_handle.id(), InstanceSettings::new())
+			.unwrap(),
+		manager.play("hum", InstanceSettings::new()).unwrap(),
+		manager
+			.play(
+				PlayableName::Arrangement("hum".into()),
+				InstanceSettings::new(),
+			)
+			.unwrap(),
+	];
+	backend.process();
+	backend.process();
+	for instance_handle in &instance_handles {
+		assert_eq!(instance_handle.state(), InstanceState::Playing);
+	}
+	assert!(matches!(
+		manager.play("buzz", InstanceSettings::new()),
+		Err(PlayError::ResolveNameError(
+			ResolveNameError::NoSoundWithName(_)
+		))
+	));
+	manager.remove_sound(&sound_handle).unwrap();
+	assert!(matches!(
+		manager.play(&sound_handle, InstanceSettings::new()),
+		Err(PlayError::NoSoundWithId(_))
+	));
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn loads_and_unloads_sound_banks() {
+	use crate::sound_bank::{LoadSoundBankError, SoundBank, SoundBankManifest};
+
+	let dir = std::env::temp_dir().join(format!("kira-sound-bank-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	for name in &["beep", "boop"] {
+		let mut writer = hound::WavWriter::create(
+			dir.join(format!("{}.wav", name)),
+			hound::WavSpec {
+				channels: 1,
+				sample_rate: 48000,
+				bits_per_sample: 16,
+				sample_format: hound::SampleFormat::Int,
+			},
+		)
+		.unwrap();
+		for _ in 0..4800 {
+			writer.write_sample(i16::MAX).unwrap();
+		}
+		writer.finalize().unwrap();
+	}
+	std::fs::write(dir.join("credits.txt"), "beep and boop").unwrap();
+
+	let (mut manager, _) = create_manager();
+	// files that aren't audio are skipped
+	let bank = SoundBank::load_dir(&mut manager, &dir, SoundSettings::default()).unwrap();
+	assert_eq!(bank.sound_names().collect::<Vec<_>>(), vec!["beep", "boop"]);
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 2);
+	assert!(bank
+		.sound("beep")
+		.unwrap()
+		.play(InstanceSettings::new())
+		.is_ok());
+	bank.unload(&mut manager).unwrap();
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 0);
+
+	// if a sound can't be loaded, the sounds that were
+	// already loaded are removed
+	let manifest = SoundBankManifest::new()
+		.sound("beep", dir.join("beep.wav"))
+		.sound("missing", dir.join("missing.wav"));
+	assert!(matches!(
+		SoundBank::load_manifest(&mut manager, &manifest),
+		Err(LoadSoundBankError::LoadSoundError { name, .. }) if name == "missing"
+	));
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 0);
+
+	std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unloads_sounds_without_handles_automatically() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			auto_unload: true,
+			..Default::default()
+		},
+		48000,
+	);
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	manager.set_name("hum", &sound_handle).unwrap();
+	let mut instance_handle = sound_handle.clone().play(InstanceSettings::new()).unwrap();
+	backend.process();
+	// clones of the handle keep the sound loaded
+	let other_handle = manager.sound("hum").unwrap();
+	drop(sound_handle);
+	manager.free_unused_resources();
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 1);
+	// names don't keep the sound loaded
+	drop(other_handle);
+	manager.free_unused_resources();
+	assert!(manager.sound("hum").is_none());
+	// the sound isn't removed until it's done playing, and
+	// it counts towards the sound limit until then
+	backend.process();
+	assert!(backend.process().left > 0.0);
+	assert_eq!(manager.free_unused_resources().sounds, 0);
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 1);
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	backend.process();
+	assert_eq!(manager.free_unused_resources().sounds, 1);
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 0);
+
+	// arrangements keep the sounds they play loaded
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let arrangement_handle = manager
+		.add_arrangement(Arrangement::new_loop(&sound_handle, Default::default()))
+		.unwrap();
+	drop(sound_handle);
+	manager.free_unused_resources();
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 1);
+	drop(arrangement_handle);
+	manager.free_unused_resources();
+	assert_eq!(manager.resource_usage(ResourceKind::Arrangement).used, 1);
+	backend.process();
+	assert_eq!(manager.free_unused_resources().arrangements, 1);
+	assert_eq!(manager.resource_usage(ResourceKind::Arrangement).used, 0);
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 1);
+	backend.process();
+	assert_eq!(manager.free_unused_resources().sounds, 1);
+	assert_eq!(manager.resource_usage(ResourceKind::Sound).used, 0);
+}
+
+#[test]
+fn keeps_unloading_sounds_within_the_sound_limit() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_sounds: 1,
+			auto_unload: true,
+			..Default::default()
+		},
+		48000,
+	);
+	let sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let mut replaced_handle = manager
+		.replace_sound(
+			sound_handle.id(),
+			constant_sound(0.5, SoundSettings::new().cooldown(0.0)),
+		)
+		.unwrap();
+	let mut instance_handle = replaced_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	drop(sound_handle);
+	drop(replaced_handle);
+	// freeing the replaced sound doesn't free up its ID
+	assert_eq!(manager.free_unused_resources().sounds, 1);
+	assert!(matches!(
+		manager.add_sound(constant_sound(1.0, Default::default())),
+		Err(AddSoundError::SoundLimitReached(_))
+	));
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	backend.process();
+	assert_eq!(manager.free_unused_resources().sounds, 1);
+	assert!(manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.is_ok());
+}
+
+#[test]
+fn reports_commands_for_removed_sounds_and_instances() {
+	let (mut manager, mut backend) = AudioManager::new_headless(Default::default(), 48000);
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let mut instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	assert!(instance_handle.exists());
+	assert!(manager.pop_audio_thread_error().is_none());
+
+	// commands for instances that have stopped fail
+	instance_handle
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert!(!instance_handle.exists());
+	instance_handle.set_volume(0.5).unwrap();
+	backend.process();
+	assert_eq!(
+		manager.pop_audio_thread_error(),
+		Some(AudioThreadError::CommandFailed(FailedCommand {
+			command: "Instance::SetInstanceVolume",
+			failure: CommandFailure::NoInstanceWithId(instance_handle.id()),
+		}))
+	);
+
+	// instances that can't start stop existing right away
+	let mut old_handle = sound_handle.clone();
+	manager
+		.replace_sound(
+			sound_handle.id(),
+			constant_sound(1.0, SoundSettings::new().cooldown(1.0)),
+		)
+		.unwrap();
+	backend.process();
+	let first = old_handle.play(InstanceSettings::new()).unwrap();
+	let second = old_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	assert!(first.exists());
+	assert!(!second.exists());
+
+	// replacing a sound keeps it loaded, removing it doesn't
+	assert!(sound_handle.is_loaded());
+	manager.remove_sound(&sound_handle).unwrap();
+	assert!(!sound_handle.is_loaded());
+	assert!(!old_handle.is_loaded());
+	backend.process();
+	let instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	assert!(!instance_handle.exists());
+	assert_eq!(
+		manager.pop_audio_thread_error(),
+		Some(AudioThreadError::CommandFailed(FailedCommand {
+			command: "Instance::Play",
+			failure: CommandFailure::NoPlayableWithId(sound_handle.id().into()),
+		}))
+	);
+	assert!(manager.pop_audio_thread_error().is_none());
+}
+
+#[test]
+fn reports_audio_stream_errors() {
+	#[derive(Debug)]
+	struct FailingStream {
+		error: Option<String>,
+	}
+
+	impl AudioStream for FailingStream {
+		fn next(&mut self, _dt: f64) -> Frame {
+			Frame::from_mono(0.0)
+		}
+
+		fn pop_error(&mut self) -> Option<String> {
+			self.error.take()
+		}
+	}
+
+	let (mut manager, mut backend) = create_manager();
+	let stream = manager
+		.add_stream(
+			FailingStream {
+				error: Some("Corrupt packet".into()),
+			},
+			TrackIndex::Main,
+		)
+		.unwrap();
+	backend.process();
+	backend.process();
+	assert_eq!(
+		manager.pop_audio_thread_error(),
+		Some(AudioThreadError::StreamError {
+			stream: stream.id(),
+			error: "Corrupt packet".into(),
+		})
+	);
+	assert!(manager.pop_audio_thread_error().is_none());
+}
+
+#[test]
+fn rejects_new_instances_at_the_instance_limit() {
+	let (mut manager, mut backend) = AudioManager::new_headless(
+		AudioManagerSettings {
+			num_instances: 1,
+			instance_limit_behavior: InstanceLimitBehavior::RejectNew,
+			..Default::default()
+		},
+		48000,
+	);
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let first = sound_handle.play(InstanceSettings::new()).unwrap();
+	let second = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	assert!(first.exists());
+	assert!(!second.exists());
+	let usage = ResourceUsage {
+		kind: ResourceKind::Instance,
+		used: 1,
+		capacity: 1,
+	};
+	assert_eq!(
+		manager.pop_audio_thread_error(),
+		Some(AudioThreadError::ResourceLimitReached(usage))
+	);
+	// once the audio thread reports that the limit is reached,
+	// the audio manager refuses to play more instances
+	backend.start_buffer();
+	assert!(matches!(
+		manager.play(&sound_handle, InstanceSettings::new()),
+		Err(PlayError::InstanceLimitReached(reported)) if reported == usage
+	));
+}
+
+#[test]
+fn renders_audio_headlessly_at_any_sample_rate() {
+	let (mut manager, mut backend) =
+		AudioManager::new_headless(AudioManagerSettings::default(), 100);
+	assert!(manager.is_started());
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			100,
+			vec![Frame::from_mono(1.0); 100],
+			Default::default(),
+		))
+		.unwrap();
+	let instance_handle = sound_handle.play(InstanceSettings::new()).unwrap();
+	// one second of audio at 100 Hz is 100 frames
+	backend.process();
+	for _ in 0..99 {
+		assert!(backend.process().left > 0.0);
+	}
+	backend.process();
+	backend.process();
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+	assert!(!instance_handle.exists());
+}
+
+#[test]
+fn routes_outputs_to_extra_devices_and_channels() {
+	let (mut manager, mut backend) = create_manager();
+	let device_track = manager
+		.add_sub_track(SubTrackSettings::new().output(1).volume(0.5))
+		.unwrap();
+	let channel_track = manager
+		.add_sub_track(SubTrackSettings::new().output(2).volume(0.25))
+		.unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Main)
+		.unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Sub(device_track.id()))
+		.unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Sub(channel_track.id()))
+		.unwrap();
+	backend.process();
+	// output 1 is played on its own device
+	let (producer, mut consumer) = RingBuffer::new(4).split();
+	let mut device_outputs = vec![None, Some(producer), None, None];
+	// output 2 gets its own pair of channels if the default
+	// device has them
+	let mut frame = [0.0; 6];
+	process_frame(&mut backend, &mut device_outputs, &mut frame);
+	assert_eq!(frame, [1.0, 1.0, 0.0, 0.0, 0.25, 0.25]);
+	assert_eq!(consumer.pop(), Some(Frame::from_mono(0.5)));
+	// otherwise, it's mixed into the main output
+	let mut frame = [0.0; 2];
+	process_frame(&mut backend, &mut device_outputs, &mut frame);
+	assert_eq!(frame, [1.25, 1.25]);
+	assert_eq!(consumer.pop(), Some(Frame::from_mono(0.5)));
+	let mut frame = [0.0; 1];
+	process_frame(&mut backend, &mut device_outputs, &mut frame);
+	assert_eq!(frame, [1.25]);
+	assert_eq!(consumer.pop(), Some(Frame::from_mono(0.5)));
+	assert_eq!(consumer.pop(), None);
+}
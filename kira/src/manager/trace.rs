@@ -0,0 +1,212 @@
+//! Records what the audio thread has been doing.
+//!
+//! Bugs that depend on the exact timing of commands, sequences,
+//! and instances can be hard to reproduce. If
+//! [`AudioManagerSettings::trace_capacity`](super::AudioManagerSettings::trace_capacity)
+//! is set, the audio thread keeps a log of its most recent
+//! decisions, which can be dumped from the main thread with
+//! [`AudioManager::dump_trace`](super::AudioManager::dump_trace)
+//! after something goes wrong:
+//!
+//! ```no_run
+//! # use kira::manager::{AudioManager, AudioManagerSettings};
+//! # #[cfg(feature = "cpal")]
+//! # {
+//! let mut audio_manager = AudioManager::new(AudioManagerSettings {
+//! 	trace_capacity: Some(1000),
+//! 	..Default::default()
+//! })?;
+//! // after a bug happens
+//! for event in audio_manager.dump_trace() {
+//! 	println!("{:.4}: {:?}", event.time, event.kind);
+//! }
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The log is a fixed-size ring buffer, so recording events
+//! never allocates memory on the audio thread. Once it's full,
+//! the oldest events are discarded.
+
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex},
+};
+
+use crate::{
+	group::GroupId,
+	instance::{InstanceId, InstanceState},
+	playable::PlayableId,
+	sequence::SequenceInstanceId,
+};
+
+/// Something the audio thread did.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TraceEventKind {
+	/// A command was received, but it won't be applied
+	/// until its start time is reached.
+	CommandScheduled(&'static str),
+	/// A command was applied.
+	CommandApplied(&'static str),
+	/// An instance started.
+	InstanceStarted {
+		/// The ID of the instance.
+		instance: InstanceId,
+		/// The sound or arrangement the instance is playing.
+		playable: PlayableId,
+	},
+	/// An instance wasn't started because its sound or
+	/// arrangement, one of its groups, or the audio
+	/// manager was cooling down.
+	InstanceCoolingDown {
+		/// The ID of the instance.
+		instance: InstanceId,
+		/// The sound or arrangement the instance would have played.
+		playable: PlayableId,
+	},
+	/// An instance wasn't started because one of its groups
+	/// already had as many instances as it allows.
+	InstanceGroupFull {
+		/// The ID of the instance.
+		instance: InstanceId,
+		/// The sound or arrangement the instance would have played.
+		playable: PlayableId,
+		/// The group that was full.
+		group: GroupId,
+	},
+	/// An instance wasn't started because the instance limit
+	/// was reached and new instances are
+	/// [rejected](super::InstanceLimitBehavior::RejectNew).
+	InstanceRejected {
+		/// The ID of the instance.
+		instance: InstanceId,
+		/// The sound or arrangement the instance would have played.
+		playable: PlayableId,
+	},
+	/// An instance was removed to make room for a new instance
+	/// because the instance limit was reached.
+	InstanceStolen {
+		/// The ID of the instance that was removed.
+		instance: InstanceId,
+		/// The ID of the instance that took its place.
+		by: InstanceId,
+	},
+	/// The playback state of an instance changed.
+	InstanceStateChanged {
+		/// The ID of the instance.
+		instance: InstanceId,
+		/// The new state of the instance.
+		state: InstanceState,
+	},
+	/// A sequence instance started a step.
+	SequenceStep {
+		/// The ID of the sequence instance.
+		sequence: SequenceInstanceId,
+		/// The index of the step in the sequence.
+		step: usize,
+	},
+	/// A sequence instance finished and was removed.
+	SequenceFinished {
+		/// The ID of the sequence instance.
+		sequence: SequenceInstanceId,
+	},
+	/// Some events weren't recorded because the main thread
+	/// was reading the trace at the time.
+	EventsMissed(u64),
+}
+
+/// An entry in the audio thread's trace.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TraceEvent {
+	/// The time on the audio clock when the event
+	/// happened (in seconds).
+	pub time: f64,
+	/// What happened.
+	pub kind: TraceEventKind,
+}
+
+/// The trace events shared between the audio thread
+/// and the main thread.
+pub(crate) type SharedTrace = Arc<Mutex<VecDeque<TraceEvent>>>;
+
+/// Records trace events on the audio thread.
+pub(crate) struct Tracer {
+	events: Option<SharedTrace>,
+	capacity: usize,
+	time: f64,
+	missed_events: u64,
+}
+
+impl Tracer {
+	/// Creates a tracer and the trace it writes to. If `capacity`
+	/// is `None` or zero, tracing is disabled.
+	pub fn new(capacity: Option<usize>) -> (Self, Option<SharedTrace>) {
+		let capacity = capacity.unwrap_or(0);
+		let events = if capacity > 0 {
+			Some(Arc::new(Mutex::new(VecDeque::with_capacity(capacity))))
+		} else {
+			None
+		};
+		(
+			Self {
+				events: events.clone(),
+				capacity,
+				time: 0.0,
+				missed_events: 0,
+			},
+			events,
+		)
+	}
+
+	/// Sets the time on the audio clock that new
+	/// events will be recorded with.
+	pub fn set_time(&mut self, time: f64) {
+		self.time = time;
+	}
+
+	/// Records an event.
+	pub fn record(&mut self, kind: TraceEventKind) {
+		let events = match &self.events {
+			Some(events) => events,
+			None => return,
+		};
+		// the main thread only holds the lock while copying the
+		// events out, so rather than waiting for it, we skip
+		// the event and report how many were missed later
+		let mut events = match events.try_lock() {
+			Ok(events) => events,
+			Err(_) => {
+				self.missed_events += 1;
+				return;
+			}
+		};
+		if self.missed_events > 0 {
+			push_event(
+				&mut events,
+				self.capacity,
+				TraceEvent {
+					time: self.time,
+					kind: TraceEventKind::EventsMissed(self.missed_events),
+				},
+			);
+			self.missed_events = 0;
+		}
+		push_event(
+			&mut events,
+			self.capacity,
+			TraceEvent {
+				time: self.time,
+				kind,
+			},
+		);
+	}
+}
+
+/// Adds an event to the trace, discarding the oldest event
+/// if the trace is full.
+fn push_event(events: &mut VecDeque<TraceEvent>, capacity: usize, event: TraceEvent) {
+	if events.len() >= capacity {
+		events.pop_front();
+	}
+	events.push_back(event);
+}
@@ -10,10 +10,11 @@ use crate::{
 		producer::{CommandError, CommandProducer},
 		MetronomeCommand,
 	},
+	instance::InstanceId,
 	Tempo, Value,
 };
 
-use super::MetronomeId;
+use super::{MetronomeEvent, MetronomeId, MetronomePositionReport};
 
 /// Something that can go wrong when using a [`MetronomeHandle`]
 /// to receive an event from a metronome.
@@ -30,19 +31,22 @@ pub enum PopMetronomeEventError {
 pub struct MetronomeHandle {
 	id: MetronomeId,
 	command_producer: CommandProducer,
-	event_consumer: Arc<Mutex<Consumer<f64>>>,
+	event_consumer: Arc<Mutex<Consumer<MetronomeEvent>>>,
+	position_report_consumer: Arc<Mutex<Consumer<MetronomePositionReport>>>,
 }
 
 impl MetronomeHandle {
 	pub(crate) fn new(
 		id: MetronomeId,
 		command_producer: CommandProducer,
-		event_consumer: Consumer<f64>,
+		event_consumer: Consumer<MetronomeEvent>,
+		position_report_consumer: Consumer<MetronomePositionReport>,
 	) -> Self {
 		Self {
 			id,
 			command_producer,
 			event_consumer: Arc::new(Mutex::new(event_consumer)),
+			position_report_consumer: Arc::new(Mutex::new(position_report_consumer)),
 		}
 	}
 
@@ -75,15 +79,61 @@ impl MetronomeHandle {
 			.push(MetronomeCommand::StopMetronome(self.id()).into())
 	}
 
+	/// Makes the metronome derive its time from the playback
+	/// position of an instance.
+	///
+	/// While synced, the metronome will only tick when the
+	/// instance is playing. Pausing or stopping the metronome
+	/// keeps it from ticking until it's started again, even
+	/// if the instance is playing.
+	///
+	/// If the instance hasn't been started yet, the metronome
+	/// waits for it. Once the instance is removed, the metronome
+	/// stops following it and keeps ticking on its own from the
+	/// last beat it reached, unless it was paused or stopped.
+	pub fn sync_to_instance(
+		&mut self,
+		instance: impl Into<InstanceId>,
+	) -> Result<(), CommandError> {
+		self.command_producer.push(
+			MetronomeCommand::SetMetronomeSyncInstance(self.id(), Some(instance.into())).into(),
+		)
+	}
+
+	/// Stops deriving the metronome's time from an instance.
+	///
+	/// The metronome will keep its current time and continue
+	/// ticking on its own, unless it was paused or stopped.
+	pub fn unsync(&mut self) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MetronomeCommand::SetMetronomeSyncInstance(self.id(), None).into())
+	}
+
 	/// Gets the first interval event that was emitted by this
 	/// metronome since the last call to `pop_event`.
-	pub fn pop_event(&mut self) -> Result<Option<f64>, PopMetronomeEventError> {
+	pub fn pop_event(&mut self) -> Result<Option<MetronomeEvent>, PopMetronomeEventError> {
 		Ok(self
 			.event_consumer
 			.lock()
 			.map_err(|_| PopMetronomeEventError::MutexPoisoned)?
 			.pop())
 	}
+
+	/// Gets the first position report that was sent by this
+	/// metronome since the last call to `pop_position_report`.
+	///
+	/// Position reports are only sent if the metronome was
+	/// created with a
+	/// [`position_report_interval`](super::MetronomeSettings::position_report_interval).
+	pub fn pop_position_report(
+		&mut self,
+	) -> Result<Option<MetronomePositionReport>, PopMetronomeEventError> {
+		Ok(self
+			.position_report_consumer
+			.lock()
+			.map_err(|_| PopMetronomeEventError::MutexPoisoned)?
+			.pop())
+	}
 }
 
 impl std::fmt::Debug for MetronomeHandle {
@@ -95,6 +145,7 @@ impl std::fmt::Debug for MetronomeHandle {
 			.field("id", &self.id)
 			.field("command_producer", &self.command_producer)
 			.field("event_consumer", &EventConsumer)
+			.field("position_report_consumer", &EventConsumer)
 			.finish()
 	}
 }
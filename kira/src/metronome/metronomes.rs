@@ -2,6 +2,11 @@ use basedrop::Owned;
 
 use crate::{
 	command::MetronomeCommand,
+	manager::{
+		backend::{AudioClock, Instances},
+		resources::{RemovedResource, RemovedResourceProducer},
+		snapshot::{MetronomeSnapshot, SnapshotRequest},
+	},
 	metronome::{Metronome, MetronomeId},
 	parameter::Parameters,
 	static_container::index_map::StaticIndexMap,
@@ -22,19 +27,43 @@ impl Metronomes {
 		self.metronomes.get(&id)
 	}
 
-	pub fn run_command(&mut self, command: MetronomeCommand) {
+	pub fn snapshot(&self, request: &mut SnapshotRequest) {
+		for (id, metronome) in &self.metronomes {
+			request.add_metronome(MetronomeSnapshot {
+				id: *id,
+				time: metronome.time(),
+				ticking: metronome.ticking(),
+			});
+		}
+	}
+
+	pub fn run_command(
+		&mut self,
+		command: MetronomeCommand,
+		removed_resources: &mut RemovedResourceProducer,
+	) {
 		match command {
 			MetronomeCommand::AddMetronome(id, metronome) => {
 				self.metronomes.try_insert(id, metronome).ok();
 			}
 			MetronomeCommand::RemoveMetronome(id) => {
-				self.metronomes.remove(&id);
+				if let Some(metronome) = self.metronomes.remove(&id) {
+					removed_resources.push(RemovedResource::Metronome {
+						id,
+						_metronome: metronome,
+					});
+				}
 			}
 			MetronomeCommand::SetMetronomeTempo(id, tempo) => {
 				if let Some(metronome) = self.metronomes.get_mut(&id) {
 					metronome.set_tempo(tempo);
 				}
 			}
+			MetronomeCommand::SetMetronomeTime(id, time) => {
+				if let Some(metronome) = self.metronomes.get_mut(&id) {
+					metronome.set_time(time);
+				}
+			}
 			MetronomeCommand::StartMetronome(id) => {
 				if let Some(metronome) = self.metronomes.get_mut(&id) {
 					metronome.start();
@@ -50,12 +79,23 @@ impl Metronomes {
 					metronome.stop();
 				}
 			}
+			MetronomeCommand::SetMetronomeSyncInstance(id, instance) => {
+				if let Some(metronome) = self.metronomes.get_mut(&id) {
+					metronome.set_sync_instance(instance);
+				}
+			}
 		}
 	}
 
-	pub fn update(&mut self, dt: f64, parameters: &Parameters) {
+	pub fn update(
+		&mut self,
+		dt: f64,
+		parameters: &Parameters,
+		instances: &Instances,
+		clock: AudioClock,
+	) {
 		for (_, metronome) in &mut self.metronomes {
-			metronome.update(dt, parameters);
+			metronome.update(dt, parameters, instances, clock);
 		}
 	}
 }
@@ -6,11 +6,20 @@
 pub mod handle;
 mod metronomes;
 mod settings;
+#[cfg(test)]
+mod tests;
 
 use ringbuf::Producer;
 use uuid::Uuid;
 
-use crate::{parameter::Parameters, tempo::Tempo, value::CachedValue, Value};
+use crate::{
+	instance::InstanceId,
+	manager::backend::{AudioClock, Instances},
+	parameter::Parameters,
+	tempo::Tempo,
+	value::CachedValue,
+	Value,
+};
 use handle::MetronomeHandle;
 pub(crate) use metronomes::Metronomes;
 pub use settings::MetronomeSettings;
@@ -40,24 +49,92 @@ impl From<&MetronomeHandle> for MetronomeId {
 	}
 }
 
+/// An event emitted when a metronome passes an interval.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MetronomeEvent {
+	/// The interval (in beats) that was passed.
+	pub interval: f64,
+	/// The index of the frame within the audio buffer
+	/// at which the interval was passed.
+	pub buffer_offset: usize,
+	/// The time on the audio clock (in seconds) at which
+	/// the interval was passed.
+	///
+	/// This can be compared with
+	/// [`AudioManager::audio_time`](crate::manager::AudioManager::audio_time)
+	/// to find out how long ago the interval was passed.
+	pub time: f64,
+}
+
+/// The position and tempo of a metronome, as reported
+/// to the main thread.
+///
+/// Position reports can be used to drive things on the main
+/// thread that should follow the music, like cutscene timelines
+/// or shader effects.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MetronomePositionReport {
+	/// How many beats the metronome has ticked.
+	pub beats: f64,
+	/// The bar the metronome is in (starting from 0).
+	pub bar: u64,
+	/// The position of the metronome within the current
+	/// bar (in beats).
+	pub beat_in_bar: f64,
+	/// The tempo of the metronome.
+	pub tempo: Tempo,
+	/// Whether the metronome is ticking.
+	pub ticking: bool,
+	/// The index of the frame within the audio buffer
+	/// at which the position was reported.
+	pub buffer_offset: usize,
+	/// The time on the audio clock (in seconds) at which
+	/// the position was reported.
+	pub time: f64,
+}
+
 pub(crate) struct Metronome {
 	tempo: CachedValue<Tempo>,
 	interval_events_to_emit: Vec<f64>,
 	ticking: bool,
+	// whether the metronome was paused or stopped and hasn't
+	// been started since. synced metronomes tick whenever their
+	// instance plays, so this is kept apart from `ticking`
+	paused: bool,
 	time: f64,
 	previous_time: f64,
-	event_producer: Producer<f64>,
+	sync_instance: Option<InstanceId>,
+	// whether the synced instance has existed yet. instance IDs
+	// can be handed out before the instance is started, so a
+	// missing instance only means it's gone if it's been seen
+	sync_instance_seen: bool,
+	event_producer: Producer<MetronomeEvent>,
+	beats_per_bar: f64,
+	position_report_interval: Option<f64>,
+	position_report_timer: f64,
+	position_report_producer: Producer<MetronomePositionReport>,
 }
 
 impl Metronome {
-	pub fn new(settings: MetronomeSettings, event_producer: Producer<f64>) -> Self {
+	pub fn new(
+		settings: MetronomeSettings,
+		event_producer: Producer<MetronomeEvent>,
+		position_report_producer: Producer<MetronomePositionReport>,
+	) -> Self {
 		Self {
 			tempo: CachedValue::new(settings.tempo, Tempo(120.0)).with_min(Tempo(0.0)),
 			interval_events_to_emit: settings.interval_events_to_emit,
 			ticking: false,
+			paused: false,
 			time: 0.0,
 			previous_time: 0.0,
+			sync_instance: settings.sync_instance,
+			sync_instance_seen: false,
 			event_producer,
+			beats_per_bar: settings.beats_per_bar,
+			position_report_interval: settings.position_report_interval,
+			position_report_timer: 0.0,
+			position_report_producer,
 		}
 	}
 
@@ -69,35 +146,139 @@ impl Metronome {
 		}
 	}
 
+	/// Returns the tempo of the metronome, even if it
+	/// isn't ticking.
+	pub fn tempo(&self) -> Tempo {
+		self.tempo.value()
+	}
+
+	/// Returns how many beats the metronome has ticked.
+	pub fn time(&self) -> f64 {
+		self.time
+	}
+
 	pub fn set_tempo(&mut self, tempo: Value<Tempo>) {
 		self.tempo.set(tempo);
 	}
 
+	pub fn ticking(&self) -> bool {
+		self.ticking
+	}
+
+	/// Jumps to a time (in beats) without passing
+	/// any intervals.
+	pub fn set_time(&mut self, time: f64) {
+		self.time = time;
+		self.previous_time = time;
+	}
+
 	pub fn start(&mut self) {
+		self.paused = false;
 		self.ticking = true;
 	}
 
 	pub fn pause(&mut self) {
+		self.paused = true;
 		self.ticking = false;
 	}
 
 	pub fn stop(&mut self) {
+		self.paused = true;
 		self.ticking = false;
 		self.time = 0.0;
 		self.previous_time = 0.0;
 	}
 
-	pub fn update(&mut self, dt: f64, parameters: &Parameters) {
+	pub fn set_sync_instance(&mut self, instance: Option<InstanceId>) {
+		if self.sync_instance.is_some() && instance.is_none() {
+			self.ticking = !self.paused;
+		}
+		self.sync_instance = instance;
+		self.sync_instance_seen = false;
+	}
+
+	pub fn update(
+		&mut self,
+		dt: f64,
+		parameters: &Parameters,
+		instances: &Instances,
+		clock: AudioClock,
+	) {
 		self.tempo.update(parameters);
-		if self.ticking {
+		if let Some(id) = self.sync_instance {
+			// while synced to an instance, the metronome only ticks
+			// when the instance is playing and the metronome isn't
+			// paused, and its time is derived from the instance's
+			// playback position
+			match instances.get(id) {
+				Some(instance) if instance.playing() => {
+					self.sync_instance_seen = true;
+					self.ticking = !self.paused;
+					if self.ticking {
+						self.previous_time = self.time;
+						self.time = (self.tempo.value().0 / 60.0) * instance.position();
+					}
+				}
+				Some(_) => {
+					self.sync_instance_seen = true;
+					self.ticking = false;
+				}
+				// the instance hasn't been started yet
+				None if !self.sync_instance_seen => {
+					self.ticking = false;
+				}
+				// once the instance is gone, there's nothing left to
+				// follow, so the metronome carries on by itself
+				// instead of staying frozen
+				None => {
+					self.sync_instance = None;
+					self.ticking = !self.paused;
+				}
+			}
+		}
+		if self.sync_instance.is_none() && self.ticking {
 			self.previous_time = self.time;
 			self.time += (self.tempo.value().0 / 60.0) * dt;
+		}
+		if self.ticking {
 			for interval in &self.interval_events_to_emit {
 				if self.interval_passed(*interval) {
-					self.event_producer.push(*interval).ok();
+					self.event_producer
+						.push(MetronomeEvent {
+							interval: *interval,
+							buffer_offset: clock.buffer_offset,
+							time: clock.time,
+						})
+						.ok();
 				}
 			}
 		}
+		self.report_position(dt, clock);
+	}
+
+	fn report_position(&mut self, dt: f64, clock: AudioClock) {
+		let position_report_interval = match self.position_report_interval {
+			Some(interval) => interval,
+			None => return,
+		};
+		self.position_report_timer -= dt;
+		if self.position_report_timer > 0.0 {
+			return;
+		}
+		self.position_report_timer =
+			(self.position_report_timer + position_report_interval).max(0.0);
+		let bar = (self.time / self.beats_per_bar).floor();
+		self.position_report_producer
+			.push(MetronomePositionReport {
+				beats: self.time,
+				bar: bar as u64,
+				beat_in_bar: self.time - bar * self.beats_per_bar,
+				tempo: self.effective_tempo(),
+				ticking: self.ticking,
+				buffer_offset: clock.buffer_offset,
+				time: clock.time,
+			})
+			.ok();
 	}
 
 	pub fn interval_passed(&self, interval: f64) -> bool {
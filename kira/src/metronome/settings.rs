@@ -1,4 +1,4 @@
-use crate::{Tempo, Value};
+use crate::{instance::InstanceId, Tempo, Value};
 
 use super::MetronomeId;
 
@@ -20,8 +20,30 @@ pub struct MetronomeSettings {
 	/// the audio manager will receive `MetronomeIntervalPassed` events
 	/// every quarter of a beat, half of a beat, and beat.
 	pub interval_events_to_emit: Vec<f64>,
-	/// How many interval events can be queued at a time.
+	/// How many interval events and position reports can be
+	/// queued at a time.
 	pub event_queue_capacity: usize,
+	/// An instance the metronome should derive its time from.
+	///
+	/// If set, the metronome's time will be calculated from
+	/// the playback position of the instance (using the
+	/// metronome's tempo), so the metronome stays locked to
+	/// the music even if the instance is seeked or paused.
+	/// If the instance is removed, the metronome goes back
+	/// to ticking on its own.
+	pub sync_instance: Option<InstanceId>,
+	/// How many beats make up a bar.
+	///
+	/// This is used to calculate the bar and beat
+	/// in [position reports](super::MetronomePositionReport).
+	pub beats_per_bar: f64,
+	/// How often (in seconds) the metronome should report
+	/// its position to the main thread, or `None` if it
+	/// shouldn't report its position.
+	///
+	/// If this is `Some(0.0)`, the position will be reported
+	/// every frame.
+	pub position_report_interval: Option<f64>,
 }
 
 impl MetronomeSettings {
@@ -54,13 +76,39 @@ impl MetronomeSettings {
 		}
 	}
 
-	/// Sets how many interval events can be queued at a time.
+	/// Sets how many interval events and position reports can be
+	/// queued at a time.
 	pub fn event_queue_capacity(self, event_queue_capacity: usize) -> Self {
 		Self {
 			event_queue_capacity,
 			..self
 		}
 	}
+
+	/// Sets how many beats make up a bar.
+	pub fn beats_per_bar(self, beats_per_bar: f64) -> Self {
+		Self {
+			beats_per_bar,
+			..self
+		}
+	}
+
+	/// Sets how often (in seconds) the metronome should report
+	/// its position to the main thread.
+	pub fn position_report_interval(self, position_report_interval: f64) -> Self {
+		Self {
+			position_report_interval: Some(position_report_interval),
+			..self
+		}
+	}
+
+	/// Sets the instance the metronome should derive its time from.
+	pub fn sync_to_instance(self, instance: impl Into<InstanceId>) -> Self {
+		Self {
+			sync_instance: Some(instance.into()),
+			..self
+		}
+	}
 }
 
 impl Default for MetronomeSettings {
@@ -70,6 +118,9 @@ impl Default for MetronomeSettings {
 			tempo: Tempo(120.0).into(),
 			interval_events_to_emit: vec![],
 			event_queue_capacity: 10,
+			sync_instance: None,
+			beats_per_bar: 4.0,
+			position_report_interval: None,
 		}
 	}
 }
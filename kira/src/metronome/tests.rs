@@ -0,0 +1,182 @@
+use crate::{
+	instance::{InstanceId, InstanceSettings},
+	manager::Backend,
+	metronome::{MetronomeHandle, MetronomePositionReport, MetronomeSettings},
+	testing::{constant_sound, create_manager},
+	Tempo,
+};
+
+/// Processes the given number of frames and returns the last
+/// position report the metronome sent.
+fn last_position_report(
+	backend: &mut Backend,
+	metronome: &mut MetronomeHandle,
+	frames: usize,
+) -> MetronomePositionReport {
+	let mut last_report = None;
+	for _ in 0..frames {
+		backend.process();
+		while let Some(report) = metronome.pop_position_report().unwrap() {
+			last_report = Some(report);
+		}
+	}
+	last_report.unwrap()
+}
+
+#[test]
+fn metronome_events_report_buffer_offset_and_time() {
+	const BUFFER_SIZE: usize = 512;
+	let (mut manager, mut backend) = create_manager();
+	let mut metronome = manager
+		.add_metronome(
+			MetronomeSettings::new()
+				.tempo(Tempo(120.0))
+				.interval_events_to_emit([1.0]),
+		)
+		.unwrap();
+	metronome.start().unwrap();
+	let mut events = vec![];
+	for frame in 0..30000 {
+		if frame % BUFFER_SIZE == 0 {
+			backend.start_buffer();
+		}
+		backend.process();
+		while let Some(event) = metronome.pop_event().unwrap() {
+			events.push((frame, event));
+		}
+	}
+	assert_eq!(events.len(), 2);
+	for (frame, event) in events {
+		assert_eq!(event.buffer_offset, frame % BUFFER_SIZE);
+		assert_eq!(event.time, frame as f64 / 48000.0);
+	}
+}
+
+#[test]
+fn metronomes_report_their_position() {
+	let (mut manager, mut backend) = create_manager();
+	let mut metronome = manager
+		.add_metronome(
+			MetronomeSettings::new()
+				.tempo(Tempo(120.0))
+				.beats_per_bar(4.0)
+				.position_report_interval(1.0),
+		)
+		.unwrap();
+	metronome.start().unwrap();
+	let mut reports = vec![];
+	// run for 2.5 seconds, which is 5 beats at 120 bpm
+	for _ in 0..120000 {
+		backend.process();
+		while let Some(report) = metronome.pop_position_report().unwrap() {
+			reports.push(report);
+		}
+	}
+	assert_eq!(reports.len(), 3);
+	let report = reports[2];
+	assert!((report.time - 2.0).abs() < 1e-3);
+	assert!((report.beats - 4.0).abs() < 1e-3);
+	assert_eq!(report.bar, 1);
+	assert!(report.beat_in_bar < 1e-3);
+	assert_eq!(report.tempo, Tempo(120.0));
+	assert!(report.ticking);
+}
+
+#[test]
+fn synced_metronomes_follow_instances_across_seeks() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut instance = sound.play(InstanceSettings::default()).unwrap();
+	// 240 bpm is 4 beats per second, so the one second
+	// long sound lasts 4 beats
+	let mut metronome = manager
+		.add_metronome(
+			MetronomeSettings::new()
+				.tempo(Tempo(240.0))
+				.position_report_interval(0.0)
+				.sync_to_instance(instance.id()),
+		)
+		.unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 12000);
+	assert!((report.beats - 1.0).abs() < 1e-3);
+	assert!(report.ticking);
+	instance.seek_to(0.5).unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 1);
+	assert!((report.beats - 2.0).abs() < 1e-3);
+	instance.seek_to(0.25).unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 12000);
+	assert!((report.beats - 2.0).abs() < 1e-3);
+	// once the instance finishes and is removed, the metronome
+	// keeps ticking on its own from where the instance ended
+	let report = last_position_report(&mut backend, &mut metronome, 48000);
+	assert!((report.beats - 6.0).abs() < 1e-2);
+	assert!(report.ticking);
+}
+
+#[test]
+fn synced_metronomes_wait_for_instances_that_start_later() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	// the ID is known before the instance exists, like the IDs
+	// of instances started later by a sequence
+	let instance_id = InstanceId::new();
+	let mut metronome = manager
+		.add_metronome(
+			MetronomeSettings::new()
+				.tempo(Tempo(240.0))
+				.position_report_interval(0.0)
+				.sync_to_instance(instance_id),
+		)
+		.unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 12000);
+	assert_eq!(report.beats, 0.0);
+	assert!(!report.ticking);
+	sound.play(InstanceSettings::new().id(instance_id)).unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 24000);
+	assert!((report.beats - 2.0).abs() < 1e-3);
+	assert!(report.ticking);
+}
+
+#[test]
+fn synced_metronomes_can_be_paused_and_unsynced() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut instance = sound.play(InstanceSettings::default()).unwrap();
+	let mut metronome = manager
+		.add_metronome(
+			MetronomeSettings::new()
+				.tempo(Tempo(240.0))
+				.position_report_interval(0.0)
+				.sync_to_instance(instance.id()),
+		)
+		.unwrap();
+	last_position_report(&mut backend, &mut metronome, 12000);
+	// pausing the metronome stops it from ticking even though
+	// the instance is still playing
+	metronome.pause().unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 4800);
+	assert!(!report.ticking);
+	let paused_beats = report.beats;
+	let report = last_position_report(&mut backend, &mut metronome, 4800);
+	assert_eq!(report.beats, paused_beats);
+	metronome.start().unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 1);
+	assert!(report.ticking);
+	assert!(report.beats > paused_beats);
+	// unsyncing from a paused instance lets the metronome
+	// tick on its own
+	instance.pause(Default::default()).unwrap();
+	let report = last_position_report(&mut backend, &mut metronome, 4800);
+	assert!(!report.ticking);
+	metronome.unsync().unwrap();
+	let unsynced_beats = report.beats;
+	let report = last_position_report(&mut backend, &mut metronome, 12000);
+	assert!(report.ticking);
+	assert!((report.beats - unsynced_beats - 1.0).abs() < 1e-2);
+}
@@ -0,0 +1,343 @@
+//! Controls parameters with MIDI hardware.
+//!
+//! During playtests, it can be handy to mix the game live using
+//! the knobs and faders of a MIDI control surface. With the `midi`
+//! feature enabled, an audio manager can listen to a MIDI input
+//! device and set parameters whenever a control change (CC)
+//! message is received:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	midi::{CcMapping, MidiInputSettings},
+//! # 	parameter::{Mapping, ParameterSettings},
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let music_volume = audio_manager.add_parameter(ParameterSettings::new().value(1.0))?;
+//! let reverb_mix = audio_manager.add_parameter(ParameterSettings::new())?;
+//! let mut midi_input = audio_manager.open_midi_input(MidiInputSettings::new())?;
+//! // the first fader controls the music volume
+//! midi_input.map_cc(CcMapping::new(0, &music_volume))?;
+//! // the first knob controls the reverb mix, up to 50%
+//! midi_input.map_cc(CcMapping::new(16, &reverb_mix).mapping(Mapping {
+//! 	output_range: (0.0, 0.5),
+//! 	..Default::default()
+//! }))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Mappings can be changed at any time while the device is open.
+//! The device is closed when the [`MidiInputHandle`] is dropped.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::{Arc, Mutex};
+
+use midir::{ConnectErrorKind, InitError, MidiInput, MidiInputConnection, PortInfoError};
+use thiserror::Error;
+
+use crate::{
+	command::{producer::CommandProducer, ParameterCommand},
+	parameter::{tween::Tween, Mapping, ParameterId},
+	StartTime,
+};
+
+const CLIENT_NAME: &str = "kira";
+const CONTROL_CHANGE: u8 = 0xB0;
+const MAX_CC_VALUE: f64 = 127.0;
+
+/// Something that can go wrong when opening a MIDI input device.
+#[derive(Debug, Error)]
+pub enum OpenMidiInputError {
+	/// MIDI support could not be initialized.
+	#[error("{0}")]
+	InitError(#[from] InitError),
+	/// There are no MIDI input devices.
+	#[error("No MIDI input devices are available")]
+	NoInputDevice,
+	/// No MIDI input device has a name containing the given text.
+	#[error("No MIDI input device has a name containing \"{0}\"")]
+	NoInputDeviceWithName(String),
+	/// The name of a MIDI input device could not be retrieved.
+	#[error("{0}")]
+	PortInfoError(#[from] PortInfoError),
+	/// The MIDI input device could not be opened.
+	#[error("{0}")]
+	ConnectError(ConnectErrorKind),
+}
+
+/// Something that can go wrong when changing the
+/// mappings of a MIDI input device.
+#[derive(Debug, Error)]
+pub enum MapCcError {
+	/// A thread panicked while using the mappings.
+	#[error("The mappings cannot be used because a thread panicked while borrowing them.")]
+	MutexPoisoned,
+}
+
+/// Settings for opening a MIDI input device.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct MidiInputSettings {
+	/// Text the name of the device should contain.
+	///
+	/// If this is `None`, the first available device is used.
+	pub device_name: Option<String>,
+	/// The control change mappings to start with.
+	pub mappings: Vec<CcMapping>,
+}
+
+impl MidiInputSettings {
+	/// Creates a new `MidiInputSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets text the name of the device should contain.
+	pub fn device_name(self, device_name: impl Into<String>) -> Self {
+		Self {
+			device_name: Some(device_name.into()),
+			..self
+		}
+	}
+
+	/// Sets the control change mappings to start with.
+	pub fn mappings(self, mappings: impl Into<Vec<CcMapping>>) -> Self {
+		Self {
+			mappings: mappings.into(),
+			..self
+		}
+	}
+}
+
+/// Connects a MIDI control change (CC) number to a parameter.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct CcMapping {
+	/// The controller number (0-127).
+	pub controller: u8,
+	/// The MIDI channel (0-15) to listen to, or `None`
+	/// to listen to every channel.
+	pub channel: Option<u8>,
+	/// The parameter to set.
+	pub parameter: ParameterId,
+	/// How to turn the controller value into a parameter value.
+	///
+	/// The input of the mapping is the controller value
+	/// divided by 127, so it ranges from 0 to 1.
+	pub mapping: Mapping,
+	/// The tween used to smooth out changes to the parameter.
+	///
+	/// Controller values are coarse, so without smoothing,
+	/// moving a fader can cause audible steps.
+	pub tween: Option<Tween>,
+}
+
+impl CcMapping {
+	/// Creates a mapping from a controller number on any channel
+	/// to a parameter, with an output range of 0 to 1.
+	pub fn new(controller: u8, parameter: impl Into<ParameterId>) -> Self {
+		Self {
+			controller,
+			channel: None,
+			parameter: parameter.into(),
+			mapping: Mapping::default(),
+			tween: Some(Tween::linear(0.02)),
+		}
+	}
+
+	/// Sets the MIDI channel (0-15) to listen to.
+	pub fn channel(self, channel: u8) -> Self {
+		Self {
+			channel: Some(channel),
+			..self
+		}
+	}
+
+	/// Sets how to turn the controller value into a parameter value.
+	pub fn mapping(self, mapping: Mapping) -> Self {
+		Self { mapping, ..self }
+	}
+
+	/// Sets the tween used to smooth out changes to the parameter.
+	pub fn tween(self, tween: impl Into<Option<Tween>>) -> Self {
+		Self {
+			tween: tween.into(),
+			..self
+		}
+	}
+
+	fn matches(&self, channel: u8, controller: u8) -> bool {
+		self.controller == controller && self.channel.map_or(true, |c| c == channel)
+	}
+
+	/// Returns the parameter value for a controller value.
+	fn parameter_value(&self, cc_value: u8) -> f64 {
+		self.mapping.map(cc_value as f64 / MAX_CC_VALUE)
+	}
+}
+
+/// Returns the names of the available MIDI input devices.
+pub fn midi_input_device_names() -> Result<Vec<String>, OpenMidiInputError> {
+	let midi_input = MidiInput::new(CLIENT_NAME)?;
+	let mut names = vec![];
+	for port in midi_input.ports() {
+		names.push(midi_input.port_name(&port)?);
+	}
+	Ok(names)
+}
+
+/// Reads the channel, controller number, and value
+/// of a control change message.
+fn parse_control_change(message: &[u8]) -> Option<(u8, u8, u8)> {
+	match message {
+		[status, controller, value] if status & 0xF0 == CONTROL_CHANGE => {
+			Some((status & 0x0F, *controller, *value))
+		}
+		_ => None,
+	}
+}
+
+pub(crate) fn open_midi_input(
+	settings: MidiInputSettings,
+	mut command_producer: CommandProducer,
+) -> Result<MidiInputHandle, OpenMidiInputError> {
+	let midi_input = MidiInput::new(CLIENT_NAME)?;
+	let mut chosen_port = None;
+	for port in midi_input.ports() {
+		let name = midi_input.port_name(&port)?;
+		let matches = match &settings.device_name {
+			Some(device_name) => name.contains(device_name.as_str()),
+			None => true,
+		};
+		if matches {
+			chosen_port = Some((port, name));
+			break;
+		}
+	}
+	let (port, device_name) = match (chosen_port, settings.device_name) {
+		(Some(port), _) => port,
+		(None, Some(device_name)) => {
+			return Err(OpenMidiInputError::NoInputDeviceWithName(device_name))
+		}
+		(None, None) => return Err(OpenMidiInputError::NoInputDevice),
+	};
+	let mappings = Arc::new(Mutex::new(settings.mappings));
+	let callback_mappings = mappings.clone();
+	let connection = midi_input
+		.connect(
+			&port,
+			CLIENT_NAME,
+			move |_, message, _| {
+				let (channel, controller, value) = match parse_control_change(message) {
+					Some(control_change) => control_change,
+					None => return,
+				};
+				let mappings = match callback_mappings.lock() {
+					Ok(mappings) => mappings,
+					Err(_) => return,
+				};
+				for mapping in mappings
+					.iter()
+					.filter(|mapping| mapping.matches(channel, controller))
+				{
+					let value = mapping.parameter_value(value);
+					command_producer
+						.push(
+							ParameterCommand::SetParameter(
+								mapping.parameter,
+								value,
+								mapping.tween,
+								StartTime::Immediate,
+							)
+							.into(),
+						)
+						.ok();
+				}
+			},
+			(),
+		)
+		.map_err(|error| OpenMidiInputError::ConnectError(error.kind()))?;
+	Ok(MidiInputHandle {
+		_connection: connection,
+		device_name,
+		mappings,
+	})
+}
+
+/// Allows you to change how an open MIDI input device
+/// controls parameters.
+///
+/// When the handle is dropped, the device is closed.
+pub struct MidiInputHandle {
+	_connection: MidiInputConnection<()>,
+	device_name: String,
+	mappings: Arc<Mutex<Vec<CcMapping>>>,
+}
+
+impl MidiInputHandle {
+	/// Returns the name of the MIDI input device.
+	pub fn device_name(&self) -> &str {
+		&self.device_name
+	}
+
+	/// Returns the current control change mappings.
+	pub fn mappings(&self) -> Result<Vec<CcMapping>, MapCcError> {
+		Ok(self
+			.mappings
+			.lock()
+			.map_err(|_| MapCcError::MutexPoisoned)?
+			.clone())
+	}
+
+	/// Adds a control change mapping, replacing any mapping
+	/// for the same controller, channel, and parameter.
+	pub fn map_cc(&mut self, mapping: CcMapping) -> Result<(), MapCcError> {
+		let mut mappings = self
+			.mappings
+			.lock()
+			.map_err(|_| MapCcError::MutexPoisoned)?;
+		mappings.retain(|existing| {
+			!(existing.controller == mapping.controller
+				&& existing.channel == mapping.channel
+				&& existing.parameter == mapping.parameter)
+		});
+		mappings.push(mapping);
+		Ok(())
+	}
+
+	/// Removes every mapping for a controller number.
+	pub fn unmap_cc(&mut self, controller: u8) -> Result<(), MapCcError> {
+		self.mappings
+			.lock()
+			.map_err(|_| MapCcError::MutexPoisoned)?
+			.retain(|mapping| mapping.controller != controller);
+		Ok(())
+	}
+
+	/// Removes every mapping.
+	pub fn clear_mappings(&mut self) -> Result<(), MapCcError> {
+		self.mappings
+			.lock()
+			.map_err(|_| MapCcError::MutexPoisoned)?
+			.clear();
+		Ok(())
+	}
+}
+
+impl std::fmt::Debug for MidiInputHandle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("MidiInputHandle")
+			.field("device_name", &self.device_name)
+			.field("mappings", &self.mappings)
+			.finish()
+	}
+}
@@ -0,0 +1,44 @@
+use crate::parameter::{Mapping, ParameterId};
+
+use super::{parse_control_change, CcMapping};
+
+#[test]
+fn parses_control_change_messages() {
+	// control change on channel 1, controller 7, value 100
+	assert_eq!(parse_control_change(&[0xB0, 7, 100]), Some((0, 7, 100)));
+	// the low nibble of the status byte is the channel
+	assert_eq!(parse_control_change(&[0xBF, 7, 100]), Some((15, 7, 100)));
+	// note on and pitch bend messages are ignored
+	assert_eq!(parse_control_change(&[0x90, 60, 127]), None);
+	assert_eq!(parse_control_change(&[0xE0, 0, 64]), None);
+	// so are truncated and oversized messages
+	assert_eq!(parse_control_change(&[0xB0, 7]), None);
+	assert_eq!(parse_control_change(&[0xB0, 7, 100, 0]), None);
+}
+
+#[test]
+fn filters_control_changes_by_controller_and_channel() {
+	let any_channel = CcMapping::new(16, ParameterId::new());
+	assert!(any_channel.matches(0, 16));
+	assert!(any_channel.matches(9, 16));
+	assert!(!any_channel.matches(0, 17));
+	let one_channel = any_channel.channel(9);
+	assert!(one_channel.matches(9, 16));
+	assert!(!one_channel.matches(0, 16));
+	assert!(!one_channel.matches(9, 17));
+}
+
+#[test]
+fn maps_controller_values_to_the_output_range() {
+	let default_range = CcMapping::new(0, ParameterId::new());
+	assert_eq!(default_range.parameter_value(0), 0.0);
+	assert_eq!(default_range.parameter_value(127), 1.0);
+	let custom_range = default_range.mapping(Mapping {
+		output_range: (-1.0, 0.5),
+		..Default::default()
+	});
+	assert_eq!(custom_range.parameter_value(0), -1.0);
+	assert!((custom_range.parameter_value(127) - 0.5).abs() < 1e-9);
+	let halfway = custom_range.parameter_value(64);
+	assert!((halfway - (-1.0 + 1.5 * 64.0 / 127.0)).abs() < 1e-9);
+}
@@ -0,0 +1,173 @@
+//! Describes a whole mixer layout as data.
+//!
+//! A [`MixerConfig`] lists the effects on the main track and the
+//! send tracks and sub-tracks to create, along with their effects.
+//! With the `serde_support` feature, it can be loaded from a data
+//! file and applied using
+//! [`AudioManager::add_mixer_config`](crate::manager::AudioManager::add_mixer_config).
+//! Built-in effects are referred to by name:
+//!
+//! ```json
+//! {
+//! 	"main_track_effects": [
+//! 		{ "effect": { "type": "Filter", "cutoff": { "Fixed": 8000.0 } } }
+//! 	],
+//! 	"send_tracks": [
+//! 		{
+//! 			"settings": { "id": "3b8e6f6e-2d1c-4f0b-9a53-0d5c6b7e9f11" },
+//! 			"effects": [{ "effect": { "type": "Reverb" } }]
+//! 		}
+//! 	],
+//! 	"sub_tracks": [
+//! 		{
+//! 			"settings": {
+//! 				"volume": { "Fixed": 0.5 },
+//! 				"sends": { "3b8e6f6e-2d1c-4f0b-9a53-0d5c6b7e9f11": { "Fixed": 0.3 } }
+//! 			}
+//! 		}
+//! 	]
+//! }
+//! ```
+
+use super::{
+	effect::{
+		delay::{Delay, DelaySettings},
+		distortion::{Distortion, DistortionSettings},
+		filter::{Filter, FilterSettings},
+		handle::EffectHandle,
+		mid_side::{MidSide, MidSideSettings},
+		muffle::{Muffle, MuffleSettings},
+		pitch_shift::{PitchShift, PitchShiftSettings},
+		reverb::{Reverb, ReverbSettings},
+		Effect, EffectSettings,
+	},
+	SendTrackHandle, SendTrackSettings, SubTrackHandle, SubTrackSettings,
+};
+
+/// One of the effects that comes with Kira, along
+/// with its settings.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(tag = "type")
+)]
+pub enum BuiltInEffect {
+	/// A [`Filter`] effect.
+	Filter(FilterSettings),
+	/// A [`Reverb`] effect.
+	Reverb(ReverbSettings),
+	/// A [`Delay`] effect.
+	Delay(DelaySettings),
+	/// A [`Distortion`] effect.
+	Distortion(DistortionSettings),
+	/// A [`Muffle`] effect.
+	Muffle(MuffleSettings),
+	/// A [`PitchShift`] effect.
+	PitchShift(PitchShiftSettings),
+	/// A [`MidSide`] effect.
+	MidSide(MidSideSettings),
+}
+
+impl BuiltInEffect {
+	/// Creates the effect.
+	pub fn create(&self) -> Box<dyn Effect> {
+		match self {
+			BuiltInEffect::Filter(settings) => Box::new(Filter::new(*settings)),
+			BuiltInEffect::Reverb(settings) => Box::new(Reverb::new(*settings)),
+			BuiltInEffect::Delay(settings) => Box::new(Delay::new(*settings)),
+			BuiltInEffect::Distortion(settings) => Box::new(Distortion::new(*settings)),
+			BuiltInEffect::Muffle(settings) => Box::new(Muffle::new(*settings)),
+			BuiltInEffect::PitchShift(settings) => Box::new(PitchShift::new(*settings)),
+			BuiltInEffect::MidSide(settings) => Box::new(MidSide::new(*settings)),
+		}
+	}
+}
+
+/// A built-in effect to add to a mixer track.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct EffectConfig {
+	/// The effect to add.
+	pub effect: BuiltInEffect,
+	/// The settings for the effect's slot on the track.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub settings: EffectSettings,
+}
+
+impl EffectConfig {
+	/// Creates a new `EffectConfig` with the default effect settings.
+	pub fn new(effect: BuiltInEffect) -> Self {
+		Self {
+			effect,
+			settings: EffectSettings::default(),
+		}
+	}
+
+	/// Sets the settings for the effect's slot on the track.
+	pub fn settings(self, settings: EffectSettings) -> Self {
+		Self { settings, ..self }
+	}
+}
+
+/// A mixer sub-track and the effects to add to it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct SubTrackConfig {
+	/// The settings for the track.
+	pub settings: SubTrackSettings,
+	/// The effects to add to the track, in order.
+	pub effects: Vec<EffectConfig>,
+}
+
+/// A mixer send track and the effects to add to it.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct SendTrackConfig {
+	/// The settings for the track.
+	pub settings: SendTrackSettings,
+	/// The effects to add to the track, in order.
+	pub effects: Vec<EffectConfig>,
+}
+
+/// A layout of mixer tracks and effects.
+///
+/// Send tracks are added before sub-tracks, and sub-tracks are
+/// added in order, so a sub-track can only use an earlier
+/// sub-track as its parent track.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct MixerConfig {
+	/// The effects to add to the main track, in order.
+	pub main_track_effects: Vec<EffectConfig>,
+	/// The send tracks to add.
+	pub send_tracks: Vec<SendTrackConfig>,
+	/// The sub-tracks to add.
+	pub sub_tracks: Vec<SubTrackConfig>,
+}
+
+/// Handles for the tracks created from a [`MixerConfig`].
+pub struct MixerConfigHandles {
+	/// The effects added to the main track, in the order
+	/// they appear in the config.
+	pub main_track_effects: Vec<EffectHandle>,
+	/// The send tracks, in the order they appear in the config.
+	pub send_tracks: Vec<SendTrackHandle>,
+	/// The sub-tracks, in the order they appear in the config.
+	pub sub_tracks: Vec<SubTrackHandle>,
+}
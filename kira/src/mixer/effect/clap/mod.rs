@@ -0,0 +1,629 @@
+//! Hosts external [CLAP](https://cleveraudio.org) plugins as effects.
+//!
+//! This module is only available with the `clap` feature enabled,
+//! and only on platforms that can load dynamic libraries.
+//!
+//! A [`ClapPlugin`] can be added to any mixer track like a built-in
+//! effect. Plugin parameters can be linked to kira
+//! [`Value`](crate::Value)s, so they can be controlled with
+//! [parameters](crate::parameter) and tweens:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::effect::{clap::ClapPlugin, EffectSettings},
+//! # 	parameter::ParameterSettings,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let mut plugin = ClapPlugin::load("plugins/reverb.clap", None)?;
+//! let mut room_size = audio_manager.add_parameter(ParameterSettings::new().value(0.5))?;
+//! if let Some(id) = plugin.parameter_id("Room Size") {
+//! 	plugin = plugin.link_parameter(id, room_size.id());
+//! }
+//! audio_manager
+//! 	.main_track()
+//! 	.add_effect(plugin, EffectSettings::default())?;
+//! room_size.set(0.9, Some(1.0.into()))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Linked parameters can also be changed directly with
+//! [`EffectHandle::set_value`](super::handle::EffectHandle::set_value),
+//! using the index returned by [`ClapPlugin::linked_value_index`].
+//!
+//! Plugins are expected to have a stereo input and a stereo output.
+//!
+//! Plugin hosting is experimental. Kira's effects process audio one
+//! frame at a time, so plugins are given blocks of a single frame.
+//! This uses a lot more CPU time than processing larger blocks, and
+//! plugins that need a minimum block size, like most convolution
+//! reverbs and linear phase EQs, won't sound right.
+
+use std::{
+	ffi::{c_char, c_void, CStr, CString},
+	fmt::{Debug, Formatter},
+	path::{Path, PathBuf},
+	ptr::null,
+	sync::{Arc, Mutex, MutexGuard},
+};
+
+use clap_sys::{
+	audio_buffer::clap_audio_buffer,
+	entry::clap_plugin_entry,
+	events::{
+		clap_event_header, clap_event_param_value, clap_input_events, clap_output_events,
+		CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE,
+	},
+	ext::params::{clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS},
+	factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID},
+	host::clap_host,
+	id::clap_id,
+	plugin::clap_plugin,
+	process::{clap_process, CLAP_PROCESS_ERROR},
+	version::CLAP_VERSION,
+};
+use libloading::Library;
+use thiserror::Error;
+
+use crate::{
+	frame::{Frame, Sample},
+	parameter::Parameters,
+	CachedValue, Value,
+};
+
+use super::{values::EffectValues, Effect};
+
+const HOST_NAME: &[u8] = b"kira\0";
+const HOST_VENDOR: &[u8] = b"kira\0";
+const HOST_URL: &[u8] = b"https://github.com/tesselode/kira\0";
+const HOST_VERSION: &[u8] = b"0.5.0\0";
+
+/// Something that can go wrong when loading a CLAP plugin.
+#[derive(Debug, Error)]
+pub enum ClapPluginError {
+	/// The plugin library could not be loaded.
+	#[error("{0}")]
+	LibraryError(#[from] libloading::Error),
+	/// The plugin path contains a null byte.
+	#[error("The plugin path contains a null byte")]
+	InvalidPath,
+	/// The plugin library failed to initialize.
+	#[error("The plugin library failed to initialize")]
+	InitFailed,
+	/// The plugin library does not provide a plugin factory.
+	#[error("The plugin library does not provide a plugin factory")]
+	NoPluginFactory,
+	/// The plugin library does not contain a plugin with the given ID.
+	#[error("The plugin library does not contain a plugin with the ID {0}")]
+	NoPluginWithId(String),
+	/// The plugin could not be created.
+	#[error("The plugin could not be created")]
+	CreatePluginFailed,
+}
+
+/// Information about a parameter of a CLAP plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClapParameterInfo {
+	/// The unique identifier of the parameter.
+	pub id: u32,
+	/// The display name of the parameter.
+	pub name: String,
+	/// The minimum value of the parameter.
+	pub min_value: f64,
+	/// The maximum value of the parameter.
+	pub max_value: f64,
+	/// The default value of the parameter.
+	pub default_value: f64,
+}
+
+/// A plugin parameter that's linked to a kira [`Value`].
+struct LinkedParameter {
+	id: clap_id,
+	/// The index of the parameter's value in the plugin's
+	/// [`EffectValues`].
+	value_index: usize,
+	last_sent_value: Option<f64>,
+}
+
+/// The parameter value events to send to the plugin
+/// for one block of audio.
+struct InputEvents {
+	events: Vec<clap_event_param_value>,
+}
+
+/// A plugin library that's been loaded and initialized.
+///
+/// Loading the same file twice gives back the same library, which
+/// CLAP only allows to be initialized once, so every plugin created
+/// from a file shares one `LoadedLibrary`.
+struct LoadedLibrary {
+	path: PathBuf,
+	entry: *const clap_plugin_entry,
+	// the library has to outlive the entry
+	_library: Library,
+}
+
+// SAFETY: CLAP requires the entry's functions to be callable from
+// any thread, and `init` and `deinit` are only called while the
+// `LOADED_LIBRARIES` lock is held.
+unsafe impl Send for LoadedLibrary {}
+unsafe impl Sync for LoadedLibrary {}
+
+/// The libraries that are initialized, along with how many
+/// plugins are using each one.
+static LOADED_LIBRARIES: Mutex<Vec<(Arc<LoadedLibrary>, usize)>> = Mutex::new(Vec::new());
+
+fn lock_loaded_libraries() -> MutexGuard<'static, Vec<(Arc<LoadedLibrary>, usize)>> {
+	LOADED_LIBRARIES
+		.lock()
+		.unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A plugin's use of a [`LoadedLibrary`].
+///
+/// The library is deinitialized when the last use of it
+/// is dropped.
+struct LibraryUse(Arc<LoadedLibrary>);
+
+impl LibraryUse {
+	/// Starts using the library at the given path, initializing
+	/// it if no plugins are using it yet.
+	///
+	/// `open` loads the library and finds its entry point.
+	unsafe fn acquire(
+		path: &Path,
+		open: impl FnOnce() -> Result<(Library, *const clap_plugin_entry), ClapPluginError>,
+	) -> Result<Self, ClapPluginError> {
+		let mut loaded_libraries = lock_loaded_libraries();
+		if let Some((library, num_uses)) = loaded_libraries
+			.iter_mut()
+			.find(|(library, _)| library.path == path)
+		{
+			*num_uses += 1;
+			return Ok(Self(library.clone()));
+		}
+		let path_cstring = CString::new(path.to_string_lossy().as_bytes())
+			.map_err(|_| ClapPluginError::InvalidPath)?;
+		let (library, entry) = open()?;
+		if !(*entry)
+			.init
+			.is_some_and(|init| init(path_cstring.as_ptr()))
+		{
+			return Err(ClapPluginError::InitFailed);
+		}
+		let library = Arc::new(LoadedLibrary {
+			path: path.to_owned(),
+			entry,
+			_library: library,
+		});
+		loaded_libraries.push((library.clone(), 1));
+		Ok(Self(library))
+	}
+
+	fn entry(&self) -> *const clap_plugin_entry {
+		self.0.entry
+	}
+}
+
+impl Drop for LibraryUse {
+	fn drop(&mut self) {
+		let mut loaded_libraries = lock_loaded_libraries();
+		let index = match loaded_libraries
+			.iter()
+			.position(|(library, _)| Arc::ptr_eq(library, &self.0))
+		{
+			Some(index) => index,
+			None => return,
+		};
+		loaded_libraries[index].1 -= 1;
+		if loaded_libraries[index].1 == 0 {
+			loaded_libraries.remove(index);
+			unsafe {
+				if let Some(deinit) = (*self.0.entry).deinit {
+					deinit();
+				}
+			}
+		}
+	}
+}
+
+/// An effect that processes audio using a CLAP plugin.
+pub struct ClapPlugin {
+	// dropped after the plugin is destroyed in `Drop`
+	_library: LibraryUse,
+	// the host has to outlive the plugin, so it's boxed
+	// to keep its address stable
+	_host: Box<clap_host>,
+	plugin: *const clap_plugin,
+	parameters: Vec<ClapParameterInfo>,
+	linked_parameters: Vec<LinkedParameter>,
+	linked_values: EffectValues,
+	input_events: InputEvents,
+	active: bool,
+	processing: bool,
+	steady_time: i64,
+}
+
+// SAFETY: the raw pointers are only used through `&mut self`, and
+// `ClapPlugin` isn't `Sync`, so the plugin is never used from two
+// threads at once. The plugin is loaded, inspected, and activated
+// (in `Effect::init`) on the main thread before it's sent to the
+// audio thread, which is the only thread that processes audio with
+// it afterwards, as CLAP requires. The library the plugin came from
+// is kept loaded and initialized by `library`.
+unsafe impl Send for ClapPlugin {}
+
+impl ClapPlugin {
+	/// Loads a plugin from a `.clap` file.
+	///
+	/// If `plugin_id` is `None`, the first plugin in the
+	/// file will be loaded.
+	pub fn load(path: impl AsRef<Path>, plugin_id: Option<&str>) -> Result<Self, ClapPluginError> {
+		let path = path.as_ref();
+		unsafe {
+			let library = LibraryUse::acquire(path, || {
+				let library = Library::new(path)?;
+				let entry = *library.get::<*const clap_plugin_entry>(b"clap_entry\0")?;
+				Ok((library, entry))
+			})?;
+			Self::create_plugin(library, plugin_id)
+		}
+	}
+
+	unsafe fn create_plugin(
+		library: LibraryUse,
+		plugin_id: Option<&str>,
+	) -> Result<Self, ClapPluginError> {
+		let entry = library.entry();
+		let factory = match (*entry).get_factory {
+			Some(get_factory) => get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()),
+			None => null(),
+		} as *const clap_plugin_factory;
+		if factory.is_null() {
+			return Err(ClapPluginError::NoPluginFactory);
+		}
+		let descriptor_id = Self::find_plugin(factory, plugin_id)?;
+		let host = Box::new(clap_host {
+			clap_version: CLAP_VERSION,
+			host_data: std::ptr::null_mut(),
+			name: HOST_NAME.as_ptr() as *const c_char,
+			vendor: HOST_VENDOR.as_ptr() as *const c_char,
+			url: HOST_URL.as_ptr() as *const c_char,
+			version: HOST_VERSION.as_ptr() as *const c_char,
+			get_extension: Some(host_get_extension),
+			request_restart: Some(host_request),
+			request_process: Some(host_request),
+			request_callback: Some(host_request),
+		});
+		let plugin = match (*factory).create_plugin {
+			Some(create_plugin) => create_plugin(factory, &*host, descriptor_id.as_ptr()),
+			None => null(),
+		};
+		if plugin.is_null() {
+			return Err(ClapPluginError::CreatePluginFailed);
+		}
+		if !(*plugin).init.is_some_and(|init| init(plugin)) {
+			if let Some(destroy) = (*plugin).destroy {
+				destroy(plugin);
+			}
+			return Err(ClapPluginError::CreatePluginFailed);
+		}
+		let parameters = Self::read_parameters(plugin);
+		Ok(Self {
+			_library: library,
+			_host: host,
+			plugin,
+			input_events: InputEvents {
+				events: Vec::with_capacity(parameters.len()),
+			},
+			parameters,
+			linked_parameters: vec![],
+			linked_values: EffectValues::new(),
+			active: false,
+			processing: false,
+			steady_time: 0,
+		})
+	}
+
+	/// Finds the ID of the plugin to load from a plugin factory.
+	unsafe fn find_plugin(
+		factory: *const clap_plugin_factory,
+		plugin_id: Option<&str>,
+	) -> Result<CString, ClapPluginError> {
+		let not_found =
+			|| ClapPluginError::NoPluginWithId(plugin_id.unwrap_or_default().to_string());
+		let count = (*factory)
+			.get_plugin_count
+			.map_or(0, |count| count(factory));
+		let get_descriptor = (*factory).get_plugin_descriptor.ok_or_else(not_found)?;
+		for i in 0..count {
+			let descriptor = get_descriptor(factory, i);
+			if descriptor.is_null() || (*descriptor).id.is_null() {
+				continue;
+			}
+			let id = CStr::from_ptr((*descriptor).id);
+			match plugin_id {
+				Some(plugin_id) if id.to_bytes() != plugin_id.as_bytes() => {}
+				_ => return Ok(id.to_owned()),
+			}
+		}
+		Err(not_found())
+	}
+
+	/// Gets information about all of the plugin's parameters.
+	unsafe fn read_parameters(plugin: *const clap_plugin) -> Vec<ClapParameterInfo> {
+		let params = match (*plugin).get_extension {
+			Some(get_extension) => get_extension(plugin, CLAP_EXT_PARAMS.as_ptr()),
+			None => null(),
+		} as *const clap_plugin_params;
+		if params.is_null() {
+			return vec![];
+		}
+		let (count, get_info) = match ((*params).count, (*params).get_info) {
+			(Some(count), Some(get_info)) => (count(plugin), get_info),
+			_ => return vec![],
+		};
+		let mut parameters = Vec::with_capacity(count as usize);
+		for i in 0..count {
+			let mut info: clap_param_info = std::mem::zeroed();
+			if get_info(plugin, i, &mut info) {
+				parameters.push(ClapParameterInfo {
+					id: info.id,
+					name: CStr::from_ptr(info.name.as_ptr())
+						.to_string_lossy()
+						.into_owned(),
+					min_value: info.min_value,
+					max_value: info.max_value,
+					default_value: info.default_value,
+				});
+			}
+		}
+		parameters
+	}
+
+	/// Returns information about the plugin's parameters.
+	pub fn parameters(&self) -> &[ClapParameterInfo] {
+		&self.parameters
+	}
+
+	/// Returns the ID of the parameter with the given name, if
+	/// there is one.
+	pub fn parameter_id(&self, name: &str) -> Option<u32> {
+		self.parameters
+			.iter()
+			.find(|parameter| parameter.name == name)
+			.map(|parameter| parameter.id)
+	}
+
+	/// Links a plugin parameter to a kira [`Value`].
+	///
+	/// Whenever the value changes, the new value will be sent
+	/// to the plugin. Values are in the plugin parameter's own
+	/// range (see [`ClapParameterInfo`]).
+	///
+	/// Linking a parameter that's already linked replaces its value.
+	pub fn link_parameter(mut self, id: u32, value: impl Into<Value<f64>>) -> Self {
+		let value = value.into();
+		if let Some(value_index) = self.linked_value_index(id) {
+			self.linked_values.set(value_index, value);
+			return self;
+		}
+		let default_value = self
+			.parameters
+			.iter()
+			.find(|parameter| parameter.id == id)
+			.map_or(0.0, |parameter| parameter.default_value);
+		let value_index = self
+			.linked_values
+			.add(CachedValue::new(value, default_value));
+		self.linked_parameters.push(LinkedParameter {
+			id,
+			value_index,
+			last_sent_value: None,
+		});
+		if self.input_events.events.capacity() < self.linked_parameters.len() {
+			self.input_events
+				.events
+				.reserve(self.linked_parameters.len());
+		}
+		self
+	}
+
+	/// Returns the index of a linked plugin parameter in the
+	/// effect's [`EffectValues`], if the parameter is linked.
+	///
+	/// The index can be passed to
+	/// [`EffectHandle::set_value`](super::handle::EffectHandle::set_value)
+	/// to change the parameter from the main thread.
+	pub fn linked_value_index(&self, id: u32) -> Option<usize> {
+		self.linked_parameters
+			.iter()
+			.find(|parameter| parameter.id == id)
+			.map(|parameter| parameter.value_index)
+	}
+
+	/// Stops the plugin from processing audio and deactivates it.
+	unsafe fn deactivate(&mut self) {
+		if self.processing {
+			if let Some(stop_processing) = (*self.plugin).stop_processing {
+				stop_processing(self.plugin);
+			}
+			self.processing = false;
+		}
+		if self.active {
+			if let Some(deactivate) = (*self.plugin).deactivate {
+				deactivate(self.plugin);
+			}
+			self.active = false;
+		}
+	}
+
+	/// Queues events for any linked parameters whose values changed.
+	fn queue_parameter_events(&mut self) {
+		self.input_events.events.clear();
+		for parameter in &mut self.linked_parameters {
+			let value = self.linked_values.value(parameter.value_index);
+			if parameter.last_sent_value == Some(value) {
+				continue;
+			}
+			parameter.last_sent_value = Some(value);
+			if self.input_events.events.len() < self.input_events.events.capacity() {
+				self.input_events.events.push(clap_event_param_value {
+					header: clap_event_header {
+						size: std::mem::size_of::<clap_event_param_value>() as u32,
+						time: 0,
+						space_id: CLAP_CORE_EVENT_SPACE_ID,
+						type_: CLAP_EVENT_PARAM_VALUE,
+						flags: 0,
+					},
+					param_id: parameter.id,
+					cookie: std::ptr::null_mut(),
+					note_id: -1,
+					port_index: -1,
+					channel: -1,
+					key: -1,
+					value,
+				});
+			}
+		}
+	}
+}
+
+impl Debug for ClapPlugin {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ClapPlugin")
+			.field("parameters", &self.parameters)
+			.finish()
+	}
+}
+
+impl Effect for ClapPlugin {
+	fn init(&mut self, sample_rate: u32) {
+		unsafe {
+			// plugins can only be activated with a new sample
+			// rate after they've been deactivated
+			self.deactivate();
+			if let Some(activate) = (*self.plugin).activate {
+				self.active = activate(self.plugin, sample_rate as f64, 1, 1);
+			}
+		}
+	}
+
+	fn cached_values(&mut self) -> Option<&mut EffectValues> {
+		Some(&mut self.linked_values)
+	}
+
+	// `Option::is_none_or` needs a newer version of Rust
+	// than kira supports
+	#[allow(clippy::unnecessary_map_or)]
+	fn process(&mut self, _dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+		if !self.active {
+			return input;
+		}
+		unsafe {
+			if !self.processing {
+				self.processing = (*self.plugin)
+					.start_processing
+					.map_or(true, |start_processing| start_processing(self.plugin));
+				if !self.processing {
+					return input;
+				}
+			}
+			self.queue_parameter_events();
+			let (mut input_left, mut input_right) = input.to_f32();
+			let mut output_left = 0.0f32;
+			let mut output_right = 0.0f32;
+			let mut input_channels = [&mut input_left as *mut f32, &mut input_right];
+			let mut output_channels = [&mut output_left as *mut f32, &mut output_right];
+			let input_buffer = clap_audio_buffer {
+				data32: input_channels.as_mut_ptr(),
+				data64: std::ptr::null_mut(),
+				channel_count: 2,
+				latency: 0,
+				constant_mask: 0,
+			};
+			let mut output_buffer = clap_audio_buffer {
+				data32: output_channels.as_mut_ptr(),
+				data64: std::ptr::null_mut(),
+				channel_count: 2,
+				latency: 0,
+				constant_mask: 0,
+			};
+			let in_events = clap_input_events {
+				ctx: &self.input_events as *const InputEvents as *mut c_void,
+				size: Some(input_events_size),
+				get: Some(input_events_get),
+			};
+			let out_events = clap_output_events {
+				ctx: std::ptr::null_mut(),
+				try_push: Some(output_events_try_push),
+			};
+			let process = clap_process {
+				steady_time: self.steady_time,
+				frames_count: 1,
+				transport: null(),
+				audio_inputs: &input_buffer,
+				audio_outputs: &mut output_buffer,
+				audio_inputs_count: 1,
+				audio_outputs_count: 1,
+				in_events: &in_events,
+				out_events: &out_events,
+			};
+			self.steady_time += 1;
+			let status = match (*self.plugin).process {
+				Some(process_fn) => process_fn(self.plugin, &process),
+				None => CLAP_PROCESS_ERROR,
+			};
+			if status == CLAP_PROCESS_ERROR {
+				return input;
+			}
+			Frame::new(output_left as Sample, output_right as Sample)
+		}
+	}
+}
+
+impl Drop for ClapPlugin {
+	fn drop(&mut self) {
+		unsafe {
+			self.deactivate();
+			if let Some(destroy) = (*self.plugin).destroy {
+				destroy(self.plugin);
+			}
+		}
+	}
+}
+
+unsafe extern "C" fn host_get_extension(
+	_host: *const clap_host,
+	_extension_id: *const c_char,
+) -> *const c_void {
+	null()
+}
+
+unsafe extern "C" fn host_request(_host: *const clap_host) {}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+	let input_events = &*((*list).ctx as *const InputEvents);
+	input_events.events.len() as u32
+}
+
+unsafe extern "C" fn input_events_get(
+	list: *const clap_input_events,
+	index: u32,
+) -> *const clap_event_header {
+	let input_events = &*((*list).ctx as *const InputEvents);
+	match input_events.events.get(index as usize) {
+		Some(event) => &event.header,
+		None => null(),
+	}
+}
+
+unsafe extern "C" fn output_events_try_push(
+	_list: *const clap_output_events,
+	_event: *const clap_event_header,
+) -> bool {
+	true
+}
+
+#[cfg(test)]
+mod tests;
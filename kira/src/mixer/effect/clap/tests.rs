@@ -0,0 +1,345 @@
+use std::{
+	cell::RefCell,
+	ffi::{c_char, c_void, CStr},
+	path::Path,
+	ptr::null,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use clap_sys::{
+	entry::clap_plugin_entry,
+	events::{clap_event_param_value, CLAP_EVENT_PARAM_VALUE},
+	ext::params::{clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS},
+	factory::plugin_factory::clap_plugin_factory,
+	host::clap_host,
+	id::clap_id,
+	plugin::{clap_plugin, clap_plugin_descriptor},
+	process::{clap_process, clap_process_status, CLAP_PROCESS_CONTINUE},
+	version::CLAP_VERSION,
+};
+use libloading::Library;
+
+use crate::{
+	audio_stream::AudioStream,
+	mixer::{effect::Effect, TrackIndex},
+	parameter::{ParameterSettings, Parameters},
+	testing::create_manager,
+	Frame,
+};
+
+use super::{ClapPlugin, ClapPluginError, LibraryUse};
+
+const GAIN_PLUGIN_ID: &[u8] = b"kira.test.gain\0";
+const GAIN_PARAMETER_ID: clap_id = 7;
+
+thread_local! {
+	/// The plugin functions the host has called, in order.
+	static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record_call(call: impl Into<String>) {
+	CALLS.with(|calls| calls.borrow_mut().push(call.into()));
+}
+
+fn take_calls() -> Vec<String> {
+	CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+}
+
+/// The state of a plugin that multiplies its input
+/// by its "Gain" parameter.
+struct GainPlugin {
+	gain: f64,
+}
+
+static GAIN_PLUGIN_DESCRIPTOR: clap_plugin_descriptor = clap_plugin_descriptor {
+	clap_version: CLAP_VERSION,
+	id: GAIN_PLUGIN_ID.as_ptr() as *const c_char,
+	name: GAIN_PLUGIN_ID.as_ptr() as *const c_char,
+	vendor: null(),
+	url: null(),
+	manual_url: null(),
+	support_url: null(),
+	version: null(),
+	description: null(),
+	features: null(),
+};
+
+static GAIN_PLUGIN_PARAMS: clap_plugin_params = clap_plugin_params {
+	count: Some(params_count),
+	get_info: Some(params_get_info),
+	get_value: None,
+	value_to_text: None,
+	text_to_value: None,
+	flush: None,
+};
+
+static FACTORY: clap_plugin_factory = clap_plugin_factory {
+	get_plugin_count: Some(factory_get_plugin_count),
+	get_plugin_descriptor: Some(factory_get_plugin_descriptor),
+	create_plugin: Some(factory_create_plugin),
+};
+
+static ENTRY: clap_plugin_entry = clap_plugin_entry {
+	clap_version: CLAP_VERSION,
+	init: Some(entry_init),
+	deinit: Some(entry_deinit),
+	get_factory: Some(entry_get_factory),
+};
+
+unsafe extern "C" fn entry_init(_plugin_path: *const c_char) -> bool {
+	record_call("init");
+	true
+}
+
+unsafe extern "C" fn entry_deinit() {
+	record_call("deinit");
+}
+
+unsafe extern "C" fn entry_get_factory(_factory_id: *const c_char) -> *const c_void {
+	&FACTORY as *const clap_plugin_factory as *const c_void
+}
+
+unsafe extern "C" fn factory_get_plugin_count(_factory: *const clap_plugin_factory) -> u32 {
+	1
+}
+
+unsafe extern "C" fn factory_get_plugin_descriptor(
+	_factory: *const clap_plugin_factory,
+	_index: u32,
+) -> *const clap_plugin_descriptor {
+	&GAIN_PLUGIN_DESCRIPTOR
+}
+
+unsafe extern "C" fn factory_create_plugin(
+	_factory: *const clap_plugin_factory,
+	_host: *const clap_host,
+	_plugin_id: *const c_char,
+) -> *const clap_plugin {
+	Box::into_raw(Box::new(clap_plugin {
+		desc: &GAIN_PLUGIN_DESCRIPTOR,
+		plugin_data: Box::into_raw(Box::new(GainPlugin { gain: 1.0 })) as *mut c_void,
+		init: Some(plugin_init),
+		destroy: Some(plugin_destroy),
+		activate: Some(plugin_activate),
+		deactivate: Some(plugin_deactivate),
+		start_processing: Some(plugin_start_processing),
+		stop_processing: Some(plugin_stop_processing),
+		reset: None,
+		process: Some(plugin_process),
+		get_extension: Some(plugin_get_extension),
+		on_main_thread: None,
+	}))
+}
+
+unsafe extern "C" fn plugin_init(_plugin: *const clap_plugin) -> bool {
+	true
+}
+
+unsafe extern "C" fn plugin_destroy(plugin: *const clap_plugin) {
+	record_call("destroy");
+	let plugin = Box::from_raw(plugin as *mut clap_plugin);
+	drop(Box::from_raw(plugin.plugin_data as *mut GainPlugin));
+}
+
+unsafe extern "C" fn plugin_activate(
+	_plugin: *const clap_plugin,
+	sample_rate: f64,
+	_min_frames_count: u32,
+	_max_frames_count: u32,
+) -> bool {
+	record_call(format!("activate {}", sample_rate));
+	true
+}
+
+unsafe extern "C" fn plugin_deactivate(_plugin: *const clap_plugin) {
+	record_call("deactivate");
+}
+
+unsafe extern "C" fn plugin_start_processing(_plugin: *const clap_plugin) -> bool {
+	record_call("start_processing");
+	true
+}
+
+unsafe extern "C" fn plugin_stop_processing(_plugin: *const clap_plugin) {
+	record_call("stop_processing");
+}
+
+unsafe extern "C" fn plugin_process(
+	plugin: *const clap_plugin,
+	process: *const clap_process,
+) -> clap_process_status {
+	let state = &mut *((*plugin).plugin_data as *mut GainPlugin);
+	let in_events = &*(*process).in_events;
+	for i in 0..in_events.size.unwrap()(in_events) {
+		let header = in_events.get.unwrap()(in_events, i);
+		if (*header).type_ == CLAP_EVENT_PARAM_VALUE {
+			let event = &*(header as *const clap_event_param_value);
+			if event.param_id == GAIN_PARAMETER_ID {
+				state.gain = event.value;
+			}
+		}
+	}
+	let input = &*(*process).audio_inputs;
+	let output = &*(*process).audio_outputs;
+	for channel in 0..2 {
+		let input = *input.data32.add(channel);
+		let output = *output.data32.add(channel);
+		for frame in 0..(*process).frames_count as usize {
+			*output.add(frame) = *input.add(frame) * state.gain as f32;
+		}
+	}
+	CLAP_PROCESS_CONTINUE
+}
+
+unsafe extern "C" fn plugin_get_extension(
+	_plugin: *const clap_plugin,
+	id: *const c_char,
+) -> *const c_void {
+	if CStr::from_ptr(id) == CLAP_EXT_PARAMS {
+		&GAIN_PLUGIN_PARAMS as *const clap_plugin_params as *const c_void
+	} else {
+		null()
+	}
+}
+
+unsafe extern "C" fn params_count(_plugin: *const clap_plugin) -> u32 {
+	1
+}
+
+unsafe extern "C" fn params_get_info(
+	_plugin: *const clap_plugin,
+	_param_index: u32,
+	param_info: *mut clap_param_info,
+) -> bool {
+	let info = &mut *param_info;
+	info.id = GAIN_PARAMETER_ID;
+	for (i, byte) in b"Gain\0".iter().enumerate() {
+		info.name[i] = *byte as c_char;
+	}
+	info.min_value = 0.0;
+	info.max_value = 2.0;
+	info.default_value = 1.0;
+	true
+}
+
+#[cfg(unix)]
+fn this_library() -> Library {
+	libloading::os::unix::Library::this().into()
+}
+
+#[cfg(windows)]
+fn this_library() -> Library {
+	libloading::os::windows::Library::this().unwrap().into()
+}
+
+/// Creates a [`ClapPlugin`] for the gain plugin defined in this
+/// file, as if it was loaded from a file at the given path.
+fn gain_plugin_at(path: &str, plugin_id: Option<&str>) -> Result<ClapPlugin, ClapPluginError> {
+	unsafe {
+		let library = LibraryUse::acquire(Path::new(path), || {
+			Ok((this_library(), &ENTRY as *const clap_plugin_entry))
+		})?;
+		ClapPlugin::create_plugin(library, plugin_id)
+	}
+}
+
+/// Creates a [`ClapPlugin`] for the gain plugin defined in this
+/// file, as if it was loaded from a file no other test uses.
+fn gain_plugin(plugin_id: Option<&str>) -> Result<ClapPlugin, ClapPluginError> {
+	static NEXT_PATH_INDEX: AtomicUsize = AtomicUsize::new(0);
+	let path_index = NEXT_PATH_INDEX.fetch_add(1, Ordering::Relaxed);
+	gain_plugin_at(&format!("gain-{}.clap", path_index), plugin_id)
+}
+
+#[test]
+fn reads_plugin_parameters() {
+	let plugin = gain_plugin(Some("kira.test.gain")).unwrap();
+	assert_eq!(plugin.parameters().len(), 1);
+	assert_eq!(plugin.parameters()[0].name, "Gain");
+	assert_eq!(plugin.parameters()[0].max_value, 2.0);
+	assert_eq!(plugin.parameter_id("Gain"), Some(GAIN_PARAMETER_ID));
+	assert_eq!(plugin.parameter_id("Mix"), None);
+}
+
+#[test]
+fn returns_error_for_missing_plugin_ids() {
+	assert!(matches!(
+		gain_plugin(Some("kira.test.reverb")),
+		Err(ClapPluginError::NoPluginWithId(id)) if id == "kira.test.reverb"
+	));
+}
+
+#[test]
+fn sends_linked_parameters_to_plugins() {
+	let (mut manager, mut backend) = create_manager();
+	let mut gain = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	let plugin = gain_plugin(None)
+		.unwrap()
+		.link_parameter(GAIN_PARAMETER_ID, gain.id());
+	assert_eq!(plugin.linked_value_index(GAIN_PARAMETER_ID), Some(0));
+	manager
+		.main_track()
+		.add_effect(plugin, Default::default())
+		.unwrap();
+	manager.add_stream(StereoStream, TrackIndex::Main).unwrap();
+	assert_eq!(backend.process(), Frame::new(0.5, -0.5));
+	gain.set(2.0, None).unwrap();
+	backend.process();
+	assert_eq!(backend.process(), Frame::new(2.0, -2.0));
+}
+
+#[test]
+fn deactivates_plugins_before_reactivating_them() {
+	let mut plugin = gain_plugin(None).unwrap();
+	let parameters = Parameters::new(0, 0);
+	plugin.init(44100);
+	plugin.process(1.0 / 44100.0, Frame::new(1.0, 1.0), &parameters);
+	plugin.init(48000);
+	plugin.process(1.0 / 48000.0, Frame::new(1.0, 1.0), &parameters);
+	drop(plugin);
+	assert_eq!(
+		take_calls(),
+		[
+			"init",
+			"activate 44100",
+			"start_processing",
+			"stop_processing",
+			"deactivate",
+			"activate 48000",
+			"start_processing",
+			"stop_processing",
+			"deactivate",
+			"destroy",
+			"deinit",
+		]
+	);
+}
+
+#[test]
+fn initializes_libraries_shared_by_plugins_once() {
+	let first_plugin = gain_plugin_at("shared.clap", None).unwrap();
+	let second_plugin = gain_plugin_at("shared.clap", None).unwrap();
+	// failing to create a plugin doesn't deinitialize the
+	// library while other plugins are using it
+	assert!(gain_plugin_at("shared.clap", Some("kira.test.reverb")).is_err());
+	drop(first_plugin);
+	assert_eq!(take_calls(), ["init", "destroy"]);
+	drop(second_plugin);
+	assert_eq!(take_calls(), ["destroy", "deinit"]);
+	// the library is initialized again once it's used again
+	drop(gain_plugin_at("shared.clap", None).unwrap());
+	assert_eq!(take_calls(), ["init", "destroy", "deinit"]);
+}
+
+/// Outputs a frame with different values on each side, so
+/// tests can tell that the channels are kept apart.
+#[derive(Debug)]
+struct StereoStream;
+
+impl AudioStream for StereoStream {
+	fn next(&mut self, _dt: f64) -> Frame {
+		Frame::new(1.0, -1.0)
+	}
+}
@@ -1,6 +1,6 @@
 //! Adds echoes to a sound.
 
-use crate::{parameter::Parameters, util, CachedValue, Frame, Value};
+use crate::{dsp, parameter::Parameters, CachedValue, Frame, Sample, Value};
 
 use super::{
 	filter::{Filter, FilterSettings},
@@ -138,9 +138,10 @@ impl Effect for Delay {
 			self.feedback.update(parameters);
 
 			// get the read position (in samples)
-			let mut read_position = *write_position as f32 - (self.delay_time.value() / dt) as f32;
+			let mut read_position =
+				*write_position as Sample - (self.delay_time.value() / dt) as Sample;
 			while read_position < 0.0 {
-				read_position += buffer.len() as f32;
+				read_position += buffer.len() as Sample;
 			}
 
 			// read an interpolated sample
@@ -153,7 +154,7 @@ impl Effect for Delay {
 			let next_sample_index = (current_sample_index + 1) % buffer.len();
 			let next_sample_index_2 = (current_sample_index + 2) % buffer.len();
 			let fraction = read_position % 1.0;
-			let output = util::interpolate_frame(
+			let output = dsp::interpolate_frame(
 				buffer[previous_sample_index],
 				buffer[current_sample_index],
 				buffer[next_sample_index],
@@ -168,7 +169,7 @@ impl Effect for Delay {
 				Some(filter) => filter.process(dt, output, parameters),
 				None => output,
 			};
-			buffer[*write_position] = input + filtered_output * self.feedback.value() as f32;
+			buffer[*write_position] = input + filtered_output * self.feedback.value() as Sample;
 
 			filtered_output
 		} else {
@@ -1,6 +1,6 @@
 //! Makes a sound harsher and noisier.
 
-use crate::{parameter::Parameters, CachedValue, Frame, Value};
+use crate::{parameter::Parameters, CachedValue, Frame, Sample, Value};
 
 use super::Effect;
 
@@ -97,7 +97,7 @@ impl Distortion {
 impl Effect for Distortion {
 	fn process(&mut self, _dt: f64, mut input: Frame, parameters: &Parameters) -> Frame {
 		self.drive.update(parameters);
-		let drive = self.drive.value() as f32;
+		let drive = self.drive.value() as Sample;
 		input *= drive;
 		input = match self.kind {
 			DistortionKind::HardClip => Frame::new(
@@ -3,7 +3,7 @@
 use std::f64::consts::PI;
 
 use crate::{
-	frame::Frame,
+	frame::{Frame, Sample},
 	parameter::Parameters,
 	value::{CachedValue, Value},
 };
@@ -108,6 +108,11 @@ impl Filter {
 			ic2eq: Frame::from_mono(0.0),
 		}
 	}
+
+	/// Sets the cutoff frequency of the filter.
+	pub(crate) fn set_cutoff(&mut self, cutoff: f64) {
+		self.cutoff.set(Value::Fixed(cutoff));
+	}
 }
 
 impl Effect for Filter {
@@ -121,15 +126,15 @@ impl Effect for Filter {
 		let a2 = g * a1;
 		let a3 = g * a2;
 		let v3 = input - self.ic2eq;
-		let v1 = (self.ic1eq * (a1 as f32)) + (v3 * (a2 as f32));
-		let v2 = self.ic2eq + (self.ic1eq * (a2 as f32)) + (v3 * (a3 as f32));
+		let v1 = (self.ic1eq * (a1 as Sample)) + (v3 * (a2 as Sample));
+		let v2 = self.ic2eq + (self.ic1eq * (a2 as Sample)) + (v3 * (a3 as Sample));
 		self.ic1eq = (v1 * 2.0) - self.ic1eq;
 		self.ic2eq = (v2 * 2.0) - self.ic2eq;
 		match self.mode {
 			FilterMode::LowPass => v2,
 			FilterMode::BandPass => v1,
-			FilterMode::HighPass => input - v1 * (k as f32) - v2,
-			FilterMode::Notch => input - v1 * (k as f32),
+			FilterMode::HighPass => input - v1 * (k as Sample) - v2,
+			FilterMode::Notch => input - v1 * (k as Sample),
 		}
 	}
 }
@@ -1,5 +1,12 @@
 //! An interface for controlling effects.
 
+use std::{
+	any::Any,
+	sync::{Arc, Mutex, MutexGuard},
+};
+
+use basedrop::Owned;
+
 use crate::{
 	command::{
 		producer::{CommandError, CommandProducer},
@@ -11,13 +18,14 @@ use crate::{
 
 use super::{EffectId, EffectSettings};
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 /// Allows you to control an effect.
 pub struct EffectHandle {
 	id: EffectId,
 	track_index: TrackIndex,
-	enabled: bool,
+	settings: Arc<Mutex<EffectSettings>>,
 	command_producer: CommandProducer,
+	resource_collector_handle: basedrop::Handle,
 }
 
 impl EffectHandle {
@@ -26,15 +34,26 @@ impl EffectHandle {
 		track_index: TrackIndex,
 		settings: &EffectSettings,
 		command_producer: CommandProducer,
+		resource_collector_handle: basedrop::Handle,
 	) -> Self {
 		Self {
 			id,
 			track_index,
-			enabled: settings.enabled,
+			settings: Arc::new(Mutex::new(EffectSettings {
+				id: Some(id),
+				..settings.clone()
+			})),
 			command_producer,
+			resource_collector_handle,
 		}
 	}
 
+	/// Returns the settings shared between this handle and
+	/// the handle of the track that contains the effect.
+	pub(crate) fn shared_settings(&self) -> Arc<Mutex<EffectSettings>> {
+		self.settings.clone()
+	}
+
 	/// Returns the ID of the effect.
 	pub fn id(&self) -> EffectId {
 		self.id
@@ -47,12 +66,20 @@ impl EffectHandle {
 
 	/// Returns whether the effect is currently enabled.
 	pub fn enabled(&self) -> bool {
-		self.enabled
+		lock_settings(&self.settings).enabled
+	}
+
+	/// Returns the current settings of the effect.
+	///
+	/// The returned settings include the effect's ID, so they
+	/// can be saved and used to add the effect again later.
+	pub fn settings(&self) -> EffectSettings {
+		lock_settings(&self.settings).clone()
 	}
 
 	/// Sets whether the effect is currently enabled.
 	pub fn set_enabled(&mut self, enabled: bool) -> Result<(), CommandError> {
-		self.enabled = enabled;
+		lock_settings(&self.settings).enabled = enabled;
 		self.command_producer
 			.push(MixerCommand::SetEffectEnabled(self.track_index, self.id, enabled).into())
 	}
@@ -60,7 +87,60 @@ impl EffectHandle {
 	/// Sets the balance between dry (unaffected) signal and wet
 	/// (affected) signal to output. 0.0 is fully dry, 1.0 is fully wet.
 	pub fn set_mix(&mut self, mix: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		let mix = mix.into();
+		lock_settings(&self.settings).mix = mix;
 		self.command_producer
-			.push(MixerCommand::SetEffectMix(self.track_index, self.id, mix.into()).into())
+			.push(MixerCommand::SetEffectMix(self.track_index, self.id, mix).into())
+	}
+
+	/// Sets one of the effect's [`EffectValues`](super::values::EffectValues).
+	///
+	/// This does nothing if the effect doesn't have a set of values
+	/// or if there's no value at the given index.
+	pub fn set_value(
+		&mut self,
+		index: usize,
+		value: impl Into<Value<f64>>,
+	) -> Result<(), CommandError> {
+		self.command_producer.push(
+			MixerCommand::SetEffectValue(self.track_index, self.id, index, value.into()).into(),
+		)
+	}
+
+	/// Sends a message to the effect.
+	///
+	/// The effect receives the message in its
+	/// [`on_message`](super::Effect::on_message) implementation.
+	pub fn send_message(&mut self, message: impl Any + Send) -> Result<(), CommandError> {
+		let message: Box<dyn Any + Send> = Box::new(message);
+		self.command_producer.push(
+			MixerCommand::SendEffectMessage(
+				self.track_index,
+				self.id,
+				Owned::new(&self.resource_collector_handle, message),
+			)
+			.into(),
+		)
+	}
+}
+
+impl std::fmt::Debug for EffectHandle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("EffectHandle")
+			.field("id", &self.id)
+			.field("track_index", &self.track_index)
+			.field("settings", &self.settings)
+			.field("command_producer", &self.command_producer)
+			.finish()
 	}
 }
+
+/// Locks the settings of an effect.
+///
+/// The settings are plain data that's always left in a valid
+/// state, so a panic while they were locked doesn't matter.
+pub(crate) fn lock_settings(settings: &Mutex<EffectSettings>) -> MutexGuard<'_, EffectSettings> {
+	settings
+		.lock()
+		.unwrap_or_else(|poisoned| poisoned.into_inner())
+}
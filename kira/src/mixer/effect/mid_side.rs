@@ -0,0 +1,120 @@
+//! Adjusts the stereo width of audio.
+
+use crate::{parameter::Parameters, CachedValue, Frame, Sample, Value};
+
+use super::{
+	filter::{Filter, FilterMode, FilterSettings},
+	Effect,
+};
+
+/// Settings for a [`MidSide`] effect.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct MidSideSettings {
+	/// The volume of the mid signal (the parts of the audio
+	/// that are the same in both channels).
+	mid_gain: Value<f64>,
+	/// The volume of the side signal (the differences between
+	/// the left and right channels).
+	side_gain: Value<f64>,
+	/// Whether to make frequencies below a cutoff mono, and if so,
+	/// the cutoff frequency (in hertz).
+	mono_cutoff: Option<Value<f64>>,
+}
+
+impl MidSideSettings {
+	/// Creates a new `MidSideSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the volume of the mid signal.
+	pub fn mid_gain(self, mid_gain: impl Into<Value<f64>>) -> Self {
+		Self {
+			mid_gain: mid_gain.into(),
+			..self
+		}
+	}
+
+	/// Sets the volume of the side signal.
+	///
+	/// Values above `1.0` widen the audio, and values
+	/// below `1.0` narrow it. `0.0` makes the audio mono.
+	pub fn side_gain(self, side_gain: impl Into<Value<f64>>) -> Self {
+		Self {
+			side_gain: side_gain.into(),
+			..self
+		}
+	}
+
+	/// Makes frequencies below a cutoff (in hertz) mono.
+	///
+	/// Keeping the low end of a mix mono makes bass sound
+	/// solid even when the rest of the audio is widened.
+	pub fn mono_cutoff(self, mono_cutoff: impl Into<Value<f64>>) -> Self {
+		Self {
+			mono_cutoff: Some(mono_cutoff.into()),
+			..self
+		}
+	}
+}
+
+impl Default for MidSideSettings {
+	fn default() -> Self {
+		Self {
+			mid_gain: Value::Fixed(1.0),
+			side_gain: Value::Fixed(1.0),
+			mono_cutoff: None,
+		}
+	}
+}
+
+/// An effect that splits audio into its mid and side signals
+/// and changes their volumes separately.
+///
+/// Turning up the side signal makes audio sound wider,
+/// which is commonly done on a music track, and removing
+/// the low frequencies of the side signal keeps the bass
+/// centered.
+#[derive(Debug, Clone)]
+pub struct MidSide {
+	mid_gain: CachedValue<f64>,
+	side_gain: CachedValue<f64>,
+	side_filter: Option<Filter>,
+}
+
+impl MidSide {
+	/// Creates a new mid/side effect.
+	pub fn new(settings: MidSideSettings) -> Self {
+		Self {
+			mid_gain: CachedValue::new(settings.mid_gain, 1.0).with_min(0.0),
+			side_gain: CachedValue::new(settings.side_gain, 1.0).with_min(0.0),
+			side_filter: settings.mono_cutoff.map(|cutoff| {
+				Filter::new(
+					FilterSettings::new()
+						.mode(FilterMode::HighPass)
+						.cutoff(cutoff),
+				)
+			}),
+		}
+	}
+}
+
+impl Effect for MidSide {
+	fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
+		self.mid_gain.update(parameters);
+		self.side_gain.update(parameters);
+		let mid = (input.left + input.right) * 0.5;
+		let mut side = (input.left - input.right) * 0.5;
+		if let Some(filter) = &mut self.side_filter {
+			side = filter.process(dt, Frame::from_mono(side), parameters).left;
+		}
+		let mid = mid * self.mid_gain.value() as Sample;
+		let side = side * self.side_gain.value() as Sample;
+		Frame::new(mid + side, mid - side)
+	}
+}
@@ -1,14 +1,24 @@
 //! Modifies audio in real time.
 
+#[cfg(all(feature = "clap", not(target_arch = "wasm32")))]
+pub mod clap;
 pub mod delay;
 pub mod distortion;
 pub mod filter;
 pub mod handle;
+pub mod mid_side;
+pub mod muffle;
+pub mod pitch_shift;
+pub mod rack;
 pub mod reverb;
+#[cfg(test)]
+mod tests;
+pub mod values;
 
 use handle::EffectHandle;
+use values::EffectValues;
 
-use std::fmt::Debug;
+use std::{any::Any, fmt::Debug};
 
 use uuid::Uuid;
 
@@ -105,6 +115,25 @@ pub trait Effect: Send + Debug {
 	/// This is called once when the effect is first added to a track.
 	fn init(&mut self, sample_rate: u32) {}
 
+	/// Returns the set of values the effect reads its settings from.
+	///
+	/// If this returns a set, the values are updated from their
+	/// parameters before each call to [`process`](Effect::process),
+	/// and they can be changed from the main thread using
+	/// [`EffectHandle::set_value`].
+	fn cached_values(&mut self) -> Option<&mut EffectValues> {
+		None
+	}
+
+	/// Receives a message sent from the main thread using
+	/// [`EffectHandle::send_message`].
+	///
+	/// Messages can be of any type, so the effect should
+	/// downcast the message to the types it understands and
+	/// ignore the rest. The message is dropped on the main
+	/// thread afterwards, unless the effect takes its contents.
+	fn on_message(&mut self, message: &mut dyn Any) {}
+
 	/// Transforms an input frame.
 	/// - `dt` is the time that's elapsed since the previous frame (in seconds)
 	/// - `input` is the input audio
@@ -113,3 +142,21 @@ pub trait Effect: Send + Debug {
 	/// which can respond to parameter changes and update their value accordingly.
 	fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame;
 }
+
+impl Effect for Box<dyn Effect> {
+	fn init(&mut self, sample_rate: u32) {
+		self.as_mut().init(sample_rate);
+	}
+
+	fn cached_values(&mut self) -> Option<&mut EffectValues> {
+		self.as_mut().cached_values()
+	}
+
+	fn on_message(&mut self, message: &mut dyn Any) {
+		self.as_mut().on_message(message);
+	}
+
+	fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
+		self.as_mut().process(dt, input, parameters)
+	}
+}
@@ -0,0 +1,198 @@
+//! Muffles audio, like it's coming from another room.
+//!
+//! A common use for this is making gameplay audio quieter and
+//! duller while a pause menu is open. [`AudioManager::add_muffle`]
+//! adds a [`Muffle`] effect to each of the given tracks, all
+//! controlled by a single [`MuffleHandle`]:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::{effect::muffle::MuffleSettings, SubTrackSettings, TrackIndex},
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let sfx_track = audio_manager.add_sub_track(SubTrackSettings::default())?;
+//! let music_track = audio_manager.add_sub_track(SubTrackSettings::default())?;
+//! let mut pause_muffle = audio_manager.add_muffle(
+//! 	&[sfx_track.id().into(), music_track.id().into()],
+//! 	MuffleSettings::default(),
+//! )?;
+//! // when the pause menu opens
+//! pause_muffle.muffle(Some(0.25.into()))?;
+//! // when the pause menu closes
+//! pause_muffle.unmuffle(Some(0.25.into()))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`AudioManager::add_muffle`]: crate::manager::AudioManager::add_muffle
+
+use crate::{
+	command::producer::CommandError,
+	frame::{Frame, Sample},
+	parameter::{handle::ParameterHandle, tween::Tween, ParameterId, Parameters},
+	value::{CachedValue, Value},
+};
+
+use super::{
+	filter::{Filter, FilterSettings},
+	Effect,
+};
+
+/// Settings for a [`Muffle`] effect.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct MuffleSettings {
+	/// How muffled the audio is, where 0 is unaffected
+	/// and 1 is fully muffled.
+	pub amount: Value<f64>,
+	/// The cutoff frequency of the low-pass filter when
+	/// the audio is fully muffled (in hertz).
+	pub cutoff: f64,
+	/// The volume of the audio when it's fully muffled.
+	pub volume: f64,
+}
+
+impl MuffleSettings {
+	/// Creates a new `MuffleSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets how muffled the audio is.
+	pub fn amount(self, amount: impl Into<Value<f64>>) -> Self {
+		Self {
+			amount: amount.into(),
+			..self
+		}
+	}
+
+	/// Sets the cutoff frequency of the low-pass filter when
+	/// the audio is fully muffled (in hertz).
+	pub fn cutoff(self, cutoff: f64) -> Self {
+		Self { cutoff, ..self }
+	}
+
+	/// Sets the volume of the audio when it's fully muffled.
+	pub fn volume(self, volume: f64) -> Self {
+		Self { volume, ..self }
+	}
+}
+
+impl Default for MuffleSettings {
+	fn default() -> Self {
+		Self {
+			amount: 1.0.into(),
+			cutoff: 500.0,
+			volume: 0.5,
+		}
+	}
+}
+
+/// An effect that filters out high frequencies and
+/// lowers the volume of audio.
+///
+/// When the amount is 0, the audio passes through unchanged.
+#[derive(Debug, Copy, Clone)]
+pub struct Muffle {
+	amount: CachedValue<f64>,
+	cutoff: f64,
+	volume: f64,
+	filter: Filter,
+}
+
+impl Muffle {
+	/// Creates a new muffle effect.
+	pub fn new(settings: MuffleSettings) -> Self {
+		Self {
+			amount: CachedValue::new(settings.amount, 1.0).with_valid_range(0.0..1.0),
+			cutoff: settings.cutoff,
+			volume: settings.volume,
+			filter: Filter::new(FilterSettings::new().cutoff(20000.0)),
+		}
+	}
+}
+
+impl Effect for Muffle {
+	fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
+		self.amount.update(parameters);
+		muffle_frame(
+			&mut self.filter,
+			dt,
+			input,
+			self.amount.value(),
+			self.cutoff,
+			self.volume,
+			parameters,
+		)
+	}
+}
+
+/// Muffles a frame of audio by the given amount, using a
+/// low-pass filter that should be kept for the next frame.
+pub(crate) fn muffle_frame(
+	filter: &mut Filter,
+	dt: f64,
+	input: Frame,
+	amount: f64,
+	cutoff: f64,
+	volume: f64,
+	parameters: &Parameters,
+) -> Frame {
+	// sweep the cutoff exponentially so the change in
+	// brightness sounds even across the whole fade
+	filter.set_cutoff(20000.0 * (cutoff / 20000.0).powf(amount));
+	// keep the filter running even when the effect is
+	// inaudible so it doesn't click when it fades back in
+	let filtered = filter.process(dt, input, parameters);
+	if amount <= 0.0 {
+		return input;
+	}
+	let wet = filtered * volume as Sample;
+	input + (wet - input) * amount as Sample
+}
+
+/// Controls muffle effects added with
+/// [`AudioManager::add_muffle`](crate::manager::AudioManager::add_muffle).
+#[derive(Debug, Clone)]
+pub struct MuffleHandle {
+	amount: ParameterHandle,
+}
+
+impl MuffleHandle {
+	pub(crate) fn new(amount: ParameterHandle) -> Self {
+		Self { amount }
+	}
+
+	/// Returns the ID of the parameter that controls how
+	/// muffled the tracks are.
+	pub fn parameter_id(&self) -> ParameterId {
+		self.amount.id()
+	}
+
+	/// Muffles the tracks.
+	pub fn muffle(&mut self, tween: impl Into<Option<Tween>>) -> Result<(), CommandError> {
+		self.amount.set(1.0, tween)
+	}
+
+	/// Returns the tracks to normal.
+	///
+	/// Once the tween is finished, audio passes through the
+	/// muffle effects unchanged.
+	pub fn unmuffle(&mut self, tween: impl Into<Option<Tween>>) -> Result<(), CommandError> {
+		self.amount.set(0.0, tween)
+	}
+
+	/// Sets how muffled the tracks are, where 0 is unaffected
+	/// and 1 is fully muffled.
+	pub fn set_amount(
+		&mut self,
+		amount: f64,
+		tween: impl Into<Option<Tween>>,
+	) -> Result<(), CommandError> {
+		self.amount.set(amount, tween)
+	}
+}
@@ -0,0 +1,169 @@
+//! Raises or lowers the pitch of audio without changing its speed.
+
+use std::f64::consts::TAU;
+
+use crate::{dsp, parameter::Parameters, CachedValue, Frame, Sample, Value};
+
+use super::Effect;
+
+/// The shortest window a [`PitchShift`] will use (in seconds).
+const MIN_WINDOW_SIZE: f64 = 0.001;
+
+/// Settings for a [`PitchShift`] effect.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct PitchShiftSettings {
+	/// The amount to shift the pitch by, as a factor of
+	/// the original pitch.
+	pitch: Value<f64>,
+	/// The length of the pieces of audio that are played
+	/// back at the shifted pitch (in seconds).
+	window_size: f64,
+}
+
+impl PitchShiftSettings {
+	/// Creates a new `PitchShiftSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the amount to shift the pitch by, as a factor of
+	/// the original pitch.
+	///
+	/// [`Semitones`](crate::Semitones) can be used to shift the
+	/// pitch by a musical interval.
+	pub fn pitch(self, pitch: impl Into<Value<f64>>) -> Self {
+		Self {
+			pitch: pitch.into(),
+			..self
+		}
+	}
+
+	/// Sets the length of the pieces of audio that are played
+	/// back at the shifted pitch (in seconds).
+	///
+	/// Shorter windows add less delay, and longer windows
+	/// sound smoother with low-pitched audio. Windows shorter
+	/// than a millisecond are lengthened to one millisecond.
+	pub fn window_size(self, window_size: f64) -> Self {
+		Self {
+			window_size,
+			..self
+		}
+	}
+}
+
+impl Default for PitchShiftSettings {
+	fn default() -> Self {
+		Self {
+			pitch: Value::Fixed(1.0),
+			window_size: 0.05,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+enum PitchShiftState {
+	Uninitialized,
+	Initialized {
+		buffer: Vec<Frame>,
+		write_position: usize,
+	},
+}
+
+/// An effect that shifts the pitch of audio independently
+/// of how fast it's played. Useful for disguising voices
+/// on a dialogue track.
+///
+/// The incoming audio is read back from two overlapping
+/// windows moving at the shifted speed, which are crossfaded
+/// so the seams between them can't be heard.
+#[derive(Debug, Clone)]
+pub struct PitchShift {
+	pitch: CachedValue<f64>,
+	window_size: f64,
+	phase: f64,
+	state: PitchShiftState,
+}
+
+impl PitchShift {
+	/// Creates a new pitch shift effect.
+	pub fn new(settings: PitchShiftSettings) -> Self {
+		Self {
+			pitch: CachedValue::new(settings.pitch, 1.0).with_min(0.0),
+			window_size: settings.window_size.max(MIN_WINDOW_SIZE),
+			phase: 0.0,
+			state: PitchShiftState::Uninitialized,
+		}
+	}
+}
+
+impl Effect for PitchShift {
+	fn init(&mut self, sample_rate: u32) {
+		if let PitchShiftState::Uninitialized = &self.state {
+			// leave room for the interpolation points on either
+			// side of the window
+			let buffer_length = (self.window_size * sample_rate as f64) as usize + 4;
+			self.state = PitchShiftState::Initialized {
+				buffer: vec![Frame::from_mono(0.0); buffer_length],
+				write_position: 0,
+			}
+		} else {
+			panic!("The pitch shift should be in the uninitialized state before init")
+		}
+	}
+
+	fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
+		if let PitchShiftState::Initialized {
+			buffer,
+			write_position,
+		} = &mut self.state
+		{
+			self.pitch.update(parameters);
+
+			// write input audio to the buffer
+			*write_position += 1;
+			*write_position %= buffer.len();
+			buffer[*write_position] = input;
+
+			// the delay of each window grows when the pitch goes
+			// down and shrinks when the pitch goes up
+			self.phase += (1.0 - self.pitch.value()) * dt / self.window_size;
+			self.phase = self.phase.rem_euclid(1.0);
+			let mut output = Frame::from_mono(0.0);
+			for offset in &[0.0, 0.5] {
+				let phase = (self.phase + offset) % 1.0;
+				let delay = phase * self.window_size / dt;
+				output += read_interpolated(buffer, *write_position, delay)
+					* (0.5 - 0.5 * (phase * TAU).cos()) as Sample;
+			}
+			output
+		} else {
+			panic!("The pitch shift should be initialized by the first process call")
+		}
+	}
+}
+
+/// Reads a frame from a circular buffer the given number
+/// of samples before the write position.
+fn read_interpolated(buffer: &[Frame], write_position: usize, delay: f64) -> Frame {
+	let mut read_position = write_position as f64 - delay;
+	while read_position < 0.0 {
+		read_position += buffer.len() as f64;
+	}
+	let current_sample_index = read_position as usize % buffer.len();
+	let previous_sample_index = (current_sample_index + buffer.len() - 1) % buffer.len();
+	let next_sample_index = (current_sample_index + 1) % buffer.len();
+	let next_sample_index_2 = (current_sample_index + 2) % buffer.len();
+	dsp::interpolate_frame(
+		buffer[previous_sample_index],
+		buffer[current_sample_index],
+		buffer[next_sample_index],
+		buffer[next_sample_index_2],
+		(read_position % 1.0) as Sample,
+	)
+}
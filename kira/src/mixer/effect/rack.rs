@@ -0,0 +1,140 @@
+//! Groups multiple effects into one unit.
+//!
+//! An [`EffectRack`] is an effect that runs its input through
+//! a chain of other effects in order. Because the rack is itself
+//! an effect, the whole chain can be added to any track, removed,
+//! or bypassed using a single [`EffectHandle`](super::handle::EffectHandle).
+//! Like other resources, the rack can be given a
+//! [name](crate::manager::names) so other parts of a game can
+//! find its handle later.
+//!
+//! To control multiple effects in the rack at once, give them
+//! [`Value`](crate::Value)s linked to the same
+//! [parameter](crate::parameter). Changing that parameter
+//! will act as a "macro" control for the whole rack:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::effect::{
+//! # 		filter::{Filter, FilterSettings},
+//! # 		rack::EffectRack,
+//! # 		reverb::{Reverb, ReverbSettings},
+//! # 		EffectSettings,
+//! # 	},
+//! # 	parameter::{Mapping, ParameterSettings},
+//! # 	Value,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let mut depth = audio_manager.add_parameter(ParameterSettings::new().value(0.0))?;
+//! let underwater = EffectRack::new()
+//! 	.effect(Filter::new(FilterSettings::new().cutoff(Value::Parameter(
+//! 		depth.id(),
+//! 		Mapping {
+//! 			input_range: (0.0, 1.0),
+//! 			output_range: (20000.0, 400.0),
+//! 			..Default::default()
+//! 		},
+//! 	))))
+//! 	.effect_with_mix(Reverb::new(ReverbSettings::default()), depth.id());
+//! let rack_handle = audio_manager
+//! 	.main_track()
+//! 	.add_effect(underwater, EffectSettings::default())?;
+//! audio_manager.set_name("underwater", &rack_handle)?;
+//! // dive in...
+//! depth.set(1.0, Some(2.0.into()))?;
+//! // ...and bypass the whole rack later
+//! if let Some(mut rack_handle) = audio_manager.effect("underwater") {
+//! 	rack_handle.set_enabled(false)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Messages sent to the rack's effect handle are passed on
+//! to every effect in the rack.
+
+use std::any::Any;
+
+use crate::{
+	frame::{Frame, Sample},
+	parameter::Parameters,
+	CachedValue, Value,
+};
+
+use super::Effect;
+
+#[derive(Debug)]
+struct RackSlot {
+	effect: Box<dyn Effect>,
+	mix: CachedValue<f64>,
+}
+
+/// An effect that processes audio through a series of other effects.
+#[derive(Debug, Default)]
+pub struct EffectRack {
+	slots: Vec<RackSlot>,
+}
+
+impl EffectRack {
+	/// Creates a new, empty effect rack.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds an effect to the end of the rack.
+	pub fn effect(self, effect: impl Effect + 'static) -> Self {
+		self.effect_with_mix(effect, 1.0)
+	}
+
+	/// Adds an effect to the end of the rack with the given
+	/// balance between dry (unaffected) signal and wet (affected)
+	/// signal. 0.0 is fully dry, 1.0 is fully wet.
+	pub fn effect_with_mix(
+		mut self,
+		effect: impl Effect + 'static,
+		mix: impl Into<Value<f64>>,
+	) -> Self {
+		self.slots.push(RackSlot {
+			effect: Box::new(effect),
+			mix: CachedValue::new(mix.into(), 1.0).with_valid_range(0.0..1.0),
+		});
+		self
+	}
+
+	/// Returns the number of effects in the rack.
+	pub fn len(&self) -> usize {
+		self.slots.len()
+	}
+
+	/// Returns `true` if the rack has no effects.
+	pub fn is_empty(&self) -> bool {
+		self.slots.is_empty()
+	}
+}
+
+impl Effect for EffectRack {
+	fn init(&mut self, sample_rate: u32) {
+		for slot in &mut self.slots {
+			slot.effect.init(sample_rate);
+		}
+	}
+
+	fn on_message(&mut self, message: &mut dyn Any) {
+		for slot in &mut self.slots {
+			slot.effect.on_message(message);
+		}
+	}
+
+	fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
+		let mut output = input;
+		for slot in &mut self.slots {
+			slot.mix.update(parameters);
+			if let Some(values) = slot.effect.cached_values() {
+				values.update(parameters);
+			}
+			let wet = slot.effect.process(dt, output, parameters);
+			output = output + (wet - output) * slot.mix.value() as Sample;
+		}
+		output
+	}
+}
@@ -3,7 +3,7 @@
 use all_pass::AllPassFilter;
 use comb::CombFilter;
 
-use crate::{CachedValue, Frame, Value};
+use crate::{CachedValue, Frame, Sample, Value};
 
 use super::Effect;
 
@@ -12,7 +12,7 @@ mod comb;
 
 const NUM_COMB_FILTERS: usize = 8;
 const NUM_ALL_PASS_FILTERS: usize = 4;
-const GAIN: f32 = 0.015;
+const GAIN: Sample = 0.015;
 const STEREO_SPREAD: usize = 23;
 
 /// Settings for a `Reverb`.
@@ -194,9 +194,9 @@ impl Effect for Reverb {
 			self.damping.update(parameters);
 			self.stereo_width.update(parameters);
 
-			let feedback = self.feedback.value() as f32;
-			let damping = self.damping.value() as f32;
-			let stereo_width = self.stereo_width.value() as f32;
+			let feedback = self.feedback.value() as Sample;
+			let damping = self.damping.value() as Sample;
+			let stereo_width = self.stereo_width.value() as Sample;
 
 			let mut output = Frame::from_mono(0.0);
 			let input = (input.left + input.right) * GAIN;
@@ -1,8 +1,10 @@
-const FEEDBACK: f32 = 0.5;
+use crate::Sample;
+
+const FEEDBACK: Sample = 0.5;
 
 #[derive(Debug)]
 pub struct AllPassFilter {
-	buffer: Vec<f32>,
+	buffer: Vec<Sample>,
 	current_index: usize,
 }
 
@@ -14,7 +16,7 @@ impl AllPassFilter {
 		}
 	}
 
-	pub fn process(&mut self, input: f32) -> f32 {
+	pub fn process(&mut self, input: Sample) -> Sample {
 		let buffer_output = self.buffer[self.current_index];
 		let output = -input + buffer_output;
 		self.buffer[self.current_index] = input + buffer_output * FEEDBACK;
@@ -1,7 +1,9 @@
+use crate::Sample;
+
 #[derive(Debug)]
 pub struct CombFilter {
-	filter_store: f32,
-	buffer: Vec<f32>,
+	filter_store: Sample,
+	buffer: Vec<Sample>,
 	current_index: usize,
 }
 
@@ -14,7 +16,7 @@ impl CombFilter {
 		}
 	}
 
-	pub fn process(&mut self, input: f32, feedback: f32, damp: f32) -> f32 {
+	pub fn process(&mut self, input: Sample, feedback: Sample, damp: Sample) -> Sample {
 		let output = self.buffer[self.current_index];
 		self.filter_store = output * (1.0 - damp) + self.filter_store * damp;
 		self.buffer[self.current_index] = input + self.filter_store * feedback;
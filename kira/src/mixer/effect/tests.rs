@@ -0,0 +1,247 @@
+use crate::{
+	audio_stream::AudioStream,
+	instance::InstanceSettings,
+	mixer::{SubTrackSettings, TrackIndex},
+	parameter::{tween::Tween, ParameterSettings},
+	sound::{Sound, SoundSettings},
+	testing::{create_manager, ConstantStream, CustomEffect, OffsetEffect, ScaleEffect},
+	CachedValue, Frame, Sample, Semitones,
+};
+
+use super::{
+	mid_side::{MidSide, MidSideSettings},
+	muffle::MuffleSettings,
+	pitch_shift::{PitchShift, PitchShiftSettings},
+	rack::EffectRack,
+	values::EffectValues,
+	EffectSettings,
+};
+
+#[test]
+fn controls_custom_effects() {
+	let (mut manager, mut backend) = create_manager();
+	let mut parameter = manager
+		.add_parameter(ParameterSettings::new().value(2.0))
+		.unwrap();
+	let mut values = EffectValues::new();
+	values.add(CachedValue::new(parameter.id().into(), 1.0));
+	let mut effect = manager
+		.main_track()
+		.add_effect(
+			CustomEffect {
+				values,
+				offset: 0.0,
+			},
+			EffectSettings::default(),
+		)
+		.unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Main)
+		.unwrap();
+	// values linked to parameters are updated automatically
+	assert_eq!(backend.process(), Frame::from_mono(2.0));
+	parameter.set(3.0, None).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(3.0));
+	// values can be changed from the main thread
+	effect.set_value(0, 4.0).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(4.0));
+	// messages the effect doesn't understand are ignored
+	effect.send_message("hello").unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(4.0));
+	effect.send_message(1.0f64).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(5.0));
+}
+
+#[test]
+fn shifts_the_pitch_of_track_audio() {
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	track
+		.add_effect(
+			PitchShift::new(PitchShiftSettings::new().pitch(Semitones(12.0))),
+			EffectSettings::default(),
+		)
+		.unwrap();
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			(0..48000)
+				.map(|i| {
+					Frame::from_mono(
+						(i as f64 / 48000.0 * 220.0 * std::f64::consts::TAU).sin() as Sample
+					)
+				})
+				.collect::<Vec<_>>(),
+			SoundSettings::new().default_track(TrackIndex::Sub(track.id())),
+		))
+		.unwrap();
+	sound_handle
+		.play(InstanceSettings::new().fade_in_tween(None))
+		.unwrap();
+	for _ in 0..4800 {
+		backend.process();
+	}
+	// count how many times the output crosses zero in half a second
+	let mut crossings = 0;
+	let mut previous = backend.process().left;
+	for _ in 0..24000 {
+		let current = backend.process().left;
+		if (previous < 0.0) != (current < 0.0) {
+			crossings += 1;
+		}
+		previous = current;
+	}
+	// a 440 Hz tone crosses zero 880 times per second
+	assert!((crossings as f64 - 440.0).abs() < 440.0 * 0.05);
+}
+
+#[test]
+fn lengthens_pitch_shift_windows_that_are_too_short() {
+	let (mut manager, mut backend) = create_manager();
+	for window_size in [0.0, -1.0, f64::NAN] {
+		let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+		track
+			.add_effect(
+				PitchShift::new(
+					PitchShiftSettings::new()
+						.pitch(Semitones(12.0))
+						.window_size(window_size),
+				),
+				EffectSettings::default(),
+			)
+			.unwrap();
+		manager
+			.add_stream(ConstantStream, TrackIndex::Sub(track.id()))
+			.unwrap();
+	}
+	for _ in 0..4800 {
+		let frame = backend.process();
+		assert!(frame.left.is_finite() && frame.right.is_finite());
+	}
+}
+
+#[test]
+fn changes_the_volume_of_mid_and_side_signals() {
+	#[derive(Debug)]
+	struct LeftStream;
+
+	impl AudioStream for LeftStream {
+		fn next(&mut self, _dt: f64) -> Frame {
+			Frame::new(1.0, 0.0)
+		}
+	}
+
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	track
+		.add_effect(
+			MidSide::new(MidSideSettings::new().side_gain(2.0)),
+			EffectSettings::default(),
+		)
+		.unwrap();
+	manager
+		.add_stream(LeftStream, TrackIndex::Sub(track.id()))
+		.unwrap();
+	// mid = 0.5, side = 0.5 * 2
+	assert_eq!(backend.process(), Frame::new(1.5, -0.5));
+
+	// the side signal can be removed below a cutoff frequency
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	track
+		.add_effect(
+			MidSide::new(MidSideSettings::new().side_gain(2.0).mono_cutoff(100.0)),
+			EffectSettings::default(),
+		)
+		.unwrap();
+	manager
+		.add_stream(LeftStream, TrackIndex::Sub(track.id()))
+		.unwrap();
+	let mut output = Frame::from_mono(0.0);
+	for _ in 0..48000 {
+		output = backend.process();
+	}
+	assert!((output.left - output.right).abs() < 0.001);
+}
+
+#[test]
+fn muffles_and_unmuffles_tracks() {
+	/// Alternates between 1 and 0, which is a steady level of
+	/// 0.5 plus a tone at the highest possible frequency.
+	#[derive(Debug)]
+	struct AlternatingStream(bool);
+
+	impl AudioStream for AlternatingStream {
+		fn next(&mut self, _dt: f64) -> Frame {
+			self.0 = !self.0;
+			Frame::from_mono(if self.0 { 1.0 } else { 0.0 })
+		}
+	}
+
+	let (mut manager, mut backend) = create_manager();
+	let track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	let mut muffle = manager
+		.add_muffle(&[track.id().into()], MuffleSettings::new().volume(0.5))
+		.unwrap();
+	manager
+		.add_stream(AlternatingStream(false), TrackIndex::Sub(track.id()))
+		.unwrap();
+	// the track starts out unmuffled
+	assert_eq!(backend.process(), Frame::from_mono(1.0));
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+	// once muffled, the tone is filtered out and the
+	// level is halved
+	muffle.muffle(None).unwrap();
+	for _ in 0..4800 {
+		backend.process();
+	}
+	for _ in 0..100 {
+		assert!((backend.process().left - 0.25).abs() < 0.01);
+	}
+	// unmuffling fades back to the unaffected audio
+	muffle.unmuffle(Tween::linear(0.1)).unwrap();
+	for _ in 0..2400 {
+		backend.process();
+	}
+	// halfway through the fade, half of the tone is back
+	let ripple = (backend.process().left - backend.process().left).abs();
+	assert!((ripple - 0.5).abs() < 0.05);
+	for _ in 0..2400 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::from_mono(1.0));
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+}
+
+#[test]
+fn effect_racks_process_their_effects_in_order() {
+	let (mut manager, mut backend) = create_manager();
+	let mut parameter = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	let rack = EffectRack::new()
+		.effect(OffsetEffect(1.0))
+		.effect(ScaleEffect(3.0))
+		.effect_with_mix(OffsetEffect(-2.0), parameter.id());
+	assert_eq!(rack.len(), 3);
+	let rack_handle = manager
+		.main_track()
+		.add_effect(rack, EffectSettings::default())
+		.unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Main)
+		.unwrap();
+	// (1 + 1) * 3 = 6, then half of the -2 offset
+	assert_eq!(backend.process(), Frame::from_mono(5.0));
+	// the mix of each effect in the rack follows its value
+	parameter.set(1.0, None).unwrap();
+	backend.process();
+	assert_eq!(backend.process(), Frame::from_mono(4.0));
+	// the whole rack can be found by name and bypassed at once
+	manager.set_name("underwater", &rack_handle).unwrap();
+	let mut rack_handle = manager.effect("underwater").unwrap();
+	assert_eq!(manager.name_of(&rack_handle), Some("underwater"));
+	rack_handle.set_enabled(false).unwrap();
+	backend.process();
+	assert_eq!(backend.process(), Frame::from_mono(1.0));
+}
@@ -0,0 +1,106 @@
+//! A set of values an effect can read on the audio thread.
+//!
+//! An effect that stores its settings in an [`EffectValues`]
+//! and returns it from [`Effect::cached_values`](super::Effect::cached_values)
+//! gets a few things for free:
+//! - Values linked to parameters are updated before every
+//!   call to [`Effect::process`](super::Effect::process).
+//! - Values can be changed from the main thread using
+//!   [`EffectHandle::set_value`](super::handle::EffectHandle::set_value).
+//!
+//! ```
+//! # use kira::{
+//! # 	mixer::effect::{values::EffectValues, Effect},
+//! # 	parameter::Parameters,
+//! # 	CachedValue, Frame, Sample,
+//! # };
+//! #[derive(Debug)]
+//! struct Gain {
+//! 	values: EffectValues,
+//! }
+//!
+//! impl Gain {
+//! 	const GAIN: usize = 0;
+//!
+//! 	fn new(gain: f64) -> Self {
+//! 		let mut values = EffectValues::new();
+//! 		values.add(CachedValue::new(gain.into(), 1.0));
+//! 		Self { values }
+//! 	}
+//! }
+//!
+//! impl Effect for Gain {
+//! 	fn cached_values(&mut self) -> Option<&mut EffectValues> {
+//! 		Some(&mut self.values)
+//! 	}
+//!
+//! 	fn process(&mut self, _dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+//! 		input * self.values.value(Self::GAIN) as Sample
+//! 	}
+//! }
+//! ```
+
+use crate::{parameter::Parameters, CachedValue, Value};
+
+/// A fixed-size set of [`CachedValue`]s owned by an effect.
+///
+/// Values are added when the effect is created on the
+/// main thread, and each value is referred to by the
+/// index it was added at.
+#[derive(Debug, Clone, Default)]
+pub struct EffectValues {
+	values: Vec<CachedValue<f64>>,
+}
+
+impl EffectValues {
+	/// Creates a new, empty set of values.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a value to the set and returns its index.
+	pub fn add(&mut self, value: CachedValue<f64>) -> usize {
+		self.values.push(value);
+		self.values.len() - 1
+	}
+
+	/// Returns the number of values in the set.
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	/// Returns `true` if the set has no values.
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// Returns the value at the given index, if it exists.
+	pub fn get(&self, index: usize) -> Option<&CachedValue<f64>> {
+		self.values.get(index)
+	}
+
+	/// Returns the current raw value at the given index.
+	///
+	/// # Panics
+	///
+	/// Panics if there's no value at the index.
+	pub fn value(&self, index: usize) -> f64 {
+		self.values[index].value()
+	}
+
+	/// Changes the setting of the value at the given index.
+	///
+	/// Does nothing if there's no value at the index.
+	pub fn set(&mut self, index: usize, value: Value<f64>) {
+		if let Some(cached_value) = self.values.get_mut(index) {
+			cached_value.set(value);
+		}
+	}
+
+	/// Updates any values that are linked to parameters.
+	pub fn update(&mut self, parameters: &Parameters) {
+		for value in &mut self.values {
+			value.update(parameters);
+		}
+	}
+}
@@ -1,6 +1,12 @@
+use std::any::Any;
+
 use basedrop::Owned;
 
-use crate::{frame::Frame, parameter::Parameters, CachedValue};
+use crate::{
+	frame::{Frame, Sample},
+	parameter::Parameters,
+	CachedValue, Value,
+};
 
 use super::effect::{Effect, EffectSettings};
 
@@ -19,11 +25,24 @@ impl EffectSlot {
 		}
 	}
 
-	pub(super) fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
+	pub fn set_value(&mut self, index: usize, value: Value<f64>) {
+		if let Some(values) = self.effect.cached_values() {
+			values.set(index, value);
+		}
+	}
+
+	pub fn receive_message(&mut self, message: &mut dyn Any) {
+		self.effect.on_message(message);
+	}
+
+	pub(crate) fn process(&mut self, dt: f64, input: Frame, parameters: &Parameters) -> Frame {
 		self.mix.update(parameters);
+		if let Some(values) = self.effect.cached_values() {
+			values.update(parameters);
+		}
 		if self.enabled {
 			let wet = self.effect.process(dt, input, parameters);
-			input + (wet - input) * self.mix.value() as f32
+			input + (wet - input) * self.mix.value() as Sample
 		} else {
 			input
 		}
@@ -0,0 +1,291 @@
+//! Measures the levels of mixer tracks to help with gain staging.
+//!
+//! Getting the volumes of different kinds of audio to sit well
+//! together without clipping the main output is tricky without
+//! a trained ear. Kira can measure how loud each mixer track
+//! actually gets while the game is being played and suggest
+//! how much to turn each track up or down to reach a
+//! [`HeadroomTarget`].
+//!
+//! Start an analysis with
+//! [`AudioManager::start_level_analysis`](crate::manager::AudioManager::start_level_analysis),
+//! play the game for a while, and then check the results with
+//! [`AudioManager::level_report`](crate::manager::AudioManager::level_report):
+//!
+//! ```no_run
+//! # use kira::{manager::{AudioManager, AudioManagerSettings}, mixer::{SubTrackSettings, levels::HeadroomTarget}};
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let music_track = audio_manager.add_sub_track(
+//! 	SubTrackSettings::new().headroom(HeadroomTarget::new().peak(-12.0).rms(-24.0)),
+//! )?;
+//! audio_manager.start_level_analysis()?;
+//! // play the game for a while...
+//! for report in audio_manager.level_report() {
+//! 	if let Some(trim) = report.suggested_trim {
+//! 		println!("{:?}: adjust the volume by {:.1} dB", report.track, trim);
+//! 	}
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Levels are measured at the output of each track, after its
+//! effects and volume have been applied. Silence isn't counted,
+//! so a track that's only used occasionally is measured by
+//! how loud it is when it's actually playing.
+
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Arc,
+};
+
+use atomic::Atomic;
+
+use crate::{dsp::amplitude_to_decibels, Frame};
+
+use super::TrackIndex;
+
+/// The quietest level (in dBFS) that isn't considered silence.
+const SILENCE_THRESHOLD: f64 = -96.0;
+/// The loudest level (in dBFS) the peak histogram can hold.
+const MAX_PEAK: f64 = 24.0;
+/// The size of each bin of the peak histogram (in decibels).
+const PEAK_BIN_SIZE: f64 = 1.0;
+const NUM_PEAK_BINS: usize = ((MAX_PEAK - SILENCE_THRESHOLD) / PEAK_BIN_SIZE) as usize;
+/// How long each window of audio is when measuring peaks (in seconds).
+const WINDOW_DURATION: f64 = 0.1;
+/// The fraction of windows whose peaks a track's typical
+/// peak is louder than.
+const TYPICAL_PEAK_PERCENTILE: f64 = 0.95;
+
+/// The levels a mixer track should stay under.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct HeadroomTarget {
+	/// The level (in dBFS) the track's typical peaks should reach.
+	pub peak: f64,
+	/// The level (in dBFS) the track's RMS loudness should reach.
+	pub rms: f64,
+}
+
+impl HeadroomTarget {
+	/// Creates a new `HeadroomTarget` with the default levels.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the level (in dBFS) the track's typical peaks
+	/// should reach.
+	pub fn peak(self, peak: f64) -> Self {
+		Self { peak, ..self }
+	}
+
+	/// Sets the level (in dBFS) the track's RMS loudness
+	/// should reach.
+	pub fn rms(self, rms: f64) -> Self {
+		Self { rms, ..self }
+	}
+}
+
+impl Default for HeadroomTarget {
+	fn default() -> Self {
+		Self {
+			peak: -6.0,
+			rms: -18.0,
+		}
+	}
+}
+
+/// The levels of a mixer track measured during an analysis.
+///
+/// All levels are in dBFS.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MeasuredLevels {
+	/// How loud the track's peaks usually get.
+	///
+	/// Short, unusually loud moments are ignored, so this is
+	/// a better guide for setting volumes than `max_peak`.
+	pub typical_peak: f64,
+	/// The loudest peak of the track.
+	pub max_peak: f64,
+	/// The RMS loudness of the track while it was playing audio.
+	pub rms: f64,
+	/// How long the track was playing audio (in seconds).
+	pub duration: f64,
+}
+
+/// The results of a level analysis for a mixer track.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrackLevelReport {
+	/// The track that was measured.
+	pub track: TrackIndex,
+	/// The headroom target of the track.
+	pub target: HeadroomTarget,
+	/// The measured levels of the track, or `None` if the
+	/// track was silent the whole time.
+	pub levels: Option<MeasuredLevels>,
+	/// How much the track's volume should be changed (in decibels)
+	/// so that neither its typical peak nor its RMS loudness
+	/// goes over the headroom target, or `None` if the track
+	/// was silent the whole time.
+	pub suggested_trim: Option<f64>,
+}
+
+impl TrackLevelReport {
+	pub(crate) fn new(track: TrackIndex, target: HeadroomTarget, levels: &TrackLevels) -> Self {
+		let levels = levels.measured();
+		Self {
+			track,
+			target,
+			levels,
+			suggested_trim: levels
+				.map(|levels| (target.peak - levels.typical_peak).min(target.rms - levels.rms)),
+		}
+	}
+}
+
+/// The levels of a track, written by the audio thread
+/// and read by the main thread.
+#[derive(Debug)]
+pub(crate) struct TrackLevels {
+	duration: Atomic<f64>,
+	sum_squares: Atomic<f64>,
+	max_peak: Atomic<f64>,
+	peak_counts: Vec<AtomicU64>,
+}
+
+impl TrackLevels {
+	fn measured(&self) -> Option<MeasuredLevels> {
+		let duration = self.duration.load(Ordering::Relaxed);
+		if duration <= 0.0 {
+			return None;
+		}
+		let peak_counts: Vec<u64> = self
+			.peak_counts
+			.iter()
+			.map(|count| count.load(Ordering::Relaxed))
+			.collect();
+		let num_windows: u64 = peak_counts.iter().sum();
+		let mut typical_peak = SILENCE_THRESHOLD;
+		let mut num_quieter_windows = 0;
+		for (i, count) in peak_counts.iter().enumerate() {
+			num_quieter_windows += count;
+			if num_quieter_windows as f64 >= num_windows as f64 * TYPICAL_PEAK_PERCENTILE {
+				typical_peak = SILENCE_THRESHOLD + (i + 1) as f64 * PEAK_BIN_SIZE;
+				break;
+			}
+		}
+		let max_peak = amplitude_to_decibels(self.max_peak.load(Ordering::Relaxed));
+		Some(MeasuredLevels {
+			typical_peak: typical_peak.min(max_peak),
+			max_peak,
+			rms: amplitude_to_decibels(
+				(self.sum_squares.load(Ordering::Relaxed) / duration).sqrt(),
+			),
+			duration,
+		})
+	}
+}
+
+impl Default for TrackLevels {
+	fn default() -> Self {
+		Self {
+			duration: Atomic::new(0.0),
+			sum_squares: Atomic::new(0.0),
+			max_peak: Atomic::new(0.0),
+			peak_counts: (0..NUM_PEAK_BINS).map(|_| AtomicU64::new(0)).collect(),
+		}
+	}
+}
+
+/// Measures the levels of a track on the audio thread.
+pub(crate) struct LevelMeter {
+	levels: Arc<TrackLevels>,
+	duration: f64,
+	sum_squares: f64,
+	max_peak: f64,
+	peak_counts: Vec<u64>,
+	window_time: f64,
+	window_sum_squares: f64,
+	window_peak: f64,
+}
+
+impl LevelMeter {
+	pub fn new(levels: Arc<TrackLevels>) -> Self {
+		Self {
+			levels,
+			duration: 0.0,
+			sum_squares: 0.0,
+			max_peak: 0.0,
+			peak_counts: vec![0; NUM_PEAK_BINS],
+			window_time: 0.0,
+			window_sum_squares: 0.0,
+			window_peak: 0.0,
+		}
+	}
+
+	pub fn levels(&self) -> &Arc<TrackLevels> {
+		&self.levels
+	}
+
+	/// Forgets all previous measurements.
+	pub fn reset(&mut self) {
+		self.duration = 0.0;
+		self.sum_squares = 0.0;
+		self.max_peak = 0.0;
+		for count in &mut self.peak_counts {
+			*count = 0;
+		}
+		self.window_time = 0.0;
+		self.window_sum_squares = 0.0;
+		self.window_peak = 0.0;
+		self.publish();
+	}
+
+	/// Measures a frame of the track's output.
+	// `Sample` is already `f64` with the `f64_processing` feature.
+	#[allow(clippy::useless_conversion)]
+	pub fn measure(&mut self, frame: Frame, dt: f64) {
+		let left = f64::from(frame.left);
+		let right = f64::from(frame.right);
+		self.window_peak = self.window_peak.max(left.abs()).max(right.abs());
+		self.window_sum_squares += (left * left + right * right) / 2.0 * dt;
+		self.window_time += dt;
+		if self.window_time >= WINDOW_DURATION {
+			self.finish_window();
+		}
+	}
+
+	fn finish_window(&mut self) {
+		let peak = amplitude_to_decibels(self.window_peak);
+		// silent windows are left out so that tracks that
+		// only play audio occasionally aren't measured as
+		// being quieter than they are
+		if peak >= SILENCE_THRESHOLD {
+			let bin =
+				(((peak - SILENCE_THRESHOLD) / PEAK_BIN_SIZE) as usize).min(NUM_PEAK_BINS - 1);
+			self.peak_counts[bin] += 1;
+			self.duration += self.window_time;
+			self.sum_squares += self.window_sum_squares;
+			self.max_peak = self.max_peak.max(self.window_peak);
+			self.publish();
+		}
+		self.window_time = 0.0;
+		self.window_sum_squares = 0.0;
+		self.window_peak = 0.0;
+	}
+
+	fn publish(&self) {
+		self.levels.duration.store(self.duration, Ordering::Relaxed);
+		self.levels
+			.sum_squares
+			.store(self.sum_squares, Ordering::Relaxed);
+		self.levels.max_peak.store(self.max_peak, Ordering::Relaxed);
+		for (shared_count, count) in self.levels.peak_counts.iter().zip(&self.peak_counts) {
+			shared_count.store(*count, Ordering::Relaxed);
+		}
+	}
+}
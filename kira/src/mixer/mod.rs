@@ -67,14 +67,47 @@
 //!        ▼
 //!   Output audio
 //! ```
+//!
+//! ## Multiple outputs
+//!
+//! For split-screen games, each player can have their own mix.
+//! A sub-track can be sent straight to another pair of channels
+//! of the audio device using [`SubTrackSettings::output`], so
+//! the same sounds can be played on each player's track without
+//! loading them twice or running multiple audio managers:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::SubTrackSettings,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! // player 1 hears the first two channels, player 2 the next two
+//! let player_1_track = audio_manager.add_sub_track(SubTrackSettings::new().output(0))?;
+//! let player_2_track = audio_manager.add_sub_track(SubTrackSettings::new().output(1))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
+pub mod config;
 pub mod effect;
 pub(crate) mod effect_slot;
+pub mod levels;
+#[cfg(test)]
+mod tests;
 mod track;
 
 pub use track::{
-	handle::{MainTrackHandle, SendTrackHandle, SubTrackHandle},
+	handle::{
+		AddEffectError, MainTrackHandle, MoveEffectError, RemoveEffectError, SendTrackHandle,
+		SubTrackHandle,
+	},
 	sends::TrackSends,
-	SendTrackId, SendTrackSettings, SubTrackId, SubTrackSettings, TrackIndex,
+	PauseTailBehavior, SendTrackId, SendTrackSettings, SubTrackId, SubTrackSettings, TrackIndex,
 };
 pub(crate) use track::{Track, TrackKind};
+
+/// The number of pairs of output channels sub-tracks can be
+/// sent to.
+///
+/// See [`SubTrackSettings::output`] for more info.
+pub const MAX_OUTPUTS: usize = 4;
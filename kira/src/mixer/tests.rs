@@ -0,0 +1,390 @@
+use crate::{
+	instance::{InstanceSettings, InstanceState, PauseInstanceSettings, StopInstanceSettings},
+	manager::{
+		error::{AddMixerConfigError, AddSubTrackError},
+		resources::ResourceKind,
+	},
+	parameter::Parameters,
+	sound::{Sound, SoundSettings},
+	testing::{constant_sound, create_manager, ConstantStream, OffsetEffect, ScaleEffect},
+	Frame, PanningLaw, Value,
+};
+
+use super::{
+	config::{BuiltInEffect, EffectConfig, MixerConfig, SendTrackConfig, SubTrackConfig},
+	effect::{Effect, EffectSettings},
+	levels::HeadroomTarget,
+	PauseTailBehavior, SendTrackId, SendTrackSettings, SubTrackId, SubTrackSettings, TrackIndex,
+	TrackSends,
+};
+
+/// An effect that keeps outputting the last sound it heard,
+/// like a reverb with an endless tail.
+#[derive(Debug)]
+struct HoldEffect {
+	held: Frame,
+}
+
+impl Effect for HoldEffect {
+	fn process(&mut self, _dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+		if input != Frame::from_mono(0.0) {
+			self.held = input;
+		}
+		self.held
+	}
+}
+
+#[test]
+fn pauses_instances_and_streams_on_a_track() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let instance_handle = sound_handle.play(InstanceSettings::default()).unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Main)
+		.unwrap();
+	backend.start_buffer();
+	backend.process();
+	backend.start_buffer();
+	assert_eq!(manager.num_voices(), 2);
+	manager
+		.pause_voices_on_track(
+			TrackIndex::Main,
+			PauseInstanceSettings::new().fade_tween(None),
+		)
+		.unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(0.0));
+	backend.start_buffer();
+	assert_eq!(manager.num_voices(), 0);
+	assert!(matches!(instance_handle.state(), InstanceState::Paused(_)));
+}
+
+#[test]
+fn controls_voices_with_track_handles() {
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(
+			1.0,
+			SoundSettings::new().default_track(&track),
+		))
+		.unwrap();
+	let instance_handle = sound_handle.play(InstanceSettings::default()).unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Main)
+		.unwrap();
+	backend.process();
+	track
+		.pause_voices(PauseInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert!(matches!(instance_handle.state(), InstanceState::Paused(_)));
+	// voices on other tracks aren't affected
+	backend.start_buffer();
+	assert_eq!(manager.num_voices(), 1);
+	track.resume_voices(Default::default()).unwrap();
+	backend.process();
+	assert_eq!(instance_handle.state(), InstanceState::Playing);
+	track
+		.stop_voices(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	backend.process();
+	assert_eq!(instance_handle.state(), InstanceState::Stopped);
+}
+
+#[test]
+fn applies_pause_tail_behaviors_to_track_effects() {
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager
+		.add_sub_track(SubTrackSettings::new().pause_tail(PauseTailBehavior::Freeze))
+		.unwrap();
+	track
+		.add_effect(
+			HoldEffect {
+				held: Frame::from_mono(0.0),
+			},
+			EffectSettings::default(),
+		)
+		.unwrap();
+	let track_index = TrackIndex::Sub(track.id());
+	manager.add_stream(ConstantStream, track_index).unwrap();
+	let mut process = |frames: usize| {
+		let mut output = Frame::from_mono(0.0);
+		for _ in 0..frames {
+			output = backend.process();
+		}
+		output
+	};
+	assert_eq!(process(1), Frame::from_mono(1.0));
+
+	// the track's own behavior freezes the effect right away
+	let pause_settings = PauseInstanceSettings::new().fade_tween(None);
+	manager
+		.pause_voices_on_track(track_index, pause_settings)
+		.unwrap();
+	assert_eq!(process(1), Frame::from_mono(0.0));
+	manager
+		.resume_voices_on_track(track_index, Default::default())
+		.unwrap();
+	assert_eq!(process(100), Frame::from_mono(1.0));
+
+	// letting the tail ring out keeps the held sound playing
+	manager
+		.pause_voices_on_track(
+			track_index,
+			pause_settings.pause_tail(PauseTailBehavior::RingOut),
+		)
+		.unwrap();
+	assert_eq!(process(100), Frame::from_mono(1.0));
+	manager
+		.resume_voices_on_track(track_index, Default::default())
+		.unwrap();
+	process(100);
+
+	// ringing out for a while and then freezing
+	manager
+		.pause_voices_on_track(
+			track_index,
+			pause_settings.pause_tail(PauseTailBehavior::FreezeAfter(0.01)),
+		)
+		.unwrap();
+	assert_eq!(process(100), Frame::from_mono(1.0));
+	assert_eq!(process(1000), Frame::from_mono(0.0));
+	manager
+		.resume_voices_on_track(track_index, Default::default())
+		.unwrap();
+	process(100);
+
+	// muting fades the tail out and back in
+	manager
+		.pause_voices_on_track(
+			track_index,
+			pause_settings.pause_tail(PauseTailBehavior::Mute),
+		)
+		.unwrap();
+	let fading_output = process(100);
+	assert!(fading_output.left > 0.0 && fading_output.left < 1.0);
+	assert_eq!(process(1000), Frame::from_mono(0.0));
+	manager
+		.resume_voices_on_track(track_index, Default::default())
+		.unwrap();
+	assert_eq!(process(1000), Frame::from_mono(1.0));
+}
+
+#[test]
+fn inserts_and_moves_track_effects() {
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Sub(track.id()))
+		.unwrap();
+	let offset = track
+		.add_effect(OffsetEffect(1.0), EffectSettings::default())
+		.unwrap();
+	// (1 + 1) * 3
+	let scale = track
+		.add_effect(ScaleEffect(3.0), EffectSettings::default())
+		.unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(6.0));
+	// (1 * 2 + 1) * 3
+	track
+		.insert_effect(0, ScaleEffect(2.0), EffectSettings::default())
+		.unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(9.0));
+	// 1 * 2 * 3 + 1
+	track.move_effect(offset.id(), 100).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(7.0));
+	// removing an effect keeps the others in order: 1 * 2 + 1
+	track.remove_effect(scale.id()).unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(3.0));
+}
+
+#[test]
+fn lists_the_settings_of_track_effects() {
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Sub(track.id()))
+		.unwrap();
+	let mut offset = track
+		.add_effect(OffsetEffect(1.0), EffectSettings::new().mix(0.5))
+		.unwrap();
+	let mut scale = track
+		.add_effect(ScaleEffect(2.0), EffectSettings::default())
+		.unwrap();
+	offset.set_mix(1.0).unwrap();
+	scale.set_enabled(false).unwrap();
+	track.move_effect(&scale, 0).unwrap();
+	let effects = track.effects();
+	assert_eq!(effects.len(), 2);
+	assert_eq!(effects[0].id, Some(scale.id()));
+	assert!(!effects[0].enabled);
+	assert_eq!(effects[1].id, Some(offset.id()));
+	assert!(matches!(effects[1].mix, Value::Fixed(mix) if mix == 1.0));
+	assert_eq!(backend.process(), Frame::from_mono(2.0));
+
+	// the saved settings can be used to rebuild the effect chain
+	track.remove_effect(&scale).unwrap();
+	track.remove_effect(&offset).unwrap();
+	track
+		.add_effect(ScaleEffect(2.0), effects[0].clone())
+		.unwrap();
+	track
+		.add_effect(OffsetEffect(1.0), effects[1].clone())
+		.unwrap();
+	assert_eq!(track.effects()[0].id, Some(scale.id()));
+	assert_eq!(backend.process(), Frame::from_mono(2.0));
+}
+
+#[test]
+fn adds_tracks_and_effects_from_a_mixer_config() {
+	let (mut manager, mut backend) = create_manager();
+	let reverb_track_id = SendTrackId::new();
+	let music_track_id = SubTrackId::new();
+	let config = MixerConfig {
+		main_track_effects: vec![EffectConfig::new(BuiltInEffect::Filter(Default::default()))],
+		send_tracks: vec![SendTrackConfig {
+			settings: SendTrackSettings::new().id(reverb_track_id),
+			effects: vec![EffectConfig::new(BuiltInEffect::Reverb(Default::default()))],
+		}],
+		sub_tracks: vec![
+			SubTrackConfig {
+				settings: SubTrackSettings::new()
+					.id(music_track_id)
+					.sends(TrackSends::new().add(reverb_track_id, 0.5)),
+				effects: vec![EffectConfig::new(BuiltInEffect::Delay(Default::default()))],
+			},
+			SubTrackConfig {
+				settings: SubTrackSettings::new().parent_track(music_track_id),
+				effects: vec![EffectConfig::new(BuiltInEffect::Distortion(
+					Default::default(),
+				))],
+			},
+		],
+	};
+	let handles = manager.add_mixer_config(&config).unwrap();
+	assert_eq!(handles.send_tracks[0].id(), reverb_track_id);
+	assert_eq!(handles.sub_tracks[0].id(), music_track_id);
+	assert_eq!(manager.resource_usage(ResourceKind::SendTrack).used, 1);
+	assert_eq!(manager.resource_usage(ResourceKind::SubTrack).used, 2);
+	backend.process();
+
+	// sub-tracks can't use later sub-tracks as their parent
+	let config = MixerConfig {
+		sub_tracks: vec![SubTrackConfig {
+			settings: SubTrackSettings::new().parent_track(SubTrackId::new()),
+			effects: vec![],
+		}],
+		..Default::default()
+	};
+	assert!(matches!(
+		manager.add_mixer_config(&config),
+		Err(AddMixerConfigError::AddSubTrackError(
+			AddSubTrackError::NonexistentParentTrack(_)
+		))
+	));
+}
+
+#[test]
+fn measures_track_levels_and_suggests_trims() {
+	let (mut manager, mut backend) = create_manager();
+	let quiet_track = manager
+		.add_sub_track(SubTrackSettings::new().headroom(HeadroomTarget::new().peak(-6.0).rms(-6.0)))
+		.unwrap();
+	let silent_track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			vec![Frame::new(0.25, 0.25); 48000],
+			SoundSettings::new().default_track(&quiet_track),
+		))
+		.unwrap();
+	// levels aren't measured until an analysis is started
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	for _ in 0..4800 {
+		backend.process();
+	}
+	assert_eq!(manager.level_report()[1].levels, None);
+
+	manager.start_level_analysis().unwrap();
+	for _ in 0..24000 {
+		backend.process();
+	}
+	let reports = manager.level_report();
+	assert_eq!(
+		reports
+			.iter()
+			.map(|report| report.track)
+			.collect::<Vec<_>>(),
+		vec![
+			TrackIndex::Main,
+			TrackIndex::Sub(quiet_track.id()),
+			TrackIndex::Sub(silent_track.id()),
+		]
+	);
+	// a constant signal has the same peak and RMS levels
+	let levels = reports[1].levels.unwrap();
+	assert!(levels.duration > 0.0);
+	assert!((levels.typical_peak - levels.max_peak).abs() < 1.0e-6);
+	assert!((levels.rms - levels.max_peak).abs() < 1.0e-6);
+	assert!(levels.max_peak < -6.0);
+	let trim = reports[1].suggested_trim.unwrap();
+	assert!((trim - (-6.0 - levels.rms)).abs() < 1.0e-6);
+	// the sub-track's output is also measured on the main track
+	assert_eq!(
+		reports[0].levels.map(|levels| levels.max_peak),
+		Some(levels.max_peak)
+	);
+	assert_eq!(reports[2].levels, None);
+	assert_eq!(reports[2].suggested_trim, None);
+
+	// removed tracks are left out of the report
+	manager.remove_sub_track(&silent_track).unwrap();
+	assert_eq!(manager.level_report().len(), 2);
+}
+
+#[test]
+fn changes_stereo_width_of_instances_and_tracks() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(Sound::from_frames(
+			48000,
+			vec![Frame::new(1.0, 0.5); 48000],
+			SoundSettings::new().cooldown(0.0),
+		))
+		.unwrap();
+	let mut instance_handle = sound_handle
+		.play(
+			InstanceSettings::new()
+				.panning_law(PanningLaw::Balance)
+				.width(0.0),
+		)
+		.unwrap();
+	assert_eq!(backend.process(), Frame::from_mono(0.75));
+	instance_handle.set_width(2.0).unwrap();
+	backend.process();
+	assert_eq!(backend.process(), Frame::new(1.25, 0.25));
+	manager.main_track().set_width(0.5).unwrap();
+	backend.process();
+	assert_eq!(backend.process(), Frame::new(1.0, 0.5));
+}
+
+#[test]
+#[cfg(feature = "f64_processing")]
+fn mixes_instances_with_f64_precision() {
+	let (mut manager, mut backend) = create_manager();
+	let mut loud_sound = manager
+		.add_sound(constant_sound(1.0, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let mut quiet_sound = manager
+		.add_sound(constant_sound(1.0e-12, SoundSettings::new().cooldown(0.0)))
+		.unwrap();
+	let settings = InstanceSettings::new().panning_law(PanningLaw::Balance);
+	loud_sound.play(settings).unwrap();
+	quiet_sound.play(settings).unwrap();
+	// an f32 sample can't tell 1 + 1e-12 apart from 1
+	let frame = backend.process();
+	assert_eq!(frame, Frame::from_mono(1.0 + 1.0e-12));
+	assert_ne!(frame, Frame::from_mono(1.0));
+}
@@ -1,15 +1,24 @@
 //! An interface for controlling mixer tracks.
 
+use std::sync::{
+	atomic::{AtomicU32, Ordering},
+	Arc, Mutex,
+};
+
 use basedrop::Owned;
-use indexmap::IndexSet;
+use indexmap::IndexMap;
 use thiserror::Error;
 
 use crate::{
 	command::{
 		producer::{CommandError, CommandProducer},
-		MixerCommand,
+		MixerCommand, VoiceCommand,
+	},
+	instance::{PauseInstanceSettings, ResumeInstanceSettings, StopInstanceSettings},
+	mixer::effect::{
+		handle::{lock_settings, EffectHandle},
+		Effect, EffectId, EffectSettings,
 	},
-	mixer::effect::{handle::EffectHandle, Effect, EffectId, EffectSettings},
 	Value,
 };
 
@@ -30,6 +39,10 @@ pub enum AddEffectError {
 	/// No effect with the specified ID exists on this track.
 	#[error("No effect with the specified ID exists on this track")]
 	NoEffectWithId(EffectId),
+	/// The audio manager has not been started yet, so the
+	/// effect cannot be initialized with a sample rate.
+	#[error("Cannot add an effect before the audio manager has been started")]
+	AudioManagerNotStarted,
 	/// A command could not be sent to the audio thread.
 	#[error("Could not send the command to the audio thread.")]
 	CommandProducerError(#[from] CommandError),
@@ -47,23 +60,35 @@ pub enum RemoveEffectError {
 	CommandProducerError(#[from] CommandError),
 }
 
+/// Something that can go wrong when using a [`TrackHandle`] to
+/// move an effect within a mixer track's effect chain.
+#[derive(Debug, Error)]
+pub enum MoveEffectError {
+	/// No effect with the specified ID exists on this track.
+	#[error("No effect with the specified ID exists on this track")]
+	NoEffectWithId(EffectId),
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
 /// Allows you to control the main mixer track.
 pub struct MainTrackHandle {
 	command_producer: CommandProducer,
-	active_effect_ids: IndexSet<EffectId>,
-	sample_rate: u32,
+	active_effects: IndexMap<EffectId, Arc<Mutex<EffectSettings>>>,
+	sample_rate: Arc<AtomicU32>,
 	resource_collector_handle: basedrop::Handle,
 }
 
 impl MainTrackHandle {
 	pub(crate) fn new(
 		command_producer: CommandProducer,
-		sample_rate: u32,
+		sample_rate: Arc<AtomicU32>,
 		resource_collector_handle: basedrop::Handle,
 	) -> Self {
 		Self {
 			command_producer,
-			active_effect_ids: IndexSet::with_capacity(MAIN_TRACK_NUM_EFFECTS),
+			active_effects: IndexMap::with_capacity(MAIN_TRACK_NUM_EFFECTS),
 			sample_rate,
 			resource_collector_handle,
 		}
@@ -77,23 +102,64 @@ impl MainTrackHandle {
 			.push(MixerCommand::SetTrackVolume(TrackIndex::Main, volume.into()).into())
 	}
 
+	/// Sets the stereo width of the main track.
+	///
+	/// 0 makes the output mono, 1 leaves it unchanged, and
+	/// values above 1 widen it.
+	pub fn set_width(&mut self, width: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackWidth(TrackIndex::Main, width.into()).into())
+	}
+
+	/// Mutes or unmutes the main track.
+	///
+	/// The track fades to silence over a few milliseconds
+	/// to avoid clicks.
+	pub fn set_muted(&mut self, muted: bool) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackMuted(TrackIndex::Main, muted).into())
+	}
+
+	/// Pauses every instance and audio stream playing on the main track.
+	pub fn pause_voices(&mut self, settings: PauseInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::PauseVoicesOnTrack(TrackIndex::Main, settings).into())
+	}
+
+	/// Resumes every instance and audio stream playing on the main track.
+	pub fn resume_voices(&mut self, settings: ResumeInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::ResumeVoicesOnTrack(TrackIndex::Main, settings).into())
+	}
+
+	/// Stops every instance and audio stream playing on the main track.
+	pub fn stop_voices(&mut self, settings: StopInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::StopVoicesOnTrack(TrackIndex::Main, settings).into())
+	}
+
 	/// Adds an effect to the track.
 	pub fn add_effect(
 		&mut self,
 		mut effect: impl Effect + 'static,
 		settings: EffectSettings,
 	) -> Result<EffectHandle, AddEffectError> {
-		if self.active_effect_ids.len() >= self.active_effect_ids.capacity() {
+		if self.active_effects.len() >= self.active_effects.capacity() {
 			return Err(AddEffectError::EffectLimitReached);
 		}
+		let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+		if sample_rate == 0 {
+			return Err(AddEffectError::AudioManagerNotStarted);
+		}
 		let effect_id = settings.id.unwrap_or(EffectId::new());
 		let handle = EffectHandle::new(
 			effect_id,
 			TrackIndex::Main,
 			&settings,
 			self.command_producer.clone(),
+			self.resource_collector_handle.clone(),
 		);
-		effect.init(self.sample_rate);
+		effect.init(sample_rate);
 		self.command_producer.push(
 			MixerCommand::AddEffect(
 				TrackIndex::Main,
@@ -103,28 +169,83 @@ impl MainTrackHandle {
 			)
 			.into(),
 		)?;
-		self.active_effect_ids.insert(effect_id);
+		self.active_effects
+			.insert(effect_id, handle.shared_settings());
 		Ok(handle)
 	}
 
 	/// Removes an effect from the track.
 	pub fn remove_effect(&mut self, id: impl Into<EffectId>) -> Result<(), RemoveEffectError> {
 		let id = id.into();
-		if !self.active_effect_ids.remove(&id) {
+		if self.active_effects.shift_remove(&id).is_none() {
 			return Err(RemoveEffectError::NoEffectWithId(id));
 		}
 		self.command_producer
 			.push(MixerCommand::RemoveEffect(TrackIndex::Main, id).into())?;
 		Ok(())
 	}
+
+	/// Adds an effect to the track at a position in its effect chain.
+	///
+	/// Effects are applied in order, starting with the effect at
+	/// index 0. If the index is past the end of the effect chain,
+	/// the effect is added to the end.
+	pub fn insert_effect(
+		&mut self,
+		index: usize,
+		effect: impl Effect + 'static,
+		settings: EffectSettings,
+	) -> Result<EffectHandle, AddEffectError> {
+		let handle = self.add_effect(effect, settings)?;
+		let index = index.min(self.active_effects.len() - 1);
+		self.active_effects
+			.move_index(self.active_effects.len() - 1, index);
+		self.command_producer
+			.push(MixerCommand::MoveEffect(TrackIndex::Main, handle.id(), index).into())?;
+		Ok(handle)
+	}
+
+	/// Moves an effect to a new position in the track's effect chain.
+	///
+	/// If the index is past the end of the effect chain,
+	/// the effect is moved to the end.
+	pub fn move_effect(
+		&mut self,
+		id: impl Into<EffectId>,
+		index: usize,
+	) -> Result<(), MoveEffectError> {
+		let id = id.into();
+		let current_index = self
+			.active_effects
+			.get_index_of(&id)
+			.ok_or(MoveEffectError::NoEffectWithId(id))?;
+		let index = index.min(self.active_effects.len() - 1);
+		self.active_effects.move_index(current_index, index);
+		self.command_producer
+			.push(MixerCommand::MoveEffect(TrackIndex::Main, id, index).into())?;
+		Ok(())
+	}
+
+	/// Returns the current settings of the effects on the track,
+	/// in the order they're applied.
+	///
+	/// The settings include the ID of each effect, which makes
+	/// it possible to save the state of the track's effect chain
+	/// and restore it later.
+	pub fn effects(&self) -> Vec<EffectSettings> {
+		self.active_effects
+			.values()
+			.map(|settings| lock_settings(settings).clone())
+			.collect()
+	}
 }
 
 /// Allows you to control a mixer sub-track.
 pub struct SubTrackHandle {
 	id: SubTrackId,
 	command_producer: CommandProducer,
-	active_effect_ids: IndexSet<EffectId>,
-	sample_rate: u32,
+	active_effects: IndexMap<EffectId, Arc<Mutex<EffectSettings>>>,
+	sample_rate: Arc<AtomicU32>,
 	resource_collector_handle: basedrop::Handle,
 }
 
@@ -133,13 +254,13 @@ impl SubTrackHandle {
 		id: SubTrackId,
 		settings: &SubTrackSettings,
 		command_producer: CommandProducer,
-		sample_rate: u32,
+		sample_rate: Arc<AtomicU32>,
 		resource_collector_handle: basedrop::Handle,
 	) -> Self {
 		Self {
 			id,
 			command_producer,
-			active_effect_ids: IndexSet::with_capacity(settings.num_effects),
+			active_effects: IndexMap::with_capacity(settings.num_effects),
 			sample_rate,
 			resource_collector_handle,
 		}
@@ -156,23 +277,74 @@ impl SubTrackHandle {
 			.push(MixerCommand::SetTrackVolume(self.id.into(), volume.into()).into())
 	}
 
+	/// Sets the stereo width of the track.
+	///
+	/// 0 makes the track's output mono, 1 leaves it unchanged,
+	/// and values above 1 widen it.
+	pub fn set_width(&mut self, width: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackWidth(self.id.into(), width.into()).into())
+	}
+
+	/// Mutes or unmutes the track.
+	///
+	/// The track fades to silence over a few milliseconds
+	/// to avoid clicks.
+	pub fn set_muted(&mut self, muted: bool) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackMuted(TrackIndex::Sub(self.id), muted).into())
+	}
+
+	/// Solos or unsolos the track.
+	///
+	/// While any sub-track is soloed, every sub-track that isn't
+	/// soloed is silenced, unless one of its parent tracks or
+	/// sub-tracks is soloed. Send tracks aren't affected.
+	pub fn set_soloed(&mut self, soloed: bool) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackSoloed(TrackIndex::Sub(self.id), soloed).into())
+	}
+
+	/// Pauses every instance and audio stream playing on the track.
+	pub fn pause_voices(&mut self, settings: PauseInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::PauseVoicesOnTrack(TrackIndex::Sub(self.id), settings).into())
+	}
+
+	/// Resumes every instance and audio stream playing on the track.
+	pub fn resume_voices(&mut self, settings: ResumeInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::ResumeVoicesOnTrack(TrackIndex::Sub(self.id), settings).into())
+	}
+
+	/// Stops every instance and audio stream playing on the track.
+	pub fn stop_voices(&mut self, settings: StopInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::StopVoicesOnTrack(TrackIndex::Sub(self.id), settings).into())
+	}
+
 	/// Adds an effect to the track.
 	pub fn add_effect(
 		&mut self,
 		mut effect: impl Effect + 'static,
 		settings: EffectSettings,
 	) -> Result<EffectHandle, AddEffectError> {
-		if self.active_effect_ids.len() >= self.active_effect_ids.capacity() {
+		if self.active_effects.len() >= self.active_effects.capacity() {
 			return Err(AddEffectError::EffectLimitReached);
 		}
+		let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+		if sample_rate == 0 {
+			return Err(AddEffectError::AudioManagerNotStarted);
+		}
 		let effect_id = settings.id.unwrap_or(EffectId::new());
 		let handle = EffectHandle::new(
 			effect_id,
 			self.id.into(),
 			&settings,
 			self.command_producer.clone(),
+			self.resource_collector_handle.clone(),
 		);
-		effect.init(self.sample_rate);
+		effect.init(sample_rate);
 		self.command_producer.push(
 			MixerCommand::AddEffect(
 				self.id.into(),
@@ -182,28 +354,83 @@ impl SubTrackHandle {
 			)
 			.into(),
 		)?;
-		self.active_effect_ids.insert(effect_id);
+		self.active_effects
+			.insert(effect_id, handle.shared_settings());
 		Ok(handle)
 	}
 
 	/// Removes an effect from the track.
 	pub fn remove_effect(&mut self, id: impl Into<EffectId>) -> Result<(), RemoveEffectError> {
 		let id = id.into();
-		if !self.active_effect_ids.remove(&id) {
+		if self.active_effects.shift_remove(&id).is_none() {
 			return Err(RemoveEffectError::NoEffectWithId(id));
 		}
 		self.command_producer
 			.push(MixerCommand::RemoveEffect(self.id.into(), id).into())?;
 		Ok(())
 	}
+
+	/// Adds an effect to the track at a position in its effect chain.
+	///
+	/// Effects are applied in order, starting with the effect at
+	/// index 0. If the index is past the end of the effect chain,
+	/// the effect is added to the end.
+	pub fn insert_effect(
+		&mut self,
+		index: usize,
+		effect: impl Effect + 'static,
+		settings: EffectSettings,
+	) -> Result<EffectHandle, AddEffectError> {
+		let handle = self.add_effect(effect, settings)?;
+		let index = index.min(self.active_effects.len() - 1);
+		self.active_effects
+			.move_index(self.active_effects.len() - 1, index);
+		self.command_producer
+			.push(MixerCommand::MoveEffect(self.id.into(), handle.id(), index).into())?;
+		Ok(handle)
+	}
+
+	/// Moves an effect to a new position in the track's effect chain.
+	///
+	/// If the index is past the end of the effect chain,
+	/// the effect is moved to the end.
+	pub fn move_effect(
+		&mut self,
+		id: impl Into<EffectId>,
+		index: usize,
+	) -> Result<(), MoveEffectError> {
+		let id = id.into();
+		let current_index = self
+			.active_effects
+			.get_index_of(&id)
+			.ok_or(MoveEffectError::NoEffectWithId(id))?;
+		let index = index.min(self.active_effects.len() - 1);
+		self.active_effects.move_index(current_index, index);
+		self.command_producer
+			.push(MixerCommand::MoveEffect(self.id.into(), id, index).into())?;
+		Ok(())
+	}
+
+	/// Returns the current settings of the effects on the track,
+	/// in the order they're applied.
+	///
+	/// The settings include the ID of each effect, which makes
+	/// it possible to save the state of the track's effect chain
+	/// and restore it later.
+	pub fn effects(&self) -> Vec<EffectSettings> {
+		self.active_effects
+			.values()
+			.map(|settings| lock_settings(settings).clone())
+			.collect()
+	}
 }
 
 /// Allows you to control a mixer send track.
 pub struct SendTrackHandle {
 	id: SendTrackId,
 	command_producer: CommandProducer,
-	active_effect_ids: IndexSet<EffectId>,
-	sample_rate: u32,
+	active_effects: IndexMap<EffectId, Arc<Mutex<EffectSettings>>>,
+	sample_rate: Arc<AtomicU32>,
 	resource_collector_handle: basedrop::Handle,
 }
 
@@ -212,13 +439,13 @@ impl SendTrackHandle {
 		id: SendTrackId,
 		settings: &SendTrackSettings,
 		command_producer: CommandProducer,
-		sample_rate: u32,
+		sample_rate: Arc<AtomicU32>,
 		resource_collector_handle: basedrop::Handle,
 	) -> Self {
 		Self {
 			id,
 			command_producer,
-			active_effect_ids: IndexSet::with_capacity(settings.num_effects),
+			active_effects: IndexMap::with_capacity(settings.num_effects),
 			sample_rate,
 			resource_collector_handle,
 		}
@@ -235,23 +462,73 @@ impl SendTrackHandle {
 			.push(MixerCommand::SetTrackVolume(self.id.into(), volume.into()).into())
 	}
 
+	/// Sets the stereo width of the track.
+	///
+	/// 0 makes the track's output mono, 1 leaves it unchanged,
+	/// and values above 1 widen it.
+	pub fn set_width(&mut self, width: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackWidth(self.id.into(), width.into()).into())
+	}
+
+	/// Mutes or unmutes the track.
+	///
+	/// The track fades to silence over a few milliseconds
+	/// to avoid clicks.
+	pub fn set_muted(&mut self, muted: bool) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackMuted(TrackIndex::Send(self.id), muted).into())
+	}
+
+	/// Solos or unsolos the track.
+	///
+	/// While any send track is soloed, every send track that
+	/// isn't soloed is silenced. Sub-tracks aren't affected.
+	pub fn set_soloed(&mut self, soloed: bool) -> Result<(), CommandError> {
+		self.command_producer
+			.push(MixerCommand::SetTrackSoloed(TrackIndex::Send(self.id), soloed).into())
+	}
+
+	/// Pauses every instance and audio stream playing on the track.
+	pub fn pause_voices(&mut self, settings: PauseInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::PauseVoicesOnTrack(TrackIndex::Send(self.id), settings).into())
+	}
+
+	/// Resumes every instance and audio stream playing on the track.
+	pub fn resume_voices(&mut self, settings: ResumeInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::ResumeVoicesOnTrack(TrackIndex::Send(self.id), settings).into())
+	}
+
+	/// Stops every instance and audio stream playing on the track.
+	pub fn stop_voices(&mut self, settings: StopInstanceSettings) -> Result<(), CommandError> {
+		self.command_producer
+			.push(VoiceCommand::StopVoicesOnTrack(TrackIndex::Send(self.id), settings).into())
+	}
+
 	/// Adds an effect to the track.
 	pub fn add_effect(
 		&mut self,
 		mut effect: impl Effect + 'static,
 		settings: EffectSettings,
 	) -> Result<EffectHandle, AddEffectError> {
-		if self.active_effect_ids.len() >= self.active_effect_ids.capacity() {
+		if self.active_effects.len() >= self.active_effects.capacity() {
 			return Err(AddEffectError::EffectLimitReached);
 		}
+		let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+		if sample_rate == 0 {
+			return Err(AddEffectError::AudioManagerNotStarted);
+		}
 		let effect_id = settings.id.unwrap_or(EffectId::new());
 		let handle = EffectHandle::new(
 			effect_id,
 			self.id.into(),
 			&settings,
 			self.command_producer.clone(),
+			self.resource_collector_handle.clone(),
 		);
-		effect.init(self.sample_rate);
+		effect.init(sample_rate);
 		self.command_producer.push(
 			MixerCommand::AddEffect(
 				self.id.into(),
@@ -261,18 +538,73 @@ impl SendTrackHandle {
 			)
 			.into(),
 		)?;
-		self.active_effect_ids.insert(effect_id);
+		self.active_effects
+			.insert(effect_id, handle.shared_settings());
 		Ok(handle)
 	}
 
 	/// Removes an effect from the track.
 	pub fn remove_effect(&mut self, id: impl Into<EffectId>) -> Result<(), RemoveEffectError> {
 		let id = id.into();
-		if !self.active_effect_ids.remove(&id) {
+		if self.active_effects.shift_remove(&id).is_none() {
 			return Err(RemoveEffectError::NoEffectWithId(id));
 		}
 		self.command_producer
 			.push(MixerCommand::RemoveEffect(self.id.into(), id).into())?;
 		Ok(())
 	}
+
+	/// Adds an effect to the track at a position in its effect chain.
+	///
+	/// Effects are applied in order, starting with the effect at
+	/// index 0. If the index is past the end of the effect chain,
+	/// the effect is added to the end.
+	pub fn insert_effect(
+		&mut self,
+		index: usize,
+		effect: impl Effect + 'static,
+		settings: EffectSettings,
+	) -> Result<EffectHandle, AddEffectError> {
+		let handle = self.add_effect(effect, settings)?;
+		let index = index.min(self.active_effects.len() - 1);
+		self.active_effects
+			.move_index(self.active_effects.len() - 1, index);
+		self.command_producer
+			.push(MixerCommand::MoveEffect(self.id.into(), handle.id(), index).into())?;
+		Ok(handle)
+	}
+
+	/// Moves an effect to a new position in the track's effect chain.
+	///
+	/// If the index is past the end of the effect chain,
+	/// the effect is moved to the end.
+	pub fn move_effect(
+		&mut self,
+		id: impl Into<EffectId>,
+		index: usize,
+	) -> Result<(), MoveEffectError> {
+		let id = id.into();
+		let current_index = self
+			.active_effects
+			.get_index_of(&id)
+			.ok_or(MoveEffectError::NoEffectWithId(id))?;
+		let index = index.min(self.active_effects.len() - 1);
+		self.active_effects.move_index(current_index, index);
+		self.command_producer
+			.push(MixerCommand::MoveEffect(self.id.into(), id, index).into())?;
+		Ok(())
+	}
+
+	/// Returns the current settings of the effects on the track,
+	/// in the order they're applied.
+	///
+	/// The settings include the ID of each effect, which makes
+	/// it possible to save the state of the track's effect chain
+	/// and restore it later.
+	pub fn effects(&self) -> Vec<EffectSettings> {
+		self.active_effects
+			.values()
+			.map(|settings| lock_settings(settings).clone())
+			.collect()
+	}
 }
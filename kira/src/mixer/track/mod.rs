@@ -4,22 +4,30 @@ pub mod settings;
 
 pub use settings::*;
 
+use std::sync::Arc;
+
 use basedrop::Owned;
 use handle::{SendTrackHandle, SubTrackHandle};
 use sends::TrackSends;
 use uuid::Uuid;
 
 use crate::{
-	frame::Frame, parameter::Parameters, static_container::index_map::StaticIndexMap, CachedValue,
-	Value,
+	frame::{Frame, Sample},
+	parameter::Parameters,
+	static_container::index_map::StaticIndexMap,
+	CachedValue, Value,
 };
 
 use super::{
 	effect::{Effect, EffectId, EffectSettings},
 	effect_slot::EffectSlot,
+	levels::{LevelMeter, TrackLevels},
 };
 
 pub(crate) const MAIN_TRACK_NUM_EFFECTS: usize = 8;
+/// How long it takes a track to fade to silence (and back)
+/// when it's muted (in seconds).
+const MUTE_FADE_DURATION: f64 = 0.01;
 
 /// A unique identifier for a sub-track.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -134,26 +142,53 @@ pub(crate) enum TrackKind {
 		id: SubTrackId,
 		parent_track: TrackIndex,
 		sends: TrackSends,
+		output: Option<usize>,
 	},
 	Send {
 		id: SendTrackId,
 	},
 }
 
+/// The state of a track whose voices are paused.
+#[derive(Debug, Copy, Clone)]
+struct TrackPause {
+	tail: PauseTailBehavior,
+	/// The time since the voices finished fading out (in seconds).
+	///
+	/// This is negative while they're still fading out.
+	time: f64,
+}
+
 pub(crate) struct Track {
 	kind: TrackKind,
 	volume: CachedValue<f64>,
+	width: CachedValue<f64>,
 	effect_slots: StaticIndexMap<EffectId, EffectSlot>,
 	input: Frame,
+	level_meter: LevelMeter,
+	pause_tail: PauseTailBehavior,
+	pause: Option<TrackPause>,
+	muted: bool,
+	soloed: bool,
+	silenced_by_solo: bool,
+	mute_volume: f64,
 }
 
 impl Track {
-	pub fn new_main_track() -> Self {
+	pub fn new_main_track(levels: Arc<TrackLevels>, pause_tail: PauseTailBehavior) -> Self {
 		Self {
 			kind: TrackKind::Main,
 			volume: CachedValue::new(Value::Fixed(1.0), 1.0),
+			width: CachedValue::new(Value::Fixed(1.0), 1.0).with_min(0.0),
 			effect_slots: StaticIndexMap::new(MAIN_TRACK_NUM_EFFECTS),
 			input: Frame::from_mono(0.0),
+			level_meter: LevelMeter::new(levels),
+			pause_tail,
+			pause: None,
+			muted: false,
+			soloed: false,
+			silenced_by_solo: false,
+			mute_volume: 1.0,
 		}
 	}
 
@@ -163,10 +198,19 @@ impl Track {
 				id,
 				parent_track: settings.parent_track,
 				sends: settings.sends,
+				output: settings.output,
 			},
 			volume: CachedValue::new(settings.volume, 1.0),
+			width: CachedValue::new(settings.width, 1.0).with_min(0.0),
 			effect_slots: StaticIndexMap::new(settings.num_effects),
 			input: Frame::from_mono(0.0),
+			level_meter: LevelMeter::new(Arc::new(TrackLevels::default())),
+			pause_tail: settings.pause_tail,
+			pause: None,
+			muted: false,
+			soloed: false,
+			silenced_by_solo: false,
+			mute_volume: 1.0,
 		}
 	}
 
@@ -174,8 +218,16 @@ impl Track {
 		Self {
 			kind: TrackKind::Send { id },
 			volume: CachedValue::new(settings.volume, 1.0),
+			width: CachedValue::new(settings.width, 1.0).with_min(0.0),
 			effect_slots: StaticIndexMap::new(settings.num_effects),
 			input: Frame::from_mono(0.0),
+			level_meter: LevelMeter::new(Arc::new(TrackLevels::default())),
+			pause_tail: settings.pause_tail,
+			pause: None,
+			muted: false,
+			soloed: false,
+			silenced_by_solo: false,
+			mute_volume: 1.0,
 		}
 	}
 
@@ -187,14 +239,37 @@ impl Track {
 		}
 	}
 
+	pub fn output(&self) -> Option<usize> {
+		match &self.kind {
+			TrackKind::Sub { output, .. } => *output,
+			_ => None,
+		}
+	}
+
 	pub fn kind(&self) -> &TrackKind {
 		&self.kind
 	}
 
+	pub fn levels(&self) -> &Arc<TrackLevels> {
+		self.level_meter.levels()
+	}
+
+	pub fn reset_levels(&mut self) {
+		self.level_meter.reset();
+	}
+
+	pub fn measure_levels(&mut self, output: Frame, dt: f64) {
+		self.level_meter.measure(output, dt);
+	}
+
 	pub fn set_volume(&mut self, volume: Value<f64>) {
 		self.volume.set(volume);
 	}
 
+	pub fn set_width(&mut self, width: Value<f64>) {
+		self.width.set(width);
+	}
+
 	pub fn add_effect(
 		&mut self,
 		id: EffectId,
@@ -209,24 +284,104 @@ impl Track {
 		self.effect_slots.get_mut(&id)
 	}
 
-	pub fn remove_effect(&mut self, id: EffectId) {
-		self.effect_slots.remove(&id);
+	pub fn remove_effect(&mut self, id: EffectId) -> Option<EffectSlot> {
+		self.effect_slots.shift_remove(&id)
+	}
+
+	pub fn move_effect(&mut self, id: EffectId, index: usize) {
+		if let Some(current_index) = self.effect_slots.get_index_of(&id) {
+			let index = index.min(self.effect_slots.len() - 1);
+			self.effect_slots.move_index(current_index, index);
+		}
 	}
 
 	pub fn add_input(&mut self, input: Frame) {
 		self.input += input;
 	}
 
+	/// Starts applying the pause tail behavior once the
+	/// voices on the track have faded out.
+	///
+	/// If `tail` is `None`, the track's own pause tail
+	/// behavior is used.
+	pub fn pause(&mut self, tail: Option<PauseTailBehavior>, fade_duration: f64) {
+		self.pause = Some(TrackPause {
+			tail: tail.unwrap_or(self.pause_tail),
+			time: -fade_duration,
+		});
+	}
+
+	pub fn resume(&mut self) {
+		self.pause = None;
+	}
+
+	/// Returns whether the track's effects should stop processing.
+	fn frozen(&self) -> bool {
+		match self.pause {
+			Some(TrackPause { tail, time }) => match tail {
+				PauseTailBehavior::Freeze => time >= 0.0,
+				PauseTailBehavior::FreezeAfter(duration) => time >= duration,
+				PauseTailBehavior::RingOut | PauseTailBehavior::Mute => false,
+			},
+			None => false,
+		}
+	}
+
+	pub fn set_muted(&mut self, muted: bool) {
+		self.muted = muted;
+	}
+
+	pub fn soloed(&self) -> bool {
+		self.soloed
+	}
+
+	pub fn set_soloed(&mut self, soloed: bool) {
+		self.soloed = soloed;
+	}
+
+	/// Sets whether the track is silenced because other
+	/// tracks are soloed.
+	pub fn set_silenced_by_solo(&mut self, silenced: bool) {
+		self.silenced_by_solo = silenced;
+	}
+
+	/// Returns whether the track's output should be silenced.
+	fn muted(&self) -> bool {
+		self.muted
+			|| self.silenced_by_solo
+			|| matches!(
+				self.pause,
+				Some(TrackPause {
+					tail: PauseTailBehavior::Mute,
+					time,
+				}) if time >= 0.0
+			)
+	}
+
 	pub fn process(&mut self, dt: f64, parameters: &Parameters) -> Frame {
 		self.volume.update(parameters);
+		self.width.update(parameters);
 		if let TrackKind::Sub { sends, .. } = &mut self.kind {
 			sends.update(parameters);
 		}
 		let mut input = self.input;
 		self.input = Frame::from_mono(0.0);
+		if let Some(pause) = &mut self.pause {
+			pause.time += dt;
+		}
+		if self.frozen() {
+			return Frame::from_mono(0.0);
+		}
 		for (_, effect_slot) in &mut self.effect_slots {
 			input = effect_slot.process(dt, input, parameters);
 		}
-		input * (self.volume.value() as f32)
+		input = input.with_width(self.width.value() as Sample);
+		let mute_volume_change = dt / MUTE_FADE_DURATION;
+		self.mute_volume = if self.muted() {
+			(self.mute_volume - mute_volume_change).max(0.0)
+		} else {
+			(self.mute_volume + mute_volume_change).min(1.0)
+		};
+		input * ((self.volume.value() * self.mute_volume) as Sample)
 	}
 }
@@ -5,7 +5,18 @@ use crate::{parameter::Parameters, CachedValue, Value};
 use super::SendTrackId;
 
 /// A mapping of send tracks to volume levels.
+///
+/// With the `serde_support` feature, this is serialized as a map
+/// of send track IDs to volume levels.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(
+		from = "IndexMap<SendTrackId, Value<f64>>",
+		into = "IndexMap<SendTrackId, Value<f64>>"
+	)
+)]
 pub struct TrackSends {
 	sends: IndexMap<SendTrackId, CachedValue<f64>>,
 }
@@ -42,3 +53,27 @@ impl TrackSends {
 		}
 	}
 }
+
+impl Default for TrackSends {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl From<IndexMap<SendTrackId, Value<f64>>> for TrackSends {
+	fn from(sends: IndexMap<SendTrackId, Value<f64>>) -> Self {
+		sends
+			.into_iter()
+			.fold(Self::new(), |sends, (id, volume)| sends.add(id, volume))
+	}
+}
+
+impl From<TrackSends> for IndexMap<SendTrackId, Value<f64>> {
+	fn from(sends: TrackSends) -> Self {
+		sends
+			.sends
+			.into_iter()
+			.map(|(id, volume)| (id, volume.setting()))
+			.collect()
+	}
+}
@@ -1,7 +1,37 @@
-use crate::Value;
+use crate::{mixer::levels::HeadroomTarget, Value};
 
 use super::{sends::TrackSends, SendTrackId, SubTrackId, TrackIndex};
 
+/// What happens to the effects on a mixer track while
+/// the voices playing on it are paused.
+///
+/// This applies when pausing voices with
+/// [`AudioManager::pause_voices_on_track`](crate::manager::AudioManager::pause_voices_on_track).
+/// Each behavior starts once the voices have faded out.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum PauseTailBehavior {
+	/// The effects keep running, so reverb and delay
+	/// tails ring out naturally.
+	#[default]
+	RingOut,
+	/// The effects stop processing, and they pick up where
+	/// they left off when the voices are resumed.
+	///
+	/// While the effects are frozen, the track is silent
+	/// and ignores any audio sent to it.
+	Freeze,
+	/// The effects ring out for the given number of seconds
+	/// and then freeze.
+	FreezeAfter(f64),
+	/// The track is silenced. The effects keep running, so
+	/// their tails are gone by the time the voices are resumed.
+	Mute,
+}
+
 /// Settings for a mixer sub-track.
 #[derive(Debug, Clone)]
 #[cfg_attr(
@@ -19,8 +49,30 @@ pub struct SubTrackSettings {
 	pub sends: TrackSends,
 	/// The volume of the track.
 	pub volume: Value<f64>,
+	/// The stereo width of the track.
+	///
+	/// 0 makes the track's output mono, 1 leaves it unchanged,
+	/// and values above 1 widen it.
+	pub width: Value<f64>,
 	/// The maximum number of effects this track can hold.
 	pub num_effects: usize,
+	/// The levels the track should stay under, used
+	/// when suggesting volume changes after a
+	/// [level analysis](crate::mixer::levels).
+	pub headroom: HeadroomTarget,
+	/// What happens to the track's effects while the
+	/// voices playing on it are paused.
+	pub pause_tail: PauseTailBehavior,
+	/// The pair of output channels this track will be sent to
+	/// directly, bypassing the main track.
+	///
+	/// Output 0 is the first two channels of the audio device
+	/// (where the main track is heard), output 1 is the next two
+	/// channels, and so on. If the device doesn't have enough
+	/// channels, the output is mixed into the first two channels.
+	///
+	/// This only affects tracks whose parent track is the main track.
+	pub output: Option<usize>,
 }
 
 impl SubTrackSettings {
@@ -59,6 +111,14 @@ impl SubTrackSettings {
 		}
 	}
 
+	/// Sets the stereo width of the track.
+	pub fn width(self, width: impl Into<Value<f64>>) -> Self {
+		Self {
+			width: width.into(),
+			..self
+		}
+	}
+
 	/// Sets the maximum number of effects this track can hold.
 	pub fn num_effects(self, num_effects: usize) -> Self {
 		Self {
@@ -66,6 +126,26 @@ impl SubTrackSettings {
 			..self
 		}
 	}
+
+	/// Sets the levels the track should stay under.
+	pub fn headroom(self, headroom: HeadroomTarget) -> Self {
+		Self { headroom, ..self }
+	}
+
+	/// Sets what happens to the track's effects while the
+	/// voices playing on it are paused.
+	pub fn pause_tail(self, pause_tail: PauseTailBehavior) -> Self {
+		Self { pause_tail, ..self }
+	}
+
+	/// Sets the pair of output channels this track will be sent
+	/// to directly, bypassing the main track.
+	pub fn output(self, output: usize) -> Self {
+		Self {
+			output: Some(output),
+			..self
+		}
+	}
 }
 
 impl Default for SubTrackSettings {
@@ -75,7 +155,11 @@ impl Default for SubTrackSettings {
 			parent_track: TrackIndex::Main,
 			sends: TrackSends::new(),
 			volume: Value::Fixed(1.0),
+			width: Value::Fixed(1.0),
 			num_effects: 10,
+			headroom: HeadroomTarget::default(),
+			pause_tail: PauseTailBehavior::default(),
+			output: None,
 		}
 	}
 }
@@ -92,8 +176,20 @@ pub struct SendTrackSettings {
 	pub id: Option<SendTrackId>,
 	/// The volume of the track.
 	pub volume: Value<f64>,
+	/// The stereo width of the track.
+	///
+	/// 0 makes the track's output mono, 1 leaves it unchanged,
+	/// and values above 1 widen it.
+	pub width: Value<f64>,
 	/// The maximum number of effects this track can hold.
 	pub num_effects: usize,
+	/// The levels the track should stay under, used
+	/// when suggesting volume changes after a
+	/// [level analysis](crate::mixer::levels).
+	pub headroom: HeadroomTarget,
+	/// What happens to the track's effects while the
+	/// voices playing on it are paused.
+	pub pause_tail: PauseTailBehavior,
 }
 
 impl SendTrackSettings {
@@ -118,6 +214,14 @@ impl SendTrackSettings {
 		}
 	}
 
+	/// Sets the stereo width of the track.
+	pub fn width(self, width: impl Into<Value<f64>>) -> Self {
+		Self {
+			width: width.into(),
+			..self
+		}
+	}
+
 	/// Sets the maximum number of effects this track can hold.
 	pub fn num_effects(self, num_effects: usize) -> Self {
 		Self {
@@ -125,6 +229,17 @@ impl SendTrackSettings {
 			..self
 		}
 	}
+
+	/// Sets the levels the track should stay under.
+	pub fn headroom(self, headroom: HeadroomTarget) -> Self {
+		Self { headroom, ..self }
+	}
+
+	/// Sets what happens to the track's effects while the
+	/// voices playing on it are paused.
+	pub fn pause_tail(self, pause_tail: PauseTailBehavior) -> Self {
+		Self { pause_tail, ..self }
+	}
 }
 
 impl Default for SendTrackSettings {
@@ -132,7 +247,10 @@ impl Default for SendTrackSettings {
 		Self {
 			id: None,
 			volume: Value::Fixed(1.0),
+			width: Value::Fixed(1.0),
 			num_effects: 10,
+			headroom: HeadroomTarget::default(),
+			pause_tail: PauseTailBehavior::default(),
 		}
 	}
 }
@@ -0,0 +1,218 @@
+//! Plays one of several variations of a sound.
+//!
+//! Footsteps, gunshots, and impacts get repetitive quickly
+//! when the same recording plays every time. A [`MultiSound`]
+//! holds several recordings of the same sound and picks one
+//! of them each time it's played, optionally with a slightly
+//! different volume and playback rate:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	multi_sound::{MultiSound, MultiSoundSelection, MultiSoundVariation},
+//! # 	sound::SoundSettings,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let mut footsteps = MultiSound::new(MultiSoundSelection::RandomNoRepeat);
+//! for path in &["step-1.ogg", "step-2.ogg", "step-3.ogg"] {
+//! 	let sound = audio_manager.load_sound(path, SoundSettings::default())?;
+//! 	footsteps.add_variation(
+//! 		MultiSoundVariation::new(&sound)
+//! 			.volume(0.8, 1.0)
+//! 			.playback_rate(0.95, 1.05),
+//! 	);
+//! }
+//! // every time the player takes a step
+//! footsteps.play(InstanceSettings::new())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Like the [`Jukebox`](crate::jukebox::Jukebox), a multi-sound
+//! lives on the main thread. To pick from several sounds in a
+//! sequence, use
+//! [`Sequence::play_random`](crate::sequence::Sequence::play_random)
+//! instead.
+
+use rand::{Rng, RngCore};
+
+use crate::{
+	instance::{handle::InstanceHandle, InstanceSettings},
+	sound::handle::SoundHandle,
+	CommandError, Value,
+};
+
+/// How a [`MultiSound`] picks which variation to play.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum MultiSoundSelection {
+	/// Play the variations in order, starting over from the
+	/// first variation after the last one.
+	RoundRobin,
+	/// Pick a random variation each time.
+	Random,
+	/// Pick a random variation each time, but never the same
+	/// variation twice in a row.
+	#[default]
+	RandomNoRepeat,
+}
+
+/// One of the sounds a [`MultiSound`] can play.
+#[derive(Debug, Clone)]
+pub struct MultiSoundVariation {
+	/// The sound to play.
+	pub sound: SoundHandle,
+	/// The range the volume of the sound is randomly
+	/// picked from.
+	pub volume: (f64, f64),
+	/// The range the playback rate of the sound is randomly
+	/// picked from.
+	pub playback_rate: (f64, f64),
+}
+
+impl MultiSoundVariation {
+	/// Creates a variation that always plays the sound
+	/// at its normal volume and playback rate.
+	pub fn new(sound: &SoundHandle) -> Self {
+		Self {
+			sound: sound.clone(),
+			volume: (1.0, 1.0),
+			playback_rate: (1.0, 1.0),
+		}
+	}
+
+	/// Sets the range the volume of the sound is randomly
+	/// picked from.
+	pub fn volume(self, min: f64, max: f64) -> Self {
+		Self {
+			volume: (min, max),
+			..self
+		}
+	}
+
+	/// Sets the range the playback rate of the sound is randomly
+	/// picked from.
+	pub fn playback_rate(self, min: f64, max: f64) -> Self {
+		Self {
+			playback_rate: (min, max),
+			..self
+		}
+	}
+}
+
+impl From<&SoundHandle> for MultiSoundVariation {
+	fn from(sound: &SoundHandle) -> Self {
+		Self::new(sound)
+	}
+}
+
+/// Several variations of a sound that can be played
+/// like a single sound.
+#[derive(Debug, Clone)]
+pub struct MultiSound {
+	selection: MultiSoundSelection,
+	variations: Vec<MultiSoundVariation>,
+	last_variation: Option<usize>,
+}
+
+impl MultiSound {
+	/// Creates a new multi-sound with no variations.
+	pub fn new(selection: MultiSoundSelection) -> Self {
+		Self {
+			selection,
+			variations: vec![],
+			last_variation: None,
+		}
+	}
+
+	/// Adds a variation to the multi-sound.
+	pub fn add_variation(&mut self, variation: impl Into<MultiSoundVariation>) -> &mut Self {
+		self.variations.push(variation.into());
+		self
+	}
+
+	/// Returns the number of variations in the multi-sound.
+	pub fn num_variations(&self) -> usize {
+		self.variations.len()
+	}
+
+	/// Returns the index of the variation that was played last.
+	pub fn last_variation(&self) -> Option<usize> {
+		self.last_variation
+	}
+
+	/// Plays one of the variations, or returns `None` if the
+	/// multi-sound has no variations.
+	///
+	/// The volume and playback rate picked for the variation
+	/// are multiplied with the volume and playback rate of
+	/// the instance settings. Values linked to a parameter
+	/// are left as they are.
+	pub fn play(
+		&mut self,
+		settings: InstanceSettings,
+	) -> Result<Option<InstanceHandle>, CommandError> {
+		let index = match self.pick_variation() {
+			Some(index) => index,
+			None => return Ok(None),
+		};
+		self.last_variation = Some(index);
+		let variation = &mut self.variations[index];
+		let (volume, playback_rate) = variation.sound.rng().with(|rng| {
+			(
+				pick_in_range(variation.volume, rng),
+				pick_in_range(variation.playback_rate, rng),
+			)
+		});
+		let settings = InstanceSettings {
+			volume: scale(settings.volume, volume),
+			playback_rate: scale(settings.playback_rate, playback_rate),
+			..settings
+		};
+		Ok(Some(variation.sound.play(settings)?))
+	}
+
+	fn pick_variation(&self) -> Option<usize> {
+		let num_variations = self.variations.len();
+		if num_variations == 0 {
+			return None;
+		}
+		// every handle shares the audio manager's generator,
+		// so seeding the audio manager makes the choices
+		// reproducible
+		let rng = self.variations[0].sound.rng();
+		Some(match (self.selection, self.last_variation) {
+			(MultiSoundSelection::RoundRobin, Some(last)) => (last + 1) % num_variations,
+			(MultiSoundSelection::RoundRobin, None) => 0,
+			(MultiSoundSelection::RandomNoRepeat, Some(last)) if num_variations > 1 => {
+				// pick from every variation but the last one
+				let index = rng.with(|rng| rng.gen_range(0..num_variations - 1));
+				if index >= last {
+					index + 1
+				} else {
+					index
+				}
+			}
+			_ => rng.with(|rng| rng.gen_range(0..num_variations)),
+		})
+	}
+}
+
+fn pick_in_range((min, max): (f64, f64), rng: &mut dyn RngCore) -> f64 {
+	if min < max {
+		rng.gen_range(min..max)
+	} else {
+		min
+	}
+}
+
+fn scale(value: Value<f64>, factor: f64) -> Value<f64> {
+	match value {
+		Value::Fixed(value) => Value::Fixed(value * factor),
+		Value::Random(lower, upper) => Value::Random(lower * factor, upper * factor),
+		Value::Parameter(..) => value,
+	}
+}
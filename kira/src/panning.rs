@@ -0,0 +1,98 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::{Frame, Sample};
+
+/// How a frame of audio is moved to the left or right
+/// when it's panned.
+///
+/// Every law keeps the loudness of the audio the same no
+/// matter how far it's panned, but they treat stereo audio
+/// differently:
+///
+/// ```
+/// # use kira::{Frame, PanningLaw};
+/// let stereo = Frame::new(1.0, 0.5);
+/// // the right channel is turned down
+/// assert_eq!(stereo.panned_with(0.0, PanningLaw::Balance), Frame::new(1.0, 0.0));
+/// // the right channel is moved into the left channel
+/// assert_eq!(stereo.panned_with(0.0, PanningLaw::TruePan), Frame::new(1.5, 0.0));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum PanningLaw {
+	/// Each channel is turned down as the audio is panned
+	/// away from it, so centered audio is 3 dB quieter than
+	/// the original.
+	///
+	/// This works best for mono audio, since a mono sound
+	/// panned anywhere is as loud as it is when panned to
+	/// one side.
+	#[default]
+	ConstantPower,
+	/// The channel on the opposite side of the panning
+	/// direction is turned down, and the other channel is
+	/// left alone, so centered audio keeps its original
+	/// volume.
+	///
+	/// This is like the balance knob on a stereo, and works
+	/// best for stereo audio that should keep its original
+	/// mix when it's centered.
+	Balance,
+	/// The channel on the opposite side of the panning
+	/// direction is gradually mixed into the other channel,
+	/// so centered audio keeps its original volume and no
+	/// part of the audio is lost when it's panned to one side.
+	///
+	/// This is how Web Audio's `StereoPannerNode` pans
+	/// stereo audio.
+	TruePan,
+}
+
+impl Frame {
+	/// Pans a frame to the left or right using a [`PanningLaw`].
+	///
+	/// An `x` of 0 represents a hard left panning, an `x` of 1
+	/// represents a hard right panning.
+	// `Sample` is already `f64` with the `f64_processing` feature.
+	#[allow(clippy::useless_conversion)]
+	pub fn panned_with(self, x: Sample, law: PanningLaw) -> Self {
+		match law {
+			PanningLaw::ConstantPower => self.panned(x),
+			PanningLaw::Balance => {
+				if x < 0.5 {
+					let (_, gain) = constant_power_gains(f64::from(x * 2.0));
+					Self::new(self.left, self.right * gain as Sample)
+				} else {
+					let (gain, _) = constant_power_gains(f64::from(x * 2.0 - 1.0));
+					Self::new(self.left * gain as Sample, self.right)
+				}
+			}
+			PanningLaw::TruePan => {
+				if x < 0.5 {
+					let (to_left, to_right) = constant_power_gains(f64::from(x * 2.0));
+					Self::new(
+						self.left + self.right * to_left as Sample,
+						self.right * to_right as Sample,
+					)
+				} else {
+					let (to_left, to_right) = constant_power_gains(f64::from(x * 2.0 - 1.0));
+					Self::new(
+						self.left * to_left as Sample,
+						self.right + self.left * to_right as Sample,
+					)
+				}
+			}
+		}
+	}
+}
+
+/// Returns the left and right gains for a constant-power
+/// pan, where an `amount` of 0 is fully left and 1 is
+/// fully right.
+fn constant_power_gains(amount: f64) -> (f64, f64) {
+	let angle = amount.clamp(0.0, 1.0) * FRAC_PI_2;
+	(angle.cos(), angle.sin())
+}
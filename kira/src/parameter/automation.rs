@@ -0,0 +1,206 @@
+use crate::{dsp::lerp, metronome::MetronomeId};
+
+use super::tween::{EaseDirection, Easing};
+
+/// A point in an [`Automation`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct AutomationPoint {
+	/// When the parameter should reach the value, relative
+	/// to the start of the automation.
+	///
+	/// This is in seconds, or in beats if the automation
+	/// is synced to a metronome.
+	pub time: f64,
+	/// The value of the parameter at this point.
+	pub value: f64,
+	/// The curve the parameter follows from the previous
+	/// point to this one.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub easing: Easing,
+	/// What direction the curve is applied in.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub ease_direction: EaseDirection,
+}
+
+/**
+A series of values a parameter moves through over time.
+
+Once an automation is started with
+[`ParameterHandle::automate`](super::handle::ParameterHandle::automate),
+the audio thread moves the parameter through each point on its
+own, so complex movements don't require sending a new command
+every frame.
+
+```no_run
+# use kira::{
+# 	manager::{AudioManager, AudioManagerSettings},
+# 	parameter::{tween::{EaseDirection, Easing}, Automation, ParameterSettings},
+# };
+# let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+let mut parameter = audio_manager.add_parameter(ParameterSettings::new())?;
+// a filter sweep that rises over 4 beats, falls over 4 beats,
+// and repeats forever
+let metronome = audio_manager.add_metronome(Default::default())?;
+parameter.automate(
+	Automation::new()
+		.point(0.0, 0.0)
+		.eased_point(4.0, 1.0, Easing::PowI(2), EaseDirection::InOut)
+		.point(8.0, 0.0)
+		.loop_start(0.0)
+		.metronome(&metronome),
+)?;
+# Ok::<(), Box<dyn std::error::Error>>(())
+```
+
+Setting the parameter with
+[`ParameterHandle::set`](super::handle::ParameterHandle::set)
+stops the automation.
+*/
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct Automation {
+	/// The points of the automation, in order of time.
+	pub points: Vec<AutomationPoint>,
+	/// Whether the automation should loop, and if so, the time
+	/// it should jump back to after it reaches the last point.
+	pub loop_start: Option<f64>,
+	/// The metronome the automation is synced to, if any.
+	///
+	/// If this is set, the times of the points are in beats,
+	/// and the automation only progresses while the
+	/// metronome is ticking.
+	pub metronome: Option<MetronomeId>,
+}
+
+impl Automation {
+	/// Creates a new, empty automation.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a point the parameter moves to linearly
+	/// from the previous point.
+	pub fn point(self, time: f64, value: f64) -> Self {
+		self.eased_point(time, value, Easing::Linear, EaseDirection::In)
+	}
+
+	/// Adds a point the parameter moves to from the previous
+	/// point following an easing curve.
+	pub fn eased_point(
+		mut self,
+		time: f64,
+		value: f64,
+		easing: Easing,
+		ease_direction: EaseDirection,
+	) -> Self {
+		let index = self
+			.points
+			.iter()
+			.position(|point| point.time > time)
+			.unwrap_or(self.points.len());
+		self.points.insert(
+			index,
+			AutomationPoint {
+				time,
+				value,
+				easing,
+				ease_direction,
+			},
+		);
+		self
+	}
+
+	/// Makes the automation loop back to the given time
+	/// after it reaches the last point.
+	pub fn loop_start(self, loop_start: f64) -> Self {
+		Self {
+			loop_start: Some(loop_start),
+			..self
+		}
+	}
+
+	/// Syncs the automation to a metronome.
+	pub fn metronome(self, metronome: impl Into<MetronomeId>) -> Self {
+		Self {
+			metronome: Some(metronome.into()),
+			..self
+		}
+	}
+
+	/// Sorts the points by time.
+	pub(crate) fn sort_points(&mut self) {
+		self.points.sort_by(|a, b| {
+			a.time
+				.partial_cmp(&b.time)
+				.unwrap_or(std::cmp::Ordering::Equal)
+		});
+	}
+
+	/// Returns the value of the automation at the given time.
+	fn value(&self, time: f64) -> Option<f64> {
+		let first = self.points.first()?;
+		if time <= first.time {
+			return Some(first.value);
+		}
+		for window in self.points.windows(2) {
+			let (previous, next) = (window[0], window[1]);
+			if time < next.time {
+				let t = (time - previous.time) / (next.time - previous.time);
+				return Some(lerp(
+					previous.value,
+					next.value,
+					next.easing.ease(next.ease_direction, t),
+				));
+			}
+		}
+		self.points.last().map(|point| point.value)
+	}
+}
+
+/// The playback state of an [`Automation`] on the audio thread.
+pub(crate) struct AutomationPlayer {
+	automation: Automation,
+	time: f64,
+}
+
+impl AutomationPlayer {
+	pub fn new(automation: Automation) -> Self {
+		Self {
+			automation,
+			time: 0.0,
+		}
+	}
+
+	pub fn metronome(&self) -> Option<MetronomeId> {
+		self.automation.metronome
+	}
+
+	/// Moves the automation forward by the given amount of time
+	/// (or beats) and returns the new value of the parameter
+	/// and whether the automation is finished.
+	pub fn update(&mut self, delta_time: f64) -> (Option<f64>, bool) {
+		self.time += delta_time;
+		let end_time = match self.automation.points.last() {
+			Some(point) => point.time,
+			None => return (None, true),
+		};
+		let mut finished = false;
+		if self.time >= end_time {
+			match self.automation.loop_start {
+				Some(loop_start) if loop_start < end_time => {
+					self.time = loop_start + (self.time - loop_start) % (end_time - loop_start);
+				}
+				_ => finished = true,
+			}
+		}
+		(self.automation.value(self.time), finished)
+	}
+}
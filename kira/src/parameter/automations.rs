@@ -0,0 +1,55 @@
+use basedrop::Owned;
+
+use crate::{metronome::Metronomes, static_container::index_map::StaticIndexMap};
+
+use super::{automation::AutomationPlayer, ParameterId, Parameters};
+
+/// The automations that are currently moving parameters.
+pub(crate) struct Automations {
+	automations: StaticIndexMap<ParameterId, Owned<AutomationPlayer>>,
+}
+
+impl Automations {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			automations: StaticIndexMap::new(capacity),
+		}
+	}
+
+	/// Starts an automation, replacing any automation
+	/// the parameter already had.
+	pub fn start(&mut self, id: ParameterId, automation: Owned<AutomationPlayer>) {
+		self.automations.remove(&id);
+		self.automations.try_insert(id, automation).ok();
+	}
+
+	pub fn stop(&mut self, id: ParameterId) {
+		self.automations.remove(&id);
+	}
+
+	/// Moves each automation forward and sets the values
+	/// of the parameters they control.
+	pub fn update(&mut self, dt: f64, parameters: &mut Parameters, metronomes: &Metronomes) {
+		let mut i = 0;
+		while i < self.automations.len() {
+			let (id, automation) = self.automations.get_index_mut(i).unwrap();
+			let id = *id;
+			let delta_time = match automation.metronome() {
+				Some(metronome) => match metronomes.get(metronome) {
+					Some(metronome) => metronome.effective_tempo().0 / 60.0 * dt,
+					None => 0.0,
+				},
+				None => dt,
+			};
+			let (value, finished) = automation.update(delta_time);
+			if let Some(value) = value {
+				parameters.set(id, value);
+			}
+			if finished {
+				self.automations.remove(&id);
+			} else {
+				i += 1;
+			}
+		}
+	}
+}
@@ -0,0 +1,170 @@
+use basedrop::Owned;
+
+use crate::static_container::index_map::StaticIndexMap;
+
+use super::{Mapping, ParameterId, Parameters};
+
+/// How the inputs of a derived parameter are combined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Combine {
+	/// The inputs are added together.
+	#[default]
+	Sum,
+	/// The inputs are multiplied together.
+	Product,
+	/// The smallest input is used.
+	Min,
+	/// The largest input is used.
+	Max,
+}
+
+/**
+Settings for a parameter whose value is computed from
+other parameters.
+
+Derived parameters are updated on the audio thread every frame,
+so combinations like "master SFX volume × distance attenuation"
+don't have to be recalculated on the gameplay thread.
+
+```no_run
+# use kira::{
+# 	instance::InstanceSettings,
+# 	manager::{AudioManager, AudioManagerSettings},
+# 	parameter::{DerivedParameterSettings, ParameterSettings},
+# 	sound::SoundSettings,
+# };
+# let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+let master_sfx_volume = audio_manager.add_parameter(ParameterSettings::new().value(1.0))?;
+let attenuation = audio_manager.add_parameter(ParameterSettings::new().value(1.0))?;
+let volume = audio_manager.add_derived_parameter(
+	DerivedParameterSettings::product()
+		.input(&master_sfx_volume)
+		.input(&attenuation),
+)?;
+let mut sound = audio_manager.load_sound("footstep.ogg", SoundSettings::default())?;
+sound.play(InstanceSettings::new().volume(&volume))?;
+# Ok::<(), Box<dyn std::error::Error>>(())
+```
+*/
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct DerivedParameterSettings {
+	/// The unique identifier for the parameter.
+	pub id: Option<ParameterId>,
+	/// How the inputs are combined.
+	pub combine: Combine,
+	/// The parameters the value is computed from, each
+	/// with a mapping that's applied before combining them.
+	pub inputs: Vec<(ParameterId, Mapping)>,
+	/// A value added to the result after combining the inputs.
+	pub offset: f64,
+}
+
+impl DerivedParameterSettings {
+	/// Creates a new `DerivedParameterSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates settings for a parameter that's the sum
+	/// of its inputs.
+	pub fn sum() -> Self {
+		Self::new().combine(Combine::Sum)
+	}
+
+	/// Creates settings for a parameter that's the product
+	/// of its inputs.
+	pub fn product() -> Self {
+		Self::new().combine(Combine::Product)
+	}
+
+	/// Sets the unique identifier for the parameter.
+	pub fn id(self, id: impl Into<ParameterId>) -> Self {
+		Self {
+			id: Some(id.into()),
+			..self
+		}
+	}
+
+	/// Sets how the inputs are combined.
+	pub fn combine(self, combine: Combine) -> Self {
+		Self { combine, ..self }
+	}
+
+	/// Adds a parameter to compute the value from.
+	pub fn input(self, parameter: impl Into<ParameterId>) -> Self {
+		self.mapped_input(parameter, Mapping::default())
+	}
+
+	/// Adds a parameter to compute the value from, transforming
+	/// its value with a mapping first.
+	pub fn mapped_input(mut self, parameter: impl Into<ParameterId>, mapping: Mapping) -> Self {
+		self.inputs.push((parameter.into(), mapping));
+		self
+	}
+
+	/// Sets a value added to the result after combining the inputs.
+	pub fn offset(self, offset: f64) -> Self {
+		Self { offset, ..self }
+	}
+
+	/// Computes the value from the current values of the inputs,
+	/// or returns `None` if none of the inputs exist.
+	fn value(&self, parameters: &Parameters) -> Option<f64> {
+		let mut values = self.inputs.iter().filter_map(|(id, mapping)| {
+			parameters
+				.get(*id)
+				.map(|parameter| mapping.map(parameter.value()))
+		});
+		let first = values.next()?;
+		let combined = values.fold(first, |combined, value| match self.combine {
+			Combine::Sum => combined + value,
+			Combine::Product => combined * value,
+			Combine::Min => combined.min(value),
+			Combine::Max => combined.max(value),
+		});
+		Some(combined + self.offset)
+	}
+}
+
+/// The derived parameters that are updated on the audio thread.
+pub(crate) struct DerivedParameters {
+	derivations: StaticIndexMap<ParameterId, Owned<DerivedParameterSettings>>,
+}
+
+impl DerivedParameters {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			derivations: StaticIndexMap::new(capacity),
+		}
+	}
+
+	pub fn add(&mut self, id: ParameterId, settings: Owned<DerivedParameterSettings>) {
+		self.derivations.try_insert(id, settings).ok();
+	}
+
+	pub fn remove(&mut self, id: ParameterId) {
+		self.derivations.shift_remove(&id);
+	}
+
+	/// Sets the value of each derived parameter.
+	///
+	/// Derived parameters are updated in the order they were
+	/// added, so a derived parameter can use the value of
+	/// another derived parameter that was added before it.
+	pub fn update(&mut self, parameters: &mut Parameters) {
+		for (id, settings) in &self.derivations {
+			if let Some(value) = settings.value(parameters) {
+				parameters.set(*id, value);
+			}
+		}
+	}
+}
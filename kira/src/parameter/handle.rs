@@ -1,24 +1,35 @@
-//! An interface for controlling parameters.
+//! Interfaces for controlling parameters and modulations.
 
-use crate::command::{
-	producer::{CommandError, CommandProducer},
-	ParameterCommand,
+use basedrop::Owned;
+
+use crate::{
+	command::{
+		producer::{CommandError, CommandProducer},
+		ParameterCommand,
+	},
+	StartTime,
 };
 
-use super::{tween::Tween, ParameterId};
+use super::{tween::Tween, Automation, AutomationPlayer, ModulationId, ParameterId};
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 /// Allows you to control a parameter.
 pub struct ParameterHandle {
 	id: ParameterId,
 	command_producer: CommandProducer,
+	resource_collector_handle: basedrop::Handle,
 }
 
 impl ParameterHandle {
-	pub(crate) fn new(id: ParameterId, command_producer: CommandProducer) -> Self {
+	pub(crate) fn new(
+		id: ParameterId,
+		command_producer: CommandProducer,
+		resource_collector_handle: basedrop::Handle,
+	) -> Self {
 		Self {
 			id,
 			command_producer,
+			resource_collector_handle,
 		}
 	}
 
@@ -29,7 +40,77 @@ impl ParameterHandle {
 
 	/// Sets the parameter to a value with an optional tween.
 	pub fn set(&mut self, value: f64, tween: impl Into<Option<Tween>>) -> Result<(), CommandError> {
+		self.set_at(value, tween, StartTime::Immediate)
+	}
+
+	/// Sets the parameter to a value with an optional tween
+	/// once the start time is reached.
+	pub fn set_at(
+		&mut self,
+		value: f64,
+		tween: impl Into<Option<Tween>>,
+		start_time: StartTime,
+	) -> Result<(), CommandError> {
+		self.command_producer
+			.push(ParameterCommand::SetParameter(self.id, value, tween.into(), start_time).into())
+	}
+
+	/// Starts moving the parameter through an [`Automation`],
+	/// replacing any automation that's already running.
+	pub fn automate(&mut self, mut automation: Automation) -> Result<(), CommandError> {
+		automation.sort_points();
+		let player = Owned::new(
+			&self.resource_collector_handle,
+			AutomationPlayer::new(automation),
+		);
+		self.command_producer
+			.push(ParameterCommand::StartAutomation(self.id, player).into())
+	}
+
+	/// Stops the parameter's automation, leaving the
+	/// parameter at its current value.
+	pub fn stop_automation(&mut self) -> Result<(), CommandError> {
+		self.command_producer
+			.push(ParameterCommand::StopAutomation(self.id).into())
+	}
+}
+
+impl std::fmt::Debug for ParameterHandle {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ParameterHandle")
+			.field("id", &self.id)
+			.field("command_producer", &self.command_producer)
+			.finish()
+	}
+}
+
+#[derive(Debug, Clone)]
+/// Allows you to control a modulation.
+pub struct ModulationHandle {
+	id: ModulationId,
+	command_producer: CommandProducer,
+}
+
+impl ModulationHandle {
+	pub(crate) fn new(id: ModulationId, command_producer: CommandProducer) -> Self {
+		Self {
+			id,
+			command_producer,
+		}
+	}
+
+	/// Returns the ID of the modulation.
+	pub fn id(&self) -> ModulationId {
+		self.id
+	}
+
+	/// Sets the depth of the modulation with an optional tween.
+	pub fn set_depth(
+		&mut self,
+		depth: f64,
+		tween: impl Into<Option<Tween>>,
+	) -> Result<(), CommandError> {
 		self.command_producer
-			.push(ParameterCommand::SetParameter(self.id, value, tween.into()).into())
+			.push(ParameterCommand::SetModulationDepth(self.id, depth, tween.into()).into())
 	}
 }
@@ -1,4 +1,68 @@
+use crate::dsp::{decibels_to_amplitude, inverse_lerp, lerp};
+
+use super::tween::{EaseDirection, Easing};
+
+/// The shape of the curve a [`Mapping`] follows from the
+/// start of its output range to the end.
+#[derive(Debug, Copy, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum MappingCurve {
+	/// The output changes at a constant rate.
+	#[default]
+	Linear,
+	/// The output is multiplied by the same amount for each
+	/// step of the input, which sounds even for frequencies
+	/// like filter cutoffs.
+	///
+	/// Both ends of the output range should be positive.
+	Exponential,
+	/// The opposite of [`Exponential`](MappingCurve::Exponential):
+	/// each time the input is multiplied by the same amount, the
+	/// output changes by the same amount. This is useful for
+	/// turning a frequency into a linear value.
+	///
+	/// Both ends of the input range should be positive.
+	Logarithmic,
+	/// The output range is in decibels, and the output is the
+	/// corresponding amplitude, so the input can be used as a
+	/// volume that sounds like it changes evenly.
+	///
+	/// Use a finite number of decibels, like `-60.0`, for the
+	/// quiet end of the output range.
+	Decibels,
+	/// The output follows an easing curve. Inputs outside
+	/// of the input range are treated as the nearest end
+	/// of the range.
+	Eased(Easing, EaseDirection),
+}
+
 /// A transformation from one range of values to another.
+///
+/// Curves other than [`Linear`](MappingCurve::Linear) make a
+/// 0-1 parameter, like the intensity of the music, drive
+/// settings in a way that sounds natural:
+///
+/// ```
+/// # use kira::parameter::{Mapping, MappingCurve};
+/// // a volume that fades evenly from -60 dB to 0 dB
+/// let volume = Mapping {
+/// 	output_range: (-60.0, 0.0),
+/// 	curve: MappingCurve::Decibels,
+/// 	..Default::default()
+/// };
+/// assert!((volume.map(0.5) - 0.001f64.sqrt()).abs() < 1.0e-9);
+/// assert_eq!(volume.map(1.0), 1.0);
+/// // a filter cutoff that sweeps evenly from 100 Hz to 10,000 Hz
+/// let cutoff = Mapping {
+/// 	output_range: (100.0, 10_000.0),
+/// 	curve: MappingCurve::Exponential,
+/// 	..Default::default()
+/// };
+/// assert!((cutoff.map(0.5) - 1000.0).abs() < 1.0e-9);
+/// ```
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(
 	feature = "serde_support",
@@ -16,6 +80,9 @@ pub struct Mapping {
 	/// Whether values should be prevented from being
 	/// greater than the top of the output range.
 	pub clamp_top: bool,
+	/// The shape of the curve from the start of the output
+	/// range to the end.
+	pub curve: MappingCurve,
 }
 
 impl Default for Mapping {
@@ -25,6 +92,7 @@ impl Default for Mapping {
 			output_range: (0.0, 1.0),
 			clamp_bottom: false,
 			clamp_top: false,
+			curve: MappingCurve::default(),
 		}
 	}
 }
@@ -32,16 +100,38 @@ impl Default for Mapping {
 impl Mapping {
 	/// Transforms an input value to an output value using this mapping.
 	pub fn map(&self, input: f64) -> f64 {
-		let relative_input =
-			(input - self.input_range.0) / (self.input_range.1 - self.input_range.0);
-		let mut output =
-			self.output_range.0 + (self.output_range.1 - self.output_range.0) * relative_input;
+		let mut output = self.output_at(self.relative_input(input));
 		if self.clamp_bottom {
-			output = output.max(self.output_range.0);
+			output = output.max(self.output_at(0.0));
 		}
 		if self.clamp_top {
-			output = output.min(self.output_range.1);
+			output = output.min(self.output_at(1.0));
 		}
 		output
 	}
+
+	/// Returns how far through the input range an input is,
+	/// where 0 is the start of the range and 1 is the end.
+	fn relative_input(&self, input: f64) -> f64 {
+		let (start, end) = self.input_range;
+		match self.curve {
+			MappingCurve::Logarithmic => (input / start).ln() / (end / start).ln(),
+			MappingCurve::Eased(..) => inverse_lerp(start, end, input).clamp(0.0, 1.0),
+			_ => inverse_lerp(start, end, input),
+		}
+	}
+
+	/// Returns the output at a point in the output range,
+	/// where 0 is the start of the range and 1 is the end.
+	fn output_at(&self, amount: f64) -> f64 {
+		let (start, end) = self.output_range;
+		match self.curve {
+			MappingCurve::Linear | MappingCurve::Logarithmic => lerp(start, end, amount),
+			MappingCurve::Exponential => start * (end / start).powf(amount),
+			MappingCurve::Decibels => decibels_to_amplitude(lerp(start, end, amount)),
+			MappingCurve::Eased(easing, direction) => {
+				lerp(start, end, easing.ease(direction, amount))
+			}
+		}
+	}
 }
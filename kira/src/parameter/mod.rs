@@ -1,12 +1,25 @@
 //! Tweenable values that can be used by many other objects.
 
+mod automation;
+mod automations;
+mod derived;
 pub mod handle;
 mod mapping;
+mod modulation;
 mod parameter;
 mod parameters;
+#[cfg(test)]
+mod tests;
 pub mod tween;
 
-pub use mapping::Mapping;
+pub(crate) use automation::AutomationPlayer;
+pub use automation::{Automation, AutomationPoint};
+pub(crate) use automations::Automations;
+pub(crate) use derived::DerivedParameters;
+pub use derived::{Combine, DerivedParameterSettings};
+pub use mapping::{Mapping, MappingCurve};
+pub(crate) use modulation::Modulation;
+pub use modulation::{ModulationId, ModulationSettings};
 pub(crate) use parameter::Parameter;
 pub use parameter::{ParameterId, ParameterSettings};
 pub use parameters::Parameters;
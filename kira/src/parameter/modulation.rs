@@ -0,0 +1,94 @@
+use uuid::Uuid;
+
+use super::{handle::ModulationHandle, Parameter, ParameterId};
+
+/// A unique identifier for a modulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(transparent)
+)]
+pub struct ModulationId {
+	uuid: Uuid,
+}
+
+impl ModulationId {
+	pub(crate) fn new() -> Self {
+		Self {
+			uuid: Uuid::new_v4(),
+		}
+	}
+}
+
+impl From<&ModulationHandle> for ModulationId {
+	fn from(handle: &ModulationHandle) -> Self {
+		handle.id()
+	}
+}
+
+/// Settings for a modulation.
+///
+/// A modulation routes one parameter (the source) to another
+/// parameter (the target). Every frame, the source's value
+/// multiplied by the depth is added to the target's value.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ModulationSettings {
+	/// The unique identifier for the modulation.
+	pub id: Option<ModulationId>,
+	/// The parameter whose value modulates the target.
+	pub source: ParameterId,
+	/// The parameter to modulate.
+	pub target: ParameterId,
+	/// How much the source's value should be scaled before
+	/// it's added to the target's value.
+	pub depth: f64,
+}
+
+impl ModulationSettings {
+	/// Creates a new `ModulationSettings` that routes the source
+	/// parameter to the target parameter with a depth of 1.
+	pub fn new(source: impl Into<ParameterId>, target: impl Into<ParameterId>) -> Self {
+		Self {
+			id: None,
+			source: source.into(),
+			target: target.into(),
+			depth: 1.0,
+		}
+	}
+
+	/// Sets the unique identifier for the modulation.
+	pub fn id(self, id: impl Into<ModulationId>) -> Self {
+		Self {
+			id: Some(id.into()),
+			..self
+		}
+	}
+
+	/// Sets how much the source's value should be scaled before
+	/// it's added to the target's value.
+	pub fn depth(self, depth: f64) -> Self {
+		Self { depth, ..self }
+	}
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Modulation {
+	pub source: ParameterId,
+	pub target: ParameterId,
+	pub depth: Parameter,
+}
+
+impl Modulation {
+	pub fn new(settings: ModulationSettings) -> Self {
+		Self {
+			source: settings.source,
+			target: settings.target,
+			depth: Parameter::new(settings.depth),
+		}
+	}
+}
@@ -85,6 +85,7 @@ struct TweenState {
 pub struct Parameter {
 	value: f64,
 	tween_state: Option<TweenState>,
+	modulation: f64,
 }
 
 impl Parameter {
@@ -92,13 +93,30 @@ impl Parameter {
 		Self {
 			value,
 			tween_state: None,
+			modulation: 0.0,
 		}
 	}
 
+	/// Returns the value of the parameter, including
+	/// any modulations.
 	pub(crate) fn value(&self) -> f64 {
+		self.value + self.modulation
+	}
+
+	/// Returns the value of the parameter without
+	/// any modulations.
+	pub(crate) fn base_value(&self) -> f64 {
 		self.value
 	}
 
+	pub(crate) fn clear_modulation(&mut self) {
+		self.modulation = 0.0;
+	}
+
+	pub(crate) fn add_modulation(&mut self, amount: f64) {
+		self.modulation += amount;
+	}
+
 	pub(crate) fn set(&mut self, target: f64, tween: Option<Tween>) {
 		if let Some(tween) = tween {
 			self.tween_state = Some(TweenState {
@@ -119,7 +137,7 @@ impl Parameter {
 				tween_state
 					.tween
 					.tween(tween_state.start, tween_state.end, tween_state.time);
-			if tween_state.time >= tween_state.tween.duration {
+			if tween_state.time >= tween_state.tween.total_duration() {
 				self.tween_state = None;
 				return true;
 			}
@@ -1,6 +1,7 @@
 use crate::{
 	command::ParameterCommand,
-	parameter::{Parameter, ParameterId},
+	manager::snapshot::{ParameterSnapshot, SnapshotRequest},
+	parameter::{Modulation, ModulationId, Parameter, ParameterId},
 	static_container::index_map::StaticIndexMap,
 };
 
@@ -11,12 +12,14 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct Parameters {
 	parameters: StaticIndexMap<ParameterId, Parameter>,
+	modulations: StaticIndexMap<ModulationId, Modulation>,
 }
 
 impl Parameters {
-	pub(crate) fn new(capacity: usize) -> Self {
+	pub(crate) fn new(capacity: usize, modulation_capacity: usize) -> Self {
 		Self {
 			parameters: StaticIndexMap::new(capacity),
+			modulations: StaticIndexMap::new(modulation_capacity),
 		}
 	}
 
@@ -24,12 +27,28 @@ impl Parameters {
 		self.parameters.get(&id)
 	}
 
+	pub(crate) fn snapshot(&self, request: &mut SnapshotRequest) {
+		for (id, parameter) in &self.parameters {
+			request.add_parameter(ParameterSnapshot {
+				id: *id,
+				value: parameter.base_value(),
+			});
+		}
+	}
+
+	/// Immediately sets the value of a parameter.
+	pub(crate) fn set(&mut self, id: ParameterId, value: f64) {
+		if let Some(parameter) = self.parameters.get_mut(&id) {
+			parameter.set(value, None);
+		}
+	}
+
 	pub(crate) fn run_command(&mut self, command: ParameterCommand) {
 		match command {
 			ParameterCommand::AddParameter(id, value) => {
 				self.parameters.try_insert(id, Parameter::new(value)).ok();
 			}
-			ParameterCommand::SetParameter(id, value, tween) => {
+			ParameterCommand::SetParameter(id, value, tween, _) => {
 				if let Some(parameter) = self.parameters.get_mut(&id) {
 					parameter.set(value, tween);
 				}
@@ -37,12 +56,40 @@ impl Parameters {
 			ParameterCommand::RemoveParameter(id) => {
 				self.parameters.remove(&id);
 			}
+			ParameterCommand::AddModulation(id, modulation) => {
+				self.modulations.try_insert(id, modulation).ok();
+			}
+			ParameterCommand::RemoveModulation(id) => {
+				self.modulations.remove(&id);
+			}
+			ParameterCommand::SetModulationDepth(id, depth, tween) => {
+				if let Some(modulation) = self.modulations.get_mut(&id) {
+					modulation.depth.set(depth, tween);
+				}
+			}
+			// automations and derived parameters are handled by the backend
+			ParameterCommand::StartAutomation(..)
+			| ParameterCommand::StopAutomation(..)
+			| ParameterCommand::AddDerivedParameter(..) => {}
 		}
 	}
 
 	pub(crate) fn update(&mut self, dt: f64) {
 		for (_, parameter) in &mut self.parameters {
 			parameter.update(dt);
+			parameter.clear_modulation();
+		}
+		// modulations are based on the unmodulated value of the
+		// source, so the order they're applied in doesn't matter
+		for (_, modulation) in &mut self.modulations {
+			modulation.depth.update(dt);
+			let source_value = match self.parameters.get(&modulation.source) {
+				Some(source) => source.base_value(),
+				None => continue,
+			};
+			if let Some(target) = self.parameters.get_mut(&modulation.target) {
+				target.add_modulation(source_value * modulation.depth.value());
+			}
 		}
 	}
 }
@@ -0,0 +1,242 @@
+use crate::{
+	instance::{InstanceSettings, StopInstanceSettings},
+	manager::error::AddDerivedParameterError,
+	testing::{constant_sound, create_manager},
+	Frame,
+};
+
+use super::{
+	tween::{EaseDirection, Easing, Tween},
+	Automation, DerivedParameterSettings, Mapping, MappingCurve, ModulationSettings, ParameterId,
+	ParameterSettings,
+};
+
+#[test]
+fn modulations_add_the_source_value_to_the_target() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let source = manager
+		.add_parameter(ParameterSettings::new().value(0.25))
+		.unwrap();
+	let target = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	sound_handle
+		.play(InstanceSettings::new().volume(&target))
+		.unwrap();
+	backend.process();
+	let unmodulated = backend.process();
+	let mut modulation = manager
+		.add_modulation(ModulationSettings::new(&source, &target).depth(2.0))
+		.unwrap();
+	backend.process();
+	assert_eq!(backend.process(), unmodulated * 2.0);
+	modulation.set_depth(0.0, None).unwrap();
+	backend.process();
+	assert_eq!(backend.process(), unmodulated);
+}
+
+#[test]
+fn waits_for_the_delay_of_a_tween() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut volume = manager
+		.add_parameter(ParameterSettings::new().value(0.0))
+		.unwrap();
+	sound_handle
+		.play(InstanceSettings::new().volume(&volume))
+		.unwrap();
+	backend.process();
+	// 480 frames = 10ms at 48kHz
+	volume
+		.set(
+			1.0,
+			Tween::linear(0.01)
+				.easing(Easing::CubicBezier(0.42, 0.0, 0.58, 1.0))
+				.delay(0.01),
+		)
+		.unwrap();
+	for _ in 0..470 {
+		assert_eq!(backend.process(), Frame::from_mono(0.0));
+	}
+	for _ in 470..1000 {
+		backend.process();
+	}
+	let full_volume = backend.process();
+	assert_ne!(full_volume, Frame::from_mono(0.0));
+	assert_eq!(backend.process(), full_volume);
+}
+
+#[test]
+fn moves_parameters_through_automations() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut volume = manager
+		.add_parameter(ParameterSettings::new().value(1.0))
+		.unwrap();
+	sound_handle
+		.play(InstanceSettings::new().volume(&volume))
+		.unwrap();
+	backend.process();
+	let full_volume = backend.process().left;
+	// 480 frames = 10ms at 48kHz
+	volume
+		.automate(
+			Automation::new()
+				.point(0.0, 0.0)
+				.point(0.01, 1.0)
+				.loop_start(0.0),
+		)
+		.unwrap();
+	for _ in 0..239 {
+		backend.process();
+	}
+	assert!((backend.process().left - full_volume * 0.5).abs() < 0.01);
+	// the automation loops back to the start
+	for _ in 0..479 {
+		backend.process();
+	}
+	assert!((backend.process().left - full_volume * 0.5).abs() < 0.01);
+	// setting the parameter stops the automation
+	volume.set(0.25, None).unwrap();
+	backend.process();
+	for _ in 0..240 {
+		assert!((backend.process().left - full_volume * 0.25).abs() < 1.0e-6);
+	}
+}
+
+#[test]
+fn computes_derived_parameters_from_other_parameters() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut a = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	let b = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	let product = manager
+		.add_derived_parameter(DerivedParameterSettings::product().input(&a).input(&b))
+		.unwrap();
+	let sum = manager
+		.add_derived_parameter(
+			DerivedParameterSettings::sum()
+				.input(&product)
+				.input(&b)
+				.offset(-0.5),
+		)
+		.unwrap();
+	let mut instance = sound_handle.play(InstanceSettings::new()).unwrap();
+	backend.process();
+	let full_volume = backend.process().left;
+	instance
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	// wait for the sound's cooldown to pass
+	for _ in 0..10 {
+		backend.process();
+	}
+	let mut instance = sound_handle
+		.play(InstanceSettings::new().volume(&product))
+		.unwrap();
+	backend.process();
+	assert!((backend.process().left - full_volume * 0.25).abs() < 1.0e-6);
+	a.set(1.0, None).unwrap();
+	backend.process();
+	assert!((backend.process().left - full_volume * 0.5).abs() < 1.0e-6);
+
+	// derived parameters can be derived from other derived parameters
+	instance
+		.stop(StopInstanceSettings::new().fade_tween(None))
+		.unwrap();
+	for _ in 0..10 {
+		backend.process();
+	}
+	sound_handle
+		.play(InstanceSettings::new().volume(&sum))
+		.unwrap();
+	backend.process();
+	assert!((backend.process().left - full_volume * 0.5).abs() < 1.0e-6);
+
+	if let Err(AddDerivedParameterError::NoParameterWithId(id)) =
+		manager.add_derived_parameter(DerivedParameterSettings::sum().input(ParameterId::new()))
+	{
+		assert_ne!(id, a.id());
+	} else {
+		panic!("AudioManager::add_derived_parameter should return Err(AddDerivedParameterError::NoParameterWithId) when an input does not exist");
+	}
+}
+
+#[test]
+fn logarithmic_mappings_change_evenly_when_the_input_is_multiplied() {
+	let mapping = Mapping {
+		input_range: (20.0, 20_000.0),
+		curve: MappingCurve::Logarithmic,
+		..Default::default()
+	};
+	assert_eq!(mapping.map(20.0), 0.0);
+	assert!((mapping.map(200.0) - 1.0 / 3.0).abs() < 1.0e-9);
+	assert!((mapping.map(2000.0) - 2.0 / 3.0).abs() < 1.0e-9);
+	assert!((mapping.map(20_000.0) - 1.0).abs() < 1.0e-9);
+	// inputs outside of the input range keep following the curve
+	assert!((mapping.map(200_000.0) - 4.0 / 3.0).abs() < 1.0e-9);
+}
+
+#[test]
+fn eased_mappings_clamp_inputs_to_the_input_range() {
+	let mapping = Mapping {
+		output_range: (10.0, 20.0),
+		curve: MappingCurve::Eased(Easing::PowI(2), EaseDirection::In),
+		..Default::default()
+	};
+	assert_eq!(mapping.map(0.5), 12.5);
+	assert_eq!(mapping.map(-1.0), 10.0);
+	assert_eq!(mapping.map(2.0), 20.0);
+}
+
+#[test]
+fn mappings_clamp_non_linear_curves_to_the_output_range() {
+	let exponential = Mapping {
+		output_range: (100.0, 10_000.0),
+		curve: MappingCurve::Exponential,
+		..Default::default()
+	};
+	assert!((exponential.map(-1.0) - 1.0).abs() < 1.0e-9);
+	assert!((exponential.map(2.0) - 1_000_000.0).abs() < 1.0e-6);
+	let clamped_exponential = Mapping {
+		clamp_bottom: true,
+		clamp_top: true,
+		..exponential
+	};
+	assert!((clamped_exponential.map(-1.0) - 100.0).abs() < 1.0e-9);
+	assert!((clamped_exponential.map(0.5) - 1000.0).abs() < 1.0e-9);
+	assert!((clamped_exponential.map(2.0) - 10_000.0).abs() < 1.0e-9);
+
+	let decibels = Mapping {
+		output_range: (-60.0, 0.0),
+		clamp_top: true,
+		curve: MappingCurve::Decibels,
+		..Default::default()
+	};
+	assert_eq!(decibels.map(2.0), 1.0);
+	// only the top is clamped
+	assert!((decibels.map(-1.0) - 0.000_001).abs() < 1.0e-12);
+
+	let logarithmic = Mapping {
+		input_range: (20.0, 20_000.0),
+		clamp_bottom: true,
+		curve: MappingCurve::Logarithmic,
+		..Default::default()
+	};
+	assert_eq!(logarithmic.map(2.0), 0.0);
+	// only the bottom is clamped
+	assert!((logarithmic.map(200_000.0) - 4.0 / 3.0).abs() < 1.0e-9);
+}
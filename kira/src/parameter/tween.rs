@@ -4,7 +4,7 @@
 ///
 /// Given a position in a tween `t` from 0-1, each easing
 /// function transforms `t` differently.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[cfg_attr(
 	feature = "serde_support",
 	derive(serde::Serialize, serde::Deserialize)
@@ -16,6 +16,21 @@ pub enum Easing {
 	PowI(i32),
 	/// Raises `t` to a float power.
 	PowF(f64),
+	/// Follows an exponential curve with the given steepness.
+	///
+	/// Positive values start slowly and speed up, negative
+	/// values start quickly and slow down, and values close
+	/// to 0 are nearly linear. Exponential curves are useful
+	/// for volume swells, since loudness is perceived
+	/// logarithmically.
+	Exponential(f64),
+	/// Follows a cubic bezier curve from (0, 0) to (1, 1) with
+	/// the control points `(x1, y1)` and `(x2, y2)`, like the
+	/// `cubic-bezier` timing function in CSS.
+	///
+	/// The x coordinates of the control points are clamped
+	/// to the range 0-1.
+	CubicBezier(f64, f64, f64, f64),
 }
 
 impl Easing {
@@ -25,10 +40,87 @@ impl Easing {
 			Easing::Linear => t,
 			Easing::PowI(power) => t.powi(*power),
 			Easing::PowF(power) => t.powf(*power),
+			Easing::Exponential(steepness) => {
+				if steepness.abs() < 1.0e-6 {
+					t
+				} else {
+					(steepness * t).exp_m1() / steepness.exp_m1()
+				}
+			}
+			Easing::CubicBezier(x1, y1, x2, y2) => {
+				cubic_bezier(x1.clamp(0.0, 1.0), *y1, x2.clamp(0.0, 1.0), *y2, t)
+			}
+		}
+	}
+
+	/// Applies the easing curve to the given `t` in
+	/// the given direction.
+	pub fn ease(&self, direction: EaseDirection, mut t: f64) -> f64 {
+		/* the code for applying In/Out/InOut directions
+		to an easing function is based on rxi's flux:
+		https://github.com/rxi/flux/blob/master/flux.lua#L33 */
+		match direction {
+			EaseDirection::In => self.apply(t),
+			EaseDirection::Out => 1.0 - self.apply(1.0 - t),
+			EaseDirection::InOut => {
+				t *= 2.0;
+				if t < 1.0 {
+					0.5 * self.apply(t)
+				} else {
+					t = 2.0 - t;
+					0.5 * (1.0 - self.apply(t)) + 0.5
+				}
+			}
 		}
 	}
 }
 
+/// Gets the y coordinate of the point on a cubic bezier curve
+/// from (0, 0) to (1, 1) with the given x coordinate.
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+	fn bezier(p1: f64, p2: f64, s: f64) -> f64 {
+		let inverse = 1.0 - s;
+		3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+	}
+	fn bezier_slope(p1: f64, p2: f64, s: f64) -> f64 {
+		let inverse = 1.0 - s;
+		3.0 * inverse * inverse * p1 + 6.0 * inverse * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+	}
+	if x <= 0.0 || x >= 1.0 {
+		return bezier(y1, y2, x.clamp(0.0, 1.0));
+	}
+	// find the position along the curve with the given x
+	// coordinate using Newton's method, falling back to
+	// bisection if the curve is too flat
+	let mut s = x;
+	for _ in 0..8 {
+		let error = bezier(x1, x2, s) - x;
+		if error.abs() < 1.0e-7 {
+			return bezier(y1, y2, s);
+		}
+		let slope = bezier_slope(x1, x2, s);
+		if slope.abs() < 1.0e-6 {
+			break;
+		}
+		s -= error / slope;
+	}
+	let (mut low, mut high) = (0.0, 1.0);
+	s = x;
+	for _ in 0..50 {
+		let error = bezier(x1, x2, s) - x;
+		if error.abs() < 1.0e-7 {
+			break;
+		}
+		if error > 0.0 {
+			high = s;
+		} else {
+			low = s;
+		}
+		s = (low + high) / 2.0;
+	}
+	bezier(y1, y2, s)
+}
+
 impl Default for Easing {
 	fn default() -> Self {
 		Self::Linear
@@ -58,6 +150,18 @@ impl Default for EaseDirection {
 }
 
 /// A movement of one value to another over time.
+///
+/// ```
+/// # use kira::parameter::tween::{EaseDirection, Easing, Tween};
+/// // wait half a second, then swell over two seconds
+/// let tween = Tween::linear(2.0)
+/// 	.easing(Easing::Exponential(5.0))
+/// 	.ease_direction(EaseDirection::In)
+/// 	.delay(0.5);
+/// assert_eq!(tween.tween(0.0, 1.0, 0.25), 0.0);
+/// assert!(tween.tween(0.0, 1.0, 1.5) < 0.5);
+/// assert_eq!(tween.tween(0.0, 1.0, 2.5), 1.0);
+/// ```
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(
 	feature = "serde_support",
@@ -70,6 +174,9 @@ pub struct Tween {
 	pub easing: Easing,
 	/// What direction the curve is applied in.
 	pub ease_direction: EaseDirection,
+	/// How long to wait before starting the motion (in seconds).
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub delay: f64,
 }
 
 impl Tween {
@@ -79,34 +186,53 @@ impl Tween {
 			duration,
 			easing: Default::default(),
 			ease_direction: Default::default(),
+			delay: 0.0,
 		}
 	}
 
+	/// Sets the curve of the motion.
+	pub fn easing(self, easing: Easing) -> Self {
+		Self { easing, ..self }
+	}
+
+	/// Sets what direction the curve is applied in.
+	pub fn ease_direction(self, ease_direction: EaseDirection) -> Self {
+		Self {
+			ease_direction,
+			..self
+		}
+	}
+
+	/// Sets how long to wait before starting the motion
+	/// (in seconds).
+	pub fn delay(self, delay: f64) -> Self {
+		Self { delay, ..self }
+	}
+
+	/// Returns the time it takes for the motion to finish,
+	/// including the delay.
+	pub fn total_duration(&self) -> f64 {
+		self.delay + self.duration
+	}
+
 	/// Applies the tween's easing curve (with easing direction)
 	/// to a relative position in an animation (where 0 is the
 	/// beginning of the animation and 1 is the end).
-	pub fn ease(&self, mut t: f64) -> f64 {
-		/* the code for applying In/Out/InOut directions
-		to an easing function is based on rxi's flux:
-		https://github.com/rxi/flux/blob/master/flux.lua#L33 */
-		match self.ease_direction {
-			EaseDirection::In => self.easing.apply(t),
-			EaseDirection::Out => 1.0 - self.easing.apply(1.0 - t),
-			EaseDirection::InOut => {
-				t *= 2.0;
-				if t < 1.0 {
-					0.5 * self.easing.apply(t)
-				} else {
-					t = 2.0 - t;
-					0.5 * (1.0 - self.easing.apply(t)) + 0.5
-				}
-			}
-		}
+	pub fn ease(&self, t: f64) -> f64 {
+		self.easing.ease(self.ease_direction, t)
 	}
 
 	/// Gets the value of an animation from one point to another
-	/// at the given time (with this tween's duration and easing curve).
+	/// at the given time (with this tween's delay, duration,
+	/// and easing curve).
 	pub fn tween(&self, from: f64, to: f64, time: f64) -> f64 {
+		let time = time - self.delay;
+		if time <= 0.0 {
+			return from;
+		}
+		if time >= self.duration {
+			return to;
+		}
 		// get the time in the animation relative to the duration
 		// of the animation (0 = beginning, 1 = end)
 		let mut t = time / self.duration;
@@ -123,6 +249,7 @@ impl From<f64> for Tween {
 			duration,
 			easing: Easing::default(),
 			ease_direction: EaseDirection::default(),
+			delay: 0.0,
 		}
 	}
 }
@@ -1,19 +1,24 @@
 //! A wrapper around sounds and arrangements.
 
 mod playables;
+mod statistics;
 
 use basedrop::Owned;
 pub(crate) use playables::Playables;
+pub(crate) use statistics::PlaybackCounters;
+pub use statistics::PlaybackStatistics;
 
 use crate::{
 	arrangement::{handle::ArrangementHandle, Arrangement, ArrangementId},
-	group::{groups::Groups, GroupId},
+	group::{groups::Groups, GroupId, GroupSet},
 	mixer::TrackIndex,
+	parameter::tween::Tween,
 	sound::{handle::SoundHandle, Sound, SoundId},
+	Tempo,
 };
 
 /// An unique identifier for something you can play multiple instances of.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(
 	feature = "serde_support",
 	derive(serde::Serialize, serde::Deserialize)
@@ -70,6 +75,13 @@ impl<'a> Playable<'a> {
 		}
 	}
 
+	pub fn native_tempo(&self) -> Option<Tempo> {
+		match self {
+			Playable::Sound(sound) => sound.native_tempo(),
+			Playable::Arrangement(arrangement) => arrangement.native_tempo(),
+		}
+	}
+
 	pub fn default_loop_start(&self) -> Option<f64> {
 		match self {
 			Playable::Sound(sound) => sound.default_loop_start(),
@@ -77,12 +89,33 @@ impl<'a> Playable<'a> {
 		}
 	}
 
+	pub fn default_fade_in_tween(&self) -> Option<Tween> {
+		match self {
+			Playable::Sound(sound) => sound.default_fade_in_tween(),
+			Playable::Arrangement(arrangement) => arrangement.default_fade_in_tween(),
+		}
+	}
+
+	pub fn default_fade_out_tween(&self) -> Option<Tween> {
+		match self {
+			Playable::Sound(sound) => sound.default_fade_out_tween(),
+			Playable::Arrangement(arrangement) => arrangement.default_fade_out_tween(),
+		}
+	}
+
 	pub fn is_in_group(&self, id: GroupId, all_groups: &Groups) -> bool {
 		match self {
 			Playable::Sound(sound) => sound.is_in_group(id, all_groups),
 			Playable::Arrangement(arrangement) => arrangement.is_in_group(id, all_groups),
 		}
 	}
+
+	pub fn statistics(&self) -> Option<&'a PlaybackCounters> {
+		match *self {
+			Playable::Sound(sound) => sound.statistics().map(AsRef::as_ref),
+			Playable::Arrangement(arrangement) => arrangement.statistics().map(AsRef::as_ref),
+		}
+	}
 }
 
 pub(crate) enum PlayableMut<'a> {
@@ -91,6 +124,13 @@ pub(crate) enum PlayableMut<'a> {
 }
 
 impl<'a> PlayableMut<'a> {
+	pub fn groups(&self) -> &GroupSet {
+		match self {
+			PlayableMut::Sound(sound) => sound.groups(),
+			PlayableMut::Arrangement(arrangement) => arrangement.groups(),
+		}
+	}
+
 	pub fn cooling_down(&self) -> bool {
 		match self {
 			PlayableMut::Sound(sound) => sound.cooling_down(),
@@ -98,6 +138,16 @@ impl<'a> PlayableMut<'a> {
 		}
 	}
 
+	pub fn record_play(&self) {
+		let statistics = match self {
+			PlayableMut::Sound(sound) => sound.statistics(),
+			PlayableMut::Arrangement(arrangement) => arrangement.statistics(),
+		};
+		if let Some(statistics) = statistics {
+			statistics.record_play();
+		}
+	}
+
 	pub fn start_cooldown(&mut self) {
 		match self {
 			PlayableMut::Sound(sound) => {
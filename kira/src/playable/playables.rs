@@ -3,8 +3,10 @@ use basedrop::Owned;
 use crate::{
 	arrangement::{Arrangement, ArrangementId},
 	command::ResourceCommand,
+	manager::resources::{RemovedResource, RemovedResourceProducer},
+	parameter::Parameters,
 	sound::{Sound, SoundId},
-	static_container::index_map::StaticIndexMap,
+	static_container::{index_map::StaticIndexMap, vec::StaticVec},
 	Frame,
 };
 
@@ -13,6 +15,9 @@ use super::{Playable, PlayableId, PlayableMut};
 pub(crate) struct Playables {
 	sounds: StaticIndexMap<SoundId, Owned<Sound>>,
 	arrangements: StaticIndexMap<ArrangementId, Owned<Arrangement>>,
+	// sounds and arrangements that will be removed once
+	// nothing is playing them
+	pending_removals: StaticVec<PlayableId>,
 }
 
 impl Playables {
@@ -20,6 +25,7 @@ impl Playables {
 		Self {
 			sounds: StaticIndexMap::new(sound_capacity),
 			arrangements: StaticIndexMap::new(arrangement_capacity),
+			pending_removals: StaticVec::new(sound_capacity + arrangement_capacity),
 		}
 	}
 
@@ -64,13 +70,23 @@ impl Playables {
 		}
 	}
 
-	pub fn run_command(&mut self, command: ResourceCommand) {
+	pub fn run_command(
+		&mut self,
+		command: ResourceCommand,
+		removed_resources: &mut RemovedResourceProducer,
+	) {
 		match command {
 			ResourceCommand::AddSound(sound) => {
 				self.sounds.try_insert(sound.id(), sound).ok();
 			}
 			ResourceCommand::RemoveSound(id) => {
-				self.sounds.remove(&id);
+				self.remove(id.into(), removed_resources);
+			}
+			ResourceCommand::ReplaceSound(mut sound) => {
+				if let Some(old_sound) = self.sounds.get_mut(&sound.id()) {
+					std::mem::swap(old_sound, &mut sound);
+					removed_resources.push(RemovedResource::ReplacedSound(sound));
+				}
 			}
 			ResourceCommand::AddArrangement(arrangement) => {
 				self.arrangements
@@ -78,17 +94,70 @@ impl Playables {
 					.ok();
 			}
 			ResourceCommand::RemoveArrangement(id) => {
-				self.arrangements.remove(&id);
+				self.remove(id.into(), removed_resources);
+			}
+			ResourceCommand::RemoveWhenUnused(id) => {
+				self.pending_removals.try_push(id).ok();
+			}
+			ResourceCommand::SetArrangementLayerVolume(id, layer, volume) => {
+				if let Some(arrangement) = self.arrangements.get_mut(&id) {
+					arrangement.set_layer_volume(layer, volume);
+				}
+			}
+		}
+	}
+
+	/// Removes the sounds and arrangements waiting to be removed
+	/// that aren't being played anymore.
+	///
+	/// A sound is still in use if an arrangement that hasn't
+	/// been removed plays it.
+	pub fn remove_unused(
+		&mut self,
+		is_playing: impl Fn(PlayableId) -> bool,
+		removed_resources: &mut RemovedResourceProducer,
+	) {
+		let mut i = 0;
+		while let Some(id) = self.pending_removals.get(i).copied() {
+			let in_use = is_playing(id)
+				|| match id {
+					PlayableId::Sound(id) => self
+						.arrangements
+						.iter()
+						.any(|(_, arrangement)| arrangement.uses_sound(id)),
+					PlayableId::Arrangement(_) => false,
+				};
+			if in_use {
+				i += 1;
+			} else {
+				self.pending_removals.remove(i);
+				self.remove(id, removed_resources);
+			}
+		}
+	}
+
+	fn remove(&mut self, id: PlayableId, removed_resources: &mut RemovedResourceProducer) {
+		match id {
+			PlayableId::Sound(id) => {
+				if let Some(sound) = self.sounds.remove(&id) {
+					removed_resources.push(RemovedResource::Sound(sound));
+				}
+			}
+			PlayableId::Arrangement(id) => {
+				if let Some(arrangement) = self.arrangements.remove(&id) {
+					removed_resources.push(RemovedResource::Arrangement(arrangement));
+				}
 			}
 		}
 	}
 
-	pub fn update(&mut self, dt: f64) {
+	pub fn update(&mut self, dt: f64, parameters: &Parameters) {
 		for (_, sound) in &mut self.sounds {
 			sound.update_cooldown(dt);
 		}
 		for (_, arrangement) in &mut self.arrangements {
 			arrangement.update_cooldown(dt);
+			arrangement.update_layer_volumes(parameters);
 		}
 	}
 }
@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use atomic::Atomic;
+
+/// How often a sound or arrangement has been played
+/// and how long it was heard for.
+///
+/// Statistics are only collected for sounds and arrangements
+/// that opt in with the `collect_statistics` setting.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlaybackStatistics {
+	/// The number of instances that were started.
+	///
+	/// Instances that were prevented from starting by
+	/// a cooldown aren't counted.
+	pub times_played: u64,
+	/// The total time instances spent producing sound (in seconds).
+	///
+	/// Time spent paused, waiting to start, or playing
+	/// silent parts of the audio isn't counted. When multiple
+	/// instances play at the same time, each one is counted.
+	pub audible_duration: f64,
+}
+
+/// The playback statistics of a sound or arrangement, written
+/// by the audio thread and read by the main thread.
+#[derive(Debug)]
+pub(crate) struct PlaybackCounters {
+	times_played: AtomicU64,
+	audible_duration: Atomic<f64>,
+}
+
+impl PlaybackCounters {
+	pub fn record_play(&self) {
+		self.times_played.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_audible_time(&self, dt: f64) {
+		// only the audio thread writes the duration, so
+		// it doesn't need to be updated atomically
+		let audible_duration = self.audible_duration.load(Ordering::Relaxed);
+		self.audible_duration
+			.store(audible_duration + dt, Ordering::Relaxed);
+	}
+
+	pub fn statistics(&self) -> PlaybackStatistics {
+		PlaybackStatistics {
+			times_played: self.times_played.load(Ordering::Relaxed),
+			audible_duration: self.audible_duration.load(Ordering::Relaxed),
+		}
+	}
+}
+
+impl Default for PlaybackCounters {
+	fn default() -> Self {
+		Self {
+			times_played: AtomicU64::new(0),
+			audible_duration: Atomic::new(0.0),
+		}
+	}
+}
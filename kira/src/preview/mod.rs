@@ -0,0 +1,357 @@
+//! Auditions sounds and mixer layouts from a project file.
+//!
+//! This module is only available with the `preview` feature.
+//! It's meant for sound designers who want to hear changes to
+//! audio files and settings without running the game.
+//!
+//! A preview project is a [RON](https://github.com/ron-rs/ron)
+//! file describing a [`MixerConfig`] and a list of named sounds.
+//! Paths are relative to the project file. Built-in effects
+//! are written as maps with a `type` field, and enums inside
+//! them, like [`Value`](crate::Value)s, have to be written as
+//! maps as well:
+//!
+//! ```text
+//! (
+//! 	mixer: (
+//! 		send_tracks: [
+//! 			(
+//! 				settings: (id: Some("3b8e6f6e-2d1c-4f0b-9a53-0d5c6b7e9f11")),
+//! 				effects: [(effect: {"type": "Reverb", "feedback": {"Fixed": 0.5}})],
+//! 			),
+//! 		],
+//! 	),
+//! 	sounds: {
+//! 		"footstep": (
+//! 			path: "sfx/footstep.ogg",
+//! 			settings: (cooldown: Some(0.1)),
+//! 		),
+//! 	},
+//! )
+//! ```
+//!
+//! [`run`] starts an interactive previewer that reads commands
+//! from the standard input and reloads the project whenever the
+//! file changes. It's also available as an example:
+//!
+//! ```text
+//! cargo run --example preview --features preview -- project.ron
+//! ```
+//!
+//! [`Preview`] can be used to build your own previewer.
+
+#[cfg(all(test, feature = "wav"))]
+mod tests;
+
+use std::{
+	io::BufRead,
+	path::{Path, PathBuf},
+	sync::mpsc::{channel, RecvTimeoutError},
+	time::{Duration, SystemTime},
+};
+
+use indexmap::IndexMap;
+use thiserror::Error;
+
+use crate::{
+	instance::{InstanceSettings, StopInstanceSettings},
+	manager::{
+		error::{
+			AddMixerConfigError, AddSoundError, RemoveSendTrackError, RemoveSoundError,
+			RemoveSubTrackError, ReplaceSoundError, SetupError,
+		},
+		AudioManager, AudioManagerSettings,
+	},
+	mixer::{
+		config::{MixerConfig, MixerConfigHandles},
+		RemoveEffectError,
+	},
+	sound::{error::SoundFromFileError, handle::SoundHandle, Sound, SoundSettings},
+	CommandError,
+};
+
+/// How often [`run`] checks whether the project file has changed.
+const RELOAD_INTERVAL: Duration = Duration::from_millis(250);
+
+const HELP: &str = "Commands:
+  <name>        play the sound with the given name
+  list          list the sounds in the project
+  stop          stop all sounds
+  reload        reload the project
+  quit          exit the previewer";
+
+/// Something that can go wrong when previewing a project.
+#[derive(Debug, Error)]
+pub enum PreviewError {
+	/// The project file could not be read.
+	#[error("{0}")]
+	IoError(#[from] std::io::Error),
+
+	/// The project file is not a valid preview project.
+	#[error("{0}")]
+	ParseError(#[from] ron::Error),
+
+	/// A sound file could not be loaded.
+	#[error("Could not load the sound \"{0}\": {1}")]
+	SoundFromFileError(String, SoundFromFileError),
+
+	/// No sound with the given name is in the project.
+	#[error("No sound named \"{0}\" is in the project")]
+	NoSoundWithName(String),
+
+	/// The audio manager could not be started.
+	#[error("{0}")]
+	SetupError(#[from] SetupError),
+
+	/// The mixer layout could not be applied.
+	#[error("{0}")]
+	AddMixerConfigError(#[from] AddMixerConfigError),
+
+	/// A sound could not be added.
+	#[error("{0}")]
+	AddSoundError(#[from] AddSoundError),
+
+	/// A sound could not be replaced.
+	#[error("{0}")]
+	ReplaceSoundError(#[from] ReplaceSoundError),
+
+	/// A sound could not be removed.
+	#[error("{0}")]
+	RemoveSoundError(#[from] RemoveSoundError),
+
+	/// A sub-track could not be removed.
+	#[error("{0}")]
+	RemoveSubTrackError(#[from] RemoveSubTrackError),
+
+	/// A send track could not be removed.
+	#[error("{0}")]
+	RemoveSendTrackError(#[from] RemoveSendTrackError),
+
+	/// An effect could not be removed from the main track.
+	#[error("{0}")]
+	RemoveEffectError(#[from] RemoveEffectError),
+
+	/// A command could not be sent to the audio thread.
+	#[error("{0}")]
+	CommandError(#[from] CommandError),
+}
+
+/// A sound in a preview project.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProjectSound {
+	/// The path to the audio file, relative to the project file.
+	pub path: PathBuf,
+	/// The settings for the sound.
+	#[serde(default)]
+	pub settings: SoundSettings,
+}
+
+/// The contents of a preview project file.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Project {
+	/// The mixer layout to use.
+	pub mixer: MixerConfig,
+	/// The sounds in the project, by name.
+	pub sounds: IndexMap<String, ProjectSound>,
+}
+
+impl Project {
+	/// Reads a project from a file.
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, PreviewError> {
+		let contents = std::fs::read_to_string(path)?;
+		Ok(ron::from_str(&contents)?)
+	}
+}
+
+/// Plays sounds from a preview project and keeps them
+/// up to date with the project file.
+pub struct Preview {
+	audio_manager: AudioManager,
+	path: PathBuf,
+	modified: Option<SystemTime>,
+	mixer_handles: Option<MixerConfigHandles>,
+	sounds: IndexMap<String, SoundHandle>,
+}
+
+impl Preview {
+	/// Starts an audio manager and loads the project
+	/// at the given path.
+	pub fn new(
+		path: impl Into<PathBuf>,
+		settings: AudioManagerSettings,
+	) -> Result<Self, PreviewError> {
+		Self::from_audio_manager(path, AudioManager::new(settings)?)
+	}
+
+	/// Loads the project at the given path into an
+	/// existing audio manager.
+	pub fn from_audio_manager(
+		path: impl Into<PathBuf>,
+		audio_manager: AudioManager,
+	) -> Result<Self, PreviewError> {
+		let mut preview = Self {
+			audio_manager,
+			path: path.into(),
+			modified: None,
+			mixer_handles: None,
+			sounds: IndexMap::new(),
+		};
+		preview.reload()?;
+		Ok(preview)
+	}
+
+	/// Returns the audio manager used for previewing.
+	pub fn audio_manager(&mut self) -> &mut AudioManager {
+		&mut self.audio_manager
+	}
+
+	/// Returns the names of the sounds in the project.
+	pub fn sound_names(&self) -> impl Iterator<Item = &str> {
+		self.sounds.keys().map(|name| name.as_str())
+	}
+
+	/// Plays the sound with the given name.
+	pub fn play(&mut self, name: &str) -> Result<(), PreviewError> {
+		self.sounds
+			.get_mut(name)
+			.ok_or_else(|| PreviewError::NoSoundWithName(name.to_string()))?
+			.play(InstanceSettings::default())?;
+		Ok(())
+	}
+
+	/// Stops every sound in the project.
+	pub fn stop_all(&mut self) -> Result<(), PreviewError> {
+		for sound in self.sounds.values_mut() {
+			sound.stop(StopInstanceSettings::default())?;
+		}
+		Ok(())
+	}
+
+	fn file_modified_time(&self) -> Option<SystemTime> {
+		std::fs::metadata(&self.path)
+			.and_then(|metadata| metadata.modified())
+			.ok()
+	}
+
+	/// Reloads the project if the file has changed since it
+	/// was last loaded. Returns `true` if the project was reloaded.
+	pub fn reload_if_changed(&mut self) -> Result<bool, PreviewError> {
+		if self.file_modified_time() == self.modified {
+			return Ok(false);
+		}
+		self.reload()?;
+		Ok(true)
+	}
+
+	/// Reloads the project file, replacing the mixer layout
+	/// and the sounds.
+	///
+	/// Sounds that were already loaded keep their IDs, so
+	/// instances that are playing continue with the new audio.
+	/// If the project can't be read, the previous project
+	/// is kept.
+	pub fn reload(&mut self) -> Result<(), PreviewError> {
+		self.modified = self.file_modified_time();
+		let project = Project::from_file(&self.path)?;
+		let directory = self.path.parent().unwrap_or_else(|| Path::new(""));
+		let mut sounds = IndexMap::new();
+		for (name, sound) in project.sounds {
+			let loaded = Sound::from_file(directory.join(&sound.path), sound.settings)
+				.map_err(|error| PreviewError::SoundFromFileError(name.clone(), error))?;
+			sounds.insert(name, loaded);
+		}
+		self.remove_mixer_layout()?;
+		self.mixer_handles = Some(self.audio_manager.add_mixer_config(&project.mixer)?);
+		let removed_names = self
+			.sounds
+			.keys()
+			.filter(|name| !sounds.contains_key(*name))
+			.cloned()
+			.collect::<Vec<_>>();
+		for name in removed_names {
+			if let Some(handle) = self.sounds.remove(&name) {
+				self.audio_manager.remove_sound(&handle)?;
+			}
+		}
+		for (name, sound) in sounds {
+			let handle = match self.sounds.get(&name) {
+				Some(handle) => self.audio_manager.replace_sound(handle, sound)?,
+				None => self.audio_manager.add_sound(sound)?,
+			};
+			self.sounds.insert(name, handle);
+		}
+		Ok(())
+	}
+
+	fn remove_mixer_layout(&mut self) -> Result<(), PreviewError> {
+		if let Some(handles) = self.mixer_handles.take() {
+			let mut main_track = self.audio_manager.main_track();
+			for effect in &handles.main_track_effects {
+				main_track.remove_effect(effect)?;
+			}
+			// remove child tracks before their parents
+			for track in handles.sub_tracks.iter().rev() {
+				self.audio_manager.remove_sub_track(track.id())?;
+			}
+			for track in &handles.send_tracks {
+				self.audio_manager.remove_send_track(track.id())?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Runs an interactive previewer for the project at the given path.
+///
+/// Type the name of a sound to play it, or `help` for a list
+/// of commands. The project is reloaded whenever the file
+/// changes. Errors that happen after the project is first
+/// loaded are printed and don't stop the previewer.
+pub fn run(path: impl Into<PathBuf>, settings: AudioManagerSettings) -> Result<(), PreviewError> {
+	let mut preview = Preview::new(path, settings)?;
+	println!("{}", HELP);
+	let (line_sender, line_receiver) = channel();
+	std::thread::spawn(move || {
+		for line in std::io::stdin().lock().lines() {
+			match line {
+				Ok(line) => {
+					if line_sender.send(line).is_err() {
+						break;
+					}
+				}
+				Err(_) => break,
+			}
+		}
+	});
+	loop {
+		let result = match line_receiver.recv_timeout(RELOAD_INTERVAL) {
+			Ok(line) => match line.trim() {
+				"" => Ok(()),
+				"quit" => return Ok(()),
+				"help" => {
+					println!("{}", HELP);
+					Ok(())
+				}
+				"list" => {
+					for name in preview.sound_names() {
+						println!("{}", name);
+					}
+					Ok(())
+				}
+				"stop" => preview.stop_all(),
+				"reload" => preview.reload().map(|()| println!("Reloaded the project")),
+				name => preview.play(name),
+			},
+			Err(RecvTimeoutError::Timeout) => preview.reload_if_changed().map(|reloaded| {
+				if reloaded {
+					println!("Reloaded the project");
+				}
+			}),
+			Err(RecvTimeoutError::Disconnected) => return Ok(()),
+		};
+		if let Err(error) = result {
+			eprintln!("{}", error);
+		}
+	}
+}
@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use crate::{testing::create_manager, Frame};
+
+use super::{Preview, PreviewError, Project};
+
+/// Creates an empty directory for a test project.
+fn create_project_directory(name: &str) -> PathBuf {
+	let directory =
+		std::env::temp_dir().join(format!("kira-preview-{}-{}", name, std::process::id()));
+	std::fs::remove_dir_all(&directory).ok();
+	std::fs::create_dir_all(&directory).unwrap();
+	directory
+}
+
+/// Writes a one second long mono wav file where every
+/// sample has the given value.
+fn write_constant_wav(path: &Path, value: f32) {
+	let mut writer = hound::WavWriter::create(
+		path,
+		hound::WavSpec {
+			channels: 1,
+			sample_rate: 48000,
+			bits_per_sample: 32,
+			sample_format: hound::SampleFormat::Float,
+		},
+	)
+	.unwrap();
+	for _ in 0..48000 {
+		writer.write_sample(value).unwrap();
+	}
+	writer.finalize().unwrap();
+}
+
+const PROJECT: &str = r#"(
+	mixer: (
+		sub_tracks: [(settings: (volume: Fixed(0.5)))],
+	),
+	sounds: {
+		"quiet": (path: "quiet.wav"),
+		"loud": (
+			path: "sfx/loud.wav",
+			settings: (cooldown: Some(0.1)),
+		),
+	},
+)"#;
+
+#[test]
+fn parses_project_files() {
+	let directory = create_project_directory("parse");
+	std::fs::write(directory.join("project.ron"), PROJECT).unwrap();
+	let project = Project::from_file(directory.join("project.ron")).unwrap();
+	assert_eq!(project.mixer.sub_tracks.len(), 1);
+	assert_eq!(project.sounds.keys().collect::<Vec<_>>(), ["quiet", "loud"]);
+	assert_eq!(project.sounds["loud"].path, Path::new("sfx/loud.wav"));
+	assert_eq!(project.sounds["loud"].settings.cooldown, Some(0.1));
+	std::fs::write(directory.join("broken.ron"), "(sounds: {").unwrap();
+	assert!(matches!(
+		Project::from_file(directory.join("broken.ron")),
+		Err(PreviewError::ParseError(_))
+	));
+}
+
+#[test]
+fn reloading_a_project_replaces_its_sounds() {
+	let directory = create_project_directory("reload");
+	let project_path = directory.join("project.ron");
+	std::fs::create_dir_all(directory.join("sfx")).unwrap();
+	write_constant_wav(&directory.join("quiet.wav"), 0.25);
+	write_constant_wav(&directory.join("sfx/loud.wav"), 1.0);
+	std::fs::write(&project_path, PROJECT).unwrap();
+	let (manager, mut backend) = create_manager();
+	let mut preview = Preview::from_audio_manager(&project_path, manager).unwrap();
+	assert_eq!(preview.sound_names().collect::<Vec<_>>(), ["quiet", "loud"]);
+	let quiet_id = preview.sounds["quiet"].id();
+	preview.play("quiet").unwrap();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::from_mono(0.25).panned(0.5));
+
+	// changed audio files are swapped in under the same ID,
+	// and instances that are playing switch to the new audio
+	write_constant_wav(&directory.join("quiet.wav"), 0.5);
+	std::fs::write(
+		&project_path,
+		r#"(sounds: {"quiet": (path: "quiet.wav"), "new": (path: "sfx/loud.wav")})"#,
+	)
+	.unwrap();
+	preview.reload().unwrap();
+	assert_eq!(preview.sound_names().collect::<Vec<_>>(), ["quiet", "new"]);
+	assert_eq!(preview.sounds["quiet"].id(), quiet_id);
+	assert_eq!(backend.process(), Frame::from_mono(0.5).panned(0.5));
+	assert!(matches!(
+		preview.play("loud"),
+		Err(PreviewError::NoSoundWithName(_))
+	));
+
+	// if the project can't be loaded, the previous one is kept
+	std::fs::write(
+		&project_path,
+		r#"(sounds: {"missing": (path: "missing.wav")})"#,
+	)
+	.unwrap();
+	assert!(matches!(
+		preview.reload(),
+		Err(PreviewError::SoundFromFileError(..))
+	));
+	assert_eq!(preview.sound_names().collect::<Vec<_>>(), ["quiet", "new"]);
+	assert_eq!(backend.process(), Frame::from_mono(0.5).panned(0.5));
+}
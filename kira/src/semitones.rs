@@ -0,0 +1,107 @@
+use std::ops::{Add, Neg, Range, Sub};
+
+use crate::Value;
+
+/// A change in pitch (in semitones).
+///
+/// Semitones can be used anywhere a playback rate is expected,
+/// so an instance can be transposed up a fifth with:
+///
+/// ```no_run
+/// # use kira::{
+/// # 	instance::InstanceSettings, manager::{AudioManager, AudioManagerSettings},
+/// # 	sound::SoundSettings, Semitones,
+/// # };
+/// # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+/// # let mut sound_handle = audio_manager.load_sound("loop.ogg", SoundSettings::default())?;
+/// let mut instance_handle = sound_handle.play(InstanceSettings::default())?;
+/// instance_handle.set_playback_rate(Semitones(7.0))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Smaller adjustments can be made in cents, which are
+/// hundredths of a semitone:
+///
+/// ```
+/// # use kira::Semitones;
+/// let detuned = Semitones(7.0) + Semitones::from_cents(-12.0);
+/// assert!((detuned.0 - 6.88).abs() < 1.0e-9);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Semitones(pub f64);
+
+impl Semitones {
+	/// Creates a change in pitch from a number of cents.
+	pub fn from_cents(cents: f64) -> Self {
+		Self(cents / 100.0)
+	}
+
+	/// Gets the change in pitch that playing a sound at the
+	/// given playback rate causes.
+	pub fn from_playback_rate(playback_rate: f64) -> Self {
+		Self(12.0 * playback_rate.log2())
+	}
+
+	/// Returns the change in pitch in cents.
+	pub fn cents(&self) -> f64 {
+		self.0 * 100.0
+	}
+
+	/// Returns the playback rate that changes the pitch of
+	/// a sound by this many semitones.
+	pub fn playback_rate(&self) -> f64 {
+		2.0f64.powf(self.0 / 12.0)
+	}
+}
+
+impl Add for Semitones {
+	type Output = Self;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		Self(self.0 + rhs.0)
+	}
+}
+
+impl Sub for Semitones {
+	type Output = Self;
+
+	fn sub(self, rhs: Self) -> Self::Output {
+		Self(self.0 - rhs.0)
+	}
+}
+
+impl Neg for Semitones {
+	type Output = Self;
+
+	fn neg(self) -> Self::Output {
+		Self(-self.0)
+	}
+}
+
+impl From<f64> for Semitones {
+	fn from(semitones: f64) -> Self {
+		Self(semitones)
+	}
+}
+
+impl From<Semitones> for f64 {
+	fn from(semitones: Semitones) -> Self {
+		semitones.0
+	}
+}
+
+impl From<Semitones> for Value<f64> {
+	fn from(semitones: Semitones) -> Self {
+		Self::Fixed(semitones.playback_rate())
+	}
+}
+
+impl From<Range<Semitones>> for Value<f64> {
+	fn from(range: Range<Semitones>) -> Self {
+		Self::Random(range.start.playback_rate(), range.end.playback_rate())
+	}
+}
@@ -0,0 +1,296 @@
+//! Describes sequences as data.
+//!
+//! A [`SequenceDefinition`] is like a [`Sequence`], but it refers
+//! to sounds, arrangements, parameters, and groups by the names
+//! given to them with [`AudioManager::set_name`](crate::manager::AudioManager::set_name),
+//! and to the instances it plays by names of its own. With the
+//! `serde_support` feature, definitions can be loaded from data
+//! files, and [`AudioManager::sequence_from_definition`](crate::manager::AudioManager::sequence_from_definition)
+//! turns them into sequences:
+//!
+//! ```json
+//! {
+//! 	"steps": [
+//! 		{ "Play": { "playable": { "Sound": "drums" }, "instance": "drums" } },
+//! 		{ "Wait": { "Beats": 4.0 } },
+//! 		"StartLoop",
+//! 		{ "Chance": 0.5 },
+//! 		{ "Play": { "playable": { "Arrangement": "fill" } } },
+//! 		{ "WaitForInterval": 4.0 },
+//! 		{ "SetParameter": { "parameter": "intensity", "target": 1.0, "tween": null } }
+//! 	]
+//! }
+//! ```
+
+use std::{collections::HashMap, hash::Hash};
+
+use crate::{
+	instance::{
+		InstanceId, InstanceSettings, PauseInstanceSettings, ResumeInstanceSettings,
+		StopInstanceSettings,
+	},
+	manager::{
+		error::ResolveNameError,
+		names::{Names, PlayableName},
+	},
+	parameter::tween::Tween,
+	Duration, Value,
+};
+
+use super::{Sequence, SequenceSettings};
+
+/// A step in a [`SequenceDefinition`].
+///
+/// Each step corresponds to the [`Sequence`] method
+/// with the same name.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SequenceStepDefinition<CustomEvent> {
+	/// Waits for a duration of time.
+	Wait(Duration),
+	/// Waits for the next multiple of an interval (in beats)
+	/// of the sequence's metronome.
+	WaitForInterval(f64),
+	/// Marks the point the sequence loops back to.
+	StartLoop,
+	/// Plays a sound or arrangement.
+	Play {
+		/// The sound or arrangement to play.
+		playable: PlayableName,
+		/// A name later steps can use to refer to the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		instance: Option<String>,
+		/// The settings for the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: InstanceSettings,
+	},
+	/// Plays a random sound or arrangement from a list of
+	/// choices, each paired with a weight.
+	PlayRandom {
+		/// The sounds or arrangements to choose from
+		/// and their weights.
+		choices: Vec<(PlayableName, f64)>,
+		/// A name later steps can use to refer to the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		instance: Option<String>,
+		/// The settings for the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: InstanceSettings,
+	},
+	/// Gives the next step a chance of running.
+	Chance(f64),
+	/// Sets the volume of an instance.
+	SetInstanceVolume {
+		/// The name of the instance.
+		instance: String,
+		/// The volume to use.
+		volume: Value<f64>,
+	},
+	/// Sets the playback rate of an instance.
+	SetInstancePlaybackRate {
+		/// The name of the instance.
+		instance: String,
+		/// The playback rate to use.
+		playback_rate: Value<f64>,
+	},
+	/// Sets the panning of an instance.
+	SetInstancePanning {
+		/// The name of the instance.
+		instance: String,
+		/// The panning to use.
+		panning: Value<f64>,
+	},
+	/// Pauses an instance.
+	PauseInstance {
+		/// The name of the instance.
+		instance: String,
+		/// The settings for pausing the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: PauseInstanceSettings,
+	},
+	/// Resumes an instance.
+	ResumeInstance {
+		/// The name of the instance.
+		instance: String,
+		/// The settings for resuming the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: ResumeInstanceSettings,
+	},
+	/// Stops an instance.
+	StopInstance {
+		/// The name of the instance.
+		instance: String,
+		/// The settings for stopping the instance.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: StopInstanceSettings,
+	},
+	/// Pauses every instance of a sound or arrangement.
+	PauseInstancesOf {
+		/// The sound or arrangement.
+		playable: PlayableName,
+		/// The settings for pausing the instances.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: PauseInstanceSettings,
+	},
+	/// Resumes every instance of a sound or arrangement.
+	ResumeInstancesOf {
+		/// The sound or arrangement.
+		playable: PlayableName,
+		/// The settings for resuming the instances.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: ResumeInstanceSettings,
+	},
+	/// Stops every instance of a sound or arrangement.
+	StopInstancesOf {
+		/// The sound or arrangement.
+		playable: PlayableName,
+		/// The settings for stopping the instances.
+		#[cfg_attr(feature = "serde_support", serde(default))]
+		settings: StopInstanceSettings,
+	},
+	/// Sets a parameter to a value.
+	SetParameter {
+		/// The name of the parameter.
+		parameter: String,
+		/// The value to set the parameter to.
+		target: f64,
+		/// The tween to use to smoothly set the parameter.
+		tween: Option<Tween>,
+	},
+	/// Emits a custom event.
+	Emit(CustomEvent),
+	/// Waits for the sequence instance to be signaled
+	/// with a custom event.
+	WaitForSignal(CustomEvent),
+}
+
+/// A sequence that refers to resources by name.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default),
+	serde(bound(
+		serialize = "CustomEvent: serde::Serialize",
+		deserialize = "CustomEvent: serde::Deserialize<'de>"
+	))
+)]
+pub struct SequenceDefinition<CustomEvent = ()> {
+	/// The names of the groups the sequence belongs to.
+	pub groups: Vec<String>,
+	/// The steps of the sequence, in order.
+	pub steps: Vec<SequenceStepDefinition<CustomEvent>>,
+}
+
+impl<CustomEvent> SequenceDefinition<CustomEvent> {
+	/// Creates a new, empty sequence definition.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl<CustomEvent> Default for SequenceDefinition<CustomEvent> {
+	fn default() -> Self {
+		Self {
+			groups: vec![],
+			steps: vec![],
+		}
+	}
+}
+
+impl<CustomEvent: Clone + Eq + Hash> SequenceDefinition<CustomEvent> {
+	/// Creates a sequence from the definition, looking up
+	/// resources using the given names.
+	pub(crate) fn resolve(&self, names: &Names) -> Result<Sequence<CustomEvent>, ResolveNameError> {
+		let mut sequence =
+			Sequence::new(SequenceSettings::new().groups(names.resolve_groups(&self.groups)?));
+		let mut instances = HashMap::<&str, InstanceId>::new();
+		let instance_id = |instances: &HashMap<&str, InstanceId>, name: &str| {
+			instances
+				.get(name)
+				.copied()
+				.ok_or_else(|| ResolveNameError::NoInstanceWithName(name.to_string()))
+		};
+		for step in &self.steps {
+			match step {
+				SequenceStepDefinition::Wait(duration) => sequence.wait(*duration),
+				SequenceStepDefinition::WaitForInterval(interval) => {
+					sequence.wait_for_interval(*interval)
+				}
+				SequenceStepDefinition::StartLoop => sequence.start_loop(),
+				SequenceStepDefinition::Play {
+					playable,
+					instance,
+					settings,
+				} => {
+					let id = sequence.play(names.resolve_playable(playable)?, *settings);
+					if let Some(name) = instance {
+						instances.insert(name, id);
+					}
+				}
+				SequenceStepDefinition::PlayRandom {
+					choices,
+					instance,
+					settings,
+				} => {
+					let choices = choices
+						.iter()
+						.map(|(playable, weight)| Ok((names.resolve_playable(playable)?, *weight)))
+						.collect::<Result<_, ResolveNameError>>()?;
+					let id = sequence.play_random_weighted(choices, *settings);
+					if let Some(name) = instance {
+						instances.insert(name, id);
+					}
+				}
+				SequenceStepDefinition::Chance(probability) => sequence.chance(*probability),
+				SequenceStepDefinition::SetInstanceVolume {
+					instance: name,
+					volume,
+				} => sequence.set_instance_volume(instance_id(&instances, name)?, *volume),
+				SequenceStepDefinition::SetInstancePlaybackRate {
+					instance: name,
+					playback_rate,
+				} => sequence
+					.set_instance_playback_rate(instance_id(&instances, name)?, *playback_rate),
+				SequenceStepDefinition::SetInstancePanning {
+					instance: name,
+					panning,
+				} => sequence.set_instance_panning(instance_id(&instances, name)?, *panning),
+				SequenceStepDefinition::PauseInstance {
+					instance: name,
+					settings,
+				} => sequence.pause_instance(instance_id(&instances, name)?, *settings),
+				SequenceStepDefinition::ResumeInstance {
+					instance: name,
+					settings,
+				} => sequence.resume_instance(instance_id(&instances, name)?, *settings),
+				SequenceStepDefinition::StopInstance {
+					instance: name,
+					settings,
+				} => sequence.stop_instance(instance_id(&instances, name)?, *settings),
+				SequenceStepDefinition::PauseInstancesOf { playable, settings } => {
+					sequence.pause_instances_of(names.resolve_playable(playable)?, *settings)
+				}
+				SequenceStepDefinition::ResumeInstancesOf { playable, settings } => {
+					sequence.resume_instances_of(names.resolve_playable(playable)?, *settings)
+				}
+				SequenceStepDefinition::StopInstancesOf { playable, settings } => {
+					sequence.stop_instances_of(names.resolve_playable(playable)?, *settings)
+				}
+				SequenceStepDefinition::SetParameter {
+					parameter,
+					target,
+					tween,
+				} => sequence.set_parameter(names.resolve_parameter(parameter)?, *target, *tween),
+				SequenceStepDefinition::Emit(event) => sequence.emit(event.clone()),
+				SequenceStepDefinition::WaitForSignal(event) => {
+					sequence.wait_for_signal(event.clone())
+				}
+			}
+		}
+		Ok(sequence)
+	}
+}
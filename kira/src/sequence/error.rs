@@ -5,8 +5,9 @@ use thiserror::Error;
 /// Invalid states a sequence can be in.
 #[derive(Debug, Error)]
 pub enum SequenceError {
-	/// The sequence has a looping section without a `wait`
-	/// or `wait_for_interval` command.
+	/// The sequence (or one of its sub-sequences) has a looping
+	/// section without a `wait`, `wait_for_interval`, or
+	/// `wait_for_signal` command.
 	///
 	/// This is invalid because if this sequence were to run,
 	/// the looping section would lock up the audio thread
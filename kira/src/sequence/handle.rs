@@ -2,7 +2,8 @@
 
 use std::{
 	fmt::Debug,
-	sync::{Arc, Mutex},
+	hash::Hash,
+	sync::{atomic::AtomicUsize, Arc, Mutex},
 };
 
 use atomic::{Atomic, Ordering};
@@ -16,9 +17,12 @@ use crate::{
 		InstanceCommand, SequenceCommand,
 	},
 	instance::{PauseInstanceSettings, ResumeInstanceSettings, StopInstanceSettings},
+	Value,
 };
 
-use super::{SequenceInstanceId, SequenceInstanceState};
+use super::{
+	instance::PublicSequencePosition, SequenceInstanceId, SequenceInstanceState, SequencePosition,
+};
 
 /// Something that can go wrong when using a [`SequenceInstanceHandle`]
 /// to receive an event from a sequence instance.
@@ -29,21 +33,40 @@ pub enum PopSequenceInstanceEventError {
 	MutexPoisoned,
 }
 
+/// Something that can go wrong when using a [`SequenceInstanceHandle`]
+/// to signal an event to a sequence instance.
+#[derive(Debug, Error)]
+pub enum SignalSequenceInstanceError {
+	/// The sequence never waits for the specified event.
+	#[error("The sequence does not wait for the specified event")]
+	NoSuchEvent,
+	/// A command could not be sent to the audio thread.
+	#[error("Could not send the command to the audio thread.")]
+	CommandProducerError(#[from] CommandError),
+}
+
 /// Allows you to control an instance of a sequence.
 
 #[derive(Clone)]
 pub struct SequenceInstanceHandle<CustomEvent> {
 	id: SequenceInstanceId,
 	state: Arc<Atomic<SequenceInstanceState>>,
+	overflow_count: Arc<AtomicUsize>,
+	loop_count: Arc<AtomicUsize>,
+	position: Arc<PublicSequencePosition>,
 	command_producer: CommandProducer,
 	raw_event_consumer: Arc<Mutex<Consumer<usize>>>,
 	events: IndexSet<CustomEvent>,
 }
 
 impl<CustomEvent> SequenceInstanceHandle<CustomEvent> {
+	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn new(
 		id: SequenceInstanceId,
 		state: Arc<Atomic<SequenceInstanceState>>,
+		overflow_count: Arc<AtomicUsize>,
+		loop_count: Arc<AtomicUsize>,
+		position: Arc<PublicSequencePosition>,
 		command_producer: CommandProducer,
 		raw_event_consumer: Consumer<usize>,
 		events: IndexSet<CustomEvent>,
@@ -51,6 +74,9 @@ impl<CustomEvent> SequenceInstanceHandle<CustomEvent> {
 		Self {
 			id,
 			state,
+			overflow_count,
+			loop_count,
+			position,
 			command_producer,
 			raw_event_consumer: Arc::new(Mutex::new(raw_event_consumer)),
 			events,
@@ -67,6 +93,40 @@ impl<CustomEvent> SequenceInstanceHandle<CustomEvent> {
 		self.state.load(Ordering::Relaxed)
 	}
 
+	/// Returns the number of times this sequence instance has had
+	/// to postpone steps because too many commands were queued up
+	/// on the audio thread at once.
+	///
+	/// When this happens, the remaining steps run on the next
+	/// audio frame in their original order, so no actions are lost,
+	/// but they will be slightly late. An overflow that takes several
+	/// frames to catch up on is only counted once. If this number
+	/// keeps going up, consider raising
+	/// [`num_commands`](crate::manager::AudioManagerSettings::num_commands).
+	///
+	/// Overflows are counted here instead of being emitted as events,
+	/// since the sequence's event queue only holds the custom events
+	/// passed to [`Sequence::emit`](super::Sequence::emit).
+	pub fn overflow_count(&self) -> usize {
+		self.overflow_count.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of times the sequence instance has
+	/// looped back to its loop point.
+	pub fn loop_count(&self) -> usize {
+		self.loop_count.load(Ordering::Relaxed)
+	}
+
+	/// Returns the position of the sequence instance as last
+	/// reported by the audio thread.
+	///
+	/// This is updated every audio frame, so it can be used to
+	/// show which section of a song is playing or to save the
+	/// progress of a sequence.
+	pub fn position(&self) -> SequencePosition {
+		self.position.load()
+	}
+
 	/// Mutes the sequence instance.
 	///
 	/// Muted instances will continue waiting for durations and
@@ -83,6 +143,16 @@ impl<CustomEvent> SequenceInstanceHandle<CustomEvent> {
 			.push(SequenceCommand::UnmuteSequenceInstance(self.id).into())
 	}
 
+	/// Sets how fast the sequence instance moves through wait steps.
+	///
+	/// A speed of `2.0` halves the length of every wait,
+	/// and a speed of `0.5` doubles it. Waits that are already
+	/// in progress will speed up or slow down accordingly.
+	pub fn set_speed(&mut self, speed: impl Into<Value<f64>>) -> Result<(), CommandError> {
+		self.command_producer
+			.push(SequenceCommand::SetSequenceInstanceSpeed(self.id, speed.into()).into())
+	}
+
 	/// Pauses the sequence instance.
 	pub fn pause(&mut self) -> Result<(), CommandError> {
 		self.command_producer
@@ -155,6 +225,23 @@ impl<CustomEvent> SequenceInstanceHandle<CustomEvent> {
 	}
 }
 
+impl<CustomEvent: Eq + Hash> SequenceInstanceHandle<CustomEvent> {
+	/// Signals an event to the sequence instance.
+	///
+	/// If the sequence instance is currently waiting for this
+	/// event (using a `wait_for_signal` step), it will move
+	/// on to the next step.
+	pub fn signal(&mut self, event: &CustomEvent) -> Result<(), SignalSequenceInstanceError> {
+		let index = self
+			.events
+			.get_index_of(event)
+			.ok_or(SignalSequenceInstanceError::NoSuchEvent)?;
+		self.command_producer
+			.push(SequenceCommand::SignalSequenceInstance(self.id, index).into())?;
+		Ok(())
+	}
+}
+
 impl<T: Debug> Debug for SequenceInstanceHandle<T> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		#[derive(Debug)]
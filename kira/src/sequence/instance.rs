@@ -1,15 +1,24 @@
-use std::sync::{atomic::Ordering, Arc};
+use std::sync::{
+	atomic::{AtomicU64, AtomicUsize, Ordering},
+	Arc,
+};
 
 use atomic::Atomic;
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use ringbuf::Producer;
 use uuid::Uuid;
 
 use crate::{
 	group::{groups::Groups, GroupId},
+	manager::{
+		snapshot::SequenceSnapshot,
+		trace::{TraceEventKind, Tracer},
+	},
 	metronome::{MetronomeId, Metronomes},
+	parameter::Parameters,
+	playable::PlayableId,
 	static_container::vec::StaticVec,
-	Tempo,
+	CachedValue, Tempo, Value,
 };
 
 use super::{RawSequence, SequenceInstanceHandle, SequenceOutputCommand, SequenceStep};
@@ -39,6 +48,63 @@ impl<CustomEvent> From<&SequenceInstanceHandle<CustomEvent>> for SequenceInstanc
 	}
 }
 
+/// The position of a sequence instance as last reported
+/// by the audio thread.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct SequencePosition {
+	/// The index of the step the sequence instance is on.
+	///
+	/// Steps are counted in the order they were added to the
+	/// sequence, including steps added by
+	/// [`run_sequence`](crate::sequence::Sequence::run_sequence).
+	pub step: usize,
+	/// How far through the current wait step the sequence
+	/// instance is, from `0.0` to `1.0`.
+	///
+	/// This is `0.0` if the current step isn't a
+	/// [`wait`](crate::sequence::Sequence::wait) step.
+	pub wait_progress: f64,
+}
+
+/// The last [`SequencePosition`] of a sequence instance, shared
+/// between the audio thread and sequence instance handles.
+///
+/// The step and wait progress are stored in separate atomics
+/// (the wait progress as the bits of an `f64`) so reporting
+/// the position never has to take a lock. A handle may see the
+/// step of one report and the wait progress of the next.
+#[derive(Debug)]
+pub(crate) struct PublicSequencePosition {
+	step: AtomicUsize,
+	wait_progress: AtomicU64,
+}
+
+impl PublicSequencePosition {
+	fn new(position: SequencePosition) -> Self {
+		Self {
+			step: AtomicUsize::new(position.step),
+			wait_progress: AtomicU64::new(position.wait_progress.to_bits()),
+		}
+	}
+
+	fn store(&self, position: SequencePosition) {
+		self.step.store(position.step, Ordering::Relaxed);
+		self.wait_progress
+			.store(position.wait_progress.to_bits(), Ordering::Relaxed);
+	}
+
+	pub fn load(&self) -> SequencePosition {
+		SequencePosition {
+			step: self.step.load(Ordering::Relaxed),
+			wait_progress: f64::from_bits(self.wait_progress.load(Ordering::Relaxed)),
+		}
+	}
+}
+
 /// The playback state of an instance of a sequence.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum SequenceInstanceState {
@@ -54,31 +120,60 @@ pub enum SequenceInstanceState {
 }
 
 pub struct SequenceInstance {
+	id: SequenceInstanceId,
 	sequence: RawSequence,
 	metronome: Option<MetronomeId>,
 	state: SequenceInstanceState,
 	public_state: Arc<Atomic<SequenceInstanceState>>,
+	overflow_count: Arc<AtomicUsize>,
+	// whether steps were postponed on the last update, so an
+	// overflow that lasts several frames is only counted once
+	overflowing: bool,
+	loop_count: Arc<AtomicUsize>,
+	public_position: Arc<PublicSequencePosition>,
 	position: usize,
+	// whether a step has started since the last time
+	// one was recorded in the trace
+	step_started: bool,
 	wait_timer: Option<f64>,
+	speed: CachedValue<f64>,
 	muted: bool,
 	event_producer: Producer<usize>,
+	rng: StdRng,
 }
 
 impl SequenceInstance {
 	pub fn new(
+		id: SequenceInstanceId,
 		sequence: RawSequence,
 		event_producer: Producer<usize>,
 		metronome: Option<MetronomeId>,
+		speed: Value<f64>,
+		random_seed: Option<u64>,
 	) -> Self {
 		Self {
+			id,
 			sequence,
 			metronome,
 			state: SequenceInstanceState::Playing,
 			public_state: Arc::new(Atomic::new(SequenceInstanceState::Playing)),
+			overflow_count: Arc::new(AtomicUsize::new(0)),
+			overflowing: false,
+			loop_count: Arc::new(AtomicUsize::new(0)),
+			public_position: Arc::new(PublicSequencePosition::new(SequencePosition {
+				step: 0,
+				wait_progress: 0.0,
+			})),
 			position: 0,
+			step_started: false,
 			wait_timer: None,
+			speed: CachedValue::new(speed, 1.0).with_min(0.0),
 			muted: false,
 			event_producer,
+			rng: match random_seed {
+				Some(seed) => StdRng::seed_from_u64(seed),
+				None => StdRng::from_entropy(),
+			},
 		}
 	}
 
@@ -86,6 +181,18 @@ impl SequenceInstance {
 		self.public_state.clone()
 	}
 
+	pub fn overflow_count(&self) -> Arc<AtomicUsize> {
+		self.overflow_count.clone()
+	}
+
+	pub fn loop_count(&self) -> Arc<AtomicUsize> {
+		self.loop_count.clone()
+	}
+
+	pub fn public_position(&self) -> Arc<PublicSequencePosition> {
+		self.public_position.clone()
+	}
+
 	fn set_state(&mut self, state: SequenceInstanceState) {
 		self.state = state;
 		self.public_state.store(state, Ordering::Relaxed);
@@ -94,23 +201,42 @@ impl SequenceInstance {
 	fn start_step(&mut self, index: usize) {
 		if let Some(step) = self.sequence.steps.get(index) {
 			self.position = index;
+			self.step_started = true;
 			if let SequenceStep::Wait(_) = step {
 				self.wait_timer = Some(1.0);
 			} else {
 				self.wait_timer = None;
 			}
 		} else if let Some(loop_point) = self.sequence.loop_point {
-			self.sequence.update_instance_ids();
+			self.sequence
+				.update_instance_ids(0..self.sequence.steps.len());
+			self.loop_count.fetch_add(1, Ordering::Relaxed);
 			self.start_step(loop_point);
 		} else {
 			self.set_state(SequenceInstanceState::Finished);
 		}
 	}
 
+	/// Moves past the step at the given index without running it.
+	///
+	/// Jumps are never skipped. They mark the end of a looping
+	/// sub-sequence, so a step right before one is the last step
+	/// of that sub-sequence and has no step of its own after it.
+	fn skip_step(&mut self, index: usize) {
+		match self.sequence.steps.get(index) {
+			Some(SequenceStep::JumpTo(_)) | None => self.start_step(index),
+			Some(_) => self.start_step(index + 1),
+		}
+	}
+
 	pub(crate) fn start(&mut self) {
 		self.start_step(0);
 	}
 
+	pub(crate) fn set_speed(&mut self, speed: Value<f64>) {
+		self.speed.set(speed);
+	}
+
 	pub(crate) fn mute(&mut self) {
 		self.muted = true;
 	}
@@ -131,20 +257,116 @@ impl SequenceInstance {
 		self.set_state(SequenceInstanceState::Finished);
 	}
 
+	pub(crate) fn signal(&mut self, event: usize) {
+		if let SequenceInstanceState::Finished = self.state {
+			return;
+		}
+		if let Some(SequenceStep::WaitForSignal(awaited_event)) =
+			self.sequence.steps.get(self.position)
+		{
+			if *awaited_event == event {
+				self.start_step(self.position + 1);
+			}
+		}
+	}
+
+	/// Gives instances played by the sequence a random seed
+	/// from the sequence's random number generator, so their
+	/// random values are reproducible.
+	fn seed_command(&mut self, command: SequenceOutputCommand) -> SequenceOutputCommand {
+		match command {
+			SequenceOutputCommand::PlaySound(playable, id, settings)
+				if settings.random_seed.is_none() =>
+			{
+				SequenceOutputCommand::PlaySound(playable, id, settings.random_seed(self.rng.gen()))
+			}
+			command => command,
+		}
+	}
+
+	/// Picks a random playable from a list of weighted choices.
+	///
+	/// Each weight is only looked up once, so random weights
+	/// get a single roll per pick.
+	fn pick_weighted(
+		choices: &[(PlayableId, Value<f64>)],
+		parameters: &Parameters,
+		rng: &mut impl Rng,
+	) -> Option<PlayableId> {
+		let mut total_weight = 0.0;
+		let mut picked = None;
+		for (choice, weight) in choices {
+			let weight = weight.get_with_rng(parameters, 0.0, rng);
+			// weights can come from parameters, so NaN and infinite
+			// weights have to be skipped here instead of rejected
+			// up front
+			if !(weight.is_finite() && weight > 0.0) {
+				continue;
+			}
+			// each choice replaces the previous pick with a chance
+			// proportional to its share of the weights seen so far.
+			// the total can still overflow to infinity, which
+			// gen_range would panic on
+			total_weight += weight;
+			if rng.gen::<f64>() * total_weight < weight {
+				picked = Some(*choice);
+			}
+		}
+		picked
+	}
+
+	/// Returns `true` if running the given step would output
+	/// a command.
+	fn step_outputs_command(&self, step: &SequenceStep<usize>) -> bool {
+		!self.muted
+			&& matches!(
+				step,
+				SequenceStep::RunCommand(_) | SequenceStep::PlayRandom(..)
+			)
+	}
+
+	/// Runs the steps of the sequence that are due.
+	///
+	/// At most `max_commands` commands will be pushed to the output
+	/// command queue. If a step would push more, it (and all
+	/// the steps after it) are postponed to the next update, so
+	/// commands are never dropped or reordered.
 	pub(crate) fn update(
 		&mut self,
 		dt: f64,
 		metronomes: &Metronomes,
+		parameters: &Parameters,
 		output_command_queue: &mut StaticVec<SequenceOutputCommand>,
+		max_commands: usize,
+		tracer: &mut Tracer,
 	) {
 		let metronome = self.metronome.map(|id| metronomes.get(id)).flatten();
+		let max_commands = max_commands.min(output_command_queue.capacity());
+		let was_overflowing = std::mem::replace(&mut self.overflowing, false);
+		self.speed.update(parameters);
 		loop {
 			match self.state {
 				SequenceInstanceState::Paused | SequenceInstanceState::Finished => {
 					break;
 				}
 				_ => {
+					if self.step_started {
+						self.step_started = false;
+						tracer.record(TraceEventKind::SequenceStep {
+							sequence: self.id,
+							step: self.position,
+						});
+					}
 					if let Some(step) = self.sequence.steps.get(self.position) {
+						if self.step_outputs_command(step)
+							&& output_command_queue.len() >= max_commands
+						{
+							if !was_overflowing {
+								self.overflow_count.fetch_add(1, Ordering::Relaxed);
+							}
+							self.overflowing = true;
+							break;
+						}
 						match step {
 							SequenceStep::Wait(duration) => {
 								if let Some(time) = self.wait_timer.as_mut() {
@@ -154,7 +376,7 @@ impl SequenceInstance {
 										} else {
 											Tempo(0.0)
 										});
-									*time -= dt / duration;
+									*time -= dt * self.speed.value() / duration;
 									if *time <= 0.0 {
 										self.start_step(self.position + 1);
 									}
@@ -171,34 +393,96 @@ impl SequenceInstance {
 							}
 							SequenceStep::RunCommand(command) => {
 								if !self.muted {
-									output_command_queue.try_push(*command).ok();
+									output_command_queue
+										.try_push(self.seed_command(*command))
+										.ok();
 								}
 								self.start_step(self.position + 1);
 							}
 							SequenceStep::PlayRandom(choices, id, settings) => {
 								if !self.muted {
-									let choice_index = thread_rng().gen_range(0..choices.len());
-									output_command_queue
-										.try_push(SequenceOutputCommand::PlaySound(
-											choices[choice_index],
-											*id,
-											*settings,
-										))
-										.ok();
+									if let Some(choice) =
+										Self::pick_weighted(choices, parameters, &mut self.rng)
+									{
+										output_command_queue
+											.try_push(self.seed_command(
+												SequenceOutputCommand::PlaySound(
+													choice, *id, *settings,
+												),
+											))
+											.ok();
+									}
 								}
 								self.start_step(self.position + 1);
 							}
+							SequenceStep::Chance(probability) => {
+								let probability =
+									probability.get_with_rng(parameters, 0.0, &mut self.rng);
+								if self.rng.gen::<f64>() < probability {
+									self.start_step(self.position + 1);
+								} else {
+									self.skip_step(self.position + 1);
+								}
+							}
 							SequenceStep::EmitCustomEvent(event) => {
 								if !self.muted {
 									self.event_producer.push(*event).ok();
 								}
 								self.start_step(self.position + 1);
 							}
+							SequenceStep::WaitForSignal(_) => {
+								break;
+							}
+							SequenceStep::JumpTo(index) => {
+								let index = *index;
+								self.sequence.update_instance_ids(index..self.position);
+								self.start_step(index);
+							}
 						}
 					}
 				}
 			}
 		}
+		self.report_position();
+	}
+
+	pub(crate) fn position(&self) -> SequencePosition {
+		let wait_progress = match self.sequence.steps.get(self.position) {
+			Some(SequenceStep::Wait(_)) => self
+				.wait_timer
+				.map_or(0.0, |time| (1.0 - time).clamp(0.0, 1.0)),
+			_ => 0.0,
+		};
+		SequencePosition {
+			step: self.position,
+			wait_progress,
+		}
+	}
+
+	/// Jumps to a position, e.g. when restoring a snapshot.
+	pub(crate) fn set_position(&mut self, position: SequencePosition, loop_count: usize) {
+		if position.step >= self.sequence.steps.len() {
+			return;
+		}
+		self.start_step(position.step);
+		if self.wait_timer.is_some() {
+			self.wait_timer = Some(1.0 - position.wait_progress.clamp(0.0, 1.0));
+		}
+		self.loop_count.store(loop_count, Ordering::Relaxed);
+		self.report_position();
+	}
+
+	pub(crate) fn snapshot(&self) -> SequenceSnapshot {
+		SequenceSnapshot {
+			id: self.id,
+			position: self.position(),
+			loop_count: self.loop_count.load(Ordering::Relaxed),
+			paused: self.state == SequenceInstanceState::Paused,
+		}
+	}
+
+	fn report_position(&self) {
+		self.public_position.store(self.position());
 	}
 
 	pub(crate) fn finished(&self) -> bool {
@@ -13,7 +13,7 @@
 //! # 	sound::Sound,
 //! # 	Duration, Tempo,
 //! # };
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! let mut sequence = Sequence::<()>::new(SequenceSettings::default());
 //! // play a sound
@@ -36,7 +36,7 @@
 //! # 	sound::Sound,
 //! # 	Duration, Tempo,
 //! # };
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! # let mut sequence = Sequence::<()>::new(SequenceSettings::default());
 //! audio_manager.start_sequence(sequence, SequenceInstanceSettings::default())?;
@@ -60,7 +60,7 @@
 //! # 	sound::Sound,
 //! # 	Duration, Tempo,
 //! # };
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! # let mut sequence = Sequence::<()>::new(SequenceSettings::default());
 //! sequence.wait_for_interval(4.0);
@@ -85,7 +85,7 @@
 //! # 	sound::Sound,
 //! # 	Duration, Tempo,
 //! # };
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! # let mut sequence = Sequence::<()>::new(SequenceSettings::default());
 //! sequence.wait_for_interval(4.0);
@@ -97,6 +97,78 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
+//! Random steps, like `Sequence::chance()` and
+//! `Sequence::play_random_weighted()`, roll again every time they
+//! run, so each loop of a sequence can turn out differently. Their
+//! probabilities and weights can also be linked to parameters to
+//! change the odds for later loops while the sequence is playing:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	parameter::ParameterSettings,
+//! # 	sequence::{Sequence, SequenceSettings, SequenceInstanceSettings},
+//! # 	sound::Sound,
+//! # 	Duration, Tempo,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+//! # let hat_sound_handle = audio_manager.add_sound(Sound::from_file("hat.ogg", Default::default())?)?;
+//! let mut busyness = audio_manager.add_parameter(ParameterSettings::new().value(0.25))?;
+//! let mut sequence = Sequence::<()>::new(SequenceSettings::default());
+//! sequence.start_loop();
+//! sequence.chance(&busyness);
+//! sequence.play(&hat_sound_handle, InstanceSettings::default());
+//! sequence.wait(Duration::Beats(0.5));
+//! let sequence_instance_handle =
+//! 	audio_manager.start_sequence(sequence, SequenceInstanceSettings::default())?;
+//! // later, after a few loops, make the hi-hats busier
+//! if sequence_instance_handle.loop_count() >= 8 {
+//! 	busyness.set(0.75, None)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Sub-sequences
+//!
+//! Larger sequences can be built out of smaller, reusable sequences
+//! using `Sequence::run_sequence()`. The steps after the sub-sequence
+//! will run once the sub-sequence is finished:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sequence::{Sequence, SequenceSettings, SequenceInstanceSettings},
+//! # 	sound::Sound,
+//! # 	Duration, Tempo,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+//! # let intro_sound_handle = audio_manager.add_sound(Sound::from_file("intro.ogg", Default::default())?)?;
+//! # let drum_sound_handle = audio_manager.add_sound(Sound::from_file("drum.ogg", Default::default())?)?;
+//! let mut fill = Sequence::<()>::new(SequenceSettings::default());
+//! for _ in 0..4 {
+//! 	fill.play(&drum_sound_handle, InstanceSettings::default());
+//! 	fill.wait(Duration::Beats(0.25));
+//! }
+//!
+//! let mut sequence = Sequence::<()>::new(SequenceSettings::default());
+//! sequence.play(&intro_sound_handle, InstanceSettings::default());
+//! sequence.wait(Duration::Beats(4.0));
+//! sequence.run_sequence(&fill);
+//! sequence.wait(Duration::Beats(3.0));
+//! sequence.run_sequence(&fill);
+//! # audio_manager.start_sequence(sequence, SequenceInstanceSettings::default())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Sub-sequences are copied into the sequence that runs them, so
+//! they aren't separate sequence instances. They're paused, stopped,
+//! and muted along with that sequence, and their events are received
+//! through its [`SequenceInstanceHandle`]. A sequence can't start an
+//! independent sequence instance; to run sequences side by side, start
+//! each of them with `AudioManager::start_sequence()`.
+//!
 //! ## Custom events
 //!
 //! Sequences can emit custom events that you can receive on the main
@@ -115,7 +187,7 @@
 //! 	Beat,
 //! }
 //!
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! # let mut sequence = Sequence::<CustomEvent>::new(SequenceSettings::default());
 //! sequence.wait_for_interval(4.0);
@@ -144,7 +216,7 @@
 //! # 	Beat,
 //! # }
 //! #
-//! # let mut audio_manager = AudioManager::new(Default::default())?;
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
 //! # let sound_handle = audio_manager.add_sound(Sound::from_file("loop.ogg", Default::default())?)?;
 //! # let mut sequence = Sequence::<CustomEvent>::new(SequenceSettings::default());
 //! # sequence.wait_for_interval(4.0);
@@ -157,7 +229,39 @@
 //! }
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
+//!
+//! Sequences can also wait for the main thread to signal an event,
+//! which is useful for holding a section of music until something
+//! happens in the game:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sequence::{Sequence, SequenceSettings, SequenceInstanceSettings},
+//! # 	sound::Sound,
+//! # 	Duration, Tempo,
+//! # };
+//! #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+//! enum CustomEvent {
+//! 	PuzzleSolved,
+//! }
+//!
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+//! # let pad_sound_handle = audio_manager.add_sound(Sound::from_file("pad.ogg", Default::default())?)?;
+//! let mut sequence = Sequence::<CustomEvent>::new(SequenceSettings::default());
+//! let pad = sequence.play(&pad_sound_handle, InstanceSettings::default());
+//! sequence.wait_for_signal(CustomEvent::PuzzleSolved);
+//! sequence.stop_instance(pad, Default::default());
+//!
+//! let mut sequence_instance_handle =
+//! 	audio_manager.start_sequence(sequence, SequenceInstanceSettings::default())?;
+//! // later...
+//! sequence_instance_handle.signal(&CustomEvent::PuzzleSolved)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
+pub mod definition;
 pub mod error;
 pub mod handle;
 mod instance;
@@ -167,12 +271,12 @@ mod tests;
 use error::SequenceError;
 use handle::SequenceInstanceHandle;
 pub(crate) use instance::SequenceInstance;
-pub use instance::{SequenceInstanceId, SequenceInstanceState};
+pub use instance::{SequenceInstanceId, SequenceInstanceState, SequencePosition};
 
 use indexmap::IndexSet;
 use ringbuf::RingBuffer;
 
-use std::{hash::Hash, vec};
+use std::{hash::Hash, ops::Range, vec};
 
 use crate::{
 	command::producer::CommandProducer,
@@ -182,8 +286,13 @@ use crate::{
 		StopInstanceSettings,
 	},
 	metronome::MetronomeId,
+	mixer::{
+		effect::{handle::EffectHandle, EffectId},
+		TrackIndex,
+	},
 	parameter::{tween::Tween, ParameterId},
 	playable::PlayableId,
+	sound::handle::SoundHandle,
 	Duration, Tempo, Value,
 };
 
@@ -201,6 +310,19 @@ pub struct SequenceInstanceSettings {
 	pub metronome: Option<MetronomeId>,
 	/// How many events can be queued at a time.
 	pub event_queue_capacity: usize,
+	/// How fast the sequence instance moves through wait steps.
+	///
+	/// A speed of `2.0` halves the length of every wait,
+	/// and a speed of `0.5` doubles it.
+	pub speed: Value<f64>,
+	/// The seed to use for the sequence instance's random choices,
+	/// like which sound a [`play_random`](Sequence::play_random) step
+	/// plays and the random values of the instances it plays.
+	///
+	/// If this is `None`, a seed is picked by the audio manager,
+	/// which is reproducible if the manager has a
+	/// [random seed](crate::manager::AudioManagerSettings::random_seed).
+	pub random_seed: Option<u64>,
 }
 
 impl SequenceInstanceSettings {
@@ -232,6 +354,22 @@ impl SequenceInstanceSettings {
 			..self
 		}
 	}
+
+	/// Sets how fast the sequence instance moves through wait steps.
+	pub fn speed(self, speed: impl Into<Value<f64>>) -> Self {
+		Self {
+			speed: speed.into(),
+			..self
+		}
+	}
+
+	/// Sets the seed to use for the sequence instance's random choices.
+	pub fn random_seed(self, random_seed: u64) -> Self {
+		Self {
+			random_seed: Some(random_seed),
+			..self
+		}
+	}
 }
 
 impl Default for SequenceInstanceSettings {
@@ -240,6 +378,8 @@ impl Default for SequenceInstanceSettings {
 			id: None,
 			metronome: None,
 			event_queue_capacity: 10,
+			speed: Value::Fixed(1.0),
+			random_seed: None,
 		}
 	}
 }
@@ -271,6 +411,10 @@ pub(crate) enum SequenceOutputCommand {
 	PauseMetronome(MetronomeId),
 	StopMetronome(MetronomeId),
 	SetParameter(ParameterId, f64, Option<Tween>),
+	SetTrackVolume(TrackIndex, Value<f64>),
+	SetEffectEnabled(TrackIndex, EffectId, bool),
+	SetEffectMix(TrackIndex, EffectId, Value<f64>),
+	SetEffectValue(TrackIndex, EffectId, usize, Value<f64>),
 }
 
 #[derive(Debug, Clone)]
@@ -286,8 +430,13 @@ pub(crate) enum SequenceStep<CustomEvent: Clone + Eq + Hash> {
 	Wait(Duration),
 	WaitForInterval(f64),
 	RunCommand(SequenceOutputCommand),
-	PlayRandom(Vec<PlayableId>, InstanceId, InstanceSettings),
+	PlayRandom(Vec<(PlayableId, Value<f64>)>, InstanceId, InstanceSettings),
+	Chance(Value<f64>),
 	EmitCustomEvent(CustomEvent),
+	WaitForSignal(CustomEvent),
+	// jumps back to the step with the given index. used to
+	// loop sub-sequences that have been embedded in a sequence
+	JumpTo(usize),
 }
 
 impl<CustomEvent: Clone + Eq + Hash> From<SequenceOutputCommand> for SequenceStep<CustomEvent> {
@@ -405,19 +554,75 @@ impl<CustomEvent: Clone + Eq + Hash> Sequence<CustomEvent> {
 		id
 	}
 
+	/// Adds a step to play one of the slices a sound is cut into.
+	///
+	/// Returns `None` without adding a step if the sound has
+	/// no slice with the given index.
+	pub fn play_slice(
+		&mut self,
+		sound: &SoundHandle,
+		index: usize,
+		settings: InstanceSettings,
+	) -> Option<InstanceId> {
+		let region = sound.slice(index)?;
+		Some(self.play(sound, settings.region(region)))
+	}
+
 	/// Adds a step to play a random sound or arrangement from a
 	/// list of choices.
 	pub fn play_random(
 		&mut self,
 		choices: Vec<PlayableId>,
 		settings: InstanceSettings,
+	) -> InstanceId {
+		self.play_random_weighted(
+			choices.into_iter().map(|choice| (choice, 1.0)).collect(),
+			settings,
+		)
+	}
+
+	/// Adds a step to play a random sound or arrangement from a
+	/// list of choices, where each choice is paired with a weight.
+	///
+	/// Choices with higher weights are more likely to be picked.
+	/// For example, a choice with a weight of `2.0` is twice as likely
+	/// to be picked as a choice with a weight of `1.0`. Choices
+	/// with a weight of `0.0` or less, or a weight that isn't a
+	/// finite number, will never be picked.
+	///
+	/// The weights are checked every time the step runs, so weights
+	/// linked to parameters can change the odds while the sequence
+	/// is playing.
+	pub fn play_random_weighted<W: Into<Value<f64>>>(
+		&mut self,
+		choices: Vec<(PlayableId, W)>,
+		settings: InstanceSettings,
 	) -> InstanceId {
 		let id = settings.id.unwrap_or(InstanceId::new());
+		let choices = choices
+			.into_iter()
+			.map(|(choice, weight)| (choice, weight.into()))
+			.collect();
 		self.steps
-			.push(SequenceStep::PlayRandom(choices, id, settings).into());
+			.push(SequenceStep::PlayRandom(choices, id, settings));
 		id
 	}
 
+	/// Adds a step that gives the next step a chance of running.
+	///
+	/// `probability` is the chance that the next step runs, from
+	/// `0.0` (never) to `1.0` (always). If the next step doesn't
+	/// run, it will be skipped. The probability is checked every
+	/// time the step runs, so a probability linked to a parameter
+	/// can change while the sequence is playing.
+	///
+	/// A chance step at the end of a sequence has no effect, even
+	/// if the sequence is added to another one with
+	/// [`run_sequence`](Sequence::run_sequence).
+	pub fn chance(&mut self, probability: impl Into<Value<f64>>) {
+		self.steps.push(SequenceStep::Chance(probability.into()));
+	}
+
 	/// Adds a step to set the volume of an instance.
 	pub fn set_instance_volume(&mut self, id: impl Into<InstanceId>, volume: Value<f64>) {
 		self.steps
@@ -579,39 +784,160 @@ impl<CustomEvent: Clone + Eq + Hash> Sequence<CustomEvent> {
 			.push(SequenceOutputCommand::SetParameter(id.into(), target, tween).into());
 	}
 
+	/// Adds a step to set the volume of a mixer track.
+	pub fn set_track_volume(
+		&mut self,
+		track: impl Into<TrackIndex>,
+		volume: impl Into<Value<f64>>,
+	) {
+		self.steps
+			.push(SequenceOutputCommand::SetTrackVolume(track.into(), volume.into()).into());
+	}
+
+	/// Adds a step to enable or disable an effect.
+	///
+	/// Effects can't be created by a sequence, so to bring an
+	/// effect in or out as part of a sequence, add it to its
+	/// track beforehand and use this step to switch it on or off.
+	pub fn set_effect_enabled(&mut self, effect: &EffectHandle, enabled: bool) {
+		self.steps.push(
+			SequenceOutputCommand::SetEffectEnabled(effect.track_index(), effect.id(), enabled)
+				.into(),
+		);
+	}
+
+	/// Adds a step to set the balance between dry (unaffected)
+	/// signal and wet (affected) signal of an effect.
+	pub fn set_effect_mix(&mut self, effect: &EffectHandle, mix: impl Into<Value<f64>>) {
+		self.steps.push(
+			SequenceOutputCommand::SetEffectMix(effect.track_index(), effect.id(), mix.into())
+				.into(),
+		);
+	}
+
+	/// Adds a step to set one of an effect's
+	/// [`EffectValues`](crate::mixer::effect::values::EffectValues).
+	pub fn set_effect_value(
+		&mut self,
+		effect: &EffectHandle,
+		index: usize,
+		value: impl Into<Value<f64>>,
+	) {
+		self.steps.push(
+			SequenceOutputCommand::SetEffectValue(
+				effect.track_index(),
+				effect.id(),
+				index,
+				value.into(),
+			)
+			.into(),
+		);
+	}
+
 	/// Adds a step to emit a custom event.
 	pub fn emit(&mut self, event: CustomEvent) {
 		self.steps.push(SequenceStep::EmitCustomEvent(event));
 	}
 
+	/// Adds a step to wait until the main thread signals
+	/// the given event using
+	/// [`SequenceInstanceHandle::signal`](handle::SequenceInstanceHandle::signal)
+	/// before moving to the next step.
+	///
+	/// Signals are only received while the sequence is waiting
+	/// for them, so signalling an event before the sequence
+	/// reaches this step has no effect.
+	pub fn wait_for_signal(&mut self, event: CustomEvent) {
+		self.steps.push(SequenceStep::WaitForSignal(event));
+	}
+
+	/// Adds the steps of another sequence to this sequence.
+	///
+	/// The sub-sequence runs as part of this sequence, and the
+	/// steps after it will run once the sub-sequence finishes.
+	/// If the sub-sequence has a loop point, it will loop forever,
+	/// so any steps added after it will never run.
+	///
+	/// The steps are copied when this is called, so changing
+	/// `sequence` afterwards doesn't affect this sequence.
+	/// Instances played by the sub-sequence are given new IDs, so
+	/// the same sub-sequence can be added multiple times. The
+	/// groups of the sub-sequence are ignored.
+	pub fn run_sequence(&mut self, sequence: &Sequence<CustomEvent>) {
+		let offset = self.steps.len();
+		let mut sub_sequence = sequence.clone();
+		sub_sequence.update_instance_ids(0..sub_sequence.steps.len());
+		self.steps
+			.extend(sub_sequence.steps.into_iter().map(|step| match step {
+				SequenceStep::JumpTo(index) => SequenceStep::JumpTo(index + offset),
+				step => step,
+			}));
+		match sub_sequence.loop_point {
+			Some(loop_point) => self.steps.push(SequenceStep::JumpTo(loop_point + offset)),
+			// a chance step at the end of a sequence has no step to
+			// skip, so it shouldn't skip the step after the sub-sequence
+			None => {
+				if let Some(step @ SequenceStep::Chance(_)) = self.steps[offset..].last_mut() {
+					*step = SequenceStep::Chance(Value::Fixed(1.0));
+				}
+			}
+		}
+	}
+
+	/// Returns `true` if there is at least one step in the given
+	/// range of steps that is guaranteed to wait.
+	fn has_wait_step(&self, start: usize, end: usize) -> bool {
+		(start..end).any(|i| {
+			let is_wait_step = matches!(
+				self.steps[i],
+				SequenceStep::Wait(_)
+					| SequenceStep::WaitForInterval(_)
+					| SequenceStep::WaitForSignal(_)
+			);
+			// steps that come right after a chance step might be skipped
+			let might_be_skipped = i > 0
+				&& matches!(
+					self.steps[i - 1],
+					SequenceStep::Chance(probability)
+						if !matches!(probability, Value::Fixed(probability) if probability >= 1.0)
+				);
+			is_wait_step && !might_be_skipped
+		})
+	}
+
 	/// Makes sure nothing's wrong with the sequence that would make
 	/// it unplayable.
 	///
 	/// Currently, this only checks that the looping portion of a
-	/// sequence (if there is one) contains at least one wait command
+	/// sequence (if there is one), as well as the looping portions
+	/// of any sub-sequences, contain at least one wait command
 	/// (to prevent infinite loops).
 	pub(crate) fn validate(&self) -> Result<(), SequenceError> {
-		if let Some(loop_point) = self.loop_point {
-			for step in self.steps.iter().skip(loop_point) {
-				match step {
-					SequenceStep::Wait(_) | SequenceStep::WaitForInterval(_) => {
-						return Ok(());
-					}
-					_ => {}
+		for (i, step) in self.steps.iter().enumerate() {
+			if let SequenceStep::JumpTo(index) = step {
+				if !self.has_wait_step(*index, i) {
+					return Err(SequenceError::InfiniteLoop);
 				}
 			}
-			Err(SequenceError::InfiniteLoop)
-		} else {
-			Ok(())
 		}
+		if let Some(loop_point) = self.loop_point {
+			if !self.has_wait_step(loop_point, self.steps.len()) {
+				return Err(SequenceError::InfiniteLoop);
+			}
+		}
+		Ok(())
 	}
 
-	/// Gets a set of all of the events this sequence can emit.
+	/// Gets a set of all of the events this sequence can emit
+	/// or wait for.
 	fn all_events(&self) -> IndexSet<CustomEvent> {
 		let mut events = IndexSet::new();
 		for step in &self.steps {
-			if let SequenceStep::EmitCustomEvent(event) = step {
-				events.insert(event.clone());
+			match step {
+				SequenceStep::EmitCustomEvent(event) | SequenceStep::WaitForSignal(event) => {
+					events.insert(event.clone());
+				}
+				_ => {}
 			}
 		}
 		events
@@ -635,6 +961,11 @@ impl<CustomEvent: Clone + Eq + Hash> Sequence<CustomEvent> {
 				SequenceStep::EmitCustomEvent(event) => {
 					SequenceStep::EmitCustomEvent(events.get_index_of(event).unwrap())
 				}
+				SequenceStep::WaitForSignal(event) => {
+					SequenceStep::WaitForSignal(events.get_index_of(event).unwrap())
+				}
+				SequenceStep::Chance(probability) => SequenceStep::Chance(*probability),
+				SequenceStep::JumpTo(index) => SequenceStep::JumpTo(*index),
 			})
 			.collect();
 		(
@@ -652,10 +983,20 @@ impl<CustomEvent: Clone + Eq + Hash> Sequence<CustomEvent> {
 		let (raw_sequence, events) = self.into_raw_sequence();
 		let (event_producer, event_consumer) =
 			RingBuffer::new(settings.event_queue_capacity).split();
-		let instance = SequenceInstance::new(raw_sequence, event_producer, settings.metronome);
+		let instance = SequenceInstance::new(
+			id,
+			raw_sequence,
+			event_producer,
+			settings.metronome,
+			settings.speed,
+			settings.random_seed,
+		);
 		let handle = SequenceInstanceHandle::new(
 			id,
 			instance.public_state(),
+			instance.overflow_count(),
+			instance.loop_count(),
+			instance.public_position(),
 			command_producer,
 			event_consumer,
 			events,
@@ -681,8 +1022,12 @@ impl<CustomEvent: Clone + Eq + Hash> Default for Sequence<CustomEvent> {
 
 pub(crate) type RawSequence = Sequence<usize>;
 
-impl RawSequence {
-	fn convert_ids(steps: &mut Vec<SequenceStep<usize>>, old_id: InstanceId, new_id: InstanceId) {
+impl<CustomEvent: Clone + Eq + Hash> Sequence<CustomEvent> {
+	fn convert_ids(
+		steps: &mut Vec<SequenceStep<CustomEvent>>,
+		old_id: InstanceId,
+		new_id: InstanceId,
+	) {
 		for step in steps {
 			match step {
 				SequenceStep::RunCommand(command) => match command {
@@ -733,13 +1078,13 @@ impl RawSequence {
 		}
 	}
 
-	/// Assigns new instance IDs to each PlaySound command and updates
-	/// other sequence commands to use the new instance ID. This allows
-	/// the sequence to play sounds with fresh instance IDs on each loop
-	/// while still correctly pausing instances, setting their parameters,
-	/// etc.
-	fn update_instance_ids(&mut self) {
-		for i in 0..self.steps.len() {
+	/// Assigns new instance IDs to each PlaySound command in the given
+	/// range of steps and updates other sequence commands to use the new
+	/// instance ID. This allows the sequence to play sounds with fresh
+	/// instance IDs on each loop while still correctly pausing instances,
+	/// setting their parameters, etc.
+	fn update_instance_ids(&mut self, steps: Range<usize>) {
+		for i in steps {
 			match &self.steps[i] {
 				SequenceStep::RunCommand(command) => match command {
 					SequenceOutputCommand::PlaySound(_, id, _) => {
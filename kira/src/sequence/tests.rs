@@ -1,4 +1,20 @@
-use super::{super::Duration, Sequence, SequenceError};
+use crate::{
+	instance::{InstanceId, InstanceSettings, StopInstanceSettings},
+	manager::backend::Backend,
+	mixer::{
+		effect::{values::EffectValues, EffectSettings},
+		SubTrackSettings, TrackIndex,
+	},
+	parameter::ParameterSettings,
+	sound::SoundSettings,
+	testing::{constant_sound, create_manager, ConstantStream, CustomEffect, OffsetEffect},
+	CachedValue, Duration, Frame,
+};
+
+use super::{
+	handle::SequenceInstanceHandle, Sequence, SequenceError, SequenceInstanceSettings,
+	SequenceOutputCommand, SequenceStep,
+};
 
 #[test]
 fn fails_validation_on_infinite_loop() {
@@ -30,3 +46,420 @@ fn fails_validation_on_infinite_loop() {
 		panic!("Sequences with infinite loops should fail validation");
 	}
 }
+
+#[test]
+fn passes_validation_on_loop_waiting_for_signal() {
+	let sequence = {
+		let mut sequence = Sequence::<()>::new(Default::default());
+		sequence.start_loop();
+		sequence.emit(());
+		sequence.wait_for_signal(());
+		sequence
+	};
+	assert!(
+		sequence.validate().is_ok(),
+		"Looping sequences that wait for a signal in the loop section should pass validation"
+	);
+}
+
+#[test]
+fn fails_validation_on_infinitely_looping_sub_sequence() {
+	let valid_sub_sequence = {
+		let mut sequence = Sequence::<()>::new(Default::default());
+		sequence.start_loop();
+		sequence.wait(Duration::Seconds(0.1));
+		sequence
+	};
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.emit(());
+	sequence.run_sequence(&valid_sub_sequence);
+	assert!(
+		sequence.validate().is_ok(),
+		"Sequences with looping sub-sequences that wait should pass validation"
+	);
+
+	let infinitely_looping_sub_sequence = {
+		let mut sequence = Sequence::<()>::new(Default::default());
+		sequence.wait(Duration::Seconds(0.1));
+		sequence.start_loop();
+		sequence.emit(());
+		sequence
+	};
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.run_sequence(&infinitely_looping_sub_sequence);
+	if let Err(SequenceError::InfiniteLoop) = sequence.validate() {
+	} else {
+		panic!("Sequences with infinitely looping sub-sequences should fail validation");
+	}
+}
+
+#[test]
+fn fails_validation_on_loop_with_skippable_wait() {
+	let sequence = {
+		let mut sequence = Sequence::<()>::new(Default::default());
+		sequence.start_loop();
+		sequence.emit(());
+		sequence.chance(0.5);
+		sequence.wait(Duration::Seconds(0.1));
+		sequence
+	};
+	if let Err(SequenceError::InfiniteLoop) = sequence.validate() {
+	} else {
+		panic!("Sequences whose only looping waits might be skipped should fail validation");
+	}
+}
+
+#[test]
+fn controls_tracks_and_effects_with_sequences() {
+	let (mut manager, mut backend) = create_manager();
+	let mut track = manager.add_sub_track(SubTrackSettings::new()).unwrap();
+	manager
+		.add_stream(ConstantStream, TrackIndex::Sub(track.id()))
+		.unwrap();
+	let offset = track
+		.add_effect(OffsetEffect(1.0), EffectSettings::new().enabled(false))
+		.unwrap();
+	let mut values = EffectValues::new();
+	values.add(CachedValue::new(1.0.into(), 1.0));
+	let custom = track
+		.add_effect(
+			CustomEffect {
+				values,
+				offset: 0.0,
+			},
+			EffectSettings::default(),
+		)
+		.unwrap();
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.set_effect_enabled(&offset, true);
+	sequence.set_effect_value(&custom, 0, 3.0);
+	sequence.set_track_volume(&track, 0.5);
+	sequence.wait(Duration::Seconds(1.0));
+	sequence.set_effect_mix(&offset, 0.0);
+	manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	// commands from sequences are run on the next frame
+	assert_eq!(backend.process(), Frame::from_mono(1.0));
+	assert_eq!(backend.process(), Frame::from_mono(3.0));
+	for _ in 0..48000 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::from_mono(1.5));
+}
+
+#[test]
+fn rerolls_chances_on_each_loop_of_a_sequence() {
+	let (mut manager, mut backend) = create_manager();
+	let mut probability = manager
+		.add_parameter(ParameterSettings::new().value(0.0))
+		.unwrap();
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.start_loop();
+	sequence.chance(&probability);
+	sequence.emit(());
+	sequence.wait(Duration::Seconds(0.001));
+	let mut handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	let mut count_events = |handle: &mut SequenceInstanceHandle<()>| {
+		for _ in 0..480 {
+			backend.process();
+		}
+		let mut num_events = 0;
+		while handle.pop_event().unwrap().is_some() {
+			num_events += 1;
+		}
+		num_events
+	};
+	assert_eq!(count_events(&mut handle), 0);
+	let loop_count = handle.loop_count();
+	assert!(loop_count >= 9);
+	// changing the parameter changes the odds for the next loops
+	probability.set(1.0, None).unwrap();
+	assert!(count_events(&mut handle) >= 9);
+	assert!(handle.loop_count() >= loop_count + 9);
+}
+
+#[test]
+fn reports_the_position_of_sequences() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.emit(());
+	sequence.wait(Duration::Seconds(0.01));
+	sequence.emit(());
+	sequence.wait(Duration::Seconds(0.01));
+	let mut handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	backend.process();
+	assert_eq!(handle.position().step, 1);
+	for _ in 0..240 {
+		backend.process();
+	}
+	let position = handle.position();
+	assert_eq!(position.step, 1);
+	assert!((position.wait_progress - 0.5).abs() < 0.01);
+	// pausing the sequence holds its position
+	handle.pause().unwrap();
+	backend.process();
+	let position = handle.position();
+	for _ in 0..480 {
+		backend.process();
+	}
+	assert_eq!(handle.position(), position);
+	handle.resume().unwrap();
+	for _ in 0..480 {
+		backend.process();
+	}
+	let position = handle.position();
+	assert_eq!(position.step, 3);
+	assert!((position.wait_progress - 0.5).abs() < 0.01);
+}
+
+/// Runs the sequence for the given number of frames and
+/// returns the events it emitted.
+fn collect_events(
+	backend: &mut Backend,
+	handle: &mut SequenceInstanceHandle<usize>,
+	num_frames: usize,
+) -> Vec<usize> {
+	let mut events = vec![];
+	for _ in 0..num_frames {
+		backend.process();
+		while let Some(event) = handle.pop_event().unwrap() {
+			events.push(*event);
+		}
+	}
+	events
+}
+
+#[test]
+fn trailing_chances_in_looping_sub_sequences_do_not_skip_the_loop() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sub_sequence = Sequence::new(Default::default());
+	sub_sequence.start_loop();
+	sub_sequence.emit(1);
+	sub_sequence.wait(Duration::Seconds(0.001));
+	sub_sequence.chance(0.0);
+	let mut sequence = Sequence::new(Default::default());
+	sequence.run_sequence(&sub_sequence);
+	sequence.emit(2);
+	let mut handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	let events = collect_events(&mut backend, &mut handle, 480);
+	assert!(events.len() >= 9);
+	assert!(events.iter().all(|event| *event == 1));
+}
+
+#[test]
+fn trailing_chances_in_sub_sequences_do_not_skip_the_next_step() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sub_sequence = Sequence::new(Default::default());
+	sub_sequence.emit(1);
+	sub_sequence.chance(0.0);
+	let mut sequence = Sequence::new(Default::default());
+	sequence.run_sequence(&sub_sequence);
+	sequence.emit(2);
+	sequence.chance(0.0);
+	sequence.emit(3);
+	let mut handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	// chances that aren't at the end of a sub-sequence
+	// still skip the next step
+	assert_eq!(collect_events(&mut backend, &mut handle, 1), [1, 2]);
+}
+
+#[test]
+fn loops_nested_sub_sequences_from_their_own_loop_points() {
+	let (mut manager, mut backend) = create_manager();
+	let mut inner = Sequence::new(Default::default());
+	inner.emit(2);
+	inner.start_loop();
+	inner.emit(3);
+	inner.wait(Duration::Seconds(0.001));
+	let mut middle = Sequence::new(Default::default());
+	middle.emit(1);
+	middle.run_sequence(&inner);
+	let mut sequence = Sequence::new(Default::default());
+	sequence.emit(0);
+	sequence.run_sequence(&middle);
+	sequence.emit(4);
+	let mut handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	let events = collect_events(&mut backend, &mut handle, 480);
+	assert_eq!(events[..3], [0, 1, 2]);
+	assert!(events.len() >= 12);
+	assert!(events[3..].iter().all(|event| *event == 3));
+	// jumping back doesn't count as a loop of the whole sequence
+	assert_eq!(handle.loop_count(), 0);
+}
+
+/// Returns the instance ID of each step that plays
+/// or stops an instance.
+fn instance_ids(sequence: &Sequence<()>) -> Vec<InstanceId> {
+	sequence
+		.steps
+		.iter()
+		.filter_map(|step| match step {
+			SequenceStep::RunCommand(SequenceOutputCommand::PlaySound(_, id, _))
+			| SequenceStep::RunCommand(SequenceOutputCommand::StopInstance(id, _)) => Some(*id),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn only_renews_instance_ids_in_the_given_range_of_steps() {
+	let (mut manager, _) = create_manager();
+	let sound = manager
+		.add_sound(constant_sound(1.0, Default::default()))
+		.unwrap();
+	let mut sequence = Sequence::<()>::new(Default::default());
+	let first = sequence.play(&sound, InstanceSettings::new());
+	let second = sequence.play(&sound, InstanceSettings::new());
+	sequence.stop_instance(first, StopInstanceSettings::new());
+	sequence.stop_instance(second, StopInstanceSettings::new());
+	sequence.update_instance_ids(1..2);
+	let ids = instance_ids(&sequence);
+	assert_eq!(ids[0], first);
+	assert_ne!(ids[1], second);
+	// steps outside of the range that refer to a renewed
+	// instance use its new ID
+	assert_eq!(ids[2], first);
+	assert_eq!(ids[3], ids[1]);
+}
+
+#[test]
+fn looping_sub_sequences_play_new_instances_each_loop() {
+	let (mut manager, mut backend) = create_manager();
+	let sound = manager
+		.add_sound(constant_sound(
+			1.0,
+			SoundSettings {
+				cooldown: None,
+				..Default::default()
+			},
+		))
+		.unwrap();
+	let mut sub_sequence = Sequence::<()>::new(Default::default());
+	sub_sequence.start_loop();
+	sub_sequence.play(&sound, InstanceSettings::new());
+	sub_sequence.wait(Duration::Seconds(0.001));
+	let mut sequence = Sequence::new(Default::default());
+	let intro = sequence.play(&sound, InstanceSettings::new());
+	sequence.run_sequence(&sub_sequence);
+	manager
+		.start_sequence(sequence.clone(), Default::default())
+		.unwrap();
+	for _ in 0..100 {
+		backend.process();
+	}
+	// the intro and the first three loops are all playing
+	assert_eq!(backend.process(), Frame::from_mono(4.0).panned(0.5));
+	// the intro keeps its ID
+	assert_eq!(instance_ids(&sequence)[0], intro);
+}
+
+#[test]
+fn scales_waits_by_the_sequence_speed() {
+	let (mut manager, mut backend) = create_manager();
+	let mut speed = manager
+		.add_parameter(ParameterSettings::new().value(0.5))
+		.unwrap();
+	let mut sequence = Sequence::new(Default::default());
+	sequence.emit(1);
+	sequence.wait(Duration::Seconds(1.0));
+	sequence.emit(2);
+	sequence.wait(Duration::Seconds(1.0));
+	sequence.emit(3);
+	sequence.wait(Duration::Seconds(1.0));
+	sequence.emit(4);
+	let mut handle = manager
+		.start_sequence(sequence, SequenceInstanceSettings::new().speed(2.0))
+		.unwrap();
+	// at double speed, a one second wait takes half a second
+	assert_eq!(collect_events(&mut backend, &mut handle, 23900), [1]);
+	assert_eq!(collect_events(&mut backend, &mut handle, 200), [2]);
+	// changing the speed affects the wait in progress
+	handle.set_speed(speed.id()).unwrap();
+	assert!(collect_events(&mut backend, &mut handle, 95000).is_empty());
+	assert_eq!(collect_events(&mut backend, &mut handle, 1000), [3]);
+	// the speed follows the parameter it's linked to
+	speed.set(4.0, None).unwrap();
+	assert!(collect_events(&mut backend, &mut handle, 11500).is_empty());
+	assert_eq!(collect_events(&mut backend, &mut handle, 1000), [4]);
+}
+
+#[test]
+fn waits_for_signals_sent_after_the_step_is_reached() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sequence = Sequence::new(Default::default());
+	sequence.emit(1);
+	sequence.wait(Duration::Seconds(0.01));
+	sequence.wait_for_signal(0);
+	sequence.emit(2);
+	let mut handle = manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	// a signal sent before the sequence is waiting for it
+	// is ignored
+	handle.signal(&0).unwrap();
+	assert_eq!(collect_events(&mut backend, &mut handle, 1000), [1]);
+	assert!(collect_events(&mut backend, &mut handle, 48000).is_empty());
+	handle.signal(&0).unwrap();
+	assert_eq!(collect_events(&mut backend, &mut handle, 1), [2]);
+}
+
+#[test]
+fn skips_choices_with_invalid_weights() {
+	let (mut manager, mut backend) = create_manager();
+	let mut add_sound = |value| {
+		manager
+			.add_sound(constant_sound(value, SoundSettings::new().cooldown(0.0)))
+			.unwrap()
+			.id()
+	};
+	let valid_sound = add_sound(0.25);
+	let nan_sound = add_sound(0.5);
+	let infinite_sound = add_sound(1.0);
+	let negative_sound = add_sound(1.0);
+	let mut sequence = Sequence::<()>::new(Default::default());
+	sequence.play_random_weighted(
+		vec![
+			(nan_sound.into(), f64::NAN),
+			(infinite_sound.into(), f64::INFINITY),
+			(negative_sound.into(), -1.0),
+			(valid_sound.into(), 1.0),
+		],
+		InstanceSettings::new(),
+	);
+	// if every weight is invalid, nothing is played
+	sequence.play_random_weighted(
+		vec![(nan_sound.into(), f64::NAN), (negative_sound.into(), -1.0)],
+		InstanceSettings::new(),
+	);
+	sequence.wait(Duration::Seconds(0.01));
+	// finite weights whose sum overflows don't panic
+	sequence.play_random_weighted(
+		vec![
+			(valid_sound.into(), f64::MAX),
+			(valid_sound.into(), f64::MAX),
+		],
+		InstanceSettings::new(),
+	);
+	manager
+		.start_sequence(sequence, Default::default())
+		.unwrap();
+	for _ in 0..100 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::from_mono(0.25).panned(0.5));
+	for _ in 0..480 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), Frame::from_mono(0.5).panned(0.5));
+}
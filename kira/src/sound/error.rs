@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::CommandError;
+
 /// Something that can go wrong when loading a sound
 /// from a file.
 #[derive(Debug, Error)]
@@ -48,3 +50,51 @@ pub enum SoundFromFileError {
 	#[error("{0}")]
 	WavError(#[from] hound::Error),
 }
+
+/// Something that can go wrong when playing a named region
+/// of a sound.
+#[derive(Debug, Error)]
+pub enum PlayRegionError {
+	/// The sound's sprite has no region with the given name.
+	#[error("The sound has no region named \"{0}\"")]
+	NoRegionWithName(String),
+
+	/// A command could not be sent to the audio thread.
+	#[error("{0}")]
+	CommandError(#[from] CommandError),
+}
+
+/// Something that can go wrong when playing a slice of a sound.
+#[derive(Debug, Error)]
+pub enum PlaySliceError {
+	/// The sound isn't cut into enough slices to have
+	/// a slice with the given index.
+	#[error("The sound has no slice with index {0}")]
+	NoSliceWithIndex(usize),
+
+	/// A command could not be sent to the audio thread.
+	#[error("{0}")]
+	CommandError(#[from] CommandError),
+}
+
+/// Something that can go wrong when decoding a sound
+/// using the browser.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Error)]
+pub enum WebDecodeError {
+	/// There is no browser window to download the sound with.
+	#[error("No window is available")]
+	NoWindow,
+
+	/// The sound could not be downloaded.
+	#[error("Could not download the sound: {0}")]
+	DownloadFailed(String),
+
+	/// The browser could not decode the sound.
+	#[error("Could not decode the sound: {0}")]
+	DecodeFailed(String),
+
+	/// The sound has more than two channels.
+	#[error("Only mono and stereo audio is supported")]
+	UnsupportedChannelConfiguration,
+}
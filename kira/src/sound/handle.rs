@@ -1,18 +1,30 @@
 //! An interface for controlling sounds.
 
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc,
+};
+
 use crate::{
 	command::{
 		producer::{CommandError, CommandProducer},
 		InstanceCommand,
 	},
 	instance::{
-		handle::InstanceHandle, Instance, InstanceId, InstanceSettings, PauseInstanceSettings,
+		handle::{InstanceEffectContext, InstanceHandle},
+		Instance, InstanceId, InstanceSettings, PauseInstanceSettings, PlaybackRegion,
 		ResumeInstanceSettings, StopInstanceSettings,
 	},
 	mixer::TrackIndex,
+	parameter::tween::Tween,
+	playable::{PlaybackCounters, PlaybackStatistics},
+	util::SharedRng,
 };
 
-use super::{Sound, SoundId};
+use super::{
+	error::{PlayRegionError, PlaySliceError},
+	Sound, SoundId, SoundSprite,
+};
 
 /// Allows you to control a sound.
 #[derive(Debug, Clone)]
@@ -22,26 +34,88 @@ pub struct SoundHandle {
 	default_track: TrackIndex,
 	semantic_duration: Option<f64>,
 	default_loop_start: Option<f64>,
+	default_fade_in_tween: Option<Tween>,
+	default_fade_out_tween: Option<Tween>,
+	sprite: Arc<SoundSprite>,
+	slices: Arc<[PlaybackRegion]>,
+	statistics: Option<Arc<PlaybackCounters>>,
 	command_producer: CommandProducer,
+	rng: SharedRng,
+	effect_context: InstanceEffectContext,
+	loaded: Arc<AtomicBool>,
+	// keeps the sound loaded while the handle exists when
+	// the audio manager unloads unused resources automatically
+	_reference: Option<Arc<()>>,
 }
 
 impl SoundHandle {
-	pub(crate) fn new(sound: &Sound, command_producer: CommandProducer) -> Self {
+	pub(crate) fn new(
+		sound: &Sound,
+		command_producer: CommandProducer,
+		rng: SharedRng,
+		effect_context: InstanceEffectContext,
+	) -> Self {
 		Self {
 			id: sound.id(),
 			duration: sound.duration(),
 			default_track: sound.default_track(),
 			semantic_duration: sound.semantic_duration(),
 			default_loop_start: sound.default_loop_start(),
+			default_fade_in_tween: sound.default_fade_in_tween(),
+			default_fade_out_tween: sound.default_fade_out_tween(),
+			sprite: sound.sprite.clone(),
+			slices: sound.slices.clone(),
+			statistics: sound.statistics().cloned(),
 			command_producer,
+			rng,
+			effect_context,
+			loaded: Arc::new(AtomicBool::new(true)),
+			_reference: None,
+		}
+	}
+
+	pub(crate) fn with_reference(self, reference: Option<Arc<()>>) -> Self {
+		Self {
+			_reference: reference,
+			..self
 		}
 	}
 
+	/// Shares whether the sound is loaded with the handles
+	/// to the sound this one replaces.
+	pub(crate) fn replacing(self, previous: &Self) -> Self {
+		Self {
+			loaded: previous.loaded.clone(),
+			..self
+		}
+	}
+
+	/// Tells every handle to the sound that it has been
+	/// removed from the audio thread.
+	pub(crate) fn mark_unloaded(&self) {
+		self.loaded.store(false, Ordering::Relaxed);
+	}
+
+	/// Returns the audio manager's random number generator.
+	pub(crate) fn rng(&self) -> &SharedRng {
+		&self.rng
+	}
+
 	/// Returns the ID of the sound.
 	pub fn id(&self) -> SoundId {
 		self.id
 	}
 
+	/// Returns `true` if the sound hasn't been removed
+	/// from the audio manager.
+	///
+	/// Playing a sound that has been removed does nothing,
+	/// and the audio thread reports it as an
+	/// [error](crate::manager::AudioManager::pop_audio_thread_error).
+	pub fn is_loaded(&self) -> bool {
+		self.loaded.load(Ordering::Relaxed)
+	}
+
 	/// Returns the duration of the sound (in seconds).
 	pub fn duration(&self) -> f64 {
 		self.duration
@@ -66,19 +140,55 @@ impl SoundHandle {
 		self.default_loop_start
 	}
 
+	/// Returns the named regions of the sound.
+	pub fn sprite(&self) -> &SoundSprite {
+		&self.sprite
+	}
+
+	/// Returns the slices the sound is cut into.
+	pub fn slices(&self) -> &[PlaybackRegion] {
+		&self.slices
+	}
+
+	/// Returns the slice with the given index, if there is one.
+	pub fn slice(&self, index: usize) -> Option<PlaybackRegion> {
+		self.slices.get(index).copied()
+	}
+
+	/// Returns how often the sound has been played and
+	/// how long it was heard for, or `None` if the sound
+	/// doesn't [collect statistics](super::SoundSettings::collect_statistics).
+	pub fn statistics(&self) -> Option<PlaybackStatistics> {
+		self.statistics
+			.as_ref()
+			.map(|statistics| statistics.statistics())
+	}
+
 	/// Plays the sound.
 	pub fn play(&mut self, settings: InstanceSettings) -> Result<InstanceHandle, CommandError> {
 		let id = settings.id.unwrap_or(InstanceId::new());
+		let settings = InstanceSettings {
+			random_seed: Some(settings.random_seed.unwrap_or_else(|| self.rng.next_seed())),
+			..settings
+		};
 		let instance = Instance::new(
 			self.id.into(),
 			self.duration,
 			None,
-			settings.into_internal(self.duration, self.default_loop_start, self.default_track),
+			settings.into_internal(
+				self.duration,
+				self.default_loop_start,
+				self.default_track,
+				self.default_fade_in_tween,
+				self.default_fade_out_tween,
+			),
 		);
 		let handle = InstanceHandle::new(
 			id,
 			instance.public_state(),
 			instance.public_position(),
+			settings.num_effects,
+			self.effect_context.clone(),
 			self.command_producer.clone(),
 		);
 		self.command_producer
@@ -86,6 +196,37 @@ impl SoundHandle {
 		Ok(handle)
 	}
 
+	/// Plays a named region of the sound.
+	///
+	/// This overrides the [region](InstanceSettings::region)
+	/// of the instance settings.
+	pub fn play_region(
+		&mut self,
+		name: &str,
+		settings: InstanceSettings,
+	) -> Result<InstanceHandle, PlayRegionError> {
+		let region = self
+			.sprite
+			.get(name)
+			.ok_or_else(|| PlayRegionError::NoRegionWithName(name.into()))?;
+		Ok(self.play(settings.region(region))?)
+	}
+
+	/// Plays one of the slices the sound is cut into.
+	///
+	/// The slice is played as a [region](crate::instance::InstanceSettings::region)
+	/// of the sound.
+	pub fn play_slice(
+		&mut self,
+		index: usize,
+		settings: InstanceSettings,
+	) -> Result<InstanceHandle, PlaySliceError> {
+		let region = self
+			.slice(index)
+			.ok_or(PlaySliceError::NoSliceWithIndex(index))?;
+		Ok(self.play(settings.region(region))?)
+	}
+
 	/// Pauses all instances of this sound.
 	pub fn pause(&mut self, settings: PauseInstanceSettings) -> Result<(), CommandError> {
 		self.command_producer
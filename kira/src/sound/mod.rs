@@ -4,40 +4,100 @@ pub mod error;
 pub mod handle;
 mod id;
 mod settings;
+mod slices;
+mod sprite;
+#[cfg(all(feature = "ogg", any(target_arch = "wasm32", test)))]
+pub mod streaming;
+#[cfg(test)]
+mod tests;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
 
 pub use id::SoundId;
 pub use settings::SoundSettings;
+pub use slices::SoundSlices;
+pub use sprite::SoundSprite;
 
 use crate::{
-	frame::Frame,
+	dsp,
+	frame::{Frame, Sample},
 	group::{groups::Groups, GroupId, GroupSet},
+	instance::PlaybackRegion,
 	mixer::TrackIndex,
-	util,
+	parameter::tween::Tween,
+	playable::PlaybackCounters,
+	Tempo,
 };
 
-use std::fmt::{Debug, Formatter};
+use std::{
+	fmt::{Debug, Formatter},
+	sync::Arc,
+};
 
 #[cfg(any(feature = "mp3", feature = "ogg", feature = "flac", feature = "wav"))]
-use std::{fs::File, path::Path};
+use std::{
+	fs::File,
+	io::{BufReader, Read, Seek},
+	path::Path,
+};
+
+/// The file extensions of the audio formats sounds can be
+/// decoded from, depending on which features are enabled.
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+	#[cfg(feature = "mp3")]
+	"mp3",
+	#[cfg(feature = "ogg")]
+	"ogg",
+	#[cfg(feature = "flac")]
+	"flac",
+	#[cfg(feature = "wav")]
+	"wav",
+];
 
 /// A piece of audio that can be played by an [`AudioManager`](crate::manager::AudioManager).
+///
+/// The audio data is reference counted, so cloning a sound is
+/// cheap, and multiple sounds can share the same audio data:
+///
+/// ```no_run
+/// # use kira::sound::{Sound, SoundSettings};
+/// let footstep = Sound::from_file("footstep.ogg", SoundSettings::default())?;
+/// // a second sound with the same audio, but different settings
+/// let quiet_footstep = footstep.with_settings(SoundSettings::new().cooldown(0.1));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
 #[derive(Clone)]
 pub struct Sound {
 	id: SoundId,
 	sample_rate: u32,
-	frames: Vec<Frame>,
+	frames: Arc<[Frame]>,
 	duration: f64,
 	default_track: TrackIndex,
 	cooldown: Option<f64>,
 	semantic_duration: Option<f64>,
+	native_tempo: Option<Tempo>,
 	default_loop_start: Option<f64>,
+	default_fade_in_tween: Option<Tween>,
+	default_fade_out_tween: Option<Tween>,
+	sprite: Arc<SoundSprite>,
+	slices: Arc<[PlaybackRegion]>,
 	groups: GroupSet,
+	collect_statistics: bool,
+	statistics: Option<Arc<PlaybackCounters>>,
 	cooldown_timer: f64,
 }
 
 impl Sound {
 	/// Creates a new sound from raw sample data.
-	pub fn from_frames(sample_rate: u32, frames: Vec<Frame>, settings: SoundSettings) -> Self {
+	///
+	/// The frames can be a `Vec<Frame>` or an `Arc<[Frame]>`
+	/// shared with other sounds.
+	pub fn from_frames(
+		sample_rate: u32,
+		frames: impl Into<Arc<[Frame]>>,
+		settings: SoundSettings,
+	) -> Self {
+		let frames = frames.into();
 		let duration = frames.len() as f64 / sample_rate as f64;
 		Self {
 			id: settings.id.unwrap_or(SoundId::new()),
@@ -47,8 +107,15 @@ impl Sound {
 			default_track: settings.default_track,
 			cooldown: settings.cooldown,
 			semantic_duration: settings.semantic_duration,
+			native_tempo: settings.native_tempo,
 			default_loop_start: settings.default_loop_start,
+			default_fade_in_tween: settings.default_fade_in_tween,
+			default_fade_out_tween: settings.default_fade_out_tween,
+			sprite: Arc::new(settings.sprite),
+			slices: settings.slices.regions(duration).into(),
 			groups: settings.groups,
+			collect_statistics: settings.collect_statistics,
+			statistics: None,
 			cooldown_timer: 0.0,
 		}
 	}
@@ -62,7 +129,19 @@ impl Sound {
 	where
 		P: AsRef<Path>,
 	{
-		let mut decoder = minimp3::Decoder::new(File::open(path)?);
+		Self::from_mp3_reader(BufReader::new(File::open(path)?), settings)
+	}
+
+	/// Decodes a sound from mp3 data.
+	#[cfg(feature = "mp3")]
+	pub fn from_mp3_reader<R>(
+		reader: R,
+		settings: SoundSettings,
+	) -> Result<Self, error::SoundFromFileError>
+	where
+		R: Read,
+	{
+		let mut decoder = minimp3::Decoder::new(reader);
 		let mut sample_rate = None;
 		let mut stereo_samples = vec![];
 		loop {
@@ -125,9 +204,21 @@ impl Sound {
 	) -> Result<Self, error::SoundFromFileError>
 	where
 		P: AsRef<Path>,
+	{
+		Self::from_ogg_reader(BufReader::new(File::open(path)?), settings)
+	}
+
+	/// Decodes a sound from ogg data.
+	#[cfg(feature = "ogg")]
+	pub fn from_ogg_reader<R>(
+		reader: R,
+		settings: SoundSettings,
+	) -> Result<Self, error::SoundFromFileError>
+	where
+		R: Read + Seek,
 	{
 		use lewton::{inside_ogg::OggStreamReader, samples::Samples};
-		let mut reader = OggStreamReader::new(File::open(path)?)?;
+		let mut reader = OggStreamReader::new(reader)?;
 		let mut stereo_samples = vec![];
 		while let Some(packet) = reader.read_dec_packet_generic::<Vec<Vec<f32>>>()? {
 			let num_channels = packet.len();
@@ -135,12 +226,13 @@ impl Sound {
 			match num_channels {
 				1 => {
 					for i in 0..num_samples {
-						stereo_samples.push(Frame::from_mono(packet[0][i]));
+						stereo_samples.push(Frame::from_mono(packet[0][i] as Sample));
 					}
 				}
 				2 => {
 					for i in 0..num_samples {
-						stereo_samples.push(Frame::new(packet[0][i], packet[1][i]));
+						stereo_samples
+							.push(Frame::new(packet[0][i] as Sample, packet[1][i] as Sample));
 					}
 				}
 				_ => return Err(error::SoundFromFileError::UnsupportedChannelConfiguration),
@@ -162,7 +254,19 @@ impl Sound {
 	where
 		P: AsRef<Path>,
 	{
-		let mut reader = claxon::FlacReader::open(path)?;
+		Self::from_flac_reader(BufReader::new(File::open(path)?), settings)
+	}
+
+	/// Decodes a sound from flac data.
+	#[cfg(feature = "flac")]
+	pub fn from_flac_reader<R>(
+		reader: R,
+		settings: SoundSettings,
+	) -> Result<Self, error::SoundFromFileError>
+	where
+		R: Read,
+	{
+		let mut reader = claxon::FlacReader::new(reader)?;
 		let streaminfo = reader.streaminfo();
 		let mut stereo_samples = vec![];
 		match reader.streaminfo().channels {
@@ -200,14 +304,26 @@ impl Sound {
 	where
 		P: AsRef<Path>,
 	{
-		let mut reader = hound::WavReader::open(path)?;
+		Self::from_wav_reader(BufReader::new(File::open(path)?), settings)
+	}
+
+	/// Decodes a sound from wav data.
+	#[cfg(feature = "wav")]
+	pub fn from_wav_reader<R>(
+		reader: R,
+		settings: SoundSettings,
+	) -> Result<Self, error::SoundFromFileError>
+	where
+		R: Read,
+	{
+		let mut reader = hound::WavReader::new(reader)?;
 		let spec = reader.spec();
 		let mut stereo_samples = vec![];
 		match reader.spec().channels {
 			1 => match spec.sample_format {
 				hound::SampleFormat::Float => {
 					for sample in reader.samples::<f32>() {
-						stereo_samples.push(Frame::from_mono(sample?))
+						stereo_samples.push(Frame::from_mono(sample? as Sample))
 					}
 				}
 				hound::SampleFormat::Int => {
@@ -225,7 +341,7 @@ impl Sound {
 				hound::SampleFormat::Float => {
 					let mut iter = reader.samples::<f32>();
 					while let (Some(left), Some(right)) = (iter.next(), iter.next()) {
-						stereo_samples.push(Frame::new(left?, right?));
+						stereo_samples.push(Frame::new(left? as Sample, right? as Sample));
 					}
 				}
 				hound::SampleFormat::Int => {
@@ -256,22 +372,39 @@ impl Sound {
 	where
 		P: AsRef<Path>,
 	{
-		if let Some(extension) = path.as_ref().extension() {
-			if let Some(extension_str) = extension.to_str() {
-				match extension_str {
-					#[cfg(feature = "mp3")]
-					"mp3" => return Self::from_mp3_file(path, settings),
-					#[cfg(feature = "ogg")]
-					"ogg" => return Self::from_ogg_file(path, settings),
-					#[cfg(feature = "flac")]
-					"flac" => return Self::from_flac_file(path, settings),
-					#[cfg(feature = "wav")]
-					"wav" => return Self::from_wav_file(path, settings),
-					_ => {}
-				}
-			}
+		let extension = path
+			.as_ref()
+			.extension()
+			.and_then(|extension| extension.to_str())
+			.ok_or(error::SoundFromFileError::UnsupportedAudioFileFormat)?;
+		if !SUPPORTED_EXTENSIONS.contains(&extension) {
+			return Err(error::SoundFromFileError::UnsupportedAudioFileFormat);
+		}
+		Self::from_reader(BufReader::new(File::open(&path)?), extension, settings)
+	}
+
+	/// Decodes a sound from audio data in the format that
+	/// files with the given extension (like `"ogg"`) use.
+	#[cfg(any(feature = "mp3", feature = "ogg", feature = "flac", feature = "wav"))]
+	pub fn from_reader<R>(
+		reader: R,
+		extension: &str,
+		settings: SoundSettings,
+	) -> Result<Self, error::SoundFromFileError>
+	where
+		R: Read + Seek,
+	{
+		match extension {
+			#[cfg(feature = "mp3")]
+			"mp3" => Self::from_mp3_reader(reader, settings),
+			#[cfg(feature = "ogg")]
+			"ogg" => Self::from_ogg_reader(reader, settings),
+			#[cfg(feature = "flac")]
+			"flac" => Self::from_flac_reader(reader, settings),
+			#[cfg(feature = "wav")]
+			"wav" => Self::from_wav_reader(reader, settings),
+			_ => Err(error::SoundFromFileError::UnsupportedAudioFileFormat),
 		}
-		Err(error::SoundFromFileError::UnsupportedAudioFileFormat)
 	}
 
 	/// Gets the unique identifier for this sound.
@@ -299,6 +432,11 @@ impl Sound {
 		self.semantic_duration
 	}
 
+	/// Gets the tempo the sound was recorded at (if there is one).
+	pub fn native_tempo(&self) -> Option<Tempo> {
+		self.native_tempo
+	}
+
 	/// Returns the default time (in seconds) instances
 	/// of this sound will loop back to when they reach
 	/// the end.
@@ -306,11 +444,56 @@ impl Sound {
 		self.default_loop_start
 	}
 
+	/// Returns the tween instances of this sound
+	/// fade in with by default.
+	pub fn default_fade_in_tween(&self) -> Option<Tween> {
+		self.default_fade_in_tween
+	}
+
+	/// Returns the tween instances of this sound
+	/// fade out with by default when they're stopped.
+	pub fn default_fade_out_tween(&self) -> Option<Tween> {
+		self.default_fade_out_tween
+	}
+
+	/// Returns the named regions of the sound.
+	pub fn sprite(&self) -> &SoundSprite {
+		&self.sprite
+	}
+
+	/// Returns the slices the sound is cut into.
+	pub fn slices(&self) -> &[PlaybackRegion] {
+		&self.slices
+	}
+
+	/// Gives the sound a different ID.
+	pub(crate) fn with_id(self, id: SoundId) -> Self {
+		Self { id, ..self }
+	}
+
+	/// Returns the sample rate of the sound's audio data.
+	pub fn sample_rate(&self) -> u32 {
+		self.sample_rate
+	}
+
+	/// Returns the sound's audio data.
+	///
+	/// Cloning the returned `Arc` doesn't copy the audio data.
+	pub fn frames(&self) -> &Arc<[Frame]> {
+		&self.frames
+	}
+
+	/// Creates a new sound that shares this sound's audio
+	/// data, but has different settings.
+	pub fn with_settings(&self, settings: SoundSettings) -> Self {
+		Self::from_frames(self.sample_rate, self.frames.clone(), settings)
+	}
+
 	/// Gets the frame of this sound at an arbitrary time
 	/// in seconds, interpolating between samples if necessary.
 	pub fn get_frame_at_position(&self, position: f64) -> Frame {
 		let sample_position = self.sample_rate as f64 * position;
-		let fraction = (sample_position % 1.0) as f32;
+		let fraction = (sample_position % 1.0) as Sample;
 		let current_sample_index = sample_position as usize;
 		let previous = if current_sample_index == 0 {
 			Frame::from_mono(0.0)
@@ -332,7 +515,24 @@ impl Sound {
 			.frames
 			.get(current_sample_index + 2)
 			.unwrap_or(&Frame::from_mono(0.0));
-		util::interpolate_frame(previous, current, next_1, next_2, fraction)
+		dsp::interpolate_frame(previous, current, next_1, next_2, fraction)
+	}
+
+	/// Creates the playback counters for the sound if it
+	/// should collect statistics.
+	///
+	/// This is done when the sound is added to an audio manager,
+	/// so each added sound has its own statistics.
+	pub(crate) fn init_statistics(&mut self) {
+		if self.collect_statistics {
+			self.statistics = Some(Arc::new(PlaybackCounters::default()));
+		}
+	}
+
+	/// Returns the playback counters of the sound, if
+	/// it collects statistics.
+	pub(crate) fn statistics(&self) -> Option<&Arc<PlaybackCounters>> {
+		self.statistics.as_ref()
 	}
 
 	/// Starts the cooldown timer for the sound.
@@ -371,8 +571,13 @@ impl Debug for Sound {
 			.field("default_track", &self.default_track)
 			.field("cooldown", &self.cooldown)
 			.field("semantic_duration", &self.semantic_duration)
+			.field("native_tempo", &self.native_tempo)
 			.field("default_loop_start", &self.default_loop_start)
+			.field("default_fade_in_tween", &self.default_fade_in_tween)
+			.field("default_fade_out_tween", &self.default_fade_out_tween)
+			.field("slices", &self.slices)
 			.field("groups", &self.groups)
+			.field("collect_statistics", &self.collect_statistics)
 			.field("cooldown_timer", &self.cooldown_timer)
 			.finish()
 	}
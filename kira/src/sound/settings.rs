@@ -1,6 +1,6 @@
-use crate::{group::GroupSet, mixer::TrackIndex};
+use crate::{group::GroupSet, mixer::TrackIndex, parameter::tween::Tween, Tempo};
 
-use super::SoundId;
+use super::{SoundId, SoundSlices, SoundSprite};
 
 /// Settings for a [`Sound`](crate::sound::Sound).
 #[derive(Debug, Clone)]
@@ -33,12 +33,38 @@ pub struct SoundSettings {
 	/// If set, the semantic duration of the sound will be
 	/// used as the default end point when looping the sound.
 	pub semantic_duration: Option<f64>,
+	/// The tempo the sound was recorded at (if it's music).
+	///
+	/// Instances of the sound that are
+	/// [synced to a metronome's tempo](crate::instance::InstanceSettings::tempo_sync)
+	/// play faster or slower so they stay in time with the metronome.
+	pub native_tempo: Option<Tempo>,
 	/// Whether the sound should be looped by default, and if so,
 	/// the point an instance should jump back to when it reaches
 	/// the end.
 	pub default_loop_start: Option<f64>,
+	/// The tween instances of this sound will fade in with,
+	/// unless a different fade-in tween is set when playing it.
+	pub default_fade_in_tween: Option<Tween>,
+	/// The tween instances of this sound will fade out with when
+	/// they're stopped, unless a different fade-out tween is set
+	/// when stopping them.
+	pub default_fade_out_tween: Option<Tween>,
+	/// Named regions of the sound that can be played with
+	/// [`SoundHandle::play_region`](crate::sound::handle::SoundHandle::play_region).
+	pub sprite: SoundSprite,
+	/// Where to cut the sound into slices that can be played with
+	/// [`SoundHandle::play_slice`](crate::sound::handle::SoundHandle::play_slice)
+	/// or [`Sequence::play_slice`](crate::sequence::Sequence::play_slice).
+	pub slices: SoundSlices,
 	/// The groups this sound belongs to.
 	pub groups: GroupSet,
+	/// Whether to keep track of how often the sound is played
+	/// and how long it's heard for.
+	///
+	/// The statistics can be retrieved with
+	/// [`SoundHandle::statistics`](crate::sound::handle::SoundHandle::statistics).
+	pub collect_statistics: bool,
 }
 
 impl SoundSettings {
@@ -79,6 +105,14 @@ impl SoundSettings {
 		}
 	}
 
+	/// Sets the tempo the sound was recorded at.
+	pub fn native_tempo(self, native_tempo: impl Into<Tempo>) -> Self {
+		Self {
+			native_tempo: Some(native_tempo.into()),
+			..self
+		}
+	}
+
 	/// Sets the default loop start point of the sound.
 	pub fn default_loop_start(self, default_loop_start: f64) -> Self {
 		Self {
@@ -87,6 +121,33 @@ impl SoundSettings {
 		}
 	}
 
+	/// Sets the tween instances of this sound will fade in with.
+	pub fn default_fade_in_tween(self, tween: impl Into<Tween>) -> Self {
+		Self {
+			default_fade_in_tween: Some(tween.into()),
+			..self
+		}
+	}
+
+	/// Sets the tween instances of this sound will fade out with
+	/// when they're stopped.
+	pub fn default_fade_out_tween(self, tween: impl Into<Tween>) -> Self {
+		Self {
+			default_fade_out_tween: Some(tween.into()),
+			..self
+		}
+	}
+
+	/// Sets the named regions of the sound.
+	pub fn sprite(self, sprite: SoundSprite) -> Self {
+		Self { sprite, ..self }
+	}
+
+	/// Sets where to cut the sound into slices.
+	pub fn slices(self, slices: SoundSlices) -> Self {
+		Self { slices, ..self }
+	}
+
 	/// Sets the group this sound belongs to.
 	pub fn groups(self, groups: impl Into<GroupSet>) -> Self {
 		Self {
@@ -94,6 +155,15 @@ impl SoundSettings {
 			..self
 		}
 	}
+
+	/// Makes the sound keep track of how often it's played
+	/// and how long it's heard for.
+	pub fn collect_statistics(self) -> Self {
+		Self {
+			collect_statistics: true,
+			..self
+		}
+	}
 }
 
 impl Default for SoundSettings {
@@ -103,8 +173,14 @@ impl Default for SoundSettings {
 			default_track: TrackIndex::Main,
 			cooldown: Some(0.0001),
 			semantic_duration: None,
+			native_tempo: None,
 			default_loop_start: None,
+			default_fade_in_tween: None,
+			default_fade_out_tween: None,
+			sprite: SoundSprite::new(),
+			slices: SoundSlices::None,
 			groups: GroupSet::new(),
+			collect_statistics: false,
 		}
 	}
 }
@@ -0,0 +1,91 @@
+use crate::instance::PlaybackRegion;
+
+/// Where to cut a sound into slices that can be played
+/// separately.
+///
+/// Slicing a drum loop at every beat and playing the slices
+/// back in a different order from a [`Sequence`](crate::sequence::Sequence)
+/// re-sequences the loop without having to cut it into
+/// separate files:
+///
+/// ```no_run
+/// # use kira::{
+/// # 	instance::InstanceSettings,
+/// # 	manager::AudioManager,
+/// # 	metronome::MetronomeSettings,
+/// # 	sequence::{Sequence, SequenceInstanceSettings},
+/// # 	sound::{Sound, SoundSettings, SoundSlices},
+/// # 	Tempo,
+/// # };
+/// # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+/// let tempo = Tempo(170.0);
+/// let mut metronome = audio_manager.add_metronome(MetronomeSettings::new().tempo(tempo))?;
+/// let sound = audio_manager.add_sound(Sound::from_file(
+/// 	"amen.ogg",
+/// 	SoundSettings::new().slices(SoundSlices::Every(tempo.beats_to_seconds(1.0))),
+/// )?)?;
+/// let mut sequence = Sequence::<()>::new(Default::default());
+/// sequence.start_loop();
+/// // play the slices out of order, stuttering the third one
+/// for &slice in &[0, 1, 2, 2, 0, 3, 2, 1] {
+/// 	sequence.wait_for_interval(1.0);
+/// 	sequence.play_slice(&sound, slice, InstanceSettings::new());
+/// }
+/// audio_manager.start_sequence(sequence, SequenceInstanceSettings::new().metronome(&metronome))?;
+/// metronome.start()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum SoundSlices {
+	/// The sound isn't sliced.
+	#[default]
+	None,
+	/// The sound is cut at each of these positions (in seconds).
+	///
+	/// The first slice starts at the beginning of the sound,
+	/// and each position starts a new slice.
+	At(Vec<f64>),
+	/// The sound is cut at regular intervals (in seconds).
+	Every(f64),
+}
+
+impl SoundSlices {
+	/// Cuts a sound of the given duration (in seconds) into
+	/// playback regions.
+	///
+	/// Positions outside of the sound are ignored, and the
+	/// last slice ends at the end of the sound.
+	pub(crate) fn regions(&self, duration: f64) -> Vec<PlaybackRegion> {
+		let mut cuts = match self {
+			SoundSlices::None => return vec![],
+			SoundSlices::At(positions) => positions
+				.iter()
+				.copied()
+				.filter(|position| *position > 0.0 && *position < duration)
+				.collect::<Vec<_>>(),
+			SoundSlices::Every(interval) => {
+				if *interval <= 0.0 {
+					return vec![];
+				}
+				(1..)
+					.map(|i| i as f64 * interval)
+					.take_while(|position| *position < duration)
+					.collect()
+			}
+		};
+		cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+		cuts.dedup();
+		let mut regions = Vec::with_capacity(cuts.len() + 1);
+		let mut start = 0.0;
+		for cut in cuts {
+			regions.push(PlaybackRegion::new(start, cut));
+			start = cut;
+		}
+		regions.push(PlaybackRegion::new(start, duration));
+		regions
+	}
+}
@@ -0,0 +1,72 @@
+use indexmap::IndexMap;
+
+use crate::instance::PlaybackRegion;
+
+/// Named regions of a sound, also known as an audio sprite.
+///
+/// Web and mobile games often pack many short sounds into one
+/// file to cut down on the number of files they have to load.
+/// A sprite describes where each of the smaller sounds is, so
+/// they can be played by name with
+/// [`SoundHandle::play_region`](super::handle::SoundHandle::play_region):
+///
+/// ```no_run
+/// # use kira::{
+/// # 	instance::InstanceSettings,
+/// # 	manager::AudioManager,
+/// # 	sound::{Sound, SoundSettings, SoundSprite},
+/// # };
+/// # let (mut audio_manager, _backend) = AudioManager::new_headless(Default::default(), 48000);
+/// let sprite = SoundSprite::new()
+/// 	.region("jump", (0.0, 0.4))
+/// 	.region("coin", (0.5, 0.8));
+/// let mut sound = audio_manager.add_sound(Sound::from_file(
+/// 	"sfx.ogg",
+/// 	SoundSettings::new().sprite(sprite),
+/// )?)?;
+/// sound.play_region("coin", InstanceSettings::new())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// With the `serde_support` feature enabled, sprites can be
+/// deserialized from the JSON files made by tools like
+/// [audiosprite](https://github.com/tonistiigi/audiosprite),
+/// which list the start and end of each region (in seconds)
+/// under a `spritemap` key. The `json` feature adds
+/// [`SoundSprite::from_json`] to do this without pulling in
+/// another dependency.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct SoundSprite {
+	/// The regions of the sound, by name.
+	#[cfg_attr(feature = "serde_support", serde(alias = "spritemap"))]
+	pub regions: IndexMap<String, PlaybackRegion>,
+}
+
+impl SoundSprite {
+	/// Creates a new sprite with no regions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a named region to the sprite.
+	pub fn region(mut self, name: impl Into<String>, region: impl Into<PlaybackRegion>) -> Self {
+		self.regions.insert(name.into(), region.into());
+		self
+	}
+
+	/// Returns the region with the given name, if there is one.
+	pub fn get(&self, name: &str) -> Option<PlaybackRegion> {
+		self.regions.get(name).copied()
+	}
+
+	/// Reads a sprite from a JSON string.
+	#[cfg(feature = "json")]
+	pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+		serde_json::from_str(json)
+	}
+}
@@ -0,0 +1,551 @@
+//! Plays sounds from the web while they're still downloading.
+//!
+//! On the web, sounds can't be loaded from the filesystem, and
+//! large pieces of music can take a while to download. A streaming
+//! sound starts playing as soon as the first chunks of the file
+//! have arrived and been decoded.
+//!
+//! Streaming sounds are [audio streams](crate::audio_stream), so
+//! they count towards the
+//! [`num_streams`](crate::manager::AudioManagerSettings::num_streams)
+//! limit and can be removed with
+//! [`AudioManager::remove_stream`](crate::manager::AudioManager::remove_stream).
+//!
+//! Currently, only ogg files can be streamed.
+//!
+//! ```no_run
+//! # use kira::{manager::{AudioManager, AudioManagerSettings}, sound::streaming::StreamingSoundSettings};
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let music = audio_manager.add_streaming_sound("music/level-1.ogg", StreamingSoundSettings::new())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Preloading and priorities
+//!
+//! A streaming sound can wait until a number of seconds have
+//! been decoded before it starts playing, so it doesn't run out
+//! of audio if the download slows down. While sounds with a higher
+//! [priority](StreamingSoundPriority) are preloading, sounds with
+//! a lower priority stop decoding, so a stinger or a music
+//! transition can start on time even while lots of other audio
+//! is being downloaded:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sound::streaming::{StreamingSoundPriority, StreamingSoundSettings},
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! let ambience = audio_manager.add_streaming_sound(
+//! 	"ambience/forest.ogg",
+//! 	StreamingSoundSettings::new().priority(StreamingSoundPriority::Low),
+//! )?;
+//! let stinger = audio_manager.add_streaming_sound(
+//! 	"music/boss-stinger.ogg",
+//! 	StreamingSoundSettings::new()
+//! 		.preload(2.0)
+//! 		.priority(StreamingSoundPriority::High),
+//! )?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+// outside of the browser, this module is only built for
+// tests, which don't go through the download task
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+
+use std::{
+	cell::{Cell, RefCell},
+	io::{Read, Seek, SeekFrom},
+	rc::Rc,
+	sync::{Arc, Mutex},
+};
+
+use atomic::{Atomic, Ordering};
+#[cfg(target_arch = "wasm32")]
+use js_sys::{Promise, Reflect, Uint8Array};
+use lewton::{inside_ogg::OggStreamReader, samples::Samples};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{JsCast, JsValue};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(target_arch = "wasm32")]
+use web_sys::{ReadableStreamDefaultReader, Response};
+
+use crate::{
+	audio_stream::{handle::AudioStreamHandle, AudioStream, AudioStreamId},
+	mixer::TrackIndex,
+	Frame, Sample,
+};
+
+const OGG_PAGE_HEADER_SIZE: usize = 27;
+
+/// How long lower priority downloads wait (in milliseconds)
+/// before checking whether they can continue.
+#[cfg(target_arch = "wasm32")]
+const PRIORITY_WAIT_MILLIS: i32 = 10;
+
+/// How urgently a streaming sound should be downloaded
+/// compared to other streaming sounds.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Default)]
+pub enum StreamingSoundPriority {
+	/// The sound waits while any other sound is preloading.
+	Low,
+	/// The sound waits while high priority sounds are preloading.
+	#[default]
+	Normal,
+	/// The sound never waits for other sounds.
+	High,
+}
+
+/// Settings for a streaming sound.
+#[derive(Debug, Copy, Clone)]
+pub struct StreamingSoundSettings {
+	/// The track the sound plays on.
+	pub track: TrackIndex,
+	/// How much of the sound (in seconds) should be decoded
+	/// before it starts playing.
+	pub preload: f64,
+	/// How urgently the sound should be downloaded.
+	pub priority: StreamingSoundPriority,
+}
+
+impl StreamingSoundSettings {
+	/// Creates a new `StreamingSoundSettings` with the default settings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the track the sound plays on.
+	pub fn track(self, track: impl Into<TrackIndex>) -> Self {
+		Self {
+			track: track.into(),
+			..self
+		}
+	}
+
+	/// Sets how much of the sound (in seconds) should be
+	/// decoded before it starts playing.
+	pub fn preload(self, preload: f64) -> Self {
+		Self { preload, ..self }
+	}
+
+	/// Sets how urgently the sound should be downloaded.
+	pub fn priority(self, priority: StreamingSoundPriority) -> Self {
+		Self { priority, ..self }
+	}
+}
+
+impl Default for StreamingSoundSettings {
+	fn default() -> Self {
+		Self {
+			track: TrackIndex::Main,
+			preload: 0.0,
+			priority: StreamingSoundPriority::default(),
+		}
+	}
+}
+
+thread_local! {
+	/// The number of sounds of each priority that are
+	/// still preloading.
+	static NUM_PRELOADING: [Cell<usize>; 3] = Default::default();
+}
+
+/// Keeps a sound counted as preloading until it's
+/// preloaded or its download ends.
+struct PreloadGuard {
+	priority: StreamingSoundPriority,
+	finished: bool,
+}
+
+impl PreloadGuard {
+	fn new(priority: StreamingSoundPriority) -> Self {
+		NUM_PRELOADING.with(|counts| {
+			let count = &counts[priority as usize];
+			count.set(count.get() + 1);
+		});
+		Self {
+			priority,
+			finished: false,
+		}
+	}
+
+	fn finish(&mut self) {
+		if self.finished {
+			return;
+		}
+		self.finished = true;
+		NUM_PRELOADING.with(|counts| {
+			let count = &counts[self.priority as usize];
+			count.set(count.get() - 1);
+		});
+	}
+}
+
+impl Drop for PreloadGuard {
+	fn drop(&mut self) {
+		self.finish();
+	}
+}
+
+/// Returns whether any sound with a higher priority
+/// is still preloading.
+fn higher_priority_preloading(priority: StreamingSoundPriority) -> bool {
+	NUM_PRELOADING.with(|counts| {
+		counts[priority as usize + 1..]
+			.iter()
+			.any(|count| count.get() > 0)
+	})
+}
+
+/// Waits until no sounds with a higher priority are preloading.
+#[cfg(target_arch = "wasm32")]
+async fn wait_for_higher_priorities(priority: StreamingSoundPriority) -> Result<(), JsValue> {
+	while higher_priority_preloading(priority) {
+		let window = web_sys::window().ok_or("No window available")?;
+		JsFuture::from(Promise::new(&mut |resolve, _| {
+			window
+				.set_timeout_with_callback_and_timeout_and_arguments_0(
+					&resolve,
+					PRIORITY_WAIT_MILLIS,
+				)
+				.ok();
+		}))
+		.await?;
+	}
+	Ok(())
+}
+
+/// The loading state of a streaming sound.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StreamingSoundState {
+	/// The sound is still being downloaded, and not enough of it
+	/// has been decoded for it to start playing.
+	Loading,
+	/// Enough of the sound has been decoded for it to start
+	/// playing, but the rest is still being downloaded.
+	Preloaded,
+	/// The whole sound has been downloaded and decoded.
+	Loaded,
+	/// The sound could not be downloaded or decoded.
+	Failed,
+}
+
+/// Audio that has been decoded so far.
+#[derive(Debug)]
+struct DecodedAudio {
+	sample_rate: u32,
+	frames: Vec<Frame>,
+	// why the download failed, waiting to be
+	// reported by the audio stream
+	error: Option<String>,
+}
+
+impl DecodedAudio {
+	/// Returns how much audio (in seconds) has been decoded.
+	fn duration(&self) -> f64 {
+		if self.sample_rate == 0 {
+			return 0.0;
+		}
+		self.frames.len() as f64 / self.sample_rate as f64
+	}
+}
+
+/// An audio stream that plays a sound as it's being downloaded.
+///
+/// The stream stays silent until the sound is preloaded. If
+/// playback catches up to the end of the audio that has been
+/// decoded so far, the stream outputs silence until more audio
+/// is available.
+#[derive(Debug)]
+pub(crate) struct StreamingSound {
+	audio: Arc<Mutex<DecodedAudio>>,
+	state: Arc<Atomic<StreamingSoundState>>,
+	started: bool,
+	position: f64,
+}
+
+impl AudioStream for StreamingSound {
+	fn next(&mut self, dt: f64) -> Frame {
+		if !self.started {
+			match self.state.load(Ordering::Relaxed) {
+				StreamingSoundState::Preloaded | StreamingSoundState::Loaded => {
+					self.started = true;
+				}
+				_ => return Frame::from_mono(0.0),
+			}
+		}
+		// the lock is only ever held briefly on the main thread,
+		// and on the web, the audio callback runs on the main thread
+		// as well, so this should never fail
+		let audio = match self.audio.try_lock() {
+			Ok(audio) => audio,
+			Err(_) => return Frame::from_mono(0.0),
+		};
+		if audio.sample_rate == 0 {
+			return Frame::from_mono(0.0);
+		}
+		let sample_position = self.position * audio.sample_rate as f64;
+		let index = sample_position as usize;
+		if index + 1 >= audio.frames.len() {
+			return Frame::from_mono(0.0);
+		}
+		let fraction = (sample_position - index as f64) as Sample;
+		let current = audio.frames[index];
+		let next = audio.frames[index + 1];
+		self.position += dt;
+		current + (next - current) * fraction
+	}
+
+	fn pop_error(&mut self) -> Option<String> {
+		if self.state.load(Ordering::Relaxed) != StreamingSoundState::Failed {
+			return None;
+		}
+		self.audio
+			.try_lock()
+			.ok()
+			.and_then(|mut audio| audio.error.take())
+	}
+}
+
+/// Allows you to check on a sound that's being streamed from the web.
+#[derive(Debug, Clone)]
+pub struct StreamingSoundHandle {
+	stream: AudioStreamHandle,
+	state: Arc<Atomic<StreamingSoundState>>,
+}
+
+impl StreamingSoundHandle {
+	/// Returns the ID of the audio stream playing the sound.
+	pub fn id(&self) -> AudioStreamId {
+		self.stream.id()
+	}
+
+	/// Returns a handle for controlling the volume, panning,
+	/// and playback of the audio stream.
+	pub fn stream(&mut self) -> &mut AudioStreamHandle {
+		&mut self.stream
+	}
+
+	/// Returns the loading state of the sound.
+	pub fn state(&self) -> StreamingSoundState {
+		self.state.load(Ordering::Relaxed)
+	}
+}
+
+impl From<&StreamingSoundHandle> for AudioStreamId {
+	fn from(handle: &StreamingSoundHandle) -> Self {
+		handle.id()
+	}
+}
+
+/// Downloaded bytes that are shared between the download task
+/// and the ogg decoder.
+#[derive(Debug, Default)]
+struct DownloadBuffer {
+	bytes: Vec<u8>,
+	// the number of bytes that make up complete ogg pages.
+	// the decoder can only see this many bytes, so it never
+	// tries to read a page that hasn't fully arrived yet
+	complete_len: usize,
+}
+
+impl DownloadBuffer {
+	/// Finds the end of the last complete ogg page in the buffer.
+	fn update_complete_len(&mut self) {
+		loop {
+			let start = self.complete_len;
+			if self.bytes.len() < start + OGG_PAGE_HEADER_SIZE {
+				return;
+			}
+			let num_segments = self.bytes[start + OGG_PAGE_HEADER_SIZE - 1] as usize;
+			let segment_table_start = start + OGG_PAGE_HEADER_SIZE;
+			let segment_table_end = segment_table_start + num_segments;
+			if self.bytes.len() < segment_table_end {
+				return;
+			}
+			let body_len: usize = self.bytes[segment_table_start..segment_table_end]
+				.iter()
+				.map(|size| *size as usize)
+				.sum();
+			let page_end = segment_table_end + body_len;
+			if self.bytes.len() < page_end {
+				return;
+			}
+			self.complete_len = page_end;
+		}
+	}
+}
+
+/// Reads the complete ogg pages in a [`DownloadBuffer`].
+struct DownloadReader {
+	buffer: Rc<RefCell<DownloadBuffer>>,
+	position: usize,
+}
+
+impl Read for DownloadReader {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		let buffer = self.buffer.borrow();
+		let available = &buffer.bytes[self.position.min(buffer.complete_len)..buffer.complete_len];
+		let len = available.len().min(buf.len());
+		buf[..len].copy_from_slice(&available[..len]);
+		self.position += len;
+		Ok(len)
+	}
+}
+
+impl Seek for DownloadReader {
+	fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+		let complete_len = self.buffer.borrow().complete_len as i64;
+		let position = match pos {
+			SeekFrom::Start(offset) => offset as i64,
+			SeekFrom::End(offset) => complete_len + offset,
+			SeekFrom::Current(offset) => self.position as i64 + offset,
+		};
+		if position < 0 {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"Cannot seek before the start of the stream",
+			));
+		}
+		self.position = position as usize;
+		Ok(self.position as u64)
+	}
+}
+
+/// Decodes all of the packets that are currently available.
+fn decode_available_packets(
+	reader: &mut OggStreamReader<DownloadReader>,
+	audio: &Mutex<DecodedAudio>,
+) -> Result<(), lewton::VorbisError> {
+	while let Some(packet) = reader.read_dec_packet_generic::<Vec<Vec<f32>>>()? {
+		let num_samples = packet.num_samples();
+		let mut audio = audio.lock().unwrap();
+		audio.sample_rate = reader.ident_hdr.audio_sample_rate;
+		match packet.len() {
+			1 => {
+				audio.frames.extend(
+					packet[0][..num_samples]
+						.iter()
+						.map(|sample| Frame::from_mono(*sample as Sample)),
+				);
+			}
+			_ => {
+				audio.frames.extend(
+					packet[0][..num_samples]
+						.iter()
+						.zip(&packet[1][..num_samples])
+						.map(|(left, right)| Frame::new(*left as Sample, *right as Sample)),
+				);
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn download(
+	url: String,
+	settings: StreamingSoundSettings,
+	audio: Arc<Mutex<DecodedAudio>>,
+	state: Arc<Atomic<StreamingSoundState>>,
+) -> Result<(), JsValue> {
+	let mut preload_guard = PreloadGuard::new(settings.priority);
+	wait_for_higher_priorities(settings.priority).await?;
+	let window = web_sys::window().ok_or("No window available")?;
+	let response: Response = JsFuture::from(window.fetch_with_str(&url))
+		.await?
+		.dyn_into()?;
+	if !response.ok() {
+		return Err(format!("Request failed with status {}", response.status()).into());
+	}
+	let body_reader: ReadableStreamDefaultReader = response
+		.body()
+		.ok_or("Response has no body")?
+		.get_reader()
+		.unchecked_into();
+	let buffer = Rc::new(RefCell::new(DownloadBuffer::default()));
+	let mut decoder: Option<OggStreamReader<DownloadReader>> = None;
+	loop {
+		wait_for_higher_priorities(settings.priority).await?;
+		let chunk = JsFuture::from(body_reader.read()).await?;
+		let done = Reflect::get(&chunk, &"done".into())?
+			.as_bool()
+			.unwrap_or(true);
+		if done {
+			break;
+		}
+		let value: Uint8Array = Reflect::get(&chunk, &"value".into())?.dyn_into()?;
+		{
+			let mut buffer = buffer.borrow_mut();
+			buffer.bytes.extend(value.to_vec());
+			buffer.update_complete_len();
+		}
+		// the decoder can't be created until the header
+		// pages have arrived, so keep trying until it works
+		if decoder.is_none() {
+			decoder = OggStreamReader::new(DownloadReader {
+				buffer: buffer.clone(),
+				position: 0,
+			})
+			.ok();
+		}
+		if let Some(decoder) = &mut decoder {
+			decode_available_packets(decoder, &audio)
+				.map_err(|error| JsValue::from(error.to_string()))?;
+			if !preload_guard.finished && audio.lock().unwrap().duration() >= settings.preload {
+				preload_guard.finish();
+				state.store(StreamingSoundState::Preloaded, Ordering::Relaxed);
+			}
+		}
+	}
+	match &mut decoder {
+		Some(decoder) => decode_available_packets(decoder, &audio)
+			.map_err(|error| JsValue::from(error.to_string())),
+		None => Err("Could not read the ogg headers".into()),
+	}
+}
+
+/// Creates a streaming sound and starts downloading it.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn start_streaming_sound(
+	url: &str,
+	settings: StreamingSoundSettings,
+	stream_handle: AudioStreamHandle,
+) -> (StreamingSound, StreamingSoundHandle) {
+	let audio = Arc::new(Mutex::new(DecodedAudio {
+		sample_rate: 0,
+		frames: vec![],
+		error: None,
+	}));
+	let state = Arc::new(Atomic::new(StreamingSoundState::Loading));
+	let stream = StreamingSound {
+		audio: audio.clone(),
+		state: state.clone(),
+		started: false,
+		position: 0.0,
+	};
+	let handle = StreamingSoundHandle {
+		stream: stream_handle,
+		state: state.clone(),
+	};
+	let url = url.to_string();
+	wasm_bindgen_futures::spawn_local(async move {
+		let result = download(url, settings, audio.clone(), state.clone()).await;
+		state.store(
+			match result {
+				Ok(_) => StreamingSoundState::Loaded,
+				Err(error) => {
+					audio.lock().unwrap().error =
+						Some(error.as_string().unwrap_or_else(|| format!("{:?}", error)));
+					StreamingSoundState::Failed
+				}
+			},
+			Ordering::Relaxed,
+		);
+	});
+	(stream, handle)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,260 @@
+use std::{
+	cell::RefCell,
+	io::{Read, Seek, SeekFrom},
+	rc::Rc,
+	sync::{Arc, Mutex},
+};
+
+use atomic::{Atomic, Ordering};
+
+use super::{
+	higher_priority_preloading, DecodedAudio, DownloadBuffer, DownloadReader, PreloadGuard,
+	StreamingSound, StreamingSoundPriority, StreamingSoundState, NUM_PRELOADING,
+	OGG_PAGE_HEADER_SIZE,
+};
+use crate::{audio_stream::AudioStream, Frame};
+
+/// Creates an ogg page with the given segment sizes. Only the
+/// segment count and segment table are filled in, since those
+/// are all the download buffer looks at.
+fn ogg_page(segment_sizes: &[u8]) -> Vec<u8> {
+	let mut page = vec![0; OGG_PAGE_HEADER_SIZE];
+	page[OGG_PAGE_HEADER_SIZE - 1] = segment_sizes.len() as u8;
+	page.extend_from_slice(segment_sizes);
+	for size in segment_sizes {
+		page.extend(vec![1; *size as usize]);
+	}
+	page
+}
+
+fn num_preloading(priority: StreamingSoundPriority) -> usize {
+	NUM_PRELOADING.with(|counts| counts[priority as usize].get())
+}
+
+fn streaming_sound(
+	frames: Vec<Frame>,
+	state: StreamingSoundState,
+	error: Option<String>,
+) -> (StreamingSound, Arc<Atomic<StreamingSoundState>>) {
+	let state = Arc::new(Atomic::new(state));
+	let stream = StreamingSound {
+		audio: Arc::new(Mutex::new(DecodedAudio {
+			sample_rate: 4,
+			frames,
+			error,
+		})),
+		state: state.clone(),
+		started: false,
+		position: 0.0,
+	};
+	(stream, state)
+}
+
+#[test]
+fn download_buffer_only_counts_complete_pages() {
+	let first_page = ogg_page(&[10, 5]);
+	let second_page = ogg_page(&[255, 20]);
+	let mut buffer = DownloadBuffer::default();
+	// part of the first page's header
+	buffer.bytes.extend(&first_page[..10]);
+	buffer.update_complete_len();
+	assert_eq!(buffer.complete_len, 0);
+	// the first page's header and segment table, but not its body
+	buffer
+		.bytes
+		.extend(&first_page[10..OGG_PAGE_HEADER_SIZE + 2]);
+	buffer.update_complete_len();
+	assert_eq!(buffer.complete_len, 0);
+	// the rest of the first page and part of the second page
+	buffer.bytes.extend(&first_page[OGG_PAGE_HEADER_SIZE + 2..]);
+	buffer.bytes.extend(&second_page[..100]);
+	buffer.update_complete_len();
+	assert_eq!(buffer.complete_len, first_page.len());
+	// the rest of the second page
+	buffer.bytes.extend(&second_page[100..]);
+	buffer.update_complete_len();
+	assert_eq!(buffer.complete_len, first_page.len() + second_page.len());
+}
+
+#[test]
+fn download_reader_only_reads_complete_pages() {
+	let first_page = ogg_page(&[4]);
+	let second_page = ogg_page(&[8]);
+	let buffer = Rc::new(RefCell::new(DownloadBuffer::default()));
+	{
+		let mut buffer = buffer.borrow_mut();
+		buffer.bytes.extend(&first_page);
+		buffer.bytes.extend(&second_page[..OGG_PAGE_HEADER_SIZE]);
+		buffer.update_complete_len();
+	}
+	let mut reader = DownloadReader {
+		buffer: buffer.clone(),
+		position: 0,
+	};
+	let mut bytes = vec![];
+	reader.read_to_end(&mut bytes).unwrap();
+	assert_eq!(bytes, first_page);
+	// once more of the download arrives, the reader picks up
+	// where it left off
+	{
+		let mut buffer = buffer.borrow_mut();
+		buffer.bytes.extend(&second_page[OGG_PAGE_HEADER_SIZE..]);
+		buffer.update_complete_len();
+	}
+	let mut bytes = vec![];
+	reader.read_to_end(&mut bytes).unwrap();
+	assert_eq!(bytes, second_page);
+}
+
+#[test]
+fn download_reader_seeks_within_complete_pages() {
+	let page = ogg_page(&[4]);
+	let buffer = Rc::new(RefCell::new(DownloadBuffer::default()));
+	{
+		let mut buffer = buffer.borrow_mut();
+		buffer.bytes.extend(&page);
+		buffer.update_complete_len();
+	}
+	let mut reader = DownloadReader {
+		buffer,
+		position: 0,
+	};
+	assert_eq!(
+		reader.seek(SeekFrom::End(-4)).unwrap(),
+		page.len() as u64 - 4
+	);
+	assert_eq!(
+		reader.seek(SeekFrom::Current(2)).unwrap(),
+		page.len() as u64 - 2
+	);
+	assert_eq!(reader.seek(SeekFrom::Start(3)).unwrap(), 3);
+	assert!(reader.seek(SeekFrom::Current(-4)).is_err());
+}
+
+#[test]
+fn streaming_sounds_are_silent_until_preloaded() {
+	let (mut stream, state) = streaming_sound(
+		vec![Frame::from_mono(1.0); 8],
+		StreamingSoundState::Loading,
+		None,
+	);
+	assert_eq!(stream.next(0.25), Frame::from_mono(0.0));
+	assert_eq!(stream.next(0.25), Frame::from_mono(0.0));
+	state.store(StreamingSoundState::Preloaded, Ordering::Relaxed);
+	assert_eq!(stream.next(0.25), Frame::from_mono(1.0));
+	// once playback starts, it doesn't stop if the download fails
+	state.store(StreamingSoundState::Failed, Ordering::Relaxed);
+	assert_eq!(stream.next(0.25), Frame::from_mono(1.0));
+}
+
+#[test]
+fn streaming_sounds_interpolate_between_frames() {
+	let (mut stream, _) = streaming_sound(
+		vec![
+			Frame::from_mono(0.0),
+			Frame::from_mono(1.0),
+			Frame::from_mono(-1.0),
+		],
+		StreamingSoundState::Loaded,
+		None,
+	);
+	for expected in [0.0, 0.5, 1.0, 0.0] {
+		assert_eq!(stream.next(0.125), Frame::from_mono(expected));
+	}
+}
+
+#[test]
+fn streaming_sounds_wait_for_more_audio_when_they_catch_up() {
+	let (mut stream, _) = streaming_sound(
+		vec![Frame::from_mono(1.0); 2],
+		StreamingSoundState::Preloaded,
+		None,
+	);
+	assert_eq!(stream.next(0.25), Frame::from_mono(1.0));
+	// the second frame can't be interpolated towards the
+	// next one, so the stream outputs silence
+	assert_eq!(stream.next(0.25), Frame::from_mono(0.0));
+	assert_eq!(stream.next(0.25), Frame::from_mono(0.0));
+	stream
+		.audio
+		.lock()
+		.unwrap()
+		.frames
+		.extend([Frame::from_mono(0.5); 2]);
+	// playback picks up where it stopped
+	assert_eq!(stream.next(0.25), Frame::from_mono(1.0));
+	assert_eq!(stream.next(0.25), Frame::from_mono(0.5));
+}
+
+#[test]
+fn streaming_sounds_report_download_errors_once() {
+	let (mut stream, state) = streaming_sound(
+		vec![],
+		StreamingSoundState::Loading,
+		Some("Request failed with status 404".into()),
+	);
+	// errors aren't reported until the download task has
+	// marked the sound as failed
+	assert_eq!(stream.pop_error(), None);
+	state.store(StreamingSoundState::Failed, Ordering::Relaxed);
+	assert_eq!(
+		stream.pop_error(),
+		Some("Request failed with status 404".into())
+	);
+	assert_eq!(stream.pop_error(), None);
+}
+
+#[test]
+fn decoded_audio_reports_its_duration() {
+	let mut audio = DecodedAudio {
+		sample_rate: 0,
+		frames: vec![],
+		error: None,
+	};
+	// the headers haven't been decoded yet
+	assert_eq!(audio.duration(), 0.0);
+	audio.sample_rate = 4;
+	audio.frames = vec![Frame::from_mono(0.0); 6];
+	assert_eq!(audio.duration(), 1.5);
+}
+
+#[test]
+fn preload_guards_count_preloading_sounds() {
+	let mut first = PreloadGuard::new(StreamingSoundPriority::Normal);
+	let second = PreloadGuard::new(StreamingSoundPriority::Normal);
+	let third = PreloadGuard::new(StreamingSoundPriority::High);
+	assert_eq!(num_preloading(StreamingSoundPriority::Low), 0);
+	assert_eq!(num_preloading(StreamingSoundPriority::Normal), 2);
+	assert_eq!(num_preloading(StreamingSoundPriority::High), 1);
+	// finishing a guard more than once only counts once
+	first.finish();
+	first.finish();
+	assert_eq!(num_preloading(StreamingSoundPriority::Normal), 1);
+	// guards for downloads that end early stop counting when dropped
+	drop(second);
+	drop(third);
+	assert_eq!(num_preloading(StreamingSoundPriority::Normal), 0);
+	assert_eq!(num_preloading(StreamingSoundPriority::High), 0);
+	// dropping a finished guard doesn't count it again
+	drop(first);
+	assert_eq!(num_preloading(StreamingSoundPriority::Normal), 0);
+}
+
+#[test]
+fn sounds_wait_for_higher_priority_sounds_to_preload() {
+	assert!(!higher_priority_preloading(StreamingSoundPriority::Low));
+	let mut normal = PreloadGuard::new(StreamingSoundPriority::Normal);
+	assert!(higher_priority_preloading(StreamingSoundPriority::Low));
+	assert!(!higher_priority_preloading(StreamingSoundPriority::Normal));
+	let mut high = PreloadGuard::new(StreamingSoundPriority::High);
+	assert!(higher_priority_preloading(StreamingSoundPriority::Low));
+	assert!(higher_priority_preloading(StreamingSoundPriority::Normal));
+	assert!(!higher_priority_preloading(StreamingSoundPriority::High));
+	// sounds keep waiting until every higher priority
+	// sound has preloaded
+	high.finish();
+	assert!(higher_priority_preloading(StreamingSoundPriority::Low));
+	assert!(!higher_priority_preloading(StreamingSoundPriority::Normal));
+	normal.finish();
+	assert!(!higher_priority_preloading(StreamingSoundPriority::Low));
+}
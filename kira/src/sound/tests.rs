@@ -0,0 +1,181 @@
+use crate::{
+	instance::{InstanceSettings, PlaybackRegion},
+	manager::error::ReplaceSoundError,
+	sequence::Sequence,
+	testing::{constant_sound, create_manager, ramp_sound},
+	Frame,
+};
+
+use super::{
+	error::{PlayRegionError, PlaySliceError},
+	Sound, SoundId, SoundSettings, SoundSlices, SoundSprite,
+};
+
+#[test]
+fn plays_named_regions_of_sounds() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(ramp_sound(
+			SoundSettings::new().sprite(SoundSprite::new().region("second half", (0.5, 1.0))),
+		))
+		.unwrap();
+	assert!(matches!(
+		sound_handle.play_region("first half", InstanceSettings::new()),
+		Err(PlayRegionError::NoRegionWithName(name)) if name == "first half"
+	));
+	sound_handle
+		.play_region(
+			"second half",
+			InstanceSettings::new().fade_in_tween(None).panning(0.0),
+		)
+		.unwrap();
+	backend.process();
+	assert!((backend.process().left - 0.5).abs() < 0.001);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn reads_sound_sprites_from_json() {
+	let sprite = SoundSprite::from_json(
+		r#"{
+			"resources": ["sfx.ogg"],
+			"spritemap": {
+				"jump": { "start": 0.0, "end": 0.4, "loop": false },
+				"coin": { "start": 0.5, "end": 0.8, "loop": false }
+			}
+		}"#,
+	)
+	.unwrap();
+	assert_eq!(sprite.get("jump"), Some(PlaybackRegion::new(0.0, 0.4)));
+	assert_eq!(sprite.get("coin"), Some(PlaybackRegion::new(0.5, 0.8)));
+	assert_eq!(sprite.get("explosion"), None);
+}
+
+#[test]
+fn sounds_can_share_audio_data() {
+	let (mut manager, _) = create_manager();
+	let sound = constant_sound(0.5, Default::default());
+	let copy = sound.with_settings(Default::default());
+	assert!(std::sync::Arc::ptr_eq(sound.frames(), copy.frames()));
+	assert_ne!(sound.id(), copy.id());
+	assert_eq!(copy.duration(), 1.0);
+	manager.add_sound(sound).unwrap();
+	manager.add_sound(copy).unwrap();
+}
+
+#[test]
+fn replaces_sound_data_while_instances_keep_playing() {
+	let (mut manager, mut backend) = create_manager();
+	let mut sound_handle = manager
+		.add_sound(constant_sound(0.25, Default::default()))
+		.unwrap();
+	sound_handle.play(InstanceSettings::default()).unwrap();
+	for _ in 0..10 {
+		backend.process();
+	}
+	let old_output = backend.process();
+	let new_handle = manager
+		.replace_sound(
+			&sound_handle,
+			Sound::from_frames(
+				48000,
+				vec![Frame::from_mono(0.5); 96000],
+				Default::default(),
+			),
+		)
+		.unwrap();
+	assert_eq!(new_handle.id(), sound_handle.id());
+	assert_eq!(new_handle.duration(), 2.0);
+	for _ in 0..10 {
+		backend.process();
+	}
+	assert_eq!(backend.process(), old_output * 2.0);
+	assert_eq!(manager.free_unused_resources().sounds, 1);
+	assert!(matches!(
+		manager.replace_sound(
+			SoundId::new(),
+			Sound::from_frames(48000, vec![], Default::default())
+		),
+		Err(ReplaceSoundError::NoSoundWithId(_))
+	));
+}
+
+#[test]
+fn plays_slices_of_sounds() {
+	let (mut manager, mut backend) = create_manager();
+	// each frame of the sound is its position in seconds
+	let mut sound_handle = manager
+		.add_sound(ramp_sound(
+			SoundSettings::new()
+				.slices(SoundSlices::Every(0.25))
+				.cooldown(0.0),
+		))
+		.unwrap();
+	assert_eq!(sound_handle.slices().len(), 4);
+	assert_eq!(sound_handle.slice(3), Some(PlaybackRegion::new(0.75, 1.0)));
+	sound_handle
+		.play_slice(2, InstanceSettings::new().fade_in_tween(None).panning(0.0))
+		.unwrap();
+	backend.process();
+	let first_frame = backend.process().left;
+	assert!((first_frame - 0.5).abs() < 0.001);
+	assert!(matches!(
+		sound_handle.play_slice(4, InstanceSettings::new()),
+		Err(PlaySliceError::NoSliceWithIndex(4))
+	));
+	let mut sequence = Sequence::<()>::new(Default::default());
+	assert!(sequence
+		.play_slice(&sound_handle, 1, InstanceSettings::new())
+		.is_some());
+	assert!(sequence
+		.play_slice(&sound_handle, 4, InstanceSettings::new())
+		.is_none());
+
+	// slice points are sorted, and points outside of the sound are ignored
+	let sound = constant_sound(
+		0.0,
+		SoundSettings::new().slices(SoundSlices::At(vec![0.5, 2.0, 0.0, 0.25, 0.5])),
+	);
+	assert_eq!(
+		sound.slices(),
+		&[
+			PlaybackRegion::new(0.0, 0.25),
+			PlaybackRegion::new(0.25, 0.5),
+			PlaybackRegion::new(0.5, 1.0),
+		]
+	);
+}
+
+#[test]
+#[cfg(feature = "wav")]
+fn decodes_sounds_from_readers() {
+	use std::io::Cursor;
+
+	use super::error::SoundFromFileError;
+
+	let mut bytes = Cursor::new(vec![]);
+	let mut writer = hound::WavWriter::new(
+		&mut bytes,
+		hound::WavSpec {
+			channels: 2,
+			sample_rate: 4,
+			bits_per_sample: 16,
+			sample_format: hound::SampleFormat::Int,
+		},
+	)
+	.unwrap();
+	for _ in 0..2 {
+		writer.write_sample(i16::MAX).unwrap();
+		writer.write_sample(0i16).unwrap();
+	}
+	writer.finalize().unwrap();
+	bytes.set_position(0);
+	let sound = Sound::from_reader(bytes.clone(), "wav", SoundSettings::default()).unwrap();
+	assert_eq!(sound.duration(), 0.5);
+	assert!((sound.frames()[1].left - 1.0).abs() < 0.001);
+	assert_eq!(sound.frames()[1].right, 0.0);
+	assert!(matches!(
+		Sound::from_reader(bytes, "aiff", SoundSettings::default()),
+		Err(SoundFromFileError::UnsupportedAudioFileFormat)
+	));
+}
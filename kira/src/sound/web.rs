@@ -0,0 +1,115 @@
+//! Decodes sounds using the browser's built-in audio decoders.
+//!
+//! On the web, decoding audio with kira's own decoders is slow,
+//! and each decoder makes the wasm binary larger. Instead, sounds
+//! can be decoded by the browser in the background, which supports
+//! every format the browser can play. Combined with
+//! `default-features = false`, this keeps the decoders out of
+//! the binary entirely:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	manager::AudioManager,
+//! # 	sound::{web, SoundSettings},
+//! # };
+//! # async fn load(audio_manager: &mut AudioManager) -> Result<(), Box<dyn std::error::Error>> {
+//! let sound = web::load_sound("music/level-1.mp3", SoundSettings::default()).await?;
+//! let sound_handle = audio_manager.add_sound(sound)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The browser decodes audio at the sample rate of the audio
+//! context it uses, so decoded sounds have a sample rate of
+//! 48kHz regardless of the sample rate of the file.
+
+use js_sys::{ArrayBuffer, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AudioBuffer, OfflineAudioContext, Response};
+
+use crate::Frame;
+
+use super::{error::WebDecodeError, Sound, SoundSettings};
+
+/// The sample rate the browser decodes sounds at.
+const DECODE_SAMPLE_RATE: u32 = 48000;
+
+fn describe(value: JsValue) -> String {
+	value.as_string().unwrap_or_else(|| format!("{:?}", value))
+}
+
+/// Downloads a sound from a URL and decodes it.
+pub async fn load_sound(url: &str, settings: SoundSettings) -> Result<Sound, WebDecodeError> {
+	let window = web_sys::window().ok_or(WebDecodeError::NoWindow)?;
+	let response = JsFuture::from(window.fetch_with_str(url))
+		.await
+		.map_err(|error| WebDecodeError::DownloadFailed(describe(error)))?;
+	let response: Response = response
+		.dyn_into()
+		.map_err(|error| WebDecodeError::DownloadFailed(describe(error)))?;
+	if !response.ok() {
+		return Err(WebDecodeError::DownloadFailed(format!(
+			"The server responded with status {}",
+			response.status()
+		)));
+	}
+	let array_buffer = response
+		.array_buffer()
+		.map_err(|error| WebDecodeError::DownloadFailed(describe(error)))?;
+	let array_buffer = JsFuture::from(array_buffer)
+		.await
+		.map_err(|error| WebDecodeError::DownloadFailed(describe(error)))?;
+	decode(array_buffer.unchecked_into(), settings).await
+}
+
+/// Decodes a sound from the bytes of an audio file.
+pub async fn decode_sound(bytes: &[u8], settings: SoundSettings) -> Result<Sound, WebDecodeError> {
+	// the browser takes ownership of the buffer it decodes,
+	// so the bytes are copied into a new one
+	decode(Uint8Array::from(bytes).buffer(), settings).await
+}
+
+async fn decode(
+	array_buffer: ArrayBuffer,
+	settings: SoundSettings,
+) -> Result<Sound, WebDecodeError> {
+	// an offline context is used because it doesn't count towards
+	// the browser's limit on audio contexts and doesn't need the
+	// user to interact with the page first
+	let context = OfflineAudioContext::new_with_number_of_channels_and_length_and_sample_rate(
+		2,
+		1,
+		DECODE_SAMPLE_RATE as f32,
+	)
+	.map_err(|error| WebDecodeError::DecodeFailed(describe(error)))?;
+	let audio_buffer = context
+		.decode_audio_data(&array_buffer)
+		.map_err(|error| WebDecodeError::DecodeFailed(describe(error)))?;
+	let audio_buffer: AudioBuffer = JsFuture::from(audio_buffer)
+		.await
+		.map_err(|error| WebDecodeError::DecodeFailed(describe(error)))?
+		.unchecked_into();
+	let channel_data = |channel| {
+		audio_buffer
+			.get_channel_data(channel)
+			.map_err(|error| WebDecodeError::DecodeFailed(describe(error)))
+	};
+	let frames = match audio_buffer.number_of_channels() {
+		1 => channel_data(0)?
+			.into_iter()
+			.map(|sample| Frame::from_mono(sample as _))
+			.collect::<Vec<_>>(),
+		2 => channel_data(0)?
+			.into_iter()
+			.zip(channel_data(1)?)
+			.map(|(left, right)| Frame::new(left as _, right as _))
+			.collect(),
+		_ => return Err(WebDecodeError::UnsupportedChannelConfiguration),
+	};
+	Ok(Sound::from_frames(
+		audio_buffer.sample_rate() as u32,
+		frames,
+		settings,
+	))
+}
@@ -0,0 +1,327 @@
+//! Loads and unloads groups of sounds together.
+//!
+//! Games often load the audio for a level when the level
+//! starts and free it when the level ends. A [`SoundBank`]
+//! holds every sound (and arrangement) that belongs together,
+//! lets you look them up by name, and removes all of them from
+//! the audio thread in one call:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::InstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	sound::SoundSettings,
+//! # 	sound_bank::SoundBank,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! // loads "audio/level-2/explosion.ogg" as "explosion", etc.
+//! let mut level_2 = SoundBank::load_dir(
+//! 	&mut audio_manager,
+//! 	"audio/level-2",
+//! 	SoundSettings::default(),
+//! )?;
+//! if let Some(mut explosion) = level_2.sound("explosion") {
+//! 	explosion.play(InstanceSettings::default())?;
+//! }
+//! // when the level is over
+//! level_2.unload(&mut audio_manager)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Sounds can also be listed in a [`SoundBankManifest`], which
+//! lets each sound have its own settings. With the `json` feature
+//! enabled, manifests can be read from JSON files using
+//! [`SoundBankManifest::from_json_file`].
+
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+use thiserror::Error;
+
+use crate::{
+	arrangement::handle::ArrangementHandle,
+	manager::{
+		error::{LoadSoundError, RemoveArrangementError, RemoveSoundError},
+		AudioManager,
+	},
+	sound::{handle::SoundHandle, SoundSettings},
+};
+
+/// Something that can go wrong when loading a sound bank.
+#[derive(Debug, Error)]
+pub enum LoadSoundBankError {
+	/// An error occurred when interacting with the filesystem.
+	#[error("{0}")]
+	IoError(#[from] std::io::Error),
+
+	/// An error occurred when reading a JSON manifest.
+	#[cfg(feature = "json")]
+	#[error("{0}")]
+	JsonError(#[from] serde_json::Error),
+
+	/// A sound in the bank could not be loaded.
+	#[error("Could not load the sound \"{name}\": {error}")]
+	LoadSoundError {
+		/// The name of the sound.
+		name: String,
+		/// The error that occurred.
+		error: LoadSoundError,
+	},
+
+	/// The sounds that were loaded before the error occurred
+	/// could not be removed.
+	#[error("{0}")]
+	UnloadSoundBankError(#[from] UnloadSoundBankError),
+}
+
+/// Something that can go wrong when unloading a sound bank.
+#[derive(Debug, Error)]
+pub enum UnloadSoundBankError {
+	/// A sound could not be removed.
+	#[error("{0}")]
+	RemoveSoundError(#[from] RemoveSoundError),
+
+	/// An arrangement could not be removed.
+	#[error("{0}")]
+	RemoveArrangementError(#[from] RemoveArrangementError),
+}
+
+/// A sound listed in a [`SoundBankManifest`].
+#[derive(Debug, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct SoundBankEntry {
+	/// The path to the audio file.
+	pub path: PathBuf,
+	/// The settings to load the sound with.
+	#[cfg_attr(feature = "serde_support", serde(default))]
+	pub settings: SoundSettings,
+}
+
+impl SoundBankEntry {
+	/// Creates a new entry with the default sound settings.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self {
+			path: path.into(),
+			settings: SoundSettings::default(),
+		}
+	}
+
+	/// Sets the settings to load the sound with.
+	pub fn settings(self, settings: SoundSettings) -> Self {
+		Self { settings, ..self }
+	}
+}
+
+impl From<&str> for SoundBankEntry {
+	fn from(path: &str) -> Self {
+		Self::new(path)
+	}
+}
+
+impl From<PathBuf> for SoundBankEntry {
+	fn from(path: PathBuf) -> Self {
+		Self::new(path)
+	}
+}
+
+/// A list of sounds to load into a [`SoundBank`].
+///
+/// In a JSON manifest, each sound is listed by name:
+///
+/// ```json
+/// {
+/// 	"sounds": {
+/// 		"explosion": { "path": "explosion.ogg" },
+/// 		"music": { "path": "music.ogg", "settings": { "default_loop_start": 4.0 } }
+/// 	}
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(default)
+)]
+pub struct SoundBankManifest {
+	/// The sounds to load, by name.
+	pub sounds: IndexMap<String, SoundBankEntry>,
+}
+
+impl SoundBankManifest {
+	/// Creates a new manifest with no sounds.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a sound to the manifest.
+	pub fn sound(mut self, name: impl Into<String>, entry: impl Into<SoundBankEntry>) -> Self {
+		self.sounds.insert(name.into(), entry.into());
+		self
+	}
+
+	/// Reads a manifest from a JSON file.
+	///
+	/// Relative paths in the manifest are relative to the
+	/// folder the manifest is in.
+	#[cfg(feature = "json")]
+	pub fn from_json_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadSoundBankError> {
+		let path = path.as_ref();
+		let mut manifest: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+		if let Some(dir) = path.parent() {
+			for entry in manifest.sounds.values_mut() {
+				entry.path = dir.join(&entry.path);
+			}
+		}
+		Ok(manifest)
+	}
+}
+
+/// A set of sounds and arrangements that are loaded
+/// and unloaded together.
+#[derive(Debug, Clone, Default)]
+pub struct SoundBank {
+	sounds: IndexMap<String, SoundHandle>,
+	arrangements: IndexMap<String, ArrangementHandle>,
+}
+
+impl SoundBank {
+	/// Creates a new, empty sound bank.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Loads every audio file in a folder into a new sound bank.
+	///
+	/// Each sound is named after its file name without the
+	/// extension. Files that aren't in a supported audio format
+	/// are skipped.
+	#[cfg(any(feature = "mp3", feature = "ogg", feature = "flac", feature = "wav"))]
+	pub fn load_dir(
+		audio_manager: &mut AudioManager,
+		path: impl AsRef<std::path::Path>,
+		settings: SoundSettings,
+	) -> Result<Self, LoadSoundBankError> {
+		let mut paths = std::fs::read_dir(path)?
+			.map(|entry| entry.map(|entry| entry.path()))
+			.collect::<Result<Vec<_>, _>>()?;
+		paths.sort();
+		let mut bank = Self::new();
+		for path in paths {
+			let name = match path.file_stem().and_then(|name| name.to_str()) {
+				Some(name) if path.is_file() => name.to_string(),
+				_ => continue,
+			};
+			match audio_manager.load_sound(&path, settings.clone()) {
+				Ok(handle) => {
+					bank.add_sound(name, &handle);
+				}
+				Err(LoadSoundError::SoundFromFileError(
+					crate::sound::error::SoundFromFileError::UnsupportedAudioFileFormat,
+				)) => {}
+				Err(error) => {
+					bank.unload(audio_manager)?;
+					return Err(LoadSoundBankError::LoadSoundError { name, error });
+				}
+			}
+		}
+		Ok(bank)
+	}
+
+	/// Loads the sounds listed in a manifest into a new sound bank.
+	#[cfg(any(feature = "mp3", feature = "ogg", feature = "flac", feature = "wav"))]
+	pub fn load_manifest(
+		audio_manager: &mut AudioManager,
+		manifest: &SoundBankManifest,
+	) -> Result<Self, LoadSoundBankError> {
+		let mut bank = Self::new();
+		for (name, entry) in &manifest.sounds {
+			match audio_manager.load_sound(&entry.path, entry.settings.clone()) {
+				Ok(handle) => {
+					bank.add_sound(name.clone(), &handle);
+				}
+				Err(error) => {
+					bank.unload(audio_manager)?;
+					return Err(LoadSoundBankError::LoadSoundError {
+						name: name.clone(),
+						error,
+					});
+				}
+			}
+		}
+		Ok(bank)
+	}
+
+	/// Adds a sound that was already sent to the audio thread
+	/// to the bank.
+	///
+	/// If the bank already had a sound with this name, the old
+	/// sound is no longer part of the bank, but it isn't removed
+	/// from the audio thread.
+	pub fn add_sound(&mut self, name: impl Into<String>, handle: &SoundHandle) -> &mut Self {
+		self.sounds.insert(name.into(), handle.clone());
+		self
+	}
+
+	/// Adds an arrangement that was already sent to the audio
+	/// thread to the bank.
+	///
+	/// If the bank already had an arrangement with this name,
+	/// the old arrangement is no longer part of the bank, but it
+	/// isn't removed from the audio thread.
+	pub fn add_arrangement(
+		&mut self,
+		name: impl Into<String>,
+		handle: &ArrangementHandle,
+	) -> &mut Self {
+		self.arrangements.insert(name.into(), handle.clone());
+		self
+	}
+
+	/// Returns a handle to the sound with the given name.
+	pub fn sound(&self, name: &str) -> Option<SoundHandle> {
+		self.sounds.get(name).cloned()
+	}
+
+	/// Returns a handle to the arrangement with the given name.
+	pub fn arrangement(&self, name: &str) -> Option<ArrangementHandle> {
+		self.arrangements.get(name).cloned()
+	}
+
+	/// Returns the names of the sounds in the bank.
+	pub fn sound_names(&self) -> impl Iterator<Item = &str> {
+		self.sounds.keys().map(|name| name.as_str())
+	}
+
+	/// Returns the names of the arrangements in the bank.
+	pub fn arrangement_names(&self) -> impl Iterator<Item = &str> {
+		self.arrangements.keys().map(|name| name.as_str())
+	}
+
+	/// Returns the number of sounds and arrangements in the bank.
+	pub fn len(&self) -> usize {
+		self.sounds.len() + self.arrangements.len()
+	}
+
+	/// Returns `true` if the bank has no sounds or arrangements.
+	pub fn is_empty(&self) -> bool {
+		self.sounds.is_empty() && self.arrangements.is_empty()
+	}
+
+	/// Removes every sound and arrangement in the bank from the
+	/// audio thread.
+	///
+	/// Arrangements are removed first, since they may use
+	/// the bank's sounds.
+	pub fn unload(self, audio_manager: &mut AudioManager) -> Result<(), UnloadSoundBankError> {
+		for handle in self.arrangements.values() {
+			audio_manager.remove_arrangement(handle)?;
+		}
+		for handle in self.sounds.values() {
+			audio_manager.remove_sound(handle)?;
+		}
+		Ok(())
+	}
+}
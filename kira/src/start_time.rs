@@ -0,0 +1,44 @@
+use crate::metronome::{MetronomeId, Metronomes};
+
+/// When something should happen on the audio thread.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum StartTime {
+	/// As soon as the audio thread receives the command.
+	Immediate,
+	/// At a time on the audio clock (in seconds).
+	///
+	/// The current time on the audio clock can be checked with
+	/// [`AudioManager::audio_time`](crate::manager::AudioManager::audio_time).
+	ClockTime(f64),
+	/// When a metronome reaches a beat.
+	MetronomeBeat(MetronomeId, f64),
+	/// The next time a metronome passes an interval (in beats),
+	/// for example, `1.0` for the next beat, or `4.0` for the
+	/// next bar in 4/4 time.
+	///
+	/// The interval is counted from the start of the metronome,
+	/// not from when the command was sent, so this can be used
+	/// to keep sounds in time with music. If the metronome
+	/// isn't ticking, the start time won't be reached.
+	NextInterval(MetronomeId, f64),
+}
+
+impl StartTime {
+	/// Returns `true` if the start time has been reached.
+	pub(crate) fn has_passed(&self, clock_time: f64, metronomes: &Metronomes) -> bool {
+		match self {
+			StartTime::Immediate => true,
+			StartTime::ClockTime(time) => clock_time >= *time,
+			StartTime::MetronomeBeat(id, beat) => metronomes
+				.get(*id)
+				.is_some_and(|metronome| metronome.time() >= *beat),
+			StartTime::NextInterval(id, interval) => metronomes
+				.get(*id)
+				.is_some_and(|metronome| metronome.interval_passed(*interval)),
+		}
+	}
+}
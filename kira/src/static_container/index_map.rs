@@ -32,6 +32,10 @@ impl<K: Eq + Hash, V> StaticIndexMap<K, V> {
 		self.index_map.get(key)
 	}
 
+	pub fn get_index_of(&self, key: &K) -> Option<usize> {
+		self.index_map.get_index_of(key)
+	}
+
 	pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
 		self.index_map.get_index(index)
 	}
@@ -40,6 +44,12 @@ impl<K: Eq + Hash, V> StaticIndexMap<K, V> {
 		self.index_map.get_mut(key)
 	}
 
+	pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+		self.index_map
+			.get_index_mut(index)
+			.map(|(key, value)| (&*key, value))
+	}
+
 	pub fn iter(&self) -> indexmap::map::Iter<K, V> {
 		self.index_map.iter()
 	}
@@ -90,6 +100,10 @@ impl<K: Eq + Hash, V> StaticIndexMap<K, V> {
 	pub fn shift_remove_index(&mut self, index: usize) -> Option<(K, V)> {
 		self.index_map.shift_remove_index(index)
 	}
+
+	pub fn move_index(&mut self, from: usize, to: usize) {
+		self.index_map.move_index(from, to)
+	}
 }
 
 impl<'a, K: Eq + Hash, V> IntoIterator for &'a StaticIndexMap<K, V> {
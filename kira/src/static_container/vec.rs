@@ -73,6 +73,10 @@ impl<T> StaticVec<T> {
 		Ok(())
 	}
 
+	pub fn remove(&mut self, index: usize) -> T {
+		self.vec.remove(index)
+	}
+
 	pub fn drain(&mut self, range: impl RangeBounds<usize>) -> std::vec::Drain<T> {
 		self.vec.drain(range)
 	}
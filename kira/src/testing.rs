@@ -0,0 +1,91 @@
+//! Fixtures shared by the tests of different modules.
+
+use std::any::Any;
+
+use crate::{
+	audio_stream::AudioStream,
+	manager::{AudioManager, AudioManagerSettings, Backend},
+	mixer::effect::{values::EffectValues, Effect},
+	parameter::Parameters,
+	sound::{Sound, SoundSettings},
+	Frame, Sample,
+};
+
+/// Creates an audio manager with the default settings along
+/// with the backend that produces its audio at 48,000 Hz.
+pub(crate) fn create_manager() -> (AudioManager, Backend) {
+	AudioManager::new_headless(AudioManagerSettings::default(), 48000)
+}
+
+/// Creates a one second long sound where every frame has
+/// the given value.
+pub(crate) fn constant_sound(value: Sample, settings: SoundSettings) -> Sound {
+	Sound::from_frames(48000, vec![Frame::from_mono(value); 48000], settings)
+}
+
+/// Creates a one second long sound whose value rises from 0
+/// to 1, so the position of a frame can be read from its value.
+pub(crate) fn ramp_sound(settings: SoundSettings) -> Sound {
+	Sound::from_frames(
+		48000,
+		(0..48000)
+			.map(|i| Frame::from_mono(i as Sample / 48000.0))
+			.collect::<Vec<_>>(),
+		settings,
+	)
+}
+
+/// An audio stream that always outputs a value of 1.
+#[derive(Debug)]
+pub(crate) struct ConstantStream;
+
+impl AudioStream for ConstantStream {
+	fn next(&mut self, _dt: f64) -> Frame {
+		Frame::from_mono(1.0)
+	}
+}
+
+/// An effect that adds a constant amount to its input.
+#[derive(Debug)]
+pub(crate) struct OffsetEffect(pub(crate) f64);
+
+impl Effect for OffsetEffect {
+	fn process(&mut self, _dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+		input + Frame::from_mono(self.0 as Sample)
+	}
+}
+
+/// An effect that multiplies its input by a constant amount.
+#[derive(Debug)]
+pub(crate) struct ScaleEffect(pub(crate) f64);
+
+impl Effect for ScaleEffect {
+	fn process(&mut self, _dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+		input * self.0 as Sample
+	}
+}
+
+/// An effect that multiplies its input by a value that
+/// can be changed with [`EffectHandle::set_value`] and
+/// offsets it by an amount sent in a message.
+#[derive(Debug)]
+pub(crate) struct CustomEffect {
+	pub(crate) values: EffectValues,
+	pub(crate) offset: f64,
+}
+
+impl Effect for CustomEffect {
+	fn cached_values(&mut self) -> Option<&mut EffectValues> {
+		Some(&mut self.values)
+	}
+
+	fn on_message(&mut self, message: &mut dyn Any) {
+		if let Some(offset) = message.downcast_ref::<f64>() {
+			self.offset = *offset;
+		}
+	}
+
+	fn process(&mut self, _dt: f64, input: Frame, _parameters: &Parameters) -> Frame {
+		input * self.values.value(0) as Sample + Frame::from_mono(self.offset as Sample)
+	}
+}
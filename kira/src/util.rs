@@ -1,31 +1,37 @@
-use crate::Frame;
+use std::sync::{Arc, Mutex};
 
-pub fn lerp(a: f64, b: f64, amount: f64) -> f64 {
-	a + (b - a) * amount
-}
-
-pub fn inverse_lerp(start: f64, end: f64, point: f64) -> f64 {
-	(point - start) / (end - start)
-}
+use rand::{rngs::StdRng, thread_rng, Rng, RngCore, SeedableRng};
 
-/// Given a previous frame, a current frame, the two next frames,
-/// and a position `x` from 0.0 to 1.0 between the current frame
-/// and next frame, get an approximated frame.
+/// A random number generator shared between the audio manager
+/// and the handles it creates.
 ///
-/// This is the 4-point, 3rd-order Hermite interpolation x-form
-/// algorithm from "Polynomial Interpolators for High-Quality
-/// Resampling of Oversampled Audio" by Olli Niemitalo, p. 43:
-/// http://yehar.com/blog/wp-content/uploads/2009/08/deip.pdf
-pub fn interpolate_frame(
-	previous: Frame,
-	current: Frame,
-	next_1: Frame,
-	next_2: Frame,
-	fraction: f32,
-) -> Frame {
-	let c0 = current;
-	let c1 = (next_1 - previous) * 0.5;
-	let c2 = previous - current * 2.5 + next_1 * 2.0 - next_2 * 0.5;
-	let c3 = (next_2 - previous) * 0.5 + (current - next_1) * 1.5;
-	((c3 * fraction + c2) * fraction + c1) * fraction + c0
+/// Instances played from handles get their random seeds from
+/// this generator, so seeding it makes every random choice
+/// made on the main thread reproducible.
+#[derive(Debug, Clone)]
+pub struct SharedRng(Arc<Mutex<StdRng>>);
+
+impl SharedRng {
+	pub fn new(seed: Option<u64>) -> Self {
+		Self(Arc::new(Mutex::new(match seed {
+			Some(seed) => StdRng::seed_from_u64(seed),
+			None => StdRng::from_entropy(),
+		})))
+	}
+
+	/// Picks a random seed for something that makes its
+	/// own random choices.
+	pub fn next_seed(&self) -> u64 {
+		self.with(|rng| rng.gen())
+	}
+
+	/// Makes random choices using the shared generator.
+	pub fn with<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+		match self.0.lock() {
+			Ok(mut rng) => f(&mut *rng),
+			// a thread panicked while using the generator, so the
+			// sequence of choices can't be reproduced anyway
+			Err(_) => f(&mut thread_rng()),
+		}
+	}
 }
@@ -43,10 +43,43 @@ impl<T: From<f64> + Into<f64> + Copy> From<Range<T>> for Value<T> {
 	}
 }
 
+impl<T: From<f64> + Into<f64> + Copy> Value<T> {
+	/// Gets the current raw value, picking a new random
+	/// value with the given random number generator every
+	/// time if the value is random.
+	///
+	/// If the value is linked to a parameter that doesn't
+	/// exist, `default_value` is returned.
+	pub(crate) fn get_with_rng(
+		&self,
+		parameters: &Parameters,
+		default_value: T,
+		rng: &mut impl Rng,
+	) -> T {
+		match *self {
+			Value::Fixed(value) => value,
+			Value::Parameter(id, mapping) => {
+				parameters.get(id).map_or(default_value, |parameter| {
+					mapping.map(parameter.value()).into()
+				})
+			}
+			Value::Random(lower, upper) => {
+				let lower: f64 = lower.into();
+				let upper: f64 = upper.into();
+				rng.gen_range(lower..upper).into()
+			}
+		}
+	}
+}
+
 /// A wrapper around [`Value`](crate::Value)s that remembers the last valid raw value.
 ///
 /// You'll only need to use this if you're writing your own effects.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct CachedValue<T: From<f64> + Into<f64> + Copy> {
 	value: Value<T>,
 	last_value: T,
@@ -58,12 +91,18 @@ impl<T: From<f64> + Into<f64> + Copy> CachedValue<T> {
 	/// Creates a `CachedValue` with an initial value setting
 	/// and a default raw value to fall back on.
 	pub fn new(value: Value<T>, default_value: T) -> Self {
+		Self::new_with_rng(value, default_value, &mut thread_rng())
+	}
+
+	/// Creates a `CachedValue`, using the given random number
+	/// generator to resolve [`Value::Random`] settings.
+	pub(crate) fn new_with_rng(value: Value<T>, default_value: T, rng: &mut impl Rng) -> Self {
 		Self {
 			value,
 			last_value: match value {
 				Value::Fixed(value) => value,
 				Value::Parameter(_, _) => default_value,
-				Value::Random(lower, upper) => Self::pick_random(lower, upper),
+				Value::Random(lower, upper) => Self::pick_random(lower, upper, rng),
 			},
 			min: None,
 			max: None,
@@ -95,10 +134,10 @@ impl<T: From<f64> + Into<f64> + Copy> CachedValue<T> {
 		}
 	}
 
-	fn pick_random(lower: T, upper: T) -> T {
+	fn pick_random(lower: T, upper: T, rng: &mut impl Rng) -> T {
 		let lower: f64 = lower.into();
 		let upper: f64 = upper.into();
-		thread_rng().gen_range(lower..upper).into()
+		rng.gen_range(lower..upper).into()
 	}
 
 	/// Sets the value.
@@ -109,7 +148,7 @@ impl<T: From<f64> + Into<f64> + Copy> CachedValue<T> {
 				self.last_value = value;
 			}
 			Value::Random(lower, upper) => {
-				self.last_value = Self::pick_random(lower, upper);
+				self.last_value = Self::pick_random(lower, upper, &mut thread_rng());
 			}
 			_ => {}
 		}
@@ -128,6 +167,11 @@ impl<T: From<f64> + Into<f64> + Copy> CachedValue<T> {
 		}
 	}
 
+	/// Gets the setting this value is derived from.
+	pub fn setting(&self) -> Value<T> {
+		self.value
+	}
+
 	/// Gets the last valid raw value.
 	pub fn value(&self) -> T {
 		let mut value: f64 = self.last_value.into();
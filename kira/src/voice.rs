@@ -0,0 +1,61 @@
+//! A common interface for everything that produces sound.
+//!
+//! Instances and audio streams are both **voices**. Voices can be
+//! controlled individually using a [`VoiceId`], or all at once by
+//! the mixer track they're playing on:
+//!
+//! ```no_run
+//! # use kira::{
+//! # 	instance::PauseInstanceSettings,
+//! # 	manager::{AudioManager, AudioManagerSettings},
+//! # 	mixer::TrackIndex,
+//! # };
+//! # let (mut audio_manager, _backend) = AudioManager::new_headless(AudioManagerSettings::default(), 48000);
+//! // pause everything playing on the main track,
+//! // including voice chat streams
+//! audio_manager.pause_voices_on_track(TrackIndex::Main, PauseInstanceSettings::default())?;
+//! println!("{} voices are still playing", audio_manager.num_voices());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{
+	audio_stream::{handle::AudioStreamHandle, AudioStreamId},
+	instance::{handle::InstanceHandle, InstanceId},
+};
+
+/// A unique identifier for a voice.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(
+	feature = "serde_support",
+	derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum VoiceId {
+	/// An instance of a sound or arrangement.
+	Instance(InstanceId),
+	/// An audio stream.
+	Stream(AudioStreamId),
+}
+
+impl From<InstanceId> for VoiceId {
+	fn from(id: InstanceId) -> Self {
+		Self::Instance(id)
+	}
+}
+
+impl From<&InstanceHandle> for VoiceId {
+	fn from(handle: &InstanceHandle) -> Self {
+		Self::Instance(handle.id())
+	}
+}
+
+impl From<AudioStreamId> for VoiceId {
+	fn from(id: AudioStreamId) -> Self {
+		Self::Stream(id)
+	}
+}
+
+impl From<&AudioStreamHandle> for VoiceId {
+	fn from(handle: &AudioStreamHandle) -> Self {
+		Self::Stream(handle.id())
+	}
+}